@@ -50,6 +50,17 @@ impl NodeDebugClient {
 
         Ok(response.json()?)
     }
+
+    /// Get the JSON snapshot served by the state provider registered under `name`.
+    pub fn get_state<S: AsRef<str>>(&mut self, name: S) -> Result<serde_json::Value> {
+        let response = self
+            .client
+            .get(&format!("{}/state/{}", self.addr, name.as_ref()))
+            .send()?
+            .error_for_status()?;
+
+        Ok(response.json()?)
+    }
 }
 
 /// Implement default utility client for AsyncNodeDebugInterface
@@ -102,4 +113,16 @@ impl AsyncNodeDebugClient {
 
         Ok(response.json().await?)
     }
+
+    /// Get the JSON snapshot served by the state provider registered under `name`.
+    pub async fn get_state<S: AsRef<str>>(&mut self, name: S) -> Result<serde_json::Value> {
+        let response = self
+            .client
+            .get(&format!("{}/state/{}", self.addr, name.as_ref()))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(response.json().await?)
+    }
 }
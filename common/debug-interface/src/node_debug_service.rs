@@ -4,9 +4,15 @@
 //! Debug interface to access information in a specific node.
 
 use libra_logger::json_log;
-use std::net::SocketAddr;
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
 use tokio::runtime::{Builder, Runtime};
-use warp::Filter;
+use warp::{http::StatusCode, Filter};
+
+/// A node component that exposes a JSON snapshot of its internal state on the debug interface,
+/// under `GET /state/<name>`.
+pub trait DebugStateProvider: Send + Sync {
+    fn snapshot(&self) -> anyhow::Result<serde_json::Value>;
+}
 
 #[derive(Debug)]
 pub struct NodeDebugService {
@@ -15,6 +21,13 @@ pub struct NodeDebugService {
 
 impl NodeDebugService {
     pub fn new(address: SocketAddr) -> Self {
+        Self::new_with_providers(address, HashMap::new())
+    }
+
+    pub fn new_with_providers(
+        address: SocketAddr,
+        providers: HashMap<&'static str, Arc<dyn DebugStateProvider>>,
+    ) -> Self {
         let runtime = Builder::new()
             .thread_name("nodedebug-")
             .threaded_scheduler()
@@ -29,7 +42,24 @@ impl NodeDebugService {
         // GET /evnets
         let events = warp::path("events").map(|| warp::reply::json(&json_log::pop_last_entries()));
 
-        let routes = warp::get().and(metrics.or(events));
+        // GET /state/<name>
+        let providers = Arc::new(providers);
+        let state = warp::path!("state" / String).map(move |name: String| {
+            let (body, status) = match providers.get(name.as_str()).map(|p| p.snapshot()) {
+                Some(Ok(snapshot)) => (snapshot, StatusCode::OK),
+                Some(Err(e)) => (
+                    serde_json::Value::String(e.to_string()),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                ),
+                None => (
+                    serde_json::Value::String(format!("Unknown state provider {}", name)),
+                    StatusCode::NOT_FOUND,
+                ),
+            };
+            warp::reply::with_status(warp::reply::json(&body), status)
+        });
+
+        let routes = warp::get().and(metrics.or(events).or(state));
 
         let server = runtime.enter(move || warp::serve(routes).bind(address));
         runtime.handle().spawn(server);
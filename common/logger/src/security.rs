@@ -45,6 +45,9 @@ pub mod security_events {
     /// Consensus received an equivocating vote
     pub const CONSENSUS_EQUIVOCATING_VOTE: &str = "ConsensusEquivocatingVote";
 
+    /// Consensus received two different proposals from the same author in the same round
+    pub const CONSENSUS_EQUIVOCATING_PROPOSAL: &str = "ConsensusEquivocatingProposal";
+
     /// Consensus received an invalid proposal
     pub const INVALID_CONSENSUS_PROPOSAL: &str = "InvalidConsensusProposal";
 
@@ -15,6 +15,11 @@
 //! during the previous interval.
 //!
 //! If fine-grained flow control is desired, the refill interval should be kept small.
+//!
+//! `WeightedRateLimiter` is a variant where every element consumes a number of tokens given by
+//! its weight, e.g. its size in bytes, from a continuously refilled [`TokenBucket`]. An element
+//! heavier than the remaining tokens is still yielded, but the stream then waits for the bucket
+//! to pay off this debt before polling the next element.
 
 use futures::{
     future::{Future, FutureExt},
@@ -23,8 +28,14 @@ use futures::{
 };
 use futures_semaphore::Semaphore;
 use pin_project::pin_project;
-use std::{mem::ManuallyDrop, pin::Pin, task, task::Poll, time::Duration};
-use tokio::time::{interval, Interval};
+use std::{
+    mem::ManuallyDrop,
+    pin::Pin,
+    task,
+    task::Poll,
+    time::{Duration, Instant},
+};
+use tokio::time::{delay_for, interval, Delay, Interval};
 
 /// Config parameters for a rate-limiter.
 /// `capacity`: Max elements allowed in an interval.
@@ -113,6 +124,154 @@ impl<T: Stream> Stream for RateLimiter<T> {
     }
 }
 
+/// A token bucket refilled continuously at `rate` tokens per second, holding at most `capacity`
+/// tokens. Tokens can be borrowed: taking more tokens than available leaves the bucket in debt,
+/// which is paid off by later refills.
+#[derive(Clone, Debug)]
+pub struct TokenBucket {
+    /// `None` for an unlimited bucket.
+    rate: Option<f64>,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket allowing `rate` tokens per second with bursts of up to `capacity`.
+    pub fn new(rate: u64, capacity: u64) -> Self {
+        assert!(rate > 0, "rate must be positive");
+        Self {
+            rate: Some(rate as f64),
+            capacity: capacity as f64,
+            tokens: capacity as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Creates a bucket which never runs out of tokens.
+    pub fn unlimited() -> Self {
+        Self {
+            rate: None,
+            capacity: 0.0,
+            tokens: 0.0,
+            last_refill: Instant::now(),
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.rate.is_none()
+    }
+
+    fn refill(&mut self, now: Instant) {
+        if let Some(rate) = self.rate {
+            let elapsed = now.saturating_duration_since(self.last_refill);
+            self.tokens = (self.tokens + elapsed.as_secs_f64() * rate).min(self.capacity);
+            self.last_refill = now;
+        }
+    }
+
+    /// Takes `amount` tokens, going into debt if needed, and returns how long to wait until the
+    /// bucket is out of debt.
+    pub fn reserve(&mut self, now: Instant, amount: u64) -> Duration {
+        let rate = match self.rate {
+            Some(rate) => rate,
+            None => return Duration::from_secs(0),
+        };
+        self.refill(now);
+        self.tokens -= amount as f64;
+        if self.tokens >= 0.0 {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs_f64(-self.tokens / rate)
+        }
+    }
+
+    /// Takes `amount` tokens if the bucket holds them, or is full in case `amount` exceeds the
+    /// capacity. Returns whether the tokens were taken.
+    pub fn try_acquire(&mut self, now: Instant, amount: u64) -> bool {
+        if self.can_acquire(now, amount) {
+            if self.rate.is_some() {
+                self.tokens -= amount as f64;
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns whether `try_acquire` would take `amount` tokens, without taking them.
+    pub fn can_acquire(&mut self, now: Instant, amount: u64) -> bool {
+        if self.rate.is_none() {
+            return true;
+        }
+        self.refill(now);
+        self.tokens >= (amount as f64).min(self.capacity)
+    }
+}
+
+/// Config parameters for a weighted rate-limiter.
+/// `bucket`: The token bucket to draw tokens from.
+/// `weigh`: The number of tokens consumed by an element.
+pub trait WeightedRateLimit: Stream {
+    fn ratelimit_by<F>(self, bucket: TokenBucket, weigh: F) -> WeightedRateLimiter<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> u64,
+    {
+        WeightedRateLimiter {
+            inner: self,
+            bucket,
+            weigh,
+            delay: None,
+        }
+    }
+}
+
+impl<T: ?Sized> WeightedRateLimit for T where T: Stream {}
+
+/// Stream for the [`WeightedRateLimit::ratelimit_by`](ratelimit_by) function.
+#[pin_project]
+#[must_use = "streams do nothing unless polled"]
+pub struct WeightedRateLimiter<T: Stream, F> {
+    // The stream to rate-limit.
+    #[pin]
+    inner: T,
+    bucket: TokenBucket,
+    weigh: F,
+    // Pending wait for the bucket to be out of debt.
+    delay: Option<Pin<Box<Delay>>>,
+}
+
+impl<T: Stream, F> WeightedRateLimiter<T, F> {
+    pub fn bucket(&self) -> &TokenBucket {
+        &self.bucket
+    }
+}
+
+impl<T, F> Stream for WeightedRateLimiter<T, F>
+where
+    T: Stream,
+    F: FnMut(&T::Item) -> u64,
+{
+    type Item = T::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+        if let Some(delay) = this.delay.as_mut() {
+            ready!(delay.as_mut().poll(cx));
+            *this.delay = None;
+        }
+        let next = ready!(this.inner.poll_next(cx));
+        if let Some(item) = &next {
+            let wait = this.bucket.reserve(Instant::now(), (this.weigh)(item));
+            if wait > Duration::from_secs(0) {
+                *this.delay = Some(Box::pin(delay_for(wait)));
+            }
+        }
+        Poll::Ready(next)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -174,4 +333,57 @@ mod test {
             assert_eq!(count, 100);
         }
     }
+
+    #[test]
+    fn test_token_bucket() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1000, 500);
+
+        // The bucket starts full, and checking for tokens does not take them.
+        assert!(bucket.can_acquire(start, 500));
+        assert!(bucket.try_acquire(start, 400));
+        assert!(!bucket.can_acquire(start, 200));
+        assert!(!bucket.try_acquire(start, 200));
+        assert_eq!(bucket.reserve(start, 100), Duration::from_secs(0));
+
+        // Borrowing 500 tokens from an empty bucket takes half a second to pay off.
+        assert_eq!(bucket.reserve(start, 500), Duration::from_millis(500));
+        assert!(!bucket.try_acquire(start + Duration::from_millis(400), 1));
+
+        // Refills never exceed the capacity, but an element heavier than the capacity is
+        // accepted once the bucket is full.
+        let later = start + Duration::from_secs(10);
+        assert!(bucket.try_acquire(later, 2000));
+        assert!(!bucket.try_acquire(later + Duration::from_secs(1), 1));
+
+        let mut unlimited = TokenBucket::unlimited();
+        assert!(unlimited.try_acquire(start, u64::max_value()));
+        assert_eq!(unlimited.reserve(start, 1 << 40), Duration::from_secs(0));
+    }
+
+    // An infinite stream of 100-byte elements limited to 10_000 bytes per second with bursts of
+    // 1000 bytes yields the burst, then about one element every 10ms.
+    #[tokio::test]
+    async fn test_weighted_limits() {
+        let s = stream::repeat(vec![0u8; 100]);
+        let mut rs = s
+            .ratelimit_by(TokenBucket::new(10_000, 1000), |item| item.len() as u64)
+            .fuse();
+
+        let mut timeout = delay_for(Duration::from_millis(100)).fuse();
+        let mut count = 0;
+        loop {
+            futures::select! {
+                _ = timeout => {
+                    break;
+                },
+                _ = rs.select_next_some() => {
+                    count += 1;
+                },
+            }
+        }
+        // 10 elements of burst, then at most 10 more over 100ms.
+        assert!(count >= 11, "count: {}", count);
+        assert!(count <= 22, "count: {}", count);
+    }
 }
@@ -13,6 +13,7 @@ pub struct ConsensusConfig {
     pub max_block_size: u64,
     pub max_pruned_blocks_in_mem: usize,
     pub round_initial_timeout_ms: u64,
+    pub round_timeout_type: RoundTimeoutType,
     pub proposer_type: ConsensusProposerType,
     pub safety_rules: SafetyRulesConfig,
 }
@@ -24,6 +25,7 @@ impl Default for ConsensusConfig {
             max_block_size: 1000,
             max_pruned_blocks_in_mem: 10000,
             round_initial_timeout_ms: 1000,
+            round_timeout_type: RoundTimeoutType::Exponential,
             proposer_type: ConsensusProposerType::LeaderReputation(LeaderReputationConfig {
                 active_weights: 99,
                 inactive_weights: 1,
@@ -54,6 +56,36 @@ pub enum ConsensusProposerType {
     RoundProposer(HashMap<Round, AccountAddress>),
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum RoundTimeoutType {
+    // Starts at round_initial_timeout_ms and grows exponentially with the number of rounds since
+    // the last commit
+    Exponential,
+    // Follows the observed round latencies and proposal sizes, backs off on timeouts and recovers
+    // with every QC. Opt-in, e.g. `AdaptiveTimeoutConfig::default()`.
+    Adaptive(AdaptiveTimeoutConfig),
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdaptiveTimeoutConfig {
+    pub min_timeout_ms: u64,
+    pub max_timeout_ms: u64,
+    // Extra time allowed for every transaction of a proposal
+    pub per_txn_timeout_us: u64,
+}
+
+impl Default for AdaptiveTimeoutConfig {
+    fn default() -> AdaptiveTimeoutConfig {
+        AdaptiveTimeoutConfig {
+            min_timeout_ms: 500,
+            max_timeout_ms: 12000,
+            per_txn_timeout_us: 100,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct LeaderReputationConfig {
@@ -199,6 +199,12 @@ impl NodeConfig {
         self.consensus.set_data_dir(data_dir.clone());
         self.execution.set_data_dir(data_dir.clone());
         self.metrics.set_data_dir(data_dir.clone());
+        if let Some(network) = self.validator_network.as_mut() {
+            network.set_data_dir(data_dir.clone());
+        }
+        for network in self.full_node_networks.iter_mut() {
+            network.set_data_dir(data_dir.clone());
+        }
         self.storage.set_data_dir(data_dir);
     }
 
@@ -291,8 +297,14 @@ impl NodeConfig {
 
         for network in self.full_node_networks.iter_mut() {
             network.listen_address = crate::utils::get_available_port_in_multiaddr(true);
-            if let DiscoveryMethod::Gossip(config) = &mut network.discovery_method {
-                config.advertised_address = network.listen_address.clone();
+            match &mut network.discovery_method {
+                DiscoveryMethod::Gossip(config) => {
+                    config.advertised_address = network.listen_address.clone();
+                }
+                DiscoveryMethod::PeerExchange(config) if config.advertised_address.is_some() => {
+                    config.advertised_address = Some(network.listen_address.clone());
+                }
+                _ => {}
             }
         }
     }
@@ -468,4 +480,16 @@ mod test {
         SafetyRulesConfig::parse(&contents)
             .unwrap_or_else(|e| panic!("Error in safety_rules.yaml: {}", e));
     }
+
+    #[test]
+    fn verify_no_bans_on_validator_network() {
+        let mut network = NetworkConfig::network_with_id(NetworkId::Public);
+        network.peer_reputation.enable_bans = true;
+        network.load(RoleType::FullNode).unwrap();
+
+        let mut network = NetworkConfig::network_with_id(NetworkId::Validator);
+        network.load(RoleType::Validator).unwrap();
+        network.peer_reputation.enable_bans = true;
+        assert!(network.load(RoleType::Validator).is_err());
+    }
 }
@@ -19,6 +19,7 @@ use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
     convert::{TryFrom, TryInto},
+    path::PathBuf,
     string::ToString,
 };
 
@@ -37,7 +38,8 @@ pub type SeedAddresses = HashMap<PeerId, Vec<NetworkAddress>>;
 #[serde(default, deny_unknown_fields)]
 pub struct NetworkConfig {
     pub connectivity_check_interval_ms: u64,
-    // Enable this network to use either gossip discovery or onchain discovery.
+    // Enable this network to use either gossip discovery, onchain discovery or, for full node
+    // networks, peer exchange.
     pub discovery_method: DiscoveryMethod,
     pub identity: Identity,
     // TODO: Add support for multiple listen/advertised addresses in config.
@@ -56,6 +58,15 @@ pub struct NetworkConfig {
     // in case some peers don't have well defined addresses.
     pub seed_pubkeys: SeedPublicKeys,
     pub max_frame_size: usize,
+    // Bandwidth and message rate limits applied to every connected peer.
+    pub rate_limit: RateLimitConfig,
+    // Disconnection and temporary bans of peers misbehaving according to upstream applications.
+    pub peer_reputation: PeerReputationConfig,
+    // Recording of the messages exchanged with peers, for offline replay.
+    pub capture: CaptureConfig,
+    // How long the previous keys of peers, and our own previous key, stay valid for handshakes
+    // after a key rotation, while the new keys propagate.
+    pub key_rotation_grace_ms: u64,
 }
 
 impl Default for NetworkConfig {
@@ -76,6 +87,10 @@ impl NetworkConfig {
             seed_pubkeys: HashMap::default(),
             seed_addrs: HashMap::default(),
             max_frame_size: 8 * 1024 * 1024, // TODO use constant
+            rate_limit: RateLimitConfig::default(),
+            peer_reputation: PeerReputationConfig::default(),
+            capture: CaptureConfig::default(),
+            key_rotation_grace_ms: 10 * 60 * 1000,
         };
         config.prepare_identity();
         config
@@ -96,6 +111,10 @@ impl NetworkConfig {
             seed_pubkeys: self.seed_pubkeys.clone(),
             seed_addrs: self.seed_addrs.clone(),
             max_frame_size: self.max_frame_size,
+            rate_limit: self.rate_limit.clone(),
+            peer_reputation: self.peer_reputation.clone(),
+            capture: self.capture.clone(),
+            key_rotation_grace_ms: self.key_rotation_grace_ms,
         }
     }
 
@@ -103,19 +122,20 @@ impl NetworkConfig {
         let key = match &mut self.identity {
             Identity::FromConfig(config) => config.keypair.take_private(),
             Identity::FromStorage(config) => {
-                let storage: Storage = (&config.backend).into();
-                let key = storage
-                    .export_private_key(&config.key_name)
-                    .expect("Unable to read key");
-                let key = x25519::PrivateKey::from_ed25519_private_bytes(&key.to_bytes())
-                    .expect("Unable to convert key");
-                Some(key)
+                Some(config.private_key().expect("Unable to read key"))
             }
             Identity::None => None,
         };
         key.expect("identity key should be present")
     }
 
+    pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        if let DiscoveryMethod::PeerExchange(config) = &mut self.discovery_method {
+            config.set_data_dir(data_dir.clone());
+        }
+        self.capture.set_data_dir(data_dir);
+    }
+
     pub fn load(&mut self, role: RoleType) -> Result<(), Error> {
         if self.listen_address.to_string().is_empty() {
             self.listen_address = utils::get_local_ip()
@@ -130,6 +150,12 @@ impl NetworkConfig {
             ));
         }
 
+        if self.network_id == NetworkId::Validator && self.peer_reputation.enable_bans {
+            return Err(Error::InvariantViolation(
+                "Peer bans can't be enabled on the validator network".to_string(),
+            ));
+        }
+
         self.prepare_identity();
         Ok(())
     }
@@ -216,6 +242,7 @@ pub enum DiscoveryMethod {
     // default until we can deprecate
     Gossip(GossipConfig),
     Onchain,
+    PeerExchange(PeerExchangeConfig),
     None,
 }
 
@@ -228,10 +255,13 @@ impl DiscoveryMethod {
     }
 
     pub fn advertised_address(&self) -> NetworkAddress {
-        if let DiscoveryMethod::Gossip(config) = self {
-            config.advertised_address.clone()
-        } else {
-            panic!("Invalid discovery method");
+        match self {
+            DiscoveryMethod::Gossip(config) => config.advertised_address.clone(),
+            DiscoveryMethod::PeerExchange(PeerExchangeConfig {
+                advertised_address: Some(address),
+                ..
+            }) => address.clone(),
+            _ => panic!("Invalid discovery method"),
         }
     }
 }
@@ -244,6 +274,161 @@ pub struct GossipConfig {
     pub discovery_interval_ms: u64,
 }
 
+/// Full nodes running peer exchange periodically ask a connected peer for the addresses of other
+/// full nodes, and keep the addresses learned in a bounded address book persisted across restarts.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeerExchangeConfig {
+    // The address this node advertises to other full nodes. Nodes not accepting inbound
+    // connections leave it unset and only learn addresses.
+    pub advertised_address: Option<NetworkAddress>,
+    pub exchange_interval_ms: u64,
+    // Max number of peers kept in the address book.
+    pub max_addresses: usize,
+    // Where the address book is persisted, relative to the data dir unless absolute. Each full
+    // node network needs its own file.
+    pub address_book_path: PathBuf,
+    #[serde(skip)]
+    data_dir: PathBuf,
+}
+
+impl Default for PeerExchangeConfig {
+    fn default() -> Self {
+        Self {
+            advertised_address: None,
+            exchange_interval_ms: 30_000,
+            max_addresses: 1000,
+            address_book_path: PathBuf::from("peer_exchange/address_book"),
+            data_dir: PathBuf::from("/opt/libra/data/common"),
+        }
+    }
+}
+
+impl PeerExchangeConfig {
+    pub fn address_book_path(&self) -> PathBuf {
+        if self.address_book_path.is_relative() {
+            self.data_dir.join(&self.address_book_path)
+        } else {
+            self.address_book_path.clone()
+        }
+    }
+
+    pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        self.data_dir = data_dir;
+    }
+}
+
+/// Rate limits enforced on each connection. Limits left unset are unlimited.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    // Max messages read from a peer per second, across all protocols.
+    pub inbound_messages_per_sec: u64,
+    // Max bytes read from a peer per second, across all protocols.
+    pub inbound_bytes_per_sec: Option<u64>,
+    // Max bytes written to a peer per second, across all protocols.
+    pub outbound_bytes_per_sec: Option<u64>,
+    // Additional limits for the traffic of a protocol with a peer, keyed by protocol name,
+    // e.g. "MempoolDirectSend".
+    pub protocols: HashMap<String, ProtocolRateLimit>,
+    // What to do with inbound messages exceeding a protocol limit.
+    pub inbound_fairness: InboundFairnessPolicy,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            inbound_messages_per_sec: 10_000,
+            inbound_bytes_per_sec: None,
+            outbound_bytes_per_sec: None,
+            protocols: HashMap::new(),
+            inbound_fairness: InboundFairnessPolicy::Backpressure,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProtocolRateLimit {
+    pub inbound_bytes_per_sec: Option<u64>,
+    pub inbound_messages_per_sec: Option<u64>,
+    pub outbound_bytes_per_sec: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InboundFairnessPolicy {
+    // Stop reading from the peer until the protocol is within its limits again. This also delays
+    // the messages of the other protocols sent by the peer.
+    Backpressure,
+    // Drop the messages exceeding the protocol limits and keep reading from the peer.
+    DropExcess,
+}
+
+/// Peers start with a score of 0. Interactions reported by upstream applications raise the score,
+/// up to a cap, or lower it. With `enable_bans`, a peer whose score drops below `ban_threshold` is
+/// disconnected and neither dialed nor accepted until the ban expires. Bans can't be enabled on
+/// the validator network.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeerReputationConfig {
+    pub enable_bans: bool,
+    pub ban_threshold: i64,
+    pub ban_duration_ms: u64,
+}
+
+impl Default for PeerReputationConfig {
+    fn default() -> Self {
+        Self {
+            enable_bans: false,
+            ban_threshold: -100,
+            ban_duration_ms: 10 * 60 * 1000,
+        }
+    }
+}
+
+/// When enabled, every message sent to or received from a peer is appended, with a timestamp, to
+/// the capture files of the network. Once the current file reaches `max_file_size_bytes` a new
+/// one is started, and only the `max_files` most recent files are kept.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CaptureConfig {
+    pub enabled: bool,
+    // Directory of the capture files, relative to the data dir unless absolute. Each network
+    // needs its own directory.
+    pub dir: PathBuf,
+    pub max_file_size_bytes: u64,
+    pub max_files: usize,
+    #[serde(skip)]
+    data_dir: PathBuf,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: PathBuf::from("network_capture"),
+            max_file_size_bytes: 64 * 1024 * 1024,
+            max_files: 8,
+            data_dir: PathBuf::from("/opt/libra/data/common"),
+        }
+    }
+}
+
+impl CaptureConfig {
+    pub fn dir(&self) -> PathBuf {
+        if self.dir.is_relative() {
+            self.data_dir.join(&self.dir)
+        } else {
+            self.dir.clone()
+        }
+    }
+
+    pub fn set_data_dir(&mut self, data_dir: PathBuf) {
+        self.data_dir = data_dir;
+    }
+}
+
 #[cfg_attr(any(test, feature = "fuzzing"), derive(Clone, PartialEq))]
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
@@ -287,11 +472,21 @@ pub struct IdentityFromConfig {
 }
 
 /// This represents an identity in a secure-storage as defined in NodeConfig::secure.
-#[cfg_attr(any(test, feature = "fuzzing"), derive(Clone, PartialEq))]
-#[derive(Debug, Deserialize, Serialize)]
+#[cfg_attr(any(test, feature = "fuzzing"), derive(PartialEq))]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct IdentityFromStorage {
     pub backend: SecureBackend,
     pub key_name: String,
     pub peer_id_name: String,
 }
+
+impl IdentityFromStorage {
+    /// Reads the latest version of the identity key, which changes when it gets rotated.
+    pub fn private_key(&self) -> Result<x25519::PrivateKey, libra_secure_storage::Error> {
+        let storage: Storage = (&self.backend).into();
+        let key = storage.export_private_key(&self.key_name)?;
+        x25519::PrivateKey::from_ed25519_private_bytes(&key.to_bytes())
+            .map_err(|e| libra_secure_storage::Error::SerializationError(e.to_string()))
+    }
+}
@@ -9,7 +9,10 @@ use crate::{
     quorum_cert::QuorumCert,
 };
 use libra_crypto::hash::HashValue;
-use libra_types::{validator_signer::ValidatorSigner, validator_verifier::ValidatorVerifier};
+use libra_types::{
+    ledger_info::LedgerInfoWithSignatures, validator_signer::ValidatorSigner,
+    validator_verifier::ValidatorVerifier,
+};
 use std::{collections::BTreeMap, panic, sync::Arc};
 
 #[test]
@@ -116,8 +119,10 @@ fn test_same_qc_different_authors() {
     );
 
     let signature = signer.sign(genesis_qc.ledger_info().ledger_info());
-    let mut ledger_info_altered = genesis_qc.ledger_info().clone();
-    ledger_info_altered.add_signature(signer.author(), signature);
+    let mut signatures = BTreeMap::new();
+    signatures.insert(signer.author(), signature);
+    let ledger_info_altered =
+        LedgerInfoWithSignatures::new(genesis_qc.ledger_info().ledger_info().clone(), signatures);
     let genesis_qc_altered = QuorumCert::new(genesis_qc.vote_data().clone(), ledger_info_altered);
 
     let block_round_1_altered = Block::new_proposal(
@@ -0,0 +1,197 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Evidence of consensus misbehavior.
+//!
+//! Every piece of evidence carries the two conflicting messages exactly as they were signed by
+//! the offending validator, so it can be verified by anyone who knows the validator set of the
+//! epoch (e.g. a future on-chain slashing transaction that receives the LCS bytes of `Evidence`).
+
+use crate::{
+    block::Block,
+    common::{Author, Round},
+    vote::Vote,
+};
+use anyhow::{ensure, format_err, Context};
+use libra_types::validator_verifier::ValidatorVerifier;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[cfg(test)]
+#[path = "evidence_test.rs"]
+mod evidence_test;
+
+/// The type of misbehavior an `Evidence` proves.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum EvidenceKind {
+    /// Two votes for different ledger infos in the same round.
+    DoubleVote,
+    /// Two different signed proposals in the same round.
+    DoubleProposal,
+    /// Two timeout votes carrying different ledger infos in the same round.
+    ConflictingTimeout,
+}
+
+impl EvidenceKind {
+    /// A short name used for metric labels and logs.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EvidenceKind::DoubleVote => "double_vote",
+            EvidenceKind::DoubleProposal => "double_proposal",
+            EvidenceKind::ConflictingTimeout => "conflicting_timeout",
+        }
+    }
+}
+
+impl fmt::Display for EvidenceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Signed proof that a validator violated the protocol. The enum is LCS-encoded as is, the
+/// variant order must therefore never change.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Evidence {
+    DoubleVote { first: Vote, second: Vote },
+    DoubleProposal { first: Block, second: Block },
+    ConflictingTimeout { first: Vote, second: Vote },
+}
+
+impl Evidence {
+    /// Builds the evidence for two different votes of the same author in the same round.
+    /// If either of the votes carries a timeout signature it's a conflicting timeout.
+    pub fn from_votes(first: Vote, second: Vote) -> Self {
+        if first.is_timeout() || second.is_timeout() {
+            Evidence::ConflictingTimeout { first, second }
+        } else {
+            Evidence::DoubleVote { first, second }
+        }
+    }
+
+    /// Builds the evidence for two different proposals of the same author in the same round.
+    pub fn from_proposals(first: Block, second: Block) -> Self {
+        Evidence::DoubleProposal { first, second }
+    }
+
+    pub fn kind(&self) -> EvidenceKind {
+        match self {
+            Evidence::DoubleVote { .. } => EvidenceKind::DoubleVote,
+            Evidence::DoubleProposal { .. } => EvidenceKind::DoubleProposal,
+            Evidence::ConflictingTimeout { .. } => EvidenceKind::ConflictingTimeout,
+        }
+    }
+
+    /// The validator that signed both conflicting messages. Evidence that was deserialized
+    /// without being verified may carry NIL blocks instead of proposals, which have no author.
+    pub fn author(&self) -> Option<Author> {
+        match self {
+            Evidence::DoubleVote { first, .. } | Evidence::ConflictingTimeout { first, .. } => {
+                Some(first.author())
+            }
+            Evidence::DoubleProposal { first, .. } => first.author(),
+        }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        match self {
+            Evidence::DoubleVote { first, .. } | Evidence::ConflictingTimeout { first, .. } => {
+                first.epoch()
+            }
+            Evidence::DoubleProposal { first, .. } => first.epoch(),
+        }
+    }
+
+    pub fn round(&self) -> Round {
+        match self {
+            Evidence::DoubleVote { first, .. } | Evidence::ConflictingTimeout { first, .. } => {
+                first.vote_data().proposed().round()
+            }
+            Evidence::DoubleProposal { first, .. } => first.round(),
+        }
+    }
+
+    /// Verifies that the evidence is self-consistent and that both messages are signed by the
+    /// accused validator according to the given verifier.
+    pub fn verify(&self, validator: &ValidatorVerifier) -> anyhow::Result<()> {
+        match self {
+            Evidence::DoubleVote { first, second } => {
+                Self::verify_votes(first, second, validator)?;
+                ensure!(
+                    !first.is_timeout() && !second.is_timeout(),
+                    "Double vote evidence must not carry timeout votes"
+                );
+            }
+            Evidence::ConflictingTimeout { first, second } => {
+                Self::verify_votes(first, second, validator)?;
+                ensure!(
+                    first.is_timeout() || second.is_timeout(),
+                    "Conflicting timeout evidence must carry a timeout vote"
+                );
+            }
+            Evidence::DoubleProposal { first, second } => {
+                let author = first
+                    .author()
+                    .ok_or_else(|| format_err!("First block in evidence is not a proposal"))?;
+                ensure!(
+                    second.author() == Some(author),
+                    "Proposals in evidence have different authors"
+                );
+                ensure!(
+                    first.epoch() == second.epoch() && first.round() == second.round(),
+                    "Proposals in evidence are not for the same epoch and round"
+                );
+                ensure!(first.id() != second.id(), "Proposals in evidence are equal");
+                for block in &[first, second] {
+                    let signature = block
+                        .signature()
+                        .ok_or_else(|| format_err!("Missing signature in proposal"))?;
+                    validator
+                        .verify(author, block.block_data(), signature)
+                        .context("Failed to verify proposal in evidence")?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn verify_votes(
+        first: &Vote,
+        second: &Vote,
+        validator: &ValidatorVerifier,
+    ) -> anyhow::Result<()> {
+        ensure!(
+            first.author() == second.author(),
+            "Votes in evidence have different authors"
+        );
+        ensure!(
+            first.epoch() == second.epoch()
+                && first.vote_data().proposed().round() == second.vote_data().proposed().round(),
+            "Votes in evidence are not for the same epoch and round"
+        );
+        ensure!(
+            first.ledger_info() != second.ledger_info(),
+            "Votes in evidence are for the same ledger info"
+        );
+        first
+            .verify(validator)
+            .context("Failed to verify first vote in evidence")?;
+        second
+            .verify(validator)
+            .context("Failed to verify second vote in evidence")
+    }
+}
+
+impl fmt::Display for Evidence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Evidence: [kind: {}, author: {}, epoch: {}, round: {}]",
+            self.kind(),
+            self.author()
+                .map_or_else(|| "NIL".to_string(), |author| author.short_str()),
+            self.epoch(),
+            self.round()
+        )
+    }
+}
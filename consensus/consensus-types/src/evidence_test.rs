@@ -0,0 +1,93 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    block::{block_test_utils::certificate_for_genesis, Block},
+    evidence::{Evidence, EvidenceKind},
+    vote::Vote,
+    vote_data::VoteData,
+};
+use libra_crypto::HashValue;
+use libra_types::{
+    block_info::BlockInfo, ledger_info::LedgerInfo, validator_signer::ValidatorSigner,
+    validator_verifier::ValidatorVerifier,
+};
+
+fn random_vote(signer: &ValidatorSigner) -> Vote {
+    let ledger_info = LedgerInfo::new(
+        BlockInfo::new(1, 0, HashValue::random(), HashValue::random(), 0, 0, None),
+        HashValue::random(),
+    );
+    let vote_data = VoteData::new(BlockInfo::random(1), BlockInfo::random(0));
+    Vote::new(vote_data, signer.author(), ledger_info, signer)
+}
+
+#[test]
+fn test_double_vote_evidence() {
+    let signer = ValidatorSigner::random(None);
+    let verifier = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+
+    let first = random_vote(&signer);
+    let second = random_vote(&signer);
+    let evidence = Evidence::from_votes(first.clone(), second);
+    assert_eq!(evidence.kind(), EvidenceKind::DoubleVote);
+    assert_eq!(evidence.author(), Some(signer.author()));
+    assert_eq!(evidence.round(), 1);
+    assert!(evidence.verify(&verifier).is_ok());
+
+    // the same vote twice is not a proof of anything
+    let evidence = Evidence::from_votes(first.clone(), first);
+    assert!(evidence.verify(&verifier).is_err());
+
+    // votes from different authors are not an equivocation
+    let other_signer = ValidatorSigner::random([1; 32]);
+    let evidence = Evidence::from_votes(random_vote(&signer), random_vote(&other_signer));
+    assert!(evidence.verify(&verifier).is_err());
+}
+
+#[test]
+fn test_conflicting_timeout_evidence() {
+    let signer = ValidatorSigner::random(None);
+    let verifier = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+
+    let first = random_vote(&signer);
+    let mut second = random_vote(&signer);
+    second.add_timeout_signature(second.timeout().sign(&signer));
+    let evidence = Evidence::from_votes(first, second);
+    assert_eq!(evidence.kind(), EvidenceKind::ConflictingTimeout);
+    assert!(evidence.verify(&verifier).is_ok());
+}
+
+#[test]
+fn test_double_proposal_evidence() {
+    let signer = ValidatorSigner::random(None);
+    let verifier = ValidatorVerifier::new_single(signer.author(), signer.public_key());
+    let genesis_qc = certificate_for_genesis();
+
+    let first = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &signer);
+    let second = Block::new_proposal(vec![], 1, 2, genesis_qc.clone(), &signer);
+    let evidence = Evidence::from_proposals(first.clone(), second);
+    assert_eq!(evidence.kind(), EvidenceKind::DoubleProposal);
+    assert_eq!(evidence.author(), Some(signer.author()));
+    assert!(evidence.verify(&verifier).is_ok());
+
+    // proposals for different rounds don't conflict
+    let other_round = Block::new_proposal(vec![], 2, 2, genesis_qc.clone(), &signer);
+    let evidence = Evidence::from_proposals(first.clone(), other_round);
+    assert!(evidence.verify(&verifier).is_err());
+
+    // NIL blocks have no author, such evidence can only come from untrusted bytes
+    let evidence = Evidence::from_proposals(Block::new_nil(1, genesis_qc), first);
+    assert_eq!(evidence.author(), None);
+    assert!(evidence.verify(&verifier).is_err());
+    assert!(evidence.to_string().contains("NIL"));
+}
+
+#[test]
+fn test_evidence_lcs_roundtrip() {
+    let signer = ValidatorSigner::random(None);
+    let evidence = Evidence::from_votes(random_vote(&signer), random_vote(&signer));
+    let bytes = lcs::to_bytes(&evidence).unwrap();
+    let decoded: Evidence = lcs::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded, evidence);
+}
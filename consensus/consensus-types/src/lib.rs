@@ -8,6 +8,7 @@ pub mod block_data;
 pub mod block_retrieval;
 pub mod common;
 pub mod epoch_retrieval;
+pub mod evidence;
 pub mod executed_block;
 pub mod proposal_msg;
 pub mod quorum_cert;
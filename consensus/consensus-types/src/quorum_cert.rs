@@ -15,6 +15,10 @@ use std::{
     fmt::{Display, Formatter},
 };
 
+#[cfg(all(test, feature = "bls"))]
+#[path = "quorum_cert_test.rs"]
+mod quorum_cert_test;
+
 #[derive(Deserialize, Serialize, Clone, Debug, Eq, PartialEq)]
 pub struct QuorumCert {
     /// The vote information certified by the quorum.
@@ -112,7 +116,9 @@ impl QuorumCert {
                 "Genesis QC has inconsistent commit block with certified block"
             );
             ensure!(
-                self.ledger_info().signatures().is_empty(),
+                self.ledger_info()
+                    .signatures()
+                    .map_or(false, |signatures| signatures.is_empty()),
                 "Genesis QC should not carry signatures"
             );
             return Ok(());
@@ -0,0 +1,75 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{quorum_cert::QuorumCert, vote_data::VoteData};
+use libra_crypto::{hash::CryptoHash, HashValue};
+use libra_types::{
+    block_info::BlockInfo,
+    ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
+    on_chain_config::ConsensusSignatureScheme,
+    validator_signer::ValidatorSigner,
+    validator_verifier::{random_validator_verifier, ValidatorVerifier},
+};
+use std::collections::BTreeMap;
+
+fn multi_signed(
+    ledger_info: LedgerInfo,
+    signers: &[ValidatorSigner],
+    validator: &ValidatorVerifier,
+) -> LedgerInfoWithSignatures {
+    let shares: BTreeMap<_, _> = signers
+        .iter()
+        .map(|signer| {
+            (
+                signer.author(),
+                signer.sign_multi_signature_share(&ledger_info),
+            )
+        })
+        .collect();
+    let multi_signature = validator
+        .multi_signature_verifier()
+        .unwrap()
+        .aggregate(&shares)
+        .unwrap();
+    LedgerInfoWithSignatures::new_with_multi_signature(ledger_info, multi_signature)
+}
+
+#[test]
+fn test_multi_signed_quorum_cert() {
+    let (signers, ed25519_validator) = random_validator_verifier(4, None, false);
+    let scheme = ConsensusSignatureScheme::Bls12381(
+        signers
+            .iter()
+            .map(ValidatorSigner::bls_validator_key)
+            .collect(),
+    );
+    let validator = ed25519_validator
+        .clone()
+        .with_signature_scheme(&scheme)
+        .unwrap();
+    assert!(ed25519_validator
+        .with_signature_scheme(&ConsensusSignatureScheme::default())
+        .unwrap()
+        .multi_signature_verifier()
+        .is_none());
+
+    let vote_data = VoteData::new(BlockInfo::random(1), BlockInfo::random(0));
+    let ledger_info = LedgerInfo::new(BlockInfo::empty(), vote_data.hash());
+    let qc = QuorumCert::new(
+        vote_data.clone(),
+        multi_signed(ledger_info.clone(), &signers[..3], &validator),
+    );
+    assert!(qc.verify(&validator).is_ok());
+
+    // not enough signers
+    let qc = QuorumCert::new(
+        vote_data.clone(),
+        multi_signed(ledger_info, &signers[..2], &validator),
+    );
+    assert!(qc.verify(&validator).is_err());
+
+    // the ledger info doesn't certify the vote data
+    let ledger_info = LedgerInfo::new(BlockInfo::empty(), HashValue::random());
+    let qc = QuorumCert::new(vote_data, multi_signed(ledger_info, &signers, &validator));
+    assert!(qc.verify(&validator).is_err());
+}
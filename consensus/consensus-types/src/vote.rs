@@ -3,6 +3,8 @@
 
 use crate::{common::Author, timeout::Timeout, vote_data::VoteData};
 use anyhow::{ensure, Context};
+#[cfg(feature = "bls")]
+use libra_crypto::bls12381::Bls12381Signature;
 use libra_crypto::{ed25519::Ed25519Signature, hash::CryptoHash};
 use libra_types::{
     ledger_info::LedgerInfo, validator_signer::ValidatorSigner,
@@ -27,6 +29,10 @@ pub struct Vote {
     signature: Ed25519Signature,
     /// The round signatures can be aggregated into a timeout certificate if present.
     timeout_signature: Option<Ed25519Signature>,
+    /// BLS12-381 signature of the LedgerInfo, aggregated into the multi-signature of the QC when
+    /// the validator set has multi-signatures enabled.
+    #[cfg(feature = "bls")]
+    multi_signature_share: Bls12381Signature,
 }
 
 impl Display for Vote {
@@ -53,12 +59,17 @@ impl Vote {
     ) -> Self {
         ledger_info_placeholder.set_consensus_data_hash(vote_data.hash());
         let li_sig = validator_signer.sign(&ledger_info_placeholder);
+        #[cfg(feature = "bls")]
+        let multi_signature_share =
+            validator_signer.sign_multi_signature_share(&ledger_info_placeholder);
         Self {
             vote_data,
             author,
             ledger_info: ledger_info_placeholder,
             signature: li_sig,
             timeout_signature: None,
+            #[cfg(feature = "bls")]
+            multi_signature_share,
         }
     }
 
@@ -91,6 +102,12 @@ impl Vote {
         &self.signature
     }
 
+    /// Return the BLS12-381 signature of the vote
+    #[cfg(feature = "bls")]
+    pub fn multi_signature_share(&self) -> &Bls12381Signature {
+        &self.multi_signature_share
+    }
+
     /// Returns the hash of the data represent by a timeout proposal
     pub fn timeout(&self) -> Timeout {
         Timeout::new(
@@ -126,6 +143,18 @@ impl Vote {
         validator
             .verify(self.author(), &self.ledger_info, &self.signature)
             .context("Failed to verify Vote")?;
+        #[cfg(feature = "bls")]
+        {
+            if let Some(verifier) = validator.multi_signature_verifier() {
+                verifier
+                    .verify_share(
+                        self.author(),
+                        &self.ledger_info,
+                        &self.multi_signature_share,
+                    )
+                    .context("Failed to verify the multi-signature share of Vote")?;
+            }
+        }
         if let Some(timeout_signature) = &self.timeout_signature {
             validator
                 .verify(self.author(), &self.timeout(), timeout_signature)
@@ -170,10 +170,10 @@ pub fn make_proposal_with_parent_and_overrides(
         validator_signer,
     );
 
-    let mut ledger_info_with_signatures =
-        LedgerInfoWithSignatures::new(vote.ledger_info().clone(), BTreeMap::new());
-
-    ledger_info_with_signatures.add_signature(vote.author(), vote.signature().clone());
+    let mut signatures = BTreeMap::new();
+    signatures.insert(vote.author(), vote.signature().clone());
+    let ledger_info_with_signatures =
+        LedgerInfoWithSignatures::new(vote.ledger_info().clone(), signatures);
 
     let qc = QuorumCert::new(vote_data, ledger_info_with_signatures);
 
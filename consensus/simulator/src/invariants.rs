@@ -0,0 +1,143 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use consensus_types::common::Round;
+use libra_crypto::HashValue;
+use std::{collections::BTreeMap, time::Duration};
+use thiserror::Error;
+
+/// A block committed by one of the nodes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CommittedBlock {
+    pub id: HashValue,
+    pub round: Round,
+    pub parent_id: HashValue,
+}
+
+/// A violation of one of the invariants checked by the simulator after every step.
+#[derive(Debug, Error, Eq, PartialEq)]
+pub enum InvariantViolation {
+    #[error(
+        "Node {node} committed block {block} at round {round}, \
+         but block {committed} is already committed at that round"
+    )]
+    ConflictingCommit {
+        node: usize,
+        round: Round,
+        block: HashValue,
+        committed: HashValue,
+    },
+    #[error("Node {node} committed block {block} at round {round} off the committed chain")]
+    ForkedCommit {
+        node: usize,
+        round: Round,
+        block: HashValue,
+    },
+    #[error("No block committed between {since:?} and {now:?} while the network is healthy")]
+    NoProgress { since: Duration, now: Duration },
+}
+
+/// Safety: all the nodes commit blocks of a single chain.
+pub(crate) struct SafetyChecker {
+    /// The blocks committed by any of the nodes, by round.
+    chain: BTreeMap<Round, CommittedBlock>,
+}
+
+impl SafetyChecker {
+    pub fn new(root: CommittedBlock) -> Self {
+        let mut chain = BTreeMap::new();
+        chain.insert(root.round, root);
+        Self { chain }
+    }
+
+    /// Checks a block committed by the given node, returns whether it extends the chain.
+    /// Nodes commit every block on the path from their previous commit, so the chain has no
+    /// gaps: the parent of a block is the committed block of the closest lower round.
+    pub fn record(
+        &mut self,
+        node: usize,
+        block: CommittedBlock,
+    ) -> Result<bool, InvariantViolation> {
+        if let Some(committed) = self.chain.get(&block.round) {
+            return if committed.id == block.id {
+                Ok(false)
+            } else {
+                Err(InvariantViolation::ConflictingCommit {
+                    node,
+                    round: block.round,
+                    block: block.id,
+                    committed: committed.id,
+                })
+            };
+        }
+        let extends_predecessor = self
+            .chain
+            .range(..block.round)
+            .next_back()
+            .map_or(true, |(_, previous)| previous.id == block.parent_id);
+        let extended_by_successor = self
+            .chain
+            .range(block.round + 1..)
+            .next()
+            .map_or(true, |(_, next)| next.parent_id == block.id);
+        if !extends_predecessor || !extended_by_successor {
+            return Err(InvariantViolation::ForkedCommit {
+                node,
+                round: block.round,
+                block: block.id,
+            });
+        }
+        self.chain.insert(block.round, block);
+        Ok(true)
+    }
+
+    pub fn chain(&self) -> impl Iterator<Item = &CommittedBlock> {
+        self.chain.values()
+    }
+
+    pub fn highest_round(&self) -> Round {
+        self.chain.keys().next_back().copied().unwrap_or(0)
+    }
+}
+
+/// Liveness: while the network is healthy the nodes keep committing blocks.
+pub(crate) struct LivenessChecker {
+    /// Maximum time without a commit, `None` disables the check.
+    bound: Option<Duration>,
+    /// Start of the current healthy period, if the network is healthy.
+    healthy_since: Option<Duration>,
+    last_commit: Duration,
+}
+
+impl LivenessChecker {
+    pub fn new(bound: Option<Duration>) -> Self {
+        Self {
+            bound,
+            healthy_since: None,
+            last_commit: Duration::from_secs(0),
+        }
+    }
+
+    pub fn on_commit(&mut self, now: Duration) {
+        self.last_commit = now;
+    }
+
+    pub fn check(&mut self, now: Duration, healthy: bool) -> Result<(), InvariantViolation> {
+        if !healthy {
+            self.healthy_since = None;
+            return Ok(());
+        }
+        let healthy_since = *self.healthy_since.get_or_insert(now);
+        match self.bound {
+            Some(bound) => {
+                let since = healthy_since.max(self.last_commit);
+                if now > since + bound {
+                    Err(InvariantViolation::NoProgress { since, now })
+                } else {
+                    Ok(())
+                }
+            }
+            None => Ok(()),
+        }
+    }
+}
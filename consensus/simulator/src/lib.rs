@@ -0,0 +1,503 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Deterministic simulator of a LibraBFT network.
+//!
+//! The simulator runs N `RoundManager`s with mock storage in a single thread, on top of a virtual
+//! clock (`SimulatedTimeService`) shared by all the nodes. Messages between the nodes go through a
+//! simulated network that delays, reorders, drops and partitions them according to the current
+//! `NetworkConditions` and a seeded random number generator. Tests script partitions, network
+//! changes and node crashes / restarts as `Action`s at given virtual times, and the simulator
+//! checks safety (no conflicting commits) and liveness (progress while the network is healthy)
+//! after every step, so a run is reproducible from its `SimulatorConfig` and script.
+//!
+//! All the randomness of a run, including the peers a node picks to retry a block retrieval, is
+//! derived from the seed. Known limitation: block retrievals are served instantly (they are
+//! still subject to partitions and message loss).
+
+#![forbid(unsafe_code)]
+
+mod invariants;
+mod network_conditions;
+mod node;
+
+#[cfg(test)]
+mod simulator_test;
+
+pub use invariants::{CommittedBlock, InvariantViolation};
+pub use network_conditions::NetworkConditions;
+
+use crate::{
+    invariants::{LivenessChecker, SafetyChecker},
+    node::{process_input, NodeInput, Outbound, SimNode},
+};
+use bytes::Bytes;
+use consensus::{
+    network_interface::ConsensusMsg,
+    simulation::{
+        IncomingBlockRetrievalRequest, MockStorage, SimulatedTimeService, TimeService,
+        UnverifiedEvent,
+    },
+};
+use consensus_types::common::{Author, Round};
+use futures::{channel::oneshot, executor::block_on, task::noop_waker};
+use libra_crypto::{ed25519::Ed25519PrivateKey, Uniform};
+use libra_logger::prelude::*;
+use libra_types::{
+    account_address::AccountAddress,
+    ledger_info::LedgerInfo,
+    on_chain_config::ValidatorSet,
+    validator_signer::ValidatorSigner,
+    validator_verifier::{ValidatorConsensusInfo, ValidatorVerifier},
+    waypoint::Waypoint,
+};
+use network::protocols::rpc::error::RpcError;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, BinaryHeap},
+    future::Future,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// The genesis block has timestamp 0, the clock starts later so that the first proposal has a
+/// strictly greater timestamp.
+const START_TIME: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug)]
+pub struct SimulatorConfig {
+    /// Number of validators, all with the same voting power.
+    pub num_nodes: usize,
+    /// Seed of all the randomness of the run: validator keys, message delays and losses, and the
+    /// peers picked by block retrievals.
+    pub seed: u64,
+    /// Initial round timeout, it grows exponentially with the rounds without a commit.
+    pub round_timeout: Duration,
+    /// Network conditions at the start of the run.
+    pub network: NetworkConditions,
+    /// Maximum time without a commit while the network is healthy and at most f nodes are down,
+    /// `None` disables the liveness check.
+    pub liveness_bound: Option<Duration>,
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        Self {
+            num_nodes: 4,
+            seed: 0,
+            round_timeout: Duration::from_secs(1),
+            network: NetworkConditions::default(),
+            liveness_bound: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+/// A scripted change of the simulated environment.
+#[derive(Clone, Debug)]
+pub enum Action {
+    /// Splits the nodes in groups that can only reach the nodes of the same group, nodes not
+    /// listed in any group are isolated.
+    Partition(Vec<Vec<usize>>),
+    /// Removes the partition.
+    Heal,
+    SetDelay {
+        min: Duration,
+        max: Duration,
+    },
+    SetDropProbability(f64),
+    /// The node loses all its in-memory state, it keeps its storage.
+    Crash(usize),
+    /// The crashed node recovers from its storage.
+    Restart(usize),
+}
+
+enum SimEvent {
+    Deliver {
+        from: usize,
+        to: usize,
+        msg: ConsensusMsg,
+    },
+    Action(Action),
+}
+
+struct ScheduledEvent {
+    time: Duration,
+    /// Breaks ties between events scheduled at the same time in scheduling order.
+    seq: u64,
+    event: SimEvent,
+}
+
+impl PartialEq for ScheduledEvent {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ScheduledEvent {}
+
+impl PartialOrd for ScheduledEvent {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScheduledEvent {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.time, self.seq).cmp(&(other.time, other.seq))
+    }
+}
+
+pub struct Simulator {
+    time_service: SimulatedTimeService,
+    nodes: Vec<SimNode>,
+    author_to_node: BTreeMap<Author, usize>,
+    verifier: ValidatorVerifier,
+    network: NetworkConditions,
+    rng: StdRng,
+    events: BinaryHeap<Reverse<ScheduledEvent>>,
+    next_seq: u64,
+    safety: SafetyChecker,
+    liveness: LivenessChecker,
+}
+
+impl Simulator {
+    pub fn new(config: SimulatorConfig) -> Self {
+        assert!(
+            config.num_nodes > 0,
+            "The simulation needs at least one node"
+        );
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        // Addresses follow the node indices so that the validator order is the node order.
+        let signers: Vec<_> = (0..config.num_nodes)
+            .map(|index| {
+                let mut address = [0; AccountAddress::LENGTH];
+                address[..8].copy_from_slice(&(index as u64 + 1).to_be_bytes());
+                ValidatorSigner::new(
+                    AccountAddress::new(address),
+                    Ed25519PrivateKey::generate(&mut rng),
+                )
+            })
+            .collect();
+        let verifier = ValidatorVerifier::new(
+            signers
+                .iter()
+                .map(|signer| {
+                    (
+                        signer.author(),
+                        ValidatorConsensusInfo::new(signer.public_key(), 1),
+                    )
+                })
+                .collect(),
+        );
+        let validator_set: ValidatorSet = (&verifier).into();
+        let waypoint =
+            Waypoint::new_epoch_boundary(&LedgerInfo::mock_genesis(Some(validator_set.clone())))
+                .expect("[Simulator] Failed to create the genesis waypoint");
+        let proposers: Vec<_> = signers.iter().map(ValidatorSigner::author).collect();
+        let author_to_node = proposers
+            .iter()
+            .enumerate()
+            .map(|(index, author)| (*author, index))
+            .collect();
+
+        let time_service = SimulatedTimeService::new();
+        time_service.advance_to(START_TIME);
+
+        let mut nodes = vec![];
+        let mut start_inputs = vec![];
+        for signer in signers {
+            let (node, input) = SimNode::new(
+                signer,
+                rng.gen(),
+                validator_set.clone(),
+                waypoint,
+                proposers.clone(),
+                config.round_timeout,
+                time_service.clone(),
+            );
+            nodes.push(node);
+            start_inputs.push(input);
+        }
+        let (genesis_data, _) = MockStorage::start_for_testing(validator_set);
+        let genesis = genesis_data.root_block();
+        let root = CommittedBlock {
+            id: genesis.id(),
+            round: genesis.round(),
+            parent_id: genesis.parent_id(),
+        };
+
+        let mut simulator = Self {
+            time_service,
+            nodes,
+            author_to_node,
+            verifier,
+            network: config.network,
+            rng,
+            events: BinaryHeap::new(),
+            next_seq: 0,
+            safety: SafetyChecker::new(root),
+            liveness: LivenessChecker::new(config.liveness_bound),
+        };
+        for (index, input) in start_inputs.into_iter().enumerate() {
+            simulator.run_input(index, input);
+        }
+        simulator
+    }
+
+    /// Schedules an action at the given virtual time (or right away if it's in the past).
+    pub fn schedule(&mut self, time: Duration, action: Action) {
+        self.push_event(time, SimEvent::Action(action));
+    }
+
+    /// Processes the next message, timeout or action. Returns false if there is nothing left to
+    /// process.
+    pub fn step(&mut self) -> Result<bool, InvariantViolation> {
+        let next_event = self.events.peek().map(|Reverse(event)| event.time);
+        match (next_event, self.time_service.next_pending_deadline()) {
+            (None, None) => return Ok(false),
+            (Some(event_time), Some(deadline)) if deadline < event_time => {
+                self.fire_timeouts(deadline)
+            }
+            (None, Some(deadline)) => self.fire_timeouts(deadline),
+            (Some(_), _) => {
+                let Reverse(event) = self.events.pop().expect("Peeked event must exist");
+                self.time_service.advance_to(event.time);
+                self.handle_event(event.event);
+            }
+        }
+        self.check_invariants()?;
+        Ok(true)
+    }
+
+    /// Steps until everything scheduled before the given virtual time is processed.
+    pub fn run_until(&mut self, time: Duration) -> Result<(), InvariantViolation> {
+        while let Some(next) = self.next_step_time() {
+            if next > time {
+                break;
+            }
+            self.step()?;
+        }
+        self.time_service.advance_to(time);
+        Ok(())
+    }
+
+    pub fn run_for(&mut self, duration: Duration) -> Result<(), InvariantViolation> {
+        self.run_until(self.now() + duration)
+    }
+
+    pub fn now(&self) -> Duration {
+        self.time_service.get_current_timestamp()
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn author(&self, node: usize) -> Author {
+        self.nodes[node].author()
+    }
+
+    pub fn is_up(&self, node: usize) -> bool {
+        self.nodes[node].is_up()
+    }
+
+    /// The highest round committed by any node.
+    pub fn committed_round(&self) -> Round {
+        self.safety.highest_round()
+    }
+
+    /// The round the given node has committed (or synced) to.
+    pub fn node_committed_round(&self, node: usize) -> Round {
+        self.nodes[node].committed_round()
+    }
+
+    /// The chain of blocks committed by the nodes, starting with genesis.
+    pub fn committed_chain(&self) -> Vec<CommittedBlock> {
+        self.safety.chain().copied().collect()
+    }
+
+    /// The network is healthy when the network conditions are and at most f nodes are down.
+    pub fn is_healthy(&self) -> bool {
+        let down = self.nodes.iter().filter(|node| !node.is_up()).count();
+        self.network.is_healthy() && down <= (self.nodes.len() - 1) / 3
+    }
+
+    fn next_step_time(&self) -> Option<Duration> {
+        let next_event = self.events.peek().map(|Reverse(event)| event.time);
+        match (next_event, self.time_service.next_pending_deadline()) {
+            (Some(event_time), Some(deadline)) => Some(event_time.min(deadline)),
+            (event_time, deadline) => event_time.or(deadline),
+        }
+    }
+
+    fn push_event(&mut self, time: Duration, event: SimEvent) {
+        self.events.push(Reverse(ScheduledEvent {
+            time: time.max(self.now()),
+            seq: self.next_seq,
+            event,
+        }));
+        self.next_seq += 1;
+    }
+
+    fn fire_timeouts(&mut self, deadline: Duration) {
+        self.time_service.advance_to(deadline);
+        for index in 0..self.nodes.len() {
+            while let Some(round) = self.nodes[index].next_timeout() {
+                self.run_input(index, NodeInput::Timeout(round));
+            }
+        }
+    }
+
+    fn handle_event(&mut self, event: SimEvent) {
+        match event {
+            SimEvent::Deliver { from, to, msg } => self.deliver(from, to, msg),
+            SimEvent::Action(action) => {
+                info!("[Simulator] {:?} at {:?}", action, self.now());
+                match action {
+                    Action::Partition(groups) => self.network.partition(&groups, self.nodes.len()),
+                    Action::Heal => self.network.heal(),
+                    Action::SetDelay { min, max } => self.network.set_delay(min, max),
+                    Action::SetDropProbability(p) => self.network.set_drop_probability(p),
+                    Action::Crash(node) => self.nodes[node].crash(),
+                    Action::Restart(node) => {
+                        if !self.nodes[node].is_up() {
+                            let input = self.nodes[node].restart();
+                            self.run_input(node, input);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn deliver(&mut self, from: usize, to: usize, msg: ConsensusMsg) {
+        if !self.nodes[to].is_up() || !self.network.is_connected(from, to) {
+            return;
+        }
+        let event = match msg {
+            ConsensusMsg::ProposalMsg(_) | ConsensusMsg::VoteMsg(_) | ConsensusMsg::SyncInfo(_) => {
+                UnverifiedEvent::from(msg)
+            }
+            // There are no reconfigurations in the simulation.
+            _ => return,
+        };
+        match event.verify(&self.verifier) {
+            Ok(event) => {
+                let author = self.nodes[from].author();
+                self.run_input(to, NodeInput::Event(author, event));
+            }
+            Err(e) => error!("[Simulator] Node {} sent an invalid message: {:?}", from, e),
+        }
+    }
+
+    /// Runs the input to completion on the given node, serving the block retrievals the node
+    /// issues on the way, then sends out its messages.
+    fn run_input(&mut self, index: usize, input: NodeInput) {
+        let mut round_manager = match self.nodes[index].round_manager.take() {
+            Some(round_manager) => round_manager,
+            None => return,
+        };
+        let result = {
+            let mut future = Box::pin(process_input(&mut round_manager, input));
+            let waker = noop_waker();
+            let mut cx = Context::from_waker(&waker);
+            loop {
+                if let Poll::Ready(result) = future.as_mut().poll(&mut cx) {
+                    break result;
+                }
+                let served_rpc = self.flush_outbound(index);
+                assert!(
+                    served_rpc,
+                    "[Simulator] Node {} is blocked on something other than a block retrieval",
+                    index
+                );
+            }
+        };
+        self.nodes[index].round_manager = Some(round_manager);
+        if let Err(e) = result {
+            debug!(
+                "[Simulator] Node {} failed to process input: {:?}",
+                index, e
+            );
+        }
+        self.flush_outbound(index);
+    }
+
+    /// Sends out everything the node produced, returns whether there was any rpc.
+    fn flush_outbound(&mut self, from: usize) -> bool {
+        let mut rpcs = false;
+        while let Some(outbound) = self.nodes[from].next_outbound() {
+            match outbound {
+                Outbound::Message(to, msg) => self.send(from, to, msg),
+                Outbound::Rpc(to, msg, response_sender) => {
+                    rpcs = true;
+                    self.serve_rpc(from, to, msg, response_sender);
+                }
+            }
+        }
+        rpcs
+    }
+
+    fn send(&mut self, from: usize, to: Author, msg: ConsensusMsg) {
+        let to = match self.author_to_node.get(&to) {
+            Some(to) => *to,
+            None => return,
+        };
+        let delay = if from == to {
+            Some(Duration::from_secs(0))
+        } else {
+            self.network.sample_delay(&mut self.rng)
+        };
+        if let Some(delay) = delay {
+            let time = self.now() + delay;
+            self.push_event(time, SimEvent::Deliver { from, to, msg });
+        }
+    }
+
+    /// Answers a block retrieval right away, or drops the response sender so that the request
+    /// fails if the peer is unreachable.
+    fn serve_rpc(
+        &mut self,
+        from: usize,
+        to: Author,
+        msg: ConsensusMsg,
+        response_sender: oneshot::Sender<Result<Bytes, RpcError>>,
+    ) {
+        let to = match self.author_to_node.get(&to) {
+            Some(to) => *to,
+            None => return,
+        };
+        if !self.network.is_connected(from, to)
+            || self.network.sample_delay(&mut self.rng).is_none()
+        {
+            return;
+        }
+        let req = match msg {
+            ConsensusMsg::BlockRetrievalRequest(req) => *req,
+            _ => return,
+        };
+        if let Some(round_manager) = self.nodes[to].round_manager.as_ref() {
+            let request = IncomingBlockRetrievalRequest {
+                req,
+                response_sender,
+            };
+            if let Err(e) = block_on(round_manager.process_block_retrieval(request)) {
+                error!("[Simulator] Node {} failed to serve rpc: {:?}", to, e);
+            }
+        }
+    }
+
+    fn check_invariants(&mut self) -> Result<(), InvariantViolation> {
+        let now = self.now();
+        for index in 0..self.nodes.len() {
+            for block in self.nodes[index].take_commits() {
+                if self.safety.record(index, block)? {
+                    self.liveness.on_commit(now);
+                }
+            }
+        }
+        let healthy = self.is_healthy();
+        self.liveness.check(now, healthy)
+    }
+}
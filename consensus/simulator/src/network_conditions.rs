@@ -0,0 +1,87 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use rand::Rng;
+use std::time::Duration;
+
+/// The behavior of the simulated network between the nodes.
+#[derive(Clone, Debug)]
+pub struct NetworkConditions {
+    /// Every message is delayed by a duration picked uniformly in `[min_delay, max_delay]`, so
+    /// messages sent in a short succession might be reordered.
+    min_delay: Duration,
+    max_delay: Duration,
+    /// Probability of every message and block retrieval to be lost.
+    drop_probability: f64,
+    /// The partition of every node, messages only flow within a partition.
+    partition_of: Option<Vec<usize>>,
+}
+
+impl Default for NetworkConditions {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(1), Duration::from_millis(20), 0.0)
+    }
+}
+
+impl NetworkConditions {
+    pub fn new(min_delay: Duration, max_delay: Duration, drop_probability: f64) -> Self {
+        let mut conditions = Self {
+            min_delay,
+            max_delay,
+            drop_probability: 0.0,
+            partition_of: None,
+        };
+        conditions.set_delay(min_delay, max_delay);
+        conditions.set_drop_probability(drop_probability);
+        conditions
+    }
+
+    pub fn set_delay(&mut self, min_delay: Duration, max_delay: Duration) {
+        assert!(
+            min_delay <= max_delay,
+            "min_delay must not exceed max_delay"
+        );
+        self.min_delay = min_delay;
+        self.max_delay = max_delay;
+    }
+
+    pub fn set_drop_probability(&mut self, drop_probability: f64) {
+        self.drop_probability = drop_probability.max(0.0).min(1.0);
+    }
+
+    /// Splits the nodes in the given groups, nodes not listed in any group are isolated.
+    pub fn partition(&mut self, groups: &[Vec<usize>], num_nodes: usize) {
+        let mut partition_of: Vec<_> = (groups.len()..groups.len() + num_nodes).collect();
+        for (partition, group) in groups.iter().enumerate() {
+            for node in group {
+                partition_of[*node] = partition;
+            }
+        }
+        self.partition_of = Some(partition_of);
+    }
+
+    pub fn heal(&mut self) {
+        self.partition_of = None;
+    }
+
+    pub fn is_connected(&self, from: usize, to: usize) -> bool {
+        self.partition_of
+            .as_ref()
+            .map_or(true, |partition_of| partition_of[from] == partition_of[to])
+    }
+
+    /// The network is healthy when there is neither a partition nor message loss.
+    pub fn is_healthy(&self) -> bool {
+        self.partition_of.is_none() && self.drop_probability <= 0.0
+    }
+
+    /// Returns the delay of a message, or `None` if it is lost.
+    pub(crate) fn sample_delay<R: Rng>(&self, rng: &mut R) -> Option<Duration> {
+        if self.drop_probability > 0.0 && rng.gen_bool(self.drop_probability) {
+            return None;
+        }
+        let min = self.min_delay.as_micros() as u64;
+        let max = self.max_delay.as_micros() as u64;
+        Some(Duration::from_micros(rng.gen_range(min, max + 1)))
+    }
+}
@@ -0,0 +1,363 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::invariants::CommittedBlock;
+use anyhow::Result;
+use bytes::Bytes;
+use channel::{libra_channel, message_queues::QueueStyle};
+use consensus::{
+    network_interface::{ConsensusMsg, ConsensusNetworkSender},
+    simulation::{
+        BlockStore, ExponentialTimeInterval, MetricsSafetyRules, MockStateComputer, MockStorage,
+        MockTransactionManager, NetworkSender, ProposalGenerator, RecoveryData, RotatingProposer,
+        RoundManager, RoundState, SimulatedTimeService, StateComputer, VerifiedEvent,
+    },
+};
+use consensus_types::{
+    block::Block,
+    common::{Author, Payload, Round},
+    vote::Vote,
+};
+use executor_types::{Error, StateComputeResult};
+use futures::{
+    channel::{mpsc, oneshot},
+    FutureExt, StreamExt,
+};
+use libra_crypto::{ed25519::Ed25519PrivateKey, HashValue, Uniform};
+use libra_logger::prelude::*;
+use libra_secure_storage::{InMemoryStorage, Storage};
+use libra_types::{
+    epoch_state::EpochState, ledger_info::LedgerInfoWithSignatures, on_chain_config::ValidatorSet,
+    validator_signer::ValidatorSigner, waypoint::Waypoint, PeerId,
+};
+use network::{
+    peer_manager::{ConnectionRequestSender, PeerManagerRequest, PeerManagerRequestSender},
+    protocols::{network::Event, rpc::error::RpcError},
+    ProtocolId,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use safety_rules::{PersistentSafetyStorage, SafetyRulesManager};
+use std::{
+    collections::HashMap,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Capacity of the channels between a node and the simulator. The simulator drains them after
+/// every input, they only need to hold what a single input can produce.
+const CHANNEL_SIZE: usize = 1024;
+
+/// An input the simulator feeds to a `RoundManager`.
+pub(crate) enum NodeInput {
+    Start(Option<Vote>),
+    Event(Author, VerifiedEvent),
+    Timeout(Round),
+}
+
+pub(crate) async fn process_input(
+    round_manager: &mut RoundManager,
+    input: NodeInput,
+) -> anyhow::Result<()> {
+    match input {
+        NodeInput::Start(last_vote) => {
+            round_manager.start(last_vote).await;
+            Ok(())
+        }
+        NodeInput::Event(_, VerifiedEvent::ProposalMsg(proposal)) => {
+            round_manager.process_proposal_msg(*proposal).await
+        }
+        NodeInput::Event(_, VerifiedEvent::VoteMsg(vote)) => {
+            round_manager.process_vote_msg(*vote).await
+        }
+        NodeInput::Event(peer, VerifiedEvent::SyncInfo(sync_info)) => {
+            round_manager.process_sync_info_msg(*sync_info, peer).await
+        }
+        NodeInput::Timeout(round) => round_manager.process_local_timeout(round).await,
+    }
+}
+
+/// Something a node wants to send out.
+pub(crate) enum Outbound {
+    Message(Author, ConsensusMsg),
+    Rpc(
+        Author,
+        ConsensusMsg,
+        oneshot::Sender<Result<Bytes, RpcError>>,
+    ),
+}
+
+/// The receiving ends of everything a running node produces.
+struct NodeChannels {
+    network_reqs_rx: libra_channel::Receiver<(PeerId, ProtocolId), PeerManagerRequest>,
+    self_receiver: channel::Receiver<anyhow::Result<Event<ConsensusMsg>>>,
+    timeout_receiver: channel::Receiver<Round>,
+    commit_cb_receiver: mpsc::UnboundedReceiver<LedgerInfoWithSignatures>,
+    state_sync_receiver: mpsc::UnboundedReceiver<Payload>,
+}
+
+/// A validator of the simulation. Its storage and safety rules survive crashes, everything else
+/// is rebuilt from the storage on restart, the same way a real node recovers.
+pub(crate) struct SimNode {
+    author: Author,
+    storage: Arc<MockStorage>,
+    safety_rules_manager: SafetyRulesManager,
+    time_service: SimulatedTimeService,
+    proposers: Vec<Author>,
+    round_timeout: Duration,
+    /// Seeds the block retrievals of the node every time it boots.
+    rng: StdRng,
+    /// Blocks committed by the node that the simulator has not checked yet.
+    commits: Arc<Mutex<Vec<CommittedBlock>>>,
+    /// Taken by the simulator while the node processes an input, `None` while the node is down.
+    pub round_manager: Option<RoundManager>,
+    channels: Option<NodeChannels>,
+}
+
+impl SimNode {
+    /// Creates the node from genesis, it has to be started with the returned input.
+    pub fn new(
+        signer: ValidatorSigner,
+        seed: u64,
+        validator_set: ValidatorSet,
+        waypoint: Waypoint,
+        proposers: Vec<Author>,
+        round_timeout: Duration,
+        time_service: SimulatedTimeService,
+    ) -> (Self, NodeInput) {
+        let (initial_data, storage) = MockStorage::start_for_testing(validator_set);
+        let safety_storage = PersistentSafetyStorage::initialize(
+            Storage::from(InMemoryStorage::new()),
+            signer.author(),
+            signer.private_key().clone(),
+            Ed25519PrivateKey::generate_for_testing(),
+            waypoint,
+        );
+        let mut node = Self {
+            author: signer.author(),
+            storage,
+            safety_rules_manager: SafetyRulesManager::new_local(safety_storage, false),
+            time_service,
+            proposers,
+            round_timeout,
+            rng: StdRng::seed_from_u64(seed),
+            commits: Arc::new(Mutex::new(vec![])),
+            round_manager: None,
+            channels: None,
+        };
+        let input = node.boot(initial_data);
+        (node, input)
+    }
+
+    pub fn author(&self) -> Author {
+        self.author
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.channels.is_some()
+    }
+
+    /// The round of the latest ledger info committed (or synced to) by the node.
+    pub fn committed_round(&self) -> Round {
+        self.storage.get_ledger_info().commit_info().round()
+    }
+
+    /// Drops all the in-memory state of the node.
+    pub fn crash(&mut self) {
+        self.round_manager = None;
+        self.channels = None;
+    }
+
+    /// Recovers the node from its storage, it has to be started with the returned input.
+    pub fn restart(&mut self) -> NodeInput {
+        let recovery_data = self
+            .storage
+            .try_start()
+            .unwrap_or_else(|e| panic!("[Simulator] Failed to restart node: {}", e));
+        self.boot(recovery_data)
+    }
+
+    fn boot(&mut self, initial_data: RecoveryData) -> NodeInput {
+        let epoch_state = EpochState {
+            epoch: 1,
+            verifier: self.storage.get_validator_set().into(),
+        };
+        let (network_reqs_tx, network_reqs_rx) = libra_channel::new(
+            QueueStyle::FIFO,
+            NonZeroUsize::new(CHANNEL_SIZE).unwrap(),
+            None,
+        );
+        let (connection_reqs_tx, _) =
+            libra_channel::new(QueueStyle::FIFO, NonZeroUsize::new(1).unwrap(), None);
+        let network_sender = ConsensusNetworkSender::new(
+            PeerManagerRequestSender::new(network_reqs_tx),
+            ConnectionRequestSender::new(connection_reqs_tx),
+        );
+        let (self_sender, self_receiver) = channel::new_test(CHANNEL_SIZE);
+        let network = NetworkSender::new(
+            self.author,
+            network_sender,
+            self_sender,
+            epoch_state.verifier.clone(),
+        );
+
+        let last_vote = initial_data.last_vote();
+        let (commit_cb_sender, commit_cb_receiver) = mpsc::unbounded();
+        let (state_sync_client, state_sync_receiver) = mpsc::unbounded();
+        let state_computer = Arc::new(SimStateComputer::new(
+            MockStateComputer::new(state_sync_client, commit_cb_sender, self.storage.clone()),
+            self.commits.clone(),
+        ));
+        let time_service = Arc::new(self.time_service.clone());
+        let block_store = Arc::new(BlockStore::new(
+            self.storage.clone(),
+            initial_data,
+            state_computer,
+            10, // max pruned blocks in mem
+            time_service.clone(),
+        ));
+        // Empty blocks: random payloads would make the block ids differ from run to run.
+        let proposal_generator = ProposalGenerator::new(
+            self.author,
+            block_store.clone(),
+            Arc::new(MockTransactionManager::new(None)),
+            time_service.clone(),
+            0,
+        );
+        let (timeout_sender, timeout_receiver) = channel::new_test(CHANNEL_SIZE);
+        let round_state = RoundState::new(
+            Box::new(ExponentialTimeInterval::new(self.round_timeout, 1.2, 6)),
+            time_service,
+            timeout_sender,
+        );
+        let mut safety_rules =
+            MetricsSafetyRules::new(self.safety_rules_manager.client(), self.storage.clone());
+        safety_rules
+            .perform_initialize()
+            .expect("[Simulator] Failed to initialize safety rules");
+
+        let mut round_manager = RoundManager::new(
+            epoch_state,
+            block_store,
+            round_state,
+            Box::new(RotatingProposer::new(self.proposers.clone(), 1)),
+            proposal_generator,
+            safety_rules,
+            network,
+            Arc::new(MockTransactionManager::new(None)),
+            self.storage.clone(),
+        );
+        round_manager.seed_retrieval_rng(self.rng.gen());
+        self.round_manager = Some(round_manager);
+        self.channels = Some(NodeChannels {
+            network_reqs_rx,
+            self_receiver,
+            timeout_receiver,
+            commit_cb_receiver,
+            state_sync_receiver,
+        });
+        NodeInput::Start(last_vote)
+    }
+
+    /// Returns the next message or rpc the node wants to send, messages to self come first.
+    pub fn next_outbound(&mut self) -> Option<Outbound> {
+        let channels = self.channels.as_mut()?;
+        while let Some(Some(event)) = channels.self_receiver.next().now_or_never() {
+            if let Ok(Event::Message((author, msg))) = event {
+                return Some(Outbound::Message(author, msg));
+            }
+        }
+        while let Some(Some(request)) = channels.network_reqs_rx.next().now_or_never() {
+            match request {
+                PeerManagerRequest::SendMessage(peer, msg) => match lcs::from_bytes(&msg.mdata) {
+                    Ok(msg) => return Some(Outbound::Message(peer, msg)),
+                    Err(e) => error!("[Simulator] Failed to decode message: {:?}", e),
+                },
+                PeerManagerRequest::SendRpc(peer, rpc) => match lcs::from_bytes(&rpc.data) {
+                    Ok(msg) => return Some(Outbound::Rpc(peer, msg, rpc.res_tx)),
+                    Err(e) => error!("[Simulator] Failed to decode rpc: {:?}", e),
+                },
+            }
+        }
+        None
+    }
+
+    /// Returns the next round the node needs to time out.
+    pub fn next_timeout(&mut self) -> Option<Round> {
+        self.channels
+            .as_mut()?
+            .timeout_receiver
+            .next()
+            .now_or_never()
+            .flatten()
+    }
+
+    /// Takes the blocks committed since the last call, discarding the notifications meant for
+    /// state sync which are not simulated.
+    pub fn take_commits(&mut self) -> Vec<CommittedBlock> {
+        if let Some(channels) = self.channels.as_mut() {
+            while let Some(Some(_)) = channels.commit_cb_receiver.next().now_or_never() {}
+            while let Some(Some(_)) = channels.state_sync_receiver.next().now_or_never() {}
+        }
+        std::mem::take(&mut *self.commits.lock().unwrap())
+    }
+}
+
+/// Wraps `MockStateComputer` to report the committed blocks to the simulator.
+struct SimStateComputer {
+    inner: MockStateComputer,
+    executed: Mutex<HashMap<HashValue, CommittedBlock>>,
+    commits: Arc<Mutex<Vec<CommittedBlock>>>,
+}
+
+impl SimStateComputer {
+    fn new(inner: MockStateComputer, commits: Arc<Mutex<Vec<CommittedBlock>>>) -> Self {
+        Self {
+            inner,
+            executed: Mutex::new(HashMap::new()),
+            commits,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl StateComputer for SimStateComputer {
+    fn compute(
+        &self,
+        block: &Block,
+        parent_block_id: HashValue,
+    ) -> Result<StateComputeResult, Error> {
+        self.executed.lock().unwrap().insert(
+            block.id(),
+            CommittedBlock {
+                id: block.id(),
+                round: block.round(),
+                parent_id: parent_block_id,
+            },
+        );
+        self.inner.compute(block, parent_block_id)
+    }
+
+    async fn commit(
+        &self,
+        block_ids: Vec<HashValue>,
+        commit: LedgerInfoWithSignatures,
+    ) -> Result<()> {
+        {
+            let mut executed = self.executed.lock().unwrap();
+            let mut commits = self.commits.lock().unwrap();
+            for id in &block_ids {
+                if let Some(block) = executed.remove(id) {
+                    commits.push(block);
+                }
+            }
+            let committed_round = commit.ledger_info().commit_info().round();
+            executed.retain(|_, block| block.round > committed_round);
+        }
+        self.inner.commit(block_ids, commit).await
+    }
+
+    async fn sync_to(&self, commit: LedgerInfoWithSignatures) -> Result<()> {
+        self.inner.sync_to(commit).await
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    invariants::{CommittedBlock, InvariantViolation, SafetyChecker},
+    Action, NetworkConditions, Simulator, SimulatorConfig,
+};
+use libra_crypto::HashValue;
+use std::time::Duration;
+
+fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+#[test]
+fn test_healthy_network_commits() {
+    let mut simulator = Simulator::new(SimulatorConfig::default());
+    simulator.run_for(secs(10)).unwrap();
+
+    assert!(simulator.is_healthy());
+    assert!(simulator.committed_round() > 10);
+    for node in 0..simulator.num_nodes() {
+        assert!(simulator.is_up(node));
+        assert!(simulator.node_committed_round(node) > 0);
+    }
+    // the committed chain starts with genesis and every block extends the previous one
+    let chain = simulator.committed_chain();
+    assert_eq!(chain[0].round, 0);
+    for pair in chain.windows(2) {
+        assert_eq!(pair[1].parent_id, pair[0].id);
+    }
+}
+
+#[test]
+fn test_partition_and_heal() {
+    let mut simulator = Simulator::new(SimulatorConfig::default());
+    simulator.schedule(secs(5), Action::Partition(vec![vec![0, 1], vec![2, 3]]));
+    // in-flight certificates might still commit right after the partition
+    simulator.run_until(secs(6)).unwrap();
+    assert!(!simulator.is_healthy());
+    let committed_round = simulator.committed_round();
+
+    // no partition has a quorum
+    simulator.run_until(secs(20)).unwrap();
+    assert_eq!(simulator.committed_round(), committed_round);
+
+    simulator.schedule(secs(20), Action::Heal);
+    simulator.run_until(secs(50)).unwrap();
+    assert!(simulator.committed_round() > committed_round);
+}
+
+#[test]
+fn test_minority_partition_keeps_committing() {
+    let mut simulator = Simulator::new(SimulatorConfig::default());
+    simulator.schedule(secs(2), Action::Partition(vec![vec![0, 1, 2]]));
+    simulator.run_until(secs(3)).unwrap();
+    let committed_round = simulator.committed_round();
+    let isolated_round = simulator.node_committed_round(3);
+
+    simulator.run_until(secs(30)).unwrap();
+    assert!(simulator.committed_round() > committed_round);
+    assert_eq!(simulator.node_committed_round(3), isolated_round);
+
+    // the isolated node catches up once the partition is gone
+    simulator.schedule(secs(30), Action::Heal);
+    simulator.run_until(secs(60)).unwrap();
+    assert!(simulator.node_committed_round(3) > isolated_round);
+}
+
+#[test]
+fn test_message_drops_and_reordering() {
+    let mut simulator = Simulator::new(SimulatorConfig {
+        seed: 7,
+        network: NetworkConditions::new(Duration::from_millis(1), Duration::from_millis(300), 0.1),
+        ..SimulatorConfig::default()
+    });
+    simulator.run_for(secs(60)).unwrap();
+    assert!(simulator.committed_round() > 0);
+
+    // once the network is reliable again the liveness invariant is enforced
+    let now = simulator.now();
+    simulator.schedule(now, Action::SetDropProbability(0.0));
+    simulator.schedule(
+        now,
+        Action::SetDelay {
+            min: Duration::from_millis(1),
+            max: Duration::from_millis(20),
+        },
+    );
+    simulator.run_for(secs(60)).unwrap();
+    assert!(simulator.is_healthy());
+}
+
+#[test]
+fn test_crash_and_restart() {
+    let mut simulator = Simulator::new(SimulatorConfig::default());
+    simulator.schedule(secs(5), Action::Crash(3));
+    simulator.run_until(secs(6)).unwrap();
+    assert!(!simulator.is_up(3));
+    // a single crashed node out of four doesn't stop the others
+    assert!(simulator.is_healthy());
+    let crashed_round = simulator.node_committed_round(3);
+
+    simulator.run_until(secs(30)).unwrap();
+    assert!(simulator.committed_round() > crashed_round);
+    assert_eq!(simulator.node_committed_round(3), crashed_round);
+
+    simulator.schedule(secs(30), Action::Restart(3));
+    simulator.run_until(secs(60)).unwrap();
+    assert!(simulator.is_up(3));
+    assert!(simulator.node_committed_round(3) > crashed_round);
+}
+
+#[test]
+fn test_same_seed_same_run() {
+    let run = |seed| {
+        let mut simulator = Simulator::new(SimulatorConfig {
+            seed,
+            network: NetworkConditions::new(
+                Duration::from_millis(1),
+                Duration::from_millis(200),
+                0.05,
+            ),
+            liveness_bound: None,
+            ..SimulatorConfig::default()
+        });
+        simulator.schedule(secs(10), Action::Crash(1));
+        simulator.schedule(secs(20), Action::Restart(1));
+        simulator.run_until(secs(40)).unwrap();
+        (
+            simulator.committed_chain(),
+            (0..simulator.num_nodes())
+                .map(|node| (simulator.author(node), simulator.node_committed_round(node)))
+                .collect::<Vec<_>>(),
+        )
+    };
+    assert_eq!(run(42), run(42));
+}
+
+#[test]
+fn test_safety_checker() {
+    let block = |round, id, parent_id| CommittedBlock {
+        id,
+        round,
+        parent_id,
+    };
+    let (genesis, a1, a2, b2, b3) = (
+        HashValue::random(),
+        HashValue::random(),
+        HashValue::random(),
+        HashValue::random(),
+        HashValue::random(),
+    );
+    let mut checker = SafetyChecker::new(block(0, genesis, HashValue::zero()));
+
+    assert_eq!(checker.record(0, block(1, a1, genesis)), Ok(true));
+    assert_eq!(checker.record(1, block(1, a1, genesis)), Ok(false));
+    assert_eq!(checker.record(0, block(2, a2, a1)), Ok(true));
+    assert_eq!(
+        checker.record(1, block(2, b2, a1)),
+        Err(InvariantViolation::ConflictingCommit {
+            node: 1,
+            round: 2,
+            block: b2,
+            committed: a2,
+        })
+    );
+    assert_eq!(
+        checker.record(2, block(3, b3, b2)),
+        Err(InvariantViolation::ForkedCommit {
+            node: 2,
+            round: 3,
+            block: b3,
+        })
+    );
+    assert_eq!(checker.highest_round(), 2);
+}
@@ -353,7 +353,7 @@ impl BlockTree {
     }
 }
 
-#[cfg(any(test, feature = "fuzzing"))]
+#[cfg(any(test, feature = "fuzzing", feature = "simulation"))]
 impl BlockTree {
     /// Returns the number of blocks in the tree
     pub(super) fn len(&self) -> usize {
@@ -18,7 +18,7 @@ use consensus_types::{
 use libra_logger::prelude::*;
 use libra_types::{account_address::AccountAddress, epoch_change::EpochChangeProof};
 use mirai_annotations::checked_precondition;
-use rand::{prelude::*, Rng};
+use rand::{rngs::StdRng, Rng};
 use std::{clone::Clone, sync::Arc, time::Duration};
 use termion::color::*;
 
@@ -233,13 +233,16 @@ impl BlockStore {
 pub struct BlockRetriever {
     network: NetworkSender,
     preferred_peer: Author,
+    /// Picks the peers that are tried after the preferred peer.
+    rng: StdRng,
 }
 
 impl BlockRetriever {
-    pub fn new(network: NetworkSender, preferred_peer: Author) -> Self {
+    pub fn new(network: NetworkSender, preferred_peer: Author, rng: StdRng) -> Self {
         Self {
             network,
             preferred_peer,
+            rng,
         }
     }
     /// Retrieve chain of n blocks for given QC
@@ -259,7 +262,8 @@ impl BlockRetriever {
         num_blocks: u64,
     ) -> anyhow::Result<Vec<Block>> {
         let block_id = qc.certified_block().id();
-        let mut peers: Vec<&AccountAddress> = qc.ledger_info().signatures().keys().collect();
+        let signers = qc.ledger_info().signers(self.network.validators());
+        let mut peers: Vec<&AccountAddress> = signers.iter().collect();
         let mut attempt = 0_u32;
         loop {
             if peers.is_empty() {
@@ -304,7 +308,7 @@ impl BlockRetriever {
         }
     }
 
-    fn pick_peer(&self, attempt: u32, peers: &mut Vec<&AccountAddress>) -> AccountAddress {
+    fn pick_peer(&mut self, attempt: u32, peers: &mut Vec<&AccountAddress>) -> AccountAddress {
         assert!(!peers.is_empty(), "pick_peer on empty peer list");
 
         if attempt == 0 {
@@ -319,7 +323,7 @@ impl BlockRetriever {
             return self.preferred_peer;
         }
 
-        let peer_idx = thread_rng().gen_range(0, peers.len());
+        let peer_idx = self.rng.gen_range(0, peers.len());
         *peers.remove(peer_idx)
     }
 }
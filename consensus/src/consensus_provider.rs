@@ -4,6 +4,7 @@
 use crate::{
     counters,
     epoch_manager::EpochManager,
+    misbehavior_detector::EvidenceDebugProvider,
     network::NetworkTask,
     network_interface::{ConsensusNetworkEvents, ConsensusNetworkSender},
     persistent_liveness_storage::StorageWriteProxy,
@@ -12,6 +13,7 @@ use crate::{
     util::time_service::ClockTimeService,
 };
 use channel::libra_channel;
+use debug_interface::node_debug_service::DebugStateProvider;
 use execution_correctness::ExecutionCorrectnessManager;
 use futures::channel::mpsc;
 use libra_config::config::NodeConfig;
@@ -23,7 +25,8 @@ use std::sync::Arc;
 use storage_interface::DbReader;
 use tokio::runtime::{self, Runtime};
 
-/// Helper function to start consensus based on configuration and return the runtime
+/// Helper function to start consensus based on configuration and return the runtime, together
+/// with the provider of the misbehavior evidence for the debug interface.
 pub fn start_consensus(
     node_config: &mut NodeConfig,
     network_sender: ConsensusNetworkSender,
@@ -32,7 +35,7 @@ pub fn start_consensus(
     consensus_to_mempool_sender: mpsc::Sender<ConsensusRequest>,
     libra_db: Arc<dyn DbReader>,
     reconfig_events: libra_channel::Receiver<(), OnChainConfigPayload>,
-) -> Runtime {
+) -> (Runtime, Arc<dyn DebugStateProvider>) {
     let runtime = runtime::Builder::new()
         .thread_name("consensus-")
         .threaded_scheduler()
@@ -40,6 +43,7 @@ pub fn start_consensus(
         .build()
         .expect("Failed to create Tokio runtime!");
     let storage = Arc::new(StorageWriteProxy::new(node_config, libra_db));
+    let evidence_provider = Arc::new(EvidenceDebugProvider::new(storage.clone()));
     let txn_manager = Arc::new(MempoolProxy::new(consensus_to_mempool_sender));
     let execution_correctness_manager = ExecutionCorrectnessManager::new(node_config);
     let state_computer = Arc::new(ExecutionProxy::new(
@@ -68,5 +72,5 @@ pub fn start_consensus(
     runtime.spawn(epoch_mgr.start(timeout_receiver, network_receiver, reconfig_events));
 
     debug!("Consensus started.");
-    runtime
+    (runtime, evidence_provider)
 }
@@ -48,3 +48,28 @@ fn test_delete_block_and_qc() {
     assert_eq!(db.get_blocks().unwrap().len(), 0);
     assert_eq!(db.get_quorum_certificates().unwrap().len(), 0);
 }
+
+#[test]
+fn test_put_get_evidence() {
+    let tmp_dir = TempPath::new();
+    let db = ConsensusDB::new(&tmp_dir);
+    assert_eq!(db.get_evidence().unwrap().len(), 0);
+
+    let signer = libra_types::validator_signer::ValidatorSigner::random(None);
+    let genesis_qc = certificate_for_genesis();
+    let proposal = |round, timestamp| {
+        Block::new_proposal(vec![], round, timestamp, genesis_qc.clone(), &signer)
+    };
+    let evidence_round_2 = Evidence::from_proposals(proposal(2, 1), proposal(2, 2));
+    let evidence_round_1 = Evidence::from_proposals(proposal(1, 1), proposal(1, 2));
+
+    db.save_evidence(&evidence_round_2).unwrap();
+    db.save_evidence(&evidence_round_1).unwrap();
+    // the same offense is stored only once
+    db.save_evidence(&evidence_round_1).unwrap();
+
+    assert_eq!(
+        db.get_evidence().unwrap(),
+        vec![evidence_round_1, evidence_round_2]
+    );
+}
@@ -7,14 +7,15 @@ mod schema;
 
 use crate::consensusdb::schema::{
     block::{BlockSchema, SchemaBlock},
+    evidence::{EvidenceKey, EvidenceSchema},
     quorum_certificate::QCSchema,
     single_entry::{SingleEntryKey, SingleEntrySchema},
 };
 use anyhow::{ensure, Result};
-use consensus_types::{block::Block, quorum_cert::QuorumCert};
+use consensus_types::{block::Block, evidence::Evidence, quorum_cert::QuorumCert};
 use libra_crypto::HashValue;
 use libra_logger::prelude::*;
-use schema::{BLOCK_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
+use schema::{BLOCK_CF_NAME, EVIDENCE_CF_NAME, QC_CF_NAME, SINGLE_ENTRY_CF_NAME};
 use schemadb::{ReadOptions, SchemaBatch, DB, DEFAULT_CF_NAME};
 use std::{collections::HashMap, iter::Iterator, path::Path, time::Instant};
 
@@ -29,6 +30,7 @@ impl ConsensusDB {
             BLOCK_CF_NAME,
             QC_CF_NAME,
             SINGLE_ENTRY_CF_NAME,
+            EVIDENCE_CF_NAME,
         ];
 
         let path = db_root_path.as_ref().join("consensusdb");
@@ -127,6 +129,22 @@ impl ConsensusDB {
         self.commit(batch)
     }
 
+    /// Persist evidence of misbehavior. Evidence for the same (epoch, round, author, kind) is
+    /// overwritten, one proof per offense is enough.
+    pub fn save_evidence(&self, evidence: &Evidence) -> Result<()> {
+        let mut batch = SchemaBatch::new();
+        batch.put::<EvidenceSchema>(&EvidenceKey::from_evidence(evidence)?, evidence)?;
+        self.commit(batch)
+    }
+
+    /// Get all the evidence of misbehavior, ordered by (epoch, round).
+    pub fn get_evidence(&self) -> Result<Vec<Evidence>> {
+        let mut iter = self.db.iter::<EvidenceSchema>(ReadOptions::default())?;
+        iter.seek_to_first();
+        iter.map(|value| value.map(|(_, evidence)| evidence))
+            .collect::<Result<Vec<Evidence>>>()
+    }
+
     /// Write the whole schema batch including all data necessary to mutate the ledger
     /// state of some transaction by leveraging rocksdb atomicity support.
     fn commit(&self, batch: SchemaBatch) -> Result<()> {
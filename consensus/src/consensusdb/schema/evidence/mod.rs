@@ -0,0 +1,93 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines physical storage schema for evidence of consensus misbehavior.
+//!
+//! Evidence is keyed by the epoch and round of the misbehavior, the offending validator and the
+//! kind of misbehavior, so that at most one piece of evidence of each kind is kept per validator
+//! and round.
+//! ```text
+//! |<---------------key--------------->|<--value-->|
+//! | epoch | round | author | kind     |  Evidence |
+//! ```
+
+use super::{ensure_slice_len_eq, EVIDENCE_CF_NAME};
+use anyhow::{format_err, Result};
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use consensus_types::{
+    common::{Author, Round},
+    evidence::{Evidence, EvidenceKind},
+};
+use schemadb::{
+    define_schema,
+    schema::{KeyCodec, ValueCodec},
+};
+use std::{convert::TryFrom, mem::size_of};
+
+define_schema!(EvidenceSchema, EvidenceKey, Evidence, EVIDENCE_CF_NAME);
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EvidenceKey {
+    pub epoch: u64,
+    pub round: Round,
+    pub author: Author,
+    pub kind: EvidenceKind,
+}
+
+impl EvidenceKey {
+    pub fn from_evidence(evidence: &Evidence) -> Result<Self> {
+        Ok(Self {
+            epoch: evidence.epoch(),
+            round: evidence.round(),
+            author: evidence
+                .author()
+                .ok_or_else(|| format_err!("Evidence without an author: {}", evidence))?,
+            kind: evidence.kind(),
+        })
+    }
+}
+
+// The kind is encoded as a single LCS enum tag byte.
+const KEY_LEN: usize = size_of::<u64>() + size_of::<Round>() + Author::LENGTH + size_of::<u8>();
+
+impl KeyCodec<EvidenceSchema> for EvidenceKey {
+    fn encode_key(&self) -> Result<Vec<u8>> {
+        let mut encoded_key = Vec::with_capacity(KEY_LEN);
+        encoded_key.write_u64::<BigEndian>(self.epoch)?;
+        encoded_key.write_u64::<BigEndian>(self.round)?;
+        encoded_key.extend_from_slice(self.author.as_ref());
+        encoded_key.extend(lcs::to_bytes(&self.kind)?);
+        Ok(encoded_key)
+    }
+
+    fn decode_key(data: &[u8]) -> Result<Self> {
+        ensure_slice_len_eq(data, KEY_LEN)?;
+
+        let author_offset = size_of::<u64>() + size_of::<Round>();
+        let kind_offset = author_offset + Author::LENGTH;
+
+        let epoch = (&data[..size_of::<u64>()]).read_u64::<BigEndian>()?;
+        let round = (&data[size_of::<u64>()..author_offset]).read_u64::<BigEndian>()?;
+        let author = Author::try_from(&data[author_offset..kind_offset])?;
+        let kind = lcs::from_bytes(&data[kind_offset..])?;
+        Ok(Self {
+            epoch,
+            round,
+            author,
+            kind,
+        })
+    }
+}
+
+impl ValueCodec<EvidenceSchema> for Evidence {
+    fn encode_value(&self) -> Result<Vec<u8>> {
+        Ok(lcs::to_bytes(self)?)
+    }
+
+    fn decode_value(data: &[u8]) -> Result<Self> {
+        Ok(lcs::from_bytes(data)?)
+    }
+}
+
+#[cfg(test)]
+mod test;
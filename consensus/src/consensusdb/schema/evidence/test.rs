@@ -0,0 +1,21 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use consensus_types::block::{block_test_utils::certificate_for_genesis, Block};
+use libra_types::validator_signer::ValidatorSigner;
+use schemadb::schema::assert_encode_decode;
+
+#[test]
+fn test_encode_decode() {
+    let signer = ValidatorSigner::random(None);
+    let genesis_qc = certificate_for_genesis();
+    let evidence = Evidence::from_proposals(
+        Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &signer),
+        Block::new_proposal(vec![], 1, 2, genesis_qc, &signer),
+    );
+    assert_encode_decode::<EvidenceSchema>(
+        &EvidenceKey::from_evidence(&evidence).unwrap(),
+        &evidence,
+    );
+}
@@ -79,6 +79,17 @@ pub static VOTE_NIL_COUNT: Lazy<IntCounter> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Count of the pieces of evidence of misbehavior (double votes, double proposals,
+/// conflicting timeouts) detected since last restart.
+pub static MISBEHAVIOR_EVIDENCE_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_consensus_misbehavior_evidence_count",
+        "Count of the pieces of evidence of misbehavior detected since last restart, by kind",
+        &["kind"]
+    )
+    .unwrap()
+});
+
 //////////////////////
 // RoundState COUNTERS
 //////////////////////
@@ -126,6 +137,27 @@ pub static ROUND_TIMEOUT_MS: Lazy<IntGauge> = Lazy::new(|| {
     .unwrap()
 });
 
+/// Number of timeouts not yet compensated by QCs in the adaptive round timeout.
+pub static ROUND_TIMEOUT_BACKOFF: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "libra_consensus_round_timeout_backoff",
+        "Number of timeouts not yet compensated by QCs in the adaptive round timeout."
+    )
+    .unwrap()
+});
+
+/// Duration between the start of a round and the moment a QC for it is observed (rounds that
+/// timed out locally are not included).
+pub static ROUND_QC_LATENCY_S: Lazy<DurationHistogram> = Lazy::new(|| {
+    DurationHistogram::new(
+        register_histogram!(
+            "libra_consensus_round_qc_latency_s",
+            "Duration between the start of a round and the moment a QC for it is observed"
+        )
+        .unwrap(),
+    )
+});
+
 ////////////////////////
 // SYNC MANAGER COUNTERS
 ////////////////////////
@@ -10,7 +10,9 @@ use crate::{
         proposer_election::ProposerElection,
         rotating_proposer_election::{choose_leader, RotatingProposer},
         round_proposer_election::RoundProposer,
-        round_state::{ExponentialTimeInterval, RoundState},
+        round_state::{
+            AdaptiveTimeInterval, ExponentialTimeInterval, RoundState, RoundTimeInterval,
+        },
     },
     metrics_safety_rules::MetricsSafetyRules,
     network::{IncomingBlockRetrievalRequest, NetworkReceivers, NetworkSender},
@@ -27,7 +29,7 @@ use consensus_types::{
     epoch_retrieval::EpochRetrievalRequest,
 };
 use futures::{select, StreamExt};
-use libra_config::config::{ConsensusConfig, ConsensusProposerType, NodeConfig};
+use libra_config::config::{ConsensusConfig, ConsensusProposerType, NodeConfig, RoundTimeoutType};
 use libra_logger::prelude::*;
 use libra_metrics::monitor;
 use libra_types::{
@@ -36,7 +38,7 @@ use libra_types::{
     epoch_state::EpochState,
     on_chain_config::{OnChainConfigPayload, ValidatorSet},
 };
-use network::protocols::network::Event;
+use network::protocols::network::{Event, PeerBehavior};
 use safety_rules::SafetyRulesManager;
 use std::{cmp::Ordering, sync::Arc, time::Duration};
 
@@ -130,13 +132,22 @@ impl EpochManager {
         time_service: Arc<dyn TimeService>,
         timeout_sender: channel::Sender<Round>,
     ) -> RoundState {
+        let initial_timeout = Duration::from_millis(self.config.round_initial_timeout_ms);
         // 1.5^6 ~= 11
         // Timeout goes from initial_timeout to initial_timeout*11 in 6 steps
-        let time_interval = Box::new(ExponentialTimeInterval::new(
-            Duration::from_millis(self.config.round_initial_timeout_ms),
-            1.5,
-            6,
-        ));
+        let time_interval: Box<dyn RoundTimeInterval> = match &self.config.round_timeout_type {
+            RoundTimeoutType::Exponential => {
+                Box::new(ExponentialTimeInterval::new(initial_timeout, 1.5, 6))
+            }
+            RoundTimeoutType::Adaptive(config) => Box::new(AdaptiveTimeInterval::new(
+                initial_timeout,
+                Duration::from_millis(config.min_timeout_ms),
+                Duration::from_millis(config.max_timeout_ms),
+                Duration::from_micros(config.per_txn_timeout_us),
+                1.5,
+                6,
+            )),
+        };
         RoundState::new(time_interval, time_service, timeout_sender)
     }
 
@@ -350,6 +361,24 @@ impl EpochManager {
         info!("SyncProcessor started");
     }
 
+    /// Returns the epoch state committed by the last ledger info of the previous epoch, which
+    /// carries the keys of the multi-signatures when the `ConsensusSignatureScheme` enables them.
+    #[cfg(feature = "bls")]
+    fn committed_epoch_state(&self, epoch_state: EpochState) -> anyhow::Result<EpochState> {
+        let committed = self.storage.retrieve_epoch_state(epoch_state.epoch)?;
+        ensure!(
+            committed.epoch == epoch_state.epoch
+                && committed
+                    .verifier
+                    .get_ordered_account_addresses_iter()
+                    .eq(epoch_state.verifier.get_ordered_account_addresses_iter()),
+            "The committed {} doesn't match the on-chain config {}",
+            committed,
+            epoch_state
+        );
+        Ok(committed)
+    }
+
     pub async fn start_processor(&mut self, payload: OnChainConfigPayload) {
         let validator_set: ValidatorSet = payload
             .get()
@@ -358,6 +387,10 @@ impl EpochManager {
             epoch: payload.epoch(),
             verifier: (&validator_set).into(),
         };
+        #[cfg(feature = "bls")]
+        let epoch_state = self
+            .committed_epoch_state(epoch_state)
+            .expect("failed to get the committed EpochState");
 
         match self.storage.start() {
             LivenessStorageData::RecoveryData(initial_data) => {
@@ -391,6 +424,7 @@ impl EpochManager {
                             .data_display("error", &err)
                             .data("event", &unverified_event)
                         );
+                        self.network_sender.report_peer(peer_id, PeerBehavior::Invalid);
                     err})?;
 
             // process the verified event
@@ -8,8 +8,11 @@
 //! The consensus protocol implemented is LibraBFT (based on
 //! [HotStuff](https://arxiv.org/pdf/1803.05069.pdf)).
 
-#![cfg_attr(not(feature = "fuzzing"), deny(missing_docs))]
-#![cfg_attr(feature = "fuzzing", allow(dead_code))]
+#![cfg_attr(
+    not(any(feature = "fuzzing", feature = "simulation")),
+    deny(missing_docs)
+)]
+#![cfg_attr(any(feature = "fuzzing", feature = "simulation"), allow(dead_code))]
 #![recursion_limit = "512"]
 
 mod block_storage;
@@ -18,6 +21,7 @@ mod counters;
 mod epoch_manager;
 mod liveness;
 mod metrics_safety_rules;
+mod misbehavior_detector;
 mod network;
 #[cfg(test)]
 mod network_tests;
@@ -26,7 +30,7 @@ mod persistent_liveness_storage;
 mod round_manager;
 mod state_computer;
 mod state_replication;
-#[cfg(any(test, feature = "fuzzing"))]
+#[cfg(any(test, feature = "fuzzing", feature = "simulation"))]
 mod test_utils;
 #[cfg(test)]
 mod twins_test;
@@ -40,4 +44,25 @@ pub mod network_interface;
 
 #[cfg(feature = "fuzzing")]
 pub use round_manager::round_manager_fuzzing;
+
+/// The parts of consensus the consensus simulator assembles into nodes and drives directly.
+#[cfg(feature = "simulation")]
+pub mod simulation {
+    pub use crate::{
+        block_storage::BlockStore,
+        liveness::{
+            proposal_generator::ProposalGenerator,
+            rotating_proposer_election::RotatingProposer,
+            round_state::{ExponentialTimeInterval, RoundState},
+        },
+        metrics_safety_rules::MetricsSafetyRules,
+        network::{IncomingBlockRetrievalRequest, NetworkSender},
+        persistent_liveness_storage::RecoveryData,
+        round_manager::{RoundManager, UnverifiedEvent, VerifiedEvent},
+        state_replication::StateComputer,
+        test_utils::{MockStateComputer, MockStorage, MockTransactionManager},
+        util::{mock_time_service::SimulatedTimeService, time_service::TimeService},
+    };
+}
+
 pub use util::config_subscription::gen_consensus_reconfig_subscription;
@@ -9,7 +9,7 @@ use crate::{
 use consensus_types::{common::Round, sync_info::SyncInfo, vote::Vote};
 use libra_logger::prelude::*;
 use libra_types::validator_verifier::ValidatorVerifier;
-use std::{fmt, sync::Arc, time::Duration};
+use std::{collections::VecDeque, convert::TryFrom, fmt, sync::Arc, time::Duration};
 
 /// A reason for starting a new round: introduced for monitoring / debug purposes.
 #[derive(Eq, Debug, PartialEq)]
@@ -61,6 +61,14 @@ pub trait RoundTimeInterval: Send + Sync + 'static {
     /// to calculate the round duration of round 6 and the highest committed round is 3 (meaning
     /// the highest round to commit a block is round 5, then the round index is 0.
     fn get_round_duration(&self, round_index_after_committed_qc: usize) -> Duration;
+
+    /// Called when a QC for the current round is observed. The latency is the time between the
+    /// start of the round and the QC, it is `None` if the round locally timed out before (the
+    /// QC is then formed out of timeout votes and the latency only reflects the timeout).
+    fn record_qc(&mut self, _round_latency: Option<Duration>, _proposal_txns: usize) {}
+
+    /// Called on the first local timeout of a round.
+    fn record_timeout(&mut self) {}
 }
 
 /// Round durations increase exponentially
@@ -82,7 +90,7 @@ pub struct ExponentialTimeInterval {
 
 #[allow(dead_code)]
 impl ExponentialTimeInterval {
-    #[cfg(any(test, feature = "fuzzing"))]
+    #[cfg(any(test, feature = "fuzzing", feature = "simulation"))]
     pub fn fixed(duration: Duration) -> Self {
         Self::new(duration, 1.0, 0)
     }
@@ -113,6 +121,139 @@ impl RoundTimeInterval for ExponentialTimeInterval {
     }
 }
 
+/// Number of recent proposals whose size is taken into account by `AdaptiveTimeInterval`.
+const RECENT_PROPOSALS: usize = 10;
+
+/// Round durations follow the observed round latencies: like TCP retransmission timeouts
+/// (RFC 6298) the duration is the smoothed latency plus four times its mean deviation, with an
+/// extra allowance for the largest of the recent proposals. Timeouts back off exponentially, but
+/// unlike `ExponentialTimeInterval` the backoff doesn't wait for a commit to come down: every QC
+/// halves it, so a single slow leader doesn't inflate the duration of the following rounds.
+pub struct AdaptiveTimeInterval {
+    // Duration used until the first latency is observed.
+    initial: Duration,
+    min: Duration,
+    max: Duration,
+    // Extra time allowed for every transaction of a proposal.
+    per_txn: Duration,
+    exponent_base: f64,
+    max_exponent: usize,
+    // Estimators of the round latency without the per transaction allowance.
+    smoothed_latency: Option<Duration>,
+    latency_deviation: Duration,
+    // Number of transactions of the recent proposals that gathered a QC.
+    recent_proposal_txns: VecDeque<usize>,
+    // Number of timeouts not compensated by QCs yet, capped by max_exponent.
+    backoff: usize,
+}
+
+impl AdaptiveTimeInterval {
+    pub fn new(
+        initial: Duration,
+        min: Duration,
+        max: Duration,
+        per_txn: Duration,
+        exponent_base: f64,
+        max_exponent: usize,
+    ) -> Self {
+        assert!(min <= max, "min round duration should not exceed max");
+        assert!(
+            max_exponent < 32,
+            "max_exponent for RoundStateTimeInterval should be <32"
+        );
+        assert!(
+            exponent_base.powf(max_exponent as f64).ceil() < f64::from(std::u32::MAX),
+            "Maximum interval multiplier should be less then u32::Max"
+        );
+        counters::ROUND_TIMEOUT_BACKOFF.set(0);
+        AdaptiveTimeInterval {
+            initial,
+            min,
+            max,
+            per_txn,
+            exponent_base,
+            max_exponent,
+            smoothed_latency: None,
+            latency_deviation: Duration::from_secs(0),
+            recent_proposal_txns: VecDeque::new(),
+            backoff: 0,
+        }
+    }
+
+    /// The extra time allowed for a proposal with the given number of transactions, capped at the
+    /// max round duration.
+    fn txn_allowance(&self, txns: usize) -> Duration {
+        u32::try_from(txns)
+            .ok()
+            .and_then(|txns| self.per_txn.checked_mul(txns))
+            .map_or(self.max, |allowance| allowance.min(self.max))
+    }
+
+    fn set_backoff(&mut self, backoff: usize) {
+        self.backoff = backoff.min(self.max_exponent);
+        counters::ROUND_TIMEOUT_BACKOFF.set(self.backoff as i64);
+    }
+}
+
+impl RoundTimeInterval for AdaptiveTimeInterval {
+    /// The index after the committed round is ignored: the backoff only grows with the actual
+    /// timeouts and goes down with QCs.
+    fn get_round_duration(&self, _round_index_after_committed_qc: usize) -> Duration {
+        let base = match self.smoothed_latency {
+            Some(latency) => {
+                let max_txns = self.recent_proposal_txns.iter().max().copied().unwrap_or(0);
+                self.latency_deviation
+                    .checked_mul(4)
+                    .and_then(|deviation| latency.checked_add(deviation))
+                    .and_then(|base| base.checked_add(self.txn_allowance(max_txns)))
+                    .unwrap_or(self.max)
+            }
+            None => self.initial,
+        };
+        let base_multiplier = self.exponent_base.powf(self.backoff as f64);
+        let duration_ms = ((base.as_millis() as f64) * base_multiplier)
+            .ceil()
+            .min(self.max.as_millis() as f64);
+        Duration::from_millis(duration_ms as u64)
+            .max(self.min)
+            .min(self.max)
+    }
+
+    fn record_qc(&mut self, round_latency: Option<Duration>, proposal_txns: usize) {
+        if let Some(latency) = round_latency {
+            // The estimators track the latency without the allowance for the proposal size, which
+            // is added back based on the recent proposals.
+            let latency = latency
+                .checked_sub(self.txn_allowance(proposal_txns))
+                .unwrap_or_default();
+            match self.smoothed_latency {
+                Some(smoothed) => {
+                    let deviation = if latency > smoothed {
+                        latency - smoothed
+                    } else {
+                        smoothed - latency
+                    };
+                    self.latency_deviation = (self.latency_deviation * 3 + deviation) / 4;
+                    self.smoothed_latency = Some((smoothed * 7 + latency) / 8);
+                }
+                None => {
+                    self.latency_deviation = latency / 2;
+                    self.smoothed_latency = Some(latency);
+                }
+            }
+            self.recent_proposal_txns.push_back(proposal_txns);
+            if self.recent_proposal_txns.len() > RECENT_PROPOSALS {
+                self.recent_proposal_txns.pop_front();
+            }
+        }
+        self.set_backoff(self.backoff / 2);
+    }
+
+    fn record_timeout(&mut self) {
+        self.set_backoff(self.backoff + 1);
+    }
+}
+
 /// `RoundState` contains information about a specific round and moves forward when
 /// receives new certificates.
 ///
@@ -141,6 +282,12 @@ pub struct RoundState {
     // a previous deadline expires.
     // Represents as Duration since UNIX_EPOCH.
     current_round_deadline: Duration,
+    // When the current round started, represented as Duration since UNIX_EPOCH.
+    current_round_start: Duration,
+    // Number of transactions in the proposal of the current round.
+    current_proposal_txns: usize,
+    // Whether the current round has already timed out locally.
+    current_round_timed_out: bool,
     // Service for timer
     time_service: Arc<dyn TimeService>,
     // To send local timeout events to the subscriber (e.g., SMR)
@@ -190,6 +337,9 @@ impl RoundState {
             highest_committed_round: 0,
             current_round: 0,
             current_round_deadline: time_service.get_current_timestamp(),
+            current_round_start: time_service.get_current_timestamp(),
+            current_proposal_txns: 0,
+            current_round_timed_out: false,
             time_service,
             timeout_sender,
             pending_votes: PendingVotes::new(),
@@ -215,6 +365,10 @@ impl RoundState {
         }
         warn!("Local timeout for round {}", round);
         counters::TIMEOUT_COUNT.inc();
+        if !self.current_round_timed_out {
+            self.current_round_timed_out = true;
+            self.time_interval.record_timeout();
+        }
         self.setup_timeout();
         true
     }
@@ -227,8 +381,25 @@ impl RoundState {
         }
         let new_round = sync_info.highest_round() + 1;
         if new_round > self.current_round {
+            let now = self.time_service.get_current_timestamp();
+            if self.current_round > 0 && sync_info.highest_certified_round() == self.current_round {
+                let round_latency = if self.current_round_timed_out {
+                    None
+                } else {
+                    let latency = now
+                        .checked_sub(self.current_round_start)
+                        .unwrap_or_default();
+                    counters::ROUND_QC_LATENCY_S.observe_duration(latency);
+                    Some(latency)
+                };
+                self.time_interval
+                    .record_qc(round_latency, self.current_proposal_txns);
+            }
             // Start a new round.
             self.current_round = new_round;
+            self.current_round_start = now;
+            self.current_proposal_txns = 0;
+            self.current_round_timed_out = false;
             self.pending_votes = PendingVotes::new();
             self.vote_sent = None;
             let timeout = self.setup_timeout();
@@ -274,6 +445,13 @@ impl RoundState {
         self.vote_sent.clone()
     }
 
+    /// Records the number of transactions of the valid proposal of the given round.
+    pub fn record_proposal(&mut self, round: Round, num_txns: usize) {
+        if round == self.current_round {
+            self.current_proposal_txns = num_txns;
+        }
+    }
+
     /// Setup the timeout task and return the duration of the current timeout
     fn setup_timeout(&mut self) -> Duration {
         let timeout_sender = self.timeout_sender.clone();
@@ -283,6 +461,7 @@ impl RoundState {
             timeout.as_millis(),
             self.current_round
         );
+        counters::ROUND_TIMEOUT_MS.set(timeout.as_millis() as i64);
         self.time_service
             .run_after(timeout, SendTask::make(timeout_sender, self.current_round));
         timeout
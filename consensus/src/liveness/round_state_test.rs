@@ -3,9 +3,10 @@
 
 use crate::{
     liveness::round_state::{
-        ExponentialTimeInterval, NewRoundEvent, NewRoundReason, RoundState, RoundTimeInterval,
+        AdaptiveTimeInterval, ExponentialTimeInterval, NewRoundEvent, NewRoundReason, RoundState,
+        RoundTimeInterval,
     },
-    util::mock_time_service::SimulatedTimeService,
+    util::{mock_time_service::SimulatedTimeService, time_service::TimeService},
 };
 
 use consensus_types::{
@@ -33,6 +34,111 @@ fn test_round_time_interval() {
     assert_eq!(6750, interval.get_round_duration(1000).as_millis());
 }
 
+fn adaptive_interval() -> AdaptiveTimeInterval {
+    AdaptiveTimeInterval::new(
+        Duration::from_millis(1000),
+        Duration::from_millis(100),
+        Duration::from_millis(5000),
+        Duration::from_millis(1),
+        2.0,
+        3,
+    )
+}
+
+#[test]
+fn test_adaptive_time_interval() {
+    let mut interval = adaptive_interval();
+    // nothing observed yet
+    assert_eq!(1000, interval.get_round_duration(0).as_millis());
+
+    // 200ms + 4 * 100ms
+    interval.record_qc(Some(Duration::from_millis(200)), 0);
+    assert_eq!(600, interval.get_round_duration(0).as_millis());
+    // the index after the committed round doesn't matter
+    assert_eq!(600, interval.get_round_duration(10).as_millis());
+
+    // a stable latency brings the deviation down
+    for _ in 0..20 {
+        interval.record_qc(Some(Duration::from_millis(200)), 0);
+    }
+    assert!(interval.get_round_duration(0) < Duration::from_millis(250));
+
+    // timeouts back off exponentially up to the max
+    interval.record_timeout();
+    let base = interval.get_round_duration(0);
+    interval.record_timeout();
+    interval.record_timeout();
+    let capped = interval.get_round_duration(0);
+    assert!(capped > base * 3);
+    for _ in 0..10 {
+        interval.record_timeout();
+    }
+    assert_eq!(capped, interval.get_round_duration(0));
+
+    // every QC halves the backoff, rounds that timed out don't count as latency samples
+    interval.record_qc(None, 0);
+    interval.record_qc(None, 0);
+    assert!(interval.get_round_duration(0) < base);
+
+    // the duration never exceeds the max
+    interval.record_qc(Some(Duration::from_secs(10)), 0);
+    assert_eq!(5000, interval.get_round_duration(0).as_millis());
+}
+
+#[test]
+fn test_adaptive_time_interval_proposal_size() {
+    let mut interval = adaptive_interval();
+    // the allowance for the transactions is not part of the latency estimate
+    interval.record_qc(Some(Duration::from_millis(300)), 100);
+    // 200ms + 4 * 100ms + 100 * 1ms
+    assert_eq!(700, interval.get_round_duration(0).as_millis());
+    // the largest recent proposal is accounted for
+    interval.record_qc(Some(Duration::from_millis(200)), 0);
+    assert!(interval.get_round_duration(0) > Duration::from_millis(300));
+    for _ in 0..10 {
+        interval.record_qc(Some(Duration::from_millis(200)), 0);
+    }
+    assert!(interval.get_round_duration(0) < Duration::from_millis(300));
+}
+
+#[test]
+fn test_adaptive_time_interval_huge_proposal() {
+    let mut interval = adaptive_interval();
+    // the allowance saturates instead of overflowing
+    interval.record_qc(Some(Duration::from_millis(200)), usize::max_value());
+    assert_eq!(5000, interval.get_round_duration(0).as_millis());
+    interval.record_qc(Some(Duration::from_millis(200)), u32::max_value() as usize);
+    assert_eq!(5000, interval.get_round_duration(0).as_millis());
+}
+
+#[test]
+fn test_round_latency_feeds_interval() {
+    let time_service = SimulatedTimeService::new();
+    let (timeout_tx, _timeout_rx) = channel::new_test(1_024);
+    let mut round_state = RoundState::new(
+        Box::new(adaptive_interval()),
+        Arc::new(time_service.clone()),
+        timeout_tx,
+    );
+    expect_qc(
+        1,
+        round_state.process_certificates(generate_sync_info(Some(0), None, None)),
+    );
+    time_service.sleep(Duration::from_millis(200));
+    let event = round_state
+        .process_certificates(generate_sync_info(Some(1), None, None))
+        .unwrap();
+    assert_eq!(600, event.timeout.as_millis());
+
+    // a round that times out locally backs off and is not a latency sample
+    assert!(round_state.process_local_timeout(2));
+    time_service.sleep(Duration::from_secs(3));
+    let event = round_state
+        .process_certificates(generate_sync_info(Some(2), None, None))
+        .unwrap();
+    assert_eq!(600, event.timeout.as_millis());
+}
+
 #[tokio::test]
 /// Verify that RoundState properly outputs local timeout events upon timeout
 async fn test_basic_timeout() {
@@ -0,0 +1,177 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! MisbehaviorDetector collects evidence of validators that equivocate within an epoch:
+//! double votes and conflicting timeouts (detected by `PendingVotes`) and double proposals
+//! (detected here). Every piece of evidence is logged, counted and persisted in ConsensusDB,
+//! from where it can be exported through the debug interface.
+//!
+//! Only proposals that passed the signature and the proposer checks are observed, and for each of
+//! them just the signed block id is remembered. The earlier block of a double proposal is looked
+//! up when the conflict is detected, which is what makes the evidence verifiable by others.
+
+use crate::{counters, persistent_liveness_storage::PersistentLivenessStorage};
+use anyhow::Result;
+use consensus_types::{
+    block::Block,
+    common::{Author, Round},
+    evidence::Evidence,
+};
+use debug_interface::node_debug_service::DebugStateProvider;
+use libra_crypto::{ed25519::Ed25519Signature, HashValue};
+use libra_logger::prelude::*;
+use serde_json::json;
+use std::{collections::BTreeMap, sync::Arc};
+
+#[cfg(test)]
+#[path = "misbehavior_detector_test.rs"]
+mod misbehavior_detector_test;
+
+/// Number of rounds behind the current round for which proposals are still remembered.
+const PROPOSAL_ROUNDS_TO_KEEP: Round = 10;
+/// Number of rounds ahead of the current round for which proposals are remembered. Anything
+/// further ahead is ignored, so that peers can't grow the map without bound.
+const PROPOSAL_ROUNDS_AHEAD: Round = 10;
+
+/// The signed id of the first proposal seen from an author in a round.
+struct ProposalRecord {
+    block_id: HashValue,
+    signature: Ed25519Signature,
+}
+
+pub struct MisbehaviorDetector {
+    storage: Arc<dyn PersistentLivenessStorage>,
+    /// The first proposal seen from every author in the recent rounds.
+    proposals: BTreeMap<(Round, Author), ProposalRecord>,
+    current_round: Round,
+}
+
+impl MisbehaviorDetector {
+    pub fn new(storage: Arc<dyn PersistentLivenessStorage>) -> Self {
+        // Our counters are initialized lazily, so they're not going to appear in
+        // Prometheus if some conditions never happen. Invoking get() function enforces creation.
+        counters::MISBEHAVIOR_EVIDENCE_COUNT.with_label_values(&["double_vote"]);
+        counters::MISBEHAVIOR_EVIDENCE_COUNT.with_label_values(&["double_proposal"]);
+        counters::MISBEHAVIOR_EVIDENCE_COUNT.with_label_values(&["conflicting_timeout"]);
+
+        Self {
+            storage,
+            proposals: BTreeMap::new(),
+            current_round: 0,
+        }
+    }
+
+    /// Remembers a verified proposal of a valid proposer and reports the evidence in case its
+    /// author already sent a different proposal for the same round. The earlier proposal is
+    /// fetched through `get_block`; if it's no longer available the equivocation is only logged,
+    /// because without the signed block data it can't be proven to anybody else. Proposals outside
+    /// of the window around the current round are ignored.
+    pub fn observe_proposal(
+        &mut self,
+        proposal: &Block,
+        get_block: impl FnOnce(HashValue) -> Option<Block>,
+    ) -> Option<Evidence> {
+        let author = proposal.author()?;
+        let signature = proposal.signature()?;
+        let min_round = self.current_round.saturating_sub(PROPOSAL_ROUNDS_TO_KEEP);
+        let max_round = self.current_round.saturating_add(PROPOSAL_ROUNDS_AHEAD);
+        if proposal.round() < min_round || proposal.round() > max_round {
+            return None;
+        }
+        let key = (proposal.round(), author);
+        match self.proposals.get(&key) {
+            Some(previous) if previous.block_id != proposal.id() => {
+                send_struct_log!(
+                    security_log(security_events::CONSENSUS_EQUIVOCATING_PROPOSAL)
+                        .data("from_peer", author)
+                        .data_display("proposal", proposal)
+                        .data_display("previous_proposal_id", previous.block_id)
+                        .data("previous_signature", &previous.signature)
+                );
+                match get_block(previous.block_id) {
+                    Some(previous_block) => {
+                        let evidence = Evidence::from_proposals(previous_block, proposal.clone());
+                        self.report(&evidence);
+                        Some(evidence)
+                    }
+                    None => {
+                        warn!(
+                            "[MisbehaviorDetector] Double proposal of {} in round {}, but the \
+                             previous proposal {} is not available anymore",
+                            author.short_str(),
+                            proposal.round(),
+                            previous.block_id
+                        );
+                        None
+                    }
+                }
+            }
+            Some(_) => None,
+            None => {
+                self.proposals.insert(
+                    key,
+                    ProposalRecord {
+                        block_id: proposal.id(),
+                        signature: signature.clone(),
+                    },
+                );
+                None
+            }
+        }
+    }
+
+    /// Counts and persists the evidence.
+    pub fn report(&self, evidence: &Evidence) {
+        warn!("[MisbehaviorDetector] {}", evidence);
+        counters::MISBEHAVIOR_EVIDENCE_COUNT
+            .with_label_values(&[evidence.kind().as_str()])
+            .inc();
+        if let Err(e) = self.storage.save_evidence(evidence) {
+            error!(
+                "[MisbehaviorDetector] Failed to persist {}: {:?}",
+                evidence, e
+            );
+        }
+    }
+
+    /// Moves the window to the given round and forgets the proposals that are too old to be
+    /// relevant.
+    pub fn prune(&mut self, current_round: Round) {
+        self.current_round = current_round;
+        let min_round = current_round.saturating_sub(PROPOSAL_ROUNDS_TO_KEEP);
+        self.proposals = self.proposals.split_off(&(min_round, Author::ZERO));
+    }
+}
+
+/// Exports the persisted evidence on the debug interface. Next to a human readable summary, each
+/// entry carries the hex-encoded LCS bytes of the `Evidence`, which is the format a slashing
+/// transaction is expected to consume.
+pub struct EvidenceDebugProvider {
+    storage: Arc<dyn PersistentLivenessStorage>,
+}
+
+impl EvidenceDebugProvider {
+    pub fn new(storage: Arc<dyn PersistentLivenessStorage>) -> Self {
+        Self { storage }
+    }
+}
+
+impl DebugStateProvider for EvidenceDebugProvider {
+    fn snapshot(&self) -> Result<serde_json::Value> {
+        let evidence = self
+            .storage
+            .retrieve_evidence()?
+            .iter()
+            .map(|evidence| {
+                Ok(json!({
+                    "kind": evidence.kind().as_str(),
+                    "author": evidence.author().map(|author| author.to_string()),
+                    "epoch": evidence.epoch(),
+                    "round": evidence.round(),
+                    "lcs": hex::encode(lcs::to_bytes(evidence)?),
+                }))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(serde_json::Value::Array(evidence))
+    }
+}
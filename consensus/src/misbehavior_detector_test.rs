@@ -0,0 +1,77 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    misbehavior_detector::MisbehaviorDetector,
+    persistent_liveness_storage::PersistentLivenessStorage,
+    test_utils::{MockSharedStorage, MockStorage},
+};
+use consensus_types::{
+    block::{block_test_utils::certificate_for_genesis, Block},
+    evidence::EvidenceKind,
+};
+use libra_crypto::HashValue;
+use libra_types::{on_chain_config::ValidatorSet, validator_signer::ValidatorSigner};
+use std::{collections::HashMap, sync::Arc};
+
+#[test]
+fn test_double_proposal_detection() {
+    let storage = Arc::new(MockStorage::new(Arc::new(MockSharedStorage::new(
+        ValidatorSet::empty(),
+    ))));
+    let mut detector = MisbehaviorDetector::new(storage.clone());
+    let signer = ValidatorSigner::random(None);
+    let other_signer = ValidatorSigner::random([1; 32]);
+    let genesis_qc = certificate_for_genesis();
+    let mut block_store = HashMap::new();
+    let mut observe = |detector: &mut MisbehaviorDetector, block: &Block| {
+        block_store.insert(block.id(), block.clone());
+        detector.observe_proposal(block, |id: HashValue| block_store.get(&id).cloned())
+    };
+
+    let proposal = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &signer);
+    assert!(observe(&mut detector, &proposal).is_none());
+    // the very same proposal is not an equivocation
+    assert!(observe(&mut detector, &proposal).is_none());
+    // a proposal from somebody else in the same round is not an equivocation either
+    let other_author = Block::new_proposal(vec![], 1, 2, genesis_qc.clone(), &other_signer);
+    assert!(observe(&mut detector, &other_author).is_none());
+
+    let conflicting = Block::new_proposal(vec![], 1, 2, genesis_qc.clone(), &signer);
+    let evidence = observe(&mut detector, &conflicting).expect("Double proposal must be detected");
+    assert_eq!(evidence.kind(), EvidenceKind::DoubleProposal);
+    assert_eq!(evidence.author(), Some(signer.author()));
+    assert_eq!(storage.retrieve_evidence().unwrap(), vec![evidence]);
+
+    // once the round is pruned the proposals are forgotten
+    detector.prune(100);
+    let late = Block::new_proposal(vec![], 1, 3, genesis_qc.clone(), &signer);
+    assert!(observe(&mut detector, &late).is_none());
+    assert!(detector.proposals.is_empty());
+
+    // proposals too far ahead of the current round are not remembered
+    let future = Block::new_proposal(vec![], 1000, 1, genesis_qc, &signer);
+    assert!(observe(&mut detector, &future).is_none());
+    assert!(detector.proposals.is_empty());
+}
+
+#[test]
+fn test_double_proposal_without_previous_block() {
+    let storage = Arc::new(MockStorage::new(Arc::new(MockSharedStorage::new(
+        ValidatorSet::empty(),
+    ))));
+    let mut detector = MisbehaviorDetector::new(storage.clone());
+    let signer = ValidatorSigner::random(None);
+    let genesis_qc = certificate_for_genesis();
+
+    let proposal = Block::new_proposal(vec![], 1, 1, genesis_qc.clone(), &signer);
+    assert!(detector.observe_proposal(&proposal, |_| None).is_none());
+    let record = &detector.proposals[&(1, signer.author())];
+    assert_eq!(record.block_id, proposal.id());
+    assert_eq!(Some(&record.signature), proposal.signature());
+
+    // without the signed data of the first proposal there is nothing to persist
+    let conflicting = Block::new_proposal(vec![], 1, 2, genesis_qc, &signer);
+    assert!(detector.observe_proposal(&conflicting, |_| None).is_none());
+    assert!(storage.retrieve_evidence().unwrap().is_empty());
+}
@@ -75,6 +75,11 @@ impl NetworkSender {
         }
     }
 
+    /// Returns the validators of the epoch.
+    pub fn validators(&self) -> &ValidatorVerifier {
+        &self.validators
+    }
+
     /// Tries to retrieve num of blocks backwards starting from id from the given peer: the function
     /// returns a future that is fulfilled with BlockRetrievalResponse.
     pub async fn request_block(
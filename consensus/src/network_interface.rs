@@ -12,6 +12,7 @@ use consensus_types::{
     sync_info::SyncInfo,
     vote_msg::VoteMsg,
 };
+use libra_logger::prelude::*;
 use libra_metrics::IntCounterVec;
 use libra_types::{epoch_change::EpochChangeProof, PeerId};
 use network::{
@@ -19,7 +20,7 @@ use network::{
     error::NetworkError,
     peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
     protocols::{
-        network::{NetworkEvents, NetworkSender, NewNetworkSender},
+        network::{NetworkEvents, NetworkSender, NewNetworkSender, PeerBehavior},
         rpc::error::RpcError,
     },
     ProtocolId,
@@ -136,4 +137,11 @@ impl ConsensusNetworkSender {
             .send_rpc(recipient, protocol, message, timeout)
             .await
     }
+
+    /// Report a peer which sent a message failing verification to the network layer.
+    pub fn report_peer(&mut self, peer: PeerId, behavior: PeerBehavior) {
+        if let Err(e) = self.network_sender.report_peer(peer, behavior) {
+            warn!("Failed to report peer {}: {}", peer.short_str(), e);
+        }
+    }
 }
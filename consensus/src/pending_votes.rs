@@ -8,12 +8,13 @@
 //! Votes are automatically dropped when the structure goes out of scope.
 
 use consensus_types::{
-    common::Author, quorum_cert::QuorumCert, timeout_certificate::TimeoutCertificate, vote::Vote,
+    common::Author, evidence::Evidence, quorum_cert::QuorumCert,
+    timeout_certificate::TimeoutCertificate, vote::Vote,
 };
 use libra_crypto::{hash::CryptoHash, HashValue};
 use libra_logger::prelude::*;
 use libra_types::{
-    ledger_info::LedgerInfoWithSignatures,
+    ledger_info::{LedgerInfoWithSignatures, LedgerInfoWithV0},
     validator_verifier::{ValidatorVerifier, VerifyError},
 };
 use std::{
@@ -32,7 +33,8 @@ pub enum VoteReceptionResult {
     /// The very same vote message has been processed in past.
     DuplicateVote,
     /// The very same author has already voted for another proposal in this round (equivocation).
-    EquivocateVote,
+    /// Carries the evidence made of the previously seen vote and the new one.
+    EquivocateVote(Box<Evidence>),
     /// This block has just been certified after adding the vote.
     NewQuorumCertificate(Arc<QuorumCert>),
     /// The vote completes a new TimeoutCertificate
@@ -48,7 +50,7 @@ pub struct PendingVotes {
     /// Maps LedgerInfo digest to associated signatures (contained in a partial LedgerInfoWithSignatures).
     /// This might keep multiple LedgerInfos for the current round: either due to different proposals (byzantine behavior)
     /// or due to different NIL proposals (clients can have a different view of what block to extend).
-    li_digest_to_votes: HashMap<HashValue /* LedgerInfo digest */, LedgerInfoWithSignatures>,
+    li_digest_to_votes: HashMap<HashValue /* LedgerInfo digest */, LedgerInfoWithV0>,
     /// Tracks all the signatures of the votes for the given round. In case we succeed to
     /// aggregate 2f+1 signatures a TimeoutCertificate is formed.
     maybe_partial_tc: Option<TimeoutCertificate>,
@@ -96,7 +98,10 @@ impl PendingVotes {
                     .data("vote", &vote)
                     .data("previous_vote", &previously_seen_vote));
 
-                return VoteReceptionResult::EquivocateVote;
+                return VoteReceptionResult::EquivocateVote(Box::new(Evidence::from_votes(
+                    previously_seen_vote.clone(),
+                    vote.clone(),
+                )));
             }
         }
 
@@ -113,35 +118,48 @@ impl PendingVotes {
         // obtain the ledger info with signatures associated to the vote's ledger info
         let li_with_sig = self.li_digest_to_votes.entry(li_digest).or_insert_with(|| {
             // if the ledger info with signatures doesn't exist yet, create it
-            LedgerInfoWithSignatures::new(vote.ledger_info().clone(), BTreeMap::new())
+            LedgerInfoWithV0::new(vote.ledger_info().clone(), BTreeMap::new())
         });
 
         // add this vote to the ledger info with signatures
         li_with_sig.add_signature(vote.author(), vote.signature().clone());
 
         // check if we have enough signatures to create a QC
-        let voting_power =
-            match validator_verifier.check_voting_power(li_with_sig.signatures().keys()) {
-                // a quorum of signature was reached, a new QC is formed
-                Ok(_) => {
-                    return VoteReceptionResult::NewQuorumCertificate(Arc::new(QuorumCert::new(
-                        vote.vote_data().clone(),
-                        li_with_sig.clone(),
-                    )));
-                }
+        let voting_power = match validator_verifier
+            .check_voting_power(li_with_sig.signatures().keys())
+        {
+            // a quorum of signature was reached, a new QC is formed
+            Ok(_) => {
+                return match quorum_signatures(
+                    li_with_sig,
+                    &self.author_to_vote,
+                    validator_verifier,
+                ) {
+                    Ok(signed_ledger_info) => VoteReceptionResult::NewQuorumCertificate(Arc::new(
+                        QuorumCert::new(vote.vote_data().clone(), signed_ledger_info),
+                    )),
+                    Err(error) => {
+                        error!(
+                            "MUST_FIX: quorum of votes could not be aggregated: {}, vote: {}",
+                            error, vote
+                        );
+                        VoteReceptionResult::ErrorAddingVote(error)
+                    }
+                };
+            }
 
-                // not enough votes
-                Err(VerifyError::TooLittleVotingPower { voting_power, .. }) => voting_power,
+            // not enough votes
+            Err(VerifyError::TooLittleVotingPower { voting_power, .. }) => voting_power,
 
-                // error
-                Err(error) => {
-                    error!(
-                        "MUST_FIX: vote received could not be added: {}, vote: {}",
-                        error, vote
-                    );
-                    return VoteReceptionResult::ErrorAddingVote(error);
-                }
-            };
+            // error
+            Err(error) => {
+                error!(
+                    "MUST_FIX: vote received could not be added: {}, vote: {}",
+                    error, vote
+                );
+                return VoteReceptionResult::ErrorAddingVote(error);
+            }
+        };
 
         //
         // 4. We couldn't form a QC, let's check if we can create a TC
@@ -189,6 +207,36 @@ impl PendingVotes {
     }
 }
 
+/// Returns the signatures of the QC formed by a quorum of votes: the individual signatures, or
+/// their BLS12-381 shares aggregated into a multi-signature if the validator set has them enabled.
+#[cfg_attr(not(feature = "bls"), allow(unused_variables))]
+fn quorum_signatures(
+    li_with_sig: &LedgerInfoWithV0,
+    author_to_vote: &HashMap<Author, Vote>,
+    validator_verifier: &ValidatorVerifier,
+) -> Result<LedgerInfoWithSignatures, VerifyError> {
+    #[cfg(feature = "bls")]
+    {
+        if let Some(verifier) = validator_verifier.multi_signature_verifier() {
+            let shares = li_with_sig
+                .signatures()
+                .keys()
+                .map(|author| {
+                    author_to_vote
+                        .get(author)
+                        .map(|vote| (*author, vote.multi_signature_share().clone()))
+                        .ok_or(VerifyError::UnknownAuthor)
+                })
+                .collect::<Result<_, _>>()?;
+            return Ok(LedgerInfoWithSignatures::new_with_multi_signature(
+                li_with_sig.ledger_info().clone(),
+                verifier.aggregate(&shares)?,
+            ));
+        }
+    }
+    Ok(LedgerInfoWithSignatures::V0(li_with_sig.clone()))
+}
+
 //
 // Helpful trait implementation
 //
@@ -230,12 +278,17 @@ impl fmt::Display for PendingVotes {
 #[cfg(test)]
 mod tests {
     use super::{PendingVotes, VoteReceptionResult};
-    use consensus_types::{vote::Vote, vote_data::VoteData};
+    use consensus_types::{evidence::EvidenceKind, vote::Vote, vote_data::VoteData};
     use libra_crypto::HashValue;
     use libra_types::{
         block_info::BlockInfo, ledger_info::LedgerInfo,
         validator_verifier::random_validator_verifier,
     };
+    #[cfg(feature = "bls")]
+    use libra_types::{
+        on_chain_config::ConsensusSignatureScheme, validator_signer::ValidatorSigner,
+        validator_verifier::VerifyError,
+    };
 
     /// Creates a random ledger info for epoch 1 and round 1.
     fn random_ledger_info() -> LedgerInfo {
@@ -286,10 +339,16 @@ mod tests {
             li2.clone(),
             &signers[0],
         );
-        assert_eq!(
-            pending_votes.insert_vote(&vote_data_2_author_0, &validator),
-            VoteReceptionResult::EquivocateVote
-        );
+        match pending_votes.insert_vote(&vote_data_2_author_0, &validator) {
+            VoteReceptionResult::EquivocateVote(evidence) => {
+                assert_eq!(evidence.kind(), EvidenceKind::DoubleVote);
+                assert_eq!(evidence.author(), Some(signers[0].author()));
+                assert!(evidence.verify(&validator).is_ok());
+            }
+            _ => {
+                panic!("No equivocation detected.");
+            }
+        };
 
         // a different author voting for a different result -> VoteAdded
         let vote_data_2_author_1 = Vote::new(
@@ -308,7 +367,7 @@ mod tests {
         match pending_votes.insert_vote(&vote_data_2_author_2, &validator) {
             VoteReceptionResult::NewQuorumCertificate(qc) => {
                 assert!(validator
-                    .check_voting_power(qc.ledger_info().signatures().keys())
+                    .check_voting_power(qc.ledger_info().signers(&validator).iter())
                     .is_ok());
             }
             _ => {
@@ -368,4 +427,54 @@ mod tests {
             }
         };
     }
+
+    #[cfg(feature = "bls")]
+    #[test]
+    /// Verify that with multi-signatures enabled, votes are aggregated into a QC with a single
+    /// signature
+    fn test_multi_signed_qc_aggregation() {
+        let (signers, ed25519_validator) = random_validator_verifier(4, None, false);
+        let scheme = ConsensusSignatureScheme::Bls12381(
+            signers
+                .iter()
+                .map(ValidatorSigner::bls_validator_key)
+                .collect(),
+        );
+        let validator = ed25519_validator
+            .clone()
+            .with_signature_scheme(&scheme)
+            .unwrap();
+        let mut pending_votes = PendingVotes::new();
+
+        let li = random_ledger_info();
+        let vote_data = random_vote_data();
+        for signer in &signers[..2] {
+            let vote = Vote::new(vote_data.clone(), signer.author(), li.clone(), signer);
+            assert!(vote.verify(&validator).is_ok());
+            assert!(matches!(
+                pending_votes.insert_vote(&vote, &validator),
+                VoteReceptionResult::VoteAdded(_)
+            ));
+        }
+
+        let vote = Vote::new(vote_data, signers[2].author(), li, &signers[2]);
+        match pending_votes.insert_vote(&vote, &validator) {
+            VoteReceptionResult::NewQuorumCertificate(qc) => {
+                let signed_ledger_info = qc.ledger_info();
+                assert!(signed_ledger_info.signatures().is_none());
+                let mut voters: Vec<_> = signers[..3].iter().map(|s| s.author()).collect();
+                voters.sort();
+                assert_eq!(signed_ledger_info.signers(&validator), voters);
+                assert!(signed_ledger_info.verify_signatures(&validator).is_ok());
+                // a validator set without multi-signatures can't verify the QC
+                assert_eq!(
+                    signed_ledger_info.verify_signatures(&ed25519_validator),
+                    Err(VerifyError::UnexpectedMultiSignature)
+                );
+            }
+            _ => {
+                panic!("No QC formed.");
+            }
+        };
+    }
 }
@@ -4,7 +4,8 @@
 use crate::{consensusdb::ConsensusDB, epoch_manager::LivenessStorageData};
 use anyhow::{format_err, Context, Result};
 use consensus_types::{
-    block::Block, quorum_cert::QuorumCert, timeout_certificate::TimeoutCertificate, vote::Vote,
+    block::Block, evidence::Evidence, quorum_cert::QuorumCert,
+    timeout_certificate::TimeoutCertificate, vote::Vote,
 };
 use executor_types::ExecutedTrees;
 use libra_config::config::NodeConfig;
@@ -12,8 +13,8 @@ use libra_crypto::HashValue;
 use libra_logger::prelude::*;
 use libra_trace::prelude::*;
 use libra_types::{
-    block_info::Round, epoch_change::EpochChangeProof, ledger_info::LedgerInfo,
-    transaction::Version,
+    block_info::Round, epoch_change::EpochChangeProof, epoch_state::EpochState,
+    ledger_info::LedgerInfo, transaction::Version,
 };
 use std::{cmp::max, collections::HashSet, sync::Arc};
 use storage_interface::DbReader;
@@ -43,10 +44,19 @@ pub trait PersistentLivenessStorage: Send + Sync {
     /// to jump to this round
     fn save_highest_timeout_cert(&self, highest_timeout_cert: TimeoutCertificate) -> Result<()>;
 
+    /// Persist the evidence of a misbehaving validator.
+    fn save_evidence(&self, evidence: &Evidence) -> Result<()>;
+
+    /// Retrieve all the evidence of misbehavior persisted so far.
+    fn retrieve_evidence(&self) -> Result<Vec<Evidence>>;
+
     /// Retrieve a epoch change proof for SafetyRules so it can instantiate its
     /// ValidatorVerifier.
     fn retrieve_epoch_change_proof(&self, version: u64) -> Result<EpochChangeProof>;
 
+    /// Retrieve the EpochState committed by the ledger info that ends the epoch before `epoch`.
+    fn retrieve_epoch_state(&self, epoch: u64) -> Result<EpochState>;
+
     /// Returns a handle of the libradb.
     fn libra_db(&self) -> Arc<dyn DbReader>;
 }
@@ -143,7 +153,7 @@ impl RootMetadata {
         max(self.num_leaves, 1) - 1
     }
 
-    #[cfg(any(test, feature = "fuzzing"))]
+    #[cfg(any(test, feature = "fuzzing", feature = "simulation"))]
     pub fn new_empty() -> Self {
         Self::new(0, *libra_crypto::hash::ACCUMULATOR_PLACEHOLDER_HASH, vec![])
     }
@@ -404,11 +414,31 @@ impl PersistentLivenessStorage for StorageWriteProxy {
             .save_highest_timeout_certificate(lcs::to_bytes(&highest_timeout_cert)?)
     }
 
+    fn save_evidence(&self, evidence: &Evidence) -> Result<()> {
+        self.db.save_evidence(evidence)
+    }
+
+    fn retrieve_evidence(&self) -> Result<Vec<Evidence>> {
+        self.db.get_evidence()
+    }
+
     fn retrieve_epoch_change_proof(&self, version: u64) -> Result<EpochChangeProof> {
         let (_, proofs, _) = self.libra_db.get_state_proof(version)?;
         Ok(proofs)
     }
 
+    fn retrieve_epoch_state(&self, epoch: u64) -> Result<EpochState> {
+        let proof = self
+            .libra_db
+            .get_epoch_ending_ledger_infos(epoch.saturating_sub(1), epoch)?;
+        proof
+            .ledger_info_with_sigs
+            .last()
+            .and_then(|ledger_info| ledger_info.ledger_info().next_epoch_state())
+            .cloned()
+            .ok_or_else(|| format_err!("No ledger info ends the epoch before {}", epoch))
+    }
+
     fn libra_db(&self) -> Arc<dyn DbReader> {
         self.libra_db.clone()
     }
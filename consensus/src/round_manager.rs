@@ -10,6 +10,7 @@ use crate::{
         round_state::{NewRoundEvent, NewRoundReason, RoundState},
     },
     metrics_safety_rules::MetricsSafetyRules,
+    misbehavior_detector::MisbehaviorDetector,
     network::{IncomingBlockRetrievalRequest, NetworkSender},
     network_interface::ConsensusMsg,
     pending_votes::VoteReceptionResult,
@@ -32,6 +33,7 @@ use consensus_types::{
 use libra_logger::prelude::*;
 use libra_trace::prelude::*;
 use libra_types::{epoch_state::EpochState, validator_verifier::ValidatorVerifier};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 #[cfg(test)]
 use safety_rules::ConsensusState;
 use safety_rules::TSafetyRules;
@@ -150,7 +152,11 @@ impl RecoveryManager {
             sync_info.epoch() == self.epoch_state.epoch,
             "[RecoveryManager] Received sync info is in different epoch than committed block"
         );
-        let mut retriever = BlockRetriever::new(self.network.clone(), peer);
+        let mut retriever = BlockRetriever::new(
+            self.network.clone(),
+            peer,
+            StdRng::seed_from_u64(thread_rng().gen()),
+        );
         let recovery_data = BlockStore::fast_forward_sync(
             &sync_info.highest_commit_cert(),
             &mut retriever,
@@ -182,6 +188,9 @@ pub struct RoundManager {
     network: NetworkSender,
     txn_manager: Arc<dyn TxnManager>,
     storage: Arc<dyn PersistentLivenessStorage>,
+    misbehavior_detector: MisbehaviorDetector,
+    /// Seeds the block retrievers, so that the peers they pick can be reproduced in tests.
+    retrieval_rng: StdRng,
 }
 
 impl RoundManager {
@@ -196,6 +205,7 @@ impl RoundManager {
         txn_manager: Arc<dyn TxnManager>,
         storage: Arc<dyn PersistentLivenessStorage>,
     ) -> Self {
+        let misbehavior_detector = MisbehaviorDetector::new(storage.clone());
         Self {
             epoch_state,
             block_store,
@@ -206,11 +216,23 @@ impl RoundManager {
             txn_manager,
             network,
             storage,
+            misbehavior_detector,
+            retrieval_rng: StdRng::seed_from_u64(thread_rng().gen()),
         }
     }
 
-    fn create_block_retriever(&self, author: Author) -> BlockRetriever {
-        BlockRetriever::new(self.network.clone(), author)
+    /// Makes the peers picked by block retrievals a function of the given seed.
+    #[cfg(feature = "simulation")]
+    pub fn seed_retrieval_rng(&mut self, seed: u64) {
+        self.retrieval_rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn create_block_retriever(&mut self, author: Author) -> BlockRetriever {
+        BlockRetriever::new(
+            self.network.clone(),
+            author,
+            StdRng::seed_from_u64(self.retrieval_rng.gen()),
+        )
     }
 
     /// Leader:
@@ -231,7 +253,6 @@ impl RoundManager {
     ) -> anyhow::Result<()> {
         debug!("Processing {}", new_round_event);
         counters::CURRENT_ROUND.set(new_round_event.round as i64);
-        counters::ROUND_TIMEOUT_MS.set(new_round_event.timeout.as_millis() as i64);
         match new_round_event.reason {
             NewRoundReason::QCReady => {
                 counters::QC_ROUNDS_COUNT.inc();
@@ -240,6 +261,7 @@ impl RoundManager {
                 counters::TIMEOUT_ROUNDS_COUNT.inc();
             }
         };
+        self.misbehavior_detector.prune(new_round_event.round);
         if self
             .proposer_election
             .is_valid_proposer(self.proposal_generator.author(), new_round_event.round)
@@ -334,10 +356,8 @@ impl RoundManager {
                         .data_display("error", &e));
                     e
                 })?;
-            let result = self
-                .block_store
-                .add_certs(&sync_info, self.create_block_retriever(author))
-                .await;
+            let retriever = self.create_block_retriever(author);
+            let result = self.block_store.add_certs(&sync_info, retriever).await;
             self.process_certificates().await?;
             result
         } else {
@@ -441,7 +461,7 @@ impl RoundManager {
     }
 
     /// This function processes a proposal for the current round:
-    /// 1. Filter if it's proposed by valid proposer.
+    /// 1. Filter if it's proposed by valid proposer and remember it for equivocation detection.
     /// 2. Execute and add it to a block store.
     /// 3. Try to vote for it following the safety rules.
     /// 4. In case a validator chooses to vote, send the vote to the representatives at the next
@@ -455,6 +475,13 @@ impl RoundManager {
                 .expect("Proposal should be verified having an author"),
             proposal,
         );
+        let block_store = &self.block_store;
+        self.misbehavior_detector
+            .observe_proposal(&proposal, |block_id| {
+                block_store
+                    .get_block(block_id)
+                    .map(|block| block.block().clone())
+            });
 
         let block_time_since_epoch = Duration::from_micros(proposal.timestamp_usecs());
 
@@ -467,6 +494,8 @@ impl RoundManager {
         );
 
         debug!("RoundManager: process_proposed_block {}", proposal);
+        self.round_state
+            .record_proposal(proposal.round(), proposal.payload().map_or(0, Vec::len));
 
         if let Some(time_to_receival) = duration_since_epoch().checked_sub(block_time_since_epoch) {
             counters::CREATION_TO_RECEIVAL_S.observe_duration(time_to_receival);
@@ -609,6 +638,10 @@ impl RoundManager {
                 self.new_qc_aggregated(qc, vote.author()).await
             }
             VoteReceptionResult::NewTimeoutCertificate(tc) => self.new_tc_aggregated(tc).await,
+            VoteReceptionResult::EquivocateVote(evidence) => {
+                self.misbehavior_detector.report(&evidence);
+                Ok(())
+            }
             _ => Ok(()),
         }
     }
@@ -618,9 +651,10 @@ impl RoundManager {
         qc: Arc<QuorumCert>,
         preferred_peer: Author,
     ) -> anyhow::Result<()> {
+        let mut retriever = self.create_block_retriever(preferred_peer);
         let result = self
             .block_store
-            .insert_quorum_cert(&qc, &mut self.create_block_retriever(preferred_peer))
+            .insert_quorum_cert(&qc, &mut retriever)
             .await
             .context("[RoundManager] Failed to process a newly aggregated QC");
         self.process_certificates().await?;
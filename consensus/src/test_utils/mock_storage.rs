@@ -9,11 +9,13 @@ use crate::{
 };
 use anyhow::Result;
 use consensus_types::{
-    block::Block, quorum_cert::QuorumCert, timeout_certificate::TimeoutCertificate, vote::Vote,
+    block::Block, evidence::Evidence, quorum_cert::QuorumCert,
+    timeout_certificate::TimeoutCertificate, vote::Vote,
 };
 use libra_crypto::HashValue;
 use libra_types::{
     epoch_change::EpochChangeProof,
+    epoch_state::EpochState,
     ledger_info::{LedgerInfo, LedgerInfoWithSignatures},
     on_chain_config::ValidatorSet,
 };
@@ -29,6 +31,7 @@ pub struct MockSharedStorage {
     pub qc: Mutex<HashMap<HashValue, QuorumCert>>,
     pub lis: Mutex<HashMap<u64, LedgerInfoWithSignatures>>,
     pub last_vote: Mutex<Option<Vote>>,
+    pub evidence: Mutex<Vec<Evidence>>,
 
     // Liveness state
     pub highest_timeout_certificate: Mutex<Option<TimeoutCertificate>>,
@@ -42,6 +45,7 @@ impl MockSharedStorage {
             qc: Mutex::new(HashMap::new()),
             lis: Mutex::new(HashMap::new()),
             last_vote: Mutex::new(None),
+            evidence: Mutex::new(vec![]),
             highest_timeout_certificate: Mutex::new(None),
             validator_set,
         }
@@ -158,6 +162,7 @@ impl MockStorage {
             qc: Mutex::new(HashMap::new()),
             lis: Mutex::new(HashMap::new()),
             last_vote: Mutex::new(None),
+            evidence: Mutex::new(vec![]),
             highest_timeout_certificate: Mutex::new(None),
             validator_set: validator_set.clone(),
         });
@@ -244,6 +249,18 @@ impl PersistentLivenessStorage for MockStorage {
         Ok(())
     }
 
+    fn save_evidence(&self, evidence: &Evidence) -> Result<()> {
+        let mut stored = self.shared_storage.evidence.lock().unwrap();
+        if !stored.contains(evidence) {
+            stored.push(evidence.clone());
+        }
+        Ok(())
+    }
+
+    fn retrieve_evidence(&self) -> Result<Vec<Evidence>> {
+        Ok(self.shared_storage.evidence.lock().unwrap().clone())
+    }
+
     fn retrieve_epoch_change_proof(&self, version: u64) -> Result<EpochChangeProof> {
         let lis = self
             .shared_storage
@@ -256,6 +273,18 @@ impl PersistentLivenessStorage for MockStorage {
         Ok(EpochChangeProof::new(vec![lis], false))
     }
 
+    fn retrieve_epoch_state(&self, epoch: u64) -> Result<EpochState> {
+        self.shared_storage
+            .lis
+            .lock()
+            .unwrap()
+            .values()
+            .filter_map(|lis| lis.ledger_info().next_epoch_state())
+            .find(|epoch_state| epoch_state.epoch == epoch)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("EpochState for epoch not found"))
+    }
+
     fn libra_db(&self) -> Arc<dyn DbReader> {
         unimplemented!()
     }
@@ -316,10 +345,22 @@ impl PersistentLivenessStorage for EmptyStorage {
         Ok(())
     }
 
+    fn save_evidence(&self, _: &Evidence) -> Result<()> {
+        Ok(())
+    }
+
+    fn retrieve_evidence(&self) -> Result<Vec<Evidence>> {
+        Ok(vec![])
+    }
+
     fn retrieve_epoch_change_proof(&self, _version: u64) -> Result<EpochChangeProof> {
         unimplemented!()
     }
 
+    fn retrieve_epoch_state(&self, _epoch: u64) -> Result<EpochState> {
+        unimplemented!()
+    }
+
     fn libra_db(&self) -> Arc<dyn DbReader> {
         unimplemented!()
     }
@@ -17,7 +17,7 @@ use tokio::{runtime, time::timeout};
 
 mod mock_state_computer;
 mod mock_storage;
-#[cfg(any(test, feature = "fuzzing"))]
+#[cfg(any(test, feature = "fuzzing", feature = "simulation"))]
 mod mock_txn_manager;
 
 use crate::util::mock_time_service::SimulatedTimeService;
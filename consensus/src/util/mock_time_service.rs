@@ -129,6 +129,45 @@ impl SimulatedTimeService {
             futures::executor::block_on(t.run());
         }
     }
+
+    /// Moves the clock forward to the given time (it never goes backwards) and runs, in the
+    /// order of their deadlines, all the pending tasks that are due by then.
+    #[cfg(feature = "simulation")]
+    pub fn advance_to(&self, time: Duration) {
+        let drain = {
+            let mut inner = self.inner.lock().unwrap();
+            if time > inner.time_limit {
+                inner.time_limit = time;
+            }
+            if time > inner.now {
+                inner.now = time;
+            }
+            let time_limit = inner.time_limit;
+            let (mut due, pending): (Vec<_>, Vec<_>) = inner
+                .pending
+                .drain(..)
+                .partition(|(deadline, _)| *deadline <= time_limit);
+            inner.pending = pending;
+            // stable sort keeps tasks with the same deadline in scheduling order
+            due.sort_by_key(|(deadline, _)| *deadline);
+            due
+        };
+        for (_, mut t) in drain {
+            futures::executor::block_on(t.run());
+        }
+    }
+
+    /// Returns the earliest deadline among the tasks that are not executed yet.
+    #[cfg(feature = "simulation")]
+    pub fn next_pending_deadline(&self) -> Option<Duration> {
+        self.inner
+            .lock()
+            .unwrap()
+            .pending
+            .iter()
+            .map(|(deadline, _)| *deadline)
+            .min()
+    }
 }
 
 impl Clone for SimulatedTimeService {
@@ -4,7 +4,7 @@
 #[macro_use]
 extern crate criterion;
 
-use criterion::Criterion;
+use criterion::{BenchmarkId, Criterion};
 
 use libra_crypto_derive::{CryptoHasher, LCSCryptoHash};
 use rand::{prelude::ThreadRng, thread_rng};
@@ -30,5 +30,41 @@ fn verify(c: &mut Criterion) {
     });
 }
 
-criterion_group!(ed25519_benches, verify);
+fn batch_verify(c: &mut Criterion) {
+    let mut csprng: ThreadRng = thread_rng();
+    let msg = TestLibraCrypto("".to_string());
+    let mut group = c.benchmark_group("Ed25519 batch signature verification");
+    // Typical quorum sizes for validator sets of 4, 10, 33 and 100 validators.
+    for size in [3usize, 7, 23, 67].iter() {
+        let keys_and_signatures: Vec<(Ed25519PublicKey, Ed25519Signature)> = (0..*size)
+            .map(|_| {
+                let priv_key = Ed25519PrivateKey::generate(&mut csprng);
+                let sig = priv_key.sign(&msg);
+                ((&priv_key).into(), sig)
+            })
+            .collect();
+
+        group.bench_with_input(
+            BenchmarkId::new("individual", size),
+            &keys_and_signatures,
+            |b, keys_and_signatures| {
+                b.iter(|| {
+                    keys_and_signatures
+                        .iter()
+                        .all(|(key, sig)| sig.verify(&msg, key).is_ok())
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("batch", size),
+            &keys_and_signatures,
+            |b, keys_and_signatures| {
+                b.iter(|| Ed25519Signature::batch_verify(&msg, keys_and_signatures.clone()))
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(ed25519_benches, verify, batch_verify);
 criterion_main!(ed25519_benches);
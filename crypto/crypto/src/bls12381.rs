@@ -0,0 +1,554 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module provides an API for the BLS signature scheme over the BLS12-381 curve, in the
+//! "minimal-pubkey-size" variant (public keys in G1, signatures in G2) with the proof-of-possession
+//! ciphersuite of the
+//! [IETF BLS draft](https://tools.ietf.org/html/draft-irtf-cfrg-bls-signature-04).
+//!
+//! Signatures of the same message can be aggregated into a single signature which verifies
+//! against the aggregate of the signers' public keys. To prevent rogue-key attacks, a public key
+//! must only be aggregated once its [`ProofOfPossession`][ProofOfPossession] has been verified.
+//!
+//! # Examples
+//!
+//! ```
+//! use libra_crypto_derive::{CryptoHasher, LCSCryptoHash};
+//! use libra_crypto::{
+//!     bls12381::*,
+//!     traits::{Signature, SigningKey, Uniform},
+//! };
+//! use rand::{rngs::StdRng, SeedableRng};
+//! use serde::{Serialize, Deserialize};
+//!
+//! #[derive(Serialize, Deserialize, CryptoHasher, LCSCryptoHash)]
+//! pub struct TestCryptoDocTest(String);
+//! let message = TestCryptoDocTest("Test message".to_string());
+//!
+//! let mut rng: StdRng = SeedableRng::from_seed([0; 32]);
+//! let private_keys: Vec<_> = (0..3).map(|_| Bls12381PrivateKey::generate(&mut rng)).collect();
+//! let public_keys: Vec<Bls12381PublicKey> = private_keys.iter().map(|key| key.into()).collect();
+//! for (private_key, public_key) in private_keys.iter().zip(public_keys.iter()) {
+//!     let pop = ProofOfPossession::create(private_key);
+//!     assert!(pop.verify(public_key).is_ok());
+//! }
+//!
+//! let signatures: Vec<_> = private_keys.iter().map(|key| key.sign(&message)).collect();
+//! let signature = Bls12381Signature::aggregate(signatures.iter()).unwrap();
+//! let public_key = Bls12381PublicKey::aggregate(public_keys.iter()).unwrap();
+//! assert!(signature.verify(&message, &public_key).is_ok());
+//! ```
+//! **Note**: The above example generates private keys using a private function intended only for
+//! testing purposes. Production code should find an alternate means for secure key generation.
+
+use crate::{
+    hash::{CryptoHash, CryptoHasher},
+    traits::*,
+};
+use anyhow::{anyhow, Result};
+use blst::{
+    min_pk::{AggregatePublicKey, AggregateSignature},
+    BLST_ERROR,
+};
+use core::convert::TryFrom;
+use libra_crypto_derive::{DeserializeKey, SerializeKey, SilentDebug, SilentDisplay};
+use rand::Rng;
+use serde::Serialize;
+use std::fmt;
+
+/// The length of the Bls12381PrivateKey
+pub const BLS12381_PRIVATE_KEY_LENGTH: usize = 32;
+/// The length of a compressed Bls12381PublicKey
+pub const BLS12381_PUBLIC_KEY_LENGTH: usize = 48;
+/// The length of a compressed Bls12381Signature
+pub const BLS12381_SIGNATURE_LENGTH: usize = 96;
+
+/// Domain separation tag of signatures, as defined for the proof-of-possession ciphersuite.
+const DST_SIGNATURE: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+/// Domain separation tag of proofs of possession, distinct from the signature one so that a
+/// proof of possession can never be mistaken for a signature.
+const DST_PROOF_OF_POSSESSION: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+/// Size of the random scalars of batch verification.
+const RANDOM_SCALAR_BITS: usize = 64;
+
+/// A BLS12-381 private key
+#[derive(DeserializeKey, SerializeKey, SilentDebug, SilentDisplay)]
+pub struct Bls12381PrivateKey(blst::min_pk::SecretKey);
+
+#[cfg(feature = "assert-private-keys-not-cloneable")]
+static_assertions::assert_not_impl_any!(Bls12381PrivateKey: Clone);
+
+#[cfg(any(test, feature = "cloneable-private-keys"))]
+impl Clone for Bls12381PrivateKey {
+    fn clone(&self) -> Self {
+        let serialized: &[u8] = &(self.to_bytes());
+        Bls12381PrivateKey::try_from(serialized).unwrap()
+    }
+}
+
+/// A BLS12-381 public key, possibly the aggregate of several public keys
+#[derive(DeserializeKey, Clone, SerializeKey)]
+pub struct Bls12381PublicKey(blst::min_pk::PublicKey);
+
+/// A BLS12-381 signature, possibly the aggregate of several signatures of the same message
+#[derive(DeserializeKey, Clone, SerializeKey)]
+pub struct Bls12381Signature(blst::min_pk::Signature);
+
+/// A proof that the owner of a public key knows the matching private key, i.e. a signature of
+/// the public key itself under a dedicated domain separation tag.
+#[derive(DeserializeKey, Clone, SerializeKey)]
+pub struct ProofOfPossession(blst::min_pk::Signature);
+
+impl Bls12381PrivateKey {
+    /// Serialize a Bls12381PrivateKey.
+    pub fn to_bytes(&self) -> [u8; BLS12381_PRIVATE_KEY_LENGTH] {
+        self.0.to_bytes()
+    }
+
+    /// Deterministically derives a private key from at least 32 bytes of secret key material,
+    /// following the KeyGen procedure of the IETF BLS draft. Different `key_info` values yield
+    /// independent keys for the same key material.
+    pub fn derive(key_material: &[u8], key_info: &[u8]) -> Result<Self> {
+        blst::min_pk::SecretKey::key_gen(key_material, key_info)
+            .map(Bls12381PrivateKey)
+            .map_err(|e| anyhow!("{:?}", e))
+    }
+
+    /// Private function aimed at minimizing code duplication between sign
+    /// methods of the SigningKey implementation. This should remain private.
+    fn sign_arbitrary_message(&self, message: &[u8]) -> Bls12381Signature {
+        Bls12381Signature(self.0.sign(message, DST_SIGNATURE, &[]))
+    }
+}
+
+impl Bls12381PublicKey {
+    /// Serialize a Bls12381PublicKey in compressed form.
+    pub fn to_bytes(&self) -> [u8; BLS12381_PUBLIC_KEY_LENGTH] {
+        self.0.compress()
+    }
+
+    /// Aggregates public keys into a single public key, against which the aggregate of their
+    /// signatures of a message verifies.
+    ///
+    /// The proof of possession of every key must have been verified beforehand, otherwise
+    /// a signer could pick its key as a function of the others and forge aggregate signatures.
+    pub fn aggregate<'a>(public_keys: impl IntoIterator<Item = &'a Self>) -> Result<Self> {
+        let public_keys: Vec<_> = public_keys.into_iter().map(|key| &key.0).collect();
+        ensure_not_empty(public_keys.len())?;
+        // Keys were validated on deserialization.
+        AggregatePublicKey::aggregate(&public_keys, false)
+            .map(|aggregate| Bls12381PublicKey(aggregate.to_public_key()))
+            .map_err(|e| anyhow!("{:?}", e))
+    }
+}
+
+impl Bls12381Signature {
+    /// Serialize a Bls12381Signature in compressed form.
+    pub fn to_bytes(&self) -> [u8; BLS12381_SIGNATURE_LENGTH] {
+        self.0.compress()
+    }
+
+    /// Aggregates signatures of the same message into a single signature, which verifies against
+    /// the aggregate of the signers' public keys.
+    pub fn aggregate<'a>(signatures: impl IntoIterator<Item = &'a Self>) -> Result<Self> {
+        let signatures: Vec<_> = signatures
+            .into_iter()
+            .map(|signature| &signature.0)
+            .collect();
+        ensure_not_empty(signatures.len())?;
+        // Signatures were subgroup-checked on deserialization.
+        AggregateSignature::aggregate(&signatures, false)
+            .map(|aggregate| Bls12381Signature(aggregate.to_signature()))
+            .map_err(|e| anyhow!("{:?}", e))
+    }
+}
+
+impl ProofOfPossession {
+    /// Creates the proof of possession of the given private key.
+    pub fn create(private_key: &Bls12381PrivateKey) -> Self {
+        let public_key: Bls12381PublicKey = private_key.into();
+        ProofOfPossession(
+            private_key
+                .0
+                .sign(&public_key.to_bytes(), DST_PROOF_OF_POSSESSION, &[]),
+        )
+    }
+
+    /// Checks that this is a proof of possession of the private key matching `public_key`.
+    pub fn verify(&self, public_key: &Bls12381PublicKey) -> Result<()> {
+        check_blst_result(self.0.verify(
+            false,
+            &public_key.to_bytes(),
+            DST_PROOF_OF_POSSESSION,
+            &[],
+            &public_key.0,
+            false,
+        ))
+    }
+
+    /// Serialize a ProofOfPossession in compressed form.
+    pub fn to_bytes(&self) -> [u8; BLS12381_SIGNATURE_LENGTH] {
+        self.0.compress()
+    }
+}
+
+fn ensure_not_empty(len: usize) -> Result<()> {
+    if len == 0 {
+        Err(anyhow!("Cannot aggregate an empty set"))
+    } else {
+        Ok(())
+    }
+}
+
+fn check_blst_result(result: BLST_ERROR) -> Result<()> {
+    match result {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        e => Err(anyhow!("{:?}", e)),
+    }
+}
+
+/// A non-zero random scalar of `RANDOM_SCALAR_BITS` bits.
+fn random_scalar<R: Rng>(rng: &mut R) -> blst::blst_scalar {
+    let mut scalar = blst::blst_scalar::default();
+    let value: u64 = rng.gen_range(1, u64::max_value());
+    scalar.b[..8].copy_from_slice(&value.to_le_bytes());
+    scalar
+}
+
+/// Deserializes a point of G2, checking that it lies in the prime order subgroup.
+fn signature_from_bytes(
+    bytes: &[u8],
+) -> std::result::Result<blst::min_pk::Signature, CryptoMaterialError> {
+    if bytes.len() != BLS12381_SIGNATURE_LENGTH {
+        return Err(CryptoMaterialError::WrongLengthError);
+    }
+    let signature = blst::min_pk::Signature::uncompress(bytes)
+        .map_err(|_| CryptoMaterialError::DeserializationError)?;
+    if !signature.subgroup_check() {
+        return Err(CryptoMaterialError::SmallSubgroupError);
+    }
+    Ok(signature)
+}
+
+///////////////////////
+// PrivateKey Traits //
+///////////////////////
+
+impl PrivateKey for Bls12381PrivateKey {
+    type PublicKeyMaterial = Bls12381PublicKey;
+}
+
+impl SigningKey for Bls12381PrivateKey {
+    type VerifyingKeyMaterial = Bls12381PublicKey;
+    type SignatureMaterial = Bls12381Signature;
+
+    fn sign<T: CryptoHash + Serialize>(&self, message: &T) -> Bls12381Signature {
+        let mut bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
+        lcs::serialize_into(&mut bytes, &message)
+            .map_err(|_| CryptoMaterialError::SerializationError)
+            .expect("Serialization of signable material should not fail.");
+        Bls12381PrivateKey::sign_arbitrary_message(&self, bytes.as_ref())
+    }
+
+    #[cfg(any(test, feature = "fuzzing"))]
+    fn sign_arbitrary_message(&self, message: &[u8]) -> Bls12381Signature {
+        Bls12381PrivateKey::sign_arbitrary_message(self, message)
+    }
+}
+
+impl Uniform for Bls12381PrivateKey {
+    fn generate<R>(rng: &mut R) -> Self
+    where
+        R: ::rand::RngCore + ::rand::CryptoRng,
+    {
+        let mut ikm = [0u8; 32];
+        rng.fill_bytes(&mut ikm);
+        Bls12381PrivateKey(
+            blst::min_pk::SecretKey::key_gen(&ikm, &[])
+                .expect("Key generation from 32 bytes of key material should not fail."),
+        )
+    }
+}
+
+impl PartialEq<Self> for Bls12381PrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for Bls12381PrivateKey {}
+
+impl TryFrom<&[u8]> for Bls12381PrivateKey {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a Bls12381PrivateKey. This method will also check that the key is a valid
+    /// non-zero scalar.
+    fn try_from(bytes: &[u8]) -> std::result::Result<Bls12381PrivateKey, CryptoMaterialError> {
+        if bytes.len() != BLS12381_PRIVATE_KEY_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+        blst::min_pk::SecretKey::from_bytes(bytes)
+            .map(Bls12381PrivateKey)
+            .map_err(|_| CryptoMaterialError::DeserializationError)
+    }
+}
+
+impl Length for Bls12381PrivateKey {
+    fn length(&self) -> usize {
+        BLS12381_PRIVATE_KEY_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for Bls12381PrivateKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+//////////////////////
+// PublicKey Traits //
+//////////////////////
+
+impl From<&Bls12381PrivateKey> for Bls12381PublicKey {
+    fn from(private_key: &Bls12381PrivateKey) -> Self {
+        Bls12381PublicKey(private_key.0.sk_to_pk())
+    }
+}
+
+impl PublicKey for Bls12381PublicKey {
+    type PrivateKeyMaterial = Bls12381PrivateKey;
+}
+
+impl std::hash::Hash for Bls12381PublicKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write(&self.to_bytes());
+    }
+}
+
+impl PartialEq for Bls12381PublicKey {
+    fn eq(&self, other: &Bls12381PublicKey) -> bool {
+        self.to_bytes()[..] == other.to_bytes()[..]
+    }
+}
+
+impl Eq for Bls12381PublicKey {}
+
+impl VerifyingKey for Bls12381PublicKey {
+    type SigningKeyMaterial = Bls12381PrivateKey;
+    type SignatureMaterial = Bls12381Signature;
+}
+
+impl fmt::Display for Bls12381PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.to_bytes()[..]))
+    }
+}
+
+impl fmt::Debug for Bls12381PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bls12381PublicKey({})", self)
+    }
+}
+
+impl TryFrom<&[u8]> for Bls12381PublicKey {
+    type Error = CryptoMaterialError;
+
+    /// Deserialize a Bls12381PublicKey. This method will also check for key validity: the point
+    /// must not be the identity and must lie in the prime order subgroup.
+    fn try_from(bytes: &[u8]) -> std::result::Result<Bls12381PublicKey, CryptoMaterialError> {
+        if bytes.len() != BLS12381_PUBLIC_KEY_LENGTH {
+            return Err(CryptoMaterialError::WrongLengthError);
+        }
+        blst::min_pk::PublicKey::key_validate(bytes)
+            .map(Bls12381PublicKey)
+            .map_err(|e| match e {
+                BLST_ERROR::BLST_POINT_NOT_IN_GROUP => CryptoMaterialError::SmallSubgroupError,
+                _ => CryptoMaterialError::DeserializationError,
+            })
+    }
+}
+
+impl Length for Bls12381PublicKey {
+    fn length(&self) -> usize {
+        BLS12381_PUBLIC_KEY_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for Bls12381PublicKey {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+//////////////////////
+// Signature Traits //
+//////////////////////
+
+impl Signature for Bls12381Signature {
+    type VerifyingKeyMaterial = Bls12381PublicKey;
+    type SigningKeyMaterial = Bls12381PrivateKey;
+
+    fn verify<T: CryptoHash + Serialize>(
+        &self,
+        message: &T,
+        public_key: &Bls12381PublicKey,
+    ) -> Result<()> {
+        let mut bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
+        lcs::serialize_into(&mut bytes, &message)
+            .map_err(|_| CryptoMaterialError::SerializationError)?;
+        Self::verify_arbitrary_msg(self, &bytes, public_key)
+    }
+
+    /// Checks that `self` is valid for an arbitrary &[u8] `message` using `public_key`, which can
+    /// be an aggregate public key if `self` is an aggregate signature.
+    fn verify_arbitrary_msg(&self, message: &[u8], public_key: &Bls12381PublicKey) -> Result<()> {
+        // Both points were checked on deserialization (or are aggregates of checked points).
+        check_blst_result(
+            self.0
+                .verify(false, message, DST_SIGNATURE, &[], &public_key.0, false),
+        )
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+
+    /// Verifies a random linear combination of the signatures against the same combination of
+    /// the public keys, which takes a single multi-pairing. Unlike verifying the plain aggregate,
+    /// this rejects invalid signatures that cancel each other out: every signature is weighted by
+    /// a fresh random 64-bit scalar.
+    fn batch_verify<T: CryptoHash + Serialize>(
+        message: &T,
+        keys_and_signatures: Vec<(Self::VerifyingKeyMaterial, Self)>,
+    ) -> Result<()> {
+        ensure_not_empty(keys_and_signatures.len())?;
+        let mut bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
+        lcs::serialize_into(&mut bytes, &message)
+            .map_err(|_| CryptoMaterialError::SerializationError)?;
+
+        let messages = vec![&bytes[..]; keys_and_signatures.len()];
+        let public_keys: Vec<_> = keys_and_signatures.iter().map(|(k, _)| &k.0).collect();
+        let signatures: Vec<_> = keys_and_signatures.iter().map(|(_, s)| &s.0).collect();
+        let mut rng = rand::thread_rng();
+        let scalars: Vec<_> = keys_and_signatures
+            .iter()
+            .map(|_| random_scalar(&mut rng))
+            .collect();
+        // Both the keys and the signatures were checked on deserialization.
+        check_blst_result(
+            blst::min_pk::Signature::verify_multiple_aggregate_signatures(
+                &messages,
+                DST_SIGNATURE,
+                &public_keys,
+                false,
+                &signatures,
+                false,
+                &scalars,
+                RANDOM_SCALAR_BITS,
+            ),
+        )
+    }
+}
+
+impl Length for Bls12381Signature {
+    fn length(&self) -> usize {
+        BLS12381_SIGNATURE_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for Bls12381Signature {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl std::hash::Hash for Bls12381Signature {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write(&self.to_bytes());
+    }
+}
+
+impl TryFrom<&[u8]> for Bls12381Signature {
+    type Error = CryptoMaterialError;
+
+    fn try_from(bytes: &[u8]) -> std::result::Result<Bls12381Signature, CryptoMaterialError> {
+        signature_from_bytes(bytes).map(Bls12381Signature)
+    }
+}
+
+impl PartialEq for Bls12381Signature {
+    fn eq(&self, other: &Bls12381Signature) -> bool {
+        self.to_bytes()[..] == other.to_bytes()[..]
+    }
+}
+
+impl Eq for Bls12381Signature {}
+
+impl fmt::Display for Bls12381Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.to_bytes()[..]))
+    }
+}
+
+impl fmt::Debug for Bls12381Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bls12381Signature({})", self)
+    }
+}
+
+///////////////////////////////
+// ProofOfPossession Traits //
+///////////////////////////////
+
+impl Length for ProofOfPossession {
+    fn length(&self) -> usize {
+        BLS12381_SIGNATURE_LENGTH
+    }
+}
+
+impl ValidCryptoMaterial for ProofOfPossession {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes().to_vec()
+    }
+}
+
+impl TryFrom<&[u8]> for ProofOfPossession {
+    type Error = CryptoMaterialError;
+
+    fn try_from(bytes: &[u8]) -> std::result::Result<ProofOfPossession, CryptoMaterialError> {
+        signature_from_bytes(bytes).map(ProofOfPossession)
+    }
+}
+
+impl PartialEq for ProofOfPossession {
+    fn eq(&self, other: &ProofOfPossession) -> bool {
+        self.to_bytes()[..] == other.to_bytes()[..]
+    }
+}
+
+impl Eq for ProofOfPossession {}
+
+impl fmt::Debug for ProofOfPossession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ProofOfPossession({})",
+            hex::encode(&self.to_bytes()[..])
+        )
+    }
+}
+
+#[cfg(any(test, feature = "fuzzing"))]
+use proptest::prelude::*;
+
+#[cfg(any(test, feature = "fuzzing"))]
+impl proptest::arbitrary::Arbitrary for Bls12381PublicKey {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        crate::test_utils::uniform_keypair_strategy::<Bls12381PrivateKey, Bls12381PublicKey>()
+            .prop_map(|v| v.public_key)
+            .boxed()
+    }
+}
@@ -40,8 +40,19 @@ use crate::{
 };
 use anyhow::{anyhow, Result};
 use core::convert::TryFrom;
+#[cfg(feature = "batch")]
+use curve25519_dalek::{
+    constants::ED25519_BASEPOINT_POINT,
+    edwards::{CompressedEdwardsY, EdwardsPoint},
+    scalar::Scalar,
+    traits::{IsIdentity, VartimeMultiscalarMul},
+};
 use libra_crypto_derive::{DeserializeKey, SerializeKey, SilentDebug, SilentDisplay};
+#[cfg(feature = "batch")]
+use rand::RngCore;
 use serde::Serialize;
+#[cfg(feature = "batch")]
+use sha2::{Digest, Sha512};
 use std::{cmp::Ordering, fmt};
 
 /// The length of the Ed25519PrivateKey
@@ -428,32 +439,78 @@ impl Signature for Ed25519Signature {
     }
 
     /// Batch signature verification as described in the original EdDSA article
-    /// by Bernstein et al. "High-speed high-security signatures". Current implementation works for
-    /// signatures on the same message and it checks for malleability.
-    #[cfg(all(feature = "batch", not(feature = "vanilla")))] // see https://github.com/dalek-cryptography/ed25519-dalek/issues/126
+    /// by Bernstein et al. "High-speed high-security signatures", for signatures on the same
+    /// message.
+    ///
+    /// The batch accepts exactly the signatures `verify` accepts: non-canonical signatures and
+    /// small order `R` or public keys are rejected upfront, as `verify_strict` does. The remaining
+    /// signatures are checked at once with the randomized, cofactorless equation
+    /// `[-sum(z_i * s_i)]B + sum([z_i]R_i) + sum([z_i * k_i]A_i) = 0`, which only agrees with the
+    /// individual cofactorless checks (but for a negligible probability) when every `R_i` and
+    /// `A_i` lies in the prime order subgroup. Signatures with a torsion component are hence
+    /// verified on their own.
+    #[cfg(feature = "batch")]
     fn batch_verify<T: CryptoHash + Serialize>(
         message: &T,
         keys_and_signatures: Vec<(Self::VerifyingKeyMaterial, Self)>,
     ) -> Result<()> {
-        for (_, sig) in keys_and_signatures.iter() {
-            Ed25519Signature::check_malleability(&sig.to_bytes())?
-        }
         let mut message_bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
         lcs::serialize_into(&mut message_bytes, &message)
             .map_err(|_| CryptoMaterialError::SerializationError)?;
 
-        let batch_argument = keys_and_signatures
-            .iter()
-            .map(|(key, signature)| (key.0, signature.0));
-        let (dalek_public_keys, dalek_signatures): (Vec<_>, Vec<_>) = batch_argument.unzip();
-        let message_ref = &(&message_bytes)[..];
-        // The original batching algorithm works for different messages and it expects as many
-        // messages as the number of signatures. In our case, we just populate the same
-        // message to meet dalek's api requirements.
-        let messages = vec![message_ref; dalek_signatures.len()];
-        ed25519_dalek::verify_batch(&messages[..], &dalek_signatures[..], &dalek_public_keys[..])
-            .map_err(|e| anyhow!("{}", e))?;
-        Ok(())
+        let mut rng = rand::thread_rng();
+        let mut basepoint_scalar = Scalar::zero();
+        let mut scalars = Vec::with_capacity(2 * keys_and_signatures.len() + 1);
+        let mut points = Vec::with_capacity(2 * keys_and_signatures.len() + 1);
+        for (public_key, signature) in keys_and_signatures.iter() {
+            let signature_bytes = signature.to_bytes();
+            Ed25519Signature::check_malleability(&signature_bytes)?;
+            let r = CompressedEdwardsY::from_slice(&signature_bytes[..32])
+                .decompress()
+                .ok_or_else(|| anyhow!("Signature R is not a valid point"))?;
+            let a = CompressedEdwardsY::from_slice(public_key.0.as_bytes())
+                .decompress()
+                .ok_or_else(|| anyhow!("Public key is not a valid point"))?;
+            if r.is_small_order() || a.is_small_order() {
+                return Err(anyhow!("Signature R or public key has small order"));
+            }
+            if !r.is_torsion_free() || !a.is_torsion_free() {
+                signature.verify_arbitrary_msg(&message_bytes, public_key)?;
+                continue;
+            }
+
+            let mut s_bytes = [0u8; 32];
+            s_bytes.copy_from_slice(&signature_bytes[32..]);
+            let s = Scalar::from_canonical_bytes(s_bytes)
+                .ok_or_else(|| anyhow!("Signature s is not canonical"))?;
+            let mut k_bytes = [0u8; 64];
+            k_bytes.copy_from_slice(
+                &Sha512::new()
+                    .chain(&signature_bytes[..32])
+                    .chain(public_key.0.as_bytes())
+                    .chain(&message_bytes)
+                    .finalize(),
+            );
+            let k = Scalar::from_bytes_mod_order_wide(&k_bytes);
+            // 128 random bits are enough to make a forgery pass with negligible probability
+            let mut z_bytes = [0u8; 32];
+            rng.fill_bytes(&mut z_bytes[..16]);
+            let z = Scalar::from_bytes_mod_order(z_bytes);
+
+            basepoint_scalar -= z * s;
+            scalars.push(z);
+            points.push(r);
+            scalars.push(z * k);
+            points.push(a);
+        }
+        scalars.push(basepoint_scalar);
+        points.push(ED25519_BASEPOINT_POINT);
+
+        if EdwardsPoint::vartime_multiscalar_mul(scalars, points).is_identity() {
+            Ok(())
+        } else {
+            Err(anyhow!("Batch signature verification failed"))
+        }
     }
 }
 
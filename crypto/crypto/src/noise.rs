@@ -390,7 +390,7 @@ impl NoiseConfig {
 
         // split
         let (k1, k2) = hkdf(&ck, None)?;
-        let session = NoiseSession::new(k1, k2, rs);
+        let session = NoiseSession::new(k1, k2, self.public_key, rs);
 
         //
         Ok((received_payload, session))
@@ -554,7 +554,7 @@ impl NoiseConfig {
 
         // split
         let (k1, k2) = hkdf(&ck, None)?;
-        let session = NoiseSession::new(k2, k1, rs);
+        let session = NoiseSession::new(k2, k1, self.public_key, rs);
 
         //
         Ok(session)
@@ -592,6 +592,8 @@ impl NoiseConfig {
 pub struct NoiseSession {
     /// a session can be marked as invalid if it has seen a decryption failure
     valid: bool,
+    /// our own public key, used during the handshake
+    local_public_key: x25519::PublicKey,
     /// the public key of the other peer
     remote_public_key: x25519::PublicKey,
     /// key used to encrypt messages to the other peer
@@ -602,17 +604,26 @@ pub struct NoiseSession {
     read_key: Vec<u8>,
     /// associated nonce (in practice the maximum u64 value cannot be reached)
     read_nonce: u64,
+    /// if set, each key is rekeyed every time its nonce reaches a multiple of this interval
+    rekey_interval: Option<u64>,
 }
 
 impl NoiseSession {
-    fn new(write_key: Vec<u8>, read_key: Vec<u8>, remote_public_key: x25519::PublicKey) -> Self {
+    fn new(
+        write_key: Vec<u8>,
+        read_key: Vec<u8>,
+        local_public_key: x25519::PublicKey,
+        remote_public_key: x25519::PublicKey,
+    ) -> Self {
         Self {
             valid: true,
+            local_public_key,
             remote_public_key,
             write_key,
             write_nonce: 0,
             read_key,
             read_nonce: 0,
+            rekey_interval: None,
         }
     }
 
@@ -621,6 +632,36 @@ impl NoiseSession {
         self.remote_public_key
     }
 
+    /// obtain the local static public key that was used during the handshake
+    pub fn get_local_static(&self) -> x25519::PublicKey {
+        self.local_public_key
+    }
+
+    /// rekey both directions every `interval` messages (see section 11.3 of the Noise specification).
+    /// Rekeys happen at fixed nonces, so both peers must enable it with the same interval,
+    /// before either of them reaches the first rekey.
+    pub fn set_rekey_interval(&mut self, interval: u64) {
+        assert!(interval > 0, "rekey interval must be positive");
+        self.rekey_interval = Some(interval);
+    }
+
+    /// replaces the key used to encrypt messages, without resetting its nonce.
+    /// The other peer must call `rekey_read` at the same point of the stream.
+    pub fn rekey_write(&mut self) -> Result<(), NoiseError> {
+        self.write_key = rekey(&self.write_key)?;
+        Ok(())
+    }
+
+    /// replaces the key used to decrypt messages, without resetting its nonce.
+    pub fn rekey_read(&mut self) -> Result<(), NoiseError> {
+        self.read_key = rekey(&self.read_key)?;
+        Ok(())
+    }
+
+    fn rekey_due(&self, nonce: u64) -> bool {
+        matches!(self.rekey_interval, Some(interval) if nonce % interval == 0)
+    }
+
     /// encrypts a message for the other peers (post-handshake)
     /// the function encrypts in place, and returns the authentication tag as result
     pub fn write_message_in_place<'a>(
@@ -647,6 +688,9 @@ impl NoiseSession {
 
         // increment nonce
         self.write_nonce += 1;
+        if self.rekey_due(self.write_nonce) {
+            self.rekey_write()?;
+        }
 
         // return a subslice without the authentication tag
         Ok(authentication_tag.to_vec())
@@ -688,12 +732,28 @@ impl NoiseSession {
 
         // increment nonce
         self.read_nonce += 1;
+        if self.rekey_due(self.read_nonce) {
+            self.rekey_read()?;
+        }
 
         // return a subslice of the buffer representing the decrypted plaintext
         Ok(buffer)
     }
 }
 
+/// REKEY(k) from the Noise specification: the first 32 bytes of ENCRYPT(k, maxnonce, zerolen, zeros)
+fn rekey(key: &[u8]) -> Result<Vec<u8>, NoiseError> {
+    let aead = Aes256Gcm::new(GenericArray::from_slice(key));
+    let mut nonce = [0u8; 4].to_vec();
+    nonce.extend_from_slice(&u64::max_value().to_be_bytes());
+    let nonce = GenericArray::from_slice(&nonce);
+
+    let mut new_key = vec![0u8; key.len()];
+    aead.encrypt_in_place_detached(nonce, b"", &mut new_key)
+        .map_err(|_| NoiseError::Encrypt)?;
+    Ok(new_key)
+}
+
 impl std::fmt::Debug for NoiseSession {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "NoiseSession[...]")
@@ -255,6 +255,22 @@ pub trait Signature:
         }
         Ok(())
     }
+
+    /// Batch-verifies signatures of the same message and, if the batch is rejected, verifies
+    /// them one by one to pinpoint the culprit. On rejection, returns the index of the first
+    /// invalid signature, or `None` if every signature is valid on its own: a batch that
+    /// disagrees with the individual checks is rejected all the same.
+    fn batch_verify_find_invalid<T: CryptoHash + Serialize>(
+        message: &T,
+        keys_and_signatures: &[(Self::VerifyingKeyMaterial, Self)],
+    ) -> std::result::Result<(), Option<usize>> {
+        if Self::batch_verify(message, keys_and_signatures.to_vec()).is_ok() {
+            return Ok(());
+        }
+        Err(keys_and_signatures
+            .iter()
+            .position(|(key, signature)| signature.verify(message, key).is_err()))
+    }
 }
 
 /// A type family for schemes which know how to generate key material from
@@ -289,7 +305,7 @@ pub trait Genesis: PrivateKey {
 pub(crate) mod private {
     pub trait Sealed {}
 
-    // Implement for the ed25519, multi-ed25519 signatures
+    // Implement for the ed25519, multi-ed25519 and bls12381 signatures
     impl Sealed for crate::ed25519::Ed25519PrivateKey {}
     impl Sealed for crate::ed25519::Ed25519PublicKey {}
     impl Sealed for crate::ed25519::Ed25519Signature {}
@@ -297,4 +313,11 @@ pub(crate) mod private {
     impl Sealed for crate::multi_ed25519::MultiEd25519PrivateKey {}
     impl Sealed for crate::multi_ed25519::MultiEd25519PublicKey {}
     impl Sealed for crate::multi_ed25519::MultiEd25519Signature {}
+
+    #[cfg(feature = "bls")]
+    impl Sealed for crate::bls12381::Bls12381PrivateKey {}
+    #[cfg(feature = "bls")]
+    impl Sealed for crate::bls12381::Bls12381PublicKey {}
+    #[cfg(feature = "bls")]
+    impl Sealed for crate::bls12381::Bls12381Signature {}
 }
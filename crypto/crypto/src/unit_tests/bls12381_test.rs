@@ -0,0 +1,159 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    bls12381::{
+        Bls12381PrivateKey, Bls12381PublicKey, Bls12381Signature, ProofOfPossession,
+        BLS12381_PUBLIC_KEY_LENGTH, BLS12381_SIGNATURE_LENGTH,
+    },
+    test_utils::{random_serializable_struct, uniform_keypair_strategy, TestLibraCrypto},
+    traits::*,
+};
+use core::convert::TryFrom;
+use proptest::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+fn generate_keys(n: usize) -> Vec<(Bls12381PrivateKey, Bls12381PublicKey)> {
+    let mut rng = StdRng::from_seed([7u8; 32]);
+    (0..n)
+        .map(|_| {
+            let private_key = Bls12381PrivateKey::generate(&mut rng);
+            let public_key = (&private_key).into();
+            (private_key, public_key)
+        })
+        .collect()
+}
+
+#[test]
+fn test_aggregate_signature() {
+    let message = TestLibraCrypto("Hello, World".to_string());
+    let keys = generate_keys(5);
+    let signatures: Vec<_> = keys.iter().map(|(key, _)| key.sign(&message)).collect();
+
+    let signature = Bls12381Signature::aggregate(signatures.iter()).unwrap();
+    let public_key = Bls12381PublicKey::aggregate(keys.iter().map(|(_, key)| key)).unwrap();
+    assert!(signature.verify(&message, &public_key).is_ok());
+
+    // A subset of the signatures doesn't verify against all the keys.
+    let partial = Bls12381Signature::aggregate(signatures.iter().take(4)).unwrap();
+    assert!(partial.verify(&message, &public_key).is_err());
+    let partial_key = Bls12381PublicKey::aggregate(keys.iter().take(4).map(|(_, k)| k)).unwrap();
+    assert!(partial.verify(&message, &partial_key).is_ok());
+
+    // Aggregation of nothing is rejected.
+    assert!(Bls12381Signature::aggregate(vec![]).is_err());
+    assert!(Bls12381PublicKey::aggregate(vec![]).is_err());
+}
+
+#[test]
+fn test_derive() {
+    let key = Bls12381PrivateKey::derive(&[1u8; 32], b"info").unwrap();
+    assert_eq!(
+        key,
+        Bls12381PrivateKey::derive(&[1u8; 32], b"info").unwrap()
+    );
+    assert_ne!(
+        key,
+        Bls12381PrivateKey::derive(&[2u8; 32], b"info").unwrap()
+    );
+    assert_ne!(
+        key,
+        Bls12381PrivateKey::derive(&[1u8; 32], b"other").unwrap()
+    );
+    // Less than 32 bytes of key material are rejected.
+    assert!(Bls12381PrivateKey::derive(&[1u8; 31], b"info").is_err());
+}
+
+#[test]
+fn test_batch_verify() {
+    let message = TestLibraCrypto("Hello, World".to_string());
+    let other_message = TestLibraCrypto("Goodbye, World".to_string());
+    let keys = generate_keys(4);
+    let mut keys_and_signatures: Vec<_> = keys
+        .iter()
+        .map(|(private_key, public_key)| (public_key.clone(), private_key.sign(&message)))
+        .collect();
+    assert!(Bls12381Signature::batch_verify(&message, keys_and_signatures.clone()).is_ok());
+    assert_eq!(
+        Bls12381Signature::batch_verify_find_invalid(&message, &keys_and_signatures),
+        Ok(())
+    );
+
+    keys_and_signatures[2].1 = keys[2].0.sign(&other_message);
+    assert!(Bls12381Signature::batch_verify(&message, keys_and_signatures.clone()).is_err());
+    assert_eq!(
+        Bls12381Signature::batch_verify_find_invalid(&message, &keys_and_signatures),
+        Err(Some(2))
+    );
+
+    // Swapped signatures are both invalid, although their aggregate verifies
+    keys_and_signatures[2].1 = keys[2].0.sign(&message);
+    let first_signature = keys_and_signatures[0].1.clone();
+    keys_and_signatures[0].1 = keys_and_signatures[1].1.clone();
+    keys_and_signatures[1].1 = first_signature;
+    assert!(Bls12381Signature::batch_verify(&message, keys_and_signatures.clone()).is_err());
+    assert_eq!(
+        Bls12381Signature::batch_verify_find_invalid(&message, &keys_and_signatures),
+        Err(Some(0))
+    );
+}
+
+#[test]
+fn test_proof_of_possession() {
+    let keys = generate_keys(2);
+    let pop = ProofOfPossession::create(&keys[0].0);
+    assert!(pop.verify(&keys[0].1).is_ok());
+    assert!(pop.verify(&keys[1].1).is_err());
+
+    // A proof of possession is not a signature of the public key and vice versa.
+    let signature = keys[0].0.sign_arbitrary_message(&keys[0].1.to_bytes());
+    assert!(signature
+        .verify_arbitrary_msg(&keys[0].1.to_bytes(), &keys[0].1)
+        .is_ok());
+    let forged_pop = ProofOfPossession::try_from(&signature.to_bytes()[..]).unwrap();
+    assert!(forged_pop.verify(&keys[0].1).is_err());
+    let forged_signature = Bls12381Signature::try_from(&pop.to_bytes()[..]).unwrap();
+    assert!(forged_signature
+        .verify_arbitrary_msg(&keys[0].1.to_bytes(), &keys[0].1)
+        .is_err());
+}
+
+#[test]
+fn test_deserialization_checks() {
+    // The compressed point at infinity is not a valid public key.
+    let mut infinity = [0u8; BLS12381_PUBLIC_KEY_LENGTH];
+    infinity[0] = 0xc0;
+    assert!(Bls12381PublicKey::try_from(&infinity[..]).is_err());
+
+    assert_eq!(
+        Bls12381PublicKey::try_from(&[0u8; BLS12381_PUBLIC_KEY_LENGTH - 1][..]),
+        Err(CryptoMaterialError::WrongLengthError)
+    );
+    assert_eq!(
+        Bls12381Signature::try_from(&[0u8; BLS12381_SIGNATURE_LENGTH + 1][..]),
+        Err(CryptoMaterialError::WrongLengthError)
+    );
+}
+
+proptest! {
+    #[test]
+    fn test_sign_verify_and_serialization(
+        message in random_serializable_struct(),
+        keypair in uniform_keypair_strategy::<Bls12381PrivateKey, Bls12381PublicKey>()
+    ) {
+        let signature = keypair.private_key.sign(&message);
+        prop_assert!(signature.verify(&message, &keypair.public_key).is_ok());
+
+        let serialized = lcs::to_bytes(&signature).unwrap();
+        let deserialized: Bls12381Signature = lcs::from_bytes(&serialized).unwrap();
+        prop_assert_eq!(&signature, &deserialized);
+
+        let serialized = lcs::to_bytes(&keypair.public_key).unwrap();
+        let deserialized: Bls12381PublicKey = lcs::from_bytes(&serialized).unwrap();
+        prop_assert_eq!(&keypair.public_key, &deserialized);
+
+        let serialized: &[u8] = &keypair.private_key.to_bytes();
+        let deserialized = Bls12381PrivateKey::try_from(serialized).ok();
+        prop_assert_eq!(Some(&keypair.private_key), deserialized.as_ref());
+    }
+}
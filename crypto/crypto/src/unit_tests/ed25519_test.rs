@@ -11,6 +11,7 @@ use crate::{
         Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature, ED25519_PRIVATE_KEY_LENGTH,
         ED25519_PUBLIC_KEY_LENGTH, ED25519_SIGNATURE_LENGTH,
     },
+    hash::{CryptoHash, CryptoHasher},
     test_utils::{random_serializable_struct, uniform_keypair_strategy},
     traits::*,
     x25519,
@@ -23,7 +24,9 @@ use core::{
 
 use libra_crypto_derive::{CryptoHasher, LCSCryptoHash};
 use proptest::{collection::vec, prelude::*};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
 
 #[derive(CryptoHasher, LCSCryptoHash, Serialize, Deserialize)]
 struct CryptoHashable(pub usize);
@@ -150,6 +153,26 @@ proptest! {
         prop_assert!(Ed25519Signature::batch_verify(&message, signatures).is_err());
     }
 
+    #[test]
+    fn test_batch_verify_find_invalid(
+        message in random_serializable_struct(),
+        keypairs in proptest::array::uniform10(uniform_keypair_strategy::<Ed25519PrivateKey, Ed25519PublicKey>()),
+        invalid_index in 0usize..10usize
+    ) {
+        let mut signatures: Vec<(Ed25519PublicKey, Ed25519Signature)> = keypairs.iter().map(|keypair| {
+            (keypair.public_key.clone(), keypair.private_key.sign(&message))
+        }).collect();
+        prop_assert_eq!(Ed25519Signature::batch_verify_find_invalid(&message, &signatures), Ok(()));
+        // We replace one signature with the signature of its neighbour, the fallback
+        // verification must point at it
+        let other_sig = signatures[(invalid_index + 1) % signatures.len()].1.clone();
+        signatures[invalid_index].1 = other_sig;
+        prop_assert_eq!(
+            Ed25519Signature::batch_verify_find_invalid(&message, &signatures),
+            Err(Some(invalid_index))
+        );
+    }
+
     #[test]
     fn test_keys_custom_serialisation(
         keypair in uniform_keypair_strategy::<Ed25519PrivateKey, Ed25519PublicKey>()
@@ -281,6 +304,114 @@ fn test_publickey_smallorder() {
     }
 }
 
+/// Signs `message` with a key and a nonce that are both offset by multiples of the order 8 point
+/// `EIGHT_TORSION[1]`: the public key by `key_torsion` of them. The nonce offset is picked such
+/// that `R - ([s]B - [k]A)` equals `offset` times the point, i.e. the signature is valid for
+/// `verify_strict` only if `offset` is 0.
+fn sign_with_torsion<T: CryptoHash + Serialize>(
+    rng: &mut StdRng,
+    message: &T,
+    key_torsion: u8,
+    offset: u8,
+) -> (Ed25519PublicKey, Ed25519Signature) {
+    use curve25519_dalek::{
+        constants::ED25519_BASEPOINT_POINT, edwards::CompressedEdwardsY, scalar::Scalar,
+    };
+
+    let mut message_bytes = <T::Hasher as CryptoHasher>::seed().to_vec();
+    lcs::serialize_into(&mut message_bytes, message).unwrap();
+    let torsion = CompressedEdwardsY(EIGHT_TORSION[1]).decompress().unwrap();
+    let a = Scalar::random(rng);
+    let public_key_point = a * ED25519_BASEPOINT_POINT + Scalar::from(key_torsion) * torsion;
+    let public_key_bytes = public_key_point.compress().to_bytes();
+    loop {
+        let r = Scalar::random(rng);
+        let nonce_torsion: u8 = rng.gen_range(0, 8);
+        let r_point = r * ED25519_BASEPOINT_POINT + Scalar::from(nonce_torsion) * torsion;
+        let mut k_bytes = [0u8; 64];
+        k_bytes.copy_from_slice(
+            &Sha512::new()
+                .chain(r_point.compress().as_bytes())
+                .chain(&public_key_bytes)
+                .chain(&message_bytes)
+                .finalize(),
+        );
+        let k = Scalar::from_bytes_mod_order_wide(&k_bytes);
+        // [k]A carries k times the key torsion, which only depends on k modulo 8
+        if (nonce_torsion + (k.to_bytes()[0] & 7) * key_torsion) % 8 != offset {
+            continue;
+        }
+        let s = r + k * a;
+        let signature_bytes = [&r_point.compress().to_bytes()[..], &s.to_bytes()[..]].concat();
+        return (
+            Ed25519PublicKey::try_from(&public_key_bytes[..]).unwrap(),
+            Ed25519Signature::try_from(&signature_bytes[..]).unwrap(),
+        );
+    }
+}
+
+// The batch must accept exactly what the individual verification accepts, also for keys and
+// nonces with a torsion component, on which the randomized batch equation alone is unreliable.
+#[test]
+fn test_batch_verify_torsion() {
+    let mut rng = StdRng::from_seed([7u8; 32]);
+    let message = CryptoHashable(42);
+    let honest_signatures = |rng: &mut StdRng| -> Vec<(Ed25519PublicKey, Ed25519Signature)> {
+        (0..3)
+            .map(|_| {
+                let private_key = Ed25519PrivateKey::generate(rng);
+                ((&private_key).into(), private_key.sign(&message))
+            })
+            .collect()
+    };
+
+    let mut signatures = honest_signatures(&mut rng);
+    for _ in 0..10 {
+        let (public_key, signature) = sign_with_torsion(&mut rng, &message, 1, 0);
+        assert!(signature.verify(&message, &public_key).is_ok());
+        signatures.push((public_key, signature));
+        assert!(Ed25519Signature::batch_verify(&message, signatures.clone()).is_ok());
+    }
+
+    for offset in 1..8 {
+        for key_torsion in 0..2 {
+            let (public_key, signature) =
+                sign_with_torsion(&mut rng, &message, key_torsion, offset);
+            assert!(signature.verify(&message, &public_key).is_err());
+            let mut signatures = honest_signatures(&mut rng);
+            signatures.push((public_key, signature));
+            assert!(Ed25519Signature::batch_verify(&message, signatures.clone()).is_err());
+            assert_eq!(
+                Ed25519Signature::batch_verify_find_invalid(&message, &signatures),
+                Err(Some(3))
+            );
+        }
+    }
+}
+
+// A single invalid signature in an otherwise valid batch rejects the whole batch.
+#[test]
+fn test_batch_verify_one_bad_signature() {
+    let mut rng = StdRng::from_seed([8u8; 32]);
+    let message = CryptoHashable(42);
+    let other_message = CryptoHashable(43);
+    let mut signatures: Vec<(Ed25519PublicKey, Ed25519Signature)> = (0..10)
+        .map(|_| {
+            let private_key = Ed25519PrivateKey::generate(&mut rng);
+            ((&private_key).into(), private_key.sign(&message))
+        })
+        .collect();
+    assert!(Ed25519Signature::batch_verify(&message, signatures.clone()).is_ok());
+
+    let private_key = Ed25519PrivateKey::generate(&mut rng);
+    signatures[5] = ((&private_key).into(), private_key.sign(&other_message));
+    assert!(Ed25519Signature::batch_verify(&message, signatures.clone()).is_err());
+    assert_eq!(
+        Ed25519Signature::batch_verify_find_invalid(&message, &signatures),
+        Err(Some(5))
+    );
+}
+
 // The 8-torsion subgroup E[8].
 //
 // In the case of Curve25519, it is cyclic; the i-th element of
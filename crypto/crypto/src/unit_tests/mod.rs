@@ -1,6 +1,8 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(feature = "bls")]
+mod bls12381_test;
 mod compat_test;
 mod cross_test;
 mod cryptohasher;
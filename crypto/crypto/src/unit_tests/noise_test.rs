@@ -108,6 +108,59 @@ fn simple_handshake() {
     }
 }
 
+#[test]
+fn rekey() {
+    // setup peers
+    let mut rng = ::rand::rngs::StdRng::from_seed(TEST_SEED);
+    let initiator_private = x25519::PrivateKey::generate(&mut rng);
+    let initiator_public = initiator_private.public_key();
+    let responder_private = x25519::PrivateKey::generate(&mut rng);
+    let responder_public = responder_private.public_key();
+    let initiator = NoiseConfig::new(initiator_private);
+    let responder = NoiseConfig::new(responder_private);
+
+    // handshake
+    let mut first_message = vec![0u8; handshake_init_msg_len(0)];
+    let initiator_state = initiator
+        .initiate_connection(&mut rng, b"", responder_public, None, &mut first_message)
+        .unwrap();
+    let mut second_message = vec![0u8; handshake_resp_msg_len(0)];
+    let (_, mut responder_session) = responder
+        .respond_to_client_and_finalize(&mut rng, b"", &first_message, None, &mut second_message)
+        .unwrap();
+    let (_, mut initiator_session) = initiator
+        .finalize_connection(initiator_state, &second_message)
+        .unwrap();
+    assert_eq!(initiator_session.get_local_static(), initiator_public);
+    assert_eq!(responder_session.get_local_static(), responder_public);
+
+    // both sides rekey every 3 messages
+    initiator_session.set_rekey_interval(3);
+    responder_session.set_rekey_interval(3);
+    for i in 0..10u8 {
+        let mut message = vec![i; 16];
+        let auth_tag = initiator_session
+            .write_message_in_place(&mut message)
+            .unwrap();
+        message.extend_from_slice(&auth_tag);
+        let received = responder_session
+            .read_message_in_place(&mut message)
+            .unwrap();
+        assert_eq!(received, vec![i; 16].as_slice());
+    }
+
+    // a peer that rekeys alone can't be understood anymore
+    let mut message = b"payload".to_vec();
+    responder_session.rekey_write().unwrap();
+    let auth_tag = responder_session
+        .write_message_in_place(&mut message)
+        .unwrap();
+    message.extend_from_slice(&auth_tag);
+    assert!(initiator_session
+        .read_message_in_place(&mut message)
+        .is_err());
+}
+
 #[test]
 fn test_vectors() {
     // structures needed to deserialize test vectors
@@ -40,6 +40,7 @@ use libra_types::{
         Transaction, TransactionInfo, TransactionListWithProof, TransactionOutput,
         TransactionPayload, TransactionStatus, TransactionToCommit, Version,
     },
+    validator_verifier::ValidatorVerifier,
     write_set::{WriteOp, WriteSet},
 };
 use libra_vm::VMExecutor;
@@ -301,9 +302,20 @@ where
                             .ok_or_else(|| format_err!("Configuration does not exist"))
                     })
                     .ok_or_else(|| format_err!("Association account does not exist"))??;
+                let verifier = ValidatorVerifier::from(&validator_set);
+                #[cfg(feature = "bls")]
+                let verifier = {
+                    let scheme = account_to_state
+                        .get(&on_chain_config::config_address())
+                        .map(|state| state.get_consensus_signature_scheme())
+                        .transpose()?
+                        .flatten()
+                        .unwrap_or_default();
+                    verifier.with_signature_scheme(&scheme)?
+                };
                 Some(EpochState {
                     epoch: configuration.epoch(),
-                    verifier: (&validator_set).into(),
+                    verifier,
                 })
             } else {
                 None
@@ -9,6 +9,7 @@ use crate::{
     struct_defs::RecursiveStructDefChecker,
 };
 use libra_types::vm_status::StatusCode;
+use move_core_types::transaction_argument::MAX_VECTOR_NESTING_DEPTH;
 use vm::{
     access::ScriptAccess,
     errors::{Location, PartialVMError, PartialVMResult, VMResult},
@@ -57,20 +58,7 @@ fn verify_main_signature_impl(script: &CompiledScript) -> PartialVMResult<()> {
         use SignatureToken as S;
         match arg_type {
             S::Bool | S::U8 | S::U64 | S::U128 | S::Address => true,
-            S::Vector(inner) => match &**inner {
-                S::U8 => true,
-                S::Bool
-                | S::U64
-                | S::U128
-                | S::Address
-                | S::Signer
-                | S::Struct(_)
-                | S::Vector(_)
-                | S::StructInstantiation(_, _)
-                | S::Reference(_)
-                | S::MutableReference(_)
-                | S::TypeParameter(_) => false,
-            },
+            S::Vector(inner) => is_valid_vector_element_type(inner, 1),
 
             // &signer is a type that can only be populated by the Move VM. And its value is filled
             // based on the sender of the transaction
@@ -84,6 +72,24 @@ fn verify_main_signature_impl(script: &CompiledScript) -> PartialVMResult<()> {
         }
     }
 
+    // Vectors of primitive types can be arguments, as well as vectors of such vectors, nested at
+    // most `MAX_VECTOR_NESTING_DEPTH` deep. `depth` is the nesting of the enclosing vector.
+    fn is_valid_vector_element_type(elem_type: &SignatureToken, depth: usize) -> bool {
+        use SignatureToken as S;
+        match elem_type {
+            S::Bool | S::U8 | S::U64 | S::U128 | S::Address => true,
+            S::Vector(inner) => {
+                depth < MAX_VECTOR_NESTING_DEPTH && is_valid_vector_element_type(inner, depth + 1)
+            }
+            S::Signer
+            | S::Struct(_)
+            | S::StructInstantiation(_, _)
+            | S::Reference(_)
+            | S::MutableReference(_)
+            | S::TypeParameter(_) => false,
+        }
+    }
+
     let arguments = script.signature_at(script.as_inner().parameters);
     for (idx, arg_type) in arguments.0.iter().enumerate() {
         if !is_valid_arg_type(idx, arg_type) {
@@ -32,7 +32,10 @@ use move_core_types::{
     identifier::Identifier,
     language_storage::{ModuleId, TypeTag},
 };
-use move_vm_runtime::move_vm::MoveVM;
+use move_vm_runtime::{
+    gas_profiler::{self, GasProfile},
+    move_vm::MoveVM,
+};
 use move_vm_types::{
     gas_schedule::{zero_cost_schedule, CostStrategy},
     values::Value,
@@ -231,6 +234,18 @@ impl FakeExecutor {
             .expect("A block with one transaction should have one output")
     }
 
+    /// Executes the transaction like `execute_transaction`, and returns the gas it used by call
+    /// stack along with its output.
+    pub fn execute_transaction_with_gas_profile(
+        &self,
+        txn: SignedTransaction,
+    ) -> (TransactionOutput, GasProfile) {
+        gas_profiler::start_profiling();
+        let output = self.execute_transaction(txn);
+        let profile = gas_profiler::stop_profiling().expect("Profiling was started");
+        (output, profile)
+    }
+
     /// Get the blob for the associated AccessPath
     pub fn read_from_access_path(&self, path: &AccessPath) -> Option<Vec<u8>> {
         StateView::get(&self.data_store, path).unwrap()
@@ -14,6 +14,7 @@ mod create_account;
 mod data_store;
 mod execution_strategies;
 mod failed_transaction_tests;
+mod gas_profile;
 mod genesis;
 mod mint;
 mod module_publishing;
@@ -0,0 +1,81 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{account::AccountData, common_transactions::peer_to_peer_txn, executor::FakeExecutor};
+use libra_types::{transaction::TransactionStatus, vm_status::VMStatus};
+
+#[test]
+fn peer_to_peer_gas_profile() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let sender = AccountData::new(1_000_000, 10);
+    let receiver = AccountData::new(100_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+
+    let txn = peer_to_peer_txn(sender.account(), receiver.account(), 10, 1_000);
+    let (output, profile) = executor.execute_transaction_with_gas_profile(txn);
+    assert_eq!(
+        output.status(),
+        &TransactionStatus::Keep(VMStatus::Executed)
+    );
+
+    // Only the gas charged while executing Move code is attributed, in internal gas units.
+    assert!(profile.total_gas() > 0);
+    assert!(profile.total_gas() <= output.gas_used() * 1000);
+
+    let summary = profile.summary();
+    let script = summary
+        .iter()
+        .find(|function| function.name == "Script::main")
+        .expect("the script must be profiled");
+    assert_eq!(script.calls, 1);
+    assert!(script.total_gas > script.self_gas);
+    let pay_from = summary
+        .iter()
+        .find(|function| function.name == "0x1::LibraAccount::pay_from")
+        .expect("pay_from must be profiled");
+    assert_eq!(pay_from.calls, 1);
+    assert!(pay_from.total_gas > 0);
+    assert!(script.total_gas >= pay_from.total_gas);
+    for function in &summary {
+        assert!(function.self_gas <= function.total_gas);
+    }
+
+    // Every call stack of the script starts with the script itself.
+    for (stack, _) in profile.stacks() {
+        if stack.iter().any(|name| name == "Script::main") {
+            assert_eq!(stack[0], "Script::main");
+        }
+    }
+
+    let folded = profile.to_folded();
+    assert!(folded
+        .lines()
+        .any(|line| line.starts_with("Script::main;0x1::LibraAccount::pay_from")));
+    let folded_total: u64 = folded
+        .lines()
+        .map(|line| line.rsplit(' ').next().unwrap().parse::<u64>().unwrap())
+        .sum();
+    assert_eq!(folded_total, profile.total_gas());
+
+    let speedscope = profile.to_speedscope("peer_to_peer");
+    assert!(speedscope.starts_with("{\"$schema\":\"https://www.speedscope.app/"));
+    assert!(speedscope.contains("{\"name\":\"0x1::LibraAccount::pay_from\"}"));
+}
+
+#[test]
+fn profiling_is_off_by_default() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let sender = AccountData::new(1_000_000, 10);
+    let receiver = AccountData::new(100_000, 10);
+    executor.add_account_data(&sender);
+    executor.add_account_data(&receiver);
+
+    executor.execute_transaction(peer_to_peer_txn(
+        sender.account(),
+        receiver.account(),
+        10,
+        1_000,
+    ));
+    assert!(move_vm_runtime::gas_profiler::stop_profiling().is_none());
+}
@@ -9,14 +9,17 @@ use crate::{
     transaction_status_eq,
 };
 use compiled_stdlib::transaction_scripts::StdlibScript;
+use compiler::Compiler;
 use libra_types::{
     account_config::LBR_NAME,
-    on_chain_config::LibraVersion,
+    on_chain_config::{new_epoch_event_key, LibraVersion, VMPublishingOption},
     transaction::{TransactionArgument, TransactionStatus},
-    vm_status::VMStatus,
+    vm_status::{StatusCode, VMStatus},
+};
+use libra_vm::{LibraVM, ARGUMENT_FORMAT_VERSION_2_MIN_LIBRA_VERSION};
+use transaction_builder::{
+    encode_update_consensus_signature_scheme_script, encode_update_dual_attestation_limit_script,
 };
-use libra_vm::LibraVM;
-use transaction_builder::encode_update_dual_attestation_limit_script;
 
 #[test]
 fn initial_libra_version() {
@@ -48,6 +51,64 @@ fn initial_libra_version() {
     );
 }
 
+#[test]
+fn vector_arguments_require_libra_version_upgrade() {
+    let mut executor = FakeExecutor::from_genesis_with_options(VMPublishingOption::open());
+    let sender = AccountData::new(1_000_000, 10);
+    executor.add_account_data(&sender);
+
+    let code = "
+    main(v: vector<u64>) {
+        return;
+    }
+    ";
+    let script = Compiler {
+        address: *sender.address(),
+        ..Compiler::default()
+    }
+    .into_script_blob("file_name", code)
+    .expect("Failed to compile");
+    let script_txn = |sequence_number| {
+        sender.account().create_signed_txn_with_args(
+            script.clone(),
+            vec![],
+            vec![TransactionArgument::U64Vector(vec![1, 2, 3])],
+            sequence_number,
+            TXN_RESERVED,
+            0,
+            LBR_NAME.to_owned(),
+        )
+    };
+
+    // Arguments of the second format are rejected until the upgrade
+    let output = executor.execute_transaction(script_txn(10));
+    assert!(transaction_status_eq(
+        &output.status(),
+        &TransactionStatus::Discard(VMStatus::Error(StatusCode::INVALID_TRANSACTION_ARGUMENT))
+    ));
+
+    let account = Account::new_genesis_account(libra_types::on_chain_config::config_address());
+    let txn = account.create_signed_txn_with_args(
+        StdlibScript::UpdateLibraVersion.compiled_bytes().into_vec(),
+        vec![],
+        vec![TransactionArgument::U64(
+            ARGUMENT_FORMAT_VERSION_2_MIN_LIBRA_VERSION,
+        )],
+        1,
+        TXN_RESERVED,
+        0,
+        LBR_NAME.to_owned(),
+    );
+    executor.new_block();
+    executor.execute_and_apply(txn);
+
+    let output = executor.execute_transaction(script_txn(10));
+    assert!(transaction_status_eq(
+        &output.status(),
+        &TransactionStatus::Keep(VMStatus::Executed)
+    ));
+}
+
 #[test]
 fn drop_txn_after_reconfiguration() {
     let mut executor = FakeExecutor::from_genesis_file();
@@ -118,3 +179,39 @@ fn updated_limit_allows_txn() {
     assert_eq!(3_999_990, sender_balance.coin());
     assert_eq!(1_000_010, receiver_balance.coin());
 }
+
+#[test]
+fn update_consensus_signature_scheme() {
+    let mut executor = FakeExecutor::from_genesis_file();
+    let libra_root = Account::new_libra_root();
+    // LCS of `ConsensusSignatureScheme::Ed25519`
+    let scheme = vec![0];
+
+    // only libra root can change the scheme
+    let output = executor.execute_transaction(Account::new_blessed_tc().signed_script_txn(
+        encode_update_consensus_signature_scheme_script(scheme.clone()),
+        0,
+    ));
+    assert!(matches!(
+        output.status(),
+        TransactionStatus::Keep(VMStatus::MoveAbort(_, 0))
+    ));
+
+    // the first update publishes the config, the following ones change it, and both start a new
+    // epoch
+    for sequence_number in 1..3 {
+        executor.new_block();
+        let output = executor.execute_and_apply(libra_root.signed_script_txn(
+            encode_update_consensus_signature_scheme_script(scheme.clone()),
+            sequence_number,
+        ));
+        assert_eq!(
+            output.status(),
+            &TransactionStatus::Keep(VMStatus::Executed)
+        );
+        assert!(output
+            .events()
+            .iter()
+            .any(|e| e.key() == &new_epoch_event_key()));
+    }
+}
@@ -7,12 +7,20 @@ use crate::{
     evaluator::{eval, EvaluationOutput},
     preprocessor::{build_transactions, extract_global_config, split_input},
 };
+use move_vm_runtime::gas_profiler;
 use regex::Regex;
-use std::{env, fs::read_to_string, io::Write, iter, path::Path};
+use std::{
+    env,
+    fs::{self, read_to_string},
+    io::Write,
+    iter,
+    path::Path,
+};
 use termcolor::{BufferWriter, Color, ColorChoice, ColorSpec, WriteColor};
 
 pub const PRETTY: &str = "PRETTY";
 pub const FILTER: &str = "FILTER";
+pub const GAS_PROFILE: &str = "GAS_PROFILE";
 
 fn at_most_n_chars(s: impl IntoIterator<Item = char>, n: usize) -> String {
     let mut it = s.into_iter();
@@ -65,6 +73,30 @@ fn print_stage(haystack: &str) -> bool {
         .unwrap_or(true)
 }
 
+/// Runs `f` while profiling the gas it uses if `GAS_PROFILE` names a directory, and writes the
+/// profile there in the folded stack and speedscope formats, in files named after `path`.
+fn with_gas_profile<T>(path: &Path, f: impl FnOnce() -> T) -> datatest_stable::Result<T> {
+    let dir = match env::var(GAS_PROFILE) {
+        Ok(dir) => dir,
+        Err(_) => return Ok(f()),
+    };
+    gas_profiler::start_profiling();
+    let res = f();
+    let profile = gas_profiler::stop_profiling().unwrap_or_default();
+
+    let name = path
+        .to_string_lossy()
+        .replace(|c: char| c == '/' || c == '\\', "__");
+    let dir = Path::new(&dir);
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(format!("{}.folded", name)), profile.to_folded())?;
+    fs::write(
+        dir.join(format!("{}.speedscope.json", name)),
+        profile.to_speedscope(&name),
+    )?;
+    Ok(res)
+}
+
 // Runs all tests under the test/testsuite directory.
 pub fn functional_tests<TComp: Compiler>(
     compiler: TComp,
@@ -77,7 +109,7 @@ pub fn functional_tests<TComp: Compiler>(
     let (directives, transactions) = split_input(&lines, &config)?;
     let commands = build_transactions(&config, &transactions)?;
 
-    let log = eval(&config, compiler, &commands)?;
+    let log = with_gas_profile(path, || eval(&config, compiler, &commands))??;
 
     let res = match_output(&log, &directives);
 
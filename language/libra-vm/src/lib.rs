@@ -118,8 +118,9 @@ pub mod libra_transaction_validator;
 pub mod system_module_names;
 
 pub use crate::{
-    libra_transaction_executor::LibraVM, libra_transaction_validator::LibraVMValidator,
-    libra_vm::txn_effects_to_writeset_and_events,
+    libra_transaction_executor::LibraVM,
+    libra_transaction_validator::LibraVMValidator,
+    libra_vm::{txn_effects_to_writeset_and_events, ARGUMENT_FORMAT_VERSION_2_MIN_LIBRA_VERSION},
 };
 
 use libra_state_view::StateView;
@@ -125,6 +125,7 @@ impl LibraVM {
             let _timer = TXN_VERIFICATION_SECONDS.start_timer();
             self.0.check_gas(txn_data)?;
             self.0.is_allowed_script(script)?;
+            self.0.check_transaction_arguments(script)?;
             self.0.run_prologue(
                 &mut session,
                 cost_strategy,
@@ -608,16 +609,7 @@ pub(crate) fn discard_error_output(err: VMStatus) -> TransactionOutput {
 
 /// Convert the transaction arguments into move values.
 fn convert_txn_args(args: &[TransactionArgument]) -> Vec<Value> {
-    args.iter()
-        .map(|arg| match arg {
-            TransactionArgument::U8(i) => Value::u8(*i),
-            TransactionArgument::U64(i) => Value::u64(*i),
-            TransactionArgument::U128(i) => Value::u128(*i),
-            TransactionArgument::Address(a) => Value::address(*a),
-            TransactionArgument::Bool(b) => Value::bool(*b),
-            TransactionArgument::U8Vector(v) => Value::vector_u8(v.clone()),
-        })
-        .collect()
+    args.iter().map(Value::from_transaction_argument).collect()
 }
 
 impl AsRef<LibraVMImpl> for LibraVM {
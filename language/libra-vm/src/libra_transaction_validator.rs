@@ -52,6 +52,7 @@ impl LibraVMValidator {
             TransactionPayload::Script(script) => {
                 self.0.check_gas(&txn_data)?;
                 self.0.is_allowed_script(script)?;
+                self.0.check_transaction_arguments(script)?;
                 self.0.run_prologue(
                     &mut session,
                     &mut cost_strategy,
@@ -83,7 +84,8 @@ impl VMValidator for LibraVMValidator {
     /// following steps:
     /// 1. The signature on the `SignedTransaction` matches the public key included in the
     ///    transaction
-    /// 2. The script to be executed is under given specific configuration.
+    /// 2. The script to be executed is under given specific configuration, and its arguments are
+    ///    well formed and supported by the on-chain Libra version.
     /// 3. Invokes `LibraAccount.prologue`, which checks properties such as the transaction has the
     /// right sequence number and the sender has enough balance to pay for the gas.
     /// TBD:
@@ -24,6 +24,7 @@ use move_core_types::{
     gas_schedule::{CostTable, GasAlgebra, GasUnits},
     identifier::IdentStr,
     language_storage::TypeTag,
+    transaction_argument::ARGUMENT_FORMAT_VERSION_2,
 };
 
 use move_vm_runtime::{
@@ -38,6 +39,10 @@ use move_vm_types::{
 use std::{convert::TryFrom, sync::Arc};
 use vm::errors::Location;
 
+/// The first major on-chain `LibraVersion` whose validators accept transaction arguments of
+/// `ARGUMENT_FORMAT_VERSION_2`. Arguments of the first format are accepted at every version.
+pub const ARGUMENT_FORMAT_VERSION_2_MIN_LIBRA_VERSION: u64 = 2;
+
 #[derive(Clone)]
 /// A wrapper to make VMRuntime standalone and thread safe.
 pub struct LibraVMImpl {
@@ -188,6 +193,33 @@ impl LibraVMImpl {
         }
     }
 
+    /// Checks that the script arguments are well formed, and that the on-chain Libra version is
+    /// recent enough for their format.
+    pub(crate) fn check_transaction_arguments(&self, script: &Script) -> Result<(), VMStatus> {
+        for arg in script.args() {
+            if let Err(err) = arg.check_well_formed() {
+                warn!("[VM] Malformed transaction argument: {}", err);
+                return Err(VMStatus::Error(StatusCode::INVALID_TRANSACTION_ARGUMENT));
+            }
+        }
+        let format_version = script
+            .args()
+            .iter()
+            .map(|arg| arg.format_version())
+            .max()
+            .unwrap_or(1);
+        if format_version >= ARGUMENT_FORMAT_VERSION_2
+            && self.get_libra_version()?.major < ARGUMENT_FORMAT_VERSION_2_MIN_LIBRA_VERSION
+        {
+            warn!(
+                "[VM] Transaction arguments of format version {} are not enabled yet",
+                format_version
+            );
+            return Err(VMStatus::Error(StatusCode::INVALID_TRANSACTION_ARGUMENT));
+        }
+        Ok(())
+    }
+
     pub(crate) fn is_allowed_module(
         &self,
         txn_data: &TransactionMetadata,
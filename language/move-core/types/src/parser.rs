@@ -25,11 +25,14 @@ enum Token {
     U64(String),
     U128(String),
     Bytes(String),
+    String(String),
     True,
     False,
     ColonColon,
     Lt,
     Gt,
+    LBracket,
+    RBracket,
     Comma,
     EOF,
 }
@@ -98,6 +101,8 @@ fn next_token(s: &str) -> Result<Option<(Token, usize)>> {
         Some(c) => Ok(Some(match c {
             '<' => (Token::Lt, 1),
             '>' => (Token::Gt, 1),
+            '[' => (Token::LBracket, 1),
+            ']' => (Token::RBracket, 1),
             ',' => (Token::Comma, 1),
             ':' => match it.next() {
                 Some(':') => (Token::ColonColon, 2),
@@ -138,6 +143,18 @@ fn next_token(s: &str) -> Result<Option<(Token, usize)>> {
                 let len = r.len() + 3;
                 (Token::Bytes(r), len)
             }
+            '"' => {
+                let mut r = String::new();
+                loop {
+                    match it.next() {
+                        Some('"') => break,
+                        Some(c) if c.is_ascii() && !c.is_ascii_control() => r.push(c),
+                        _ => bail!("unrecognized token"),
+                    }
+                }
+                let len = r.len() + 2;
+                (Token::String(r), len)
+            }
             c if c.is_ascii_whitespace() => {
                 let mut r = String::new();
                 r.push(c);
@@ -294,9 +311,83 @@ impl<I: Iterator<Item = Token>> Parser<I> {
                 TransactionArgument::Address(AccountAddress::from_hex_literal(&addr)?)
             }
             Token::Bytes(s) => TransactionArgument::U8Vector(hex::decode(s)?),
+            Token::String(s) => TransactionArgument::AsciiString(s),
+            Token::LBracket => {
+                let elems = self.parse_vector_elements()?;
+                vector_argument(None, elems)?
+            }
+            Token::VectorType => {
+                self.consume(Token::Lt)?;
+                let ty = self.parse_type_tag()?;
+                self.consume(Token::Gt)?;
+                self.consume(Token::LBracket)?;
+                let elems = self.parse_vector_elements()?;
+                vector_argument(Some(ty), elems)?
+            }
             tok => bail!("unexpected token {:?}, expected transaction argument", tok),
         })
     }
+
+    // Parses the elements of a vector argument, after its opening bracket
+    fn parse_vector_elements(&mut self) -> Result<Vec<TransactionArgument>> {
+        let elems = self.parse_comma_list(
+            |parser| parser.parse_transaction_argument(),
+            Token::RBracket,
+            true,
+        )?;
+        self.consume(Token::RBracket)?;
+        Ok(elems)
+    }
+}
+
+/// Builds a vector argument from its elements. The element type is inferred from the first
+/// element unless given, which it must be for an empty vector.
+fn vector_argument(
+    elem_ty: Option<TypeTag>,
+    elems: Vec<TransactionArgument>,
+) -> Result<TransactionArgument> {
+    use TransactionArgument as T;
+
+    let elem_ty = match (elem_ty, elems.first()) {
+        (Some(ty), _) => ty,
+        (None, Some(T::U8(_))) => TypeTag::U8,
+        (None, Some(T::U64(_))) => TypeTag::U64,
+        (None, Some(T::U128(_))) => TypeTag::U128,
+        (None, Some(T::Bool(_))) => TypeTag::Bool,
+        (None, Some(T::Address(_))) => TypeTag::Address,
+        // The element type of the nested vectors is not needed to build the argument
+        (None, Some(_)) => TypeTag::Vector(Box::new(TypeTag::U8)),
+        (None, None) => bail!("the type of an empty vector must be given, e.g. vector<u64>[]"),
+    };
+    macro_rules! collect_elements {
+        ($elem_variant:ident, $vector_variant:ident) => {
+            T::$vector_variant(
+                elems
+                    .into_iter()
+                    .map(|elem| match elem {
+                        T::$elem_variant(value) => Ok(value),
+                        elem => bail!("unexpected element {:?} in vector<{}>", elem, elem_ty),
+                    })
+                    .collect::<Result<_>>()?,
+            )
+        };
+    }
+    Ok(match &elem_ty {
+        TypeTag::U8 => collect_elements!(U8, U8Vector),
+        TypeTag::U64 => collect_elements!(U64, U64Vector),
+        TypeTag::U128 => collect_elements!(U128, U128Vector),
+        TypeTag::Bool => collect_elements!(Bool, BoolVector),
+        TypeTag::Address => collect_elements!(Address, AddressVector),
+        TypeTag::Vector(_) => {
+            if let Some(elem) = elems.iter().find(|elem| !elem.is_vector()) {
+                bail!("unexpected element {:?} in vector<{}>", elem, elem_ty)
+            }
+            T::Vector(elems)
+        }
+        TypeTag::Signer | TypeTag::Struct(_) => {
+            bail!("vector<{}> is not a valid argument type", elem_ty)
+        }
+    })
 }
 
 fn parse<F, T>(s: &str, f: F) -> Result<T>
@@ -371,6 +462,32 @@ fn tests_parse_transaction_argument_positive() {
         ("b\"\"", T::U8Vector(vec![])),
         ("b\"00\"", T::U8Vector(vec![0x00])),
         ("b\"deadbeef\"", T::U8Vector(vec![0xde, 0xad, 0xbe, 0xef])),
+        ("\"\"", T::AsciiString("".to_string())),
+        (
+            "\"Hello, world\"",
+            T::AsciiString("Hello, world".to_string()),
+        ),
+        ("[1u8, 2u8]", T::U8Vector(vec![1, 2])),
+        ("[1, 2, 3,]", T::U64Vector(vec![1, 2, 3])),
+        ("[0u128]", T::U128Vector(vec![0])),
+        ("[true, false]", T::BoolVector(vec![true, false])),
+        (
+            "[0x1, 0x2]",
+            T::AddressVector(vec![
+                AccountAddress::from_hex_literal("0x1").unwrap(),
+                AccountAddress::from_hex_literal("0x2").unwrap(),
+            ]),
+        ),
+        ("vector<u64>[]", T::U64Vector(vec![])),
+        ("vector<vector<u8>>[]", T::Vector(vec![])),
+        (
+            "[[1, 2], vector<u64>[]]",
+            T::Vector(vec![T::U64Vector(vec![1, 2]), T::U64Vector(vec![])]),
+        ),
+        (
+            "[b\"00\", \"ab\"]",
+            T::Vector(vec![T::U8Vector(vec![0]), T::AsciiString("ab".to_string())]),
+        ),
     ] {
         assert_eq!(&parse_transaction_argument(s).unwrap(), expected)
     }
@@ -404,6 +521,15 @@ fn tests_parse_transaction_argument_negative() {
         "3false",
         "3 false",
         "",
+        "\"unterminated",
+        "\"caf\u{e9}\"",
+        "[]",
+        "[1, true]",
+        "[[1], 2]",
+        "[1 2]",
+        "[1",
+        "vector<u64>[true]",
+        "vector<signer>[]",
     ] {
         assert!(parse_transaction_argument(s).is_err())
     }
@@ -55,6 +55,17 @@ impl Arbitrary for TransactionArgument {
             any::<u64>().prop_map(TransactionArgument::U64),
             any::<AccountAddress>().prop_map(TransactionArgument::Address),
             vec(any::<u8>(), 0..10).prop_map(TransactionArgument::U8Vector),
+            vec(any::<u64>(), 0..10).prop_map(TransactionArgument::U64Vector),
+            vec(any::<AccountAddress>(), 0..4).prop_map(TransactionArgument::AddressVector),
+            vec(vec(any::<u8>(), 0..10), 0..4).prop_map(|vectors| {
+                TransactionArgument::Vector(
+                    vectors
+                        .into_iter()
+                        .map(TransactionArgument::U8Vector)
+                        .collect(),
+                )
+            }),
+            "[ -~]{0,20}".prop_map(TransactionArgument::AsciiString),
         ]
         .boxed()
     }
@@ -2,9 +2,22 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::account_address::AccountAddress;
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+/// The version of the argument format which introduced vectors of u64, u128, bool and address,
+/// nested vectors and ASCII strings. Version 1 only has the first six variants.
+///
+/// New variants are only ever appended to `TransactionArgument`, so that the LCS encoding of
+/// existing arguments never changes. Validators only accept an argument once the on-chain
+/// `LibraVersion` has reached the one enabling its format, which an upgrade sets after every
+/// validator runs software able to decode it.
+pub const ARGUMENT_FORMAT_VERSION_2: u64 = 2;
+
+/// The maximum number of nested vectors in an argument, e.g. 2 for `vector<vector<u8>>`.
+pub const MAX_VECTOR_NESTING_DEPTH: usize = 8;
+
 #[derive(Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub enum TransactionArgument {
     U8(u8),
@@ -13,6 +26,87 @@ pub enum TransactionArgument {
     Address(AccountAddress),
     U8Vector(#[serde(with = "serde_bytes")] Vec<u8>),
     Bool(bool),
+    // Argument format version 2
+    U64Vector(Vec<u64>),
+    U128Vector(Vec<u128>),
+    BoolVector(Vec<bool>),
+    AddressVector(Vec<AccountAddress>),
+    /// A vector of vectors. Each element must itself be a vector argument.
+    Vector(Vec<TransactionArgument>),
+    /// An ASCII string, passed to the script as a `vector<u8>`. Script ABIs have no string type,
+    /// so generated transaction builders use `U8Vector` instead.
+    AsciiString(String),
+}
+
+impl TransactionArgument {
+    /// Returns the version of the argument format which introduced this kind of argument.
+    pub fn format_version(&self) -> u64 {
+        match self {
+            TransactionArgument::U8(_)
+            | TransactionArgument::U64(_)
+            | TransactionArgument::U128(_)
+            | TransactionArgument::Address(_)
+            | TransactionArgument::U8Vector(_)
+            | TransactionArgument::Bool(_) => 1,
+            TransactionArgument::U64Vector(_)
+            | TransactionArgument::U128Vector(_)
+            | TransactionArgument::BoolVector(_)
+            | TransactionArgument::AddressVector(_)
+            | TransactionArgument::Vector(_)
+            | TransactionArgument::AsciiString(_) => ARGUMENT_FORMAT_VERSION_2,
+        }
+    }
+
+    /// Returns true if the argument is passed to the script as a vector.
+    pub fn is_vector(&self) -> bool {
+        match self {
+            TransactionArgument::U8Vector(_)
+            | TransactionArgument::U64Vector(_)
+            | TransactionArgument::U128Vector(_)
+            | TransactionArgument::BoolVector(_)
+            | TransactionArgument::AddressVector(_)
+            | TransactionArgument::Vector(_)
+            | TransactionArgument::AsciiString(_) => true,
+            TransactionArgument::U8(_)
+            | TransactionArgument::U64(_)
+            | TransactionArgument::U128(_)
+            | TransactionArgument::Address(_)
+            | TransactionArgument::Bool(_) => false,
+        }
+    }
+
+    /// Checks what the encoding does not guarantee: strings are ASCII, the elements of a nested
+    /// vector are vectors, and vectors are nested at most `MAX_VECTOR_NESTING_DEPTH` deep.
+    /// Whether the argument matches the type of the script parameter is checked by the VM.
+    pub fn check_well_formed(&self) -> Result<()> {
+        self.check_well_formed_at_depth(1)
+    }
+
+    fn check_well_formed_at_depth(&self, depth: usize) -> Result<()> {
+        match self {
+            TransactionArgument::AsciiString(s) => {
+                if !s.is_ascii() {
+                    bail!("string argument is not ASCII")
+                }
+            }
+            TransactionArgument::Vector(elems) => {
+                if depth >= MAX_VECTOR_NESTING_DEPTH {
+                    bail!("vectors nested more than {} deep", MAX_VECTOR_NESTING_DEPTH)
+                }
+                for elem in elems {
+                    if !elem.is_vector() {
+                        bail!(
+                            "nested vector argument with a non-vector element {:?}",
+                            elem
+                        )
+                    }
+                    elem.check_well_formed_at_depth(depth + 1)?
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Debug for TransactionArgument {
@@ -26,6 +120,14 @@ impl fmt::Debug for TransactionArgument {
             TransactionArgument::U8Vector(vector) => {
                 write!(f, "{{U8Vector: 0x{}}}", hex::encode(vector))
             }
+            TransactionArgument::U64Vector(vector) => write!(f, "{{U64Vector: {:?}}}", vector),
+            TransactionArgument::U128Vector(vector) => write!(f, "{{U128Vector: {:?}}}", vector),
+            TransactionArgument::BoolVector(vector) => write!(f, "{{BoolVector: {:?}}}", vector),
+            TransactionArgument::AddressVector(vector) => {
+                write!(f, "{{AddressVector: {:?}}}", vector)
+            }
+            TransactionArgument::Vector(vector) => write!(f, "{{Vector: {:?}}}", vector),
+            TransactionArgument::AsciiString(s) => write!(f, "{{AsciiString: {:?}}}", s),
         }
     }
 }
@@ -313,6 +313,9 @@ pub enum StatusCode {
     INVALID_MODULE_PUBLISHER = 21,
     // The sending account has no role
     NO_ACCOUNT_ROLE = 22,
+    // A transaction argument is malformed, or uses an argument format newer than the on-chain
+    // Libra version
+    INVALID_TRANSACTION_ARGUMENT = 23,
 
     // When a code module/script is published it is verified. These are the
     // possible errors that can arise from the verification process.
@@ -5,6 +5,8 @@
 
 use move_lang::{
     command_line::{self as cli},
+    errors,
+    lints::{self, LintConfig},
     shared::*,
 };
 use structopt::*;
@@ -35,6 +37,26 @@ pub struct Options {
         parse(try_from_str = cli::parse_address)
     )]
     pub sender: Option<Address>,
+
+    /// Run the lints over the checked source files, and report their warnings
+    #[structopt(long = "lint")]
+    pub lint: bool,
+
+    /// Disable a lint. Can be given several times
+    #[structopt(name = "LINT", long = "allow", requires = "lint")]
+    pub allow: Vec<String>,
+
+    /// The format of the warnings, either 'text' or 'json'
+    #[structopt(
+        long = "format",
+        default_value = "text",
+        possible_values = &["text", "json"]
+    )]
+    pub format: String,
+
+    /// List the lints and exit
+    #[structopt(long = "list-lints")]
+    pub list_lints: bool,
 }
 
 pub fn main() -> anyhow::Result<()> {
@@ -42,6 +64,35 @@ pub fn main() -> anyhow::Result<()> {
         source_files,
         dependencies,
         sender,
+        lint,
+        allow,
+        format,
+        list_lints,
     } = Options::from_args();
-    move_lang::move_check(&source_files, &dependencies, sender)
+    if list_lints {
+        for lint in lints::all_lints() {
+            println!("{:<28}{}", lint.name(), lint.description());
+        }
+        return Ok(());
+    }
+    if !lint {
+        return move_lang::move_check(&source_files, &dependencies, sender);
+    }
+
+    let mut config = LintConfig::default();
+    for name in &allow {
+        config.allow(name)?;
+    }
+    let (files, warnings_or_errors) =
+        move_lang::move_lint_no_report(&source_files, &dependencies, sender, &config)?;
+    let warnings = match warnings_or_errors {
+        Err(errors) => errors::report_errors(files, errors),
+        Ok(warnings) => warnings,
+    };
+    if format == "json" {
+        println!("{}", errors::report_warnings_to_json(&files, warnings));
+    } else {
+        errors::report_warnings(files, warnings)
+    }
+    Ok(())
 }
@@ -3,7 +3,7 @@
 
 use codespan::{FileId, Files, Span};
 use codespan_reporting::{
-    diagnostic::{Diagnostic, Label},
+    diagnostic::{Diagnostic, Label, Severity},
     term::{
         emit,
         termcolor::{Buffer, ColorChoice, StandardStream, WriteColor},
@@ -11,6 +11,7 @@ use codespan_reporting::{
     },
 };
 use move_ir_types::location::*;
+use serde_json::json;
 use std::collections::{HashMap, HashSet};
 
 //**************************************************************************************************
@@ -22,6 +23,10 @@ pub type Error = Vec<(Loc, String)>;
 pub type ErrorSlice = [(Loc, String)];
 pub type HashableError = Vec<(&'static str, usize, usize, String)>;
 
+/// A warning, along with the name of the lint which raised it
+pub type Warning = (&'static str, Error);
+pub type Warnings = Vec<Warning>;
+
 pub type FilesSourceText = HashMap<&'static str, String>;
 
 type FileMapping = HashMap<&'static str, FileId>;
@@ -71,6 +76,58 @@ fn output_errors<W: WriteColor>(writer: &mut W, sources: FilesSourceText, errors
     render_errors(writer, &files, &file_mapping, errors);
 }
 
+pub fn report_warnings(files: FilesSourceText, warnings: Warnings) {
+    let mut writer = StandardStream::stderr(ColorChoice::Auto);
+    output_warnings(&mut writer, files, warnings);
+}
+
+pub fn report_warnings_to_buffer(files: FilesSourceText, warnings: Warnings) -> Vec<u8> {
+    let mut writer = Buffer::no_color();
+    output_warnings(&mut writer, files, warnings);
+    writer.into_inner()
+}
+
+/// Renders the warnings as a JSON array, with an object for each warning giving the lint, the
+/// position and the message of the warning, and its notes
+pub fn report_warnings_to_json(files: &FilesSourceText, mut warnings: Warnings) -> String {
+    let position = |loc: Loc, msg: String| {
+        let source = &files[loc.file()];
+        let offset = loc.span().start().to_usize();
+        let line_start = source[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+        json!({
+            "file": loc.file(),
+            "line": source[..offset].matches('\n').count() + 1,
+            "column": source[line_start..offset].chars().count() + 1,
+            "message": msg,
+        })
+    };
+    warnings.sort_by(|(_, e1), (_, e2)| e1[0].0.cmp(&e2[0].0));
+    let warnings = warnings
+        .into_iter()
+        .map(|(lint, mut error)| {
+            let (loc, msg) = error.remove(0);
+            let mut warning = position(loc, msg);
+            warning["lint"] = json!(lint);
+            warning["notes"] = error
+                .into_iter()
+                .map(|(loc, msg)| position(loc, msg))
+                .collect();
+            warning
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_string_pretty(&warnings).unwrap()
+}
+
+fn output_warnings<W: WriteColor>(writer: &mut W, sources: FilesSourceText, warnings: Warnings) {
+    let mut files = Files::new();
+    let mut file_mapping = HashMap::new();
+    for (fname, source) in sources.into_iter() {
+        let id = files.add(fname, source);
+        file_mapping.insert(fname, id);
+    }
+    render_warnings(writer, &files, &file_mapping, warnings);
+}
+
 fn hashable_error(error: &ErrorSlice) -> HashableError {
     error
         .iter()
@@ -103,11 +160,28 @@ fn render_errors<W: WriteColor>(
             continue;
         }
         seen.insert(hashable_error);
-        let err = render_error(files, file_mapping, error);
+        let err = render_error(files, file_mapping, Severity::Error, error);
         emit(writer, &Config::default(), &files, &err).unwrap()
     }
 }
 
+fn render_warnings<W: WriteColor>(
+    writer: &mut W,
+    files: &Files<String>,
+    file_mapping: &FileMapping,
+    mut warnings: Warnings,
+) {
+    warnings.sort_by(|(_, e1), (_, e2)| e1[0].0.cmp(&e2[0].0));
+    let mut seen: HashSet<(&'static str, HashableError)> = HashSet::new();
+    for (lint, warning) in warnings.into_iter() {
+        if !seen.insert((lint, hashable_error(&warning))) {
+            continue;
+        }
+        let diag = render_error(files, file_mapping, Severity::Warning, warning).with_code(lint);
+        emit(writer, &Config::default(), &files, &diag).unwrap()
+    }
+}
+
 fn convert_loc(files: &Files<String>, file_mapping: &FileMapping, loc: Loc) -> (FileId, Span) {
     let fname = loc.file();
     let id = *file_mapping.get(fname).unwrap();
@@ -117,14 +191,19 @@ fn convert_loc(files: &Files<String>, file_mapping: &FileMapping, loc: Loc) -> (
     (id, Span::new(begin_index, end_index))
 }
 
-fn render_error(files: &Files<String>, file_mapping: &FileMapping, mut error: Error) -> Diagnostic {
+fn render_error(
+    files: &Files<String>,
+    file_mapping: &FileMapping,
+    severity: Severity,
+    mut error: Error,
+) -> Diagnostic {
     let mk_lbl = |err: (Loc, String)| -> Label {
         let (id, span) = convert_loc(files, file_mapping, err.0);
         Label::new(id, span, err.1)
     };
     let err = error.remove(0);
     // TODO message with each error msg
-    let mut diag = Diagnostic::new_error("", mk_lbl(err));
+    let mut diag = Diagnostic::new(severity, "", mk_lbl(err));
     diag = diag.with_secondary_labels(error.into_iter().map(mk_lbl));
     diag
 }
@@ -220,7 +220,12 @@ fn set_sender_address(context: &mut Context, loc: Loc, sender: Option<Address>)
 }
 
 fn module_(context: &mut Context, mdef: P::ModuleDefinition) -> (ModuleIdent, E::ModuleDefinition) {
-    let P::ModuleDefinition { loc, name, members } = mdef;
+    let P::ModuleDefinition {
+        attributes: _,
+        loc,
+        name,
+        members,
+    } = mdef;
     let _ = check_restricted_self_name(context, "module", &name.0);
 
     let name_loc = name.loc();
@@ -596,6 +601,7 @@ fn struct_def_(
     pstruct: P::StructDefinition,
 ) -> (StructName, E::StructDefinition) {
     let P::StructDefinition {
+        attributes: _,
         loc,
         name,
         resource_opt,
@@ -661,6 +667,7 @@ fn constant(
 fn constant_(context: &mut Context, pconstant: P::Constant) -> (ConstantName, E::Constant) {
     assert!(context.exp_specs.is_empty());
     let P::Constant {
+        attributes: _,
         loc,
         name,
         signature: psignature,
@@ -694,6 +701,7 @@ fn function(
 
 fn function_(context: &mut Context, pfunction: P::Function) -> (FunctionName, E::Function) {
     let P::Function {
+        attributes: _,
         loc,
         name,
         visibility,
@@ -13,12 +13,14 @@ pub mod errors;
 pub mod expansion;
 pub mod hlir;
 pub mod ir_translation;
+pub mod lints;
 pub mod naming;
 pub mod parser;
 pub mod shared;
 pub mod test_utils;
 mod to_bytecode;
 pub mod typing;
+pub mod unit_test;
 
 use anyhow::anyhow;
 use codespan::{ByteIndex, Span};
@@ -67,7 +69,7 @@ pub fn move_check_no_report(
     deps: &[String],
     sender_opt: Option<Address>,
 ) -> anyhow::Result<(FilesSourceText, Errors)> {
-    let (files, pprog_and_comments_res) = parse_program(targets, deps)?;
+    let (files, pprog_and_comments_res) = parse_program(targets, deps, false)?;
     let pprog_res = pprog_and_comments_res.map(|(pprog, _)| pprog);
     match check_program(pprog_res, sender_opt) {
         Err(errors) => Ok((files, errors)),
@@ -85,7 +87,7 @@ pub fn move_compile(
     deps: &[String],
     sender_opt: Option<Address>,
 ) -> anyhow::Result<(FilesSourceText, Vec<CompiledUnit>)> {
-    let (files, pprog_and_comments_res) = parse_program(targets, deps)?;
+    let (files, pprog_and_comments_res) = parse_program(targets, deps, false)?;
     let pprog_res = pprog_and_comments_res.map(|(pprog, _)| pprog);
     match compile_program(pprog_res, sender_opt) {
         Err(errors) => errors::report_errors(files, errors),
@@ -99,7 +101,7 @@ pub fn move_compile_no_report(
     deps: &[String],
     sender_opt: Option<Address>,
 ) -> anyhow::Result<(FilesSourceText, Result<Vec<CompiledUnit>, Errors>)> {
-    let (files, pprog_and_comments_res) = parse_program(targets, deps)?;
+    let (files, pprog_and_comments_res) = parse_program(targets, deps, false)?;
     let pprog_res = pprog_and_comments_res.map(|(pprog, _)| pprog);
     Ok(match compile_program(pprog_res, sender_opt) {
         Err(errors) => (files, Err(errors)),
@@ -107,6 +109,30 @@ pub fn move_compile_no_report(
     })
 }
 
+/// Move compile for testing, returning errors instead of reporting them to stderr.
+///
+/// Unlike the other entry points, this compiles the test functions and the `#[test_only]` modules
+/// and members, and returns the plan of the tests found in `targets`. As the tests run against
+/// the dependencies, these are compiled as well, and returned along with the targets.
+pub fn move_compile_for_testing_no_report(
+    targets: &[String],
+    deps: &[String],
+    sender_opt: Option<Address>,
+) -> anyhow::Result<(
+    FilesSourceText,
+    Result<(Vec<CompiledUnit>, unit_test::TestPlan), Errors>,
+)> {
+    let (files, pprog_and_comments_res) = parse_program(targets, deps, true)?;
+    let res = pprog_and_comments_res.and_then(|(mut pprog, _)| {
+        let test_plan = unit_test::plan_builder::construct_test_plan(&pprog, sender_opt)?;
+        let lib_definitions = std::mem::replace(&mut pprog.lib_definitions, vec![]);
+        pprog.source_definitions.extend(lib_definitions);
+        let units = compile_program(Ok(pprog), sender_opt)?;
+        Ok((units, test_plan))
+    });
+    Ok((files, res))
+}
+
 /// Move compile up to expansion phase, returning errors instead of reporting them to stderr.
 ///
 /// This also returns a map containing documentation comments for each source in `targets`.
@@ -118,7 +144,7 @@ pub fn move_compile_to_expansion_no_report(
     FilesSourceText,
     Result<(expansion::ast::Program, CommentMap), Errors>,
 )> {
-    let (files, pprog_and_comments_res) = parse_program(targets, deps)?;
+    let (files, pprog_and_comments_res) = parse_program(targets, deps, false)?;
     let res = pprog_and_comments_res.and_then(|(pprog, comment_map)| {
         let (eprog, errors) = expansion::translate::program(pprog, sender_opt);
         check_errors(errors)?;
@@ -127,6 +153,43 @@ pub fn move_compile_to_expansion_no_report(
     Ok((files, res))
 }
 
+/// Move check up to typing, returning errors instead of reporting them to stderr.
+///
+/// The typed program is returned along with the errors found by the expansion, naming and typing
+/// passes, which do not stop on errors. Only parsing errors prevent the program from being typed.
+pub fn move_check_to_typing_no_report(
+    targets: &[String],
+    deps: &[String],
+    sender_opt: Option<Address>,
+) -> anyhow::Result<(
+    FilesSourceText,
+    Result<(typing::ast::Program, Errors), Errors>,
+)> {
+    let (files, pprog_and_comments_res) = parse_program(targets, deps, false)?;
+    let res = pprog_and_comments_res.map(|(pprog, _)| {
+        let (eprog, errors) = expansion::translate::program(pprog, sender_opt);
+        let (nprog, errors) = naming::translate::program(eprog, errors);
+        typing::translate::program(nprog, errors)
+    });
+    Ok((files, res))
+}
+
+/// Move check, then run the lints enabled in `config`, returning the errors or the warnings
+/// instead of reporting them.
+///
+/// The lints are only run if the targets check without errors. Warnings raised inside of items
+/// annotated with `#[allow(<lint>)]` are not returned.
+pub fn move_lint_no_report(
+    targets: &[String],
+    deps: &[String],
+    sender_opt: Option<Address>,
+    config: &lints::LintConfig,
+) -> anyhow::Result<(FilesSourceText, Result<Warnings, Errors>)> {
+    let (files, pprog_and_comments_res) = parse_program(targets, deps, false)?;
+    let res = pprog_and_comments_res.and_then(|(pprog, _)| lint_program(pprog, sender_opt, config));
+    Ok((files, res))
+}
+
 //**************************************************************************************************
 // Utils
 //**************************************************************************************************
@@ -215,6 +278,24 @@ fn check_program(
     Ok(cprog)
 }
 
+fn lint_program(
+    prog: parser::ast::Program,
+    sender_opt: Option<Address>,
+    config: &lints::LintConfig,
+) -> Result<Warnings, Errors> {
+    let suppressions = lints::Suppressions::new(&prog)?;
+    let (eprog, errors) = expansion::translate::program(prog, sender_opt);
+    let (nprog, errors) = naming::translate::program(eprog, errors);
+    let (tprog, errors) = typing::translate::program(nprog, errors);
+    check_errors(errors)?;
+    let mut warnings = lints::check_typing(config, &tprog);
+    let (hprog, errors) = hlir::translate::program(tprog);
+    let (cprog, errors) = cfgir::translate::program(errors, hprog);
+    check_errors(errors)?;
+    warnings.extend(lints::check_cfgir(config, &cprog));
+    Ok(suppressions.filter(warnings))
+}
+
 fn compile_program(
     prog: Result<parser::ast::Program, Errors>,
     sender_opt: Option<Address>,
@@ -227,9 +308,12 @@ fn compile_program(
 // Parsing
 //**************************************************************************************************
 
+// Parses the targets and the dependencies. Unless `test_mode` is set, the members which only exist
+// for testing are removed.
 fn parse_program(
     targets: &[String],
     deps: &[String],
+    test_mode: bool,
 ) -> anyhow::Result<(
     FilesSourceText,
     Result<(parser::ast::Program, CommentMap), Errors>,
@@ -262,13 +346,12 @@ fn parse_program(
     }
 
     let res = if errors.is_empty() {
-        Ok((
-            parser::ast::Program {
-                source_definitions,
-                lib_definitions,
-            },
-            source_comments,
-        ))
+        let pprog = parser::ast::Program {
+            source_definitions,
+            lib_definitions,
+        };
+        unit_test::filter_test_members::program(pprog, test_mode)
+            .map(|pprog| (pprog, source_comments))
     } else {
         Err(errors)
     };
@@ -344,6 +427,16 @@ fn parse_file(
     Ok((defs, comments, errors))
 }
 
+/// Parses a single source file from a string rather than from disk, returning its definitions
+/// and its documentation comments.
+pub fn parse_source_string(
+    fname: &'static str,
+    source: &str,
+) -> Result<(Vec<parser::ast::Definition>, MatchedFileCommentMap), Errors> {
+    let (no_comments_buffer, comment_map) = strip_comments_and_verify(fname, source)?;
+    parse_file_string(fname, &no_comments_buffer, comment_map)
+}
+
 //**************************************************************************************************
 // Comments
 //**************************************************************************************************
@@ -573,7 +666,7 @@ fn strip_comments(fname: &'static str, input: &str) -> Result<(String, FileComme
 
 // We restrict strings to only ascii visual characters (0x20 <= c <= 0x7E) or a permitted newline
 // character--\n--or a tab--\t.
-fn strip_comments_and_verify(
+pub fn strip_comments_and_verify(
     fname: &'static str,
     string: &str,
 ) -> Result<(String, FileCommentMap), Errors> {
@@ -0,0 +1,202 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Lints over checked Move programs.
+//!
+//! A lint reports code which compiles, but is likely to be a mistake or could be improved. Lints
+//! run over the typed program and over the control flow graphs of the CFGIR, and only once the
+//! program checks without errors. As the compiler already rejects the plain cases of unused
+//! locals, unused `acquires` and code following an `abort`, the corresponding lints report the
+//! cases the compiler accepts.
+//!
+//! Each warning is named after the lint which raised it. A lint can be disabled for the whole
+//! program with a `LintConfig`, or for a module or module member by annotating it with
+//! `#[allow(<lint>, ...)]`.
+
+mod reused_abort_codes;
+mod unguarded_global_mutation;
+mod unnecessary_copy;
+mod unreachable_code;
+mod unused_acquires;
+mod unused_variables;
+mod visitor;
+
+use crate::{
+    cfgir::ast as G,
+    errors::*,
+    parser::ast::{self as P, Attribute_},
+    typing::ast as T,
+};
+use anyhow::bail;
+use move_ir_types::location::*;
+use std::collections::BTreeSet;
+
+pub const ALLOW_ATTR: &str = "allow";
+
+//**************************************************************************************************
+// Lints
+//**************************************************************************************************
+
+pub trait Lint {
+    /// The name of the lint, as used in `#[allow(...)]` and in the reported warnings
+    fn name(&self) -> &'static str;
+
+    /// A short description of what the lint reports
+    fn description(&self) -> &'static str;
+
+    fn check_typing(&self, _prog: &T::Program) -> Errors {
+        vec![]
+    }
+
+    fn check_cfgir(&self, _prog: &G::Program) -> Errors {
+        vec![]
+    }
+}
+
+/// All lints, in the order they are run
+pub fn all_lints() -> Vec<Box<dyn Lint>> {
+    vec![
+        Box::new(unused_acquires::UnusedAcquires),
+        Box::new(unused_variables::UnusedVariables),
+        Box::new(unnecessary_copy::UnnecessaryCopy),
+        Box::new(reused_abort_codes::ReusedAbortCodes),
+        Box::new(unguarded_global_mutation::UnguardedGlobalMutation),
+        Box::new(unreachable_code::UnreachableCode),
+    ]
+}
+
+fn is_lint(name: &str) -> bool {
+    all_lints().iter().any(|lint| lint.name() == name)
+}
+
+//**************************************************************************************************
+// Config
+//**************************************************************************************************
+
+/// The lints to run. All lints are run by default.
+#[derive(Debug, Default, Clone)]
+pub struct LintConfig {
+    allowed: BTreeSet<String>,
+}
+
+impl LintConfig {
+    /// Disables the lint named `name` for the whole program
+    pub fn allow(&mut self, name: &str) -> anyhow::Result<()> {
+        if !is_lint(name) {
+            bail!("Unknown lint '{}'", name)
+        }
+        self.allowed.insert(name.to_string());
+        Ok(())
+    }
+
+    fn is_enabled(&self, lint: &dyn Lint) -> bool {
+        !self.allowed.contains(lint.name())
+    }
+}
+
+//**************************************************************************************************
+// Entry
+//**************************************************************************************************
+
+pub fn check_typing(config: &LintConfig, prog: &T::Program) -> Warnings {
+    all_lints()
+        .into_iter()
+        .filter(|lint| config.is_enabled(lint.as_ref()))
+        .flat_map(|lint| tag(lint.name(), lint.check_typing(prog)))
+        .collect()
+}
+
+pub fn check_cfgir(config: &LintConfig, prog: &G::Program) -> Warnings {
+    all_lints()
+        .into_iter()
+        .filter(|lint| config.is_enabled(lint.as_ref()))
+        .flat_map(|lint| tag(lint.name(), lint.check_cfgir(prog)))
+        .collect()
+}
+
+fn tag(name: &'static str, errors: Errors) -> impl Iterator<Item = Warning> {
+    errors.into_iter().map(move |error| (name, error))
+}
+
+//**************************************************************************************************
+// Suppressions
+//**************************************************************************************************
+
+/// The items annotated with `#[allow(...)]`, along with the lints allowed in each
+pub struct Suppressions(Vec<(Loc, BTreeSet<String>)>);
+
+impl Suppressions {
+    /// Collects the `#[allow(...)]` annotations of the source definitions. Fails if an annotation
+    /// names an unknown lint.
+    pub fn new(prog: &P::Program) -> Result<Self, Errors> {
+        let mut errors = vec![];
+        let mut items = vec![];
+        let mut add = |loc: Loc, attributes: &[P::Attributes]| {
+            let allowed = allowed_lints(&mut errors, attributes);
+            if !allowed.is_empty() {
+                items.push((loc, allowed))
+            }
+        };
+        for def in &prog.source_definitions {
+            let mdefs = match def {
+                P::Definition::Module(mdef) => std::slice::from_ref(mdef),
+                P::Definition::Address(_, _, mdefs) => &mdefs[..],
+                P::Definition::Script(script) => {
+                    add(script.function.loc, &script.function.attributes);
+                    continue;
+                }
+            };
+            for mdef in mdefs {
+                add(mdef.loc, &mdef.attributes);
+                for member in &mdef.members {
+                    match member {
+                        P::ModuleMember::Function(f) => add(f.loc, &f.attributes),
+                        P::ModuleMember::Struct(s) => add(s.loc, &s.attributes),
+                        P::ModuleMember::Constant(c) => add(c.loc, &c.attributes),
+                        P::ModuleMember::Spec(_) | P::ModuleMember::Use(_) => (),
+                    }
+                }
+            }
+        }
+        check_errors(errors)?;
+        Ok(Suppressions(items))
+    }
+
+    /// Removes the warnings raised inside of items which allow the lint that raised them
+    pub fn filter(&self, warnings: Warnings) -> Warnings {
+        warnings
+            .into_iter()
+            .filter(|(name, error)| !self.is_allowed(name, error[0].0))
+            .collect()
+    }
+
+    fn is_allowed(&self, name: &str, loc: Loc) -> bool {
+        self.0.iter().any(|(item_loc, allowed)| {
+            allowed.contains(name)
+                && item_loc.file() == loc.file()
+                && item_loc.span().start() <= loc.span().start()
+                && loc.span().end() <= item_loc.span().end()
+        })
+    }
+}
+
+fn allowed_lints(errors: &mut Errors, attributes: &[P::Attributes]) -> BTreeSet<String> {
+    let mut allowed = BTreeSet::new();
+    for attr in attributes.iter().flat_map(|attrs| &attrs.value) {
+        let lints = match &attr.value {
+            Attribute_::Parameterized(n, lints) if n.value == ALLOW_ATTR => lints,
+            _ => continue,
+        };
+        for lint in lints {
+            // the form of the arguments is checked along with the other attributes
+            if let Attribute_::Name(n) = &lint.value {
+                if is_lint(&n.value) {
+                    allowed.insert(n.value.clone());
+                } else {
+                    errors.push(vec![(lint.loc, format!("Unknown lint '{}'", n))]);
+                }
+            }
+        }
+    }
+    allowed
+}
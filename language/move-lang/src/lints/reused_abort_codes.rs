@@ -0,0 +1,88 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    visitor::{visit_function_body, TypingVisitor},
+    Lint,
+};
+use crate::{
+    errors::*,
+    expansion::ast::Value_,
+    parser::ast::FunctionName,
+    typing::ast::{self as T, BuiltinFunction_, ExpListItem, UnannotatedExp_},
+};
+use move_ir_types::location::*;
+use std::collections::BTreeMap;
+
+/// Reports abort codes given as literals, in `abort` or `assert`, which are used by more than one
+/// function of a module. A failure can then not be traced back to the function which raised it.
+pub struct ReusedAbortCodes;
+
+impl Lint for ReusedAbortCodes {
+    fn name(&self) -> &'static str {
+        "reused_abort_codes"
+    }
+
+    fn description(&self) -> &'static str {
+        "literal abort codes used by more than one function of a module"
+    }
+
+    fn check_typing(&self, prog: &T::Program) -> Errors {
+        let mut errors = vec![];
+        for (_, mdef) in prog
+            .modules
+            .iter()
+            .filter(|(_, mdef)| mdef.is_source_module)
+        {
+            // the uses of each abort code, by function name
+            let mut uses: BTreeMap<u64, Vec<(FunctionName, Loc)>> = BTreeMap::new();
+            for (fname, fdef) in &mdef.functions {
+                let mut context = Context { codes: vec![] };
+                visit_function_body(&mut context, &fdef.body);
+                for (code, loc) in context.codes {
+                    uses.entry(code).or_default().push((fname.clone(), loc))
+                }
+            }
+            for (code, uses) in uses {
+                let (first_fname, first_loc) = &uses[0];
+                for (_, loc) in uses.iter().filter(|(fname, _)| fname != first_fname) {
+                    let msg = format!(
+                        "Abort code {} is also used by '{}'. Consider a distinct code for each \
+                         failure, declared as a constant",
+                        code, first_fname
+                    );
+                    errors.push(vec![
+                        (*loc, msg),
+                        (*first_loc, "Previously used here".into()),
+                    ])
+                }
+            }
+        }
+        errors
+    }
+}
+
+struct Context {
+    codes: Vec<(u64, Loc)>,
+}
+
+impl TypingVisitor for Context {
+    fn exp(&mut self, e: &T::Exp) {
+        let code = match &e.exp.value {
+            UnannotatedExp_::Abort(code) => code,
+            UnannotatedExp_::Builtin(b, args) if b.value == BuiltinFunction_::Assert => {
+                match &args.exp.value {
+                    UnannotatedExp_::ExpList(items) if items.len() == 2 => match &items[1] {
+                        ExpListItem::Single(code, _) => code,
+                        ExpListItem::Splat(_, _, _) => return,
+                    },
+                    _ => return,
+                }
+            }
+            _ => return,
+        };
+        if let UnannotatedExp_::Value(sp!(_, Value_::U64(value))) = &code.exp.value {
+            self.codes.push((*value, code.exp.loc))
+        }
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    visitor::{visit_function_body, TypingVisitor},
+    Lint,
+};
+use crate::{
+    errors::*,
+    naming::ast::{BuiltinTypeName_, Type, TypeName_, Type_},
+    parser::ast::{FunctionName, FunctionVisibility, ModuleIdent},
+    shared::*,
+    typing::ast::{self as T, BuiltinFunction_, UnannotatedExp_},
+};
+use move_ir_types::location::*;
+use std::collections::BTreeMap;
+
+/// Reports public functions which modify global storage, directly or through calls to functions
+/// of the same module, but do not take a signer. Nothing then restricts who can modify it.
+pub struct UnguardedGlobalMutation;
+
+impl Lint for UnguardedGlobalMutation {
+    fn name(&self) -> &'static str {
+        "unguarded_global_mutation"
+    }
+
+    fn description(&self) -> &'static str {
+        "public functions which modify global storage without taking a signer"
+    }
+
+    fn check_typing(&self, prog: &T::Program) -> Errors {
+        let mut errors = vec![];
+        for (mident, mdef) in prog
+            .modules
+            .iter()
+            .filter(|(_, mdef)| mdef.is_source_module)
+        {
+            module(&mut errors, &mident, mdef)
+        }
+        errors
+    }
+}
+
+struct Context<'a> {
+    mident: &'a ModuleIdent,
+    // the first modification of global storage
+    modification: Option<(Loc, String)>,
+    // the functions of the module called, along with the location of their first call
+    calls: BTreeMap<FunctionName, Loc>,
+}
+
+impl<'a> TypingVisitor for Context<'a> {
+    fn exp(&mut self, e: &T::Exp) {
+        use BuiltinFunction_ as B;
+        use UnannotatedExp_ as E;
+        match &e.exp.value {
+            E::ModuleCall(mcall) if &mcall.module == self.mident => {
+                self.calls.entry(mcall.name.clone()).or_insert(e.exp.loc);
+            }
+            E::Builtin(b, _) => match &b.value {
+                B::MoveTo(_) | B::MoveFrom(_) | B::BorrowGlobal(true, _) => {
+                    if self.modification.is_none() {
+                        let msg = format!("Global storage is modified here, by '{}'", b.value);
+                        self.modification = Some((e.exp.loc, msg))
+                    }
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+}
+
+fn module(errors: &mut Errors, mident: &ModuleIdent, mdef: &T::ModuleDefinition) {
+    let mut contexts = BTreeMap::new();
+    for (fname, fdef) in &mdef.functions {
+        let mut context = Context {
+            mident,
+            modification: None,
+            calls: BTreeMap::new(),
+        };
+        visit_function_body(&mut context, &fdef.body);
+        contexts.insert(fname, context);
+    }
+
+    // propagate the modifications to the callers, until a fixpoint is reached
+    loop {
+        let mut changed = false;
+        let modifying = contexts
+            .iter()
+            .filter(|(_, context)| context.modification.is_some())
+            .map(|(fname, _)| fname.clone())
+            .collect::<Vec<_>>();
+        for context in contexts.values_mut() {
+            if context.modification.is_some() {
+                continue;
+            }
+            let call = context
+                .calls
+                .iter()
+                .find(|(callee, _)| modifying.contains(callee));
+            if let Some((callee, loc)) = call {
+                let msg = format!(
+                    "Global storage is modified here, by the call to '{}'",
+                    callee
+                );
+                context.modification = Some((*loc, msg));
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    for (fname, fdef) in &mdef.functions {
+        let is_public = matches!(fdef.visibility, FunctionVisibility::Public(_));
+        let takes_signer = fdef
+            .signature
+            .parameters
+            .iter()
+            .any(|(_, ty)| is_signer(ty));
+        let modification = match &contexts[&fname].modification {
+            Some(modification) if is_public && !takes_signer => modification.clone(),
+            _ => continue,
+        };
+        let msg = format!(
+            "The public function '{}::{}' modifies global storage, but does not take a signer \
+             to authorize it",
+            mident, fname
+        );
+        errors.push(vec![(fname.loc(), msg), modification])
+    }
+}
+
+fn is_signer(sp!(_, ty_): &Type) -> bool {
+    match ty_ {
+        Type_::Ref(_, inner) => is_signer(inner),
+        Type_::Apply(_, sp!(_, TypeName_::Builtin(sp!(_, b))), _) => b == &BuiltinTypeName_::Signer,
+        _ => false,
+    }
+}
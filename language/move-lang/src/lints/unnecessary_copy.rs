@@ -0,0 +1,177 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{visitor::visit_command_exps, Lint};
+use crate::{
+    cfgir::ast as G,
+    errors::*,
+    hlir::{
+        ast::{
+            BaseType_, Command, Command_, Exp, LValue, LValue_, Label, SingleType_, TypeName_,
+            Type_, UnannotatedExp_,
+        },
+        translate::{display_var, DisplayVar},
+    },
+    naming::ast::BuiltinTypeName_,
+    parser::ast::Var,
+    shared::*,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Reports copies of vectors and structs out of locals which are not used afterwards, and could
+/// be moved instead. The compiler only turns the last copy of a reference into a move.
+pub struct UnnecessaryCopy;
+
+impl Lint for UnnecessaryCopy {
+    fn name(&self) -> &'static str {
+        "unnecessary_copy"
+    }
+
+    fn description(&self) -> &'static str {
+        "copies of vectors and structs out of locals which are not used afterwards"
+    }
+
+    fn check_cfgir(&self, prog: &G::Program) -> Errors {
+        let mut errors = vec![];
+        let modules = prog
+            .modules
+            .iter()
+            .filter(|(_, mdef)| mdef.is_source_module);
+        let functions = modules
+            .flat_map(|(_, mdef)| mdef.functions.iter().map(|(_, fdef)| fdef))
+            .chain(prog.scripts.values().map(|script| &script.function));
+        for fdef in functions {
+            if let G::FunctionBody_::Defined { blocks, .. } = &fdef.body.value {
+                function(&mut errors, blocks)
+            }
+        }
+        errors
+    }
+}
+
+fn function(errors: &mut Errors, blocks: &G::BasicBlocks) {
+    // moving a borrowed local might be invalid even if it is not used afterwards
+    let mut borrowed = BTreeSet::new();
+    for cmd in blocks.values().flatten() {
+        visit_command_exps(cmd, &mut |e| {
+            if let UnannotatedExp_::BorrowLocal(_, var) = &e.exp.value {
+                borrowed.insert(var.clone());
+            }
+        })
+    }
+
+    let live_in = liveness(blocks);
+    for block in blocks.values() {
+        let mut live = live_out(&live_in, block);
+        for cmd in block.iter().rev() {
+            let uses = command_uses(cmd);
+            for (var, e) in &uses {
+                let is_last_use =
+                    !live.contains(var) && uses.iter().filter(|(v, _)| v == var).count() == 1;
+                if !is_last_use || borrowed.contains(var) || !is_large_copy(e) {
+                    continue;
+                }
+                let vstr = match display_var(var.value()) {
+                    DisplayVar::Tmp => continue,
+                    DisplayVar::Orig(vstr) => vstr,
+                };
+                let msg = format!(
+                    "Unnecessary copy of '{0}'. It is not used afterwards, and can be moved \
+                     instead: 'move {0}'",
+                    vstr
+                );
+                errors.push(vec![(e.exp.loc, msg)])
+            }
+            transfer(&mut live, cmd, uses);
+        }
+    }
+}
+
+// The locals read by the command, along with the expressions reading them
+fn command_uses(cmd: &Command) -> Vec<(Var, &Exp)> {
+    use UnannotatedExp_ as E;
+    let mut uses = vec![];
+    visit_command_exps(cmd, &mut |e| match &e.exp.value {
+        E::Copy { var, .. } | E::Move { var, .. } | E::BorrowLocal(_, var) => {
+            uses.push((var.clone(), e))
+        }
+        E::Spec(_, used_locals) => uses.extend(used_locals.keys().map(|var| (var.clone(), e))),
+        _ => (),
+    });
+    uses
+}
+
+fn is_large_copy(e: &Exp) -> bool {
+    use BaseType_ as B;
+    use SingleType_ as S;
+    use TypeName_ as N;
+    let is_copy = matches!(&e.exp.value, UnannotatedExp_::Copy { .. });
+    let is_large = match &e.ty.value {
+        Type_::Single(sp!(_, S::Base(sp!(_, B::Apply(_, sp!(_, n), _))))) => match n {
+            N::ModuleType(_, _) => true,
+            N::Builtin(sp!(_, b)) => b == &BuiltinTypeName_::Vector,
+        },
+        _ => false,
+    };
+    is_copy && is_large
+}
+
+//**************************************************************************************************
+// Liveness
+//**************************************************************************************************
+
+type LiveVars = BTreeSet<Var>;
+
+// The locals live at the start of each block
+fn liveness(blocks: &G::BasicBlocks) -> BTreeMap<Label, LiveVars> {
+    let mut live_in: BTreeMap<Label, LiveVars> =
+        blocks.keys().map(|lbl| (*lbl, LiveVars::new())).collect();
+    loop {
+        let mut changed = false;
+        for (lbl, block) in blocks.iter().rev() {
+            let mut live = live_out(&live_in, block);
+            for cmd in block.iter().rev() {
+                transfer(&mut live, cmd, command_uses(cmd));
+            }
+            let cur = live_in.get_mut(lbl).unwrap();
+            if *cur != live {
+                *cur = live;
+                changed = true;
+            }
+        }
+        if !changed {
+            break live_in;
+        }
+    }
+}
+
+fn live_out(live_in: &BTreeMap<Label, LiveVars>, block: &G::BasicBlock) -> LiveVars {
+    let successors = match block.back().map(|cmd| &cmd.value) {
+        Some(Command_::Jump(lbl)) => vec![*lbl],
+        Some(Command_::JumpIf {
+            if_true, if_false, ..
+        }) => vec![*if_true, *if_false],
+        _ => vec![],
+    };
+    successors
+        .iter()
+        .flat_map(|lbl| live_in[lbl].iter().cloned())
+        .collect()
+}
+
+fn transfer(live: &mut LiveVars, cmd: &Command, uses: Vec<(Var, &Exp)>) {
+    if let Command_::Assign(ls, _) = &cmd.value {
+        ls.iter().for_each(|l| assigned(live, l))
+    }
+    live.extend(uses.into_iter().map(|(var, _)| var))
+}
+
+fn assigned(live: &mut LiveVars, sp!(_, l_): &LValue) {
+    match l_ {
+        LValue_::Ignore => (),
+        LValue_::Var(v, _) => {
+            live.remove(v);
+        }
+        LValue_::Unpack(_, _, fields) => fields.iter().for_each(|(_, l)| assigned(live, l)),
+    }
+}
@@ -0,0 +1,185 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    visitor::{visit_exp, visit_function_body, TypingVisitor},
+    Lint,
+};
+use crate::{
+    errors::*,
+    parser::ast::{BinOp_, FunctionName, ModuleIdent},
+    typing::ast::{self as T, ExpListItem, SequenceItem_, UnannotatedExp_},
+};
+use std::collections::BTreeSet;
+
+/// Code following an `abort`, a `return` or a loop without a `break` is a compiler error. This
+/// reports code following a call to a function which never returns, e.g. as it always aborts.
+pub struct UnreachableCode;
+
+impl Lint for UnreachableCode {
+    fn name(&self) -> &'static str {
+        "unreachable_code"
+    }
+
+    fn description(&self) -> &'static str {
+        "code following a call to a function which always aborts"
+    }
+
+    fn check_typing(&self, prog: &T::Program) -> Errors {
+        let never_return = never_returning_functions(prog);
+        let mut context = Context {
+            never_return: &never_return,
+            errors: vec![],
+        };
+        let modules = prog
+            .modules
+            .iter()
+            .filter(|(_, mdef)| mdef.is_source_module);
+        let functions = modules
+            .flat_map(|(_, mdef)| mdef.functions.iter().map(|(_, fdef)| fdef))
+            .chain(prog.scripts.values().map(|script| &script.function));
+        for fdef in functions {
+            visit_function_body(&mut context, &fdef.body)
+        }
+        context.errors
+    }
+}
+
+type FunctionSet = BTreeSet<(ModuleIdent, FunctionName)>;
+
+struct Context<'a> {
+    never_return: &'a FunctionSet,
+    errors: Errors,
+}
+
+impl<'a> TypingVisitor for Context<'a> {
+    fn sequence(&mut self, seq: &T::Sequence) {
+        let diverging = seq.iter().position(|item| match &item.value {
+            SequenceItem_::Seq(e) | SequenceItem_::Bind(_, _, e) => diverges(self.never_return, e),
+            SequenceItem_::Declare(_) => false,
+        });
+        let idx = match diverging {
+            Some(idx) if idx + 1 < seq.len() => idx,
+            _ => return,
+        };
+        // the trailing unit of a block ending with a ';' is not written out
+        let next = &seq[idx + 1];
+        let is_trailing_unit = matches!(
+            &next.value,
+            SequenceItem_::Seq(e) if matches!(e.exp.value, UnannotatedExp_::Unit { trailing: true })
+        );
+        if is_trailing_unit && idx + 2 == seq.len() {
+            return;
+        }
+        self.errors.push(vec![
+            (
+                next.loc,
+                "Unreachable code. This statement (and any following statements) will not be \
+                 executed"
+                    .into(),
+            ),
+            (
+                seq[idx].loc,
+                "Execution never continues past this expression".into(),
+            ),
+        ])
+    }
+}
+
+// The functions which never return, as every path through them aborts or loops forever
+fn never_returning_functions(prog: &T::Program) -> FunctionSet {
+    let mut never_return = FunctionSet::new();
+    loop {
+        let mut changed = false;
+        for (mident, mdef) in &prog.modules {
+            for (fname, fdef) in &mdef.functions {
+                let seq = match &fdef.body.value {
+                    T::FunctionBody_::Defined(seq) => seq,
+                    T::FunctionBody_::Native => continue,
+                };
+                let key = (mident.clone(), fname);
+                if !never_return.contains(&key) && sequence_diverges(&never_return, seq) {
+                    never_return.insert(key);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break never_return;
+        }
+    }
+}
+
+fn sequence_diverges(never_return: &FunctionSet, seq: &T::Sequence) -> bool {
+    seq.iter().any(|item| match &item.value {
+        SequenceItem_::Seq(e) | SequenceItem_::Bind(_, _, e) => diverges(never_return, e),
+        SequenceItem_::Declare(_) => false,
+    })
+}
+
+// Whether evaluating the expression never completes, nor returns from the function
+fn diverges(never_return: &FunctionSet, e: &T::Exp) -> bool {
+    use UnannotatedExp_ as E;
+    let exp_diverges = |e: &T::Exp| diverges(never_return, e);
+    match &e.exp.value {
+        E::Unit { .. }
+        | E::Value(_)
+        | E::InferredNum(_)
+        | E::Move { .. }
+        | E::Copy { .. }
+        | E::Use(_)
+        | E::Constant(_, _)
+        | E::BorrowLocal(_, _)
+        | E::Spec(_, _)
+        | E::Break
+        | E::Continue
+        | E::UnresolvedError => false,
+
+        E::Abort(_) => true,
+        E::Loop {
+            has_break: false,
+            body,
+        } => !contains_return(body),
+        E::ModuleCall(mcall) => {
+            exp_diverges(&mcall.arguments)
+                || never_return.contains(&(mcall.module.clone(), mcall.name.clone()))
+        }
+
+        E::Loop { body: e, .. }
+        | E::While(e, _)
+        | E::Builtin(_, e)
+        | E::Return(e)
+        | E::Dereference(e)
+        | E::UnaryExp(_, e)
+        | E::Borrow(_, e, _)
+        | E::TempBorrow(_, e)
+        | E::Cast(e, _)
+        | E::Annotate(e, _)
+        | E::Assign(_, _, e) => exp_diverges(e),
+        E::IfElse(econd, etrue, efalse) => {
+            exp_diverges(econd) || (exp_diverges(etrue) && exp_diverges(efalse))
+        }
+        E::Block(seq) => sequence_diverges(never_return, seq),
+        E::Mutate(el, er) => exp_diverges(er) || exp_diverges(el),
+        E::BinopExp(e1, sp!(_, BinOp_::And), _, _) | E::BinopExp(e1, sp!(_, BinOp_::Or), _, _) => {
+            exp_diverges(e1)
+        }
+        E::BinopExp(e1, _, _, e2) => exp_diverges(e1) || exp_diverges(e2),
+        E::Pack(_, _, _, fields) => fields.iter().any(|(_, (_, (_, e)))| exp_diverges(e)),
+        E::ExpList(items) => items.iter().any(|item| match item {
+            ExpListItem::Single(e, _) | ExpListItem::Splat(_, e, _) => exp_diverges(e),
+        }),
+    }
+}
+
+fn contains_return(e: &T::Exp) -> bool {
+    struct Returns(bool);
+    impl TypingVisitor for Returns {
+        fn exp(&mut self, e: &T::Exp) {
+            self.0 |= matches!(e.exp.value, UnannotatedExp_::Return(_))
+        }
+    }
+    let mut returns = Returns(false);
+    visit_exp(&mut returns, e);
+    returns.0
+}
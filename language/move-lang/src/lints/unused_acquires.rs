@@ -0,0 +1,78 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{visitor::visit_command_exps, Lint};
+use crate::{
+    cfgir::ast as G,
+    errors::*,
+    hlir::ast::{BaseType_, BuiltinFunction_, Exp, TypeName_, UnannotatedExp_},
+    parser::ast::{ModuleIdent, StructName},
+};
+use std::collections::BTreeSet;
+
+/// Reports the resources listed in `acquires` which are only acquired in code removed by the
+/// optimizer, e.g. under an `if` whose condition is always false. The compiler requires every
+/// listed resource to be acquired somewhere in the function, but not on a path that can run.
+pub struct UnusedAcquires;
+
+impl Lint for UnusedAcquires {
+    fn name(&self) -> &'static str {
+        "unused_acquires"
+    }
+
+    fn description(&self) -> &'static str {
+        "'acquires' of resources which are only acquired in code that is never executed"
+    }
+
+    fn check_cfgir(&self, prog: &G::Program) -> Errors {
+        let mut errors = vec![];
+        for (mident, mdef) in prog
+            .modules
+            .iter()
+            .filter(|(_, mdef)| mdef.is_source_module)
+        {
+            for (fname, fdef) in &mdef.functions {
+                let blocks = match &fdef.body.value {
+                    G::FunctionBody_::Native => continue,
+                    G::FunctionBody_::Defined { blocks, .. } => blocks,
+                };
+                let mut acquired = BTreeSet::new();
+                for cmd in blocks.values().flatten() {
+                    visit_command_exps(cmd, &mut |e| exp(&mident, &mut acquired, e))
+                }
+                for (s, loc) in &fdef.acquires {
+                    if acquired.contains(s) {
+                        continue;
+                    }
+                    let msg = format!(
+                        "Unnecessary 'acquires'. The resource '{}::{}' is only acquired by '{}' \
+                         in code that is never executed",
+                        mident, s, fname
+                    );
+                    errors.push(vec![(*loc, msg)])
+                }
+            }
+        }
+        errors
+    }
+}
+
+fn exp(mident: &ModuleIdent, acquired: &mut BTreeSet<StructName>, e: &Exp) {
+    use BuiltinFunction_ as B;
+    use UnannotatedExp_ as E;
+    match &e.exp.value {
+        E::ModuleCall(mcall) if &mcall.module == mident => {
+            acquired.extend(mcall.acquires.keys().cloned())
+        }
+        E::Builtin(b, _) => match &b.value {
+            B::MoveFrom(bt) | B::BorrowGlobal(_, bt) => match &bt.value {
+                BaseType_::Apply(_, sp!(_, TypeName_::ModuleType(m, s)), _) if m == mident => {
+                    acquired.insert(s.clone());
+                }
+                _ => (),
+            },
+            B::MoveTo(_) | B::Exists(_) => (),
+        },
+        _ => (),
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::{
+    visitor::{visit_function_body, TypingVisitor},
+    Lint,
+};
+use crate::{errors::*, parser::ast::Var, shared::*, typing::ast as T};
+use move_ir_types::location::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Unused locals and parameters are compiler errors, unless their name starts with an underscore.
+/// This reports the cases the compiler accepts: variables which are only used in specifications,
+/// and variables whose name starts with an underscore even though they are used.
+pub struct UnusedVariables;
+
+impl Lint for UnusedVariables {
+    fn name(&self) -> &'static str {
+        "unused_variables"
+    }
+
+    fn description(&self) -> &'static str {
+        "locals and parameters only used in specifications, or used despite a leading underscore"
+    }
+
+    fn check_typing(&self, prog: &T::Program) -> Errors {
+        let mut errors = vec![];
+        let modules = prog
+            .modules
+            .iter()
+            .filter(|(_, mdef)| mdef.is_source_module);
+        let functions = modules
+            .flat_map(|(_, mdef)| mdef.functions.iter().map(|(_, fdef)| fdef))
+            .chain(prog.scripts.values().map(|script| &script.function));
+        for fdef in functions {
+            function(&mut errors, fdef)
+        }
+        errors
+    }
+}
+
+#[derive(Default)]
+struct Context {
+    // The location of the first declaration of each variable, and whether it is a parameter
+    declared: BTreeMap<String, (Loc, bool)>,
+    used: BTreeSet<String>,
+    used_in_specs: BTreeSet<String>,
+}
+
+impl Context {
+    fn declare(&mut self, v: &Var, is_parameter: bool) {
+        self.declared
+            .entry(v.value().to_string())
+            .or_insert((v.loc(), is_parameter));
+    }
+}
+
+impl TypingVisitor for Context {
+    fn lvalues(&mut self, is_binding: bool, ls: &T::LValueList) {
+        if is_binding {
+            ls.value.iter().for_each(|l| lvalue(self, l))
+        }
+    }
+
+    fn exp(&mut self, e: &T::Exp) {
+        use T::UnannotatedExp_ as E;
+        match &e.exp.value {
+            E::Move { var, .. } | E::Copy { var, .. } | E::Use(var) | E::BorrowLocal(_, var) => {
+                self.used.insert(var.value().to_string());
+            }
+            E::Spec(_, used_locals) => self
+                .used_in_specs
+                .extend(used_locals.keys().map(|v| v.value().to_string())),
+            _ => (),
+        }
+    }
+}
+
+fn lvalue(context: &mut Context, sp!(_, l_): &T::LValue) {
+    use T::LValue_ as L;
+    match l_ {
+        L::Ignore => (),
+        L::Var(v, _) => context.declare(v, false),
+        L::Unpack(_, _, _, fields) | L::BorrowUnpack(_, _, _, _, fields) => {
+            for (_, (_, (_, l))) in fields {
+                lvalue(context, l)
+            }
+        }
+    }
+}
+
+fn function(errors: &mut Errors, fdef: &T::Function) {
+    let mut context = Context::default();
+    for (v, _) in &fdef.signature.parameters {
+        context.declare(v, true)
+    }
+    visit_function_body(&mut context, &fdef.body);
+    let Context {
+        declared,
+        used,
+        used_in_specs,
+    } = context;
+    for (name, (loc, is_parameter)) in declared {
+        let kind = if is_parameter { "parameter" } else { "local" };
+        let msg = if name.starts_with('_') {
+            if !used.contains(&name) {
+                continue;
+            }
+            format!(
+                "The {0} '{1}' is used, but its leading underscore marks it as unused. Consider \
+                 renaming it to '{2}'",
+                kind,
+                name,
+                name.trim_start_matches('_')
+            )
+        } else {
+            if used.contains(&name) || !used_in_specs.contains(&name) {
+                continue;
+            }
+            format!(
+                "Unused {} '{}'. It is only used in specifications",
+                kind, name
+            )
+        };
+        errors.push(vec![(loc, msg)])
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    hlir::ast::{self as H, Command_},
+    typing::ast as T,
+};
+
+//**************************************************************************************************
+// Typing
+//**************************************************************************************************
+
+/// Hooks called while walking a typed function body. Sequences and expressions are visited before
+/// their children, and in evaluation order.
+pub trait TypingVisitor {
+    fn sequence(&mut self, _seq: &T::Sequence) {}
+
+    /// Called for the left hand side of `let`s, where `is_binding` is set, and of assignments
+    fn lvalues(&mut self, _is_binding: bool, _ls: &T::LValueList) {}
+
+    fn exp(&mut self, _e: &T::Exp) {}
+}
+
+pub fn visit_function_body<V: TypingVisitor>(v: &mut V, body: &T::FunctionBody) {
+    match &body.value {
+        T::FunctionBody_::Native => (),
+        T::FunctionBody_::Defined(seq) => visit_sequence(v, seq),
+    }
+}
+
+pub fn visit_sequence<V: TypingVisitor>(v: &mut V, seq: &T::Sequence) {
+    use T::SequenceItem_ as S;
+    v.sequence(seq);
+    for item in seq {
+        match &item.value {
+            S::Seq(e) => visit_exp(v, e),
+            S::Declare(ls) => v.lvalues(true, ls),
+            S::Bind(ls, _, e) => {
+                visit_exp(v, e);
+                v.lvalues(true, ls)
+            }
+        }
+    }
+}
+
+pub fn visit_exp<V: TypingVisitor>(v: &mut V, e: &T::Exp) {
+    use T::UnannotatedExp_ as E;
+    v.exp(e);
+    match &e.exp.value {
+        E::Unit { .. }
+        | E::Value(_)
+        | E::InferredNum(_)
+        | E::Move { .. }
+        | E::Copy { .. }
+        | E::Use(_)
+        | E::Constant(_, _)
+        | E::BorrowLocal(_, _)
+        | E::Spec(_, _)
+        | E::Break
+        | E::Continue
+        | E::UnresolvedError => (),
+
+        E::ModuleCall(mcall) => visit_exp(v, &mcall.arguments),
+        E::Builtin(_, e)
+        | E::Loop { body: e, .. }
+        | E::Return(e)
+        | E::Abort(e)
+        | E::Dereference(e)
+        | E::UnaryExp(_, e)
+        | E::Borrow(_, e, _)
+        | E::TempBorrow(_, e)
+        | E::Cast(e, _)
+        | E::Annotate(e, _) => visit_exp(v, e),
+
+        E::IfElse(econd, etrue, efalse) => {
+            visit_exp(v, econd);
+            visit_exp(v, etrue);
+            visit_exp(v, efalse)
+        }
+        E::While(econd, ebody) => {
+            visit_exp(v, econd);
+            visit_exp(v, ebody)
+        }
+        E::Block(seq) => visit_sequence(v, seq),
+        E::Assign(ls, _, e) => {
+            visit_exp(v, e);
+            v.lvalues(false, ls)
+        }
+        E::Mutate(el, er) => {
+            visit_exp(v, er);
+            visit_exp(v, el)
+        }
+        E::BinopExp(e1, _, _, e2) => {
+            visit_exp(v, e1);
+            visit_exp(v, e2)
+        }
+        E::Pack(_, _, _, fields) => {
+            for (_, (_, (_, e))) in fields {
+                visit_exp(v, e)
+            }
+        }
+        E::ExpList(items) => {
+            for item in items {
+                match item {
+                    T::ExpListItem::Single(e, _) | T::ExpListItem::Splat(_, e, _) => {
+                        visit_exp(v, e)
+                    }
+                }
+            }
+        }
+    }
+}
+
+//**************************************************************************************************
+// CFGIR
+//**************************************************************************************************
+
+/// Calls `f` on each expression of the command, parents before their children
+pub fn visit_command_exps<'a, F: FnMut(&'a H::Exp)>(sp!(_, cmd_): &'a H::Command, f: &mut F) {
+    use Command_ as C;
+    match cmd_ {
+        C::Assign(_, e) => visit_exp_cfgir(e, f),
+        C::Mutate(el, er) => {
+            visit_exp_cfgir(er, f);
+            visit_exp_cfgir(el, f)
+        }
+        C::Return(e) | C::Abort(e) | C::IgnoreAndPop { exp: e, .. } | C::JumpIf { cond: e, .. } => {
+            visit_exp_cfgir(e, f)
+        }
+        C::Jump(_) => (),
+        C::Break | C::Continue => panic!("ICE break/continue not translated to jumps"),
+    }
+}
+
+fn visit_exp_cfgir<'a, F: FnMut(&'a H::Exp)>(e: &'a H::Exp, f: &mut F) {
+    use H::UnannotatedExp_ as E;
+    f(e);
+    match &e.exp.value {
+        E::Unit { .. }
+        | E::Value(_)
+        | E::Move { .. }
+        | E::Copy { .. }
+        | E::Constant(_)
+        | E::BorrowLocal(_, _)
+        | E::Unreachable
+        | E::Spec(_, _)
+        | E::UnresolvedError => (),
+
+        E::ModuleCall(mcall) => visit_exp_cfgir(&mcall.arguments, f),
+        E::Builtin(_, e)
+        | E::Freeze(e)
+        | E::Dereference(e)
+        | E::UnaryExp(_, e)
+        | E::Borrow(_, e, _)
+        | E::Cast(e, _) => visit_exp_cfgir(e, f),
+
+        E::BinopExp(e1, _, e2) => {
+            visit_exp_cfgir(e1, f);
+            visit_exp_cfgir(e2, f)
+        }
+        E::Pack(_, _, fields) => fields.iter().for_each(|(_, _, e)| visit_exp_cfgir(e, f)),
+        E::ExpList(items) => {
+            for item in items {
+                match item {
+                    H::ExpListItem::Single(e, _) | H::ExpListItem::Splat(_, e, _) => {
+                        visit_exp_cfgir(e, f)
+                    }
+                }
+            }
+        }
+    }
+}
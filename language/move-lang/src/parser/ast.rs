@@ -77,6 +77,23 @@ pub enum Use {
     Members(ModuleIdent, Vec<(Name, Option<Name>)>),
 }
 
+//**************************************************************************************************
+// Attributes
+//**************************************************************************************************
+
+// An attribute on a module or a module member, e.g. `#[test]`, `#[test(a = 0x1)]` or
+// `#[expected_failure(abort_code = 7)]`
+#[derive(Debug, PartialEq)]
+pub enum Attribute_ {
+    Name(Name),
+    Assigned(Name, Value),
+    Parameterized(Name, Vec<Attribute>),
+}
+pub type Attribute = Spanned<Attribute_>;
+
+// The attributes of a single `#[...]`
+pub type Attributes = Spanned<Vec<Attribute>>;
+
 //**************************************************************************************************
 // Modules
 //**************************************************************************************************
@@ -93,6 +110,7 @@ pub struct ModuleIdent(pub Spanned<ModuleIdent_>);
 
 #[derive(Debug)]
 pub struct ModuleDefinition {
+    pub attributes: Vec<Attributes>,
     pub loc: Loc,
     pub name: ModuleName,
     pub members: Vec<ModuleMember>,
@@ -118,6 +136,7 @@ pub type ResourceLoc = Option<Loc>;
 
 #[derive(Debug, PartialEq)]
 pub struct StructDefinition {
+    pub attributes: Vec<Attributes>,
     pub loc: Loc,
     pub resource_opt: ResourceLoc,
     pub name: StructName,
@@ -163,6 +182,7 @@ pub type FunctionBody = Spanned<FunctionBody_>;
 //  }
 // (public?) native foo<T1(: copyable?), ..., TN(: copyable?)>(x1: t1, ..., xn: tn): t1 * ... * tn;
 pub struct Function {
+    pub attributes: Vec<Attributes>,
     pub loc: Loc,
     pub visibility: FunctionVisibility,
     pub signature: FunctionSignature,
@@ -179,6 +199,7 @@ new_name!(ConstantName);
 
 #[derive(PartialEq, Debug)]
 pub struct Constant {
+    pub attributes: Vec<Attributes>,
     pub loc: Loc,
     pub signature: Type,
     pub name: ConstantName,
@@ -775,13 +796,42 @@ impl AstDebug for Script {
     }
 }
 
+impl AstDebug for Vec<Attributes> {
+    fn ast_debug(&self, w: &mut AstWriter) {
+        for attributes in self {
+            w.write("#[");
+            w.comma(&attributes.value, |w, attr| attr.ast_debug(w));
+            w.writeln("]");
+        }
+    }
+}
+
+impl AstDebug for Attribute_ {
+    fn ast_debug(&self, w: &mut AstWriter) {
+        match self {
+            Attribute_::Name(n) => w.write(&format!("{}", n)),
+            Attribute_::Assigned(n, v) => {
+                w.write(&format!("{} = ", n));
+                v.ast_debug(w);
+            }
+            Attribute_::Parameterized(n, attrs) => {
+                w.write(&format!("{}(", n));
+                w.comma(attrs, |w, attr| attr.ast_debug(w));
+                w.write(")");
+            }
+        }
+    }
+}
+
 impl AstDebug for ModuleDefinition {
     fn ast_debug(&self, w: &mut AstWriter) {
         let ModuleDefinition {
+            attributes,
             loc: _loc,
             name,
             members,
         } = self;
+        attributes.ast_debug(w);
         w.write(&format!("module {}", name));
         w.block(|w| {
             for mem in members {
@@ -831,12 +881,14 @@ impl AstDebug for Use {
 impl AstDebug for StructDefinition {
     fn ast_debug(&self, w: &mut AstWriter) {
         let StructDefinition {
+            attributes,
             loc: _loc,
             resource_opt,
             name,
             type_parameters,
             fields,
         } = self;
+        attributes.ast_debug(w);
         if let StructFields::Native(_) = fields {
             w.write("native ");
         }
@@ -1026,6 +1078,7 @@ impl AstDebug for PragmaProperty_ {
 impl AstDebug for Function {
     fn ast_debug(&self, w: &mut AstWriter) {
         let Function {
+            attributes,
             loc: _loc,
             visibility,
             signature,
@@ -1033,6 +1086,7 @@ impl AstDebug for Function {
             name,
             body,
         } = self;
+        attributes.ast_debug(w);
         visibility.ast_debug(w);
         if let FunctionBody_::Native = &body.value {
             w.write("native ");
@@ -1082,11 +1136,13 @@ impl AstDebug for FunctionSignature {
 impl AstDebug for Constant {
     fn ast_debug(&self, w: &mut AstWriter) {
         let Constant {
+            attributes,
             loc: _loc,
             name,
             signature,
             value,
         } = self;
+        attributes.ast_debug(w);
         w.write(&format!("const {}:", name));
         signature.ast_debug(w);
         w.write(" = ");
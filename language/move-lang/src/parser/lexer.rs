@@ -18,6 +18,7 @@ pub enum Tok {
     IdentifierValue,
     Exclaim,
     ExclaimEqual,
+    NumSign,
     Percent,
     Amp,
     AmpAmp,
@@ -94,6 +95,7 @@ impl fmt::Display for Tok {
             IdentifierValue => "[Identifier]",
             Exclaim => "!",
             ExclaimEqual => "!=",
+            NumSign => "#",
             Percent => "%",
             Amp => "&",
             AmpAmp => "&&",
@@ -236,7 +238,8 @@ impl<'input> Lexer<'input> {
     //
     // Calling this function during parsing effectively marks a valid point for documentation
     // comments. The documentation comments are not stored in the AST, but can be retrieved by
-    // using the start position of an item as an index into `matched_doc_comments`.
+    // using the start position of an item as an index into `matched_doc_comments`. Calling it
+    // again at the same position does not drop the comments matched by the first call.
     pub fn match_doc_comments(&mut self) {
         let start = self.previous_end_loc() as u32;
         let end = self.cur_start as u32;
@@ -250,6 +253,9 @@ impl<'input> Lexer<'input> {
             })
             .collect::<Vec<String>>()
             .join("\n");
+        if matched.is_empty() && self.matched_doc_comments.contains_key(&ByteIndex(end)) {
+            return;
+        }
         for span in matched {
             self.doc_comments.remove(&span);
         }
@@ -392,6 +398,7 @@ fn find_token(file: &'static str, text: &str, start_offset: usize) -> Result<(To
                 (Tok::Colon, 1)
             }
         }
+        '#' => (Tok::NumSign, 1),
         '%' => (Tok::Percent, 1),
         '(' => (Tok::LParen, 1),
         ')' => (Tok::RParen, 1),
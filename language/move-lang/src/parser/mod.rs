@@ -1,7 +1,7 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-mod lexer;
+pub mod lexer;
 pub(crate) mod syntax;
 
 pub mod ast;
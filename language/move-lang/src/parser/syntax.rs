@@ -1283,13 +1283,13 @@ fn parse_optional_type_parameters<'input>(
 //          <NativeFunctionDecl>
 //          | <MoveFunctionDecl>
 //      NativeFunctionDecl =
-//          <DocComments> "native" ( "public" )? "fun"
+//          <DocComments> <Attributes> "native" ( "public" )? "fun"
 //          <FunctionDefName> "(" Comma<Parameter> ")"
 //          (":" <Type>)?
 //          ("acquires" <ModuleAccess> ("," <ModuleAccess>)*)?
 //          ";"
 //      MoveFunctionDecl =
-//          <DocComments> ( "public" )? "fun"
+//          <DocComments> <Attributes> ( "public" )? "fun"
 //          <FunctionDefName> "(" Comma<Parameter> ")"
 //          (":" <Type>)?
 //          ("acquires" <ModuleAccess> ("," <ModuleAccess>)*)?
@@ -1298,13 +1298,14 @@ fn parse_optional_type_parameters<'input>(
 //          <Identifier> <OptionalTypeParameters>
 //
 // If the "allow_native" parameter is false, this will only accept Move
-// functions.
+// functions. The doc comments and the attributes are parsed by the caller, and
+// "start_loc" is the start of the first of them.
 fn parse_function_decl<'input>(
     tokens: &mut Lexer<'input>,
+    attributes: Vec<Attributes>,
+    start_loc: usize,
     allow_native: bool,
 ) -> Result<Function, Error> {
-    tokens.match_doc_comments();
-    let start_loc = tokens.start_loc();
     // Record the source location of the "native" keyword (if there is one).
     let native_opt = if allow_native {
         consume_optional_token_with_loc(tokens, Tok::Native)?
@@ -1391,6 +1392,7 @@ fn parse_function_decl<'input>(
 
     let loc = make_loc(tokens.file_name(), start_loc, tokens.previous_end_loc());
     Ok(Function {
+        attributes,
         loc,
         visibility,
         signature,
@@ -1415,14 +1417,16 @@ fn parse_parameter<'input>(tokens: &mut Lexer<'input>) -> Result<(Var, Type), Er
 
 // Parse a struct definition:
 //      StructDefinition =
-//          <DocComments> "resource"? "struct" <StructDefName> "{" Comma<FieldAnnot> "}"
-//          | <DocComments> "native" "resource"? "struct" <StructDefName> ";"
+//          <DocComments> <Attributes> "resource"? "struct" <StructDefName>
+//              "{" Comma<FieldAnnot> "}"
+//          | <DocComments> <Attributes> "native" "resource"? "struct" <StructDefName> ";"
 //      StructDefName =
 //          <Identifier> <OptionalTypeParameters>
-fn parse_struct_definition<'input>(tokens: &mut Lexer<'input>) -> Result<StructDefinition, Error> {
-    tokens.match_doc_comments();
-    let start_loc = tokens.start_loc();
-
+fn parse_struct_definition<'input>(
+    tokens: &mut Lexer<'input>,
+    attributes: Vec<Attributes>,
+    start_loc: usize,
+) -> Result<StructDefinition, Error> {
     // Record the source location of the "native" keyword (if there is one).
     let native_opt = consume_optional_token_with_loc(tokens, Tok::Native)?;
 
@@ -1454,6 +1458,7 @@ fn parse_struct_definition<'input>(tokens: &mut Lexer<'input>) -> Result<StructD
 
     let loc = make_loc(tokens.file_name(), start_loc, tokens.previous_end_loc());
     Ok(StructDefinition {
+        attributes,
         loc,
         resource_opt,
         name,
@@ -1477,11 +1482,12 @@ fn parse_field_annot<'input>(tokens: &mut Lexer<'input>) -> Result<(Field, Type)
 //**************************************************************************************************
 
 // Parse a constant:
-//      ConstantDecl = "const" <Identifier> ":" <Type> "=" <Exp> ";"
-fn parse_constant<'input>(tokens: &mut Lexer<'input>) -> Result<Constant, Error> {
-    tokens.match_doc_comments();
-    let start_loc = tokens.start_loc();
-
+//      ConstantDecl = <DocComments> <Attributes> "const" <Identifier> ":" <Type> "=" <Exp> ";"
+fn parse_constant<'input>(
+    tokens: &mut Lexer<'input>,
+    attributes: Vec<Attributes>,
+    start_loc: usize,
+) -> Result<Constant, Error> {
     consume_token(tokens, Tok::Const)?;
     let name = ConstantName(parse_identifier(tokens)?);
     consume_token(tokens, Tok::Colon)?;
@@ -1491,6 +1497,7 @@ fn parse_constant<'input>(tokens: &mut Lexer<'input>) -> Result<Constant, Error>
     consume_token(tokens, Tok::Semicolon)?;
     let loc = make_loc(tokens.file_name(), start_loc, tokens.previous_end_loc());
     Ok(Constant {
+        attributes,
         loc,
         name,
         signature,
@@ -1498,6 +1505,76 @@ fn parse_constant<'input>(tokens: &mut Lexer<'input>) -> Result<Constant, Error>
     })
 }
 
+//**************************************************************************************************
+// Attributes
+//**************************************************************************************************
+
+// Parse the attributes in front of a module or a module member:
+//      Attributes = ("#" "[" Comma<Attribute> "]")*
+fn parse_attributes<'input>(tokens: &mut Lexer<'input>) -> Result<Vec<Attributes>, Error> {
+    let mut attributes = vec![];
+    while tokens.peek() == Tok::NumSign {
+        let start_loc = tokens.start_loc();
+        tokens.advance()?;
+        let attrs = parse_comma_list(
+            tokens,
+            Tok::LBracket,
+            Tok::RBracket,
+            parse_attribute,
+            "an attribute",
+        )?;
+        let end_loc = tokens.previous_end_loc();
+        attributes.push(spanned(tokens.file_name(), start_loc, end_loc, attrs));
+    }
+    Ok(attributes)
+}
+
+// Parse a single attribute:
+//      Attribute =
+//          <Identifier>
+//          | <Identifier> "=" <AttributeValue>
+//          | <Identifier> "(" Comma<Attribute> ")"
+//      AttributeValue = <Value> | <Num>
+fn parse_attribute<'input>(tokens: &mut Lexer<'input>) -> Result<Attribute, Error> {
+    let start_loc = tokens.start_loc();
+    let name = parse_identifier(tokens)?;
+    let attr = match tokens.peek() {
+        Tok::Equal => {
+            tokens.advance()?;
+            let value = match tokens.peek() {
+                Tok::NumValue => {
+                    let start_loc = tokens.start_loc();
+                    let n = parse_num(tokens)?;
+                    let end_loc = tokens.previous_end_loc();
+                    spanned(tokens.file_name(), start_loc, end_loc, Value_::U128(n))
+                }
+                Tok::AddressValue
+                | Tok::True
+                | Tok::False
+                | Tok::U8Value
+                | Tok::U64Value
+                | Tok::U128Value
+                | Tok::ByteStringValue => parse_value(tokens)?,
+                _ => return Err(unexpected_token_error(tokens, "an attribute value")),
+            };
+            Attribute_::Assigned(name, value)
+        }
+        Tok::LParen => {
+            let attrs = parse_comma_list(
+                tokens,
+                Tok::LParen,
+                Tok::RParen,
+                parse_attribute,
+                "an attribute",
+            )?;
+            Attribute_::Parameterized(name, attrs)
+        }
+        _ => Attribute_::Name(name),
+    };
+    let end_loc = tokens.previous_end_loc();
+    Ok(spanned(tokens.file_name(), start_loc, end_loc, attr))
+}
+
 //**************************************************************************************************
 // AddressBlock
 //**************************************************************************************************
@@ -1608,13 +1685,14 @@ fn is_struct_definition<'input>(tokens: &mut Lexer<'input>) -> Result<bool, Erro
 
 // Parse a module:
 //      Module =
-//          <DocComments> "module" <ModuleName> "{"
+//          <DocComments> <Attributes> "module" <ModuleName> "{"
 //              <UseDecl>*
 //              ( <ConstantDecl> | <StructDefinition> | <FunctionDecl> | <Spec> )*
 //          "}"
 fn parse_module<'input>(tokens: &mut Lexer<'input>) -> Result<ModuleDefinition, Error> {
     tokens.match_doc_comments();
     let start_loc = tokens.start_loc();
+    let attributes = parse_attributes(tokens)?;
 
     consume_token(tokens, Tok::Module)?;
     let name = parse_module_name(tokens)?;
@@ -1622,21 +1700,42 @@ fn parse_module<'input>(tokens: &mut Lexer<'input>) -> Result<ModuleDefinition,
 
     let mut members = vec![];
     while tokens.peek() != Tok::RBrace {
+        // Use declarations cannot be documented.
+        if tokens.peek() != Tok::Use {
+            tokens.match_doc_comments();
+        }
+        let start_loc = tokens.start_loc();
+        let attributes = parse_attributes(tokens)?;
         members.push(match tokens.peek() {
+            Tok::Spec | Tok::Use if !attributes.is_empty() => {
+                let loc = attributes[0].loc;
+                return Err(vec![(
+                    loc,
+                    "Attributes are only allowed on modules, constants, structs and functions"
+                        .to_string(),
+                )]);
+            }
             Tok::Spec => ModuleMember::Spec(parse_spec_block(tokens)?),
             Tok::Use => ModuleMember::Use(parse_use_decl(tokens)?),
-            Tok::Const => ModuleMember::Constant(parse_constant(tokens)?),
+            Tok::Const => ModuleMember::Constant(parse_constant(tokens, attributes, start_loc)?),
             // TODO rework parsing modifiers
             _ if is_struct_definition(tokens)? => {
-                ModuleMember::Struct(parse_struct_definition(tokens)?)
+                ModuleMember::Struct(parse_struct_definition(tokens, attributes, start_loc)?)
             }
-            _ => ModuleMember::Function(parse_function_decl(tokens, /* allow_native */ true)?),
+            _ => ModuleMember::Function(parse_function_decl(
+                tokens, attributes, start_loc, /* allow_native */ true,
+            )?),
         })
     }
     consume_token(tokens, Tok::RBrace)?;
 
     let loc = make_loc(tokens.file_name(), start_loc, tokens.previous_end_loc());
-    Ok(ModuleDefinition { loc, name, members })
+    Ok(ModuleDefinition {
+        attributes,
+        loc,
+        name,
+        members,
+    })
 }
 
 //**************************************************************************************************
@@ -1662,9 +1761,13 @@ fn parse_script<'input>(tokens: &mut Lexer<'input>) -> Result<Script, Error> {
     }
     let mut constants = vec![];
     while tokens.peek() == Tok::Const {
-        constants.push(parse_constant(tokens)?);
+        tokens.match_doc_comments();
+        let start_loc = tokens.start_loc();
+        constants.push(parse_constant(tokens, vec![], start_loc)?);
     }
-    let function = parse_function_decl(tokens, /* allow_native */ false)?;
+    tokens.match_doc_comments();
+    let start_loc = tokens.start_loc();
+    let function = parse_function_decl(tokens, vec![], start_loc, /* allow_native */ false)?;
     let mut specs = vec![];
     while tokens.peek() == Tok::Spec {
         specs.push(parse_spec_block(tokens)?)
@@ -2185,7 +2288,7 @@ fn parse_file<'input>(tokens: &mut Lexer<'input>) -> Result<Vec<Definition>, Err
     let mut defs = vec![];
     while tokens.peek() != Tok::EOF {
         defs.push(match tokens.peek() {
-            Tok::Module => Definition::Module(parse_module(tokens)?),
+            Tok::Module | Tok::NumSign => Definition::Module(parse_module(tokens)?),
             Tok::Script => Definition::Script(parse_script(tokens)?),
             _ => {
                 let (loc, addr, modules) = parse_address_block(tokens)?;
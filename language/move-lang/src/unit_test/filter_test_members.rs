@@ -0,0 +1,178 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    errors::*,
+    lints::ALLOW_ATTR,
+    parser::ast::{self as P, Attribute_},
+    unit_test::{EXPECTED_FAILURE_ATTR, TEST_ATTR, TEST_ONLY_ATTR},
+};
+use move_ir_types::location::*;
+
+//**************************************************************************************************
+// Entry
+//**************************************************************************************************
+
+/// Checks the unit test attributes of the program and, unless `test_mode` is set, removes the
+/// modules and the module members which only exist for testing.
+pub fn program(prog: P::Program, test_mode: bool) -> Result<P::Program, Errors> {
+    let mut errors = vec![];
+    let P::Program {
+        source_definitions,
+        lib_definitions,
+    } = prog;
+    let source_definitions = definitions(&mut errors, test_mode, source_definitions);
+    let lib_definitions = definitions(&mut errors, test_mode, lib_definitions);
+    check_errors(errors)?;
+    Ok(P::Program {
+        source_definitions,
+        lib_definitions,
+    })
+}
+
+fn definitions(
+    errors: &mut Errors,
+    test_mode: bool,
+    defs: Vec<P::Definition>,
+) -> Vec<P::Definition> {
+    defs.into_iter()
+        .filter_map(|def| match def {
+            P::Definition::Module(mdef) => {
+                module(errors, test_mode, mdef).map(P::Definition::Module)
+            }
+            P::Definition::Address(loc, addr, mdefs) => {
+                let mdefs = mdefs
+                    .into_iter()
+                    .filter_map(|mdef| module(errors, test_mode, mdef))
+                    .collect();
+                Some(P::Definition::Address(loc, addr, mdefs))
+            }
+            P::Definition::Script(script) => Some(P::Definition::Script(script)),
+        })
+        .collect()
+}
+
+fn module(
+    errors: &mut Errors,
+    test_mode: bool,
+    mdef: P::ModuleDefinition,
+) -> Option<P::ModuleDefinition> {
+    let P::ModuleDefinition {
+        attributes,
+        loc,
+        name,
+        members,
+    } = mdef;
+    let test_only = check_attributes(errors, &attributes, /* is_function */ false);
+    let members = members
+        .into_iter()
+        .filter(|member| {
+            let test_only = match member {
+                P::ModuleMember::Function(f) => {
+                    check_attributes(errors, &f.attributes, /* is_function */ true)
+                }
+                P::ModuleMember::Struct(s) => {
+                    check_attributes(errors, &s.attributes, /* is_function */ false)
+                }
+                P::ModuleMember::Constant(c) => {
+                    check_attributes(errors, &c.attributes, /* is_function */ false)
+                }
+                P::ModuleMember::Spec(_) | P::ModuleMember::Use(_) => false,
+            };
+            test_mode || !test_only
+        })
+        .collect();
+    if test_only && !test_mode {
+        return None;
+    }
+    Some(P::ModuleDefinition {
+        attributes,
+        loc,
+        name,
+        members,
+    })
+}
+
+//**************************************************************************************************
+// Attributes
+//**************************************************************************************************
+
+// Checks that the attributes are known and used where they are allowed, and returns whether they
+// restrict the annotated item to testing. The lints named by `allow` are checked when linting.
+fn check_attributes(errors: &mut Errors, attributes: &[P::Attributes], is_function: bool) -> bool {
+    let mut test_only: Option<Loc> = None;
+    let mut test: Option<Loc> = None;
+    let mut expected_failure: Option<Loc> = None;
+    for attr in attributes.iter().flat_map(|attrs| &attrs.value) {
+        let (name, assigned) = match &attr.value {
+            Attribute_::Name(n) | Attribute_::Parameterized(n, _) => (n, false),
+            Attribute_::Assigned(n, _) => (n, true),
+        };
+        if name.value == ALLOW_ATTR {
+            check_allow_attribute(errors, attr);
+            continue;
+        }
+        let seen = match name.value.as_str() {
+            TEST_ONLY_ATTR => &mut test_only,
+            TEST_ATTR => &mut test,
+            EXPECTED_FAILURE_ATTR => &mut expected_failure,
+            _ => {
+                let msg = format!("Unknown attribute '{}'", name);
+                errors.push(vec![(attr.loc, msg)]);
+                continue;
+            }
+        };
+        if let Some(prev) = seen {
+            errors.push(vec![
+                (attr.loc, format!("Duplicate '{}' attribute", name)),
+                (*prev, "Previously given here".to_string()),
+            ]);
+            continue;
+        }
+        *seen = Some(attr.loc);
+        if assigned {
+            let msg = format!("Invalid attribute. '{}' cannot be assigned a value", name);
+            errors.push(vec![(attr.loc, msg)]);
+        } else if name.value != TEST_ONLY_ATTR && !is_function {
+            let msg = format!("Invalid attribute. '{}' is only allowed on functions", name);
+            errors.push(vec![(attr.loc, msg)]);
+        }
+    }
+    if let (Some(test_only), Some(test)) = (test_only, test) {
+        errors.push(vec![
+            (
+                test_only,
+                format!(
+                    "Invalid attribute. A '{}' function is always '{}'",
+                    TEST_ATTR, TEST_ONLY_ATTR
+                ),
+            ),
+            (test, format!("'{}' given here", TEST_ATTR)),
+        ]);
+    }
+    if let (Some(expected_failure), None) = (expected_failure, test) {
+        let msg = format!(
+            "Invalid attribute. '{}' is only allowed on '{}' functions",
+            EXPECTED_FAILURE_ATTR, TEST_ATTR
+        );
+        errors.push(vec![(expected_failure, msg)]);
+    }
+    test_only.is_some() || test.is_some()
+}
+
+fn check_allow_attribute(errors: &mut Errors, attr: &P::Attribute) {
+    let msg = format!(
+        "Invalid attribute. '{0}' takes a list of lint names, e.g. '#[{0}(unused_acquires)]'",
+        ALLOW_ATTR
+    );
+    match &attr.value {
+        Attribute_::Parameterized(_, lints) => {
+            for lint in lints {
+                if !matches!(lint.value, Attribute_::Name(_)) {
+                    errors.push(vec![(lint.loc, msg.clone())]);
+                }
+            }
+        }
+        Attribute_::Name(_) | Attribute_::Assigned(_, _) => errors.push(vec![(attr.loc, msg)]),
+    }
+}
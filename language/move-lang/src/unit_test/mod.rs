@@ -0,0 +1,50 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Unit tests written in Move.
+//!
+//! A test is a function annotated with `#[test]`, which takes no arguments but signers. The
+//! signers are bound to addresses in the attribute, e.g. `#[test(account = 0x1)]` for a parameter
+//! `account: &signer`. A test passes if it returns, unless it is also annotated with
+//! `#[expected_failure]`, in which case it passes if it aborts or fails in any other way, and
+//! with `#[expected_failure(abort_code = <code>)]` only if it aborts with that code.
+//!
+//! Tests, as well as modules and module members annotated with `#[test_only]`, are only compiled
+//! when compiling for testing.
+
+use crate::{parser::ast::ModuleIdent, shared::Address};
+use move_ir_types::location::Loc;
+
+pub mod filter_test_members;
+pub mod plan_builder;
+
+pub const TEST_ATTR: &str = "test";
+pub const TEST_ONLY_ATTR: &str = "test_only";
+pub const EXPECTED_FAILURE_ATTR: &str = "expected_failure";
+pub const ABORT_CODE_ATTR: &str = "abort_code";
+
+/// The tests of the modules being compiled, in declaration order.
+pub type TestPlan = Vec<ModuleTestPlan>;
+
+#[derive(Debug, Clone)]
+pub struct ModuleTestPlan {
+    pub module: ModuleIdent,
+    pub tests: Vec<TestCase>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    pub loc: Loc,
+    pub test_name: String,
+    /// The addresses of the signers passed to the test, in parameter order.
+    pub signers: Vec<Address>,
+    pub expected_failure: Option<ExpectedFailure>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpectedFailure {
+    /// The test is expected to fail, by aborting with any code or with an execution error.
+    Any,
+    /// The test is expected to abort with the given code.
+    AbortCode(u64),
+}
@@ -0,0 +1,215 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    errors::*,
+    parser::ast::{self as P, Attribute_, ModuleAccess_, ModuleIdent, ModuleIdent_, Type_, Value_},
+    shared::{Address, Identifier},
+    unit_test::{
+        ExpectedFailure, ModuleTestPlan, TestCase, TestPlan, ABORT_CODE_ATTR,
+        EXPECTED_FAILURE_ATTR, TEST_ATTR,
+    },
+};
+use move_ir_types::location::*;
+use std::{collections::BTreeMap, convert::TryFrom};
+
+//**************************************************************************************************
+// Entry
+//**************************************************************************************************
+
+/// Collects the tests of the source modules of the program, which must have gone through
+/// `filter_test_members` in test mode. Modules outside of an address block are published under
+/// `sender_opt`; their tests are skipped if it is not set, which expansion reports.
+pub fn construct_test_plan(
+    prog: &P::Program,
+    sender_opt: Option<Address>,
+) -> Result<TestPlan, Errors> {
+    let mut errors = vec![];
+    let mut plan = vec![];
+    for def in &prog.source_definitions {
+        match def {
+            P::Definition::Module(mdef) => {
+                if let Some(sender) = sender_opt {
+                    plan.extend(module(&mut errors, sender, mdef))
+                }
+            }
+            P::Definition::Address(_, addr, mdefs) => {
+                for mdef in mdefs {
+                    plan.extend(module(&mut errors, *addr, mdef))
+                }
+            }
+            P::Definition::Script(_) => (),
+        }
+    }
+    check_errors(errors)?;
+    Ok(plan)
+}
+
+fn module(
+    errors: &mut Errors,
+    address: Address,
+    mdef: &P::ModuleDefinition,
+) -> Option<ModuleTestPlan> {
+    let tests: Vec<_> = mdef
+        .members
+        .iter()
+        .filter_map(|member| match member {
+            P::ModuleMember::Function(f) => test_case(errors, f),
+            _ => None,
+        })
+        .collect();
+    if tests.is_empty() {
+        return None;
+    }
+    let name = mdef.name.clone();
+    let module = ModuleIdent(sp(name.loc(), ModuleIdent_ { name, address }));
+    Some(ModuleTestPlan { module, tests })
+}
+
+//**************************************************************************************************
+// Tests
+//**************************************************************************************************
+
+fn test_case(errors: &mut Errors, f: &P::Function) -> Option<TestCase> {
+    let mut test = None;
+    let mut expected_failure = None;
+    for attr in f.attributes.iter().flat_map(|attrs| &attrs.value) {
+        match &attr.value {
+            Attribute_::Name(n) if n.value == TEST_ATTR => test = Some((attr.loc, &[][..])),
+            Attribute_::Parameterized(n, args) if n.value == TEST_ATTR => {
+                test = Some((attr.loc, &args[..]))
+            }
+            Attribute_::Name(n) if n.value == EXPECTED_FAILURE_ATTR => {
+                expected_failure = Some(ExpectedFailure::Any)
+            }
+            Attribute_::Parameterized(n, args) if n.value == EXPECTED_FAILURE_ATTR => {
+                expected_failure = Some(expected_failure_args(errors, attr.loc, args)?)
+            }
+            _ => (),
+        }
+    }
+    let (test_loc, args) = test?;
+
+    let signature = &f.signature;
+    if let Some((tp, _)) = signature.type_parameters.first() {
+        let msg = "Invalid test function. Test functions cannot have type parameters";
+        errors.push(vec![
+            (tp.loc, msg.to_string()),
+            (test_loc, "Test given here".into()),
+        ]);
+        return None;
+    }
+    if !matches!(signature.return_type.value, Type_::Unit) {
+        let msg = "Invalid test function. Test functions cannot return values";
+        errors.push(vec![(signature.return_type.loc, msg.to_string())]);
+        return None;
+    }
+
+    let mut bound = BTreeMap::new();
+    for arg in args {
+        match &arg.value {
+            Attribute_::Assigned(n, value) => match &value.value {
+                Value_::Address(addr) => {
+                    if bound.insert(n.value.as_str(), (arg.loc, *addr)).is_some() {
+                        let msg = format!("Duplicate signer '{}' for the test", n);
+                        errors.push(vec![(arg.loc, msg)]);
+                    }
+                }
+                _ => {
+                    let msg = format!("Invalid test signer. Expected an address for '{}'", n);
+                    errors.push(vec![(value.loc, msg)]);
+                }
+            },
+            _ => {
+                let msg = "Invalid test attribute. Expected the signers of the test, e.g. \
+                           'test(account = 0x1)'";
+                errors.push(vec![(arg.loc, msg.to_string())]);
+            }
+        }
+    }
+
+    let mut signers = vec![];
+    for (var, ty) in &signature.parameters {
+        if !is_signer_ref(ty) {
+            let msg = "Invalid test function parameter. Test functions can only take '&signer' \
+                       parameters";
+            errors.push(vec![(ty.loc, msg.to_string())]);
+            continue;
+        }
+        match bound.remove(var.0.value.as_str()) {
+            Some((_, addr)) => signers.push(addr),
+            None => {
+                let msg = format!("Missing address for the signer '{}' of the test", var);
+                errors.push(vec![(var.loc(), msg), (test_loc, "Test given here".into())]);
+            }
+        }
+    }
+    for (name, (loc, _)) in bound {
+        let msg = format!("Unused test signer. The test has no parameter '{}'", name);
+        errors.push(vec![(loc, msg)]);
+    }
+
+    Some(TestCase {
+        loc: f.name.loc(),
+        test_name: f.name.0.value.clone(),
+        signers,
+        expected_failure,
+    })
+}
+
+fn expected_failure_args(
+    errors: &mut Errors,
+    loc: Loc,
+    args: &[P::Attribute],
+) -> Option<ExpectedFailure> {
+    match args {
+        [] => Some(ExpectedFailure::Any),
+        [arg] => match &arg.value {
+            Attribute_::Assigned(n, value) if n.value == ABORT_CODE_ATTR => {
+                match abort_code(&value.value) {
+                    Some(code) => Some(ExpectedFailure::AbortCode(code)),
+                    None => {
+                        let msg = "Invalid abort code. Expected a number which fits a 'u64'";
+                        errors.push(vec![(value.loc, msg.to_string())]);
+                        None
+                    }
+                }
+            }
+            _ => {
+                let msg = format!("Invalid attribute. Expected '{}' = <code>", ABORT_CODE_ATTR);
+                errors.push(vec![(arg.loc, msg)]);
+                None
+            }
+        },
+        _ => {
+            let msg = format!(
+                "Invalid attribute. '{}' takes at most one argument",
+                EXPECTED_FAILURE_ATTR
+            );
+            errors.push(vec![(loc, msg)]);
+            None
+        }
+    }
+}
+
+fn abort_code(value: &Value_) -> Option<u64> {
+    match value {
+        Value_::U8(u) => Some(*u as u64),
+        Value_::U64(u) => Some(*u),
+        Value_::U128(u) => u64::try_from(*u).ok(),
+        _ => None,
+    }
+}
+
+fn is_signer_ref(ty: &P::Type) -> bool {
+    match &ty.value {
+        Type_::Ref(false, inner) => match &inner.value {
+            Type_::Apply(access, tys) => {
+                tys.is_empty()
+                    && matches!(&access.value, ModuleAccess_::Name(n) if n.value == "signer")
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
@@ -0,0 +1,4 @@
+module M {
+    #[test_only]
+    use 0x1::Vector;
+}
@@ -0,0 +1,29 @@
+address 0x2 {
+#[test_only]
+module TestHelpers {
+    public fun helper() {}
+}
+
+module M {
+    /// Only compiled for testing.
+    #[test_only]
+    resource struct Marker {}
+
+    #[test_only]
+    const CODE: u64 = 7;
+
+    public fun f(): u64 { 0 }
+
+    #[test(account = 0x1, other = 0x2), expected_failure(abort_code = 7)]
+    fun test_f(account: &signer, other: &signer) {
+        0x2::TestHelpers::helper();
+        abort CODE
+    }
+
+    #[test]
+    #[expected_failure]
+    fun test_marker() {
+        let Marker {} = Marker {};
+    }
+}
+}
@@ -0,0 +1,7 @@
+module M {
+    #[allow]
+    fun f() {}
+
+    #[allow(unused_variables, unreachable_code = 1)]
+    fun g() {}
+}
@@ -0,0 +1,4 @@
+module M {
+    #[allow_unused]
+    fun f() {}
+}
@@ -0,0 +1,73 @@
+address 0x2 {
+module Lints {
+    resource struct R { value: u64 }
+    struct Wrapper { items: vector<u64> }
+
+    const EFAILED: u64 = 1;
+
+    fun destroy(a: address) acquires R {
+        if (false) {
+            let R { value: _ } = move_from<R>(a);
+        }
+    }
+
+    fun only_in_specs(x: u64) {
+        spec {
+            assert x > 0;
+        };
+    }
+
+    fun underscore(_count: u64): u64 {
+        _count + 1
+    }
+
+    fun wrap(items: vector<u64>): Wrapper {
+        Wrapper { items: items }
+    }
+
+    fun check_a(x: u64) {
+        assert(x > 0, 7);
+    }
+
+    fun check_b(x: u64) {
+        if (x == 0) abort 7;
+    }
+
+    #[allow(reused_abort_codes)]
+    fun check_c(x: u64) {
+        assert(x < 100, 7);
+    }
+
+    public fun publish(account: &signer) {
+        move_to(account, R { value: 0 })
+    }
+
+    public fun reset(a: address) acquires R {
+        set(a, 0)
+    }
+
+    fun set(a: address, value: u64) acquires R {
+        let r = borrow_global_mut<R>(a);
+        r.value = value;
+    }
+
+    fun fail() {
+        abort EFAILED
+    }
+
+    public fun fail_and_count(): u64 {
+        fail();
+        0
+    }
+}
+
+#[allow(unguarded_global_mutation)]
+module Allowed {
+    resource struct T { value: u64 }
+
+    public fun bump(a: address) acquires T {
+        let t = borrow_global_mut<T>(a);
+        t.value = t.value + 1;
+    }
+}
+}
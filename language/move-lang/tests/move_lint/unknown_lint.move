@@ -0,0 +1,6 @@
+address 0x2 {
+module M {
+    #[allow(unused_everything)]
+    fun f() {}
+}
+}
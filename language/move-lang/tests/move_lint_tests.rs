@@ -0,0 +1,84 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_lang::{
+    errors::report_warnings_to_json, lints::LintConfig, move_lint_no_report, shared::Address,
+    test_utils::*,
+};
+use std::collections::BTreeSet;
+
+const LINTS_FILE: &str = "tests/move_lint/lints.move";
+const UNKNOWN_LINT_FILE: &str = "tests/move_lint/unknown_lint.move";
+
+// Lints the file, and returns the name and the line of each warning
+fn lint(file: &str, config: &LintConfig) -> BTreeSet<(String, u64)> {
+    let sender = Some(Address::parse_str(SENDER).unwrap());
+    let (files, warnings_or_errors) =
+        move_lint_no_report(&[file.to_string()], &[], sender, config).unwrap();
+    let warnings = match warnings_or_errors {
+        Ok(warnings) => warnings,
+        Err(errors) => panic!("Unexpected errors: {:?}", errors),
+    };
+    let json: serde_json::Value =
+        serde_json::from_str(&report_warnings_to_json(&files, warnings)).unwrap();
+    json.as_array()
+        .unwrap()
+        .iter()
+        .map(|warning| {
+            let lint = warning["lint"].as_str().unwrap().to_string();
+            (lint, warning["line"].as_u64().unwrap())
+        })
+        .collect()
+}
+
+fn expected(warnings: &[(&str, u64)]) -> BTreeSet<(String, u64)> {
+    warnings
+        .iter()
+        .map(|(lint, line)| (lint.to_string(), *line))
+        .collect()
+}
+
+#[test]
+fn test_lints() {
+    let warnings = lint(LINTS_FILE, &LintConfig::default());
+    assert_eq!(
+        warnings,
+        expected(&[
+            ("unused_acquires", 8),
+            ("unused_variables", 14),
+            ("unused_variables", 20),
+            ("unnecessary_copy", 25),
+            ("reused_abort_codes", 33),
+            ("unguarded_global_mutation", 45),
+            ("unreachable_code", 60),
+        ])
+    );
+}
+
+#[test]
+fn test_allowed_lints() {
+    let mut config = LintConfig::default();
+    config.allow("unused_variables").unwrap();
+    config.allow("unreachable_code").unwrap();
+    let warnings = lint(LINTS_FILE, &config);
+    assert!(warnings
+        .iter()
+        .all(|(lint, _)| lint != "unused_variables" && lint != "unreachable_code"));
+    assert_eq!(warnings.len(), 5);
+
+    assert!(config.allow("unused_everything").is_err());
+}
+
+#[test]
+fn test_unknown_lint() {
+    let (_, warnings_or_errors) = move_lint_no_report(
+        &[UNKNOWN_LINT_FILE.to_string()],
+        &[],
+        None,
+        &LintConfig::default(),
+    )
+    .unwrap();
+    let errors = warnings_or_errors.unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0][0].1, "Unknown lint 'unused_everything'");
+}
@@ -0,0 +1,268 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Debugger support for the interpreter.
+//!
+//! A [`Debugger`] is handed control whenever execution stops before an instruction, either
+//! because it reached a breakpoint or because it completed a step. While stopped, the debugger
+//! can inspect the call stack, the locals, the operand stack and the global resources through a
+//! [`DebugState`], and then tells the interpreter how to resume with a [`DebugCommand`].
+//!
+//! This module is only built with the `debugger` feature, so that the interpreter does not check
+//! for a debugger before each instruction otherwise.
+
+use crate::loader::{Function, Loader};
+use move_core_types::{
+    account_address::AccountAddress,
+    identifier::Identifier,
+    language_storage::{ModuleId, StructTag, TypeTag},
+    vm_status::StatusCode,
+};
+use move_vm_types::{
+    data_store::DataStore,
+    loaded_data::runtime_types::Type,
+    values::{self, Locals, Value},
+};
+use vm::{
+    errors::*,
+    file_format::{Bytecode, CodeOffset, FunctionDefinitionIndex},
+};
+
+/// Implemented by debuggers driving the execution of a script.
+pub trait Debugger {
+    /// The breakpoints at which execution stops, whatever the current command.
+    fn breakpoints(&self) -> &[Breakpoint];
+
+    /// Called whenever execution stops, before the instruction at `state.location()` runs.
+    /// Returns how execution resumes.
+    fn on_stop(&mut self, state: &mut DebugState, reason: StopReason) -> DebugCommand;
+}
+
+/// How execution resumes after a stop.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DebugCommand {
+    /// Run until a breakpoint is reached.
+    Continue,
+    /// Stop before the next instruction, entering calls.
+    StepIn,
+    /// Stop before the next instruction of the current function, or of a caller once it returns.
+    StepOver,
+    /// Stop once the current function has returned to its caller.
+    StepOut,
+}
+
+/// Why execution stopped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StopReason {
+    /// The last command, or the start of the execution, asked to stop here.
+    Step,
+    /// A breakpoint is set on the instruction.
+    Breakpoint,
+}
+
+/// A bytecode instruction of a function.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Breakpoint {
+    /// The module declaring the function, or `None` for the script.
+    pub module: Option<ModuleId>,
+    pub function: Identifier,
+    pub offset: CodeOffset,
+}
+
+impl Breakpoint {
+    fn is_at(&self, function: &Function, offset: CodeOffset) -> bool {
+        self.offset == offset
+            && self.function.as_str() == function.name()
+            && self.module.as_ref() == function.module_id()
+    }
+}
+
+/// The position of execution in a function.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CodeLocation {
+    /// The module declaring the function, or `None` for the script.
+    pub module: Option<ModuleId>,
+    pub function: Identifier,
+    pub function_index: FunctionDefinitionIndex,
+    pub offset: CodeOffset,
+}
+
+impl CodeLocation {
+    fn new(function: &Function, offset: CodeOffset) -> Self {
+        Self {
+            module: function.module_id().cloned(),
+            function: function.identifier().clone(),
+            function_index: function.index(),
+            offset,
+        }
+    }
+}
+
+/// Tracks the last command given by the debugger, to decide where execution stops next.
+pub(crate) struct DebugContext<'d> {
+    debugger: &'d mut dyn Debugger,
+    command: DebugCommand,
+    // The depth of the call stack when the command was given.
+    depth: usize,
+}
+
+impl<'d> DebugContext<'d> {
+    /// Execution stops before the first instruction, so that breakpoints can be set.
+    pub(crate) fn new(debugger: &'d mut dyn Debugger) -> Self {
+        Self {
+            debugger,
+            command: DebugCommand::StepIn,
+            depth: 0,
+        }
+    }
+
+    pub(crate) fn stop_reason(
+        &self,
+        function: &Function,
+        offset: CodeOffset,
+        depth: usize,
+    ) -> Option<StopReason> {
+        let stepped = match self.command {
+            DebugCommand::Continue => false,
+            DebugCommand::StepIn => true,
+            DebugCommand::StepOver => depth <= self.depth,
+            DebugCommand::StepOut => depth < self.depth,
+        };
+        if stepped {
+            Some(StopReason::Step)
+        } else if self
+            .debugger
+            .breakpoints()
+            .iter()
+            .any(|bp| bp.is_at(function, offset))
+        {
+            Some(StopReason::Breakpoint)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn stop(&mut self, state: &mut DebugState, reason: StopReason) {
+        self.command = self.debugger.on_stop(state, reason);
+        self.depth = state.depth();
+    }
+}
+
+/// What a debugger sees of a frame of the interpreter.
+pub(crate) struct FrameView<'a> {
+    pub(crate) function: &'a Function,
+    pub(crate) pc: CodeOffset,
+    pub(crate) locals: &'a Locals,
+    pub(crate) ty_args: &'a [Type],
+}
+
+/// The state of a stopped execution.
+pub struct DebugState<'a> {
+    // The frames of the call stack, the current one last.
+    frames: Vec<FrameView<'a>>,
+    operand_stack: &'a [Value],
+    loader: &'a Loader,
+    data_store: &'a mut dyn DataStore,
+}
+
+impl<'a> DebugState<'a> {
+    pub(crate) fn new(
+        frames: Vec<FrameView<'a>>,
+        operand_stack: &'a [Value],
+        loader: &'a Loader,
+        data_store: &'a mut dyn DataStore,
+    ) -> Self {
+        Self {
+            frames,
+            operand_stack,
+            loader,
+            data_store,
+        }
+    }
+
+    fn current_frame(&self) -> &FrameView<'a> {
+        self.frames
+            .last()
+            .expect("a stopped execution has a current frame")
+    }
+
+    /// The location of the instruction about to run.
+    pub fn location(&self) -> CodeLocation {
+        let frame = self.current_frame();
+        CodeLocation::new(frame.function, frame.pc)
+    }
+
+    /// The number of callers of the current function.
+    pub fn depth(&self) -> usize {
+        self.frames.len() - 1
+    }
+
+    /// The instruction about to run.
+    pub fn instruction(&self) -> &Bytecode {
+        let frame = self.current_frame();
+        &frame.function.code()[frame.pc as usize]
+    }
+
+    /// The call stack, from the function execution started with to the current one. The
+    /// location of a caller is the one of its pending call.
+    pub fn call_stack(&self) -> Vec<CodeLocation> {
+        let (_, callers) = self
+            .frames
+            .split_last()
+            .expect("a stopped execution has a current frame");
+        let mut call_stack: Vec<_> = callers
+            .iter()
+            .map(|frame| CodeLocation::new(frame.function, frame.pc.saturating_sub(1)))
+            .collect();
+        call_stack.push(self.location());
+        call_stack
+    }
+
+    /// The locals of the function at `depth` in the call stack, rendered with their types.
+    pub fn locals(&self, depth: usize) -> PartialVMResult<Vec<String>> {
+        let frame = self.frames.get(depth).ok_or_else(|| {
+            PartialVMError::new(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR)
+                .with_message(format!("no frame at depth {}", depth))
+        })?;
+        let resolver = frame.function.get_resolver(self.loader);
+        let mut locals = vec![];
+        for (idx, local) in frame.function.locals().0.iter().enumerate() {
+            let ty = resolver.make_fat_type(local, frame.ty_args)?;
+            let mut buf = String::new();
+            values::debug::print_local(&mut buf, &ty, frame.locals, idx)?;
+            locals.push(buf);
+        }
+        Ok(locals)
+    }
+
+    /// The operand stack, from the bottom to the top.
+    pub fn operand_stack(&self) -> Vec<String> {
+        // TODO: The types of the values on the operand stack are not known, so the values are
+        // printed without them.
+        self.operand_stack
+            .iter()
+            .map(|value| value.to_string())
+            .collect()
+    }
+
+    /// The resource of type `tag` published under `address`, if any. The module declaring the
+    /// resource must have been loaded by the execution.
+    pub fn resource(
+        &mut self,
+        address: AccountAddress,
+        tag: &StructTag,
+    ) -> PartialVMResult<Option<String>> {
+        let ty = self
+            .loader
+            .resolve_loaded_type(&TypeTag::Struct(tag.clone()))?;
+        let fat_ty = self.loader.type_to_fat_type(&ty)?;
+        match self.data_store.borrow_resource(address, &ty)? {
+            Some(gv) => {
+                let mut buf = String::new();
+                values::debug::print_global_value(&mut buf, &fat_ty, gv)?;
+                Ok(Some(buf))
+            }
+            None => Ok(None),
+        }
+    }
+}
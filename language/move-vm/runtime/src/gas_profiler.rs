@@ -0,0 +1,278 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Attributes the gas charged by the interpreter to the call stacks that were active when it was
+//! charged.
+//!
+//! Profiling is enabled per thread with [`start_profiling`], so it covers every execution on that
+//! thread, whichever client drives the VM. The gas of an instruction, including the gas of a
+//! `Call`, is attributed to the function running it, and the gas of a native function to the
+//! native function itself. Gas charged outside of the interpreter, such as the intrinsic gas of
+//! a transaction, is not attributed.
+//!
+//! Gas is measured in internal gas units, the unit the `CostTable` is expressed in, so that the
+//! cost of cheap instructions is not lost to rounding.
+//!
+//! This module is only built with the `gas_profiler` feature, so that the interpreter does not
+//! record calls and returns otherwise.
+
+use crate::loader::Function;
+use move_core_types::gas_schedule::GasAlgebra;
+use move_vm_types::gas_schedule::CostStrategy;
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+thread_local! {
+    static PROFILER: RefCell<Option<Profiler>> = RefCell::new(None);
+}
+
+/// Starts attributing the gas charged on the current thread, discarding any profile being
+/// collected.
+pub fn start_profiling() {
+    PROFILER.with(|profiler| *profiler.borrow_mut() = Some(Profiler::default()));
+}
+
+/// Stops profiling the current thread and returns the profile collected since
+/// [`start_profiling`], or `None` if profiling was not started.
+pub fn stop_profiling() -> Option<GasProfile> {
+    PROFILER.with(|profiler| {
+        profiler
+            .borrow_mut()
+            .take()
+            .map(|profiler| profiler.profile)
+    })
+}
+
+/// Records a call to `function`, once the call itself has been charged.
+pub(crate) fn enter(function: &Function, cost_strategy: &CostStrategy) {
+    with_profiler(cost_strategy, |profiler, gas_left| {
+        profiler.attribute(gas_left);
+        let name = function_name(function);
+        *profiler.profile.calls.entry(name.clone()).or_insert(0) += 1;
+        profiler.stack.push(name);
+    })
+}
+
+/// Records the return of the current function.
+pub(crate) fn exit(cost_strategy: &CostStrategy) {
+    with_profiler(cost_strategy, |profiler, gas_left| {
+        profiler.attribute(gas_left);
+        profiler.stack.pop();
+    })
+}
+
+/// Records the end of an execution, which unwinds all the functions still on the stack if it
+/// failed.
+pub(crate) fn unwind(cost_strategy: &CostStrategy) {
+    with_profiler(cost_strategy, |profiler, gas_left| {
+        profiler.attribute(gas_left);
+        profiler.stack.clear();
+    })
+}
+
+fn with_profiler(cost_strategy: &CostStrategy, f: impl FnOnce(&mut Profiler, u64)) {
+    PROFILER.with(|profiler| {
+        if let Some(profiler) = profiler.borrow_mut().as_mut() {
+            f(profiler, cost_strategy.remaining_internal_gas().get())
+        }
+    })
+}
+
+/// The name of a function as written in Move sources, e.g. `0x1::LibraAccount::pay_from`.
+fn function_name(function: &Function) -> String {
+    match function.module_id() {
+        Some(module_id) => {
+            let address = format!("{:x}", module_id.address());
+            let address = match address.trim_start_matches('0') {
+                "" => "0",
+                address => address,
+            };
+            format!("0x{}::{}::{}", address, module_id.name(), function.name())
+        }
+        None => format!("Script::{}", function.name()),
+    }
+}
+
+#[derive(Default)]
+struct Profiler {
+    profile: GasProfile,
+    // The functions currently executing, the innermost last.
+    stack: Vec<String>,
+    // The gas left when gas was last attributed.
+    gas_left: u64,
+}
+
+impl Profiler {
+    /// Attributes the gas charged since the last call to the innermost function. The first
+    /// function of an execution only sets the starting point.
+    fn attribute(&mut self, gas_left: u64) {
+        if !self.stack.is_empty() {
+            let gas = self.gas_left.saturating_sub(gas_left);
+            *self.profile.stacks.entry(self.stack.clone()).or_insert(0) += gas;
+        }
+        self.gas_left = gas_left;
+    }
+}
+
+/// The gas charged by the executions profiled, by call stack.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GasProfile {
+    // The gas charged by the innermost function of each call stack, outermost function first.
+    stacks: BTreeMap<Vec<String>, u64>,
+    // The number of calls to each function.
+    calls: BTreeMap<String, u64>,
+}
+
+/// The gas charged by a function, as reported by [`GasProfile::summary`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FunctionSummary {
+    pub name: String,
+    pub calls: u64,
+    /// The gas charged by the function itself.
+    pub self_gas: u64,
+    /// The gas charged by the function and the functions it called.
+    pub total_gas: u64,
+}
+
+impl GasProfile {
+    /// The gas charged by all the executions profiled.
+    pub fn total_gas(&self) -> u64 {
+        self.stacks.values().sum()
+    }
+
+    /// The call stacks, outermost function first, with the gas charged by their innermost
+    /// function.
+    pub fn stacks(&self) -> impl Iterator<Item = (&[String], u64)> {
+        self.stacks
+            .iter()
+            .map(|(stack, gas)| (stack.as_slice(), *gas))
+    }
+
+    /// Adds the gas and calls recorded in `other` to this profile.
+    pub fn merge(&mut self, other: &GasProfile) {
+        for (stack, gas) in &other.stacks {
+            *self.stacks.entry(stack.clone()).or_insert(0) += gas;
+        }
+        for (name, calls) in &other.calls {
+            *self.calls.entry(name.clone()).or_insert(0) += calls;
+        }
+    }
+
+    /// The functions called, the most expensive one first.
+    pub fn summary(&self) -> Vec<FunctionSummary> {
+        let mut summary: BTreeMap<&str, FunctionSummary> = self
+            .calls
+            .iter()
+            .map(|(name, calls)| {
+                let function = FunctionSummary {
+                    name: name.clone(),
+                    calls: *calls,
+                    self_gas: 0,
+                    total_gas: 0,
+                };
+                (name.as_str(), function)
+            })
+            .collect();
+        for (stack, gas) in &self.stacks {
+            // Count the gas once for recursive functions.
+            let callers: BTreeSet<_> = stack.iter().map(String::as_str).collect();
+            for name in callers {
+                if let Some(function) = summary.get_mut(name) {
+                    function.total_gas += gas;
+                }
+            }
+            if let Some(function) = stack.last().and_then(|name| summary.get_mut(name.as_str())) {
+                function.self_gas += gas;
+            }
+        }
+        let mut summary: Vec<_> = summary.into_iter().map(|(_, function)| function).collect();
+        summary.sort_by(|f1, f2| {
+            f2.total_gas
+                .cmp(&f1.total_gas)
+                .then(f2.self_gas.cmp(&f1.self_gas))
+                .then(f1.name.cmp(&f2.name))
+        });
+        summary
+    }
+
+    /// Renders the profile in the folded stack format read by `flamegraph.pl` and `inferno`,
+    /// one `outer;inner gas` line per call stack.
+    pub fn to_folded(&self) -> String {
+        let mut out = String::new();
+        for (stack, gas) in self.stacks() {
+            if gas > 0 {
+                writeln!(out, "{} {}", stack.join(";"), gas).unwrap();
+            }
+        }
+        out
+    }
+
+    /// Renders the profile as a sampled profile in the speedscope file format, see
+    /// https://www.speedscope.app/file-format-schema.json.
+    pub fn to_speedscope(&self, name: &str) -> String {
+        let mut frames: BTreeMap<&str, usize> = BTreeMap::new();
+        for stack in self.stacks.keys() {
+            for function in stack {
+                let next = frames.len();
+                frames.entry(function.as_str()).or_insert(next);
+            }
+        }
+        let mut frame_names = vec![""; frames.len()];
+        for (function, idx) in &frames {
+            frame_names[*idx] = function;
+        }
+
+        let mut samples = vec![];
+        let mut weights = vec![];
+        for (stack, gas) in self.stacks() {
+            if gas > 0 {
+                let sample: Vec<_> = stack
+                    .iter()
+                    .map(|function| frames[function.as_str()].to_string())
+                    .collect();
+                samples.push(format!("[{}]", sample.join(",")));
+                weights.push(gas.to_string());
+            }
+        }
+
+        let frames: Vec<_> = frame_names
+            .iter()
+            .map(|name| format!("{{\"name\":{}}}", json_string(name)))
+            .collect();
+        format!(
+            "{{\"$schema\":\"https://www.speedscope.app/file-format-schema.json\",\
+             \"exporter\":\"move-vm-runtime\",\"name\":{name},\"activeProfileIndex\":0,\
+             \"shared\":{{\"frames\":[{frames}]}},\
+             \"profiles\":[{{\"type\":\"sampled\",\"name\":{name},\"unit\":\"none\",\
+             \"startValue\":0,\"endValue\":{total},\
+             \"samples\":[{samples}],\"weights\":[{weights}]}}]}}",
+            name = json_string(name),
+            frames = frames.join(","),
+            total = self.total_gas(),
+            samples = samples.join(","),
+            weights = weights.join(","),
+        )
+    }
+}
+
+/// Quotes and escapes `s` as a JSON string.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
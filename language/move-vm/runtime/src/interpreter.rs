@@ -26,6 +26,18 @@ use vm::{
     file_format_common::Opcodes,
 };
 
+#[cfg(feature = "debugger")]
+pub(crate) use crate::debug::DebugContext;
+#[cfg(feature = "debugger")]
+use crate::debug::{DebugState, FrameView};
+#[cfg(feature = "gas_profiler")]
+use crate::gas_profiler;
+
+/// Stands in for the debugger support when the `debugger` feature is off. It has no values, so
+/// the interpreter never holds one and the checks before each instruction compile away.
+#[cfg(not(feature = "debugger"))]
+pub(crate) enum DebugContext {}
+
 macro_rules! debug_write {
     ($($toks: tt)*) => {
         write!($($toks)*).map_err(|_|
@@ -58,6 +70,9 @@ pub(crate) struct Interpreter {
 impl Interpreter {
     /// Entrypoint into the interpreter. All external calls need to be routed through this
     /// function.
+    ///
+    /// A debugger, if given, is handed control before the first instruction and then whenever
+    /// execution stops.
     pub(crate) fn entrypoint(
         function: Arc<Function>,
         ty_args: Vec<Type>,
@@ -65,11 +80,20 @@ impl Interpreter {
         data_store: &mut dyn DataStore,
         cost_strategy: &mut CostStrategy,
         loader: &Loader,
+        debug: Option<DebugContext>,
     ) -> VMResult<()> {
         // We count the intrinsic cost of the transaction here, since that needs to also cover the
         // setup of the function.
         let mut interp = Self::new();
-        interp.execute(loader, data_store, cost_strategy, function, ty_args, args)
+        interp.execute(
+            loader,
+            data_store,
+            cost_strategy,
+            function,
+            ty_args,
+            args,
+            debug,
+        )
     }
 
     /// Create a new instance of an `Interpreter` in the context of a transaction with a
@@ -82,6 +106,7 @@ impl Interpreter {
     }
 
     /// Internal execution entry point.
+    #[cfg_attr(not(feature = "gas_profiler"), allow(clippy::let_and_return))]
     fn execute(
         &mut self,
         loader: &Loader,
@@ -90,10 +115,22 @@ impl Interpreter {
         function: Arc<Function>,
         ty_args: Vec<Type>,
         args: Vec<Value>,
+        debug: Option<DebugContext>,
     ) -> VMResult<()> {
         // No unwinding of the call stack and value stack need to be done here -- the context will
         // take care of that.
-        self.execute_main(loader, data_store, cost_strategy, function, ty_args, args)
+        let result = self.execute_main(
+            loader,
+            data_store,
+            cost_strategy,
+            function,
+            ty_args,
+            args,
+            debug,
+        );
+        #[cfg(feature = "gas_profiler")]
+        gas_profiler::unwind(cost_strategy);
+        result
     }
 
     /// Main loop for the execution of a function.
@@ -112,6 +149,7 @@ impl Interpreter {
         function: Arc<Function>,
         ty_args: Vec<Type>,
         args: Vec<Value>,
+        mut debug: Option<DebugContext>,
     ) -> VMResult<()> {
         verify_args(function.parameters(), &ty_args, &args).map_err(|e| self.set_location(e))?;
         let mut locals = Locals::new(function.local_count());
@@ -121,12 +159,14 @@ impl Interpreter {
                 .map_err(|e| self.set_location(e))?;
         }
 
+        #[cfg(feature = "gas_profiler")]
+        gas_profiler::enter(&function, cost_strategy);
         let mut current_frame = Frame::new(function, ty_args, locals);
         loop {
             let resolver = current_frame.resolver(loader);
             let exit_code =
                 current_frame //self
-                    .execute_code(&resolver, self, data_store, cost_strategy)
+                    .execute_code(&resolver, self, data_store, cost_strategy, &mut debug)
                     .map_err(|err| self.maybe_core_dump(err, &current_frame))?;
             match exit_code {
                 ExitCode::Return => {
@@ -134,6 +174,8 @@ impl Interpreter {
                         .locals
                         .check_resources_for_return()
                         .map_err(|e| self.set_location(e))?;
+                    #[cfg(feature = "gas_profiler")]
+                    gas_profiler::exit(cost_strategy);
                     if let Some(frame) = self.call_stack.pop() {
                         current_frame = frame;
                     } else {
@@ -148,8 +190,12 @@ impl Interpreter {
                         )
                         .map_err(|e| self.set_location(e))?;
                     let func = resolver.function_from_handle(fh_idx);
+                    #[cfg(feature = "gas_profiler")]
+                    gas_profiler::enter(&func, cost_strategy);
                     if func.is_native() {
                         self.call_native(&resolver, data_store, cost_strategy, func, vec![])?;
+                        #[cfg(feature = "gas_profiler")]
+                        gas_profiler::exit(cost_strategy);
                         continue;
                     }
                     let frame = self
@@ -176,8 +222,12 @@ impl Interpreter {
                         .instantiate_generic_function(idx, current_frame.ty_args())
                         .map_err(|e| self.set_location(e))?;
                     let func = resolver.function_from_instantiation(idx);
+                    #[cfg(feature = "gas_profiler")]
+                    gas_profiler::enter(&func, cost_strategy);
                     if func.is_native() {
                         self.call_native(&resolver, data_store, cost_strategy, func, ty_args)?;
+                        #[cfg(feature = "gas_profiler")]
+                        gas_profiler::exit(cost_strategy);
                         continue;
                     }
                     let frame = self
@@ -641,9 +691,14 @@ impl Frame {
         interpreter: &mut Interpreter,
         data_store: &mut dyn DataStore,
         cost_strategy: &mut CostStrategy,
+        debug: &mut Option<DebugContext>,
     ) -> VMResult<ExitCode> {
-        self.execute_code_impl(resolver, interpreter, data_store, cost_strategy)
-            .map_err(|e| e.finish(self.location()))
+        self.execute_code_impl(resolver, interpreter, data_store, cost_strategy, debug)
+            .map_err(|e| {
+                // The program counter has already moved past the failing instruction.
+                e.at_code_offset(self.function.index(), self.pc.saturating_sub(1))
+                    .finish(self.location())
+            })
     }
 
     fn execute_code_impl(
@@ -652,11 +707,15 @@ impl Frame {
         interpreter: &mut Interpreter,
         data_store: &mut dyn DataStore,
         cost_strategy: &mut CostStrategy,
+        debug: &mut Option<DebugContext>,
     ) -> PartialVMResult<ExitCode> {
         let code = self.function.code();
         loop {
             for instruction in &code[self.pc as usize..] {
                 trace!(self.function.pretty_string(), self.pc, instruction);
+                if let Some(debug) = debug.as_mut() {
+                    self.stop_for_debugger(debug, interpreter, resolver.loader(), data_store);
+                }
                 self.pc += 1;
 
                 match instruction {
@@ -1073,6 +1132,51 @@ impl Frame {
         }
     }
 
+    /// Hands control to the debugger if execution stops before the current instruction.
+    #[cfg(feature = "debugger")]
+    fn stop_for_debugger(
+        &self,
+        debug: &mut DebugContext,
+        interpreter: &Interpreter,
+        loader: &Loader,
+        data_store: &mut dyn DataStore,
+    ) {
+        let depth = interpreter.call_stack.0.len();
+        if let Some(reason) = debug.stop_reason(&self.function, self.pc, depth) {
+            let frames = interpreter
+                .call_stack
+                .0
+                .iter()
+                .chain(std::iter::once(self))
+                .map(Frame::view)
+                .collect();
+            let mut state =
+                DebugState::new(frames, &interpreter.operand_stack.0, loader, data_store);
+            debug.stop(&mut state, reason);
+        }
+    }
+
+    #[cfg(not(feature = "debugger"))]
+    fn stop_for_debugger(
+        &self,
+        debug: &mut DebugContext,
+        _interpreter: &Interpreter,
+        _loader: &Loader,
+        _data_store: &mut dyn DataStore,
+    ) {
+        match *debug {}
+    }
+
+    #[cfg(feature = "debugger")]
+    fn view(&self) -> FrameView {
+        FrameView {
+            function: &self.function,
+            pc: self.pc,
+            locals: &self.locals,
+            ty_args: &self.ty_args,
+        }
+    }
+
     fn ty_args(&self) -> &[Type] {
         &self.ty_args
     }
@@ -14,6 +14,10 @@ extern crate mirai_annotations;
 
 pub mod data_cache;
 mod data_operations;
+#[cfg(feature = "debugger")]
+pub mod debug;
+#[cfg(feature = "gas_profiler")]
+pub mod gas_profiler;
 mod interpreter;
 mod loader;
 pub mod move_vm;
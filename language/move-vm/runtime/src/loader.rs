@@ -33,7 +33,7 @@ use vm::{
     errors::{verification_error, Location, PartialVMError, PartialVMResult, VMResult},
     file_format::{
         Bytecode, CompiledModule, CompiledScript, Constant, ConstantPoolIndex, FieldHandleIndex,
-        FieldInstantiationIndex, FunctionDefinition, FunctionHandleIndex,
+        FieldInstantiationIndex, FunctionDefinition, FunctionDefinitionIndex, FunctionHandleIndex,
         FunctionInstantiationIndex, Kind, Signature, SignatureToken, StructDefInstantiationIndex,
         StructDefinition, StructDefinitionIndex, StructFieldInformation, TableIndex,
     },
@@ -178,8 +178,8 @@ impl ModuleCache {
             self.structs.truncate(starting_idx);
             Err(err.finish(Location::Undefined))
         })?;
-        for func in module.function_defs() {
-            let function = Function::new(func, module);
+        for (idx, func) in module.function_defs().iter().enumerate() {
+            let function = Function::new(FunctionDefinitionIndex(idx as TableIndex), func, module);
             self.functions.push(Arc::new(function));
         }
         Ok(())
@@ -713,6 +713,38 @@ impl Loader {
         })
     }
 
+    // Resolves a type tag against the modules loaded so far, without loading any module.
+    #[cfg(feature = "debugger")]
+    pub(crate) fn resolve_loaded_type(&self, type_tag: &TypeTag) -> PartialVMResult<Type> {
+        Ok(match type_tag {
+            TypeTag::Bool => Type::Bool,
+            TypeTag::U8 => Type::U8,
+            TypeTag::U64 => Type::U64,
+            TypeTag::U128 => Type::U128,
+            TypeTag::Address => Type::Address,
+            TypeTag::Signer => Type::Signer,
+            TypeTag::Vector(tt) => Type::Vector(Box::new(self.resolve_loaded_type(tt)?)),
+            TypeTag::Struct(struct_tag) => {
+                let module_id = ModuleId::new(struct_tag.address, struct_tag.module.clone());
+                let (idx, struct_type) = self
+                    .module_cache
+                    .lock()
+                    .unwrap()
+                    .resolve_struct_by_name(&struct_tag.name, &module_id)?;
+                if struct_type.type_parameters.is_empty() && struct_tag.type_params.is_empty() {
+                    Type::Struct(idx)
+                } else {
+                    let mut type_params = vec![];
+                    for ty_param in &struct_tag.type_params {
+                        type_params.push(self.resolve_loaded_type(ty_param)?);
+                    }
+                    self.verify_ty_args(&struct_type.type_parameters, &type_params)?;
+                    Type::StructInstantiation(idx, type_params)
+                }
+            }
+        })
+    }
+
     fn load_module(&self, id: &ModuleId, data_store: &mut impl DataStore) -> VMResult<Arc<Module>> {
         if let Some(module) = self.module_cache.lock().unwrap().module_at(id) {
             return Ok(module);
@@ -828,6 +860,10 @@ pub(crate) struct Resolver<'a> {
 }
 
 impl<'a> Resolver<'a> {
+    pub(crate) fn loader(&self) -> &'a Loader {
+        self.loader
+    }
+
     fn for_module(loader: &'a Loader, module: Arc<Module>) -> Self {
         let binary = BinaryType::Module(module);
         Self { loader, binary }
@@ -1359,6 +1395,7 @@ impl Script {
         let name = Identifier::new("main").unwrap();
         let native = None; // Script entries cannot be native
         let main: Arc<Function> = Arc::new(Function {
+            index: FunctionDefinitionIndex(0),
             code,
             parameters,
             return_,
@@ -1401,6 +1438,7 @@ enum Scope {
 // A runtime function
 #[derive(Debug)]
 pub(crate) struct Function {
+    index: FunctionDefinitionIndex,
     code: Vec<Bytecode>,
     parameters: Signature,
     return_: Signature,
@@ -1412,7 +1450,11 @@ pub(crate) struct Function {
 }
 
 impl Function {
-    fn new(def: &FunctionDefinition, module: &CompiledModule) -> Self {
+    fn new(
+        index: FunctionDefinitionIndex,
+        def: &FunctionDefinition,
+        module: &CompiledModule,
+    ) -> Self {
         let handle = module.function_handle_at(def.function);
         let name = module.identifier_at(handle.name).to_owned();
         let module_id = module.self_id();
@@ -1445,6 +1487,7 @@ impl Function {
         let return_ = module.signature_at(handle.return_).clone();
         let type_parameters = handle.type_parameters.clone();
         Self {
+            index,
             code,
             parameters,
             return_,
@@ -1476,6 +1519,10 @@ impl Function {
         }
     }
 
+    pub(crate) fn index(&self) -> FunctionDefinitionIndex {
+        self.index
+    }
+
     pub(crate) fn local_count(&self) -> usize {
         self.locals.len()
     }
@@ -1492,6 +1539,11 @@ impl Function {
         self.name.as_str()
     }
 
+    #[cfg(feature = "debugger")]
+    pub(crate) fn identifier(&self) -> &Identifier {
+        &self.name
+    }
+
     pub(crate) fn code(&self) -> &[Bytecode] {
         &self.code
     }
@@ -1642,7 +1694,7 @@ impl TypeCache {
 const VALUE_DEPTH_MAX: usize = 256;
 
 impl Loader {
-    fn type_to_fat_type(&self, ty: &Type) -> PartialVMResult<FatType> {
+    pub(crate) fn type_to_fat_type(&self, ty: &Type) -> PartialVMResult<FatType> {
         use Type::*;
 
         Ok(match ty {
@@ -3,7 +3,7 @@
 
 use crate::{
     data_cache::{RemoteCache, TransactionDataCache},
-    interpreter::Interpreter,
+    interpreter::{DebugContext, Interpreter},
     loader::Loader,
     session::Session,
 };
@@ -93,6 +93,7 @@ impl VMRuntime {
         sender: AccountAddress,
         data_store: &mut impl DataStore,
         cost_strategy: &mut CostStrategy,
+        debug: Option<DebugContext>,
     ) -> VMResult<()> {
         // signer helper closure
         fn is_signer_reference(s: &SignatureToken) -> bool {
@@ -121,6 +122,7 @@ impl VMRuntime {
             data_store,
             cost_strategy,
             &self.loader,
+            debug,
         )
     }
 
@@ -150,6 +152,7 @@ impl VMRuntime {
             data_store,
             cost_strategy,
             &self.loader,
+            None,
         )
     }
 }
@@ -1,6 +1,8 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(feature = "debugger")]
+use crate::debug::{DebugContext, Debugger};
 use crate::{
     data_cache::{RemoteCache, TransactionDataCache, TransactionEffects},
     runtime::VMRuntime,
@@ -53,6 +55,30 @@ impl<'r, 'l, R: RemoteCache> Session<'r, 'l, R> {
             sender,
             &mut self.data_cache,
             cost_strategy,
+            None,
+        )
+    }
+
+    /// Executes a script like `execute_script`, handing control to `debugger` before the first
+    /// instruction and then whenever execution stops.
+    #[cfg(feature = "debugger")]
+    pub fn execute_script_with_debugger(
+        &mut self,
+        script: Vec<u8>,
+        ty_args: Vec<TypeTag>,
+        args: Vec<Value>,
+        sender: AccountAddress,
+        cost_strategy: &mut CostStrategy,
+        debugger: &mut dyn Debugger,
+    ) -> VMResult<()> {
+        self.runtime.execute_script(
+            script,
+            ty_args,
+            args,
+            sender,
+            &mut self.data_cache,
+            cost_strategy,
+            Some(DebugContext::new(debugger)),
         )
     }
 
@@ -72,6 +72,11 @@ impl<'a> CostStrategy<'a> {
         })
     }
 
+    /// Return the gas left in internal gas units, i.e. before scaling it down to gas units.
+    pub fn remaining_internal_gas(&self) -> GasUnits<GasCarrier> {
+        self.gas_left
+    }
+
     /// Charge a given amount of gas and fail if not enough gas units are left.
     pub fn deduct_gas(&mut self, amount: GasUnits<GasCarrier>) -> PartialVMResult<()> {
         if !self.charge {
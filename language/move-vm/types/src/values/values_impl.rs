@@ -14,6 +14,7 @@ use move_core_types::{
         words_in, AbstractMemorySize, GasAlgebra, GasCarrier, GasUnits, CONST_SIZE, REFERENCE_SIZE,
         STRUCT_SIZE,
     },
+    transaction_argument::TransactionArgument,
     value::{MoveKind, MoveKindInfo, MoveStructLayout, MoveTypeLayout},
 };
 use std::{
@@ -257,12 +258,28 @@ impl ValueImpl {
 }
 
 impl Value {
-    // REVIEW: this allows primitive (including `Signer`) and vectors of them. Vectors can only
-    // be one level (a different organization of `ValueImpl` could give us that property in an
-    // efficient way). Conceptually if we serialize the value and deserialize according to
-    // the argument type we would have accomplished a proper check (reference handling aside...)
+    // REVIEW: this allows primitive (including `Signer`), vectors of them, and vectors of such
+    // vectors, checked element by element. Conceptually if we serialize the value and deserialize
+    // according to the argument type we would have accomplished a proper check (reference handling
+    // aside...)
     pub fn is_valid_arg(&self, sig: &SignatureToken) -> bool {
-        match (sig, &self.0) {
+        self.0.is_valid_arg(sig)
+    }
+
+    pub fn is_constant_or_signer_ref(&self) -> bool {
+        match &self.0 {
+            ValueImpl::ContainerRef(ContainerRef::Local(inner_ref)) => match &*inner_ref.borrow() {
+                Container::StructR(v) => v.len() == 1 && matches!(&v[0], ValueImpl::Address(_)),
+                _ => false,
+            },
+            v => v.is_constant(),
+        }
+    }
+}
+
+impl ValueImpl {
+    fn is_valid_arg(&self, sig: &SignatureToken) -> bool {
+        match (sig, self) {
             (SignatureToken::U8, ValueImpl::U8(_)) => true,
             (SignatureToken::U64, ValueImpl::U64(_)) => true,
             (SignatureToken::U128, ValueImpl::U128(_)) => true,
@@ -274,6 +291,9 @@ impl Value {
                 | (SignatureToken::U64, Container::VecU64(_))
                 | (SignatureToken::U128, Container::VecU128(_))
                 | (SignatureToken::Address, Container::VecAddress(_)) => true,
+                (SignatureToken::Vector(_), Container::VecC(v)) => {
+                    v.iter().all(|elem| elem.is_valid_arg(ty))
+                }
                 _ => false,
             },
             (
@@ -289,27 +309,24 @@ impl Value {
         }
     }
 
-    pub fn is_constant_or_signer_ref(&self) -> bool {
-        match &self.0 {
+    fn is_constant(&self) -> bool {
+        match self {
             ValueImpl::Bool(_)
             | ValueImpl::U8(_)
             | ValueImpl::U64(_)
             | ValueImpl::U128(_)
             | ValueImpl::Address(_) => true,
-            ValueImpl::Container(r) => {
-                match &*r.borrow() {
-                    Container::VecBool(_)
-                    | Container::VecU8(_)
-                    | Container::VecU64(_)
-                    | Container::VecU128(_)
-                    | Container::VecAddress(_) => true,
-                    // TODO: we need to enable vector of vectors and move Value will change
-                    // to have a knowledge of vectors vs structs
-                    _ => false,
-                }
-            }
-            ValueImpl::ContainerRef(ContainerRef::Local(inner_ref)) => match &*inner_ref.borrow() {
-                Container::StructR(v) => v.len() == 1 && matches!(&v[0], ValueImpl::Address(_)),
+            ValueImpl::Container(r) => match &*r.borrow() {
+                Container::VecBool(_)
+                | Container::VecU8(_)
+                | Container::VecU64(_)
+                | Container::VecU128(_)
+                | Container::VecAddress(_) => true,
+                // Vectors of vectors. Elements which are not vectors, e.g. structs, are rejected
+                // by the recursive call.
+                Container::VecC(v) => v
+                    .iter()
+                    .all(|elem| matches!(elem, ValueImpl::Container(_)) && elem.is_constant()),
                 _ => false,
             },
             _ => false,
@@ -1185,6 +1202,34 @@ impl Value {
         )))
     }
 
+    /// Builds a vector of vectors, e.g. a `vector<vector<u8>>` from values built with `vector_u8`.
+    pub fn vector_of_vectors(it: impl IntoIterator<Item = Value>) -> Self {
+        Self(ValueImpl::new_container(Container::VecC(
+            it.into_iter().map(|v| v.0).collect(),
+        )))
+    }
+
+    /// Converts a transaction argument into the value passed to the script. An ASCII string is
+    /// passed as its bytes.
+    pub fn from_transaction_argument(arg: &TransactionArgument) -> Self {
+        match arg {
+            TransactionArgument::U8(i) => Value::u8(*i),
+            TransactionArgument::U64(i) => Value::u64(*i),
+            TransactionArgument::U128(i) => Value::u128(*i),
+            TransactionArgument::Address(a) => Value::address(*a),
+            TransactionArgument::Bool(b) => Value::bool(*b),
+            TransactionArgument::U8Vector(v) => Value::vector_u8(v.clone()),
+            TransactionArgument::U64Vector(v) => Value::vector_u64(v.clone()),
+            TransactionArgument::U128Vector(v) => Value::vector_u128(v.clone()),
+            TransactionArgument::BoolVector(v) => Value::vector_bool(v.clone()),
+            TransactionArgument::AddressVector(v) => Value::vector_address(v.clone()),
+            TransactionArgument::Vector(v) => {
+                Value::vector_of_vectors(v.iter().map(Value::from_transaction_argument))
+            }
+            TransactionArgument::AsciiString(s) => Value::vector_u8(s.as_bytes().to_vec()),
+        }
+    }
+
     // REVIEW: This API can break
     pub fn vector_resource_for_testing_only(it: impl IntoIterator<Item = Value>) -> Self {
         Self(ValueImpl::new_container(Container::VecR(
@@ -2494,6 +2539,45 @@ pub mod debug {
         }
     }
 
+    pub fn print_local<B: Write>(
+        buf: &mut B,
+        ty: &FatType,
+        locals: &Locals,
+        idx: usize,
+    ) -> PartialVMResult<()> {
+        match &*locals.0.borrow() {
+            Container::Locals(v) => match v.get(idx) {
+                Some(val) => print_value_impl(buf, ty, val),
+                None => Err(
+                    PartialVMError::new(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR)
+                        .with_message("local index out of bounds".to_string()),
+                ),
+            },
+
+            Container::StructC(_)
+            | Container::StructR(_)
+            | Container::VecC(_)
+            | Container::VecR(_)
+            | Container::VecU8(_)
+            | Container::VecU64(_)
+            | Container::VecU128(_)
+            | Container::VecBool(_)
+            | Container::VecAddress(_) => unreachable!(),
+        }
+    }
+
+    pub fn print_global_value<B: Write>(
+        buf: &mut B,
+        ty: &FatType,
+        gv: &GlobalValue,
+    ) -> PartialVMResult<()> {
+        match ty {
+            FatType::Struct(struct_ty) => print_struct(buf, struct_ty, &*gv.container.borrow()),
+            _ => Err(PartialVMError::new(StatusCode::INTERNAL_TYPE_ERROR)
+                .with_message(format!("cannot print global value as type {:?}", ty))),
+        }
+    }
+
     pub fn print_locals<B: Write>(
         buf: &mut B,
         tys: &[FatType],
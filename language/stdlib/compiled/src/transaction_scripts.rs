@@ -60,6 +60,7 @@ pub enum StdlibScript {
     UpdateLibraVersion,
     UpdateMintingAbility,
     UpdateDualAttestationLimit,
+    UpdateConsensusSignatureScheme,
     // ...add new scripts here
 }
 
@@ -108,6 +109,7 @@ impl StdlibScript {
             UpdateLibraVersion,
             UpdateMintingAbility,
             UpdateDualAttestationLimit,
+            UpdateConsensusSignatureScheme,
             // ...add new scripts here
         ]
     }
@@ -237,6 +239,7 @@ impl fmt::Display for StdlibScript {
                 UpdateLibraVersion => "update_libra_version",
                 UpdateExchangeRate => "update_exchange_rate",
                 UpdateMintingAbility => "update_minting_ability",
+                UpdateConsensusSignatureScheme => "update_consensus_signature_scheme",
             }
         )
     }
@@ -57,6 +57,11 @@ module LibraConfig {
         *&borrow_global<LibraConfig<Config>>(addr).payload
     }
 
+    // Returns true if a `Config` value is published under the config address.
+    public fun is_published<Config: copyable>(): bool {
+        exists<LibraConfig<Config>>(CoreAddresses::LIBRA_ROOT_ADDRESS())
+    }
+
     // Set a config item to a new value with the default capability stored under config address and trigger a
     // reconfiguration.
     public fun set<Config: copyable>(account: &signer, payload: Config)
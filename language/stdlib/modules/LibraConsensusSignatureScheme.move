@@ -0,0 +1,35 @@
+address 0x1 {
+
+module LibraConsensusSignatureScheme {
+    use 0x1::CoreAddresses;
+    use 0x1::LibraConfig;
+    use 0x1::Signer;
+
+    // Selects how quorum certificates are signed. `scheme` holds the LCS bytes of the
+    // `ConsensusSignatureScheme` of the validators. Until this config is published, validators
+    // sign quorum certificates with one Ed25519 signature each.
+    struct LibraConsensusSignatureScheme {
+        scheme: vector<u8>,
+    }
+
+    const EINVALID_SINGLETON_ADDRESS: u64 = 0;
+
+    // Publishes the scheme, or changes it if it is already published. Either way a new epoch
+    // starts, in which the scheme is used.
+    public fun set(lr_account: &signer, scheme: vector<u8>) {
+        assert(Signer::address_of(lr_account) == CoreAddresses::LIBRA_ROOT_ADDRESS(), EINVALID_SINGLETON_ADDRESS);
+
+        if (!LibraConfig::is_published<LibraConsensusSignatureScheme>()) {
+            LibraConfig::publish_new_config<LibraConsensusSignatureScheme>(
+                lr_account,
+                LibraConsensusSignatureScheme { scheme: copy scheme },
+            );
+        };
+        LibraConfig::set<LibraConsensusSignatureScheme>(
+            lr_account,
+            LibraConsensusSignatureScheme { scheme },
+        );
+    }
+}
+
+}
@@ -51,10 +51,13 @@ pub fn filter_move_files(dir_iter: impl Iterator<Item = PathBuf>) -> impl Iterat
     })
 }
 
+/// The directory holding the sources of the standard library modules.
+pub fn stdlib_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(STD_LIB_DIR)
+}
+
 pub fn stdlib_files() -> Vec<String> {
-    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    path.push(STD_LIB_DIR);
-    let dirfiles = datatest_stable::utils::iterate_directory(&path);
+    let dirfiles = datatest_stable::utils::iterate_directory(&stdlib_dir());
     filter_move_files(dirfiles)
         .flat_map(|path| path.into_os_string().into_string())
         .collect()
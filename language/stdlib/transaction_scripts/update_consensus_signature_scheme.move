@@ -0,0 +1,8 @@
+script {
+use 0x1::LibraConsensusSignatureScheme;
+
+/// Update the signature scheme of consensus quorum certificates to the LCS-encoded `scheme`.
+fun update_consensus_signature_scheme(account: &signer, scheme: vector<u8>) {
+    LibraConsensusSignatureScheme::set(account, scheme)
+}
+}
@@ -1,10 +1,12 @@
 #! /bin/bash
 
-TRACE_PATH=$HOME/trace
+TRACE_DIR=$HOME/trace
 
-[ ! -e  "$TRACE_PATH" ] || rm -f "$TRACE_PATH"
+[ ! -e  "$TRACE_DIR" ] || rm -rf "$TRACE_DIR"
+mkdir -p "$TRACE_DIR"
 
-export MOVE_VM_TRACE=$TRACE_PATH
+# Each testsuite is traced to its own file, and the traces are merged when converted
+export MOVE_VM_TRACE=$TRACE_DIR/stdlib.trace
 
 echo "Rebuilding stdlib..."
 pushd ../../stdlib || exit 1
@@ -12,23 +14,30 @@ cargo run
 popd || exit 1
 
 echo "Running IR testsuite..."
+export MOVE_VM_TRACE=$TRACE_DIR/ir.trace
 pushd ../../ir-testsuite || exit 1
 cargo test
 popd || exit 1
 
 echo "Running e2e testsuite..."
+export MOVE_VM_TRACE=$TRACE_DIR/e2e.trace
 pushd ../../e2e-tests || exit 1
 cargo test -- --skip account_universe
 popd || exit 1
 
 echo "Running Move testsuite..."
+export MOVE_VM_TRACE=$TRACE_DIR/move.trace
 pushd ../../move-lang || exit 1
 cargo test
 cargo run --bin move-build -- ../stdlib/modules -m
 popd || exit 1
 
 echo "Converting trace file..."
-cargo run --bin move-trace-conversion -- -f "$TRACE_PATH" -o trace.mvcov
+TRACE_ARGS=()
+for TRACE_FILE in "$TRACE_DIR"/*.trace; do
+  TRACE_ARGS+=(-f "$TRACE_FILE")
+done
+cargo run --bin move-trace-conversion -- "${TRACE_ARGS[@]}" -o trace.mvcov
 echo "Producing coverage summaries..."
 cargo run --bin coverage-summaries -- -t trace.mvcov -s ../../stdlib/compiled/stdlib.mv -o "$1"
 cat ./baseline/coverage_report > "$2"
@@ -0,0 +1,83 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use bytecode_source_map::utils::{remap_owned_loc_to_loc, source_map_from_file, OwnedLoc};
+use move_coverage::{
+    coverage_map::CoverageMap, html::output_html, lcov::output_lcov,
+    line_coverage::ModuleLineCoverage,
+};
+use std::{fs, fs::File, path::Path};
+use structopt::StructOpt;
+use vm::{access::ModuleAccess, file_format::CompiledModule};
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "Move Coverage Report",
+    about = "Creates lcov and HTML reports of the line and branch coverage of Move modules"
+)]
+struct Args {
+    /// The path to the coverage map or trace file. Can be given several times, to merge the
+    /// coverage of several runs
+    #[structopt(long = "input-trace-path", short = "t", required = true)]
+    pub input_trace_path: Vec<String>,
+    /// Whether the passed-in files are raw trace files or serialized coverage maps
+    #[structopt(long = "is-raw-trace", short = "r")]
+    pub is_raw_trace_file: bool,
+    /// The path to a module binary. Its source map is read from the `.mvsm` file next to it, and
+    /// its source file from the source map
+    #[structopt(long = "module-path", short = "b", required = true)]
+    pub module_binary_paths: Vec<String>,
+    /// The directory that relative source paths in the source maps are resolved against
+    #[structopt(long = "source-root")]
+    pub source_root: Option<String>,
+    /// Path of the lcov tracefile to write
+    #[structopt(long = "lcov")]
+    pub lcov_path: Option<String>,
+    /// Directory to write the HTML report to
+    #[structopt(long = "html")]
+    pub html_dir: Option<String>,
+}
+
+fn main() {
+    let args = Args::from_args();
+    let source_map_extension = "mvsm";
+    if args.lcov_path.is_none() && args.html_dir.is_none() {
+        panic!("No report requested, pass --lcov or --html")
+    }
+    let coverage_map = CoverageMap::from_files(&args.input_trace_path, args.is_raw_trace_file);
+    let source_root = args.source_root.as_ref().map(Path::new);
+
+    let modules: Vec<_> = args
+        .module_binary_paths
+        .iter()
+        .filter_map(|module_binary_path| {
+            let bytecode_bytes =
+                fs::read(module_binary_path).expect("Unable to read bytecode file");
+            let compiled_module = CompiledModule::deserialize(&bytecode_bytes)
+                .expect("Module blob can't be deserialized");
+            // Modules without functions have no code to cover
+            if compiled_module.function_defs().is_empty() {
+                return None;
+            }
+            let source_map = source_map_from_file::<OwnedLoc>(
+                &Path::new(module_binary_path).with_extension(source_map_extension),
+            )
+            .map(remap_owned_loc_to_loc)
+            .unwrap();
+            Some(
+                ModuleLineCoverage::new(&compiled_module, &coverage_map, &source_map, source_root)
+                    .unwrap(),
+            )
+        })
+        .collect();
+
+    if let Some(lcov_path) = &args.lcov_path {
+        let mut lcov_file = File::create(lcov_path).unwrap();
+        output_lcov(&modules, &mut lcov_file).unwrap();
+    }
+    if let Some(html_dir) = &args.html_dir {
+        output_html(&modules, Path::new(html_dir)).unwrap();
+    }
+}
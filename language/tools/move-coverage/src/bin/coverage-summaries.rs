@@ -5,12 +5,13 @@
 
 use move_coverage::{
     coverage_map::CoverageMap,
-    summary::{self, ModuleSummary, ModuleSummaryOptions},
+    summary::{CoverageCounts, ModuleSummary, ModuleSummaryOptions},
 };
 use std::{
     fs::{self, File},
     io::{self, Write},
     path::Path,
+    process,
 };
 use structopt::StructOpt;
 use vm::file_format::CompiledModule;
@@ -21,9 +22,10 @@ use vm::file_format::CompiledModule;
     about = "Creates a coverage summary from the trace data collected from the Move VM"
 )]
 struct Args {
-    /// The path to the coverage map or trace file
-    #[structopt(long = "input-trace-path", short = "t")]
-    pub input_trace_path: String,
+    /// The path to the coverage map or trace file. Can be given several times, to merge the
+    /// coverage of several runs
+    #[structopt(long = "input-trace-path", short = "t", required = true)]
+    pub input_trace_path: Vec<String>,
     /// Whether the passed-in file is a raw trace file or a serialized coverage map
     #[structopt(long = "is-raw-trace", short = "r")]
     pub is_raw_trace_file: bool,
@@ -42,6 +44,12 @@ struct Args {
     /// Output CSV data of coverage
     #[structopt(long = "csv", short = "c")]
     pub csv_output: bool,
+    /// Exit with an error if the total instruction coverage, in percent, is below this threshold
+    #[structopt(long = "min-coverage")]
+    pub min_coverage: Option<f64>,
+    /// Exit with an error if the total branch coverage, in percent, is below this threshold
+    #[structopt(long = "min-branch-coverage")]
+    pub min_branch_coverage: Option<f64>,
 }
 
 fn get_modules(args: &Args) -> Vec<CompiledModule> {
@@ -71,55 +79,68 @@ fn get_modules(args: &Args) -> Vec<CompiledModule> {
     modules
 }
 
-fn format_human_summary<W: Write>(args: &Args, coverage_map: &CoverageMap, summary_writer: &mut W) {
+fn format_human_summary<W: Write>(
+    args: &Args,
+    coverage_map: &CoverageMap,
+    summary_writer: &mut W,
+) -> CoverageCounts {
     writeln!(summary_writer, "+-------------------------+").unwrap();
     writeln!(summary_writer, "| Move Coverage Summary   |").unwrap();
     writeln!(summary_writer, "+-------------------------+").unwrap();
 
-    let mut total_covered = 0;
-    let mut total_instructions = 0;
+    let mut total = CoverageCounts::default();
 
     for module in get_modules(&args).iter() {
         let mut summary_options = ModuleSummaryOptions::default();
         summary_options.summarize_function_coverage = args.summarize_functions;
-        let (total, covered) = ModuleSummary::new(summary_options, &module, coverage_map)
+        let counts = ModuleSummary::new(summary_options, &module, coverage_map)
             .summarize_human(summary_writer)
             .unwrap();
-        total_covered += covered;
-        total_instructions += total;
+        total.add(&counts);
     }
 
     writeln!(summary_writer, "+-------------------------+").unwrap();
     writeln!(
         summary_writer,
         "| % Move Coverage: {:.2}  |",
-        summary::percent_coverage_for_counts(total_instructions, total_covered)
+        total.percent_coverage()
+    )
+    .unwrap();
+    writeln!(
+        summary_writer,
+        "| % Branch Coverage: {:.2}|",
+        total.percent_branch_coverage()
     )
     .unwrap();
     writeln!(summary_writer, "+-------------------------+").unwrap();
+    total
 }
 
-fn format_csv_summary<W: Write>(args: &Args, coverage_map: &CoverageMap, summary_writer: &mut W) {
-    writeln!(summary_writer, "ModuleName,FunctionName,Covered,Uncovered").unwrap();
+fn format_csv_summary<W: Write>(
+    args: &Args,
+    coverage_map: &CoverageMap,
+    summary_writer: &mut W,
+) -> CoverageCounts {
+    writeln!(
+        summary_writer,
+        "ModuleName,FunctionName,Covered,Uncovered,CoveredBranches,Branches"
+    )
+    .unwrap();
 
+    let mut total = CoverageCounts::default();
     for module in get_modules(&args).iter() {
         let mut summary_options = ModuleSummaryOptions::default();
         summary_options.summarize_function_coverage = true;
-        ModuleSummary::new(summary_options, &module, coverage_map)
-            .summarize_csv(summary_writer)
-            .unwrap();
+        let summary = ModuleSummary::new(summary_options, &module, coverage_map);
+        summary.summarize_csv(summary_writer).unwrap();
+        total.add(&summary.counts());
     }
+    total
 }
 
 fn main() {
     let args = Args::from_args();
-    let input_trace_path = Path::new(&args.input_trace_path);
-
-    let coverage_map = if args.is_raw_trace_file {
-        CoverageMap::from_trace_file(&input_trace_path)
-    } else {
-        CoverageMap::from_binary_file(&input_trace_path)
-    };
+    let coverage_map = CoverageMap::from_files(&args.input_trace_path, args.is_raw_trace_file);
 
     let mut summary_writer: Box<dyn Write> = match &args.summary_path {
         Some(x) => {
@@ -129,9 +150,18 @@ fn main() {
         None => Box::new(io::stdout()),
     };
 
-    if !args.csv_output {
+    let total = if !args.csv_output {
         format_human_summary(&args, &coverage_map, &mut summary_writer)
     } else {
         format_csv_summary(&args, &coverage_map, &mut summary_writer)
+    };
+    summary_writer.flush().unwrap();
+
+    let failures = total.check_thresholds(args.min_coverage, args.min_branch_coverage);
+    for failure in &failures {
+        eprintln!("{}", failure);
+    }
+    if !failures.is_empty() {
+        process::exit(1)
     }
 }
@@ -13,28 +13,23 @@ use structopt::StructOpt;
     about = "Creates a coverage map from the raw data collected from the Move VM"
 )]
 struct Args {
-    /// The path to the input file
-    #[structopt(long = "input-file-path", short = "f")]
-    pub input_file_path: String,
+    /// The path to the input trace file. Can be given several times, e.g. once per test suite
+    #[structopt(long = "input-file-path", short = "f", required = true)]
+    pub input_file_path: Vec<String>,
     /// The path to the output file location
     #[structopt(long = "output-file-path", short = "o")]
     pub output_file_path: String,
-    /// Add traces from `input_file_path` to an existing coverage map at `update_coverage_map`
+    /// Add traces from `input_file_path` to an existing coverage map at `update_coverage_map`.
+    /// Can be given several times, in which case the existing coverage maps are merged
     #[structopt(long = "update-coverage-map", short = "u")]
-    pub update_coverage_map: Option<String>,
+    pub update_coverage_map: Vec<String>,
 }
 
 fn main() {
     let args = Args::from_args();
-    let input_path = Path::new(&args.input_file_path);
     let output_path = Path::new(&args.output_file_path);
-    let coverage_map = if let Some(old_coverage_path) = &args.update_coverage_map {
-        let path = Path::new(&old_coverage_path);
-        let old_coverage_map = CoverageMap::from_binary_file(&path);
-        old_coverage_map.update_coverage_from_trace_file(&input_path)
-    } else {
-        CoverageMap::from_trace_file(&input_path)
-    };
+    let mut coverage_map = CoverageMap::from_files(&args.update_coverage_map, false);
+    coverage_map.merge(CoverageMap::from_files(&args.input_file_path, true));
 
     output_map_to_file(&output_path, &coverage_map)
         .expect("Unable to serialize coverage map to output file")
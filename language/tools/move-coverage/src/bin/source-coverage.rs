@@ -20,9 +20,10 @@ use vm::file_format::CompiledModule;
     about = "Annotate Move Source Code with Coverage Information"
 )]
 struct Args {
-    /// The path to the coverage map or trace file
-    #[structopt(long = "input-trace-path", short = "t")]
-    pub input_trace_path: String,
+    /// The path to the coverage map or trace file. Can be given several times, to merge the
+    /// coverage of several runs
+    #[structopt(long = "input-trace-path", short = "t", required = true)]
+    pub input_trace_path: Vec<String>,
     /// Whether the passed-in file is a raw trace file or a serialized coverage map
     #[structopt(long = "is-raw-trace", short = "r")]
     pub is_raw_trace_file: bool,
@@ -40,11 +41,7 @@ struct Args {
 fn main() {
     let args = Args::from_args();
     let source_map_extension = "mvsm";
-    let coverage_map = if args.is_raw_trace_file {
-        CoverageMap::from_trace_file(&args.input_trace_path)
-    } else {
-        CoverageMap::from_binary_file(&args.input_trace_path)
-    };
+    let coverage_map = CoverageMap::from_files(&args.input_trace_path, args.is_raw_trace_file);
 
     let bytecode_bytes = fs::read(&args.module_binary_path).expect("Unable to read bytecode file");
     let compiled_module =
@@ -16,6 +16,10 @@ use std::{
 
 pub type FunctionCoverage = BTreeMap<u64, u64>;
 
+/// The number of times each edge of a conditional branch was taken, keyed by the offset of the
+/// branch instruction and the offset executed after it.
+pub type BranchCoverage = BTreeMap<(u64, u64), u64>;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CoverageMap {
     pub module_maps: BTreeMap<(AccountAddress, Identifier), ModuleCoverageMap>,
@@ -26,17 +30,43 @@ pub struct ModuleCoverageMap {
     pub module_addr: AccountAddress,
     pub module_name: Identifier,
     pub function_maps: BTreeMap<Identifier, FunctionCoverage>,
+    /// Missing from coverage maps written before branch coverage was recorded, see
+    /// `CoverageMap::from_bytes`.
+    #[serde(default)]
+    pub branch_maps: BTreeMap<Identifier, BranchCoverage>,
+}
+
+/// The layout of coverage maps written before branch coverage was recorded. LCS does not encode
+/// field names, so these maps cannot be read as a `CoverageMap` despite the `serde(default)`.
+#[derive(Deserialize)]
+struct LegacyCoverageMap {
+    module_maps: BTreeMap<(AccountAddress, Identifier), LegacyModuleCoverageMap>,
+}
+
+#[derive(Deserialize)]
+struct LegacyModuleCoverageMap {
+    module_addr: AccountAddress,
+    module_name: Identifier,
+    function_maps: BTreeMap<Identifier, FunctionCoverage>,
 }
 
 impl CoverageMap {
     /// Takes in a file containing a raw VM trace, and returns an updated coverage map.
-    pub fn update_coverage_from_trace_file<P: AsRef<Path>>(mut self, filename: P) -> Self {
+    pub fn update_coverage_from_trace_file<P: AsRef<Path>>(self, filename: P) -> Self {
         let file = File::open(filename).unwrap();
-        for line in BufReader::new(file).lines() {
+        self.update_coverage_from_trace(BufReader::new(file))
+    }
+
+    fn update_coverage_from_trace<R: BufRead>(mut self, trace: R) -> Self {
+        // The context, offset and target of the conditional branch on the previous line, if any
+        let mut last_branch: Option<(String, u64, u64)> = None;
+        for line in trace.lines() {
             let line = line.unwrap();
-            let mut splits = line.split(',');
+            let mut splits = line.splitn(3, ',');
             let context = splits.next().unwrap();
             let pc = splits.next().unwrap().parse::<u64>().unwrap();
+            let branch_target = splits.next().and_then(parse_branch_target);
+            let branch = last_branch.take();
 
             let mut context_segs: Vec<_> = context.split("::").collect();
             let is_script = context_segs.len() == 2;
@@ -49,7 +79,17 @@ impl CoverageMap {
                     .module_maps
                     .entry((addr, module_name.clone()))
                     .or_insert_with(|| ModuleCoverageMap::new(addr, module_name));
-                entry.insert(func_name, pc);
+                entry.insert(func_name.clone(), pc);
+                // A conditional branch is always followed by an instruction of the same function.
+                // Checking the offsets guards against traces interleaved by concurrent runs.
+                if let Some((branch_context, branch_pc, target)) = branch {
+                    if branch_context == context && (pc == branch_pc + 1 || pc == target) {
+                        entry.insert_branch(func_name, branch_pc, pc);
+                    }
+                }
+                if let Some(target) = branch_target {
+                    last_branch = Some((context.to_string(), pc, target));
+                }
             }
         }
         self
@@ -71,10 +111,61 @@ impl CoverageMap {
             .and_then(|mut file| file.read_to_end(&mut bytes).ok())
             .ok_or_else(|| format_err!("Error while reading in coverage map binary"))
             .unwrap();
-        lcs::from_bytes(&bytes)
+        Self::from_bytes(&bytes)
             .map_err(|_| format_err!("Error deserializing into coverage map"))
             .unwrap()
     }
+
+    /// Deserializes a coverage map, which may have been written before branch coverage was
+    /// recorded.
+    fn from_bytes(bytes: &[u8]) -> lcs::Result<Self> {
+        lcs::from_bytes(bytes).or_else(|err| {
+            let legacy: LegacyCoverageMap = lcs::from_bytes(bytes).map_err(|_| err)?;
+            let module_maps = legacy
+                .module_maps
+                .into_iter()
+                .map(|(key, module_map)| {
+                    let module_map = ModuleCoverageMap {
+                        module_addr: module_map.module_addr,
+                        module_name: module_map.module_name,
+                        function_maps: module_map.function_maps,
+                        branch_maps: BTreeMap::new(),
+                    };
+                    (key, module_map)
+                })
+                .collect();
+            Ok(CoverageMap { module_maps })
+        })
+    }
+
+    /// Takes in files containing either raw VM traces or serialized coverage maps, e.g. one per test
+    /// suite, and returns their merged coverage map.
+    pub fn from_files<P: AsRef<Path>>(filenames: &[P], is_raw_trace: bool) -> Self {
+        let mut coverage_map = CoverageMap {
+            module_maps: BTreeMap::new(),
+        };
+        for filename in filenames {
+            if is_raw_trace {
+                coverage_map = coverage_map.update_coverage_from_trace_file(filename);
+            } else {
+                coverage_map.merge(CoverageMap::from_binary_file(filename));
+            }
+        }
+        coverage_map
+    }
+
+    /// Adds the counts of `other` to this coverage map, e.g. to combine the coverage of several
+    /// test suites.
+    pub fn merge(&mut self, other: CoverageMap) {
+        for (key, other_module_map) in other.module_maps {
+            match self.module_maps.get_mut(&key) {
+                Some(module_map) => module_map.merge(other_module_map),
+                None => {
+                    self.module_maps.insert(key, other_module_map);
+                }
+            }
+        }
+    }
 }
 
 impl ModuleCoverageMap {
@@ -83,6 +174,7 @@ impl ModuleCoverageMap {
             module_addr,
             module_name,
             function_maps: BTreeMap::new(),
+            branch_maps: BTreeMap::new(),
         }
     }
 
@@ -95,9 +187,55 @@ impl ModuleCoverageMap {
         *pc_entry += 1;
     }
 
+    pub fn insert_branch(&mut self, func_name: Identifier, branch_pc: u64, next_pc: u64) {
+        let func_entry = self
+            .branch_maps
+            .entry(func_name)
+            .or_insert_with(BranchCoverage::new);
+        let edge_entry = func_entry.entry((branch_pc, next_pc)).or_insert(0);
+        *edge_entry += 1;
+    }
+
+    pub fn merge(&mut self, other: ModuleCoverageMap) {
+        for (func_name, other_coverage) in other.function_maps {
+            let func_entry = self
+                .function_maps
+                .entry(func_name)
+                .or_insert_with(FunctionCoverage::new);
+            for (pc, count) in other_coverage {
+                *func_entry.entry(pc).or_insert(0) += count;
+            }
+        }
+        for (func_name, other_coverage) in other.branch_maps {
+            let func_entry = self
+                .branch_maps
+                .entry(func_name)
+                .or_insert_with(BranchCoverage::new);
+            for (edge, count) in other_coverage {
+                *func_entry.entry(edge).or_insert(0) += count;
+            }
+        }
+    }
+
     pub fn get_function_coverage(&self, func_name: &IdentStr) -> Option<&FunctionCoverage> {
         self.function_maps.get(func_name)
     }
+
+    pub fn get_branch_coverage(&self, func_name: &IdentStr) -> Option<&BranchCoverage> {
+        self.branch_maps.get(func_name)
+    }
+}
+
+/// Returns the target of a `BrTrue` or `BrFalse` instruction, as printed in a trace.
+fn parse_branch_target(instr: &str) -> Option<u64> {
+    let target = if instr.starts_with("BrTrue(") {
+        &instr["BrTrue(".len()..]
+    } else if instr.starts_with("BrFalse(") {
+        &instr["BrFalse(".len()..]
+    } else {
+        return None;
+    };
+    target.trim_end_matches(')').parse().ok()
 }
 
 pub fn output_map_to_file<M: Serialize, P: AsRef<Path>>(file_name: P, data: &M) -> Result<()> {
@@ -106,3 +244,112 @@ pub fn output_map_to_file<M: Serialize, P: AsRef<Path>>(file_name: P, data: &M)
     file.write_all(&bytes)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ident(name: &str) -> Identifier {
+        Identifier::new(name).unwrap()
+    }
+
+    fn get_module_map<'a>(coverage_map: &'a CoverageMap, name: &str) -> &'a ModuleCoverageMap {
+        let addr = AccountAddress::from_hex_literal("0x1").unwrap();
+        &coverage_map.module_maps[&(addr, ident(name))]
+    }
+
+    fn from_trace(trace: &str) -> CoverageMap {
+        CoverageMap {
+            module_maps: BTreeMap::new(),
+        }
+        .update_coverage_from_trace(trace.as_bytes())
+    }
+
+    #[test]
+    fn test_branch_attribution() {
+        let coverage_map = from_trace(
+            "0x1::M::f,0,CopyLoc(0)
+0x1::M::f,1,BrTrue(4)
+0x1::M::f,4,LdU64(1)
+0x1::M::f,5,BrFalse(7)
+0x1::M::f,6,Ret
+Script::main,0,Call(0)
+0x1::M::f,1,BrTrue(4)
+0x1::M::f,2,LdU64(0)
+0x1::M::f,5,BrFalse(7)
+0x1::M::g,0,Ret
+",
+        );
+        let module_map = get_module_map(&coverage_map, "M");
+        let function_map = module_map.get_function_coverage(&ident("f")).unwrap();
+        assert_eq!(function_map[&1], 2);
+        assert_eq!(function_map[&5], 2);
+        // The second `BrFalse` is followed by an instruction of another function, so none of its
+        // edges is taken.
+        let expected: BranchCoverage = vec![((1, 4), 1), ((1, 2), 1), ((5, 6), 1)]
+            .into_iter()
+            .collect();
+        assert_eq!(module_map.get_branch_coverage(&ident("f")), Some(&expected));
+        assert_eq!(module_map.get_branch_coverage(&ident("g")), None);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut coverage_map = from_trace(
+            "0x1::M::f,0,BrTrue(2)
+0x1::M::f,1,Ret
+0x1::N::g,0,Ret
+",
+        );
+        coverage_map.merge(from_trace(
+            "0x1::M::f,0,BrTrue(2)
+0x1::M::f,2,Ret
+0x1::M::f,0,BrTrue(2)
+0x1::M::f,1,Ret
+",
+        ));
+        let module_map = get_module_map(&coverage_map, "M");
+        let expected: FunctionCoverage = vec![(0, 3), (1, 2), (2, 1)].into_iter().collect();
+        assert_eq!(
+            module_map.get_function_coverage(&ident("f")),
+            Some(&expected)
+        );
+        let expected: BranchCoverage = vec![((0, 1), 2), ((0, 2), 1)].into_iter().collect();
+        assert_eq!(module_map.get_branch_coverage(&ident("f")), Some(&expected));
+        assert_eq!(coverage_map.module_maps.len(), 2);
+    }
+
+    #[test]
+    fn test_from_legacy_bytes() {
+        let coverage_map = from_trace("0x1::M::f,0,BrTrue(2)\n0x1::M::f,1,Ret\n");
+        let bytes = lcs::to_bytes(&coverage_map).unwrap();
+        let deserialized = CoverageMap::from_bytes(&bytes).unwrap();
+        let module_map = get_module_map(&deserialized, "M");
+        assert_eq!(
+            module_map.branch_maps,
+            get_module_map(&coverage_map, "M").branch_maps
+        );
+
+        // A coverage map written before branch coverage was recorded.
+        let legacy: BTreeMap<_, _> = coverage_map
+            .module_maps
+            .iter()
+            .map(|(key, module_map)| {
+                let legacy_module_map = (
+                    module_map.module_addr,
+                    module_map.module_name.clone(),
+                    module_map.function_maps.clone(),
+                );
+                (key.clone(), legacy_module_map)
+            })
+            .collect();
+        let bytes = lcs::to_bytes(&legacy).unwrap();
+        let legacy_map = CoverageMap::from_bytes(&bytes).unwrap();
+        let module_map = get_module_map(&legacy_map, "M");
+        assert_eq!(
+            module_map.get_function_coverage(&ident("f")).unwrap().len(),
+            2
+        );
+        assert!(module_map.branch_maps.is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use crate::{
+    line_coverage::ModuleLineCoverage,
+    summary::{percent_coverage_for_counts, CoverageCounts},
+};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+};
+
+const STYLE: &str = "
+body { font-family: sans-serif; }
+table { border-collapse: collapse; }
+td, th { padding: 0 8px; text-align: left; }
+pre { margin: 0; }
+.covered { background-color: #d4f4d4; }
+.uncovered { background-color: #f8d0d0; }
+.partial { background-color: #f8f0c0; }
+.count { color: #808080; text-align: right; }
+";
+
+/// Writes an HTML coverage report to `output_dir`: an `index.html` with the percentages of each
+/// module, and one page per module with its annotated source.
+pub fn output_html(modules: &[ModuleLineCoverage], output_dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(output_dir)?;
+    output_index(modules, &mut File::create(output_dir.join("index.html"))?)?;
+    for module in modules {
+        let page = output_dir.join(module_page_name(module));
+        output_module_page(module, &mut File::create(page)?)?;
+    }
+    Ok(())
+}
+
+fn output_index<W: Write>(modules: &[ModuleLineCoverage], index: &mut W) -> io::Result<()> {
+    header(index, "Move Coverage")?;
+    writeln!(
+        index,
+        "<table><tr><th>Module</th><th>Instructions</th><th>Branches</th>\
+         <th>Functions</th><th>Lines</th></tr>"
+    )?;
+    let mut total = CoverageCounts::default();
+    for module in modules {
+        let page = module_page_name(module);
+        writeln!(
+            index,
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            page,
+            escape(&module.module_name.to_string()),
+            ratio(
+                module.counts.covered_instructions,
+                module.counts.total_instructions
+            ),
+            ratio(module.counts.covered_branches, module.counts.total_branches),
+            ratio(module.functions_hit() as u64, module.functions.len() as u64),
+            ratio(module.lines_hit() as u64, module.lines.len() as u64),
+        )?;
+        total.add(&module.counts);
+    }
+    writeln!(
+        index,
+        "<tr><th>Total</th><th>{}</th><th>{}</th><th></th><th></th></tr></table>",
+        ratio(total.covered_instructions, total.total_instructions),
+        ratio(total.covered_branches, total.total_branches),
+    )?;
+    footer(index)
+}
+
+fn output_module_page<W: Write>(module: &ModuleLineCoverage, page: &mut W) -> io::Result<()> {
+    let title = module.module_name.to_string();
+    header(page, &title)?;
+    writeln!(
+        page,
+        "<p>{}</p><p>Instructions: {}, branches: {}</p>",
+        escape(&module.source_path.display().to_string()),
+        ratio(
+            module.counts.covered_instructions,
+            module.counts.total_instructions
+        ),
+        ratio(module.counts.covered_branches, module.counts.total_branches),
+    )?;
+
+    // The branch edges of each line which were never taken
+    let mut untaken_edges: BTreeMap<u32, usize> = BTreeMap::new();
+    for branch in &module.branches {
+        let untaken = branch.edges.iter().filter(|(_, taken)| *taken == 0).count();
+        *untaken_edges.entry(branch.line).or_insert(0) += untaken;
+    }
+
+    writeln!(page, "<table>")?;
+    for (idx, text) in module.source.lines().enumerate() {
+        let line = idx as u32 + 1;
+        let (class, count) = match module.lines.get(&line).copied() {
+            None => ("", String::new()),
+            Some(0) => ("uncovered", "0".to_string()),
+            Some(count) if untaken_edges.get(&line).map_or(false, |n| *n > 0) => {
+                ("partial", count.to_string())
+            }
+            Some(count) => ("covered", count.to_string()),
+        };
+        writeln!(
+            page,
+            "<tr class=\"{}\"><td class=\"count\">{}</td><td class=\"count\">{}</td>\
+             <td><pre>{}</pre></td></tr>",
+            class,
+            line,
+            count,
+            escape(text)
+        )?;
+    }
+    writeln!(page, "</table>")?;
+    footer(page)
+}
+
+fn module_page_name(module: &ModuleLineCoverage) -> String {
+    format!(
+        "{}_{}.html",
+        module.module_name.address(),
+        module.module_name.name()
+    )
+}
+
+fn header<W: Write>(page: &mut W, title: &str) -> io::Result<()> {
+    writeln!(
+        page,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{0}</title>\
+         <style>{1}</style></head><body><h1>{0}</h1>",
+        escape(title),
+        STYLE
+    )
+}
+
+fn footer<W: Write>(page: &mut W) -> io::Result<()> {
+    writeln!(page, "</body></html>")
+}
+
+fn ratio(covered: u64, total: u64) -> String {
+    if total == 0 {
+        return "-".to_string();
+    }
+    format!(
+        "{} / {} ({:.2}%)",
+        covered,
+        total,
+        percent_coverage_for_counts(total, covered)
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(output: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> String {
+        let mut page = vec![];
+        output(&mut page).unwrap();
+        String::from_utf8(page).unwrap()
+    }
+
+    #[test]
+    fn test_output_index() {
+        let module = ModuleLineCoverage::for_tests();
+        let index = render(|page| output_index(&[module], page));
+        let module = ModuleLineCoverage::for_tests();
+        let row = format!(
+            "<tr><td><a href=\"{}\">{}</a></td><td>5 / 8 (62.50%)</td><td>1 / 4 (25.00%)</td>\
+             <td>1 / 2 (50.00%)</td><td>3 / 4 (75.00%)</td></tr>",
+            module_page_name(&module),
+            module.module_name
+        );
+        assert!(index.contains(&row), "{}", index);
+        assert!(
+            index.contains("<tr><th>Total</th><th>5 / 8 (62.50%)</th><th>1 / 4 (25.00%)</th>"),
+            "{}",
+            index
+        );
+        let empty = render(|page| output_index(&[], page));
+        assert!(
+            empty.contains("<tr><th>Total</th><th>-</th><th>-</th>"),
+            "{}",
+            empty
+        );
+    }
+
+    #[test]
+    fn test_output_module_page() {
+        let module = ModuleLineCoverage::for_tests();
+        let page = render(|page| output_module_page(&module, page));
+        let line = |class: &str, line: u32, count: &str| {
+            format!(
+                "<tr class=\"{}\"><td class=\"count\">{}</td><td class=\"count\">{}</td>",
+                class, line, count
+            )
+        };
+        // Lines without instructions are not annotated, and lines with a branch edge never taken
+        // are partially covered.
+        assert!(page.contains(&line("", 1, "")), "{}", page);
+        assert!(page.contains(&line("covered", 2, "3")), "{}", page);
+        assert!(page.contains(&line("partial", 3, "3")), "{}", page);
+        assert!(page.contains(&line("uncovered", 5, "0")), "{}", page);
+        assert!(page.contains("module M { // &lt;M&gt;"), "{}", page);
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use crate::line_coverage::ModuleLineCoverage;
+use std::io::{self, Write};
+
+/// Writes the coverage of the modules in the lcov tracefile format, with one record per module.
+/// Branches are numbered by the offset of their instruction within the function.
+pub fn output_lcov<W: Write>(
+    modules: &[ModuleLineCoverage],
+    output_writer: &mut W,
+) -> io::Result<()> {
+    for module in modules {
+        writeln!(output_writer, "TN:")?;
+        writeln!(output_writer, "SF:{}", module.source_path.display())?;
+
+        for function in &module.functions {
+            writeln!(output_writer, "FN:{},{}", function.decl_line, function.name)?;
+        }
+        for function in &module.functions {
+            writeln!(
+                output_writer,
+                "FNDA:{},{}",
+                function.execution_count, function.name
+            )?;
+        }
+        writeln!(output_writer, "FNF:{}", module.functions.len())?;
+        writeln!(output_writer, "FNH:{}", module.functions_hit())?;
+
+        for branch in &module.branches {
+            for (idx, (_, taken)) in branch.edges.iter().enumerate() {
+                let taken = if branch.executed {
+                    taken.to_string()
+                } else {
+                    "-".to_string()
+                };
+                writeln!(
+                    output_writer,
+                    "BRDA:{},{},{},{}",
+                    branch.line, branch.branch_pc, idx, taken
+                )?;
+            }
+        }
+        writeln!(output_writer, "BRF:{}", module.counts.total_branches)?;
+        writeln!(output_writer, "BRH:{}", module.counts.covered_branches)?;
+
+        for (line, count) in &module.lines {
+            writeln!(output_writer, "DA:{},{}", line, count)?;
+        }
+        writeln!(output_writer, "LF:{}", module.lines.len())?;
+        writeln!(output_writer, "LH:{}", module.lines_hit())?;
+        writeln!(output_writer, "end_of_record")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_lcov() {
+        let mut output = vec![];
+        output_lcov(&[ModuleLineCoverage::for_tests()], &mut output).unwrap();
+        let expected = "TN:
+SF:M.move
+FN:2,f
+FN:5,g
+FNDA:3,f
+FNDA:0,g
+FNF:2
+FNH:1
+BRDA:3,1,0,0
+BRDA:3,1,1,3
+BRDA:3,5,0,-
+BRDA:3,5,1,-
+BRF:4
+BRH:1
+DA:2,3
+DA:3,3
+DA:4,3
+DA:5,0
+LF:4
+LH:3
+end_of_record
+";
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+}
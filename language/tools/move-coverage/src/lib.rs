@@ -2,5 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod coverage_map;
+pub mod html;
+pub mod lcov;
+pub mod line_coverage;
 pub mod source_coverage;
 pub mod summary;
@@ -0,0 +1,242 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use crate::{
+    coverage_map::CoverageMap,
+    summary::{branch_edges, CoverageCounts},
+};
+use anyhow::{format_err, Result};
+use bytecode_source_map::source_map::SourceMap;
+use codespan::{ByteIndex, FileId, Files};
+use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+use move_ir_types::location::Loc;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use vm::{
+    access::ModuleAccess,
+    file_format::{CodeOffset, FunctionDefinitionIndex},
+    CompiledModule,
+};
+
+/// The coverage of a module, mapped onto the lines of its source file. Line numbers start at 1.
+#[derive(Debug)]
+pub struct ModuleLineCoverage {
+    pub module_name: ModuleId,
+    pub source_path: PathBuf,
+    pub source: String,
+    pub functions: Vec<FunctionLineCoverage>,
+    /// The execution count of each line holding at least one instruction. A line counts as
+    /// executed as soon as one of its instructions is.
+    pub lines: BTreeMap<u32, u64>,
+    pub branches: Vec<BranchLineCoverage>,
+    pub counts: CoverageCounts,
+}
+
+#[derive(Debug)]
+pub struct FunctionLineCoverage {
+    pub name: Identifier,
+    pub decl_line: u32,
+    /// The number of times the function was entered
+    pub execution_count: u64,
+}
+
+/// The edges out of one conditional branch, with the number of times each was taken.
+#[derive(Debug)]
+pub struct BranchLineCoverage {
+    pub line: u32,
+    pub branch_pc: u64,
+    /// Whether the branch instruction itself was executed
+    pub executed: bool,
+    pub edges: Vec<(u64, u64)>,
+}
+
+impl ModuleLineCoverage {
+    /// Computes the line coverage of `module`. The source file is the one recorded in the source
+    /// map; a relative path is resolved against `source_root` when given.
+    pub fn new(
+        module: &CompiledModule,
+        coverage_map: &CoverageMap,
+        source_map: &SourceMap<Loc>,
+        source_root: Option<&Path>,
+    ) -> Result<Self> {
+        let module_name = module.self_id();
+        let module_map = coverage_map
+            .module_maps
+            .get(&(*module_name.address(), module_name.name().to_owned()));
+
+        let recorded_path = PathBuf::from(source_file(module, source_map)?);
+        let source_path = match source_root {
+            Some(root) if recorded_path.is_relative() => root.join(recorded_path),
+            _ => recorded_path,
+        };
+        let source = fs::read_to_string(&source_path).map_err(|err| {
+            format_err!(
+                "Unable to read source file {}: {}",
+                source_path.display(),
+                err
+            )
+        })?;
+        let mut files = Files::new();
+        let file_id = files.add(source_path.as_os_str().to_os_string(), source.clone());
+        let line_of = |loc: Loc| line_number(&files, file_id, loc.span().start());
+
+        let mut functions = vec![];
+        let mut lines = BTreeMap::new();
+        let mut branches = vec![];
+        let mut counts = CoverageCounts::default();
+        for (function_def_idx, function_def) in module.function_defs().iter().enumerate() {
+            let code_unit = match &function_def.code {
+                None => continue,
+                Some(code_unit) => code_unit,
+            };
+            let fn_handle = module.function_handle_at(function_def.function);
+            let fn_name = module.identifier_at(fn_handle.name);
+            let function_def_idx = FunctionDefinitionIndex(function_def_idx as u16);
+            let function_map = source_map.get_function_source_map(function_def_idx)?;
+            let function_coverage =
+                module_map.and_then(|fn_map| fn_map.get_function_coverage(fn_name));
+            let branch_coverage = module_map.and_then(|fn_map| fn_map.get_branch_coverage(fn_name));
+            let count_at = |pc: u64| {
+                function_coverage
+                    .and_then(|coverage| coverage.get(&pc))
+                    .copied()
+                    .unwrap_or(0)
+            };
+
+            functions.push(FunctionLineCoverage {
+                name: fn_name.to_owned(),
+                decl_line: line_of(function_map.decl_location)?,
+                execution_count: count_at(0),
+            });
+            for code_offset in 0..code_unit.code.len() {
+                let loc =
+                    source_map.get_code_location(function_def_idx, code_offset as CodeOffset)?;
+                let line = lines.entry(line_of(loc)?).or_insert(0);
+                *line = std::cmp::max(*line, count_at(code_offset as u64));
+                counts.total_instructions += 1;
+                if count_at(code_offset as u64) > 0 {
+                    counts.covered_instructions += 1;
+                }
+            }
+
+            let mut edges_by_branch: BTreeMap<u64, Vec<(u64, u64)>> = BTreeMap::new();
+            for edge in branch_edges(&code_unit.code) {
+                let taken = branch_coverage
+                    .and_then(|coverage| coverage.get(&edge))
+                    .copied()
+                    .unwrap_or(0);
+                counts.total_branches += 1;
+                if taken > 0 {
+                    counts.covered_branches += 1;
+                }
+                edges_by_branch
+                    .entry(edge.0)
+                    .or_default()
+                    .push((edge.1, taken));
+            }
+            for (branch_pc, edges) in edges_by_branch {
+                let loc =
+                    source_map.get_code_location(function_def_idx, branch_pc as CodeOffset)?;
+                branches.push(BranchLineCoverage {
+                    line: line_of(loc)?,
+                    branch_pc,
+                    executed: count_at(branch_pc) > 0,
+                    edges,
+                })
+            }
+        }
+
+        Ok(Self {
+            module_name,
+            source_path,
+            source,
+            functions,
+            lines,
+            branches,
+            counts,
+        })
+    }
+
+    pub fn functions_hit(&self) -> usize {
+        self.functions
+            .iter()
+            .filter(|function| function.execution_count > 0)
+            .count()
+    }
+
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|count| **count > 0).count()
+    }
+}
+
+/// Returns the source file of the module, as recorded in its source map.
+fn source_file(module: &CompiledModule, source_map: &SourceMap<Loc>) -> Result<&'static str> {
+    if module.function_defs().is_empty() {
+        return Err(format_err!(
+            "Module {} has no functions to report coverage for",
+            module.self_id()
+        ));
+    }
+    let function_map = source_map.get_function_source_map(FunctionDefinitionIndex(0))?;
+    Ok(function_map.decl_location.file())
+}
+
+fn line_number(files: &Files<String>, file_id: FileId, offset: ByteIndex) -> Result<u32> {
+    files
+        .location(file_id, offset)
+        .map(|location| location.line.0 + 1)
+        .map_err(|_| format_err!("Source map does not match the source file"))
+}
+
+#[cfg(test)]
+impl ModuleLineCoverage {
+    /// The coverage of a module with a function entered three times, which took one edge of its
+    /// first branch and never reached the second, and of a function never entered.
+    pub(crate) fn for_tests() -> Self {
+        use move_core_types::account_address::AccountAddress;
+
+        let function = |name, decl_line, execution_count| FunctionLineCoverage {
+            name: Identifier::new(name).unwrap(),
+            decl_line,
+            execution_count,
+        };
+        let branch = |branch_pc, executed, edges| BranchLineCoverage {
+            line: 3,
+            branch_pc,
+            executed,
+            edges,
+        };
+        Self {
+            module_name: ModuleId::new(
+                AccountAddress::from_hex_literal("0x1").unwrap(),
+                Identifier::new("M").unwrap(),
+            ),
+            source_path: PathBuf::from("M.move"),
+            source: "module M { // <M>
+    fun f(x: bool, y: bool) {
+        if (x) abort 1; if (y) abort 2;
+    }
+    fun g() {}
+}
+"
+            .to_string(),
+            functions: vec![function("f", 2, 3), function("g", 5, 0)],
+            lines: vec![(2, 3), (3, 3), (4, 3), (5, 0)].into_iter().collect(),
+            branches: vec![
+                branch(1, true, vec![(2, 0), (4, 3)]),
+                branch(5, false, vec![(6, 0), (7, 0)]),
+            ],
+            counts: CoverageCounts {
+                total_instructions: 8,
+                covered_instructions: 5,
+                total_branches: 4,
+                covered_branches: 1,
+            },
+        }
+    }
+}
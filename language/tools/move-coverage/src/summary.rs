@@ -10,7 +10,7 @@ use std::{
     collections::BTreeMap,
     io::{self, Write},
 };
-use vm::{access::ModuleAccess, CompiledModule};
+use vm::{access::ModuleAccess, file_format::Bytecode, CompiledModule};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ModuleSummaryOptions {
@@ -29,6 +29,18 @@ pub struct FunctionSummary {
     pub fn_is_native: bool,
     pub total_number_of_instructions: u64,
     pub covered_instructions: u64,
+    pub total_number_of_branches: u64,
+    pub covered_branches: u64,
+}
+
+/// Instruction and branch counts, summed over the functions of a module or over several modules.
+/// A branch is one edge out of a conditional jump, i.e. either taken or not taken.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct CoverageCounts {
+    pub total_instructions: u64,
+    pub covered_instructions: u64,
+    pub total_branches: u64,
+    pub covered_branches: u64,
 }
 
 impl Default for ModuleSummaryOptions {
@@ -62,6 +74,8 @@ impl ModuleSummary {
                         fn_is_native: true,
                         total_number_of_instructions: 0,
                         covered_instructions: 0,
+                        total_number_of_branches: 0,
+                        covered_branches: 0,
                     },
                     Some(code_unit) => {
                         let total_number_of_instructions = code_unit.code.len() as u64;
@@ -73,10 +87,19 @@ impl ModuleSummary {
                                     .map(|function_map| function_map.len())
                             })
                             .unwrap_or(0) as u64;
+                        let branch_map =
+                            module_map.and_then(|fn_map| fn_map.get_branch_coverage(&fn_name));
+                        let branches = branch_edges(&code_unit.code);
+                        let covered_branches = branches
+                            .iter()
+                            .filter(|edge| branch_map.map_or(false, |map| map.contains_key(edge)))
+                            .count() as u64;
                         FunctionSummary {
                             fn_is_native: false,
                             total_number_of_instructions,
                             covered_instructions,
+                            total_number_of_branches: branches.len() as u64,
+                            covered_branches,
                         }
                     }
                 };
@@ -100,11 +123,16 @@ impl ModuleSummary {
             self.module_name.name()
         );
 
-        let mut format_line = |fn_name, covered, uncovered| {
+        let mut format_line = |fn_name, fn_summary: &FunctionSummary| {
             writeln!(
                 summary_writer,
-                "{},{},{},{}",
-                module, fn_name, covered, uncovered
+                "{},{},{},{},{},{}",
+                module,
+                fn_name,
+                fn_summary.covered_instructions,
+                fn_summary.total_number_of_instructions,
+                fn_summary.covered_branches,
+                fn_summary.total_number_of_branches
             )
         };
 
@@ -113,11 +141,7 @@ impl ModuleSummary {
             .iter()
             .filter(|(_, summary)| !summary.fn_is_native)
         {
-            format_line(
-                fn_name,
-                fn_summary.covered_instructions,
-                fn_summary.total_number_of_instructions,
-            )?;
+            format_line(fn_name, fn_summary)?;
         }
 
         Ok(())
@@ -125,9 +149,8 @@ impl ModuleSummary {
 
     /// Summarizes the modules coverage, and returns the total module coverage in a human-readable
     /// format.
-    pub fn summarize_human<W: Write>(&self, summary_writer: &mut W) -> io::Result<(u64, u64)> {
-        let mut total_covered = 0;
-        let mut total_instructions = 0;
+    pub fn summarize_human<W: Write>(&self, summary_writer: &mut W) -> io::Result<CoverageCounts> {
+        let counts = self.counts();
 
         writeln!(
             summary_writer,
@@ -136,11 +159,8 @@ impl ModuleSummary {
             self.module_name.name()
         )?;
 
-        for (fn_name, fn_summary) in self.function_summaries.iter() {
-            total_instructions += fn_summary.total_number_of_instructions;
-            total_covered += fn_summary.covered_instructions;
-
-            if self.summary_options.summarize_function_coverage {
+        if self.summary_options.summarize_function_coverage {
+            for (fn_name, fn_summary) in self.function_summaries.iter() {
                 let native = if fn_summary.fn_is_native {
                     "native "
                 } else {
@@ -162,16 +182,45 @@ impl ModuleSummary {
                     "\t\t% coverage: {:.2}",
                     fn_summary.percent_coverage()
                 )?;
+                if fn_summary.total_number_of_branches > 0 {
+                    writeln!(
+                        summary_writer,
+                        "\t\tcovered branches: {} of {} ({:.2}%)",
+                        fn_summary.covered_branches,
+                        fn_summary.total_number_of_branches,
+                        fn_summary.percent_branch_coverage()
+                    )?;
+                }
             }
         }
 
-        let covered_percentage = percent_coverage_for_counts(total_instructions, total_covered);
         writeln!(
             summary_writer,
             ">>> % Module coverage: {:.2}",
-            covered_percentage
+            counts.percent_coverage()
         )?;
-        Ok((total_instructions, total_covered))
+        if counts.total_branches > 0 {
+            writeln!(
+                summary_writer,
+                ">>> % Module branch coverage: {:.2}",
+                counts.percent_branch_coverage()
+            )?;
+        }
+        Ok(counts)
+    }
+
+    /// Returns the instruction and branch counts over all functions of the module.
+    pub fn counts(&self) -> CoverageCounts {
+        let mut counts = CoverageCounts::default();
+        for fn_summary in self.function_summaries.values() {
+            counts.add(&CoverageCounts {
+                total_instructions: fn_summary.total_number_of_instructions,
+                covered_instructions: fn_summary.covered_instructions,
+                total_branches: fn_summary.total_number_of_branches,
+                covered_branches: fn_summary.covered_branches,
+            })
+        }
+        counts
     }
 }
 
@@ -179,10 +228,146 @@ impl FunctionSummary {
     pub fn percent_coverage(&self) -> f64 {
         percent_coverage_for_counts(self.total_number_of_instructions, self.covered_instructions)
     }
+
+    pub fn percent_branch_coverage(&self) -> f64 {
+        percent_coverage_for_counts(self.total_number_of_branches, self.covered_branches)
+    }
+}
+
+impl CoverageCounts {
+    pub fn add(&mut self, other: &CoverageCounts) {
+        self.total_instructions += other.total_instructions;
+        self.covered_instructions += other.covered_instructions;
+        self.total_branches += other.total_branches;
+        self.covered_branches += other.covered_branches;
+    }
+
+    pub fn percent_coverage(&self) -> f64 {
+        percent_coverage_for_counts(self.total_instructions, self.covered_instructions)
+    }
+
+    pub fn percent_branch_coverage(&self) -> f64 {
+        percent_coverage_for_counts(self.total_branches, self.covered_branches)
+    }
+
+    /// Checks the coverage against the minimum instruction and branch coverage, in percent, and
+    /// returns why it falls short of them, if it does.
+    pub fn check_thresholds(
+        &self,
+        min_coverage: Option<f64>,
+        min_branch_coverage: Option<f64>,
+    ) -> Vec<String> {
+        let mut failures = vec![];
+        if let Some(min_coverage) = min_coverage {
+            if self.percent_coverage() < min_coverage {
+                failures.push(format!(
+                    "Instruction coverage {:.2}% is below the threshold of {:.2}%",
+                    self.percent_coverage(),
+                    min_coverage
+                ));
+            }
+        }
+        if let Some(min_branch_coverage) = min_branch_coverage {
+            if self.percent_branch_coverage() < min_branch_coverage {
+                failures.push(format!(
+                    "Branch coverage {:.2}% is below the threshold of {:.2}%",
+                    self.percent_branch_coverage(),
+                    min_branch_coverage
+                ));
+            }
+        }
+        failures
+    }
 }
 
+/// Returns the edges out of the conditional branches of `code`, as (branch offset, successor
+/// offset) pairs.
+pub fn branch_edges(code: &[Bytecode]) -> Vec<(u64, u64)> {
+    let mut edges = vec![];
+    for (pc, instr) in code.iter().enumerate() {
+        let pc = pc as u64;
+        match instr {
+            Bytecode::BrTrue(target) | Bytecode::BrFalse(target) => {
+                let target = *target as u64;
+                edges.push((pc, pc + 1));
+                if target != pc + 1 {
+                    edges.push((pc, target));
+                }
+            }
+            _ => (),
+        }
+    }
+    edges
+}
+
+/// Returns the percentage of `total` that is `covered`. Nothing to cover counts as fully covered.
 pub fn percent_coverage_for_counts(total: u64, covered: u64) -> f64 {
+    if total == 0 {
+        return 100f64;
+    }
     let total = total as f64;
     let covered = covered as f64;
     (covered as f64) / (total as f64) * 100f64
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(covered_instructions: u64, covered_branches: u64) -> CoverageCounts {
+        CoverageCounts {
+            total_instructions: 10,
+            covered_instructions,
+            total_branches: 4,
+            covered_branches,
+        }
+    }
+
+    #[test]
+    fn test_branch_edges() {
+        let code = vec![
+            Bytecode::CopyLoc(0),
+            Bytecode::BrTrue(4),
+            Bytecode::LdU64(0),
+            Bytecode::BrFalse(4),
+            Bytecode::Branch(0),
+            Bytecode::Ret,
+        ];
+        // The `BrFalse` falls through to its target either way, so it has a single edge.
+        assert_eq!(branch_edges(&code), vec![(1, 2), (1, 4), (3, 4)]);
+        assert!(branch_edges(&[Bytecode::Branch(0)]).is_empty());
+    }
+
+    #[test]
+    fn test_percent_coverage() {
+        let close_to = |percent: f64, expected: f64| (percent - expected).abs() < 1e-9;
+        assert!(close_to(percent_coverage_for_counts(4, 1), 25f64));
+        assert!(close_to(percent_coverage_for_counts(0, 0), 100f64));
+        assert!(close_to(
+            CoverageCounts::default().percent_branch_coverage(),
+            100f64
+        ));
+    }
+
+    #[test]
+    fn test_check_thresholds() {
+        assert!(counts(8, 2).check_thresholds(None, None).is_empty());
+        assert!(counts(8, 2)
+            .check_thresholds(Some(80f64), Some(50f64))
+            .is_empty());
+        assert_eq!(
+            counts(7, 2).check_thresholds(Some(80f64), Some(50f64)),
+            vec!["Instruction coverage 70.00% is below the threshold of 80.00%".to_string()]
+        );
+        assert_eq!(
+            counts(7, 1)
+                .check_thresholds(Some(80f64), Some(50f64))
+                .len(),
+            2
+        );
+        // Nothing to cover meets any threshold.
+        assert!(CoverageCounts::default()
+            .check_thresholds(Some(100f64), Some(100f64))
+            .is_empty());
+    }
+}
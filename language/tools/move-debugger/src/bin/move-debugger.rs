@@ -0,0 +1,70 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use move_core_types::{account_address::AccountAddress, parser::parse_transaction_arguments};
+use move_debugger::DebugTarget;
+use move_lang::command_line as cli;
+use std::{io, process};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "Move Debugger",
+    about = "Step through the execution of a Move script"
+)]
+struct Options {
+    /// The Move source file of the script
+    #[structopt(name = "PATH_TO_SCRIPT_FILE")]
+    script_file: String,
+
+    /// The modules used by the script, compiled along with it and published over the genesis
+    /// modules
+    #[structopt(
+        name = "PATH_TO_DEPENDENCY_FILE",
+        short = cli::DEPENDENCY_SHORT,
+        long = cli::DEPENDENCY,
+    )]
+    dependencies: Vec<String>,
+
+    /// The sender of the script. A new account is created if not given
+    #[structopt(
+        name = "ADDRESS",
+        short = cli::SENDER_SHORT,
+        long = cli::SENDER,
+        parse(try_from_str = AccountAddress::from_hex_literal)
+    )]
+    sender: Option<AccountAddress>,
+
+    /// The arguments of the script, e.g. "1, 0x2, true"
+    #[structopt(long = "args", short = "a")]
+    args: Option<String>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let Options {
+        script_file,
+        dependencies,
+        sender,
+        args,
+    } = Options::from_args();
+    let args = match args {
+        Some(args) => parse_transaction_arguments(&args)?,
+        None => vec![],
+    };
+    let target = DebugTarget::compile(&script_file, &dependencies)?;
+    let stdin = io::stdin();
+    let result = target.run(&args, sender, &mut stdin.lock(), &mut io::stdout());
+    match result {
+        Ok(()) => println!("The script executed successfully"),
+        Err(e) => {
+            match e.sub_status() {
+                Some(code) => println!("The script failed with {:?} ({})", e.major_status(), code),
+                None => println!("The script failed with {:?}", e.major_status()),
+            }
+            process::exit(1);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,548 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! A source level debugger for Move scripts.
+//!
+//! The script and the modules it uses are compiled from source and published over the genesis
+//! state of a `FakeDataStore`. The script then runs under the control of a REPL, which maps the
+//! bytecode being executed back to the Move source lines through the source maps of the compiler.
+
+#![forbid(unsafe_code)]
+
+use anyhow::{bail, format_err, Result};
+use bytecode_source_map::source_map::SourceMap;
+use language_e2e_tests::executor::FakeExecutor;
+use move_core_types::{
+    account_address::AccountAddress,
+    gas_schedule::{GasAlgebra, GasUnits},
+    identifier::Identifier,
+    language_storage::{ModuleId, StructTag, TypeTag},
+    parser::parse_type_tags,
+    transaction_argument::TransactionArgument,
+};
+use move_ir_types::location::Loc;
+use move_lang::{
+    compiled_unit::CompiledUnit,
+    errors::{report_errors_to_buffer, FilesSourceText},
+    move_compile_no_report,
+    shared::Address,
+};
+use move_vm_runtime::{
+    debug::{Breakpoint, CodeLocation, DebugCommand, DebugState, Debugger, StopReason},
+    move_vm::MoveVM,
+};
+use move_vm_types::{
+    gas_schedule::{zero_cost_schedule, CostStrategy},
+    values::Value,
+};
+use std::io::{BufRead, Write};
+use vm::{
+    access::ModuleAccess,
+    errors::VMResult,
+    file_format::{CodeOffset, CompiledModule, FunctionDefinitionIndex},
+};
+
+const GAS_BUDGET: u64 = 100_000_000;
+
+/// The number of lines shown around the current one by the `list` command.
+const LIST_CONTEXT: usize = 3;
+
+const HELP: &str = "\
+step, s                   run to the next source line, entering calls
+next, n                   run to the next source line of the current function
+stepi, si                 run a single instruction
+finish, out               run until the current function returns
+continue, c               run until a breakpoint is reached
+break, b <location>       set a breakpoint on a line of the current file, or on a function
+                          given as 0xADDR::Module::function[@offset], or main[@offset]
+delete, d <n>             delete the breakpoint numbered n
+breakpoints               list the breakpoints
+backtrace, bt             print the call stack
+locals [<frame>]          print the locals of the current function, or of a frame of the stack
+stack                     print the operand stack
+resource <address> <type> print a resource, e.g. resource 0x1 0x1::Module::Resource
+list, l                   print the source around the current line
+instruction, i            print the instruction about to run
+quit, q                   run the rest of the script without stopping";
+
+/// A script and the modules it uses, compiled along with their source maps.
+pub struct DebugTarget {
+    files: FilesSourceText,
+    script: Vec<u8>,
+    script_source_map: SourceMap<Loc>,
+    modules: Vec<(CompiledModule, SourceMap<Loc>)>,
+}
+
+impl DebugTarget {
+    /// Compiles the script in `script_file` along with the modules in `deps`.
+    pub fn compile(script_file: &str, deps: &[String]) -> Result<Self> {
+        let mut targets = vec![script_file.to_string()];
+        targets.extend(deps.iter().cloned());
+        let (files, units_or_errors) = move_compile_no_report(&targets, &[], None)?;
+        let units = match units_or_errors {
+            Ok(units) => units,
+            Err(errors) => bail!(
+                "Unable to compile the script:\n{}",
+                String::from_utf8_lossy(&report_errors_to_buffer(files, errors))
+            ),
+        };
+        let mut script = None;
+        let mut modules = vec![];
+        for unit in units {
+            match unit {
+                CompiledUnit::Script {
+                    script: compiled,
+                    source_map,
+                    ..
+                } => {
+                    if script.is_some() {
+                        bail!("Expected a single script to debug, found several");
+                    }
+                    let mut blob = vec![];
+                    compiled.serialize(&mut blob)?;
+                    script = Some((blob, source_map));
+                }
+                CompiledUnit::Module {
+                    module, source_map, ..
+                } => modules.push((module, source_map)),
+            }
+        }
+        let (script, script_source_map) =
+            script.ok_or_else(|| format_err!("No script found in {}", script_file))?;
+        Ok(Self {
+            files,
+            script,
+            script_source_map,
+            modules,
+        })
+    }
+
+    /// Executes the script with `args` on behalf of `sender`, under the control of a REPL which
+    /// reads commands from `input` and writes to `output`. Without a sender, the script runs on
+    /// behalf of a new account.
+    pub fn run(
+        &self,
+        args: &[TransactionArgument],
+        sender: Option<AccountAddress>,
+        input: &mut dyn BufRead,
+        output: &mut dyn Write,
+    ) -> VMResult<()> {
+        let mut executor = FakeExecutor::from_genesis_file();
+        for (module, _) in &self.modules {
+            executor.add_module(&module.self_id(), module);
+        }
+        let sender = match sender {
+            Some(sender) => sender,
+            None => *executor.create_accounts(1, 1_000_000, 0)[0].address(),
+        };
+
+        let vm = MoveVM::new();
+        let mut session = vm.new_session(executor.get_state_view());
+        let cost_table = zero_cost_schedule();
+        let mut cost_strategy = CostStrategy::system(&cost_table, GasUnits::new(GAS_BUDGET));
+        let mut repl = Repl {
+            target: self,
+            input,
+            output,
+            breakpoints: vec![],
+            source_step: None,
+            detached: false,
+        };
+        session.execute_script_with_debugger(
+            self.script.clone(),
+            vec![],
+            convert_txn_args(args),
+            sender,
+            &mut cost_strategy,
+            &mut repl,
+        )
+    }
+
+    fn source_map(&self, module: Option<&ModuleId>) -> Option<&SourceMap<Loc>> {
+        match module {
+            None => Some(&self.script_source_map),
+            Some(module_id) => self
+                .modules
+                .iter()
+                .find(|(module, _)| &module.self_id() == module_id)
+                .map(|(_, source_map)| source_map),
+        }
+    }
+
+    /// The source line of an instruction, as a file and a line number starting at 1.
+    fn line(&self, location: &CodeLocation) -> Option<(&'static str, usize)> {
+        let loc = self
+            .source_map(location.module.as_ref())?
+            .get_code_location(location.function_index, location.offset)
+            .ok()?;
+        self.line_of(loc)
+    }
+
+    fn line_of(&self, loc: Loc) -> Option<(&'static str, usize)> {
+        let source = self.files.get(loc.file())?;
+        let start = loc.span().start().to_usize();
+        if start > source.len() {
+            return None;
+        }
+        Some((loc.file(), source[..start].matches('\n').count() + 1))
+    }
+
+    fn source_line(&self, file: &str, line: usize) -> Option<&str> {
+        self.files.get(file)?.lines().nth(line.checked_sub(1)?)
+    }
+
+    /// A breakpoint on the first instruction of a source line.
+    fn line_breakpoint(&self, file: &str, line: usize) -> Option<Breakpoint> {
+        let main = Identifier::new("main").unwrap();
+        let mut functions = vec![(
+            None,
+            &self.script_source_map,
+            FunctionDefinitionIndex(0),
+            main,
+        )];
+        for (module, source_map) in &self.modules {
+            for (idx, def) in module.function_defs().iter().enumerate() {
+                let handle = module.function_handle_at(def.function);
+                let name = module.identifier_at(handle.name).to_owned();
+                let idx = FunctionDefinitionIndex(idx as u16);
+                functions.push((Some(module.self_id()), source_map, idx, name));
+            }
+        }
+        for (module, source_map, idx, function) in functions {
+            let function_map = match source_map.get_function_source_map(idx) {
+                Ok(function_map) => function_map,
+                Err(_) => continue,
+            };
+            let offset =
+                function_map
+                    .code_map
+                    .iter()
+                    .find_map(|(offset, loc)| match self.line_of(*loc) {
+                        Some((f, l)) if f == file && l == line => Some(*offset),
+                        _ => None,
+                    });
+            if let Some(offset) = offset {
+                return Some(Breakpoint {
+                    module,
+                    function,
+                    offset,
+                });
+            }
+        }
+        None
+    }
+}
+
+// A source line being stepped through.
+struct SourceStep {
+    command: DebugCommand,
+    depth: usize,
+    line: Option<(&'static str, usize)>,
+}
+
+struct Repl<'a> {
+    target: &'a DebugTarget,
+    input: &'a mut dyn BufRead,
+    output: &'a mut dyn Write,
+    breakpoints: Vec<Breakpoint>,
+    source_step: Option<SourceStep>,
+    // Set once the user quits or the input ends, to run the rest of the script without stopping.
+    detached: bool,
+}
+
+impl<'a> Debugger for Repl<'a> {
+    fn breakpoints(&self) -> &[Breakpoint] {
+        if self.detached {
+            &[]
+        } else {
+            &self.breakpoints
+        }
+    }
+
+    fn on_stop(&mut self, state: &mut DebugState, reason: StopReason) -> DebugCommand {
+        if self.detached {
+            return DebugCommand::Continue;
+        }
+        let location = state.location();
+        let depth = state.depth();
+        let line = self.target.line(&location);
+        if let (Some(step), StopReason::Step) = (&self.source_step, reason) {
+            // Keep going until a new source line is reached, and leave the functions without
+            // source.
+            if line.is_none() {
+                return DebugCommand::StepOut;
+            }
+            if depth == step.depth && line == step.line {
+                return step.command;
+            }
+        }
+        self.source_step = None;
+
+        match reason {
+            StopReason::Breakpoint => {
+                self.print(format!("Breakpoint reached in {}", describe(&location)))
+            }
+            StopReason::Step => self.print(format!("Stopped in {}", describe(&location))),
+        }
+        self.print_line(line);
+        loop {
+            write!(self.output, "(move-debugger) ").expect("Unable to write to the output");
+            self.output.flush().expect("Unable to write to the output");
+            let mut command = String::new();
+            match self.input.read_line(&mut command) {
+                Ok(0) | Err(_) => {
+                    self.detached = true;
+                    return DebugCommand::Continue;
+                }
+                Ok(_) => (),
+            }
+            let words: Vec<_> = command.split_whitespace().collect();
+            if let Some(command) = self.command(state, depth, line, &words) {
+                return command;
+            }
+        }
+    }
+}
+
+impl<'a> Repl<'a> {
+    fn print(&mut self, text: impl AsRef<str>) {
+        writeln!(self.output, "{}", text.as_ref()).expect("Unable to write to the output");
+    }
+
+    fn print_line(&mut self, line: Option<(&'static str, usize)>) {
+        match line {
+            Some((file, line)) => {
+                let source = self.target.source_line(file, line).unwrap_or("");
+                self.print(format!("  at {}:{}", file, line));
+                self.print(format!("{:>5} | {}", line, source));
+            }
+            None => self.print("  (no source available)"),
+        }
+    }
+
+    // Runs a command of the user, and returns how to resume execution if it is one.
+    fn command(
+        &mut self,
+        state: &mut DebugState,
+        depth: usize,
+        line: Option<(&'static str, usize)>,
+        words: &[&str],
+    ) -> Option<DebugCommand> {
+        let (command, args) = match words.split_first() {
+            Some((command, args)) => (*command, args),
+            None => return None,
+        };
+        match (command, args) {
+            ("step", []) | ("s", []) | ("next", []) | ("n", []) => {
+                let command = if command.starts_with('s') {
+                    DebugCommand::StepIn
+                } else {
+                    DebugCommand::StepOver
+                };
+                self.source_step = Some(SourceStep {
+                    command,
+                    depth,
+                    line,
+                });
+                return Some(command);
+            }
+            ("stepi", []) | ("si", []) => return Some(DebugCommand::StepIn),
+            ("finish", []) | ("out", []) => return Some(DebugCommand::StepOut),
+            ("continue", []) | ("c", []) => return Some(DebugCommand::Continue),
+            ("quit", []) | ("q", []) => {
+                self.detached = true;
+                return Some(DebugCommand::Continue);
+            }
+            ("break", [location]) | ("b", [location]) => {
+                match self.parse_breakpoint(location, line) {
+                    Ok(breakpoint) => {
+                        self.print(format!(
+                            "Breakpoint {} at {}",
+                            self.breakpoints.len(),
+                            describe_breakpoint(&breakpoint)
+                        ));
+                        self.breakpoints.push(breakpoint);
+                    }
+                    Err(e) => self.print(format!("Invalid breakpoint: {}", e)),
+                }
+            }
+            ("delete", [n]) | ("d", [n]) => match n.parse::<usize>() {
+                Ok(n) if n < self.breakpoints.len() => {
+                    self.breakpoints.remove(n);
+                }
+                _ => self.print(format!("No breakpoint numbered {}", n)),
+            },
+            ("breakpoints", []) => {
+                let lines: Vec<_> = self
+                    .breakpoints
+                    .iter()
+                    .enumerate()
+                    .map(|(n, bp)| format!("{}: {}", n, describe_breakpoint(bp)))
+                    .collect();
+                for line in lines {
+                    self.print(line);
+                }
+            }
+            ("backtrace", []) | ("bt", []) => {
+                for (n, location) in state.call_stack().iter().enumerate().rev() {
+                    let position = match self.target.line(location) {
+                        Some((file, line)) => format!(" at {}:{}", file, line),
+                        None => "".to_string(),
+                    };
+                    self.print(format!("#{} {}{}", n, describe(location), position));
+                }
+            }
+            ("locals", []) => self.print_locals(state, depth),
+            ("locals", [frame]) => match frame.parse::<usize>() {
+                Ok(frame) if frame <= depth => self.print_locals(state, frame),
+                _ => self.print(format!("No frame numbered {}", frame)),
+            },
+            ("stack", []) => {
+                let values = state.operand_stack();
+                if values.is_empty() {
+                    self.print("The operand stack is empty");
+                }
+                for (idx, value) in values.iter().enumerate() {
+                    self.print(format!("[{}] {}", idx, value));
+                }
+            }
+            ("resource", [address, tag]) => {
+                let resource = parse_resource(address, tag).and_then(|(address, tag)| {
+                    state
+                        .resource(address, &tag)
+                        .map_err(|e| format_err!("{:?}", e.major_status()))
+                });
+                match resource {
+                    Ok(Some(resource)) => self.print(resource),
+                    Ok(None) => self.print(format!("No {} published under {}", tag, address)),
+                    Err(e) => self.print(format!("Unable to read the resource: {}", e)),
+                }
+            }
+            ("list", []) | ("l", []) => match line {
+                Some((file, line)) => {
+                    let first = line.saturating_sub(LIST_CONTEXT).max(1);
+                    for n in first..=line + LIST_CONTEXT {
+                        if let Some(source) = self.target.source_line(file, n) {
+                            let marker = if n == line { ">" } else { " " };
+                            self.print(format!("{}{:>4} | {}", marker, n, source));
+                        }
+                    }
+                }
+                None => self.print("No source available"),
+            },
+            ("instruction", []) | ("i", []) => {
+                let text = format!("[{}] {:?}", state.location().offset, state.instruction());
+                self.print(text)
+            }
+            ("help", []) | ("h", []) => self.print(HELP),
+            _ => self.print(format!(
+                "Unknown command '{}'. Type 'help' for the list of commands",
+                words.join(" ")
+            )),
+        }
+        None
+    }
+
+    fn print_locals(&mut self, state: &DebugState, frame: usize) {
+        let location = &state.call_stack()[frame];
+        let values = match state.locals(frame) {
+            Ok(values) => values,
+            Err(e) => {
+                self.print(format!("Unable to read the locals: {:?}", e.major_status()));
+                return;
+            }
+        };
+        if values.is_empty() {
+            self.print("The function has no locals");
+        }
+        let source_map = self.target.source_map(location.module.as_ref());
+        for (idx, value) in values.iter().enumerate() {
+            let name = source_map
+                .and_then(|source_map| {
+                    source_map
+                        .get_parameter_or_local_name(location.function_index, idx as u64)
+                        .ok()
+                })
+                .map_or_else(|| format!("[{}]", idx), |(name, _)| name);
+            self.print(format!("{} = {}", name, value));
+        }
+    }
+
+    fn parse_breakpoint(
+        &self,
+        location: &str,
+        line: Option<(&'static str, usize)>,
+    ) -> Result<Breakpoint> {
+        if let Ok(n) = location.parse::<usize>() {
+            let file = match line {
+                Some((file, _)) => file,
+                None => bail!("No source file for the current location"),
+            };
+            return self
+                .target
+                .line_breakpoint(file, n)
+                .ok_or_else(|| format_err!("No code at {}:{}", file, n));
+        }
+        let mut parts = location.splitn(2, '@');
+        let path = parts.next().unwrap_or("");
+        let offset = match parts.next() {
+            Some(offset) => offset.parse::<CodeOffset>()?,
+            None => 0,
+        };
+        let names: Vec<_> = path.split("::").collect();
+        let (module, function) = match names.as_slice() {
+            [function] => (None, function),
+            [address, module, function] => {
+                let address = AccountAddress::from_hex_literal(address)?;
+                let module = ModuleId::new(address, Identifier::new(*module)?);
+                (Some(module), function)
+            }
+            _ => bail!("Expected a function like 0x1::Module::function or main"),
+        };
+        Ok(Breakpoint {
+            module,
+            function: Identifier::new(*function)?,
+            offset,
+        })
+    }
+}
+
+/// Formats the function of a location, and the offset in it, e.g. `0x1::Vector::length@2`.
+fn describe(location: &CodeLocation) -> String {
+    format!(
+        "{}@{}",
+        function_name(location.module.as_ref(), &location.function),
+        location.offset
+    )
+}
+
+fn describe_breakpoint(breakpoint: &Breakpoint) -> String {
+    format!(
+        "{}@{}",
+        function_name(breakpoint.module.as_ref(), &breakpoint.function),
+        breakpoint.offset
+    )
+}
+
+fn function_name(module: Option<&ModuleId>, function: &Identifier) -> String {
+    match module {
+        Some(module_id) => {
+            let address = Address::new((*module_id.address()).into());
+            format!("{}::{}::{}", address, module_id.name(), function)
+        }
+        None => function.to_string(),
+    }
+}
+
+fn parse_resource(address: &str, tag: &str) -> Result<(AccountAddress, StructTag)> {
+    let address = AccountAddress::from_hex_literal(address)?;
+    match parse_type_tags(tag)?.as_slice() {
+        [TypeTag::Struct(tag)] => Ok((address, tag.clone())),
+        _ => bail!("Expected a resource type like 0x1::Module::Resource"),
+    }
+}
+
+/// Converts the transaction arguments into move values.
+fn convert_txn_args(args: &[TransactionArgument]) -> Vec<Value> {
+    args.iter().map(Value::from_transaction_argument).collect()
+}
@@ -0,0 +1,107 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_core_types::{account_address::AccountAddress, transaction_argument::TransactionArgument};
+use move_debugger::DebugTarget;
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+fn source_path(name: &str) -> String {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/sources")
+        .join(name)
+        .to_string_lossy()
+        .to_string()
+}
+
+// Debugs the sum script with the given REPL commands, and returns the output of the REPL.
+fn debug_sum(commands: &str) -> String {
+    let target =
+        DebugTarget::compile(&source_path("sum.move"), &[source_path("Math.move")]).unwrap();
+    let sender = AccountAddress::from_hex_literal("0xa11ce").unwrap();
+    let mut output = vec![];
+    target
+        .run(
+            &[TransactionArgument::U64(3)],
+            Some(sender),
+            &mut commands.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn test_function_breakpoint_and_steps() {
+    let output = debug_sum(
+        "break 0x2::Math::sum
+         continue
+         locals
+         bt
+         finish
+         next
+         step
+         finish
+         resource 0xa11ce 0x2::Math::Total
+         continue",
+    );
+    assert!(output.starts_with("Stopped in main@0\n"), "{}", output);
+    assert!(output.contains("sum.move:5\n"), "{}", output);
+    assert!(
+        output.contains("Breakpoint 0 at 0x2::Math::sum@0\n"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("Breakpoint reached in 0x2::Math::sum@0\n"),
+        "{}",
+        output
+    );
+    assert!(output.contains("Math.move:6\n"), "{}", output);
+    assert!(output.contains("n = 3u64\n"), "{}", output);
+    assert!(output.contains("#1 0x2::Math::sum@0 at "), "{}", output);
+    assert!(output.contains("#0 main@"), "{}", output);
+    // `next` moves from the call to `sum` to the call to `publish`, and `step` enters it.
+    assert!(
+        output.contains("    6 |     Math::publish(account, total);\n"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("Stopped in 0x2::Math::publish@0\n"),
+        "{}",
+        output
+    );
+    assert!(output.contains("Total { 6u64 }"), "{}", output);
+}
+
+#[test]
+fn test_line_breakpoint() {
+    let output = debug_sum("break 6\ncontinue\n");
+    assert!(output.contains("Breakpoint 0 at main@"), "{}", output);
+    assert!(output.contains("Breakpoint reached in main@"), "{}", output);
+    assert!(
+        output.contains("    6 |     Math::publish(account, total);\n"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn test_command_line() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_move-debugger"))
+        .arg(source_path("sum.move"))
+        .args(&["-d", &source_path("Math.move")])
+        .args(&["--args", "3"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.as_mut().unwrap().write_all(b"quit\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("The script executed successfully"));
+}
@@ -0,0 +1,19 @@
+address 0x2 {
+module Math {
+    resource struct Total { value: u64 }
+
+    public fun sum(n: u64): u64 {
+        let total = 0;
+        let i = 1;
+        while (i <= n) {
+            total = total + i;
+            i = i + 1;
+        };
+        total
+    }
+
+    public fun publish(account: &signer, value: u64) {
+        move_to(account, Total { value })
+    }
+}
+}
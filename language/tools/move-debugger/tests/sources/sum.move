@@ -0,0 +1,8 @@
+script {
+use 0x2::Math;
+
+fun main(account: &signer, n: u64) {
+    let total = Math::sum(n);
+    Math::publish(account, total);
+}
+}
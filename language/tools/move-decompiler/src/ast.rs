@@ -0,0 +1,368 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The structured Move code reconstructed from a function body, and its rendering to source.
+//!
+//! Names of module members, types and constants are resolved to source text when the tree is
+//! built; only the names of variables are resolved when printing, once the variables in use are
+//! known.
+
+use std::fmt::Write;
+use vm::file_format::LocalIndex;
+
+/// A variable of the decompiled function.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(crate) enum Var {
+    /// A parameter or a local of the function.
+    Local(LocalIndex),
+    /// A temporary holding a value that could not be kept on the stack.
+    Temp(usize),
+    /// A value left on the stack at the end of a basic block, at the given depth.
+    Stack(usize),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Exp {
+    Var(Var),
+    /// A literal or a constant, as written in source.
+    Value(String),
+    BorrowLocal(bool, LocalIndex),
+    /// A borrow of a field, through a reference to the struct.
+    BorrowField(bool, Box<Exp>, String),
+    Deref(Box<Exp>),
+    Not(Box<Exp>),
+    Cast(Box<Exp>, &'static str),
+    Binary(&'static str, Box<Exp>, Box<Exp>),
+    /// A call of a function or a builtin, with the type arguments in its name.
+    Call(String, Vec<Exp>),
+    Pack(String, Vec<(String, Exp)>),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum LValue {
+    Var(Var),
+    Ignore,
+    Unpack(String, Vec<(String, LValue)>),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Stmt {
+    /// Assigns variables declared at the start of the function.
+    Assign(Vec<LValue>, Exp),
+    /// Declares and assigns temporaries.
+    Let(Vec<LValue>, Exp),
+    /// Writes through a reference.
+    Mutate(Exp, Exp),
+    Exp(Exp),
+    Abort(Exp),
+    Return(Vec<Exp>),
+    If(Exp, Vec<Stmt>, Vec<Stmt>),
+    Loop(Vec<Stmt>),
+    While(Exp, Vec<Stmt>),
+    Break,
+    Continue,
+}
+
+impl Exp {
+    /// Whether the value of the expression cannot change with the code evaluated before it.
+    pub(crate) fn is_stable(&self) -> bool {
+        match self {
+            Exp::Value(_) | Exp::BorrowLocal(..) => true,
+            Exp::Var(var) => !matches!(var, Var::Local(_)),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn mentions_stack(&self) -> bool {
+        let mut found = false;
+        self.visit(&mut |exp| found |= matches!(exp, Exp::Var(Var::Stack(_))));
+        found
+    }
+
+    /// Calls `f` on all the variables the expression reads or borrows.
+    pub(crate) fn vars(&self, f: &mut dyn FnMut(Var)) {
+        self.visit(&mut |e| match e {
+            Exp::Var(var) => f(*var),
+            Exp::BorrowLocal(_, idx) => f(Var::Local(*idx)),
+            _ => (),
+        })
+    }
+
+    /// Calls `f` on this expression and all its subexpressions.
+    pub(crate) fn visit(&self, f: &mut dyn FnMut(&Exp)) {
+        f(self);
+        match self {
+            Exp::Var(_) | Exp::Value(_) | Exp::BorrowLocal(..) => (),
+            Exp::BorrowField(_, e, _) | Exp::Deref(e) | Exp::Not(e) | Exp::Cast(e, _) => e.visit(f),
+            Exp::Binary(_, lhs, rhs) => {
+                lhs.visit(f);
+                rhs.visit(f);
+            }
+            Exp::Call(_, args) => args.iter().for_each(|arg| arg.visit(f)),
+            Exp::Pack(_, fields) => fields.iter().for_each(|(_, e)| e.visit(f)),
+        }
+    }
+}
+
+impl LValue {
+    pub(crate) fn visit(&self, f: &mut dyn FnMut(Var)) {
+        match self {
+            LValue::Var(var) => f(*var),
+            LValue::Ignore => (),
+            LValue::Unpack(_, fields) => fields.iter().for_each(|(_, lv)| lv.visit(f)),
+        }
+    }
+}
+
+impl Stmt {
+    /// Whether control never reaches the end of the statement.
+    pub(crate) fn diverges(&self) -> bool {
+        match self {
+            Stmt::Abort(_) | Stmt::Return(_) | Stmt::Break | Stmt::Continue => true,
+            Stmt::If(_, then, else_) => diverges(then) && diverges(else_),
+            _ => false,
+        }
+    }
+
+    /// Calls `f` on all the variables of the statement and of the statements nested in it.
+    pub(crate) fn vars(&self, f: &mut dyn FnMut(Var)) {
+        match self {
+            Stmt::Assign(lvalues, e) | Stmt::Let(lvalues, e) => {
+                lvalues.iter().for_each(|lv| lv.visit(f));
+                e.vars(f);
+            }
+            Stmt::Mutate(r, e) => {
+                r.vars(f);
+                e.vars(f);
+            }
+            Stmt::Exp(e) | Stmt::Abort(e) => e.vars(f),
+            Stmt::Return(es) => es.iter().for_each(|e| e.vars(f)),
+            Stmt::If(cond, then, else_) => {
+                cond.vars(f);
+                then.iter().chain(else_).for_each(|stmt| stmt.vars(f));
+            }
+            Stmt::While(cond, body) => {
+                cond.vars(f);
+                body.iter().for_each(|stmt| stmt.vars(f));
+            }
+            Stmt::Loop(body) => body.iter().for_each(|stmt| stmt.vars(f)),
+            Stmt::Break | Stmt::Continue => (),
+        }
+    }
+}
+
+pub(crate) fn diverges(stmts: &[Stmt]) -> bool {
+    stmts.last().map_or(false, Stmt::diverges)
+}
+
+//**************************************************************************************************
+// Printing
+//**************************************************************************************************
+
+const INDENT: &str = "    ";
+
+/// Renders statements, given the names of the variables.
+pub(crate) struct Printer<'a> {
+    pub(crate) names: &'a dyn Fn(Var) -> String,
+}
+
+impl<'a> Printer<'a> {
+    /// Renders the body of a function, ending with the expression of its result if any.
+    pub(crate) fn body(&self, out: &mut String, stmts: &[Stmt], result: &[Exp], indent: usize) {
+        if result.is_empty() {
+            return self.block(out, stmts, indent);
+        }
+        for stmt in stmts {
+            out.push_str(&INDENT.repeat(indent));
+            self.stmt(out, stmt, indent);
+            out.push_str(";\n");
+        }
+        out.push_str(&INDENT.repeat(indent));
+        out.push_str(&self.exps(result));
+        out.push('\n');
+    }
+
+    /// Renders `stmts` as the lines of a block, without the braces, indented by `indent` levels.
+    pub(crate) fn block(&self, out: &mut String, stmts: &[Stmt], indent: usize) {
+        for (i, stmt) in stmts.iter().enumerate() {
+            out.push_str(&INDENT.repeat(indent));
+            self.stmt(out, stmt, indent);
+            if i + 1 < stmts.len() {
+                out.push(';');
+            }
+            out.push('\n');
+        }
+    }
+
+    fn braced(&self, out: &mut String, stmts: &[Stmt], indent: usize) {
+        out.push_str("{\n");
+        self.block(out, stmts, indent + 1);
+        out.push_str(&INDENT.repeat(indent));
+        out.push('}');
+    }
+
+    pub(crate) fn stmt(&self, out: &mut String, stmt: &Stmt, indent: usize) {
+        match stmt {
+            Stmt::Assign(lvalues, e) => {
+                write!(out, "{} = {}", self.lvalues(lvalues), self.exp(e)).unwrap()
+            }
+            Stmt::Let(lvalues, e) => {
+                write!(out, "let {} = {}", self.lvalues(lvalues), self.exp(e)).unwrap()
+            }
+            Stmt::Mutate(r, e) => {
+                let target = match r {
+                    Exp::BorrowField(_, base, field) => format!("{}.{}", self.path(base), field),
+                    r => format!("*{}", self.operand(r)),
+                };
+                write!(out, "{} = {}", target, self.exp(e)).unwrap()
+            }
+            Stmt::Exp(e) => out.push_str(&self.exp(e)),
+            Stmt::Abort(e) => write!(out, "abort {}", self.exp(e)).unwrap(),
+            Stmt::Return(es) => match es.len() {
+                0 => out.push_str("return"),
+                _ => write!(out, "return {}", self.exps(es)).unwrap(),
+            },
+            Stmt::If(cond, then, else_) => {
+                write!(out, "if ({}) ", self.exp(cond)).unwrap();
+                self.braced(out, then, indent);
+                if !else_.is_empty() {
+                    out.push_str(" else ");
+                    match else_.as_slice() {
+                        [if_ @ Stmt::If(..)] => self.stmt(out, if_, indent),
+                        _ => self.braced(out, else_, indent),
+                    }
+                }
+            }
+            Stmt::Loop(body) => {
+                out.push_str("loop ");
+                self.braced(out, body, indent);
+            }
+            Stmt::While(cond, body) => {
+                write!(out, "while ({}) ", self.exp(cond)).unwrap();
+                self.braced(out, body, indent);
+            }
+            Stmt::Break => out.push_str("break"),
+            Stmt::Continue => out.push_str("continue"),
+        }
+    }
+
+    /// Renders the values of a `return`, or of the trailing expression of a function.
+    pub(crate) fn exps(&self, es: &[Exp]) -> String {
+        match es {
+            [e] => self.exp(e),
+            es => format!(
+                "({})",
+                es.iter()
+                    .map(|e| self.exp(e))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    fn lvalues(&self, lvalues: &[LValue]) -> String {
+        match lvalues {
+            [lv] => self.lvalue(lv),
+            lvs => format!(
+                "({})",
+                lvs.iter()
+                    .map(|lv| self.lvalue(lv))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    fn lvalue(&self, lvalue: &LValue) -> String {
+        match lvalue {
+            LValue::Var(var) => (self.names)(*var),
+            LValue::Ignore => "_".to_string(),
+            LValue::Unpack(name, fields) => format!(
+                "{} {{ {} }}",
+                name,
+                fields
+                    .iter()
+                    .map(|(field, lv)| format!("{}: {}", field, self.lvalue(lv)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    pub(crate) fn exp(&self, e: &Exp) -> String {
+        match e {
+            Exp::Var(var) => (self.names)(*var),
+            Exp::Value(value) => value.clone(),
+            Exp::BorrowLocal(mut_, idx) => {
+                format!("{}{}", borrow(*mut_), (self.names)(Var::Local(*idx)))
+            }
+            Exp::BorrowField(mut_, base, field) => {
+                format!("{}{}.{}", borrow(*mut_), self.path(base), field)
+            }
+            // Reading a field through a reference is written as a field access.
+            Exp::Deref(r) => match r.as_ref() {
+                Exp::BorrowField(_, base, field) => format!("{}.{}", self.path(base), field),
+                r => format!("*{}", self.operand(r)),
+            },
+            Exp::Not(e) => format!("!{}", self.operand(e)),
+            Exp::Cast(e, ty) => format!("({} as {})", self.exp(e), ty),
+            Exp::Binary(op, lhs, rhs) => format!(
+                "{} {} {}",
+                self.binary_operand(lhs),
+                op,
+                self.binary_operand(rhs)
+            ),
+            Exp::Call(name, args) => format!(
+                "{}({})",
+                name,
+                args.iter()
+                    .map(|arg| self.exp(arg))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Exp::Pack(name, fields) => format!(
+                "{} {{ {} }}",
+                name,
+                fields
+                    .iter()
+                    .map(|(field, e)| format!("{}: {}", field, self.exp(e)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Renders the struct a field is borrowed from: `x` for a field of the local `x`, `x.f` for
+    /// a field of the field `f` of `x`, and otherwise the reference itself.
+    fn path(&self, base: &Exp) -> String {
+        match base {
+            Exp::BorrowLocal(_, idx) => (self.names)(Var::Local(*idx)),
+            Exp::BorrowField(_, base, field) => format!("{}.{}", self.path(base), field),
+            base => self.operand(base),
+        }
+    }
+
+    /// Renders the operand of a unary operator or of a field access.
+    fn operand(&self, e: &Exp) -> String {
+        match e {
+            Exp::Var(_) | Exp::Value(_) | Exp::Call(..) | Exp::Cast(..) => self.exp(e),
+            Exp::Deref(r) if matches!(r.as_ref(), Exp::BorrowField(..)) => self.exp(e),
+            e => format!("({})", self.exp(e)),
+        }
+    }
+
+    fn binary_operand(&self, e: &Exp) -> String {
+        match e {
+            Exp::Binary(..) => format!("({})", self.exp(e)),
+            e => self.exp(e),
+        }
+    }
+}
+
+fn borrow(mut_: bool) -> &'static str {
+    if mut_ {
+        "&mut "
+    } else {
+        "&"
+    }
+}
@@ -0,0 +1,1358 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Decompiles the body of a function.
+//!
+//! Each basic block is first executed symbolically, turning the values on the operand stack into
+//! expressions and the instructions with side effects into statements. The control flow graph is
+//! then structured: loops are found from the back edges of the dominator tree, and each branch
+//! is turned into an `if` that ends where the two sides meet again, at the immediate
+//! postdominator of the branch. Edges leaving a loop become `break`, edges back to the header
+//! `continue`.
+
+use crate::{
+    ast::{diverges, Exp, LValue, Printer, Stmt, Var},
+    ModuleContext,
+};
+use anyhow::{bail, Result};
+use bytecode_verifier::control_flow_graph::{BlockId, ControlFlowGraph, VMControlFlowGraph};
+use std::{
+    cell::Cell,
+    collections::{BTreeMap, BTreeSet},
+};
+use vm::{
+    access::ModuleAccess,
+    file_format::{
+        Bytecode, CodeOffset, FieldHandleIndex, FunctionDefinition, FunctionHandle, LocalIndex,
+        SignatureToken, StructDefInstantiationIndex, StructDefinitionIndex, StructFieldInformation,
+    },
+};
+
+/// The decompiled body of a function.
+pub(crate) struct FunctionBody {
+    pub(crate) param_names: Vec<String>,
+    /// The code between the braces of the function, indented.
+    pub(crate) code: String,
+}
+
+pub(crate) fn decompile(
+    context: &ModuleContext,
+    def: &FunctionDefinition,
+    type_params: &[String],
+) -> Result<FunctionBody> {
+    let module = context.module();
+    let code_unit = match &def.code {
+        Some(code_unit) => code_unit,
+        None => bail!("Native functions have no body"),
+    };
+    let handle = module.function_handle_at(def.function);
+    let num_params = module.signature_at(handle.parameters).len();
+    let local_types: Vec<_> = module
+        .signature_at(handle.parameters)
+        .0
+        .iter()
+        .chain(&module.signature_at(code_unit.locals).0)
+        .cloned()
+        .collect();
+
+    let code = &code_unit.code;
+    let cfg = VMControlFlowGraph::new(code);
+    let graph = Graph::new(&cfg);
+    let mut decompiler = Decompiler {
+        context,
+        type_params,
+        code,
+        cfg: &cfg,
+        dead_stores: dead_stores(code, &cfg, &graph, local_types.len()),
+        heights: BTreeMap::new(),
+        temps: Cell::new(0),
+    };
+    decompiler.heights = decompiler.stack_heights(&graph);
+    let mut blocks = BTreeMap::new();
+    for block in &graph.blocks {
+        blocks.insert(*block, decompiler.block_code(*block)?);
+    }
+
+    let structurer = Structurer::new(&graph, &blocks);
+    let mut stmts = vec![];
+    let mut budget = 16 * graph.blocks.len() + 64;
+    structurer.emit(
+        graph.entry,
+        Ctx {
+            stop: None,
+            loop_: None,
+        },
+        true,
+        &mut stmts,
+        &mut budget,
+    )?;
+    let mut stmts = simplify(stmts);
+    let result = match stmts.pop() {
+        Some(Stmt::Return(values)) => values,
+        Some(stmt) => {
+            stmts.push(stmt);
+            vec![]
+        }
+        None => vec![],
+    };
+
+    // Name the variables in use, parameters first.
+    let mut used: BTreeSet<Var> = BTreeSet::new();
+    let mut use_var = |var: Var| {
+        used.insert(var);
+    };
+    stmts.iter().for_each(|stmt| stmt.vars(&mut use_var));
+    result.iter().for_each(|e| e.vars(&mut use_var));
+    let mut namer = Namer::default();
+    let mut names = BTreeMap::new();
+    let mut param_names = vec![];
+    for (idx, ty) in local_types.iter().enumerate() {
+        let var = Var::Local(idx as LocalIndex);
+        if idx < num_params {
+            let name = namer.fresh(&decompiler.base_name(ty));
+            if used.contains(&var) {
+                param_names.push(name.clone());
+            } else {
+                param_names.push(format!("_{}", name));
+            }
+            names.insert(var, name);
+        } else if used.contains(&var) {
+            names.insert(var, namer.fresh(&decompiler.base_name(ty)));
+        }
+    }
+    for var in &used {
+        match var {
+            Var::Temp(_) => names.insert(*var, namer.fresh("tmp")),
+            Var::Stack(_) => names.insert(*var, namer.fresh("stack")),
+            Var::Local(_) => None,
+        };
+    }
+
+    let indent = "        ";
+    let mut out = String::new();
+    for (idx, ty) in local_types.iter().enumerate().skip(num_params) {
+        if let Some(name) = names.get(&Var::Local(idx as LocalIndex)) {
+            out.push_str(&format!(
+                "{}let {}: {};\n",
+                indent,
+                name,
+                context.type_(ty, type_params)
+            ));
+        }
+    }
+    for var in &used {
+        if let Var::Stack(_) = var {
+            out.push_str(&format!("{}let {};\n", indent, names[var]));
+        }
+    }
+    let name_of = |var: Var| names[&var].clone();
+    let printer = Printer { names: &name_of };
+    printer.body(&mut out, &stmts, &result, 2);
+    Ok(FunctionBody {
+        param_names,
+        code: out,
+    })
+}
+
+//**************************************************************************************************
+// Symbolic execution
+//**************************************************************************************************
+
+/// How control leaves a basic block.
+#[derive(Clone, Debug)]
+enum Exit {
+    Jump(BlockId),
+    /// Jumps to the first block if the condition holds, to the second otherwise.
+    Cond(Exp, BlockId, BlockId),
+    Return(Vec<Exp>),
+    Abort(Exp),
+}
+
+#[derive(Clone, Debug)]
+struct BlockCode {
+    stmts: Vec<Stmt>,
+    exit: Exit,
+}
+
+struct Decompiler<'a> {
+    context: &'a ModuleContext<'a>,
+    type_params: &'a [String],
+    code: &'a [Bytecode],
+    cfg: &'a VMControlFlowGraph,
+    // The offsets of the `StLoc` instructions storing a value that is never read.
+    dead_stores: BTreeSet<CodeOffset>,
+    // The height of the operand stack at the start of each block.
+    heights: BTreeMap<BlockId, usize>,
+    temps: Cell<usize>,
+}
+
+impl<'a> Decompiler<'a> {
+    fn block_code(&self, block: BlockId) -> Result<BlockCode> {
+        let height = self.heights.get(&block).copied().unwrap_or(0);
+        let mut stack: Vec<Exp> = (0..height).map(|i| Exp::Var(Var::Stack(i))).collect();
+        let mut stmts = vec![];
+        let end = self.cfg.block_end(block) as usize;
+        let mut pc = self.cfg.block_start(block) as usize;
+        while pc <= end {
+            match &self.code[pc] {
+                Bytecode::Pop => {
+                    let e = pop(&mut stack)?;
+                    self.spill(&mut stack, &mut stmts);
+                    stmts.push(Stmt::Assign(vec![LValue::Ignore], e));
+                }
+                Bytecode::Ret => {
+                    return Ok(BlockCode {
+                        stmts,
+                        exit: Exit::Return(stack),
+                    });
+                }
+                Bytecode::BrTrue(target) | Bytecode::BrFalse(target) => {
+                    let cond = pop(&mut stack)?;
+                    self.save_stack(stack, &mut stmts);
+                    let next = pc as BlockId + 1;
+                    let exit = if *target == next {
+                        stmts.push(Stmt::Assign(vec![LValue::Ignore], cond));
+                        Exit::Jump(next)
+                    } else if let Bytecode::BrTrue(_) = &self.code[pc] {
+                        Exit::Cond(cond, *target, next)
+                    } else {
+                        Exit::Cond(cond, next, *target)
+                    };
+                    return Ok(BlockCode { stmts, exit });
+                }
+                Bytecode::Branch(target) => {
+                    self.save_stack(stack, &mut stmts);
+                    return Ok(BlockCode {
+                        stmts,
+                        exit: Exit::Jump(*target),
+                    });
+                }
+                Bytecode::Abort => {
+                    let code = pop(&mut stack)?;
+                    for e in stack {
+                        if !matches!(e, Exp::Value(_)) {
+                            stmts.push(Stmt::Assign(vec![LValue::Ignore], e));
+                        }
+                    }
+                    return Ok(BlockCode {
+                        stmts,
+                        exit: Exit::Abort(code),
+                    });
+                }
+                Bytecode::LdU8(n) => stack.push(Exp::Value(format!("{}u8", n))),
+                Bytecode::LdU64(n) => stack.push(Exp::Value(n.to_string())),
+                Bytecode::LdU128(n) => stack.push(Exp::Value(format!("{}u128", n))),
+                Bytecode::LdConst(idx) => stack.push(Exp::Value(self.context.constant(*idx)?)),
+                Bytecode::LdTrue => stack.push(Exp::Value("true".to_string())),
+                Bytecode::LdFalse => stack.push(Exp::Value("false".to_string())),
+                Bytecode::CastU8 => cast(&mut stack, "u8")?,
+                Bytecode::CastU64 => cast(&mut stack, "u64")?,
+                Bytecode::CastU128 => cast(&mut stack, "u128")?,
+                Bytecode::CopyLoc(idx) | Bytecode::MoveLoc(idx) => {
+                    stack.push(Exp::Var(Var::Local(*idx)))
+                }
+                Bytecode::StLoc(idx) => {
+                    let e = pop(&mut stack)?;
+                    self.spill(&mut stack, &mut stmts);
+                    stmts.push(Stmt::Assign(vec![self.store_target(pc, *idx)], e));
+                }
+                Bytecode::Call(idx) => {
+                    let handle = self.context.module().function_handle_at(*idx);
+                    let name = self.context.function_name(*idx);
+                    pc = self.call(pc, end, handle, name, &mut stack, &mut stmts)?;
+                }
+                Bytecode::CallGeneric(idx) => {
+                    let module = self.context.module();
+                    let inst = module.function_instantiation_at(*idx);
+                    let handle = module.function_handle_at(inst.handle);
+                    let name = format!(
+                        "{}{}",
+                        self.context.function_name(inst.handle),
+                        self.context
+                            .type_arguments(inst.type_parameters, self.type_params)
+                    );
+                    pc = self.call(pc, end, handle, name, &mut stack, &mut stmts)?;
+                }
+                Bytecode::Pack(idx) => {
+                    let name = self.struct_type(*idx, None);
+                    self.pack(*idx, name, &mut stack)?;
+                }
+                Bytecode::PackGeneric(idx) => {
+                    let (def, name) = self.struct_instantiation(*idx);
+                    self.pack(def, name, &mut stack)?;
+                }
+                Bytecode::Unpack(idx) => {
+                    let name = self.struct_type(*idx, None);
+                    pc = self.unpack(pc, end, *idx, name, &mut stack, &mut stmts)?;
+                }
+                Bytecode::UnpackGeneric(idx) => {
+                    let (def, name) = self.struct_instantiation(*idx);
+                    pc = self.unpack(pc, end, def, name, &mut stack, &mut stmts)?;
+                }
+                Bytecode::ReadRef => {
+                    let r = pop(&mut stack)?;
+                    stack.push(Exp::Deref(Box::new(r)));
+                }
+                Bytecode::WriteRef => {
+                    let r = pop(&mut stack)?;
+                    let e = pop(&mut stack)?;
+                    self.spill(&mut stack, &mut stmts);
+                    stmts.push(Stmt::Mutate(r, e));
+                }
+                Bytecode::FreezeRef => {
+                    let r = pop(&mut stack)?;
+                    stack.push(Exp::Call("freeze".to_string(), vec![r]));
+                }
+                Bytecode::MutBorrowLoc(idx) => stack.push(Exp::BorrowLocal(true, *idx)),
+                Bytecode::ImmBorrowLoc(idx) => stack.push(Exp::BorrowLocal(false, *idx)),
+                Bytecode::MutBorrowField(idx) => self.borrow_field(true, *idx, &mut stack)?,
+                Bytecode::ImmBorrowField(idx) => self.borrow_field(false, *idx, &mut stack)?,
+                Bytecode::MutBorrowFieldGeneric(idx) => {
+                    let handle = self.context.module().field_instantiation_at(*idx).handle;
+                    self.borrow_field(true, handle, &mut stack)?
+                }
+                Bytecode::ImmBorrowFieldGeneric(idx) => {
+                    let handle = self.context.module().field_instantiation_at(*idx).handle;
+                    self.borrow_field(false, handle, &mut stack)?
+                }
+                Bytecode::MutBorrowGlobal(idx) => {
+                    let ty = self.struct_type(*idx, None);
+                    global(&mut stack, "borrow_global_mut", ty)?
+                }
+                Bytecode::MutBorrowGlobalGeneric(idx) => {
+                    let (_, ty) = self.struct_instantiation(*idx);
+                    global(&mut stack, "borrow_global_mut", ty)?
+                }
+                Bytecode::ImmBorrowGlobal(idx) => {
+                    let ty = self.struct_type(*idx, None);
+                    global(&mut stack, "borrow_global", ty)?
+                }
+                Bytecode::ImmBorrowGlobalGeneric(idx) => {
+                    let (_, ty) = self.struct_instantiation(*idx);
+                    global(&mut stack, "borrow_global", ty)?
+                }
+                Bytecode::Exists(idx) => {
+                    let ty = self.struct_type(*idx, None);
+                    global(&mut stack, "exists", ty)?
+                }
+                Bytecode::ExistsGeneric(idx) => {
+                    let (_, ty) = self.struct_instantiation(*idx);
+                    global(&mut stack, "exists", ty)?
+                }
+                Bytecode::MoveFrom(idx) => {
+                    let ty = self.struct_type(*idx, None);
+                    global(&mut stack, "move_from", ty)?
+                }
+                Bytecode::MoveFromGeneric(idx) => {
+                    let (_, ty) = self.struct_instantiation(*idx);
+                    global(&mut stack, "move_from", ty)?
+                }
+                Bytecode::MoveTo(idx) => {
+                    let ty = self.struct_type(*idx, None);
+                    self.move_to(ty, &mut stack, &mut stmts)?
+                }
+                Bytecode::MoveToGeneric(idx) => {
+                    let (_, ty) = self.struct_instantiation(*idx);
+                    self.move_to(ty, &mut stack, &mut stmts)?
+                }
+                Bytecode::Add => binary(&mut stack, "+")?,
+                Bytecode::Sub => binary(&mut stack, "-")?,
+                Bytecode::Mul => binary(&mut stack, "*")?,
+                Bytecode::Mod => binary(&mut stack, "%")?,
+                Bytecode::Div => binary(&mut stack, "/")?,
+                Bytecode::BitOr => binary(&mut stack, "|")?,
+                Bytecode::BitAnd => binary(&mut stack, "&")?,
+                Bytecode::Xor => binary(&mut stack, "^")?,
+                Bytecode::Shl => binary(&mut stack, "<<")?,
+                Bytecode::Shr => binary(&mut stack, ">>")?,
+                Bytecode::Or => binary(&mut stack, "||")?,
+                Bytecode::And => binary(&mut stack, "&&")?,
+                Bytecode::Eq => binary(&mut stack, "==")?,
+                Bytecode::Neq => binary(&mut stack, "!=")?,
+                Bytecode::Lt => binary(&mut stack, "<")?,
+                Bytecode::Gt => binary(&mut stack, ">")?,
+                Bytecode::Le => binary(&mut stack, "<=")?,
+                Bytecode::Ge => binary(&mut stack, ">=")?,
+                Bytecode::Not => {
+                    let e = pop(&mut stack)?;
+                    stack.push(Exp::Not(Box::new(e)));
+                }
+                Bytecode::Nop => (),
+            }
+            pc += 1;
+        }
+        // The block falls through to the next one.
+        self.save_stack(stack, &mut stmts);
+        Ok(BlockCode {
+            stmts,
+            exit: Exit::Jump(end as BlockId + 1),
+        })
+    }
+
+    /// Moves the values on the stack that a statement could change to temporaries, so that
+    /// they are evaluated before the statement, as in the bytecode.
+    fn spill(&self, stack: &mut Vec<Exp>, stmts: &mut Vec<Stmt>) {
+        for e in stack.iter_mut() {
+            if !e.is_stable() {
+                let temp = self.new_temp();
+                let value = std::mem::replace(e, Exp::Var(temp));
+                stmts.push(Stmt::Let(vec![LValue::Var(temp)], value));
+            }
+        }
+    }
+
+    /// Stores the values left on the stack at the end of a block in the variables the next
+    /// blocks read them from.
+    fn save_stack(&self, stack: Vec<Exp>, stmts: &mut Vec<Stmt>) {
+        let is_slot = |i: usize, e: &Exp| *e == Exp::Var(Var::Stack(i));
+        // Assigning a slot could change the value of another slot still to be assigned.
+        let through_temps = stack
+            .iter()
+            .enumerate()
+            .any(|(i, e)| !is_slot(i, e) && e.mentions_stack());
+        let mut assigns = vec![];
+        for (i, e) in stack.into_iter().enumerate() {
+            if is_slot(i, &e) {
+                continue;
+            }
+            let e = if through_temps {
+                let temp = self.new_temp();
+                stmts.push(Stmt::Let(vec![LValue::Var(temp)], e));
+                Exp::Var(temp)
+            } else {
+                e
+            };
+            assigns.push(Stmt::Assign(vec![LValue::Var(Var::Stack(i))], e));
+        }
+        stmts.extend(assigns);
+    }
+
+    fn new_temp(&self) -> Var {
+        let temp = self.temps.get();
+        self.temps.set(temp + 1);
+        Var::Temp(temp)
+    }
+
+    fn store_target(&self, pc: usize, idx: LocalIndex) -> LValue {
+        if self.dead_stores.contains(&(pc as CodeOffset)) {
+            LValue::Ignore
+        } else {
+            LValue::Var(Var::Local(idx))
+        }
+    }
+
+    /// The targets of the `count` values stored by the instructions following `pc` in the
+    /// block, if they all store a value, the first value first.
+    fn stored_values(&self, pc: usize, end: usize, count: usize) -> Option<Vec<LValue>> {
+        if pc + count > end {
+            return None;
+        }
+        let mut lvalues = vec![LValue::Ignore; count];
+        for k in 0..count {
+            let store_pc = pc + 1 + k;
+            lvalues[count - 1 - k] = match &self.code[store_pc] {
+                Bytecode::StLoc(idx) => self.store_target(store_pc, *idx),
+                Bytecode::Pop => LValue::Ignore,
+                _ => return None,
+            };
+        }
+        Some(lvalues)
+    }
+
+    /// Decompiles a call and returns the offset of its last instruction, which includes the
+    /// stores of its results when they are assigned at once.
+    fn call(
+        &self,
+        pc: usize,
+        end: usize,
+        handle: &FunctionHandle,
+        name: String,
+        stack: &mut Vec<Exp>,
+        stmts: &mut Vec<Stmt>,
+    ) -> Result<usize> {
+        let module = self.context.module();
+        let num_args = module.signature_at(handle.parameters).len();
+        let num_results = module.signature_at(handle.return_).len();
+        let args = pop_n(stack, num_args)?;
+        let call = Exp::Call(name, args);
+        match num_results {
+            0 => {
+                self.spill(stack, stmts);
+                stmts.push(Stmt::Exp(call));
+            }
+            1 => stack.push(call),
+            n => {
+                self.spill(stack, stmts);
+                if let Some(lvalues) = self.stored_values(pc, end, n) {
+                    stmts.push(Stmt::Assign(lvalues, call));
+                    return Ok(pc + n);
+                }
+                let temps: Vec<_> = (0..n).map(|_| self.new_temp()).collect();
+                stmts.push(Stmt::Let(
+                    temps.iter().map(|temp| LValue::Var(*temp)).collect(),
+                    call,
+                ));
+                stack.extend(temps.into_iter().map(Exp::Var));
+            }
+        }
+        Ok(pc)
+    }
+
+    fn pack(&self, def: StructDefinitionIndex, name: String, stack: &mut Vec<Exp>) -> Result<()> {
+        let fields = self.field_names(def);
+        let values = pop_n(stack, fields.len())?;
+        stack.push(Exp::Pack(name, fields.into_iter().zip(values).collect()));
+        Ok(())
+    }
+
+    /// Decompiles an unpack and returns the offset of its last instruction, which includes the
+    /// stores of the fields when they are assigned at once.
+    fn unpack(
+        &self,
+        pc: usize,
+        end: usize,
+        def: StructDefinitionIndex,
+        name: String,
+        stack: &mut Vec<Exp>,
+        stmts: &mut Vec<Stmt>,
+    ) -> Result<usize> {
+        let fields = self.field_names(def);
+        let e = pop(stack)?;
+        self.spill(stack, stmts);
+        if let Some(lvalues) = self.stored_values(pc, end, fields.len()) {
+            let pattern = LValue::Unpack(name, fields.iter().cloned().zip(lvalues).collect());
+            stmts.push(Stmt::Assign(vec![pattern], e));
+            return Ok(pc + fields.len());
+        }
+        let temps: Vec<_> = fields.iter().map(|_| self.new_temp()).collect();
+        let pattern = LValue::Unpack(
+            name,
+            fields
+                .into_iter()
+                .zip(temps.iter().map(|temp| LValue::Var(*temp)))
+                .collect(),
+        );
+        stmts.push(Stmt::Let(vec![pattern], e));
+        stack.extend(temps.into_iter().map(Exp::Var));
+        Ok(pc)
+    }
+
+    fn borrow_field(&self, mut_: bool, idx: FieldHandleIndex, stack: &mut Vec<Exp>) -> Result<()> {
+        let handle = self.context.module().field_handle_at(idx);
+        let field = self.field_names(handle.owner)[handle.field as usize].clone();
+        let r = pop(stack)?;
+        stack.push(Exp::BorrowField(mut_, Box::new(r), field));
+        Ok(())
+    }
+
+    fn move_to(&self, ty: String, stack: &mut Vec<Exp>, stmts: &mut Vec<Stmt>) -> Result<()> {
+        let value = pop(stack)?;
+        let signer = pop(stack)?;
+        self.spill(stack, stmts);
+        stmts.push(Stmt::Exp(Exp::Call(
+            format!("move_to<{}>", ty),
+            vec![signer, value],
+        )));
+        Ok(())
+    }
+
+    fn field_names(&self, idx: StructDefinitionIndex) -> Vec<String> {
+        let module = self.context.module();
+        match &module.struct_def_at(idx).field_information {
+            StructFieldInformation::Native => vec![],
+            StructFieldInformation::Declared(fields) => fields
+                .iter()
+                .map(|field| module.identifier_at(field.name).to_string())
+                .collect(),
+        }
+    }
+
+    /// The name of a struct, followed by its type arguments if any.
+    fn struct_type(&self, idx: StructDefinitionIndex, type_args: Option<String>) -> String {
+        let module = self.context.module();
+        let name = self
+            .context
+            .struct_name(module.struct_def_at(idx).struct_handle);
+        format!("{}{}", name, type_args.unwrap_or_default())
+    }
+
+    fn struct_instantiation(
+        &self,
+        idx: StructDefInstantiationIndex,
+    ) -> (StructDefinitionIndex, String) {
+        let inst = self.context.module().struct_instantiation_at(idx);
+        let type_args = self
+            .context
+            .type_arguments(inst.type_parameters, self.type_params);
+        (inst.def, self.struct_type(inst.def, Some(type_args)))
+    }
+
+    /// The number of values an instruction pops from the stack and pushes onto it.
+    fn stack_effect(&self, instr: &Bytecode) -> (usize, usize) {
+        let module = self.context.module();
+        let call = |handle: &FunctionHandle| {
+            (
+                module.signature_at(handle.parameters).len(),
+                module.signature_at(handle.return_).len(),
+            )
+        };
+        match instr {
+            Bytecode::Call(idx) => call(module.function_handle_at(*idx)),
+            Bytecode::CallGeneric(idx) => {
+                call(module.function_handle_at(module.function_instantiation_at(*idx).handle))
+            }
+            Bytecode::Pack(idx) => (self.field_names(*idx).len(), 1),
+            Bytecode::PackGeneric(idx) => {
+                let def = module.struct_instantiation_at(*idx).def;
+                (self.field_names(def).len(), 1)
+            }
+            Bytecode::Unpack(idx) => (1, self.field_names(*idx).len()),
+            Bytecode::UnpackGeneric(idx) => {
+                let def = module.struct_instantiation_at(*idx).def;
+                (1, self.field_names(def).len())
+            }
+            Bytecode::Ret => (0, 0),
+            Bytecode::Pop
+            | Bytecode::BrTrue(_)
+            | Bytecode::BrFalse(_)
+            | Bytecode::StLoc(_)
+            | Bytecode::Abort => (1, 0),
+            Bytecode::Branch(_) | Bytecode::Nop => (0, 0),
+            Bytecode::LdU8(_)
+            | Bytecode::LdU64(_)
+            | Bytecode::LdU128(_)
+            | Bytecode::LdConst(_)
+            | Bytecode::LdTrue
+            | Bytecode::LdFalse
+            | Bytecode::CopyLoc(_)
+            | Bytecode::MoveLoc(_)
+            | Bytecode::MutBorrowLoc(_)
+            | Bytecode::ImmBorrowLoc(_) => (0, 1),
+            Bytecode::CastU8
+            | Bytecode::CastU64
+            | Bytecode::CastU128
+            | Bytecode::ReadRef
+            | Bytecode::FreezeRef
+            | Bytecode::MutBorrowField(_)
+            | Bytecode::MutBorrowFieldGeneric(_)
+            | Bytecode::ImmBorrowField(_)
+            | Bytecode::ImmBorrowFieldGeneric(_)
+            | Bytecode::MutBorrowGlobal(_)
+            | Bytecode::MutBorrowGlobalGeneric(_)
+            | Bytecode::ImmBorrowGlobal(_)
+            | Bytecode::ImmBorrowGlobalGeneric(_)
+            | Bytecode::Exists(_)
+            | Bytecode::ExistsGeneric(_)
+            | Bytecode::MoveFrom(_)
+            | Bytecode::MoveFromGeneric(_)
+            | Bytecode::Not => (1, 1),
+            Bytecode::WriteRef | Bytecode::MoveTo(_) | Bytecode::MoveToGeneric(_) => (2, 0),
+            Bytecode::Add
+            | Bytecode::Sub
+            | Bytecode::Mul
+            | Bytecode::Mod
+            | Bytecode::Div
+            | Bytecode::BitOr
+            | Bytecode::BitAnd
+            | Bytecode::Xor
+            | Bytecode::Shl
+            | Bytecode::Shr
+            | Bytecode::Or
+            | Bytecode::And
+            | Bytecode::Eq
+            | Bytecode::Neq
+            | Bytecode::Lt
+            | Bytecode::Gt
+            | Bytecode::Le
+            | Bytecode::Ge => (2, 1),
+        }
+    }
+
+    /// The height of the operand stack at the start of each reachable block.
+    fn stack_heights(&self, graph: &Graph) -> BTreeMap<BlockId, usize> {
+        let mut heights = BTreeMap::new();
+        heights.insert(graph.entry, 0);
+        let mut work = vec![graph.entry];
+        while let Some(block) = work.pop() {
+            let mut height = heights[&block];
+            for pc in self.cfg.block_start(block)..=self.cfg.block_end(block) {
+                let (pops, pushes) = self.stack_effect(&self.code[pc as usize]);
+                height = height.saturating_sub(pops) + pushes;
+            }
+            for succ in &graph.succs[&block] {
+                if !heights.contains_key(succ) {
+                    heights.insert(*succ, height);
+                    work.push(*succ);
+                }
+            }
+        }
+        heights
+    }
+
+    /// The name a local of type `ty` is given, before it is made unique.
+    fn base_name(&self, ty: &SignatureToken) -> String {
+        match ty {
+            SignatureToken::Signer => "account".to_string(),
+            SignatureToken::Address => "addr".to_string(),
+            SignatureToken::Bool => "flag".to_string(),
+            SignatureToken::U8 | SignatureToken::U64 | SignatureToken::U128 => "n".to_string(),
+            SignatureToken::Vector(_) => "v".to_string(),
+            SignatureToken::Struct(idx) | SignatureToken::StructInstantiation(idx, _) => {
+                let module = self.context.module();
+                snake_case(
+                    module
+                        .identifier_at(module.struct_handle_at(*idx).name)
+                        .as_str(),
+                )
+            }
+            SignatureToken::Reference(ty) | SignatureToken::MutableReference(ty) => {
+                self.base_name(ty)
+            }
+            SignatureToken::TypeParameter(_) => "x".to_string(),
+        }
+    }
+}
+
+fn pop(stack: &mut Vec<Exp>) -> Result<Exp> {
+    match stack.pop() {
+        Some(e) => Ok(e),
+        None => bail!("Operand stack underflow"),
+    }
+}
+
+/// Pops `count` values, the deepest first.
+fn pop_n(stack: &mut Vec<Exp>, count: usize) -> Result<Vec<Exp>> {
+    if stack.len() < count {
+        bail!("Operand stack underflow");
+    }
+    Ok(stack.split_off(stack.len() - count))
+}
+
+fn cast(stack: &mut Vec<Exp>, ty: &'static str) -> Result<()> {
+    let e = pop(stack)?;
+    stack.push(Exp::Cast(Box::new(e), ty));
+    Ok(())
+}
+
+fn binary(stack: &mut Vec<Exp>, op: &'static str) -> Result<()> {
+    let rhs = pop(stack)?;
+    let lhs = pop(stack)?;
+    stack.push(Exp::Binary(op, Box::new(lhs), Box::new(rhs)));
+    Ok(())
+}
+
+fn global(stack: &mut Vec<Exp>, builtin: &str, ty: String) -> Result<()> {
+    let addr = pop(stack)?;
+    stack.push(Exp::Call(format!("{}<{}>", builtin, ty), vec![addr]));
+    Ok(())
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Hands out unique local names.
+#[derive(Default)]
+struct Namer {
+    taken: BTreeSet<String>,
+}
+
+const KEYWORDS: &[&str] = &[
+    "abort",
+    "acquires",
+    "address",
+    "as",
+    "break",
+    "const",
+    "continue",
+    "copy",
+    "copyable",
+    "define",
+    "else",
+    "false",
+    "fun",
+    "if",
+    "invariant",
+    "let",
+    "loop",
+    "module",
+    "move",
+    "native",
+    "public",
+    "resource",
+    "return",
+    "script",
+    "spec",
+    "struct",
+    "true",
+    "use",
+    "while",
+];
+
+impl Namer {
+    fn fresh(&mut self, base: &str) -> String {
+        if !KEYWORDS.contains(&base) && self.taken.insert(base.to_string()) {
+            return base.to_string();
+        }
+        let mut suffix = 1;
+        loop {
+            let name = format!("{}_{}", base, suffix);
+            if self.taken.insert(name.clone()) {
+                return name;
+            }
+            suffix += 1;
+        }
+    }
+}
+
+/// The offsets of the stores to a local whose value is never read.
+fn dead_stores(
+    code: &[Bytecode],
+    cfg: &VMControlFlowGraph,
+    graph: &Graph,
+    num_locals: usize,
+) -> BTreeSet<CodeOffset> {
+    // Updates `live` to the locals live before the instruction at `pc`, and returns whether the
+    // instruction is a dead store.
+    let step = |pc: CodeOffset, live: &mut Vec<bool>| match &code[pc as usize] {
+        Bytecode::StLoc(idx) => {
+            let dead = !live[*idx as usize];
+            live[*idx as usize] = false;
+            dead
+        }
+        Bytecode::CopyLoc(idx)
+        | Bytecode::MoveLoc(idx)
+        | Bytecode::MutBorrowLoc(idx)
+        | Bytecode::ImmBorrowLoc(idx) => {
+            live[*idx as usize] = true;
+            false
+        }
+        _ => false,
+    };
+    let live_before = |block: BlockId, live_out: &BTreeMap<BlockId, Vec<bool>>| {
+        let mut live = live_out[&block].clone();
+        for pc in (cfg.block_start(block)..=cfg.block_end(block)).rev() {
+            step(pc, &mut live);
+        }
+        live
+    };
+
+    let mut live_out: BTreeMap<BlockId, Vec<bool>> = graph
+        .blocks
+        .iter()
+        .map(|block| (*block, vec![false; num_locals]))
+        .collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in graph.blocks.iter().rev() {
+            let mut live = vec![false; num_locals];
+            for succ in &graph.succs[block] {
+                for (idx, is_live) in live_before(*succ, &live_out).into_iter().enumerate() {
+                    live[idx] |= is_live;
+                }
+            }
+            if live != live_out[block] {
+                live_out.insert(*block, live);
+                changed = true;
+            }
+        }
+    }
+
+    let mut dead = BTreeSet::new();
+    for block in &graph.blocks {
+        let mut live = live_out[block].clone();
+        for pc in (cfg.block_start(*block)..=cfg.block_end(*block)).rev() {
+            if step(pc, &mut live) {
+                dead.insert(pc);
+            }
+        }
+    }
+    dead
+}
+
+//**************************************************************************************************
+// Structuring
+//**************************************************************************************************
+
+/// The blocks reachable from the entry of a function and their successors.
+struct Graph {
+    entry: BlockId,
+    /// The reachable blocks, in reverse postorder.
+    blocks: Vec<BlockId>,
+    succs: BTreeMap<BlockId, Vec<BlockId>>,
+}
+
+impl Graph {
+    fn new(cfg: &VMControlFlowGraph) -> Self {
+        let entry = cfg.entry_block_id();
+        let mut postorder = vec![];
+        let mut visited = BTreeSet::new();
+        visited.insert(entry);
+        // Each entry is a block and the number of its successors already visited.
+        let mut stack = vec![(entry, 0)];
+        while let Some((block, next)) = stack.pop() {
+            let succs = cfg.successors(block);
+            if next < succs.len() {
+                stack.push((block, next + 1));
+                let succ = succs[next];
+                if visited.insert(succ) {
+                    stack.push((succ, 0));
+                }
+            } else {
+                postorder.push(block);
+            }
+        }
+        postorder.reverse();
+        let succs = postorder
+            .iter()
+            .map(|block| (*block, cfg.successors(*block).clone()))
+            .collect();
+        Self {
+            entry,
+            blocks: postorder,
+            succs,
+        }
+    }
+}
+
+/// Where the code being emitted is.
+#[derive(Clone, Copy)]
+struct Ctx {
+    /// The block where the enclosing `if` branch ends.
+    stop: Option<BlockId>,
+    /// The header and the follow block of the innermost enclosing loop.
+    loop_: Option<(BlockId, Option<BlockId>)>,
+}
+
+struct Structurer<'a> {
+    blocks: &'a BTreeMap<BlockId, BlockCode>,
+    /// The blocks of each loop, by header.
+    loops: BTreeMap<BlockId, BTreeSet<BlockId>>,
+    /// The block each loop continues with once it is left, by header.
+    follows: BTreeMap<BlockId, Option<BlockId>>,
+    /// The blocks reachable from each block.
+    reach: BTreeMap<BlockId, BTreeSet<BlockId>>,
+    /// The block where the two sides of each branch meet again, if any.
+    joins: BTreeMap<BlockId, Option<BlockId>>,
+}
+
+/// Stands for the exit of a region in the postdominator computation.
+const EXIT: BlockId = BlockId::max_value();
+
+impl<'a> Structurer<'a> {
+    fn new(graph: &Graph, blocks: &'a BTreeMap<BlockId, BlockCode>) -> Self {
+        let mut preds: BTreeMap<BlockId, Vec<BlockId>> = BTreeMap::new();
+        for (block, succs) in &graph.succs {
+            for succ in succs {
+                preds.entry(*succ).or_default().push(*block);
+            }
+        }
+
+        // Dominators.
+        let all: BTreeSet<_> = graph.blocks.iter().copied().collect();
+        let mut doms: BTreeMap<BlockId, BTreeSet<BlockId>> = graph
+            .blocks
+            .iter()
+            .map(|block| (*block, all.clone()))
+            .collect();
+        doms.insert(graph.entry, [graph.entry].iter().copied().collect());
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in graph.blocks.iter().skip(1) {
+                let mut dom: Option<BTreeSet<BlockId>> = None;
+                for pred in preds.get(block).into_iter().flatten() {
+                    dom = Some(match dom {
+                        None => doms[pred].clone(),
+                        Some(dom) => dom.intersection(&doms[pred]).copied().collect(),
+                    });
+                }
+                let mut dom = dom.unwrap_or_default();
+                dom.insert(*block);
+                if dom != doms[block] {
+                    doms.insert(*block, dom);
+                    changed = true;
+                }
+            }
+        }
+
+        // Natural loops, from the back edges.
+        let mut loops: BTreeMap<BlockId, BTreeSet<BlockId>> = BTreeMap::new();
+        for (block, succs) in &graph.succs {
+            for header in succs {
+                if doms[block].contains(header) {
+                    let body = loops.entry(*header).or_default();
+                    body.insert(*header);
+                    let mut work = vec![*block];
+                    while let Some(node) = work.pop() {
+                        if body.insert(node) {
+                            work.extend(preds.get(&node).into_iter().flatten().copied());
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut reach = BTreeMap::new();
+        for block in &graph.blocks {
+            let mut seen = BTreeSet::new();
+            let mut work = vec![*block];
+            while let Some(node) = work.pop() {
+                if seen.insert(node) {
+                    work.extend(graph.succs[&node].iter().copied());
+                }
+            }
+            reach.insert(*block, seen);
+        }
+
+        let mut structurer = Self {
+            blocks,
+            loops,
+            follows: BTreeMap::new(),
+            reach,
+            joins: BTreeMap::new(),
+        };
+        let headers: Vec<_> = structurer.loops.keys().copied().collect();
+        for header in &headers {
+            let follow = structurer.follow(graph, *header);
+            structurer.follows.insert(*header, follow);
+        }
+        let mut regions: Vec<Option<BlockId>> = vec![None];
+        regions.extend(headers.into_iter().map(Some));
+        for region in regions {
+            structurer.compute_joins(graph, region);
+        }
+        structurer
+    }
+
+    /// The innermost loop containing `block`.
+    fn loop_of(&self, block: BlockId) -> Option<BlockId> {
+        self.loops
+            .iter()
+            .filter(|(_, body)| body.contains(&block))
+            .min_by_key(|(_, body)| body.len())
+            .map(|(header, _)| *header)
+    }
+
+    /// Chooses the block a loop continues with among the blocks it can exit to: the one the
+    /// others lead to, the one leading to the most code otherwise.
+    fn follow(&self, graph: &Graph, header: BlockId) -> Option<BlockId> {
+        let body = &self.loops[&header];
+        let exits: BTreeSet<_> = body
+            .iter()
+            .flat_map(|block| graph.succs[block].iter().copied())
+            .filter(|succ| !body.contains(succ))
+            .collect();
+        exits.iter().copied().max_by_key(|exit| {
+            let reached_from = exits
+                .iter()
+                .filter(|other| *other != exit && self.reach[*other].contains(exit))
+                .count();
+            (reached_from, self.reach[exit].len(), *exit)
+        })
+    }
+
+    /// Computes the joins of the branches whose innermost loop is `region`, or which are in no
+    /// loop if `region` is `None`.
+    fn compute_joins(&mut self, graph: &Graph, region: Option<BlockId>) {
+        let nodes: BTreeSet<BlockId> = match region {
+            Some(header) => self.loops[&header].clone(),
+            None => graph.blocks.iter().copied().collect(),
+        };
+        // Edges back to the header and out of the loop leave the region.
+        let targets = |block: BlockId| -> Vec<Option<BlockId>> {
+            graph.succs[&block]
+                .iter()
+                .map(|succ| {
+                    if nodes.contains(succ) && Some(*succ) != region {
+                        Some(*succ)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        };
+        // The branches meet where all paths through the region meet. When they only meet at
+        // the exit, the branches that leave the region are ignored instead, so that an early
+        // `return`, `abort`, `break` or `continue` does not hide the join of the other paths.
+        let strict = postdominators(&nodes, &targets, false);
+        let relaxed = postdominators(&nodes, &targets, true);
+        for block in &nodes {
+            if self.loop_of(*block) != region {
+                continue;
+            }
+            if let Some(Exit::Cond(..)) = self.blocks.get(block).map(|code| &code.exit) {
+                let join = immediate_postdominator(*block, &strict)
+                    .or_else(|| immediate_postdominator(*block, &relaxed));
+                self.joins.insert(*block, join);
+            }
+        }
+    }
+
+    fn join(&self, block: BlockId, ctx: &Ctx) -> Option<BlockId> {
+        let join = self.joins.get(&block).copied().flatten()?;
+        if let Some((header, Some(follow))) = ctx.loop_ {
+            // A join after the loop is reached with a `break` instead.
+            if !self.loops[&header].contains(&join)
+                && join != follow
+                && self.reach[&follow].contains(&join)
+            {
+                return None;
+            }
+        }
+        Some(join)
+    }
+
+    /// Emits the code starting at `block` until `ctx.stop`, or until control leaves the
+    /// function or the loop. `first` is set when `block` is the header of the loop of `ctx`,
+    /// entered for the first time.
+    fn emit(
+        &self,
+        mut block: BlockId,
+        ctx: Ctx,
+        mut first: bool,
+        out: &mut Vec<Stmt>,
+        budget: &mut usize,
+    ) -> Result<()> {
+        loop {
+            if *budget == 0 {
+                bail!("Unable to structure the control flow");
+            }
+            *budget -= 1;
+            if Some(block) == ctx.stop {
+                return Ok(());
+            }
+            if let Some((header, follow)) = ctx.loop_ {
+                if block == header && !first {
+                    out.push(Stmt::Continue);
+                    return Ok(());
+                }
+                if Some(block) == follow {
+                    out.push(Stmt::Break);
+                    return Ok(());
+                }
+            }
+            let entering = first && ctx.loop_.map(|(header, _)| header) == Some(block);
+            first = false;
+            if self.loops.contains_key(&block) && !entering {
+                let follow = self.follows[&block];
+                let mut body = vec![];
+                let loop_ctx = Ctx {
+                    stop: None,
+                    loop_: Some((block, follow)),
+                };
+                self.emit(block, loop_ctx, true, &mut body, budget)?;
+                out.push(Stmt::Loop(body));
+                match follow {
+                    Some(follow) => {
+                        block = follow;
+                        continue;
+                    }
+                    None => return Ok(()),
+                }
+            }
+
+            let code = &self.blocks[&block];
+            out.extend(code.stmts.iter().cloned());
+            match &code.exit {
+                Exit::Jump(target) => block = *target,
+                Exit::Return(values) => {
+                    out.push(Stmt::Return(values.clone()));
+                    return Ok(());
+                }
+                Exit::Abort(e) => {
+                    out.push(Stmt::Abort(e.clone()));
+                    return Ok(());
+                }
+                Exit::Cond(cond, then, else_) => {
+                    let join = self.join(block, &ctx);
+                    let branch_ctx = Ctx {
+                        stop: join.or(ctx.stop),
+                        loop_: ctx.loop_,
+                    };
+                    let mut then_stmts = vec![];
+                    self.emit(*then, branch_ctx, false, &mut then_stmts, budget)?;
+                    let mut else_stmts = vec![];
+                    self.emit(*else_, branch_ctx, false, &mut else_stmts, budget)?;
+                    out.push(Stmt::If(cond.clone(), then_stmts, else_stmts));
+                    match join {
+                        Some(join) => block = join,
+                        None => return Ok(()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The postdominators of each node of a region, `None` standing for all nodes. Edges to `None`
+/// leave the region: they lead to the exit, or to any node if `relaxed` is set, as do nodes
+/// without successors.
+fn postdominators(
+    nodes: &BTreeSet<BlockId>,
+    targets: &dyn Fn(BlockId) -> Vec<Option<BlockId>>,
+    relaxed: bool,
+) -> BTreeMap<BlockId, Option<BTreeSet<BlockId>>> {
+    let exit: Option<BTreeSet<BlockId>> = if relaxed {
+        None
+    } else {
+        Some([EXIT].iter().copied().collect())
+    };
+    let mut pdoms: BTreeMap<BlockId, Option<BTreeSet<BlockId>>> =
+        nodes.iter().map(|node| (*node, None)).collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for node in nodes.iter().rev() {
+            let succs = targets(*node);
+            let mut pdom: Option<BTreeSet<BlockId>> =
+                if succs.is_empty() { exit.clone() } else { None };
+            for succ in succs {
+                let succ_pdom = match succ {
+                    Some(succ) => &pdoms[&succ],
+                    None => &exit,
+                };
+                pdom = match (pdom, succ_pdom) {
+                    (None, other) => other.clone(),
+                    (pdom, None) => pdom,
+                    (Some(pdom), Some(other)) => Some(pdom.intersection(other).copied().collect()),
+                };
+            }
+            let pdom = pdom.map(|mut pdom| {
+                pdom.insert(*node);
+                pdom
+            });
+            if pdom != pdoms[node] {
+                pdoms.insert(*node, pdom);
+                changed = true;
+            }
+        }
+    }
+    pdoms
+}
+
+fn immediate_postdominator(
+    node: BlockId,
+    pdoms: &BTreeMap<BlockId, Option<BTreeSet<BlockId>>>,
+) -> Option<BlockId> {
+    // The closest strict postdominator is the one with the most postdominators itself.
+    pdoms[&node]
+        .as_ref()?
+        .iter()
+        .filter(|pdom| **pdom != node && **pdom != EXIT)
+        .max_by_key(|pdom| pdoms[*pdom].as_ref().map_or(0, BTreeSet::len))
+        .copied()
+}
+
+//**************************************************************************************************
+// Cleanup
+//**************************************************************************************************
+
+/// Rewrites the structured code into the forms a programmer would write.
+fn simplify(stmts: Vec<Stmt>) -> Vec<Stmt> {
+    let mut out = vec![];
+    for stmt in stmts {
+        match stmt {
+            Stmt::If(cond, then, else_) => {
+                simplify_if(cond, simplify(then), simplify(else_), &mut out)
+            }
+            Stmt::Loop(mut body) => {
+                strip_continue(&mut body);
+                if let [Stmt::If(cond, then, else_)] = body.as_slice() {
+                    let while_ = if else_.as_slice() == [Stmt::Break] {
+                        Some((cond.clone(), then.clone()))
+                    } else if then.as_slice() == [Stmt::Break] {
+                        Some((negate(cond.clone()), else_.clone()))
+                    } else {
+                        None
+                    };
+                    if let Some((cond, mut body)) = while_ {
+                        strip_continue(&mut body);
+                        out.push(Stmt::While(cond, simplify(body)));
+                        continue;
+                    }
+                }
+                let mut body = simplify(body);
+                strip_continue(&mut body);
+                out.push(Stmt::Loop(body));
+            }
+            Stmt::While(cond, body) => out.push(Stmt::While(cond, simplify(body))),
+            stmt => out.push(stmt),
+        }
+    }
+    out
+}
+
+fn simplify_if(cond: Exp, then: Vec<Stmt>, else_: Vec<Stmt>, out: &mut Vec<Stmt>) {
+    // `a && b` and `a || b` compile to branches assigning the same variable.
+    if let ([Stmt::Assign(lhs, then_value)], [Stmt::Assign(else_lhs, else_value)]) =
+        (then.as_slice(), else_.as_slice())
+    {
+        if lhs == else_lhs && matches!(lhs.as_slice(), [LValue::Var(_)]) {
+            let false_ = Exp::Value("false".to_string());
+            let true_ = Exp::Value("true".to_string());
+            if *else_value == false_ {
+                let value = Exp::Binary("&&", Box::new(cond), Box::new(then_value.clone()));
+                out.push(Stmt::Assign(lhs.clone(), value));
+                return;
+            }
+            if *then_value == true_ {
+                let value = Exp::Binary("||", Box::new(cond), Box::new(else_value.clone()));
+                out.push(Stmt::Assign(lhs.clone(), value));
+                return;
+            }
+        }
+    }
+    let abort_code = match else_.as_slice() {
+        [Stmt::Abort(code)] => Some(code.clone()),
+        _ => None,
+    };
+    if then.is_empty() {
+        match abort_code {
+            Some(code) => out.push(assert(cond, code)),
+            None if else_.is_empty() => out.push(Stmt::Assign(vec![LValue::Ignore], cond)),
+            None => out.push(Stmt::If(negate(cond), else_, vec![])),
+        }
+    } else if let (Some(code), false) = (abort_code, diverges(&then)) {
+        out.push(assert(cond, code));
+        out.extend(then);
+    } else if diverges(&then) && !else_.is_empty() {
+        out.push(Stmt::If(cond, then, vec![]));
+        out.extend(else_);
+    } else {
+        out.push(Stmt::If(cond, then, else_));
+    }
+}
+
+fn assert(cond: Exp, code: Exp) -> Stmt {
+    Stmt::Exp(Exp::Call("assert".to_string(), vec![cond, code]))
+}
+
+fn negate(e: Exp) -> Exp {
+    match e {
+        Exp::Not(e) => *e,
+        e => Exp::Not(Box::new(e)),
+    }
+}
+
+/// Removes the `continue` statements that end the body of a loop.
+fn strip_continue(body: &mut Vec<Stmt>) {
+    if let Some(Stmt::Continue) = body.last() {
+        body.pop();
+    } else if let Some(Stmt::If(_, then, else_)) = body.last_mut() {
+        strip_continue(then);
+        strip_continue(else_);
+    }
+}
@@ -0,0 +1,407 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Decompiles Move bytecode back to Move source.
+//!
+//! The source produced declares the same structs and functions as the bytecode, with the same
+//! signatures, and recompiles to bytecode with the same behavior. It does not recover what the
+//! compiler throws away: local and constant names are made up from their types, and specs and
+//! comments are lost.
+
+#![forbid(unsafe_code)]
+
+mod ast;
+mod function;
+
+use anyhow::{bail, Result};
+use move_core_types::{account_address::AccountAddress, value::MoveValue};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+use vm::{
+    access::ModuleAccess,
+    file_format::{
+        CompiledModule, CompiledScript, ConstantPoolIndex, FunctionDefinition, FunctionHandleIndex,
+        Kind, ModuleHandleIndex, SignatureIndex, SignatureToken, StructDefinition,
+        StructFieldInformation, StructHandleIndex,
+    },
+};
+
+/// Decompiles a module to the source of an `address` block declaring it.
+pub fn decompile_module(module: &CompiledModule) -> Result<String> {
+    let context = ModuleContext::new(module, false);
+    let mut members = vec![];
+    for def in module.struct_defs() {
+        members.push(context.struct_(def));
+    }
+    for def in module.function_defs() {
+        members.push(context.function(def)?);
+    }
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "address {} {{\n\nmodule {} {{",
+        format_address(module.address()),
+        module.name()
+    )?;
+    context.header(&mut out);
+    for member in members {
+        writeln!(out)?;
+        out.push_str(&member);
+    }
+    out.push_str("}\n\n}\n");
+    Ok(out)
+}
+
+/// Decompiles a script to the source of a `script` block.
+pub fn decompile_script(script: &CompiledScript) -> Result<String> {
+    let (_, module) = script.clone().into_module();
+    let context = ModuleContext::new(&module, true);
+    let main = context.function(&module.function_defs()[0])?;
+
+    let mut out = String::new();
+    out.push_str("script {\n");
+    context.header(&mut out);
+    if !out.ends_with("{\n") {
+        out.push('\n');
+    }
+    out.push_str(&main);
+    out.push_str("}\n");
+    Ok(out)
+}
+
+/// Renders the names and types of a module, and records the modules and constants the source
+/// refers to so that it can declare them.
+pub(crate) struct ModuleContext<'a> {
+    module: &'a CompiledModule,
+    is_script: bool,
+    // The alias of each module handle, unique in the module.
+    aliases: Vec<String>,
+    used_modules: RefCell<BTreeSet<ModuleHandleIndex>>,
+    // The constants that are declared as module constants, by pool index.
+    used_constants: RefCell<BTreeMap<ConstantPoolIndex, String>>,
+}
+
+impl<'a> ModuleContext<'a> {
+    fn new(module: &'a CompiledModule, is_script: bool) -> Self {
+        let self_idx = module.self_handle_idx().0 as usize;
+        let mut taken = BTreeSet::new();
+        if !is_script {
+            taken.insert(module.name().to_string());
+        }
+        let aliases = module
+            .module_handles()
+            .iter()
+            .enumerate()
+            .map(|(idx, handle)| {
+                let name = module.identifier_at(handle.name).to_string();
+                if idx == self_idx {
+                    return name;
+                }
+                let mut alias = name.clone();
+                let mut suffix = 1;
+                while !taken.insert(alias.clone()) {
+                    alias = format!("{}{}", name, suffix);
+                    suffix += 1;
+                }
+                alias
+            })
+            .collect();
+        Self {
+            module,
+            is_script,
+            aliases,
+            used_modules: RefCell::new(BTreeSet::new()),
+            used_constants: RefCell::new(BTreeMap::new()),
+        }
+    }
+
+    pub(crate) fn module(&self) -> &'a CompiledModule {
+        self.module
+    }
+
+    /// Renders the `use` and `const` declarations for what the members rendered so far refer
+    /// to.
+    fn header(&self, out: &mut String) {
+        let mut uses: Vec<_> = self
+            .used_modules
+            .borrow()
+            .iter()
+            .map(|idx| {
+                let handle = self.module.module_handle_at(*idx);
+                let address = format_address(self.module.address_identifier_at(handle.address));
+                let name = self.module.identifier_at(handle.name).as_str();
+                let alias = &self.aliases[idx.0 as usize];
+                if alias == name {
+                    format!("    use {}::{};\n", address, name)
+                } else {
+                    format!("    use {}::{} as {};\n", address, name, alias)
+                }
+            })
+            .collect();
+        uses.sort();
+        if !uses.is_empty() {
+            out.push('\n');
+            uses.iter().for_each(|use_| out.push_str(use_));
+        }
+
+        let constants = self.used_constants.borrow();
+        if !constants.is_empty() {
+            out.push('\n');
+        }
+        for (idx, name) in constants.iter() {
+            let constant = self.module.constant_at(*idx);
+            let value = constant
+                .deserialize_constant()
+                .map_or_else(String::new, |value| format_value(&value));
+            out.push_str(&format!(
+                "    const {}: {} = {};\n",
+                name,
+                self.type_(&constant.type_, &[]),
+                value
+            ));
+        }
+    }
+
+    fn struct_(&self, def: &StructDefinition) -> String {
+        let handle = self.module.struct_handle_at(def.struct_handle);
+        let type_params = type_parameter_names(handle.type_parameters.len());
+        let mut out = format!(
+            "    {}struct {}{}",
+            if handle.is_nominal_resource {
+                "resource "
+            } else {
+                ""
+            },
+            self.module.identifier_at(handle.name),
+            type_parameter_decls(&type_params, &handle.type_parameters)
+        );
+        match &def.field_information {
+            StructFieldInformation::Native => {
+                out.insert_str(4, "native ");
+                out.push_str(";\n");
+            }
+            StructFieldInformation::Declared(fields) => {
+                out.push_str(" {\n");
+                for (i, field) in fields.iter().enumerate() {
+                    out.push_str(&format!(
+                        "        {}: {}{}\n",
+                        self.module.identifier_at(field.name),
+                        self.type_(&field.signature.0, &type_params),
+                        if i + 1 < fields.len() { "," } else { "" }
+                    ));
+                }
+                out.push_str("    }\n");
+            }
+        }
+        out
+    }
+
+    fn function(&self, def: &FunctionDefinition) -> Result<String> {
+        let handle = self.module.function_handle_at(def.function);
+        let type_params = type_parameter_names(handle.type_parameters.len());
+        let name = if self.is_script {
+            "main".to_string()
+        } else {
+            self.module.identifier_at(handle.name).to_string()
+        };
+        let returns = &self.module.signature_at(handle.return_).0;
+
+        let body = match &def.code {
+            Some(_) => Some(function::decompile(self, def, &type_params)?),
+            None => None,
+        };
+        let params = self.module.signature_at(handle.parameters);
+        let param_names = match &body {
+            Some(body) => body.param_names.clone(),
+            None => (0..params.len()).map(|i| format!("a{}", i)).collect(),
+        };
+
+        let mut out = String::from("    ");
+        if body.is_none() {
+            out.push_str("native ");
+        }
+        if def.is_public && !self.is_script {
+            out.push_str("public ");
+        }
+        write!(
+            out,
+            "fun {}{}({})",
+            name,
+            type_parameter_decls(&type_params, &handle.type_parameters),
+            params
+                .0
+                .iter()
+                .zip(&param_names)
+                .map(|(ty, name)| format!("{}: {}", name, self.type_(ty, &type_params)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+        match returns.as_slice() {
+            [] => (),
+            [ty] => write!(out, ": {}", self.type_(ty, &type_params))?,
+            tys => write!(out, ": ({})", self.types(tys, &type_params))?,
+        }
+        if !def.acquires_global_resources.is_empty() {
+            let acquires: Vec<_> = def
+                .acquires_global_resources
+                .iter()
+                .map(|idx| self.struct_name(self.module.struct_def_at(*idx).struct_handle))
+                .collect();
+            write!(out, " acquires {}", acquires.join(", "))?;
+        }
+        match body {
+            Some(body) => {
+                out.push_str(" {\n");
+                out.push_str(&body.code);
+                out.push_str("    }\n");
+            }
+            None => out.push_str(";\n"),
+        }
+        Ok(out)
+    }
+
+    /// The prefix qualifying the members of a module, empty for this module.
+    fn qualifier(&self, idx: ModuleHandleIndex) -> String {
+        if idx == self.module.self_handle_idx() {
+            return String::new();
+        }
+        self.used_modules.borrow_mut().insert(idx);
+        format!("{}::", self.aliases[idx.0 as usize])
+    }
+
+    pub(crate) fn struct_name(&self, idx: StructHandleIndex) -> String {
+        let handle = self.module.struct_handle_at(idx);
+        format!(
+            "{}{}",
+            self.qualifier(handle.module),
+            self.module.identifier_at(handle.name)
+        )
+    }
+
+    pub(crate) fn function_name(&self, idx: FunctionHandleIndex) -> String {
+        let handle = self.module.function_handle_at(idx);
+        format!(
+            "{}{}",
+            self.qualifier(handle.module),
+            self.module.identifier_at(handle.name)
+        )
+    }
+
+    pub(crate) fn type_(&self, ty: &SignatureToken, type_params: &[String]) -> String {
+        match ty {
+            SignatureToken::Bool => "bool".to_string(),
+            SignatureToken::U8 => "u8".to_string(),
+            SignatureToken::U64 => "u64".to_string(),
+            SignatureToken::U128 => "u128".to_string(),
+            SignatureToken::Address => "address".to_string(),
+            SignatureToken::Signer => "signer".to_string(),
+            SignatureToken::Vector(ty) => format!("vector<{}>", self.type_(ty, type_params)),
+            SignatureToken::Struct(idx) => self.struct_name(*idx),
+            SignatureToken::StructInstantiation(idx, tys) => format!(
+                "{}<{}>",
+                self.struct_name(*idx),
+                self.types(tys, type_params)
+            ),
+            SignatureToken::Reference(ty) => format!("&{}", self.type_(ty, type_params)),
+            SignatureToken::MutableReference(ty) => format!("&mut {}", self.type_(ty, type_params)),
+            SignatureToken::TypeParameter(idx) => type_params[*idx as usize].clone(),
+        }
+    }
+
+    fn types(&self, tys: &[SignatureToken], type_params: &[String]) -> String {
+        tys.iter()
+            .map(|ty| self.type_(ty, type_params))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Renders the type arguments of an instantiation, e.g. `<u64, T>`.
+    pub(crate) fn type_arguments(&self, idx: SignatureIndex, type_params: &[String]) -> String {
+        format!(
+            "<{}>",
+            self.types(&self.module.signature_at(idx).0, type_params)
+        )
+    }
+
+    /// Renders a constant of the pool: booleans and integers as a module constant, other
+    /// values as a literal.
+    pub(crate) fn constant(&self, idx: ConstantPoolIndex) -> Result<String> {
+        let constant = self.module.constant_at(idx);
+        let value = match constant.deserialize_constant() {
+            Some(value) => value,
+            None => bail!("Unable to deserialize constant {}", idx),
+        };
+        match &value {
+            MoveValue::Bool(_) | MoveValue::U8(_) | MoveValue::U64(_) | MoveValue::U128(_) => {
+                Ok(self
+                    .used_constants
+                    .borrow_mut()
+                    .entry(idx)
+                    .or_insert_with(|| format!("C{}", idx.0))
+                    .clone())
+            }
+            MoveValue::Address(_) => Ok(format_value(&value)),
+            MoveValue::Vector(elems) if elems.iter().all(|e| matches!(e, MoveValue::U8(_))) => {
+                Ok(format_value(&value))
+            }
+            _ => bail!("Constant {} has no literal syntax: {:?}", idx, value),
+        }
+    }
+}
+
+/// Renders an address the way it is usually written in source, e.g. `0x1`.
+pub(crate) fn format_address(address: &AccountAddress) -> String {
+    let hex = format!("{:x}", address);
+    match hex.trim_start_matches('0') {
+        "" => "0x0".to_string(),
+        hex => format!("0x{}", hex),
+    }
+}
+
+fn format_value(value: &MoveValue) -> String {
+    match value {
+        MoveValue::Bool(b) => b.to_string(),
+        MoveValue::U8(n) => format!("{}u8", n),
+        MoveValue::U64(n) => n.to_string(),
+        MoveValue::U128(n) => format!("{}u128", n),
+        MoveValue::Address(address) | MoveValue::Signer(address) => format_address(address),
+        MoveValue::Vector(elems) => {
+            let mut out = String::from("x\"");
+            for elem in elems {
+                if let MoveValue::U8(byte) = elem {
+                    write!(out, "{:02x}", byte).unwrap();
+                }
+            }
+            out.push('"');
+            out
+        }
+        MoveValue::Struct(s) => format!("{:?}", s),
+    }
+}
+
+fn type_parameter_names(count: usize) -> Vec<String> {
+    match count {
+        1 => vec!["T".to_string()],
+        n => (0..n).map(|i| format!("T{}", i)).collect(),
+    }
+}
+
+fn type_parameter_decls(names: &[String], kinds: &[Kind]) -> String {
+    if names.is_empty() {
+        return String::new();
+    }
+    let decls: Vec<_> = names
+        .iter()
+        .zip(kinds)
+        .map(|(name, kind)| match kind {
+            Kind::All => name.clone(),
+            Kind::Resource => format!("{}: resource", name),
+            Kind::Copyable => format!("{}: copyable", name),
+        })
+        .collect();
+    format!("<{}>", decls.join(", "))
+}
@@ -0,0 +1,48 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use anyhow::{anyhow, Result};
+use move_decompiler::{decompile_module, decompile_script};
+use std::{fs, path::PathBuf};
+use structopt::StructOpt;
+use vm::file_format::{CompiledModule, CompiledScript};
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "Move Decompiler",
+    about = "Print the Move source of Move bytecode (.mv files)"
+)]
+struct Options {
+    /// Treat the input file as a script (default is to treat it as a module)
+    #[structopt(short = "s", long = "script")]
+    is_script: bool,
+
+    /// The path to the bytecode file to decompile
+    #[structopt(short = "b", long = "bytecode", parse(from_os_str))]
+    bytecode_file_path: PathBuf,
+
+    /// Write the source to this file instead of the standard output
+    #[structopt(short = "o", long = "output", parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let options = Options::from_args();
+    let bytes = fs::read(&options.bytecode_file_path)?;
+    let source = if options.is_script {
+        let script = CompiledScript::deserialize(&bytes)
+            .map_err(|e| anyhow!("Unable to deserialize the script: {:?}", e))?;
+        decompile_script(&script)?
+    } else {
+        let module = CompiledModule::deserialize(&bytes)
+            .map_err(|e| anyhow!("Unable to deserialize the module: {:?}", e))?;
+        decompile_module(&module)?
+    };
+    match &options.output {
+        Some(path) => fs::write(path, source)?,
+        None => print!("{}", source),
+    }
+    Ok(())
+}
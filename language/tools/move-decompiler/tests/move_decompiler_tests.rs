@@ -0,0 +1,523 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Decompiles bytecode, compiles the source produced again and checks that the result declares
+//! the same members and that its functions do the same things.
+
+use compiled_stdlib::{stdlib_modules, transaction_scripts::StdlibScript, StdLibOptions};
+use libra_temppath::TempPath;
+use move_decompiler::{decompile_module, decompile_script};
+use move_lang::{
+    compiled_unit::CompiledUnit, errors::report_errors_to_buffer, move_compile_no_report,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+};
+use vm::{
+    access::ModuleAccess,
+    file_format::{
+        Bytecode, CodeOffset, CompiledModule, CompiledScript, FunctionDefinition, SignatureToken,
+        StructFieldInformation, StructHandleIndex,
+    },
+};
+
+const MODULE: &str = "address 0x2 {
+module M {
+    resource struct R<T: copyable> { items: vector<T>, count: u64 }
+    struct Pair { a: u64, b: bool }
+
+    public fun sum(n: u64): u64 {
+        let i = 0;
+        let total = 0;
+        while (i < n) {
+            if (i % 2 == 0 && total < 100) {
+                total = total + i
+            } else {
+                total = total + 1
+            };
+            i = i + 1;
+        };
+        if (total > 1000) return 0;
+        total
+    }
+
+    public fun find(x: u64): (bool, u64) {
+        let i = 0;
+        loop {
+            if (i >= x) break;
+            if (i * i == x) return (true, i);
+            i = i + 1;
+        };
+        (false, 0)
+    }
+
+    public fun publish<T: copyable>(account: &signer, items: vector<T>) {
+        assert(!exists<R<T>>(0x2), 7);
+        move_to(account, R<T> { items: items, count: 0 })
+    }
+
+    public fun bump<T: copyable>(addr: address): u64 acquires R {
+        let r = borrow_global_mut<R<T>>(addr);
+        r.count = r.count + 1;
+        let (found, _) = find(r.count);
+        if (found) r.count else 0
+    }
+
+    public fun swap(p: Pair): Pair {
+        let Pair { a, b } = p;
+        Pair { a: if (b) a else a + 1, b: !b }
+    }
+}
+}
+";
+
+#[test]
+fn test_round_trip() {
+    let temp = TempPath::new();
+    temp.create_as_dir().unwrap();
+    let source = temp.path().join("M.move");
+    fs::write(&source, MODULE).unwrap();
+    let original = compile(&[source.to_string_lossy().to_string()]);
+    let decompiled: Vec<_> = original
+        .iter()
+        .map(|unit| match unit {
+            CompiledUnit::Module { module, .. } => decompile_module(module).unwrap(),
+            CompiledUnit::Script { .. } => panic!("unexpected script"),
+        })
+        .collect();
+    let source = &decompiled[0];
+    assert!(source.contains("while ("), "{}", source);
+    assert!(source.contains(" acquires R {"), "{}", source);
+    assert!(
+        source.contains("resource struct R<T: copyable> {"),
+        "{}",
+        source
+    );
+
+    let file = temp.path().join("M.decompiled.move");
+    fs::write(&file, source).unwrap();
+    let recompiled = compile(&[file.to_string_lossy().to_string()]);
+    assert_eq!(recompiled.len(), 1);
+    match (&original[0], &recompiled[0]) {
+        (
+            CompiledUnit::Module { module, .. },
+            CompiledUnit::Module {
+                module: recompiled, ..
+            },
+        ) => assert_same_module(module, recompiled, source),
+        _ => panic!("expected modules"),
+    }
+}
+
+#[test]
+fn test_stdlib_round_trip() {
+    let temp = TempPath::new();
+    temp.create_as_dir().unwrap();
+    let modules = stdlib_modules(StdLibOptions::Compiled);
+    let mut sources = BTreeMap::new();
+    let mut files = vec![];
+    for module in modules {
+        let source = decompile_module(module).unwrap();
+        let file = temp.path().join(format!("{}.move", module.name()));
+        fs::write(&file, &source).unwrap();
+        files.push(file.to_string_lossy().to_string());
+        sources.insert(module.name().to_string(), source);
+    }
+
+    let recompiled: BTreeMap<_, _> = compile(&files)
+        .into_iter()
+        .filter_map(|unit| match unit {
+            CompiledUnit::Module { module, .. } => Some((module.name().to_string(), module)),
+            CompiledUnit::Script { .. } => None,
+        })
+        .collect();
+    assert_eq!(recompiled.len(), modules.len());
+    for module in modules {
+        let name = module.name().to_string();
+        assert_same_module(module, &recompiled[&name], &sources[&name]);
+    }
+
+    for script in StdlibScript::all() {
+        let original = CompiledScript::deserialize(&script.compiled_bytes().into_vec()).unwrap();
+        let source = decompile_script(&original).unwrap();
+        let file = temp.path().join(format!("{}.move", script.name()));
+        fs::write(&file, &source).unwrap();
+        let units = compile_with_deps(&[file.to_string_lossy().to_string()], &files);
+        match units.as_slice() {
+            [CompiledUnit::Script {
+                script: recompiled, ..
+            }] => {
+                let (_, original) = original.into_module();
+                let (_, recompiled) = recompiled.clone().into_module();
+                assert_same_module(&original, &recompiled, &source);
+            }
+            _ => panic!("expected a script for {}", script.name()),
+        }
+    }
+}
+
+fn compile(targets: &[String]) -> Vec<CompiledUnit> {
+    compile_with_deps(targets, &[])
+}
+
+fn compile_with_deps(targets: &[String], deps: &[String]) -> Vec<CompiledUnit> {
+    let (files, units) = move_compile_no_report(targets, deps, None).unwrap();
+    units.unwrap_or_else(|errors| {
+        panic!(
+            "{}",
+            String::from_utf8_lossy(&report_errors_to_buffer(files, errors))
+        )
+    })
+}
+
+/// Checks that two modules declare the same members and that their functions have the same
+/// control flow graphs and run the same instructions in each block, up to the moves between
+/// locals and the stack, the negation of branch conditions and the jumps through empty blocks.
+fn assert_same_module(expected: &CompiledModule, actual: &CompiledModule, source: &str) {
+    assert_eq!(
+        interface(actual),
+        interface(expected),
+        "{}: declarations differ in\n{}",
+        expected.name(),
+        source
+    );
+    let functions = |module: &CompiledModule| -> BTreeMap<String, ControlFlow> {
+        module
+            .function_defs()
+            .iter()
+            .map(|def| (function_name(module, def), control_flow(module, def)))
+            .collect()
+    };
+    let expected_functions = functions(expected);
+    for (name, actual) in functions(actual) {
+        let expected_function = &expected_functions[&name];
+        assert!(
+            same_control_flow(expected_function, &actual),
+            "{}::{}: code differs, expected {:?}, got {:?} in\n{}",
+            expected.name(),
+            name,
+            expected_function,
+            actual,
+            source
+        );
+    }
+}
+
+fn function_name(module: &CompiledModule, def: &FunctionDefinition) -> String {
+    let handle = module.function_handle_at(def.function);
+    module.identifier_at(handle.name).to_string()
+}
+
+/// The struct and function declarations of a module, by name.
+fn interface(module: &CompiledModule) -> BTreeMap<String, String> {
+    let mut decls = BTreeMap::new();
+    for def in module.struct_defs() {
+        let handle = module.struct_handle_at(def.struct_handle);
+        let fields = match &def.field_information {
+            StructFieldInformation::Native => "native".to_string(),
+            StructFieldInformation::Declared(fields) => fields
+                .iter()
+                .map(|field| {
+                    format!(
+                        "{}: {}",
+                        module.identifier_at(field.name),
+                        type_name(module, &field.signature.0)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+        decls.insert(
+            format!("struct {}", module.identifier_at(handle.name)),
+            format!(
+                "resource: {}, {:?} {{ {} }}",
+                handle.is_nominal_resource, handle.type_parameters, fields
+            ),
+        );
+    }
+    for def in module.function_defs() {
+        let handle = module.function_handle_at(def.function);
+        let types = |tys: &[SignatureToken]| {
+            tys.iter()
+                .map(|ty| type_name(module, ty))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let acquires: Vec<_> = def
+            .acquires_global_resources
+            .iter()
+            .map(|idx| struct_name(module, module.struct_def_at(*idx).struct_handle))
+            .collect();
+        decls.insert(
+            format!("fun {}", function_name(module, def)),
+            format!(
+                "public: {}, native: {}, {:?} ({}): ({}) acquires {}",
+                def.is_public,
+                def.code.is_none(),
+                handle.type_parameters,
+                types(&module.signature_at(handle.parameters).0),
+                types(&module.signature_at(handle.return_).0),
+                acquires.join(", ")
+            ),
+        );
+    }
+    decls
+}
+
+fn struct_name(module: &CompiledModule, idx: StructHandleIndex) -> String {
+    let handle = module.struct_handle_at(idx);
+    let module_handle = module.module_handle_at(handle.module);
+    format!(
+        "{}::{}::{}",
+        module.address_identifier_at(module_handle.address),
+        module.identifier_at(module_handle.name),
+        module.identifier_at(handle.name)
+    )
+}
+
+fn type_name(module: &CompiledModule, ty: &SignatureToken) -> String {
+    match ty {
+        SignatureToken::Vector(ty) => format!("vector<{}>", type_name(module, ty)),
+        SignatureToken::Struct(idx) => struct_name(module, *idx),
+        SignatureToken::StructInstantiation(idx, tys) => format!(
+            "{}<{}>",
+            struct_name(module, *idx),
+            tys.iter()
+                .map(|ty| type_name(module, ty))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        SignatureToken::Reference(ty) => format!("&{}", type_name(module, ty)),
+        SignatureToken::MutableReference(ty) => format!("&mut {}", type_name(module, ty)),
+        ty => format!("{:?}", ty),
+    }
+}
+
+/// The control flow graph of a function, with its blocks by offset of their first instruction.
+/// The function starts with the block at offset 0.
+#[derive(Debug)]
+struct ControlFlow(BTreeMap<CodeOffset, Block>);
+
+#[derive(Debug)]
+struct Block {
+    // The instructions of the block that do not depend on how its source is written, counted.
+    instructions: BTreeMap<String, usize>,
+    // Where execution continues: nowhere after a return or an abort, one block after a jump, and
+    // the blocks reached when the branch condition, without its negations, holds and when it
+    // does not.
+    successors: Vec<CodeOffset>,
+}
+
+impl ControlFlow {
+    /// The block `offset` jumps to through blocks without instructions, or `offset` itself.
+    fn resolve(&self, mut offset: CodeOffset) -> CodeOffset {
+        let mut visited = BTreeSet::new();
+        while visited.insert(offset) {
+            match &self.0[&offset] {
+                Block {
+                    instructions,
+                    successors,
+                } if instructions.is_empty() && successors.len() == 1 => offset = successors[0],
+                _ => break,
+            }
+        }
+        offset
+    }
+
+    /// The successors of the block at `offset`, through blocks without instructions. A branch to
+    /// the same block either way is a jump.
+    fn successors(&self, offset: CodeOffset) -> Vec<CodeOffset> {
+        let mut successors: Vec<_> = self.0[&offset]
+            .successors
+            .iter()
+            .map(|succ| self.resolve(*succ))
+            .collect();
+        successors.dedup();
+        successors
+    }
+}
+
+/// Checks that the blocks reached from the start of the functions map one to one, so that
+/// mapped blocks run the same instructions and have mapped successors in the same order.
+fn same_control_flow(expected: &ControlFlow, actual: &ControlFlow) -> bool {
+    if expected.0.is_empty() || actual.0.is_empty() {
+        return expected.0.is_empty() && actual.0.is_empty();
+    }
+    let mut mapping = BTreeMap::new();
+    let mut mapped = BTreeSet::new();
+    let mut pending = vec![(expected.resolve(0), actual.resolve(0))];
+    while let Some((expected_block, actual_block)) = pending.pop() {
+        if let Some(mapped_block) = mapping.get(&expected_block) {
+            if *mapped_block != actual_block {
+                return false;
+            }
+            continue;
+        }
+        if !mapped.insert(actual_block) {
+            return false;
+        }
+        mapping.insert(expected_block, actual_block);
+        if expected.0[&expected_block].instructions != actual.0[&actual_block].instructions {
+            return false;
+        }
+        let expected_successors = expected.successors(expected_block);
+        let actual_successors = actual.successors(actual_block);
+        if expected_successors.len() != actual_successors.len() {
+            return false;
+        }
+        pending.extend(expected_successors.into_iter().zip(actual_successors));
+    }
+    true
+}
+
+fn control_flow(module: &CompiledModule, def: &FunctionDefinition) -> ControlFlow {
+    let code = match &def.code {
+        Some(code) => &code.code,
+        None => return ControlFlow(BTreeMap::new()),
+    };
+    let mut starts = BTreeSet::new();
+    starts.insert(0);
+    for (pc, instr) in code.iter().enumerate() {
+        match instr {
+            Bytecode::Branch(target) | Bytecode::BrTrue(target) | Bytecode::BrFalse(target) => {
+                starts.insert(*target);
+            }
+            Bytecode::Ret | Bytecode::Abort => (),
+            _ => continue,
+        }
+        if pc + 1 < code.len() {
+            starts.insert(pc as CodeOffset + 1);
+        }
+    }
+
+    let mut blocks = BTreeMap::new();
+    let starts: Vec<_> = starts.into_iter().collect();
+    for (idx, start) in starts.iter().enumerate() {
+        let end = starts
+            .get(idx + 1)
+            .map_or(code.len(), |next| *next as usize);
+        let block = &code[*start as usize..end];
+        let mut instructions = BTreeMap::new();
+        for instr in block {
+            if let Some(key) = instruction_key(module, instr) {
+                *instructions.entry(key).or_insert(0) += 1;
+            }
+        }
+        let next = end as CodeOffset;
+        // `Not`s right before a branch swap where it goes.
+        let negated = block
+            .iter()
+            .rev()
+            .skip(1)
+            .take_while(|instr| matches!(instr, Bytecode::Not))
+            .count()
+            % 2
+            == 1;
+        let mut successors = match block.last() {
+            Some(Bytecode::Ret) | Some(Bytecode::Abort) => vec![],
+            Some(Bytecode::Branch(target)) => vec![*target],
+            Some(Bytecode::BrTrue(target)) => vec![*target, next],
+            Some(Bytecode::BrFalse(target)) => vec![next, *target],
+            _ => vec![next],
+        };
+        if negated && successors.len() == 2 {
+            successors.swap(0, 1);
+        }
+        blocks.insert(
+            *start,
+            Block {
+                instructions,
+                successors,
+            },
+        );
+    }
+    ControlFlow(blocks)
+}
+
+/// The instruction as it does not depend on how the source is written, or `None` if it can be
+/// written in different ways, like the moves between locals and the stack, or is a branch.
+fn instruction_key(module: &CompiledModule, instr: &Bytecode) -> Option<String> {
+    let types = |idx| {
+        module
+            .signature_at(idx)
+            .0
+            .iter()
+            .map(|ty| type_name(module, ty))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let struct_def = |idx| struct_name(module, module.struct_def_at(idx).struct_handle);
+    let struct_inst = |idx| {
+        let inst = module.struct_instantiation_at(idx);
+        format!("{}<{}>", struct_def(inst.def), types(inst.type_parameters))
+    };
+    let field = |idx| {
+        let handle = module.field_handle_at(idx);
+        format!("{}.{}", struct_def(handle.owner), handle.field)
+    };
+    let function = |idx| {
+        let handle = module.function_handle_at(idx);
+        let module_handle = module.module_handle_at(handle.module);
+        format!(
+            "{}::{}",
+            module.identifier_at(module_handle.name),
+            module.identifier_at(handle.name)
+        )
+    };
+    let key = match instr {
+        Bytecode::CopyLoc(_)
+        | Bytecode::MoveLoc(_)
+        | Bytecode::StLoc(_)
+        | Bytecode::Pop
+        | Bytecode::MutBorrowLoc(_)
+        | Bytecode::ImmBorrowLoc(_)
+        | Bytecode::FreezeRef
+        | Bytecode::Branch(_)
+        | Bytecode::BrTrue(_)
+        | Bytecode::BrFalse(_)
+        | Bytecode::Nop
+        | Bytecode::Not
+        | Bytecode::LdTrue
+        | Bytecode::LdFalse => return None,
+        Bytecode::Call(idx) => format!("Call {}", function(*idx)),
+        Bytecode::CallGeneric(idx) => {
+            let inst = module.function_instantiation_at(*idx);
+            format!(
+                "Call {}<{}>",
+                function(inst.handle),
+                types(inst.type_parameters)
+            )
+        }
+        Bytecode::LdConst(idx) => format!("LdConst {:?}", module.constant_at(*idx)),
+        Bytecode::Pack(idx) => format!("Pack {}", struct_def(*idx)),
+        Bytecode::PackGeneric(idx) => format!("Pack {}", struct_inst(*idx)),
+        Bytecode::Unpack(idx) => format!("Unpack {}", struct_def(*idx)),
+        Bytecode::UnpackGeneric(idx) => format!("Unpack {}", struct_inst(*idx)),
+        Bytecode::MutBorrowField(idx) | Bytecode::ImmBorrowField(idx) => {
+            format!("BorrowField {}", field(*idx))
+        }
+        Bytecode::MutBorrowFieldGeneric(idx) | Bytecode::ImmBorrowFieldGeneric(idx) => {
+            let inst = module.field_instantiation_at(*idx);
+            format!(
+                "BorrowField {}<{}>",
+                field(inst.handle),
+                types(inst.type_parameters)
+            )
+        }
+        Bytecode::MutBorrowGlobal(idx) | Bytecode::ImmBorrowGlobal(idx) => {
+            format!("BorrowGlobal {}", struct_def(*idx))
+        }
+        Bytecode::MutBorrowGlobalGeneric(idx) | Bytecode::ImmBorrowGlobalGeneric(idx) => {
+            format!("BorrowGlobal {}", struct_inst(*idx))
+        }
+        Bytecode::Exists(idx) => format!("Exists {}", struct_def(*idx)),
+        Bytecode::ExistsGeneric(idx) => format!("Exists {}", struct_inst(*idx)),
+        Bytecode::MoveFrom(idx) => format!("MoveFrom {}", struct_def(*idx)),
+        Bytecode::MoveFromGeneric(idx) => format!("MoveFrom {}", struct_inst(*idx)),
+        Bytecode::MoveTo(idx) => format!("MoveTo {}", struct_def(*idx)),
+        Bytecode::MoveToGeneric(idx) => format!("MoveTo {}", struct_inst(*idx)),
+        instr => format!("{:?}", instr),
+    };
+    Some(key)
+}
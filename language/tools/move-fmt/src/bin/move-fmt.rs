@@ -0,0 +1,50 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use move_fmt::format_source;
+use move_lang::find_move_filenames;
+use std::{fs, process};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "Move Format", about = "Format Move source files in place")]
+struct Options {
+    /// The Move source files, or directories searched for them
+    #[structopt(name = "PATH_TO_SOURCE_FILE")]
+    source_files: Vec<String>,
+
+    /// List the files which are not formatted, and fail if there are any, instead of
+    /// formatting them
+    #[structopt(long = "check", short = "c")]
+    check: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    let Options {
+        source_files,
+        check,
+    } = Options::from_args();
+    let mut unformatted = 0;
+    for fname in find_move_filenames(&source_files)? {
+        let source = fs::read_to_string(&fname)?;
+        let fname: &'static str = Box::leak(fname.into_boxed_str());
+        let formatted = format_source(fname, &source)?;
+        if formatted == source {
+            continue;
+        }
+        unformatted += 1;
+        if check {
+            println!("{}", fname);
+        } else {
+            fs::write(fname, formatted)?;
+            println!("Formatted {}", fname);
+        }
+    }
+    if check && unformatted > 0 {
+        eprintln!("{} file(s) need formatting", unformatted);
+        process::exit(1);
+    }
+    Ok(())
+}
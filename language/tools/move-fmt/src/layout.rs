@@ -0,0 +1,714 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Layout of the tokens and comments of a source file.
+//!
+//! The layout keeps the line breaks of the source, except for blank lines at the start and end
+//! of a block and breaks before separators, opening braces and an `else` after a block, and adds
+//! the ones which make it canonical:
+//! - a block which spans several lines, as well as the body of an `address`, a `module` or a
+//!   `script`, has each of its statements, or each of its comma separated items, on a line of its
+//!   own, and its braces on the lines of the code around it;
+//! - a parenthesized or bracketed list which breaks lines between its items has each of them on a
+//!   line of its own;
+//! - an attribute, like `#[test]`, is on a line of its own.
+//!
+//! Indentation follows the nesting of delimiters, with four spaces per level. The body of an
+//! `address` block is not indented, and a line which continues an unfinished statement gets one
+//! more level. Spacing between tokens on a line is canonical, with the exception that type
+//! arguments and the fragments of an `apply` pattern stay attached to the name before them, which
+//! the parser relies on.
+
+use crate::tokens::{Item, ItemKind};
+use move_lang::parser::lexer::Tok;
+
+const INDENT: &str = "    ";
+
+/// Identifiers which act as keywords inside of specification blocks.
+const SPEC_KEYWORDS: &[&str] = &[
+    "aborts_if",
+    "assert",
+    "assume",
+    "define",
+    "emits",
+    "ensures",
+    "in",
+    "include",
+    "modifies",
+    "pragma",
+    "requires",
+    "succeeds_if",
+    "where",
+];
+
+/// Role of a token which is not determined by its kind alone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Role {
+    Plain,
+    TypeArgsOpen,
+    TypeArgsClose,
+    Unary,
+    Binary,
+}
+
+/// A pair of delimiters, or the whole file.
+struct Frame {
+    /// Indentation of the line of the opening delimiter.
+    base: usize,
+    /// Whether the first item inside of the delimiters starts a new line, once known.
+    broken: Option<bool>,
+    indent_body: bool,
+    /// Whether statements and items are put on lines of their own.
+    multi_line: bool,
+    /// Whether the items are separated by commas rather than by semicolons.
+    separated: bool,
+    is_brace: bool,
+    /// Whether a single line block has spaces inside of its braces.
+    padded: bool,
+    spec: bool,
+    /// Whether the last token inside of the frame ends a statement or an item.
+    terminated: bool,
+    angle_depth: usize,
+}
+
+struct Layout<'a> {
+    source: &'a str,
+    items: &'a [Item],
+    roles: Vec<Role>,
+    angle_depths: Vec<usize>,
+    matching: Vec<Option<usize>>,
+    out: String,
+    frames: Vec<Frame>,
+    line_indent: usize,
+    prev: Option<usize>,
+    prev_token: Option<usize>,
+    break_pending: bool,
+    after_block: bool,
+    spec_pending: bool,
+    apply_pattern: bool,
+}
+
+/// Lays out the items of `source`.
+pub fn layout(source: &str, items: &[Item]) -> String {
+    let mut layout = Layout::new(source, items);
+    for i in 0..items.len() {
+        layout.item(i);
+    }
+    let mut out = layout.out;
+    out.truncate(out.trim_end().len());
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+impl<'a> Layout<'a> {
+    fn new(source: &'a str, items: &'a [Item]) -> Self {
+        let mut roles = vec![Role::Plain; items.len()];
+        let mut angle_depths = vec![0; items.len()];
+        let mut matching = vec![None; items.len()];
+        let mut open = vec![];
+        let mut depth = 0;
+        let mut prev: Option<usize> = None;
+        let mut prev2: Option<usize> = None;
+        for (i, item) in items.iter().enumerate() {
+            let tok = match item.tok() {
+                Some(tok) => tok,
+                None => continue,
+            };
+            angle_depths[i] = depth;
+            roles[i] = match tok {
+                Tok::Less => {
+                    let attached = prev.map_or(false, |p| {
+                        items[p].end == item.start
+                            && (items[p].is(Tok::IdentifierValue) || items[p].is(Tok::Star))
+                    });
+                    let declared = prev.map_or(false, |p| items[p].is(Tok::IdentifierValue))
+                        && prev2.map_or(false, |p| {
+                            let item = &items[p];
+                            item.is(Tok::Fun)
+                                || item.is(Tok::Struct)
+                                || item.is_ident("schema")
+                                || item.is_ident("define")
+                        });
+                    if attached || declared {
+                        depth += 1;
+                        Role::TypeArgsOpen
+                    } else {
+                        Role::Binary
+                    }
+                }
+                Tok::Greater if depth > 0 => {
+                    depth -= 1;
+                    Role::TypeArgsClose
+                }
+                Tok::GreaterGreater if depth > 1 => {
+                    depth -= 2;
+                    Role::TypeArgsClose
+                }
+                Tok::Amp | Tok::Star => {
+                    if prev.map_or(false, |p| ends_operand(&items[p], roles[p])) {
+                        Role::Binary
+                    } else {
+                        Role::Unary
+                    }
+                }
+                Tok::Exclaim => Role::Unary,
+                _ => Role::Plain,
+            };
+            match tok {
+                Tok::LBrace | Tok::LParen | Tok::LBracket => open.push(i),
+                Tok::RBrace | Tok::RParen | Tok::RBracket => {
+                    if let Some(j) = open.pop() {
+                        matching[j] = Some(i);
+                        matching[i] = Some(j);
+                    }
+                }
+                _ => (),
+            }
+            prev2 = prev;
+            prev = Some(i);
+        }
+        Self {
+            source,
+            items,
+            roles,
+            angle_depths,
+            matching,
+            out: String::new(),
+            frames: vec![Frame {
+                base: 0,
+                broken: Some(false),
+                indent_body: false,
+                multi_line: false,
+                separated: false,
+                is_brace: false,
+                padded: false,
+                spec: false,
+                terminated: true,
+                angle_depth: 0,
+            }],
+            line_indent: 0,
+            prev: None,
+            prev_token: None,
+            break_pending: false,
+            after_block: false,
+            spec_pending: false,
+            apply_pattern: false,
+        }
+    }
+
+    fn top(&self) -> &Frame {
+        self.frames.last().unwrap()
+    }
+
+    fn top_mut(&mut self) -> &mut Frame {
+        self.frames.last_mut().unwrap()
+    }
+
+    fn closes_frame(&self, i: usize) -> bool {
+        match self.items[i].tok() {
+            Some(Tok::RBrace) | Some(Tok::RParen) | Some(Tok::RBracket) => {
+                self.matching[i].is_some()
+            }
+            _ => false,
+        }
+    }
+
+    fn item(&mut self, i: usize) {
+        let items = self.items;
+        let item = &items[i];
+        let closes = self.closes_frame(i);
+        let prev_is_line_comment = self
+            .prev
+            .map_or(false, |p| self.items[p].kind == ItemKind::LineComment);
+        let new_line = match (self.prev, item.tok()) {
+            (None, _) => false,
+            (Some(_), None) => item.newlines_before > 0 || prev_is_line_comment,
+            (Some(_), Some(tok)) => {
+                let forced = self.break_pending
+                    || (closes && self.top().multi_line)
+                    || (self.after_block && !continues_block(tok));
+                // Separators, opening braces and an `else` after a block join the previous line.
+                let joined = self.prev.map_or(false, |p| match items[p].tok() {
+                    Some(prev_tok) => {
+                        matches!(tok, Tok::Comma | Tok::Semicolon | Tok::LBrace)
+                            || (tok == Tok::Else && prev_tok == Tok::RBrace)
+                    }
+                    None => false,
+                });
+                forced || (item.newlines_before > 0 && !joined) || prev_is_line_comment
+            }
+        };
+        if !closes && self.top().broken.is_none() && (new_line || !item.is_comment()) {
+            self.top_mut().broken = Some(new_line);
+        }
+
+        if new_line {
+            let after_open = self.prev.map_or(false, |p| {
+                self.items[p].is(Tok::LBrace)
+                    || self.items[p].is(Tok::LParen)
+                    || self.items[p].is(Tok::LBracket)
+            });
+            // Blank lines at the start and end of a block go, except in an address block.
+            let keep_blank = !(after_open || closes) || !self.top().indent_body;
+            self.new_line(item.newlines_before > 1 && keep_blank);
+            self.line_indent = self.indent(i, closes);
+            for _ in 0..self.line_indent {
+                self.out.push_str(INDENT);
+            }
+        } else if self.prev.is_some() && self.space_before(i) {
+            self.out.push(' ');
+        }
+        self.push_text(item);
+        self.prev = Some(i);
+
+        match item.tok() {
+            Some(tok) => self.token(i, tok),
+            None => {
+                if item.kind == ItemKind::LineComment {
+                    self.break_pending = true;
+                }
+            }
+        }
+    }
+
+    fn new_line(&mut self, blank: bool) {
+        self.out.truncate(self.out.trim_end_matches(' ').len());
+        self.out.push('\n');
+        if blank {
+            self.out.push('\n');
+        }
+    }
+
+    fn indent(&self, i: usize, closes: bool) -> usize {
+        let frame = self.top();
+        if closes {
+            return frame.base;
+        }
+        let mut indent = frame.base;
+        if frame.indent_body && frame.broken == Some(true) {
+            indent += 1;
+        }
+        let item = &self.items[i];
+        if !frame.terminated && !item.is(Tok::Acquires) && !item.is(Tok::LBrace) {
+            indent += 1;
+        }
+        indent
+    }
+
+    /// Appends the text of an item, moving the lines of a block comment along with its start.
+    fn push_text(&mut self, item: &Item) {
+        let mut lines = item.text.lines();
+        self.out.push_str(lines.next().unwrap_or(""));
+        let old_column = item.start - self.source[..item.start].rfind('\n').map_or(0, |n| n + 1);
+        let new_column = self.out.len() - self.out.rfind('\n').map_or(0, |n| n + 1);
+        let new_column = new_column - item.text.lines().next().unwrap_or("").len();
+        for line in lines {
+            self.out.push('\n');
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            if new_column >= old_column {
+                for _ in old_column..new_column {
+                    self.out.push(' ');
+                }
+                self.out.push_str(line);
+            } else {
+                let removable = line.len() - line.trim_start_matches(' ').len();
+                self.out
+                    .push_str(&line[removable.min(old_column - new_column)..]);
+            }
+        }
+    }
+
+    fn token(&mut self, i: usize, tok: Tok) {
+        let items = self.items;
+        let item = &items[i];
+        self.break_pending = false;
+        self.after_block = false;
+        match tok {
+            Tok::LBrace | Tok::LParen | Tok::LBracket => self.open(i, tok),
+            Tok::RBrace | Tok::RParen | Tok::RBracket if self.frames.len() > 1 => {
+                let frame = self.frames.pop().unwrap();
+                // An attribute, like `#[test]`, goes on a line of its own.
+                let attribute = tok == Tok::RBracket
+                    && self.matching[i]
+                        .and_then(|open| open.checked_sub(1))
+                        .map_or(false, |p| items[p].is(Tok::NumSign));
+                self.top_mut().terminated = tok == Tok::RBrace || attribute;
+                self.after_block = tok == Tok::RBrace && frame.multi_line;
+                self.break_pending = attribute;
+            }
+            Tok::Semicolon => {
+                let frame = self.top_mut();
+                frame.terminated = true;
+                self.break_pending = frame.multi_line && frame.is_brace;
+                self.apply_pattern = false;
+            }
+            Tok::Comma => {
+                let depth = self.angle_depths[i];
+                let frame = self.top_mut();
+                frame.terminated = true;
+                self.break_pending =
+                    frame.multi_line && frame.separated && frame.angle_depth == depth;
+            }
+            Tok::Spec => {
+                self.spec_pending = true;
+                self.top_mut().terminated = false;
+            }
+            _ => {
+                if self.top().spec && item.is_ident("apply") {
+                    self.apply_pattern = true;
+                }
+                self.top_mut().terminated = false;
+            }
+        }
+        self.prev_token = Some(i);
+    }
+
+    fn open(&mut self, i: usize, tok: Tok) {
+        let items = self.items;
+        let close = self.matching[i].unwrap_or(i);
+        let spans_lines = self.source[items[i].start..items[close].end].contains('\n');
+        let prev = self.prev_token.map(|p| &items[p]);
+        let prev2 = self
+            .prev_token
+            .and_then(|p| items[..p].iter().rev().find(|item| !item.is_comment()));
+        let is_brace = tok == Tok::LBrace;
+        let address_body = is_brace
+            && prev.map_or(false, |item| item.is(Tok::AddressValue))
+            && prev2.map_or(false, |item| item.is_ident("address"));
+        let module_body = is_brace
+            && (address_body
+                || prev.map_or(false, |item| item.is(Tok::Script) || item.is(Tok::Module))
+                || prev2.map_or(false, |item| item.is(Tok::Module)));
+        let (multi_line, separated) = if is_brace {
+            (
+                close > i + 1 && (spans_lines || module_body),
+                !module_body && !self.has_top_level(i, close, Tok::Semicolon),
+            )
+        } else {
+            let separated = self.has_top_level(i, close, Tok::Comma);
+            (separated && self.breaks_at_top_level(i, close), true)
+        };
+        let padded = is_brace && !prev.map_or(false, |item| item.is(Tok::ColonColon));
+        let spec = self.top().spec || (is_brace && self.spec_pending);
+        if is_brace {
+            self.spec_pending = false;
+        }
+        self.frames.push(Frame {
+            base: self.line_indent,
+            broken: None,
+            indent_body: !address_body,
+            multi_line,
+            separated,
+            is_brace,
+            padded,
+            spec,
+            terminated: true,
+            angle_depth: self.angle_depths[i],
+        });
+        self.break_pending = multi_line;
+    }
+
+    /// Whether `tok` appears between the delimiters at `open` and `close`, outside of nested
+    /// delimiters and type arguments.
+    fn has_top_level(&self, open: usize, close: usize, tok: Tok) -> bool {
+        let mut depth = 0;
+        (open + 1..close).any(|j| {
+            let item = &self.items[j];
+            match item.tok() {
+                Some(Tok::LBrace) | Some(Tok::LParen) | Some(Tok::LBracket) => depth += 1,
+                Some(Tok::RBrace) | Some(Tok::RParen) | Some(Tok::RBracket) => depth -= 1,
+                _ => (),
+            }
+            depth == 0 && item.is(tok) && self.angle_depths[j] == self.angle_depths[open]
+        })
+    }
+
+    /// Whether the source breaks a line between the delimiters at `open` and `close`, outside of
+    /// nested delimiters.
+    fn breaks_at_top_level(&self, open: usize, close: usize) -> bool {
+        let mut depth = 0;
+        (open + 1..=close).any(|j| {
+            let item = &self.items[j];
+            let breaks = depth == 0 && item.newlines_before > 0;
+            match item.tok() {
+                Some(Tok::LBrace) | Some(Tok::LParen) | Some(Tok::LBracket) => depth += 1,
+                Some(Tok::RBrace) | Some(Tok::RParen) | Some(Tok::RBracket) => depth -= 1,
+                _ => (),
+            }
+            breaks
+        })
+    }
+
+    fn space_before(&self, i: usize) -> bool {
+        let p = self.prev.unwrap();
+        let (prev, next) = (&self.items[p], &self.items[i]);
+        let (prev_tok, next_tok) = match (prev.tok(), next.tok()) {
+            (Some(prev_tok), Some(next_tok)) => (prev_tok, next_tok),
+            (_, None) => return !prev.is(Tok::LParen) && !prev.is(Tok::LBracket),
+            (None, Some(next_tok)) => {
+                return !matches!(
+                    next_tok,
+                    Tok::RParen | Tok::RBracket | Tok::Comma | Tok::Semicolon
+                )
+            }
+        };
+        let (prev_role, next_role) = (self.roles[p], self.roles[i]);
+        if prev_tok == Tok::AmpMut {
+            return true;
+        }
+        // Fragments of a name pattern in an `apply` must stay together.
+        let fragment = |tok| tok == Tok::IdentifierValue || tok == Tok::Star;
+        if self.apply_pattern && prev.end == next.start && fragment(prev_tok) && fragment(next_tok)
+        {
+            return false;
+        }
+        if matches!(
+            prev_tok,
+            Tok::LParen
+                | Tok::LBracket
+                | Tok::NumSign
+                | Tok::ColonColon
+                | Tok::Period
+                | Tok::PeriodPeriod
+        ) || prev_role == Role::Unary
+            || prev_role == Role::TypeArgsOpen
+        {
+            // Keep `& &x` from becoming `&&x`.
+            return prev_tok == Tok::Amp
+                && prev_role == Role::Unary
+                && matches!(next_tok, Tok::Amp | Tok::AmpAmp | Tok::AmpMut);
+        }
+        if matches!(
+            next_tok,
+            Tok::RParen
+                | Tok::RBracket
+                | Tok::Comma
+                | Tok::Semicolon
+                | Tok::Period
+                | Tok::ColonColon
+                | Tok::Colon
+                | Tok::PeriodPeriod
+        ) || next_role == Role::TypeArgsOpen
+            || next_role == Role::TypeArgsClose
+        {
+            return false;
+        }
+        if prev_tok == Tok::LBrace {
+            return next_tok != Tok::RBrace && self.top().padded;
+        }
+        if next_tok == Tok::RBrace {
+            return self.top().padded;
+        }
+        match next_tok {
+            Tok::LParen => {
+                let keyword = self.top().spec && SPEC_KEYWORDS.contains(&prev.text.as_str());
+                !(prev_tok == Tok::IdentifierValue && !keyword || prev_role == Role::TypeArgsClose)
+            }
+            Tok::LBracket => {
+                !matches!(prev_tok, Tok::IdentifierValue | Tok::RParen | Tok::RBracket)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Whether `item` can end an operand, which makes a following `&` or `*` a binary operator.
+fn ends_operand(item: &Item, role: Role) -> bool {
+    match item.tok() {
+        Some(Tok::IdentifierValue) => !SPEC_KEYWORDS.contains(&item.text.as_str()),
+        Some(Tok::AddressValue)
+        | Some(Tok::NumValue)
+        | Some(Tok::U8Value)
+        | Some(Tok::U64Value)
+        | Some(Tok::U128Value)
+        | Some(Tok::ByteStringValue)
+        | Some(Tok::True)
+        | Some(Tok::False)
+        | Some(Tok::RParen)
+        | Some(Tok::RBracket) => true,
+        Some(_) => role == Role::TypeArgsClose,
+        None => false,
+    }
+}
+
+/// Whether `tok` continues an expression after a block, e.g. `} else {`.
+fn continues_block(tok: Tok) -> bool {
+    matches!(
+        tok,
+        Tok::Else
+            | Tok::Semicolon
+            | Tok::Comma
+            | Tok::RParen
+            | Tok::RBracket
+            | Tok::Period
+            | Tok::As
+            | Tok::Equal
+            | Tok::EqualEqual
+            | Tok::ExclaimEqual
+            | Tok::Less
+            | Tok::LessEqual
+            | Tok::Greater
+            | Tok::GreaterEqual
+            | Tok::AmpAmp
+            | Tok::PipePipe
+            | Tok::EqualEqualGreater
+            | Tok::Plus
+            | Tok::Minus
+            | Tok::Star
+            | Tok::Slash
+            | Tok::Percent
+            | Tok::Amp
+            | Tok::Pipe
+            | Tok::Caret
+            | Tok::LessLess
+            | Tok::GreaterGreater
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format_source;
+
+    fn check(source: &str, expected: &str) {
+        let formatted = format_source("test", source).unwrap();
+        assert_eq!(formatted, expected);
+        assert_eq!(format_source("test", &formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn test_module() {
+        check(
+            "address 0x2 {
+
+module M {
+    use 0x1::Vector::{Self,
+        empty};
+    resource struct R<T> { value: T, other: vector<vector<u8>> }
+
+    /// Documented.
+    public fun f<T: copyable>(x: &mut R<T>, y: u64): u64 acquires R { // trailing
+        let z = y+ *&x.count; if (z < y) abort 1;
+        if (y > 0)
+        {
+            z = z * 2
+        }
+        else { z = 0 };
+        g(y,
+          z)
+    }
+}
+}
+",
+            "address 0x2 {
+
+module M {
+    use 0x1::Vector::{
+        Self,
+        empty
+    };
+    resource struct R<T> { value: T, other: vector<vector<u8>> }
+
+    /// Documented.
+    public fun f<T: copyable>(x: &mut R<T>, y: u64): u64 acquires R { // trailing
+        let z = y + *&x.count;
+        if (z < y) abort 1;
+        if (y > 0) {
+            z = z * 2
+        } else { z = 0 };
+        g(
+            y,
+            z
+        )
+    }
+}
+}
+",
+        );
+    }
+
+    #[test]
+    fn test_script_and_comments() {
+        check(
+            "script {
+use 0x1::M;
+  /* Entry
+     point */
+fun main(account: &signer) {
+
+
+    M::publish(account, M::R {
+        value: 1,    // one
+    });
+}
+}
+",
+            "script {
+    use 0x1::M;
+    /* Entry
+       point */
+    fun main(account: &signer) {
+        M::publish(account, M::R {
+            value: 1, // one
+        });
+    }
+}
+",
+        );
+    }
+
+    #[test]
+    fn test_spec() {
+        check(
+            "module M {
+    spec fun f {
+        aborts_if y == 0;
+        ensures result ==
+                old(y);
+    }
+    spec module {
+        apply Foo to set_*<T>, *;
+        invariant forall x: u64 where (x > 0): x >= 1;
+    }
+}
+",
+            "module M {
+    spec fun f {
+        aborts_if y == 0;
+        ensures result ==
+            old(y);
+    }
+    spec module {
+        apply Foo to set_*<T>, *;
+        invariant forall x: u64 where (x > 0): x >= 1;
+    }
+}
+",
+        );
+    }
+
+    #[test]
+    fn test_attributes() {
+        check(
+            "#[test_only]
+module M {
+    /// Documented.
+    #[test(account=0x1)] #[expected_failure(abort_code = 7)]
+    fun f(account: &signer) { abort 7 }
+}
+",
+            "#[test_only]
+module M {
+    /// Documented.
+    #[test(account = 0x1)]
+    #[expected_failure(abort_code = 7)]
+    fun f(account: &signer) { abort 7 }
+}
+",
+        );
+    }
+}
@@ -0,0 +1,77 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Canonical formatting of Move source files.
+
+#![forbid(unsafe_code)]
+
+pub mod layout;
+pub mod tokens;
+
+use anyhow::{bail, Result};
+use move_lang::{
+    errors::{report_errors_to_buffer, Errors, FilesSourceText},
+    parse_source_string,
+    parser::ast::{Definition, Program},
+    shared::ast_debug,
+};
+
+/// Formats the Move source `source` of the file `fname`. Fails if the source does not parse, or
+/// if formatting would change anything but its layout.
+pub fn format_source(fname: &'static str, source: &str) -> Result<String> {
+    let original = normalized_ast(fname, source)?;
+    let items =
+        tokens::scan(fname, source).or_else(|errors| parse_errors(fname, source, errors))?;
+    let formatted = layout::layout(source, &items);
+
+    let formatted_items = tokens::scan(fname, &formatted)
+        .or_else(|errors| parse_errors(fname, &formatted, errors))?;
+    let same_tokens = items.len() == formatted_items.len()
+        && items
+            .iter()
+            .zip(&formatted_items)
+            .all(|(item, formatted)| item.kind == formatted.kind && same_text(item, formatted));
+    if !same_tokens || normalized_ast(fname, &formatted)? != original {
+        bail!("Formatting {} would change its meaning", fname);
+    }
+    Ok(formatted)
+}
+
+/// Prints the definitions and documentation comments of a source, without their locations, such
+/// that two sources which only differ in their layout print the same.
+pub fn normalized_ast(fname: &'static str, source: &str) -> Result<String> {
+    let (definitions, doc_comments) = match parse_source_string(fname, source) {
+        Ok(parsed) => parsed,
+        Err(errors) => return parse_errors(fname, source, errors),
+    };
+    let mut printed = ast_debug::display(&Program {
+        source_definitions: definitions,
+        lib_definitions: Vec::<Definition>::new(),
+    });
+    for comment in doc_comments.values() {
+        printed.push_str("\n///");
+        for line in comment.lines() {
+            printed.push_str(line.trim());
+            printed.push('\n');
+        }
+    }
+    Ok(printed)
+}
+
+/// Whether two items have the same text, ignoring the indentation of block comments.
+fn same_text(item: &tokens::Item, formatted: &tokens::Item) -> bool {
+    item.text
+        .lines()
+        .map(str::trim)
+        .eq(formatted.text.lines().map(str::trim))
+}
+
+fn parse_errors<T>(fname: &'static str, source: &str, errors: Errors) -> Result<T> {
+    let mut files = FilesSourceText::new();
+    files.insert(fname, source.to_string());
+    bail!(
+        "Unable to parse {}:\n{}",
+        fname,
+        String::from_utf8_lossy(&report_errors_to_buffer(files, errors))
+    )
+}
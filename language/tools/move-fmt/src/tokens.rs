@@ -0,0 +1,184 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Splits a source file into the tokens of the Move lexer and the comments between them.
+
+use codespan::{ByteIndex, Span};
+use move_ir_types::location::Loc;
+use move_lang::{
+    errors::Errors,
+    parser::lexer::{Lexer, Tok},
+    strip_comments_and_verify,
+};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ItemKind {
+    Token(Tok),
+    LineComment,
+    BlockComment,
+}
+
+/// A token or a comment, with its position in the source.
+#[derive(Clone, Debug)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+    /// Number of line breaks between the previous item and this one.
+    pub newlines_before: usize,
+}
+
+impl Item {
+    pub fn tok(&self) -> Option<Tok> {
+        match self.kind {
+            ItemKind::Token(tok) => Some(tok),
+            _ => None,
+        }
+    }
+
+    pub fn is(&self, tok: Tok) -> bool {
+        self.tok() == Some(tok)
+    }
+
+    pub fn is_ident(&self, name: &str) -> bool {
+        self.is(Tok::IdentifierValue) && self.text == name
+    }
+
+    pub fn is_comment(&self) -> bool {
+        self.tok().is_none()
+    }
+}
+
+/// Returns the items of `source` in order. The text of a token is its source text, except for
+/// `&mut`, which the lexer matches together with the following space.
+pub fn scan(fname: &'static str, source: &str) -> Result<Vec<Item>, Errors> {
+    let (no_comments, _) = strip_comments_and_verify(fname, source)?;
+    if no_comments.len() != source.len() {
+        // Stripping nested block comments does not preserve the positions of the tokens.
+        let loc = Loc::new(fname, Span::new(ByteIndex(0), ByteIndex(0)));
+        return Err(vec![vec![(
+            loc,
+            "Nested block comments are not supported by the formatter".to_string(),
+        )]]);
+    }
+    let mut lexer = Lexer::new(&no_comments, fname, BTreeMap::new());
+    let mut items = vec![];
+    let mut last_end = 0;
+    loop {
+        lexer.advance().map_err(|err| vec![err])?;
+        let start = lexer.start_loc();
+        let newlines_before = scan_gap(source, last_end, start, &mut items);
+        if lexer.peek() == Tok::EOF {
+            return Ok(items);
+        }
+        let content = lexer.content();
+        let text = if lexer.peek() == Tok::AmpMut {
+            content.trim_end()
+        } else {
+            content
+        };
+        items.push(Item {
+            kind: ItemKind::Token(lexer.peek()),
+            text: text.to_string(),
+            start,
+            end: start + content.len(),
+            newlines_before,
+        });
+        last_end = start + content.len();
+    }
+}
+
+/// Collects the comments in `source[start..end]`, which only holds whitespace and comments, and
+/// returns the number of line breaks after the last of them.
+fn scan_gap(source: &str, start: usize, end: usize, items: &mut Vec<Item>) -> usize {
+    let mut newlines = 0;
+    let mut pos = start;
+    while pos < end {
+        let rest = &source[pos..end];
+        let (kind, len) = if rest.starts_with("//") {
+            (
+                ItemKind::LineComment,
+                rest.find('\n').unwrap_or_else(|| rest.len()),
+            )
+        } else if rest.starts_with("/*") {
+            (ItemKind::BlockComment, block_comment_len(rest))
+        } else {
+            if rest.starts_with('\n') {
+                newlines += 1;
+            }
+            pos += 1;
+            continue;
+        };
+        items.push(Item {
+            kind,
+            text: source[pos..pos + len].trim_end().to_string(),
+            start: pos,
+            end: pos + len,
+            newlines_before: newlines,
+        });
+        newlines = 0;
+        pos += len;
+    }
+    newlines
+}
+
+/// Length of the (possibly nested) block comment at the start of `text`.
+fn block_comment_len(text: &str) -> usize {
+    let mut depth = 0;
+    let mut pos = 0;
+    while pos < text.len() {
+        if text[pos..].starts_with("/*") {
+            depth += 1;
+            pos += 2;
+        } else if text[pos..].starts_with("*/") {
+            depth -= 1;
+            pos += 2;
+            if depth == 0 {
+                break;
+            }
+        } else {
+            pos += 1;
+        }
+    }
+    pos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let source = "module M { // one\n\n    /* two */ fun f(x: &mut u64) {}\n}\n";
+        let items = scan("test", source).unwrap();
+        let texts: Vec<_> = items.iter().map(|item| item.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "module",
+                "M",
+                "{",
+                "// one",
+                "/* two */",
+                "fun",
+                "f",
+                "(",
+                "x",
+                ":",
+                "&mut",
+                "u64",
+                ")",
+                "{",
+                "}",
+                "}"
+            ]
+        );
+        assert_eq!(items[3].kind, ItemKind::LineComment);
+        assert_eq!(items[4].kind, ItemKind::BlockComment);
+        assert_eq!(items[4].newlines_before, 2);
+        assert_eq!(items[5].newlines_before, 0);
+        assert_eq!(items[15].newlines_before, 1);
+    }
+}
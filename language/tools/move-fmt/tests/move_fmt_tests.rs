@@ -0,0 +1,61 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use libra_temppath::TempPath;
+use move_fmt::{format_source, normalized_ast};
+use move_lang::find_move_filenames;
+use std::{fs, path::Path, process::Command};
+
+const UNFORMATTED: &str = "module M {
+fun f(): u64 {  1+2 }
+}
+";
+
+#[test]
+fn test_stdlib_modules() {
+    let modules = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../stdlib/modules");
+    let fnames = find_move_filenames(&[modules.to_string_lossy().to_string()]).unwrap();
+    assert!(!fnames.is_empty());
+    for fname in fnames {
+        let source = fs::read_to_string(&fname).unwrap();
+        let fname: &'static str = Box::leak(fname.into_boxed_str());
+        let formatted = format_source(fname, &source).unwrap();
+        assert_eq!(
+            normalized_ast(fname, &formatted).unwrap(),
+            normalized_ast(fname, &source).unwrap(),
+            "{}",
+            fname
+        );
+        assert_eq!(
+            format_source(fname, &formatted).unwrap(),
+            formatted,
+            "{} is not formatted idempotently",
+            fname
+        );
+    }
+}
+
+#[test]
+fn test_check_mode() {
+    let temp = TempPath::new();
+    temp.create_as_dir().unwrap();
+    let file = temp.path().join("M.move");
+    fs::write(&file, UNFORMATTED).unwrap();
+    let move_fmt = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_move-fmt"))
+            .args(args)
+            .arg(temp.path())
+            .status()
+            .unwrap()
+    };
+
+    assert!(!move_fmt(&["--check"]).success());
+    assert_eq!(fs::read_to_string(&file).unwrap(), UNFORMATTED);
+
+    assert!(move_fmt(&[]).success());
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "module M {\n    fun f(): u64 { 1 + 2 }\n}\n"
+    );
+    assert!(move_fmt(&["--check"]).success());
+}
@@ -0,0 +1,116 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replays a committed transaction against the state it was executed on, as read from a Libra
+//! DB, and profiles the gas its execution uses.
+
+#![forbid(unsafe_code)]
+
+use anyhow::{anyhow, bail, ensure, Result};
+use libra_state_view::StateView;
+use libra_types::{
+    access_path::AccessPath,
+    account_address::AccountAddress,
+    account_state::AccountState,
+    transaction::{Transaction, TransactionInfo, TransactionOutput, Version},
+};
+use libra_vm::{LibraVM, VMExecutor};
+use move_vm_runtime::gas_profiler::{self, GasProfile};
+use std::{cell::RefCell, collections::HashMap, convert::TryFrom};
+use storage_interface::DbReader;
+
+/// A view of the global state at a version of the DB.
+pub struct DbStateView<'a> {
+    db: &'a dyn DbReader,
+    version: Version,
+    // The account states read so far.
+    accounts: RefCell<HashMap<AccountAddress, Option<AccountState>>>,
+}
+
+impl<'a> DbStateView<'a> {
+    /// The state right after the transaction at `version` was committed.
+    pub fn new(db: &'a dyn DbReader, version: Version) -> Self {
+        Self {
+            db,
+            version,
+            accounts: RefCell::new(HashMap::new()),
+        }
+    }
+}
+
+impl<'a> StateView for DbStateView<'a> {
+    fn get(&self, access_path: &AccessPath) -> Result<Option<Vec<u8>>> {
+        let mut accounts = self.accounts.borrow_mut();
+        if !accounts.contains_key(&access_path.address) {
+            let (blob, _proof) = self
+                .db
+                .get_account_state_with_proof_by_version(access_path.address, self.version)?;
+            let account_state = match blob {
+                Some(blob) => Some(AccountState::try_from(&blob)?),
+                None => None,
+            };
+            accounts.insert(access_path.address, account_state);
+        }
+        Ok(accounts[&access_path.address]
+            .as_ref()
+            .and_then(|account_state| account_state.get(&access_path.path))
+            .cloned())
+    }
+
+    fn multi_get(&self, access_paths: &[AccessPath]) -> Result<Vec<Option<Vec<u8>>>> {
+        access_paths
+            .iter()
+            .map(|access_path| self.get(access_path))
+            .collect()
+    }
+
+    fn is_genesis(&self) -> bool {
+        false
+    }
+}
+
+/// A transaction executed again, along with what the DB recorded of its first execution.
+pub struct Replay {
+    pub version: Version,
+    pub transaction: Transaction,
+    pub recorded: TransactionInfo,
+    pub output: TransactionOutput,
+    pub profile: GasProfile,
+}
+
+/// Executes the transaction committed at `version` again, on the state committed right before
+/// it, and profiles the gas used.
+pub fn replay(db: &dyn DbReader, version: Version) -> Result<Replay> {
+    ensure!(version > 0, "The genesis transaction cannot be replayed");
+    let latest_version = db.get_latest_version()?;
+    ensure!(
+        version <= latest_version,
+        "Version {} is not committed, the latest version is {}",
+        version,
+        latest_version
+    );
+    let mut txns = db.get_transactions(version, 1, latest_version, false)?;
+    let (transaction, recorded) = match (
+        txns.transactions.pop(),
+        txns.proof.transaction_infos().first(),
+    ) {
+        (Some(transaction), Some(info)) => (transaction, info.clone()),
+        _ => bail!("No transaction found at version {}", version),
+    };
+
+    let state_view = DbStateView::new(db, version - 1);
+    gas_profiler::start_profiling();
+    let outputs = LibraVM::execute_block(vec![transaction.clone()], &state_view);
+    let profile = gas_profiler::stop_profiling().unwrap_or_default();
+    let output = outputs
+        .map_err(|status| anyhow!("Unable to execute the transaction: {:?}", status))?
+        .pop()
+        .ok_or_else(|| anyhow!("No output for the transaction"))?;
+    Ok(Replay {
+        version,
+        transaction,
+        recorded,
+        output,
+        profile,
+    })
+}
@@ -0,0 +1,89 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use anyhow::Result;
+use libra_types::transaction::TransactionStatus;
+use libradb::LibraDB;
+use move_gas_profiler::replay;
+use std::{fs, path::PathBuf};
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "Move Gas Profiler",
+    about = "Replay a transaction from a Libra DB and profile the gas it uses"
+)]
+struct Options {
+    /// The directory of the DB
+    #[structopt(long, parse(from_os_str))]
+    db: PathBuf,
+
+    /// The version of the transaction to replay
+    version: u64,
+
+    /// Write the profile in the folded stack format to this file, e.g. for flamegraph.pl
+    #[structopt(long, parse(from_os_str))]
+    folded: Option<PathBuf>,
+
+    /// Write the profile in the speedscope format to this file
+    #[structopt(long, parse(from_os_str))]
+    speedscope: Option<PathBuf>,
+
+    /// The number of functions to list, the most expensive first
+    #[structopt(long, default_value = "20")]
+    top: usize,
+}
+
+fn main() -> Result<()> {
+    let options = Options::from_args();
+    let db = LibraDB::open(
+        &options.db,
+        true, /* readonly */
+        None, /* pruner */
+    )?;
+    let replay = replay(&db, options.version)?;
+
+    let status = match replay.output.status() {
+        TransactionStatus::Keep(status) => format!("{:?}", status.status_code()),
+        TransactionStatus::Discard(status) => format!("discarded with {:?}", status.status_code()),
+        TransactionStatus::Retry => "retry".to_string(),
+    };
+    println!(
+        "Transaction {}: {}, gas used {}",
+        replay.version,
+        status,
+        replay.output.gas_used()
+    );
+    if replay.output.gas_used() != replay.recorded.gas_used()
+        || replay.output.status().vm_status().status_code() != replay.recorded.major_status()
+    {
+        println!(
+            "warning: the transaction was committed with {:?}, gas used {}",
+            replay.recorded.major_status(),
+            replay.recorded.gas_used()
+        );
+    }
+
+    println!(
+        "Gas attributed to Move functions, in internal gas units: {}",
+        replay.profile.total_gas()
+    );
+    println!("{:>12} {:>12} {:>8}  function", "total", "self", "calls");
+    for function in replay.profile.summary().iter().take(options.top) {
+        println!(
+            "{:>12} {:>12} {:>8}  {}",
+            function.total_gas, function.self_gas, function.calls, function.name
+        );
+    }
+
+    if let Some(path) = &options.folded {
+        fs::write(path, replay.profile.to_folded())?;
+    }
+    if let Some(path) = &options.speedscope {
+        let name = format!("transaction {}", replay.version);
+        fs::write(path, replay.profile.to_speedscope(&name))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,83 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Analysis of the Move files of a workspace by the front end of the compiler.
+
+use crate::symbols::Symbols;
+use anyhow::Result;
+use lsp_types::Position;
+use move_lang::{
+    errors::{Errors, FilesSourceText},
+    move_check_no_report, move_check_to_typing_no_report,
+    shared::Address,
+};
+
+pub struct Analysis {
+    /// Sources of the targets and dependencies, as they were analyzed.
+    pub files: FilesSourceText,
+    pub targets: Vec<String>,
+    /// Errors found by all the passes of the compiler.
+    pub errors: Errors,
+    /// Symbols of the typed program, empty if the targets do not parse.
+    pub symbols: Symbols,
+}
+
+impl Analysis {
+    pub fn new(targets: &[String], deps: &[String], sender: Option<Address>) -> Result<Self> {
+        let (files, errors) = move_check_no_report(targets, deps, sender)?;
+        let (_, typed) = move_check_to_typing_no_report(targets, deps, sender)?;
+        let symbols = match typed {
+            Ok((prog, _)) => Symbols::new(&prog),
+            Err(_) => Symbols::default(),
+        };
+        Ok(Self {
+            files,
+            targets: targets.to_vec(),
+            errors,
+            symbols,
+        })
+    }
+}
+
+/// Converts a byte offset into a position. Move sources only contain ASCII characters, so the
+/// UTF-16 column of the protocol is the byte offset within the line.
+pub fn offset_to_position(text: &str, offset: usize) -> Position {
+    let offset = offset.min(text.len());
+    let line_start = text[..offset].rfind('\n').map_or(0, |pos| pos + 1);
+    let line = text[..offset].matches('\n').count();
+    Position::new(line as u64, (offset - line_start) as u64)
+}
+
+/// Converts a position into a byte offset, clamped to the line and the text.
+pub fn position_to_offset(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(pos) => line_start += pos + 1,
+            None => return text.len(),
+        }
+    }
+    let line_end = text[line_start..]
+        .find('\n')
+        .map_or(text.len(), |pos| line_start + pos);
+    (line_start + position.character as usize).min(line_end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        let text = "module M {\n    fun f() {}\n}\n";
+        for offset in 0..=text.len() {
+            assert_eq!(
+                position_to_offset(text, offset_to_position(text, offset)),
+                offset
+            );
+        }
+        assert_eq!(offset_to_position(text, 15), Position::new(1, 4));
+        assert_eq!(position_to_offset(text, Position::new(0, 100)), 10);
+        assert_eq!(position_to_offset(text, Position::new(100, 0)), text.len());
+    }
+}
@@ -0,0 +1,50 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use move_lang::{command_line as cli, shared::Address};
+use move_lsp::server::{self, Config};
+use std::io;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "Move Language Server",
+    about = "Language server for Move, speaking the Language Server Protocol over stdio"
+)]
+struct Options {
+    /// The library files needed as dependencies
+    #[structopt(
+        name = "PATH_TO_DEPENDENCY_FILE",
+        short = cli::DEPENDENCY_SHORT,
+        long = cli::DEPENDENCY,
+    )]
+    dependencies: Vec<String>,
+
+    /// The sender address for modules and scripts
+    #[structopt(
+        name = "ADDRESS",
+        short = cli::SENDER_SHORT,
+        long = cli::SENDER,
+        parse(try_from_str = cli::parse_address)
+    )]
+    sender: Option<Address>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let Options {
+        dependencies,
+        sender,
+    } = Options::from_args();
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    server::run(
+        stdin.lock(),
+        stdout.lock(),
+        Config {
+            dependencies,
+            sender,
+        },
+    )
+}
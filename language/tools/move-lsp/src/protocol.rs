@@ -0,0 +1,83 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Base protocol of LSP: JSON-RPC messages preceded by a `Content-Length` header.
+
+use anyhow::{bail, format_err, Result};
+use serde_json::Value;
+use std::io::{BufRead, Write};
+
+const CONTENT_LENGTH: &str = "content-length";
+
+/// Reads a message, returning `None` at the end of the input.
+pub fn read_message(input: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            if content_length.is_some() {
+                bail!("Unexpected end of input in message headers");
+            }
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let mut parts = line.splitn(2, ':');
+        let name = parts.next().unwrap_or_default().trim();
+        let value = parts.next().unwrap_or_default().trim();
+        if name.eq_ignore_ascii_case(CONTENT_LENGTH) {
+            content_length = Some(value.parse::<usize>()?);
+        }
+    }
+    let content_length =
+        content_length.ok_or_else(|| format_err!("Message without a Content-Length header"))?;
+    let mut body = vec![0; content_length];
+    input.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_read_write() {
+        let messages = vec![
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+            json!({"jsonrpc": "2.0", "method": "exit"}),
+        ];
+        let mut buffer = vec![];
+        for message in &messages {
+            write_message(&mut buffer, message).unwrap();
+        }
+
+        let mut input = &buffer[..];
+        for message in &messages {
+            assert_eq!(read_message(&mut input).unwrap().as_ref(), Some(message));
+        }
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn test_headers() {
+        let body = r#"{"jsonrpc":"2.0","method":"exit"}"#;
+        let message = format!(
+            "content-length: {}\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        assert!(read_message(&mut message.as_bytes()).unwrap().is_some());
+        assert!(read_message(&mut "Content-Type: json\r\n\r\n{}".as_bytes()).is_err());
+        assert!(read_message(&mut "Content-Length: 10\r\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,440 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The language server, answering the requests of a client read from its input.
+//!
+//! The Move files of the workspace are analyzed when the client is initialized and whenever a
+//! document is saved, which publishes the diagnostics of all the files. Requests on symbols are
+//! answered from the last analysis, so they refer to the last saved version of the documents;
+//! completion looks at the text being edited to find the module or local being completed.
+
+use crate::{
+    analysis::{offset_to_position, position_to_offset, Analysis},
+    protocol::{read_message, write_message},
+    symbols::{Member, SymbolKind, SymbolLoc},
+};
+use anyhow::Result;
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticRelatedInformation,
+    DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, Hover, HoverContents, Location, MarkupContent, MarkupKind,
+    PublishDiagnosticsParams, Range, TextDocumentPositionParams, Url,
+};
+use move_ir_types::location::Loc;
+use move_lang::{find_move_filenames, shared::Address};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{BufRead, Write},
+    path::PathBuf,
+};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// Move files the files of the workspace depend on, such as the stdlib.
+    pub dependencies: Vec<String>,
+    /// The sender address for modules and scripts.
+    pub sender: Option<Address>,
+}
+
+/// Serves the client until it sends the `exit` notification or closes the input.
+pub fn run(mut input: impl BufRead, output: impl Write, config: Config) -> Result<()> {
+    let mut server = Server::new(output, config);
+    while let Some(message) = read_message(&mut input)? {
+        if !server.handle(message)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+struct ResponseError {
+    code: i64,
+    message: String,
+}
+
+fn invalid_params(error: impl ToString) -> ResponseError {
+    ResponseError {
+        code: INVALID_PARAMS,
+        message: error.to_string(),
+    }
+}
+
+struct Server<W> {
+    output: W,
+    config: Config,
+    root: Option<PathBuf>,
+    /// Text of the open documents, as edited.
+    documents: BTreeMap<Url, String>,
+    analysis: Option<Analysis>,
+    /// Files with published diagnostics.
+    published: BTreeSet<String>,
+}
+
+impl<W: Write> Server<W> {
+    fn new(output: W, config: Config) -> Self {
+        Self {
+            output,
+            config,
+            root: None,
+            documents: BTreeMap::new(),
+            analysis: None,
+            published: BTreeSet::new(),
+        }
+    }
+
+    /// Handles a message, returning false once the client asked the server to exit.
+    fn handle(&mut self, message: Value) -> Result<bool> {
+        let method = match message.get("method").and_then(Value::as_str) {
+            Some(method) => method.to_string(),
+            // Responses to requests of the server, which does not send any.
+            None => return Ok(true),
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        match message.get("id") {
+            Some(id) => {
+                let response = match self.request(&method, params) {
+                    Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                    Err(error) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": {"code": error.code, "message": error.message},
+                    }),
+                };
+                write_message(&mut self.output, &response)?;
+                Ok(true)
+            }
+            None => self.notification(&method, params),
+        }
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Result<Value, ResponseError> {
+        match method {
+            "initialize" => {
+                self.root = params
+                    .get("rootUri")
+                    .and_then(Value::as_str)
+                    .and_then(|uri| Url::parse(uri).ok()?.to_file_path().ok())
+                    .or_else(|| params.get("rootPath")?.as_str().map(PathBuf::from));
+                Ok(json!({
+                    "capabilities": {
+                        "textDocumentSync": {
+                            "openClose": true,
+                            // Full
+                            "change": 1,
+                            "save": {"includeText": false},
+                        },
+                        "definitionProvider": true,
+                        "hoverProvider": true,
+                        "referencesProvider": true,
+                        "completionProvider": {"triggerCharacters": [":", "."]},
+                    },
+                    "serverInfo": {"name": "move-lsp"},
+                }))
+            }
+            "shutdown" => Ok(Value::Null),
+            "textDocument/definition" => {
+                let (_, def) = match self.occurrence(params)? {
+                    Some(occurrence) => occurrence,
+                    None => return Ok(Value::Null),
+                };
+                Ok(json!(self.location(def)))
+            }
+            "textDocument/hover" => {
+                let (loc, def) = match self.occurrence(params)? {
+                    Some(occurrence) => occurrence,
+                    None => return Ok(Value::Null),
+                };
+                let analysis = self
+                    .analysis
+                    .as_ref()
+                    .expect("symbols come from an analysis");
+                let definition = match analysis.symbols.definition(&def) {
+                    Some(definition) => definition,
+                    None => return Ok(Value::Null),
+                };
+                Ok(json!(Hover {
+                    contents: HoverContents::Markup(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: format!("```move\n{}\n```", definition.detail),
+                    }),
+                    range: self.range(loc),
+                }))
+            }
+            "textDocument/references" => {
+                let include_declaration = params
+                    .pointer("/context/includeDeclaration")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(true);
+                let (_, def) = match self.occurrence(params)? {
+                    Some(occurrence) => occurrence,
+                    None => return Ok(Value::Null),
+                };
+                let analysis = self
+                    .analysis
+                    .as_ref()
+                    .expect("symbols come from an analysis");
+                let locations: Vec<_> = analysis
+                    .symbols
+                    .references(def)
+                    .into_iter()
+                    .filter(|loc| include_declaration || *loc != def)
+                    .filter_map(|loc| self.location(loc))
+                    .collect();
+                Ok(json!(locations))
+            }
+            "textDocument/completion" => self.completion(params),
+            _ => Err(ResponseError {
+                code: METHOD_NOT_FOUND,
+                message: format!("Unsupported method {}", method),
+            }),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: Value) -> Result<bool> {
+        match method {
+            "initialized" => self.analyze()?,
+            "textDocument/didOpen" => {
+                if let Ok(params) = parse_params::<DidOpenTextDocumentParams>(params) {
+                    self.documents
+                        .insert(params.text_document.uri, params.text_document.text);
+                    if self.analysis.is_none() || self.root.is_none() {
+                        self.analyze()?;
+                    }
+                }
+            }
+            "textDocument/didChange" => {
+                if let Ok(params) = parse_params::<DidChangeTextDocumentParams>(params) {
+                    if let Some(change) = params.content_changes.into_iter().last() {
+                        self.documents.insert(params.text_document.uri, change.text);
+                    }
+                }
+            }
+            "textDocument/didSave" => self.analyze()?,
+            "textDocument/didClose" => {
+                if let Ok(params) = parse_params::<DidCloseTextDocumentParams>(params) {
+                    self.documents.remove(&params.text_document.uri);
+                }
+            }
+            "exit" => return Ok(false),
+            _ => (),
+        }
+        Ok(true)
+    }
+
+    //**********************************************************************************************
+    // Analysis
+    //**********************************************************************************************
+
+    /// Analyzes the Move files of the workspace, or the open documents without a workspace, and
+    /// publishes their diagnostics.
+    fn analyze(&mut self) -> Result<()> {
+        let targets = match &self.root {
+            Some(root) => find_move_filenames(&[root.to_string_lossy().into_owned()]),
+            None => Ok(self
+                .documents
+                .keys()
+                .filter_map(|uri| Some(uri.to_file_path().ok()?.to_string_lossy().into_owned()))
+                .collect()),
+        };
+        let analysis = targets.and_then(|targets| {
+            Analysis::new(&targets, &self.config.dependencies, self.config.sender)
+        });
+        match analysis {
+            Ok(analysis) => {
+                self.analysis = Some(analysis);
+                self.publish_diagnostics()
+            }
+            Err(error) => write_message(
+                &mut self.output,
+                &json!({
+                    "jsonrpc": "2.0",
+                    "method": "window/logMessage",
+                    // Error
+                    "params": {"type": 1, "message": format!("Unable to analyze: {}", error)},
+                }),
+            ),
+        }
+    }
+
+    fn publish_diagnostics(&mut self) -> Result<()> {
+        let analysis = match &self.analysis {
+            Some(analysis) => analysis,
+            None => return Ok(()),
+        };
+        let mut diagnostics: BTreeMap<String, Vec<Diagnostic>> = analysis
+            .targets
+            .iter()
+            .map(|target| (target.clone(), vec![]))
+            .collect();
+        for error in &analysis.errors {
+            let (primary, secondary) = match error.split_first() {
+                Some(split) => split,
+                None => continue,
+            };
+            let range = match self.range(primary.0.into()) {
+                Some(range) => range,
+                None => continue,
+            };
+            let related_information = secondary
+                .iter()
+                .filter_map(|(loc, message): &(Loc, String)| {
+                    Some(DiagnosticRelatedInformation {
+                        location: self.location((*loc).into())?,
+                        message: message.clone(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            diagnostics
+                .entry(primary.0.file().to_string())
+                .or_insert_with(Vec::new)
+                .push(Diagnostic {
+                    range,
+                    severity: Some(DiagnosticSeverity::Error),
+                    source: Some("move".to_string()),
+                    message: primary.1.clone(),
+                    related_information: Some(related_information),
+                    ..Diagnostic::default()
+                });
+        }
+        // Clear the diagnostics of files which are not analyzed anymore.
+        for file in &self.published {
+            diagnostics.entry(file.clone()).or_insert_with(Vec::new);
+        }
+
+        let mut published = BTreeSet::new();
+        for (file, diagnostics) in diagnostics {
+            let uri = match Url::from_file_path(&file) {
+                Ok(uri) => uri,
+                Err(()) => continue,
+            };
+            if !diagnostics.is_empty() {
+                published.insert(file);
+            }
+            let params = PublishDiagnosticsParams {
+                uri,
+                diagnostics,
+                version: None,
+            };
+            write_message(
+                &mut self.output,
+                &json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": params,
+                }),
+            )?;
+        }
+        self.published = published;
+        Ok(())
+    }
+
+    //**********************************************************************************************
+    // Symbols
+    //**********************************************************************************************
+
+    /// Returns the occurrence of a symbol at the position of a request, with its definition.
+    fn occurrence(&self, params: Value) -> Result<Option<(SymbolLoc, SymbolLoc)>, ResponseError> {
+        let params = parse_params::<TextDocumentPositionParams>(params)?;
+        let analysis = match &self.analysis {
+            Some(analysis) => analysis,
+            None => return Ok(None),
+        };
+        let file = file_name(&params.text_document.uri)?;
+        let text = match analysis.files.get(file.as_str()) {
+            Some(text) => text,
+            None => return Ok(None),
+        };
+        let offset = position_to_offset(text, params.position);
+        Ok(analysis.symbols.occurrence_at(&file, offset))
+    }
+
+    fn completion(&self, params: Value) -> Result<Value, ResponseError> {
+        let params = parse_params::<TextDocumentPositionParams>(params)?;
+        let analysis = match &self.analysis {
+            Some(analysis) => analysis,
+            None => return Ok(json!([])),
+        };
+        let uri = &params.text_document.uri;
+        let file = file_name(uri)?;
+        let text = match self
+            .documents
+            .get(uri)
+            .or_else(|| analysis.files.get(file.as_str()))
+        {
+            Some(text) => text,
+            None => return Ok(json!([])),
+        };
+        let offset = position_to_offset(text, params.position);
+        // Skip the identifier being typed.
+        let before = &text[..identifier_start(text, offset)];
+
+        let members: &[Member] = if before.ends_with("::") {
+            let module_end = before.len() - 2;
+            let module = &before[identifier_start(before, module_end)..module_end];
+            analysis.symbols.module_members(module)
+        } else if before.ends_with('.') {
+            let local_end = before.len() - 1;
+            let local = &before[identifier_start(before, local_end)..local_end];
+            analysis.symbols.local_fields(&file, offset, local)
+        } else {
+            &[]
+        };
+        let items: Vec<_> = members
+            .iter()
+            .map(|member| CompletionItem {
+                kind: Some(completion_kind(member.kind)),
+                ..CompletionItem::new_simple(member.name.clone(), member.detail.clone())
+            })
+            .collect();
+        Ok(json!(items))
+    }
+
+    fn range(&self, loc: SymbolLoc) -> Option<Range> {
+        let text = self.analysis.as_ref()?.files.get(loc.file)?;
+        Some(Range::new(
+            offset_to_position(text, loc.start),
+            offset_to_position(text, loc.end),
+        ))
+    }
+
+    fn location(&self, loc: SymbolLoc) -> Option<Location> {
+        Some(Location::new(
+            Url::from_file_path(loc.file).ok()?,
+            self.range(loc)?,
+        ))
+    }
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, ResponseError> {
+    serde_json::from_value(params).map_err(invalid_params)
+}
+
+fn file_name(uri: &Url) -> Result<String, ResponseError> {
+    let path = uri
+        .to_file_path()
+        .map_err(|()| invalid_params(format!("{} is not a file", uri)))?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Returns the start of the identifier ending at `offset`.
+fn identifier_start(text: &str, offset: usize) -> usize {
+    text[..offset]
+        .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .map_or(0, |pos| pos + 1)
+}
+
+fn completion_kind(kind: SymbolKind) -> CompletionItemKind {
+    match kind {
+        SymbolKind::Module => CompletionItemKind::Module,
+        SymbolKind::Struct => CompletionItemKind::Struct,
+        SymbolKind::Field => CompletionItemKind::Field,
+        SymbolKind::Function => CompletionItemKind::Function,
+        SymbolKind::Constant => CompletionItemKind::Constant,
+        SymbolKind::Local => CompletionItemKind::Variable,
+    }
+}
@@ -0,0 +1,582 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Index of the symbols of a typed program.
+//!
+//! Every occurrence of a module member, struct field or local is recorded along with the location
+//! of its definition, which answers go-to-definition, hover and find-references requests. The
+//! public members of modules and the fields of structs are kept for completion.
+
+use move_ir_types::location::Loc;
+use move_lang::{
+    naming::ast::{self as N, StructFields, TypeName_, Type_},
+    parser::ast::{ConstantName, Field, FunctionVisibility, ModuleIdent, StructName, Var},
+    shared::Identifier,
+    typing::ast as T,
+};
+use std::collections::BTreeMap;
+
+/// Location of a symbol, as byte offsets in its file.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SymbolLoc {
+    pub file: &'static str,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl From<Loc> for SymbolLoc {
+    fn from(loc: Loc) -> Self {
+        Self {
+            file: loc.file(),
+            start: loc.span().start().to_usize(),
+            end: loc.span().end().to_usize(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SymbolKind {
+    Module,
+    Struct,
+    Field,
+    Function,
+    Constant,
+    Local,
+}
+
+#[derive(Clone, Debug)]
+pub struct Definition {
+    pub kind: SymbolKind,
+    pub name: String,
+    /// Declaration of the symbol, with its type.
+    pub detail: String,
+}
+
+/// A module member or struct field offered for completion.
+#[derive(Clone, Debug)]
+pub struct Member {
+    pub kind: SymbolKind,
+    pub name: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Symbols {
+    definitions: BTreeMap<SymbolLoc, Definition>,
+    /// Occurrences of symbols by file, definitions included, along with their definition.
+    occurrences: BTreeMap<&'static str, Vec<(SymbolLoc, SymbolLoc)>>,
+    /// Public functions and structs by module name.
+    members: BTreeMap<String, Vec<Member>>,
+    /// Fields by struct, named `address::Module::Struct`.
+    fields: BTreeMap<String, Vec<Member>>,
+    /// Locals with the struct they hold, if any.
+    locals: Vec<(SymbolLoc, String, Option<String>)>,
+}
+
+impl Symbols {
+    pub fn new(prog: &T::Program) -> Self {
+        let mut builder = Builder::default();
+        builder.declarations(prog);
+        builder.bodies(prog);
+        builder.symbols
+    }
+
+    /// Returns the innermost occurrence of a symbol at `offset`, with its definition.
+    pub fn occurrence_at(&self, file: &str, offset: usize) -> Option<(SymbolLoc, SymbolLoc)> {
+        self.occurrences
+            .get(file)?
+            .iter()
+            .filter(|(loc, _)| loc.start <= offset && offset <= loc.end)
+            .min_by_key(|(loc, _)| loc.end - loc.start)
+            .copied()
+    }
+
+    pub fn definition(&self, loc: &SymbolLoc) -> Option<&Definition> {
+        self.definitions.get(loc)
+    }
+
+    /// Returns the occurrences of the symbol defined at `def`, sorted.
+    pub fn references(&self, def: SymbolLoc) -> Vec<SymbolLoc> {
+        let mut references: Vec<_> = self
+            .occurrences
+            .values()
+            .flatten()
+            .filter(|(_, occurrence_def)| *occurrence_def == def)
+            .map(|(loc, _)| *loc)
+            .collect();
+        references.sort();
+        references.dedup();
+        references
+    }
+
+    /// Returns the public functions and structs of the modules named `module`.
+    pub fn module_members(&self, module: &str) -> &[Member] {
+        self.members.get(module).map_or(&[][..], Vec::as_slice)
+    }
+
+    /// Returns the fields of the struct held by the last local named `name` declared before
+    /// `offset`.
+    pub fn local_fields(&self, file: &str, offset: usize, name: &str) -> &[Member] {
+        self.locals
+            .iter()
+            .filter(|(loc, local, _)| loc.file == file && loc.start < offset && local == name)
+            .max_by_key(|(loc, _, _)| loc.start)
+            .and_then(|(_, _, struct_opt)| self.fields.get(struct_opt.as_ref()?))
+            .map_or(&[][..], Vec::as_slice)
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    symbols: Symbols,
+    functions: BTreeMap<(String, String), SymbolLoc>,
+    structs: BTreeMap<String, SymbolLoc>,
+    fields: BTreeMap<(String, String), SymbolLoc>,
+    /// Constants by module, or by script.
+    constants: BTreeMap<(String, String), SymbolLoc>,
+    /// Module or script whose bodies are visited.
+    context: String,
+    /// Locals in scope, innermost scope last.
+    scopes: Vec<BTreeMap<String, SymbolLoc>>,
+}
+
+impl Builder {
+    fn define(&mut self, loc: Loc, kind: SymbolKind, name: &str, detail: String) -> SymbolLoc {
+        let loc = SymbolLoc::from(loc);
+        self.symbols.definitions.insert(
+            loc,
+            Definition {
+                kind,
+                name: name.to_string(),
+                detail,
+            },
+        );
+        self.occurrence(loc, loc);
+        loc
+    }
+
+    fn occurrence(&mut self, loc: SymbolLoc, def: SymbolLoc) {
+        self.symbols
+            .occurrences
+            .entry(loc.file)
+            .or_insert_with(Vec::new)
+            .push((loc, def));
+    }
+
+    //**********************************************************************************************
+    // Declarations
+    //**********************************************************************************************
+
+    fn declarations(&mut self, prog: &T::Program) {
+        for (m, mdef) in prog.modules.iter() {
+            let module = format!("{}", m);
+            self.define(
+                m.0.loc,
+                SymbolKind::Module,
+                &m.0.value.name.to_string(),
+                format!("module {}", module),
+            );
+            let mut members = vec![];
+
+            for (s, sdef) in mdef.structs.iter() {
+                let name = format!("{}::{}", module, s);
+                let detail = struct_detail(&name, sdef);
+                let loc = self.define(s.loc(), SymbolKind::Struct, s.value(), detail.clone());
+                self.structs.insert(name.clone(), loc);
+                members.push(Member {
+                    kind: SymbolKind::Struct,
+                    name: s.to_string(),
+                    detail,
+                });
+                if let StructFields::Defined(fields) = &sdef.fields {
+                    let mut struct_fields = vec![];
+                    for (f, (_, ty)) in fields.iter() {
+                        let detail = format!("{}: {}", f, type_string(ty));
+                        let loc =
+                            self.define(f.loc(), SymbolKind::Field, f.value(), detail.clone());
+                        self.fields.insert((name.clone(), f.to_string()), loc);
+                        struct_fields.push(Member {
+                            kind: SymbolKind::Field,
+                            name: f.to_string(),
+                            detail,
+                        });
+                    }
+                    self.symbols.fields.insert(name, struct_fields);
+                }
+            }
+
+            for (f, fdef) in mdef.functions.iter() {
+                let detail = function_detail(&format!("{}::{}", module, f), fdef);
+                let loc = self.define(f.loc(), SymbolKind::Function, f.value(), detail.clone());
+                self.functions.insert((module.clone(), f.to_string()), loc);
+                if let FunctionVisibility::Public(_) = fdef.visibility {
+                    members.push(Member {
+                        kind: SymbolKind::Function,
+                        name: f.to_string(),
+                        detail,
+                    });
+                }
+            }
+
+            for (c, cdef) in mdef.constants.iter() {
+                self.constant_declaration(&module, &c, cdef);
+            }
+
+            self.symbols
+                .members
+                .entry(m.0.value.name.to_string())
+                .or_insert_with(Vec::new)
+                .extend(members);
+        }
+
+        for (key, script) in &prog.scripts {
+            let context = script_context(key);
+            for (c, cdef) in script.constants.iter() {
+                self.constant_declaration(&context, &c, cdef);
+            }
+            let f = &script.function_name;
+            self.define(
+                f.loc(),
+                SymbolKind::Function,
+                f.value(),
+                function_detail(f.value(), &script.function),
+            );
+        }
+    }
+
+    fn constant_declaration(&mut self, context: &str, c: &ConstantName, cdef: &T::Constant) {
+        let detail = format!("const {}: {}", c, type_string(&cdef.signature));
+        let loc = self.define(c.loc(), SymbolKind::Constant, c.value(), detail);
+        self.constants
+            .insert((context.to_string(), c.to_string()), loc);
+    }
+
+    //**********************************************************************************************
+    // Bodies
+    //**********************************************************************************************
+
+    fn bodies(&mut self, prog: &T::Program) {
+        for (m, mdef) in prog.modules.iter() {
+            self.context = format!("{}", m);
+            for (_, sdef) in mdef.structs.iter() {
+                if let StructFields::Defined(fields) = &sdef.fields {
+                    for (_, (_, ty)) in fields.iter() {
+                        self.type_(ty);
+                    }
+                }
+            }
+            for (_, cdef) in mdef.constants.iter() {
+                self.exp(&cdef.value);
+            }
+            for (_, fdef) in mdef.functions.iter() {
+                self.function(fdef);
+            }
+        }
+        for (key, script) in &prog.scripts {
+            self.context = script_context(key);
+            for (_, cdef) in script.constants.iter() {
+                self.exp(&cdef.value);
+            }
+            self.function(&script.function);
+        }
+    }
+
+    fn function(&mut self, fdef: &T::Function) {
+        self.scopes.push(BTreeMap::new());
+        for (var, ty) in &fdef.signature.parameters {
+            self.type_(ty);
+            self.local_declaration(var, ty);
+        }
+        self.type_(&fdef.signature.return_type);
+        for s in fdef.acquires.keys() {
+            let context = self.context.clone();
+            self.struct_use(&context, s);
+        }
+        if let T::FunctionBody_::Defined(seq) = &fdef.body.value {
+            self.sequence(seq);
+        }
+        self.scopes.pop();
+    }
+
+    fn sequence(&mut self, seq: &T::Sequence) {
+        use T::SequenceItem_ as S;
+        self.scopes.push(BTreeMap::new());
+        for item in seq {
+            match &item.value {
+                S::Seq(e) => self.exp(e),
+                S::Declare(lvalues) => self.lvalues(lvalues, true),
+                S::Bind(lvalues, _, e) => {
+                    self.exp(e);
+                    self.lvalues(lvalues, true)
+                }
+            }
+        }
+        self.scopes.pop();
+    }
+
+    fn lvalues(&mut self, lvalues: &T::LValueList, declare: bool) {
+        for lvalue in &lvalues.value {
+            self.lvalue(lvalue, declare)
+        }
+    }
+
+    fn lvalue(&mut self, lvalue: &T::LValue, declare: bool) {
+        use T::LValue_ as L;
+        match &lvalue.value {
+            L::Ignore => (),
+            L::Var(var, ty) if declare => self.local_declaration(var, ty),
+            L::Var(var, _) => self.local_use(var),
+            L::Unpack(m, s, _, fields) | L::BorrowUnpack(_, m, s, _, fields) => {
+                let module = format!("{}", m);
+                self.struct_use(&module, s);
+                for (f, (_, (_, lvalue))) in fields.iter() {
+                    self.field_use(m, s, &f);
+                    self.lvalue(lvalue, declare)
+                }
+            }
+        }
+    }
+
+    fn exp(&mut self, e: &T::Exp) {
+        use T::UnannotatedExp_ as E;
+        match &e.exp.value {
+            E::Move { var, .. } | E::Copy { var, .. } | E::Use(var) | E::BorrowLocal(_, var) => {
+                self.local_use(var)
+            }
+            E::Constant(m_opt, c) => {
+                let context = m_opt
+                    .as_ref()
+                    .map_or_else(|| self.context.clone(), |m| format!("{}", m));
+                if let Some(def) = self.constants.get(&(context, c.to_string())).copied() {
+                    self.occurrence(c.loc().into(), def);
+                }
+            }
+            E::ModuleCall(call) => {
+                let key = (format!("{}", call.module), call.name.to_string());
+                if let Some(def) = self.functions.get(&key).copied() {
+                    self.occurrence(call.name.loc().into(), def);
+                }
+                for ty in &call.type_arguments {
+                    self.type_(ty);
+                }
+                self.exp(&call.arguments)
+            }
+            E::Builtin(_, e)
+            | E::Loop { body: e, .. }
+            | E::Return(e)
+            | E::Abort(e)
+            | E::Dereference(e)
+            | E::UnaryExp(_, e)
+            | E::TempBorrow(_, e)
+            | E::Cast(e, _)
+            | E::Annotate(e, _) => self.exp(e),
+            E::IfElse(econd, et, ef) => {
+                self.exp(econd);
+                self.exp(et);
+                self.exp(ef)
+            }
+            E::While(econd, ebody) => {
+                self.exp(econd);
+                self.exp(ebody)
+            }
+            E::Mutate(el, er) | E::BinopExp(el, _, _, er) => {
+                self.exp(el);
+                self.exp(er)
+            }
+            E::Block(seq) => self.sequence(seq),
+            E::Assign(lvalues, _, e) => {
+                self.exp(e);
+                self.lvalues(lvalues, false)
+            }
+            E::Pack(m, s, tys, fields) => {
+                let module = format!("{}", m);
+                self.struct_use(&module, s);
+                for ty in tys {
+                    self.type_(ty);
+                }
+                for (f, (_, (_, e))) in fields.iter() {
+                    self.field_use(m, s, &f);
+                    self.exp(e)
+                }
+            }
+            E::ExpList(items) => {
+                for item in items {
+                    match item {
+                        T::ExpListItem::Single(e, _) | T::ExpListItem::Splat(_, e, _) => {
+                            self.exp(e)
+                        }
+                    }
+                }
+            }
+            E::Borrow(_, e, f) => {
+                self.exp(e);
+                if let Some((m, s)) = struct_type(&e.ty) {
+                    self.field_use(m, s, f)
+                }
+            }
+            E::Unit { .. }
+            | E::Value(_)
+            | E::InferredNum(_)
+            | E::Break
+            | E::Continue
+            | E::Spec(_, _)
+            | E::UnresolvedError => (),
+        }
+    }
+
+    /// Records the structs named in a type written in the sources.
+    fn type_(&mut self, ty: &N::Type) {
+        match &ty.value {
+            Type_::Ref(_, inner) => self.type_(inner),
+            Type_::Apply(_, name, tys) => {
+                if let TypeName_::ModuleType(m, s) = &name.value {
+                    let module = format!("{}", m);
+                    self.struct_use(&module, s);
+                }
+                for ty in tys {
+                    self.type_(ty);
+                }
+            }
+            Type_::Unit
+            | Type_::Param(_)
+            | Type_::Var(_)
+            | Type_::Anything
+            | Type_::UnresolvedError => (),
+        }
+    }
+
+    fn struct_use(&mut self, module: &str, s: &StructName) {
+        if let Some(def) = self.structs.get(&format!("{}::{}", module, s)).copied() {
+            self.occurrence(s.loc().into(), def);
+        }
+    }
+
+    fn field_use(&mut self, m: &ModuleIdent, s: &StructName, f: &Field) {
+        let key = (format!("{}::{}", m, s), f.to_string());
+        if let Some(def) = self.fields.get(&key).copied() {
+            self.occurrence(f.loc().into(), def);
+        }
+    }
+
+    fn local_declaration(&mut self, var: &Var, ty: &N::Type) {
+        let detail = format!("{}: {}", var, type_string(ty));
+        let loc = self.define(var.loc(), SymbolKind::Local, var.value(), detail);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(var.to_string(), loc);
+        }
+        let struct_opt = struct_type(ty).map(|(m, s)| format!("{}::{}", m, s));
+        self.symbols.locals.push((loc, var.to_string(), struct_opt));
+    }
+
+    fn local_use(&mut self, var: &Var) {
+        let def = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(var.value()))
+            .copied();
+        if let Some(def) = def {
+            self.occurrence(var.loc().into(), def);
+        }
+    }
+}
+
+fn script_context(key: &str) -> String {
+    format!("script {}", key)
+}
+
+/// Returns the struct a value of type `ty` holds or references.
+fn struct_type(ty: &N::Type) -> Option<(&ModuleIdent, &StructName)> {
+    match &ty.value {
+        Type_::Ref(_, inner) => struct_type(inner),
+        Type_::Apply(_, name, _) => match &name.value {
+            TypeName_::ModuleType(m, s) => Some((m, s)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub fn type_string(ty: &N::Type) -> String {
+    match &ty.value {
+        Type_::Unit => "()".to_string(),
+        Type_::Ref(mut_, inner) => {
+            format!("&{}{}", if *mut_ { "mut " } else { "" }, type_string(inner))
+        }
+        Type_::Param(tp) => tp.user_specified_name.value.to_string(),
+        Type_::Apply(_, name, tys) => {
+            let tys = tys.iter().map(type_string).collect::<Vec<_>>();
+            match &name.value {
+                TypeName_::Multiple(_) => format!("({})", tys.join(", ")),
+                name if tys.is_empty() => format!("{}", name),
+                name => format!("{}<{}>", name, tys.join(", ")),
+            }
+        }
+        Type_::Var(_) | Type_::Anything | Type_::UnresolvedError => "_".to_string(),
+    }
+}
+
+fn type_parameters(tparams: &[N::TParam]) -> String {
+    if tparams.is_empty() {
+        return "".to_string();
+    }
+    let names = tparams
+        .iter()
+        .map(|tp| tp.user_specified_name.value.clone())
+        .collect::<Vec<_>>();
+    format!("<{}>", names.join(", "))
+}
+
+fn function_detail(name: &str, fdef: &T::Function) -> String {
+    let signature = &fdef.signature;
+    let parameters = signature
+        .parameters
+        .iter()
+        .map(|(var, ty)| format!("{}: {}", var, type_string(ty)))
+        .collect::<Vec<_>>();
+    let return_type = match &signature.return_type.value {
+        Type_::Unit => "".to_string(),
+        _ => format!(": {}", type_string(&signature.return_type)),
+    };
+    format!(
+        "{}{}fun {}{}({}){}",
+        match fdef.visibility {
+            FunctionVisibility::Public(_) => "public ",
+            FunctionVisibility::Internal => "",
+        },
+        match fdef.body.value {
+            T::FunctionBody_::Native => "native ",
+            T::FunctionBody_::Defined(_) => "",
+        },
+        name,
+        type_parameters(&signature.type_parameters),
+        parameters.join(", "),
+        return_type
+    )
+}
+
+fn struct_detail(name: &str, sdef: &N::StructDefinition) -> String {
+    let resource = if sdef.resource_opt.is_some() {
+        "resource "
+    } else {
+        ""
+    };
+    let header = format!(
+        "{}struct {}{}",
+        resource,
+        name,
+        type_parameters(&sdef.type_parameters)
+    );
+    match &sdef.fields {
+        StructFields::Native(_) => format!("native {}", header),
+        StructFields::Defined(fields) => {
+            let mut fields = fields
+                .iter()
+                .map(|(f, (idx, ty))| (*idx, format!("    {}: {}", f, type_string(ty))))
+                .collect::<Vec<_>>();
+            fields.sort();
+            let fields = fields.into_iter().map(|(_, f)| f).collect::<Vec<_>>();
+            format!("{} {{\n{}\n}}", header, fields.join(",\n"))
+        }
+    }
+}
@@ -0,0 +1,267 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use libra_temppath::TempPath;
+use lsp_types::Url;
+use move_lsp::protocol::{read_message, write_message};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeSet, VecDeque},
+    fs,
+    io::BufReader,
+    path::Path,
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+const MODULE: &str = "address 0x2 {
+module M {
+    resource struct Coin { value: u64 }
+
+    public fun mint(value: u64): Coin {
+        Coin { value: value }
+    }
+
+    public fun value(coin: &Coin): u64 {
+        coin.value
+    }
+
+    public fun burn(coin: Coin) {
+        let Coin { value: _ } = coin;
+    }
+}
+}
+";
+
+const SCRIPT: &str = "script {
+use 0x2::M;
+fun main() {
+    let coin = M::mint(10);
+    assert(M::value(&coin) == 10, 1);
+    M::burn(coin);
+}
+}
+";
+
+/// Client driving the server over its stdio.
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+    notifications: VecDeque<Value>,
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_move-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Self {
+            child,
+            stdin,
+            stdout,
+            next_id: 0,
+            notifications: VecDeque::new(),
+        }
+    }
+
+    fn read(&mut self) -> Value {
+        read_message(&mut self.stdout)
+            .unwrap()
+            .expect("server closed its output")
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = json!(self.next_id);
+        let request = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+        write_message(&mut self.stdin, &request).unwrap();
+        loop {
+            let message = self.read();
+            if message.get("id") == Some(&id) {
+                assert_eq!(message.get("error"), None);
+                return message["result"].clone();
+            }
+            self.notifications.push_back(message);
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        let notification = json!({"jsonrpc": "2.0", "method": method, "params": params});
+        write_message(&mut self.stdin, &notification).unwrap();
+    }
+
+    /// Waits for the next diagnostics of a document.
+    fn diagnostics(&mut self, uri: &Url) -> Vec<Value> {
+        loop {
+            let message = match self.notifications.pop_front() {
+                Some(message) => message,
+                None => self.read(),
+            };
+            if message["method"] == "textDocument/publishDiagnostics"
+                && message["params"]["uri"] == uri.as_str()
+            {
+                return message["params"]["diagnostics"].as_array().unwrap().clone();
+            }
+        }
+    }
+
+    fn stop(mut self) {
+        assert_eq!(self.request("shutdown", Value::Null), Value::Null);
+        self.notify("exit", Value::Null);
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+fn position(uri: &Url, line: u64, character: u64) -> Value {
+    json!({
+        "textDocument": {"uri": uri},
+        "position": {"line": line, "character": character},
+    })
+}
+
+fn range_start(location: &Value) -> (u64, u64) {
+    let start = &location["range"]["start"];
+    (
+        start["line"].as_u64().unwrap(),
+        start["character"].as_u64().unwrap(),
+    )
+}
+
+fn labels(items: &Value) -> BTreeSet<String> {
+    items
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["label"].as_str().unwrap().to_string())
+        .collect()
+}
+
+fn start_workspace(root: &Path) -> (Client, Url, Url) {
+    fs::write(root.join("M.move"), MODULE).unwrap();
+    fs::write(root.join("main.move"), SCRIPT).unwrap();
+    let module = Url::from_file_path(root.join("M.move")).unwrap();
+    let script = Url::from_file_path(root.join("main.move")).unwrap();
+
+    let mut client = Client::start();
+    let result = client.request(
+        "initialize",
+        json!({
+            "processId": null,
+            "rootUri": Url::from_file_path(root).unwrap(),
+            "capabilities": {},
+        }),
+    );
+    assert_eq!(result["capabilities"]["definitionProvider"], true);
+    client.notify("initialized", json!({}));
+    (client, module, script)
+}
+
+#[test]
+fn test_symbols() {
+    let temp = TempPath::new();
+    temp.create_as_dir().unwrap();
+    let root = temp.path().canonicalize().unwrap();
+    let (mut client, module, script) = start_workspace(&root);
+    assert_eq!(client.diagnostics(&module), Vec::<Value>::new());
+    assert_eq!(client.diagnostics(&script), Vec::<Value>::new());
+
+    // `mint` in `M::mint(10)`.
+    let location = client.request("textDocument/definition", position(&script, 3, 19));
+    assert_eq!(location["uri"], module.as_str());
+    assert_eq!(range_start(&location), (4, 15));
+
+    // `coin` in `M::burn(coin)`.
+    let hover = client.request("textDocument/hover", position(&script, 5, 13));
+    let contents = hover["contents"]["value"].as_str().unwrap();
+    assert!(contents.contains("coin: 0x2::M::Coin"), "{}", contents);
+    assert_eq!(range_start(&hover), (5, 12));
+
+    // The declaration of `mint` and its call.
+    let mut params = position(&module, 4, 16);
+    params["context"] = json!({"includeDeclaration": true});
+    let references = client.request("textDocument/references", params);
+    let references = references.as_array().unwrap();
+    assert_eq!(references.len(), 2);
+    assert!(references.iter().any(|loc| loc["uri"] == script.as_str()));
+
+    // The declaration of the `value` field, packed, borrowed and unpacked.
+    let mut params = position(&module, 2, 28);
+    params["context"] = json!({"includeDeclaration": false});
+    let references = client.request("textDocument/references", params);
+    assert_eq!(references.as_array().unwrap().len(), 3);
+
+    client.stop();
+}
+
+#[test]
+fn test_completion() {
+    let temp = TempPath::new();
+    temp.create_as_dir().unwrap();
+    let root = temp.path().canonicalize().unwrap();
+    let (mut client, module, script) = start_workspace(&root);
+
+    // Fields of `coin` in `coin.value`.
+    let items = client.request("textDocument/completion", position(&module, 9, 13));
+    let expected: BTreeSet<_> = vec!["value".to_string()].into_iter().collect();
+    assert_eq!(labels(&items), expected);
+
+    // Members of `M` while editing the script.
+    let edited = SCRIPT.replace("    M::burn(coin);\n", "    M::burn(coin);\n    M::\n");
+    client.notify(
+        "textDocument/didOpen",
+        json!({"textDocument": {"uri": script, "languageId": "move", "version": 1, "text": SCRIPT}}),
+    );
+    client.notify(
+        "textDocument/didChange",
+        json!({
+            "textDocument": {"uri": script, "version": 2},
+            "contentChanges": [{"text": edited}],
+        }),
+    );
+    let items = client.request("textDocument/completion", position(&script, 6, 7));
+    let expected: BTreeSet<_> = vec!["Coin", "burn", "mint", "value"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    assert_eq!(labels(&items), expected);
+
+    client.stop();
+}
+
+#[test]
+fn test_diagnostics_on_save() {
+    let temp = TempPath::new();
+    temp.create_as_dir().unwrap();
+    let root = temp.path().canonicalize().unwrap();
+    let (mut client, module, script) = start_workspace(&root);
+    assert!(client.diagnostics(&script).is_empty());
+
+    fs::write(
+        root.join("main.move"),
+        SCRIPT.replace("M::mint(10)", "M::mint(true)"),
+    )
+    .unwrap();
+    client.notify(
+        "textDocument/didSave",
+        json!({"textDocument": {"uri": script}}),
+    );
+    assert!(client.diagnostics(&module).is_empty());
+    let diagnostics = client.diagnostics(&script);
+    assert!(!diagnostics.is_empty());
+    assert_eq!(range_start(&diagnostics[0]).0, 3);
+
+    // Fixing the error clears the diagnostics.
+    fs::write(root.join("main.move"), SCRIPT).unwrap();
+    client.notify(
+        "textDocument/didSave",
+        json!({"textDocument": {"uri": script}}),
+    );
+    assert!(client.diagnostics(&script).is_empty());
+
+    client.stop();
+}
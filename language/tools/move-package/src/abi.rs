@@ -0,0 +1,118 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! ABIs of compiled scripts, in the format of the ones `abigen` generates for the stdlib.
+
+use anyhow::{bail, Result};
+use bytecode_source_map::source_map::SourceMap;
+use heck::SnakeCase;
+use libra_types::transaction::{ArgumentABI, ScriptABI, TypeArgumentABI};
+use move_core_types::language_storage::TypeTag;
+use move_ir_types::location::Loc;
+use move_lang::errors::FilesSourceText;
+use vm::{
+    access::ScriptAccess,
+    file_format::{CompiledScript, FunctionDefinitionIndex, SignatureToken},
+};
+
+/// Computes the ABI of a script, taking argument names from its source map and documentation
+/// from the `///` comments preceding its declaration at `loc`.
+pub fn script_abi(
+    name: &str,
+    loc: Loc,
+    script: &CompiledScript,
+    source_map: &SourceMap<Loc>,
+    files: &FilesSourceText,
+) -> Result<ScriptABI> {
+    let function_map = source_map.get_function_source_map(FunctionDefinitionIndex(0))?;
+    let ty_args = function_map
+        .type_parameters
+        .iter()
+        .map(|(name, _)| TypeArgumentABI::new(name.to_snake_case()))
+        .collect();
+    let parameters = &script.signature_at(script.as_inner().parameters).0;
+    if parameters.len() != function_map.parameters.len() {
+        bail!(
+            "Source map of script {} does not match its parameters",
+            name
+        );
+    }
+    let args = function_map
+        .parameters
+        .iter()
+        .zip(parameters)
+        .filter_map(
+            |((name, _), token)| match type_tag_skipping_references(token) {
+                Ok(Some(tag)) => Some(Ok(ArgumentABI::new(name.clone(), tag))),
+                Ok(None) => None,
+                Err(error) => Some(Err(error)),
+            },
+        )
+        .collect::<Result<_>>()?;
+    let mut code = vec![];
+    script.serialize(&mut code)?;
+    let doc = files
+        .get(loc.file())
+        .map(|source| doc_comment(source, loc.span().start().to_usize()))
+        .unwrap_or_default();
+    Ok(ScriptABI::new(name.to_string(), doc, code, ty_args, args))
+}
+
+fn type_tag_skipping_references(token: &SignatureToken) -> Result<Option<TypeTag>> {
+    use SignatureToken::*;
+    let tag = match token {
+        Bool => TypeTag::Bool,
+        U8 => TypeTag::U8,
+        U64 => TypeTag::U64,
+        U128 => TypeTag::U128,
+        Address => TypeTag::Address,
+        Signer => TypeTag::Signer,
+        Vector(token) => match type_tag_skipping_references(token)? {
+            Some(tag) => TypeTag::Vector(Box::new(tag)),
+            None => bail!(
+                "References such as {:?} are only allowed in the list of parameters.",
+                token
+            ),
+        },
+        // Skip references (most likely a `&signer` type)
+        Reference(_) | MutableReference(_) => return Ok(None),
+        Struct(_) | StructInstantiation(_, _) | TypeParameter(_) => {
+            bail!("Type {:?} is not allowed in scripts.", token)
+        }
+    };
+    Ok(Some(tag))
+}
+
+/// Collects the `///` lines right above the line containing `offset`.
+fn doc_comment(source: &str, offset: usize) -> String {
+    let line_start = source[..offset].rfind('\n').map_or(0, |pos| pos + 1);
+    let mut lines: Vec<_> = source[..line_start]
+        .lines()
+        .rev()
+        .map(str::trim)
+        .take_while(|line| line.starts_with("///"))
+        .map(|line| {
+            let text = &line[3..];
+            if text.starts_with(' ') {
+                &text[1..]
+            } else {
+                text
+            }
+        })
+        .collect();
+    lines.reverse();
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_doc_comment() {
+        let source = "script {\n/// Pays a payee.\n///\n///  Indented.\nfun main() {}\n}";
+        let offset = source.find("main").unwrap();
+        assert_eq!(doc_comment(source, offset), "Pays a payee.\n\n Indented.");
+        assert_eq!(doc_comment(source, source.find("script").unwrap()), "");
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use move_package::build::{build, BUILD_DIR};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "Move Package",
+    about = "Build Move packages described by a Move.toml"
+)]
+enum Command {
+    /// Compile a package and its local dependencies into its build directory
+    Build {
+        /// The directory of the package
+        #[structopt(long = "path", short = "p", default_value = ".")]
+        path: PathBuf,
+        /// Compile all the packages, even the ones unchanged since the previous build
+        #[structopt(long = "force", short = "f")]
+        force: bool,
+    },
+}
+
+fn main() -> anyhow::Result<()> {
+    match Command::from_args() {
+        Command::Build { path, force } => {
+            let summary = build(&path, force)?;
+            for name in &summary.compiled {
+                println!("Compiled {}", name);
+            }
+            for name in &summary.up_to_date {
+                println!("Up to date {}", name);
+            }
+            println!("Build output in {}", path.join(BUILD_DIR).display());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,371 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Building a package and its local dependencies.
+//!
+//! Every local package of the graph is compiled into its own directory of the `build` directory
+//! of the root package:
+//!
+//! ```text
+//! build/<Package>/
+//!     BuildInfo.toml
+//!     sources/<path of the source in the package>.move
+//!     bytecode_modules/<Module>.mv
+//!     bytecode_scripts/<script>.mv
+//!     source_maps/<Module or script>.mvsm
+//!     abis/<script>.abi
+//! ```
+//!
+//! The `sources` directory holds the sources of the package with their named addresses replaced
+//! by their value, which is what the package and its dependents are compiled from, see
+//! `named_addresses`.
+//!
+//! A package is only compiled again when the hash of its sources, its address or the hash of one
+//! of its dependencies changed since the previous build. Sources are hashed along with their path
+//! relative to the `sources` directory of their package. The stdlib is not compiled; its sources
+//! are only dependencies of the packages using it.
+//!
+//! The compiler places modules declared outside of an `address` block under the address of the
+//! package being compiled, including the ones of its dependencies: packages used as dependencies
+//! must declare their modules in an `address` block.
+
+use crate::{
+    abi::script_abi,
+    manifest::SOURCES_DIR,
+    named_addresses,
+    resolver::{PackageSource, ResolvedGraph, ResolvedPackage},
+};
+use anyhow::{bail, format_err, Result};
+use move_core_types::account_address::AccountAddress;
+use move_lang::{
+    compiled_unit::{verify_units, CompiledUnit},
+    errors::{report_errors_to_buffer, Errors, FilesSourceText},
+    move_compile_no_report,
+    shared::Address,
+    MOVE_COMPILED_EXTENSION, SOURCE_MAP_EXTENSION,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use vm::access::ModuleAccess;
+
+pub const BUILD_DIR: &str = "build";
+pub const BUILD_INFO_FILE_NAME: &str = "BuildInfo.toml";
+pub const BYTECODE_MODULES_DIR: &str = "bytecode_modules";
+pub const BYTECODE_SCRIPTS_DIR: &str = "bytecode_scripts";
+pub const SOURCE_MAPS_DIR: &str = "source_maps";
+pub const ABIS_DIR: &str = "abis";
+pub const ABI_EXTENSION: &str = "abi";
+
+/// Packages compiled or skipped by a build, in build order.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct BuildSummary {
+    pub compiled: Vec<String>,
+    /// Packages left unchanged since the previous build.
+    pub up_to_date: Vec<String>,
+}
+
+/// Recorded after a package is compiled, to skip it in the next builds while it is unchanged.
+#[derive(Debug, Deserialize, Serialize)]
+struct BuildInfo {
+    address: String,
+    source_hash: String,
+}
+
+/// Builds the package in `root_dir` and its local dependencies, compiling all of them again if
+/// `force` is set.
+pub fn build(root_dir: &Path, force: bool) -> Result<BuildSummary> {
+    let graph = ResolvedGraph::resolve(root_dir)?;
+    let build_dir = root_dir.join(BUILD_DIR);
+    let mut source_files = BTreeMap::new();
+    let mut source_hashes = BTreeMap::new();
+    let mut summary = BuildSummary::default();
+
+    for pkg in &graph.packages {
+        let sources = read_sources(pkg, &graph.addresses)?;
+        let deps = graph.transitive_dependencies(&pkg.name);
+        let dep_hashes: Vec<&str> = deps
+            .iter()
+            .map(|dep| source_hashes[&dep.name].as_str())
+            .collect();
+        let hash = source_hash(pkg, &sources, &dep_hashes);
+        let dep_files: Vec<String> = deps
+            .iter()
+            .flat_map(|dep| source_files[&dep.name].clone())
+            .collect();
+        source_hashes.insert(pkg.name.clone(), hash.clone());
+
+        let files = match pkg.source {
+            PackageSource::Local(_) => {
+                let out_dir = build_dir.join(&pkg.name);
+                // Dependents are compiled from the substituted sources, so they are written even
+                // if the package is up to date.
+                let files = write_sources(&out_dir.join(SOURCES_DIR), &sources)?;
+                if !force
+                    && read_build_info(&out_dir).map_or(false, |info| info.source_hash == hash)
+                {
+                    summary.up_to_date.push(pkg.name.clone());
+                } else {
+                    compile_package(pkg, &files, &dep_files, &out_dir)?;
+                    let info = BuildInfo {
+                        address: format!("{}", pkg.address),
+                        source_hash: hash,
+                    };
+                    fs::write(out_dir.join(BUILD_INFO_FILE_NAME), toml::to_string(&info)?)?;
+                    summary.compiled.push(pkg.name.clone());
+                }
+                files
+            }
+            PackageSource::Stdlib => pkg.source_files()?,
+        };
+        source_files.insert(pkg.name.clone(), files);
+    }
+    Ok(summary)
+}
+
+/// A source of a package, by its path relative to the `sources` directory of the package.
+struct Source {
+    path: PathBuf,
+    contents: String,
+}
+
+/// Reads the sources of a package, with the named addresses of local packages replaced.
+fn read_sources(
+    pkg: &ResolvedPackage,
+    addresses: &BTreeMap<String, Address>,
+) -> Result<Vec<Source>> {
+    let sources_dir = pkg.sources_dir();
+    pkg.source_files()?
+        .iter()
+        .map(|file| {
+            let contents = fs::read_to_string(file)
+                .map_err(|e| format_err!("Unable to read {}: {}", file, e))?;
+            let contents = match pkg.source {
+                PackageSource::Local(_) => named_addresses::substitute(file, &contents, addresses)?,
+                PackageSource::Stdlib => contents,
+            };
+            let path = Path::new(file)
+                .strip_prefix(&sources_dir)
+                .map_err(|_| format_err!("{} is not in {}", file, sources_dir.display()))?
+                .to_path_buf();
+            Ok(Source { path, contents })
+        })
+        .collect()
+}
+
+/// Writes `sources` into `dir`, replacing its previous contents, and returns their paths.
+fn write_sources(dir: &Path, sources: &[Source]) -> Result<Vec<String>> {
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    sources
+        .iter()
+        .map(|source| {
+            let path = dir.join(&source.path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, &source.contents)?;
+            Ok(path.to_string_lossy().into_owned())
+        })
+        .collect()
+}
+
+fn source_hash(pkg: &ResolvedPackage, sources: &[Source], dep_hashes: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(pkg.name.as_bytes());
+    hasher.update(pkg.address.as_ref());
+    for source in sources {
+        // Components are joined with `/`, so the hash doesn't depend on the platform.
+        let components: Vec<_> = source
+            .path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect();
+        hasher.update(components.join("/").as_bytes());
+        hasher.update(&[0]);
+        hasher.update(source.contents.as_bytes());
+        hasher.update(&[0]);
+    }
+    for dep_hash in dep_hashes {
+        hasher.update(dep_hash.as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+fn read_build_info(out_dir: &Path) -> Option<BuildInfo> {
+    let contents = fs::read_to_string(out_dir.join(BUILD_INFO_FILE_NAME)).ok()?;
+    toml::from_str(&contents).ok()
+}
+
+fn compile_package(
+    pkg: &ResolvedPackage,
+    files: &[String],
+    deps: &[String],
+    out_dir: &Path,
+) -> Result<()> {
+    let (files_text, units) = move_compile_no_report(files, deps, Some(pkg.address))?;
+    let units = match units {
+        Ok(units) => units,
+        Err(errors) => return compile_errors(pkg, files_text, errors),
+    };
+    let (units, errors) = verify_units(units);
+    if !errors.is_empty() {
+        return compile_errors(pkg, files_text, errors);
+    }
+    let package_address = AccountAddress::new(pkg.address.to_u8());
+    for unit in &units {
+        if let CompiledUnit::Module { module, .. } = unit {
+            let address = *module.self_id().address();
+            if address != package_address {
+                bail!(
+                    "Module {} is declared at {} but package '{}' is published at {}",
+                    unit.name(),
+                    address,
+                    pkg.name,
+                    pkg.address
+                );
+            }
+        }
+    }
+
+    // The substituted sources are kept.
+    for dir in &[
+        BYTECODE_MODULES_DIR,
+        BYTECODE_SCRIPTS_DIR,
+        SOURCE_MAPS_DIR,
+        ABIS_DIR,
+    ] {
+        let dir = out_dir.join(dir);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)?;
+        }
+        fs::create_dir_all(dir)?;
+    }
+    let out_file = |dir: &str, name: &str, extension: &str| -> PathBuf {
+        out_dir.join(dir).join(format!("{}.{}", name, extension))
+    };
+
+    for unit in units {
+        let name = unit.name();
+        fs::write(
+            out_file(SOURCE_MAPS_DIR, &name, SOURCE_MAP_EXTENSION),
+            unit.serialize_source_map(),
+        )?;
+        let dir = match &unit {
+            CompiledUnit::Module { .. } => BYTECODE_MODULES_DIR,
+            CompiledUnit::Script {
+                loc,
+                script,
+                source_map,
+                ..
+            } => {
+                let abi = script_abi(&name, *loc, script, source_map, &files_text)?;
+                fs::write(
+                    out_file(ABIS_DIR, &name, ABI_EXTENSION),
+                    lcs::to_bytes(&abi)?,
+                )?;
+                BYTECODE_SCRIPTS_DIR
+            }
+        };
+        fs::write(
+            out_file(dir, &name, MOVE_COMPILED_EXTENSION),
+            unit.serialize(),
+        )?;
+    }
+    Ok(())
+}
+
+fn compile_errors(pkg: &ResolvedPackage, files: FilesSourceText, errors: Errors) -> Result<()> {
+    bail!(
+        "Unable to compile package '{}':\n{}",
+        pkg.name,
+        String::from_utf8_lossy(&report_errors_to_buffer(files, errors))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{MANIFEST_FILE_NAME, SOURCES_DIR};
+    use libra_temppath::TempPath;
+    use libra_types::transaction::ScriptABI;
+    use move_core_types::language_storage::TypeTag;
+
+    fn write_package(root: &Path, dir: &str, manifest: &str, sources: &[(&str, &str)]) {
+        let dir = root.join(dir);
+        fs::create_dir_all(dir.join(SOURCES_DIR)).unwrap();
+        fs::write(dir.join(MANIFEST_FILE_NAME), manifest).unwrap();
+        for (name, source) in sources {
+            fs::write(dir.join(SOURCES_DIR).join(name), source).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        let temp = TempPath::new();
+        temp.create_as_dir().unwrap();
+        let root = temp.path();
+        write_package(
+            root,
+            "util",
+            "[package]\nname = \"Util\"\naddress = \"0x2\"\n",
+            &[(
+                "Util.move",
+                "address 0x2 { module Util { public fun double(x: u64): u64 { x * 2 } } }",
+            )],
+        );
+        write_package(
+            root,
+            "app",
+            "[package]\nname = \"App\"\naddress = \"0x3\"\n[dependencies]\nUtil = { local = \"../util\" }\n",
+            &[(
+                "pay.move",
+                "script {\nuse 0x2::Util;\n/// Pays twice the amount.\nfun pay(_account: &signer, amount: u64) { Util::double(amount); }\n}",
+            )],
+        );
+        let app = root.join("app");
+        let out_dir = app.join(BUILD_DIR);
+
+        let summary = build(&app, false).unwrap();
+        assert_eq!(summary.compiled, vec!["Util", "App"]);
+        assert!(out_dir.join("Util/bytecode_modules/Util.mv").exists());
+        assert!(out_dir.join("Util/source_maps/Util.mvsm").exists());
+        assert!(out_dir.join("App/bytecode_scripts/pay.mv").exists());
+        let abi: ScriptABI =
+            lcs::from_bytes(&fs::read(out_dir.join("App/abis/pay.abi")).unwrap()).unwrap();
+        assert_eq!(abi.name(), "pay");
+        assert_eq!(abi.doc(), "Pays twice the amount.");
+        assert_eq!(abi.args().len(), 1);
+        assert_eq!(abi.args()[0].name(), "amount");
+        assert_eq!(abi.args()[0].type_tag(), &TypeTag::U64);
+
+        // Nothing changed.
+        let summary = build(&app, false).unwrap();
+        assert_eq!(summary.up_to_date, vec!["Util", "App"]);
+
+        // A change in a dependency rebuilds its dependents.
+        fs::write(
+            root.join("util").join(SOURCES_DIR).join("Util.move"),
+            "address 0x2 { module Util { public fun double(x: u64): u64 { x + x } } }",
+        )
+        .unwrap();
+        let summary = build(&app, false).unwrap();
+        assert_eq!(summary.compiled, vec!["Util", "App"]);
+
+        let summary = build(&app, true).unwrap();
+        assert_eq!(summary.compiled, vec!["Util", "App"]);
+
+        // Modules are published under the address of their package.
+        fs::write(
+            root.join("app").join(SOURCES_DIR).join("App.move"),
+            "address 0x2 { module App {} }",
+        )
+        .unwrap();
+        assert!(build(&app, false).is_err());
+    }
+}
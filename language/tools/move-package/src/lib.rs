@@ -0,0 +1,10 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+pub mod abi;
+pub mod build;
+pub mod manifest;
+pub mod named_addresses;
+pub mod resolver;
@@ -0,0 +1,166 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The `Move.toml` manifest of a package.
+//!
+//! ```toml
+//! [package]
+//! name = "Payments"
+//! version = "0.1.0"
+//! # Address the modules of the package are published under, literal or named.
+//! address = "Payments"
+//!
+//! [addresses]
+//! Payments = "0x42"
+//!
+//! [dependencies]
+//! Stdlib = { stdlib = true }
+//! Utils = { local = "../utils" }
+//! ```
+//!
+//! Named addresses can be used in the sources of the packages in place of address literals, e.g.
+//! `address Payments { ... }` or `use Payments::Account;`, see `named_addresses`.
+
+use anyhow::{bail, format_err, Result};
+use move_lang::shared::{Address, ADDRESS_LENGTH};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, path::Path};
+
+pub const MANIFEST_FILE_NAME: &str = "Move.toml";
+/// Directory of a package holding its Move sources.
+pub const SOURCES_DIR: &str = "sources";
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub package: PackageInfo,
+    /// Named addresses, from name to hex literal.
+    #[serde(default)]
+    pub addresses: BTreeMap<String, String>,
+    /// Dependencies, keyed by package name.
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Dependency>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PackageInfo {
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Address modules and scripts are compiled with, either a hex literal or a named address.
+    pub address: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum Dependency {
+    /// A package in a local directory, relative to the depending package.
+    Local { local: String },
+    /// The standard library bundled with this tool, published at `0x1`.
+    Stdlib { stdlib: bool },
+}
+
+impl Manifest {
+    pub fn parse(contents: &str) -> Result<Self> {
+        let manifest: Self = toml::from_str(contents)?;
+        for (name, dep) in &manifest.dependencies {
+            if let Dependency::Stdlib { stdlib: false } = dep {
+                bail!("Dependency '{}' sets 'stdlib = false'", name);
+            }
+        }
+        for (name, address) in &manifest.addresses {
+            parse_address_literal(address)
+                .map_err(|e| format_err!("Named address '{}': {}", name, e))?;
+        }
+        Ok(manifest)
+    }
+
+    /// Reads the manifest of the package in `package_dir`.
+    pub fn read(package_dir: &Path) -> Result<Self> {
+        let path = package_dir.join(MANIFEST_FILE_NAME);
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format_err!("Unable to read {}: {}", path.display(), e))?;
+        Self::parse(&contents).map_err(|e| format_err!("Invalid {}: {}", path.display(), e))
+    }
+}
+
+/// Parses a `0x`-prefixed hex address, which `Address::parse_str` expects to be well-formed.
+pub fn parse_address_literal(s: &str) -> Result<Address> {
+    if !s.starts_with("0x") {
+        bail!("'{}' is not a 0x-prefixed hex address", s);
+    }
+    let digits = &s[2..];
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!("'{}' is not a 0x-prefixed hex address", s);
+    }
+    if digits.len() > 2 * ADDRESS_LENGTH {
+        bail!("'{}' is longer than {} bytes", s, ADDRESS_LENGTH);
+    }
+    Address::parse_str(s).map_err(|e| format_err!(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = Manifest::parse(
+            r#"
+            [package]
+            name = "Payments"
+            version = "0.1.0"
+            address = "Payments"
+
+            [addresses]
+            Payments = "0x42"
+
+            [dependencies]
+            Stdlib = { stdlib = true }
+            Utils = { local = "../utils" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(manifest.package.name, "Payments");
+        assert_eq!(manifest.package.address, "Payments");
+        assert_eq!(manifest.addresses["Payments"], "0x42");
+        assert_eq!(
+            manifest.dependencies["Stdlib"],
+            Dependency::Stdlib { stdlib: true }
+        );
+        assert_eq!(
+            manifest.dependencies["Utils"],
+            Dependency::Local {
+                local: "../utils".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_invalid_manifest() {
+        let package = "[package]\nname = \"P\"\naddress = \"0x2\"\n";
+        assert!(Manifest::parse(package).is_ok());
+        assert!(Manifest::parse("[package]\nname = \"P\"\n").is_err());
+        assert!(Manifest::parse(&format!("{}[addresses]\nA = \"42\"\n", package)).is_err());
+        assert!(Manifest::parse(&format!("{}[addresses]\nA = \"0xg\"\n", package)).is_err());
+        assert!(Manifest::parse(&format!(
+            "{}[dependencies]\nStdlib = {{ stdlib = false }}\n",
+            package
+        ))
+        .is_err());
+        assert!(Manifest::parse(&format!(
+            "{}[dependencies]\nA = {{ git = \"a\" }}\n",
+            package
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_address_literal() {
+        assert_eq!(parse_address_literal("0x1").unwrap(), Address::LIBRA_CORE);
+        assert!(parse_address_literal("0x").is_err());
+        assert!(parse_address_literal("1").is_err());
+        assert!(parse_address_literal(&format!("0x{}", "1".repeat(33))).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Substitution of named addresses in Move sources.
+//!
+//! The compiler only knows of address literals, so the named addresses of the package graph are
+//! replaced by their value before compiling. A name is only replaced where an address is
+//! expected:
+//!
+//! - after `address`, e.g. `address Payments { ... }`,
+//! - after `use`, e.g. `use Payments::Account;`,
+//! - in front of a module access, e.g. `Payments::Account::T`.
+//!
+//! Module aliases, like `Account::T`, are left alone, even when a named address has the same name.
+
+use anyhow::{bail, Result};
+use move_ir_types::location::Loc;
+use move_lang::{
+    parser::lexer::{Lexer, Tok},
+    shared::Address,
+    strip_comments_and_verify,
+};
+use std::collections::BTreeMap;
+
+/// Returns `source` with the named addresses replaced by their value. `file` is only used in
+/// error messages.
+pub fn substitute(
+    file: &str,
+    source: &str,
+    addresses: &BTreeMap<String, Address>,
+) -> Result<String> {
+    let fname = "source";
+    let no_comments = match strip_comments_and_verify(fname, source) {
+        Ok((no_comments, _)) => no_comments,
+        Err(errors) => return syntax_error(file, errors.into_iter().flatten().next()),
+    };
+    if no_comments.len() != source.len() {
+        bail!(
+            "{}: nested block comments are not supported in packages",
+            file
+        );
+    }
+
+    // Tokens as (token, start, end), the positions being the same in `source`.
+    let mut lexer = Lexer::new(&no_comments, fname, BTreeMap::new());
+    let mut tokens = vec![];
+    loop {
+        if let Err(error) = lexer.advance() {
+            return syntax_error(file, error.into_iter().next());
+        }
+        if lexer.peek() == Tok::EOF {
+            break;
+        }
+        let start = lexer.start_loc();
+        tokens.push((lexer.peek(), start, start + lexer.content().len()));
+    }
+
+    let text = |index: usize| {
+        tokens
+            .get(index)
+            .map(|(_, start, end)| &source[*start..*end])
+    };
+    let tok = |index: usize| tokens.get(index).map(|(tok, _, _)| *tok);
+    let mut substituted = String::with_capacity(source.len());
+    let mut last_end = 0;
+    for (index, (token, start, end)) in tokens.iter().enumerate() {
+        if *token != Tok::IdentifierValue {
+            continue;
+        }
+        let address = match addresses.get(&source[*start..*end]) {
+            Some(address) => address,
+            None => continue,
+        };
+        let after_address = index > 0
+            && tok(index - 1) == Some(Tok::IdentifierValue)
+            && text(index - 1) == Some("address");
+        let after_use = index > 0 && tok(index - 1) == Some(Tok::Use);
+        let module_access = tok(index + 1) == Some(Tok::ColonColon)
+            && tok(index + 2) == Some(Tok::IdentifierValue)
+            && tok(index + 3) == Some(Tok::ColonColon);
+        if after_address || after_use || module_access {
+            substituted.push_str(&source[last_end..*start]);
+            substituted.push_str(&address.to_string());
+            last_end = *end;
+        }
+    }
+    substituted.push_str(&source[last_end..]);
+    Ok(substituted)
+}
+
+fn syntax_error<T>(file: &str, error: Option<(Loc, String)>) -> Result<T> {
+    match error {
+        Some((_, msg)) => bail!("{}: {}", file, msg),
+        None => bail!("{}: invalid source", file),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::parse_address_literal;
+
+    #[test]
+    fn test_substitute() {
+        let addresses: BTreeMap<_, _> = vec![
+            (
+                "Payments".to_string(),
+                parse_address_literal("0x42").unwrap(),
+            ),
+            (
+                "Account".to_string(),
+                parse_address_literal("0x43").unwrap(),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let source = "address Payments {
+module Pay {
+    // Payments::Account::T is left alone in comments
+    use Account::Account;
+    use 0x1::Signer;
+    fun f(a: &Payments::Account::T): u64 { Account::balance(a) + Payments::Account::fee() }
+}
+}";
+        let expected = "address 0x42 {
+module Pay {
+    // Payments::Account::T is left alone in comments
+    use 0x43::Account;
+    use 0x1::Signer;
+    fun f(a: &0x42::Account::T): u64 { Account::balance(a) + 0x42::Account::fee() }
+}
+}";
+        assert_eq!(
+            substitute("Pay.move", source, &addresses).unwrap(),
+            expected
+        );
+        assert!(substitute("Pay.move", "module M { fun f() { $ } }", &addresses).is_err());
+    }
+}
@@ -0,0 +1,386 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Resolution of the dependency graph of a package.
+//!
+//! Dependencies are loaded depth first from the root package. Named addresses of all the packages
+//! in the graph are merged, so the root package can assign the addresses its dependencies are
+//! published under, and a name bound to two different addresses is an error.
+
+use crate::manifest::{parse_address_literal, Dependency, Manifest, SOURCES_DIR};
+use anyhow::{bail, format_err, Result};
+use move_lang::{find_move_filenames, shared::Address};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PackageSource {
+    /// Directory holding the manifest of the package.
+    Local(PathBuf),
+    /// The bundled standard library.
+    Stdlib,
+}
+
+#[derive(Clone, Debug)]
+pub struct ResolvedPackage {
+    pub name: String,
+    pub source: PackageSource,
+    /// Address modules and scripts of the package are compiled with.
+    pub address: Address,
+    /// Names of the direct dependencies.
+    pub dependencies: Vec<String>,
+}
+
+impl ResolvedPackage {
+    /// Directory holding the Move sources of the package.
+    pub fn sources_dir(&self) -> PathBuf {
+        match &self.source {
+            PackageSource::Local(dir) => dir.join(SOURCES_DIR),
+            PackageSource::Stdlib => stdlib::stdlib_dir(),
+        }
+    }
+
+    /// Move source files of the package, sorted.
+    pub fn source_files(&self) -> Result<Vec<String>> {
+        let mut files = match &self.source {
+            PackageSource::Local(_) => {
+                let sources = self.sources_dir();
+                if !sources.exists() {
+                    return Ok(vec![]);
+                }
+                find_move_filenames(&[sources.to_string_lossy().into_owned()])?
+            }
+            PackageSource::Stdlib => stdlib::stdlib_files(),
+        };
+        files.sort();
+        Ok(files)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ResolvedGraph {
+    /// Named addresses of all the packages.
+    pub addresses: BTreeMap<String, Address>,
+    /// Packages ordered with dependencies before their dependents, the root package last.
+    pub packages: Vec<ResolvedPackage>,
+}
+
+impl ResolvedGraph {
+    /// Resolves the graph of the package in `root_dir`.
+    pub fn resolve(root_dir: &Path) -> Result<Self> {
+        let root_dir = root_dir
+            .canonicalize()
+            .map_err(|e| format_err!("Package at {}: {}", root_dir.display(), e))?;
+        let mut resolver = Resolver::default();
+        resolver.visit(PackageSource::Local(root_dir), None)?;
+        let Resolver {
+            addresses,
+            packages,
+            ..
+        } = resolver;
+        let packages = packages
+            .into_iter()
+            .map(|(pkg, address)| {
+                let address = match address {
+                    Some(address) if address.starts_with("0x") => {
+                        parse_address_literal(&address)
+                            .map_err(|e| format_err!("Package '{}': {}", pkg.name, e))?
+                    }
+                    Some(address) => *addresses.get(&address).ok_or_else(|| {
+                        format_err!(
+                            "Package '{}' is published under unknown named address '{}'",
+                            pkg.name,
+                            address
+                        )
+                    })?,
+                    None => Address::LIBRA_CORE,
+                };
+                Ok(ResolvedPackage { address, ..pkg })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            addresses,
+            packages,
+        })
+    }
+
+    pub fn root(&self) -> &ResolvedPackage {
+        self.packages
+            .last()
+            .expect("the root package is always resolved")
+    }
+
+    pub fn package(&self, name: &str) -> Option<&ResolvedPackage> {
+        self.packages.iter().find(|pkg| pkg.name == name)
+    }
+
+    /// Transitive dependencies of a package, dependencies first.
+    pub fn transitive_dependencies(&self, name: &str) -> Vec<&ResolvedPackage> {
+        let mut names = BTreeSet::new();
+        let mut stack = vec![name];
+        while let Some(name) = stack.pop() {
+            if let Some(pkg) = self.package(name) {
+                for dep in &pkg.dependencies {
+                    if names.insert(dep.as_str()) {
+                        stack.push(dep);
+                    }
+                }
+            }
+        }
+        self.packages
+            .iter()
+            .filter(|pkg| names.contains(pkg.name.as_str()))
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct Resolver {
+    addresses: BTreeMap<String, Address>,
+    /// Resolved packages in build order, with their unresolved address.
+    packages: Vec<(ResolvedPackage, Option<String>)>,
+    /// Packages being visited, to detect cycles.
+    visiting: Vec<String>,
+}
+
+impl Resolver {
+    /// Visits a package and its dependencies, and returns its name.
+    fn visit(&mut self, source: PackageSource, dep_name: Option<&str>) -> Result<String> {
+        let (name, manifest) = match &source {
+            PackageSource::Local(dir) => {
+                let manifest = Manifest::read(dir)?;
+                if let Some(dep_name) = dep_name {
+                    if dep_name != manifest.package.name {
+                        bail!(
+                            "Dependency '{}' in {} is named '{}' in its manifest",
+                            dep_name,
+                            dir.display(),
+                            manifest.package.name
+                        );
+                    }
+                }
+                (manifest.package.name.clone(), Some(manifest))
+            }
+            PackageSource::Stdlib => (
+                dep_name.expect("stdlib is only a dependency").to_string(),
+                None,
+            ),
+        };
+
+        if let Some(pos) = self.visiting.iter().position(|n| n == &name) {
+            let mut cycle = self.visiting[pos..].to_vec();
+            cycle.push(name);
+            bail!("Cyclic dependency: {}", cycle.join(" -> "));
+        }
+        if let Some((pkg, _)) = self.packages.iter().find(|(pkg, _)| pkg.name == name) {
+            if pkg.source != source {
+                bail!("Two different packages are named '{}'", name);
+            }
+            return Ok(name);
+        }
+        if let Some((pkg, _)) = self.packages.iter().find(|(pkg, _)| pkg.source == source) {
+            bail!(
+                "A package is a dependency under both names '{}' and '{}'",
+                pkg.name,
+                name
+            );
+        }
+
+        let manifest = match manifest {
+            Some(manifest) => manifest,
+            None => {
+                self.packages.push((
+                    ResolvedPackage {
+                        name: name.clone(),
+                        source,
+                        address: Address::LIBRA_CORE,
+                        dependencies: vec![],
+                    },
+                    None,
+                ));
+                return Ok(name);
+            }
+        };
+        for (named, literal) in &manifest.addresses {
+            let address = parse_address_literal(literal)?;
+            match self.addresses.insert(named.clone(), address) {
+                Some(previous) if previous != address => bail!(
+                    "Named address '{}' is both {} and {}",
+                    named,
+                    previous,
+                    address
+                ),
+                _ => (),
+            }
+        }
+
+        let dir = match &source {
+            PackageSource::Local(dir) => dir.clone(),
+            PackageSource::Stdlib => unreachable!("stdlib has no manifest"),
+        };
+        self.visiting.push(name.clone());
+        let mut dependencies = vec![];
+        for (dep_name, dep) in &manifest.dependencies {
+            let dep_source = match dep {
+                Dependency::Local { local } => {
+                    let dep_dir = dir.join(local);
+                    PackageSource::Local(dep_dir.canonicalize().map_err(|e| {
+                        format_err!(
+                            "Dependency '{}' of '{}' at {}: {}",
+                            dep_name,
+                            name,
+                            dep_dir.display(),
+                            e
+                        )
+                    })?)
+                }
+                Dependency::Stdlib { .. } => PackageSource::Stdlib,
+            };
+            dependencies.push(self.visit(dep_source, Some(dep_name))?);
+        }
+        self.visiting.pop();
+
+        self.packages.push((
+            ResolvedPackage {
+                name: name.clone(),
+                source,
+                address: Address::default(),
+                dependencies,
+            },
+            Some(manifest.package.address),
+        ));
+        Ok(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libra_temppath::TempPath;
+    use std::fs;
+
+    fn write_package(root: &Path, dir: &str, manifest: &str) {
+        let dir = root.join(dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(crate::manifest::MANIFEST_FILE_NAME), manifest).unwrap();
+    }
+
+    fn names(packages: &[&ResolvedPackage]) -> Vec<String> {
+        packages.iter().map(|pkg| pkg.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let temp = TempPath::new();
+        temp.create_as_dir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        write_package(
+            &root,
+            "app",
+            r#"
+            [package]
+            name = "App"
+            address = "App"
+            [addresses]
+            App = "0x42"
+            Util = "0x43"
+            [dependencies]
+            Util = { local = "../util" }
+            Base = { local = "../base" }
+            "#,
+        );
+        write_package(
+            &root,
+            "util",
+            r#"
+            [package]
+            name = "Util"
+            address = "Util"
+            [dependencies]
+            Base = { local = "../base" }
+            Stdlib = { stdlib = true }
+            "#,
+        );
+        write_package(
+            &root,
+            "base",
+            "[package]\nname = \"Base\"\naddress = \"0x2\"\n",
+        );
+
+        let graph = ResolvedGraph::resolve(&root.join("app")).unwrap();
+        let order: Vec<_> = graph.packages.iter().collect();
+        assert_eq!(names(&order), vec!["Base", "Stdlib", "Util", "App"]);
+        assert_eq!(graph.root().name, "App");
+        assert_eq!(
+            graph.package("Util").unwrap().address,
+            parse_address_literal("0x43").unwrap()
+        );
+        assert_eq!(
+            graph.package("Base").unwrap().address,
+            parse_address_literal("0x2").unwrap()
+        );
+        assert_eq!(
+            graph.package("Stdlib").unwrap().address,
+            Address::LIBRA_CORE
+        );
+        assert_eq!(
+            names(&graph.transitive_dependencies("App")),
+            vec!["Base", "Stdlib", "Util"]
+        );
+        assert_eq!(
+            names(&graph.transitive_dependencies("Base")),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_resolve_errors() {
+        let temp = TempPath::new();
+        temp.create_as_dir().unwrap();
+        let root = temp.path().canonicalize().unwrap();
+        let resolve_err = |dir: &str| {
+            ResolvedGraph::resolve(&root.join(dir))
+                .unwrap_err()
+                .to_string()
+        };
+
+        write_package(
+            &root,
+            "a",
+            "[package]\nname = \"A\"\naddress = \"0x2\"\n[dependencies]\nB = { local = \"../b\" }\n",
+        );
+        write_package(
+            &root,
+            "b",
+            "[package]\nname = \"B\"\naddress = \"0x2\"\n[dependencies]\nA = { local = \"../a\" }\n",
+        );
+        assert_eq!(resolve_err("a"), "Cyclic dependency: A -> B -> A");
+
+        write_package(
+            &root,
+            "c",
+            "[package]\nname = \"C\"\naddress = \"0x2\"\n[addresses]\nX = \"0x3\"\n[dependencies]\nD = { local = \"../d\" }\n",
+        );
+        write_package(
+            &root,
+            "d",
+            "[package]\nname = \"D\"\naddress = \"X\"\n[addresses]\nX = \"0x4\"\n",
+        );
+        assert!(resolve_err("c").starts_with("Named address 'X' is both"));
+
+        write_package(
+            &root,
+            "e",
+            "[package]\nname = \"E\"\naddress = \"0x2\"\n[dependencies]\nF = { local = \"../d\" }\n",
+        );
+        assert!(resolve_err("e").starts_with("Dependency 'F'"));
+
+        write_package(&root, "f", "[package]\nname = \"F\"\naddress = \"Y\"\n");
+        assert_eq!(
+            resolve_err("f"),
+            "Package 'F' is published under unknown named address 'Y'"
+        );
+    }
+}
@@ -0,0 +1,136 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use libra_temppath::TempPath;
+use move_core_types::account_address::AccountAddress;
+use move_package::{
+    build::{build, BuildSummary, BUILD_DIR, BYTECODE_MODULES_DIR},
+    manifest::SOURCES_DIR,
+};
+use std::{fs, path::Path};
+use vm::{access::ModuleAccess, file_format::CompiledModule};
+
+// Copies the packages of `tests/packages` into `dir`, as building writes into the packages.
+fn copy_packages(dir: &Path) {
+    fn copy_dir(from: &Path, to: &Path) {
+        fs::create_dir_all(to).unwrap();
+        for entry in fs::read_dir(from).unwrap() {
+            let path = entry.unwrap().path();
+            let target = to.join(path.file_name().unwrap());
+            if path.is_dir() {
+                copy_dir(&path, &target);
+            } else {
+                fs::copy(&path, &target).unwrap();
+            }
+        }
+    }
+    copy_dir(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/packages"),
+        dir,
+    );
+}
+
+fn module_address(app: &Path, package: &str, module: &str) -> AccountAddress {
+    let path = app
+        .join(BUILD_DIR)
+        .join(package)
+        .join(BYTECODE_MODULES_DIR)
+        .join(format!("{}.mv", module));
+    let module = CompiledModule::deserialize(&fs::read(path).unwrap()).unwrap();
+    *module.self_id().address()
+}
+
+fn summary(compiled: &[&str], up_to_date: &[&str]) -> BuildSummary {
+    BuildSummary {
+        compiled: compiled.iter().map(|name| name.to_string()).collect(),
+        up_to_date: up_to_date.iter().map(|name| name.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_named_addresses() {
+    let temp = TempPath::new();
+    temp.create_as_dir().unwrap();
+    copy_packages(temp.path());
+    let app = temp.path().join("app");
+
+    assert_eq!(build(&app, false).unwrap(), summary(&["Util", "App"], &[]));
+    assert_eq!(
+        module_address(&app, "Util", "Math"),
+        AccountAddress::from_hex_literal("0x43").unwrap()
+    );
+    assert_eq!(
+        module_address(&app, "App", "Wallet"),
+        AccountAddress::from_hex_literal("0x42").unwrap()
+    );
+    let wallet = fs::read_to_string(
+        app.join(BUILD_DIR)
+            .join("App")
+            .join(SOURCES_DIR)
+            .join("Wallet.move"),
+    )
+    .unwrap();
+    assert!(wallet.contains("use 0x43::Math;"), "{}", wallet);
+    assert!(
+        wallet.contains("0x43::Math::double(Math::double(x))"),
+        "{}",
+        wallet
+    );
+
+    assert_eq!(build(&app, false).unwrap(), summary(&[], &["Util", "App"]));
+
+    // Assigning another address to a named address rebuilds the packages using it.
+    let manifest = fs::read_to_string(app.join("Move.toml")).unwrap();
+    fs::write(
+        app.join("Move.toml"),
+        manifest.replace("Util = \"0x43\"", "Util = \"0x44\""),
+    )
+    .unwrap();
+    assert_eq!(build(&app, false).unwrap(), summary(&["Util", "App"], &[]));
+    assert_eq!(
+        module_address(&app, "Util", "Math"),
+        AccountAddress::from_hex_literal("0x44").unwrap()
+    );
+}
+
+#[test]
+fn test_moved_source() {
+    let temp = TempPath::new();
+    temp.create_as_dir().unwrap();
+    copy_packages(temp.path());
+    let app = temp.path().join("app");
+    assert_eq!(build(&app, false).unwrap(), summary(&["Util", "App"], &[]));
+
+    // Moving a source to another directory of the package rebuilds it, though its contents and
+    // file name are unchanged.
+    let sources = app.join(SOURCES_DIR);
+    fs::create_dir_all(sources.join("wallet")).unwrap();
+    fs::rename(
+        sources.join("Wallet.move"),
+        sources.join("wallet").join("Wallet.move"),
+    )
+    .unwrap();
+    assert_eq!(build(&app, false).unwrap(), summary(&["App"], &["Util"]));
+    let build_sources = app.join(BUILD_DIR).join("App").join(SOURCES_DIR);
+    assert!(build_sources.join("wallet").join("Wallet.move").exists());
+    assert!(!build_sources.join("Wallet.move").exists());
+}
+
+#[test]
+fn test_unknown_named_address() {
+    let temp = TempPath::new();
+    temp.create_as_dir().unwrap();
+    copy_packages(temp.path());
+    let app = temp.path().join("app");
+    fs::write(
+        app.join(SOURCES_DIR).join("Other.move"),
+        "address App { module Other { use Unknown::Math; } }",
+    )
+    .unwrap();
+    let err = build(&app, false).unwrap_err().to_string();
+    assert!(
+        err.starts_with("Unable to compile package 'App'"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,9 @@
+address App {
+module Wallet {
+    use Util::Math;
+
+    public fun quadruple(x: u64): u64 {
+        Util::Math::double(Math::double(x))
+    }
+}
+}
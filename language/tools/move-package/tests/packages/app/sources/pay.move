@@ -0,0 +1,8 @@
+script {
+use App::Wallet;
+
+/// Pays four times the amount.
+fun pay(_account: &signer, amount: u64) {
+    Wallet::quadruple(amount);
+}
+}
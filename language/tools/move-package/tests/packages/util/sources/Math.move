@@ -0,0 +1,7 @@
+address Util {
+module Math {
+    public fun double(x: u64): u64 {
+        x * 2
+    }
+}
+}
@@ -0,0 +1,76 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+#![forbid(unsafe_code)]
+
+use move_lang::{command_line as cli, shared::Address};
+use move_unit_test::TestPackage;
+use std::process;
+use structopt::StructOpt;
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "Move Unit Test",
+    about = "Run the #[test] functions of Move modules"
+)]
+struct Options {
+    /// The Move source files holding the modules and their tests, or directories searched for them
+    #[structopt(name = "PATH_TO_SOURCE_FILE")]
+    source_files: Vec<String>,
+
+    /// The library files needed as dependencies
+    #[structopt(
+        name = "PATH_TO_DEPENDENCY_FILE",
+        short = cli::DEPENDENCY_SHORT,
+        long = cli::DEPENDENCY,
+    )]
+    dependencies: Vec<String>,
+
+    /// The sender address for modules outside of an address block
+    #[structopt(
+        name = "ADDRESS",
+        short = cli::SENDER_SHORT,
+        long = cli::SENDER,
+        parse(try_from_str = cli::parse_address)
+    )]
+    sender: Option<Address>,
+
+    /// Only run the tests whose fully qualified name, e.g. 0x2::M::test_f, contains this string
+    #[structopt(long = "filter", short = "f")]
+    filter: Option<String>,
+
+    /// The maximum number of gas units each test can use
+    #[structopt(long = "gas-budget", short = "g", default_value = "4000000")]
+    gas_budget: u64,
+}
+
+fn main() -> anyhow::Result<()> {
+    let Options {
+        source_files,
+        dependencies,
+        sender,
+        filter,
+        gas_budget,
+    } = Options::from_args();
+    let package = TestPackage::compile(&source_files, &dependencies, sender)?;
+    let results = package.run(gas_budget, filter.as_deref())?;
+
+    let mut failed = 0;
+    for result in &results {
+        println!("{}", result);
+        if let Some(failure) = &result.failure {
+            println!("    {}", failure);
+            failed += 1;
+        }
+    }
+    println!(
+        "Test result: {}. {} passed; {} failed",
+        if failed == 0 { "OK" } else { "FAILED" },
+        results.len() - failed,
+        failed
+    );
+    if failed > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
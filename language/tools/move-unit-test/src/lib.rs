@@ -0,0 +1,238 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compiles Move modules along with their `#[test]` functions, and runs each test in a fresh VM
+//! session over an in-memory storage holding the compiled modules.
+
+#![forbid(unsafe_code)]
+
+pub mod storage;
+
+use anyhow::{bail, Result};
+use bytecode_source_map::source_map::SourceMap;
+use move_core_types::{
+    account_address::AccountAddress,
+    gas_schedule::{GasAlgebra, GasUnits},
+    identifier::Identifier,
+    language_storage::ModuleId,
+    vm_status::StatusCode,
+};
+use move_ir_types::location::Loc;
+use move_lang::{
+    compiled_unit::CompiledUnit,
+    errors::{report_errors_to_buffer, FilesSourceText},
+    move_compile_for_testing_no_report,
+    shared::Address,
+    unit_test::{ExpectedFailure, TestCase, TestPlan},
+};
+use move_vm_runtime::move_vm::MoveVM;
+use move_vm_types::{gas_schedule::CostStrategy, values::Value};
+use std::fmt;
+use storage::InMemoryStorage;
+use vm::{
+    access::ModuleAccess,
+    errors::{Location, VMError},
+    file_format::CompiledModule,
+};
+use vm_genesis::GENESIS_COST_SCHEDULE;
+
+/// The Move sources under test, compiled for testing.
+pub struct TestPackage {
+    files: FilesSourceText,
+    modules: Vec<(CompiledModule, SourceMap<Loc>)>,
+    plan: TestPlan,
+    storage: InMemoryStorage,
+}
+
+/// The outcome of a single test.
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    /// The fully qualified name of the test, e.g. `0x2::M::test_f`.
+    pub name: String,
+    pub gas_used: u64,
+    /// Why the test failed, or `None` if it passed.
+    pub failure: Option<String>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+impl fmt::Display for TestResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = if self.passed() { "PASS" } else { "FAIL" };
+        write!(
+            f,
+            "[ {} ] {} (gas used: {})",
+            status, self.name, self.gas_used
+        )
+    }
+}
+
+impl TestPackage {
+    /// Compiles the `targets` and their dependencies for testing. Modules outside of an address
+    /// block are published under `sender_opt`.
+    pub fn compile(
+        targets: &[String],
+        deps: &[String],
+        sender_opt: Option<Address>,
+    ) -> Result<Self> {
+        let (files, units_or_errors) =
+            move_compile_for_testing_no_report(targets, deps, sender_opt)?;
+        let (units, plan) = match units_or_errors {
+            Ok(units_and_plan) => units_and_plan,
+            Err(errors) => bail!(
+                "Unable to compile the tests:\n{}",
+                String::from_utf8_lossy(&report_errors_to_buffer(files, errors))
+            ),
+        };
+        let mut storage = InMemoryStorage::new();
+        let mut modules = vec![];
+        for unit in units {
+            if let CompiledUnit::Module {
+                module, source_map, ..
+            } = unit
+            {
+                storage.add_module(&module);
+                modules.push((module, source_map));
+            }
+        }
+        Ok(Self {
+            files,
+            modules,
+            plan,
+            storage,
+        })
+    }
+
+    /// Runs the tests whose fully qualified name contains `filter`, or all of them, each with at
+    /// most `gas_budget` units of gas.
+    pub fn run(&self, gas_budget: u64, filter: Option<&str>) -> Result<Vec<TestResult>> {
+        let vm = MoveVM::new();
+        let mut results = vec![];
+        for module_plan in &self.plan {
+            let ident = &module_plan.module.0.value;
+            let address = AccountAddress::new(ident.address.to_u8());
+            let module_id = ModuleId::new(address, Identifier::new(ident.name.0.value.as_str())?);
+            for test in &module_plan.tests {
+                let name = format!("{}::{}", module_plan.module, test.test_name);
+                if filter.map_or(false, |filter| !name.contains(filter)) {
+                    continue;
+                }
+                let mut session = vm.new_session(&self.storage);
+                let mut cost_strategy =
+                    CostStrategy::transaction(&GENESIS_COST_SCHEDULE, GasUnits::new(gas_budget));
+                let signers = test
+                    .signers
+                    .iter()
+                    .map(|addr| {
+                        Value::transaction_argument_signer_reference(AccountAddress::new(
+                            addr.to_u8(),
+                        ))
+                    })
+                    .collect();
+                let function_name = Identifier::new(test.test_name.as_str())?;
+                let res = session.execute_function(
+                    &module_id,
+                    &function_name,
+                    vec![],
+                    signers,
+                    address,
+                    &mut cost_strategy,
+                );
+                let gas_used = gas_budget - cost_strategy.remaining_gas().get();
+                results.push(TestResult {
+                    name,
+                    gas_used,
+                    failure: self.check_outcome(test, res.err()),
+                });
+            }
+        }
+        Ok(results)
+    }
+
+    /// Compares the outcome of a test to the expected one, and describes the difference.
+    fn check_outcome(&self, test: &TestCase, error: Option<VMError>) -> Option<String> {
+        let error = match (error, test.expected_failure) {
+            (None, None) => return None,
+            (None, Some(ExpectedFailure::Any)) => {
+                return Some("Expected the test to fail, but it returned".to_string())
+            }
+            (None, Some(ExpectedFailure::AbortCode(code))) => {
+                return Some(format!(
+                    "Expected the test to abort with code {}, but it returned",
+                    code
+                ))
+            }
+            (Some(error), _) => error,
+        };
+        let abort_code = match (error.major_status(), error.sub_status()) {
+            (StatusCode::ABORTED, Some(code)) => Some(code),
+            _ => None,
+        };
+        let failure = match abort_code {
+            Some(code) => format!("aborted with code {}", code),
+            None => format!("failed with {:?}", error.major_status()),
+        };
+        let failure = match self.error_location(&error) {
+            Some(location) => format!("{} in {}", failure, location),
+            None => failure,
+        };
+        match test.expected_failure {
+            None => Some(format!("The test {}", failure)),
+            Some(ExpectedFailure::Any) => None,
+            Some(ExpectedFailure::AbortCode(code)) if abort_code == Some(code) => None,
+            Some(ExpectedFailure::AbortCode(code)) => Some(format!(
+                "Expected the test to abort with code {}, but it {}",
+                code, failure
+            )),
+        }
+    }
+
+    /// The function and the source position at which an execution error happened, e.g.
+    /// `0x2::M::f at sources/M.move:12:9`.
+    fn error_location(&self, error: &VMError) -> Option<String> {
+        let module_id = match error.location() {
+            Location::Module(module_id) => module_id,
+            Location::Script | Location::Undefined => return None,
+        };
+        let (module, source_map) = self
+            .modules
+            .iter()
+            .find(|(module, _)| &module.self_id() == module_id)?;
+        let (function_idx, offset) = match error.offsets().first() {
+            Some(function_offset) => *function_offset,
+            None => return Some(module_name(module_id)),
+        };
+        let handle = module.function_handle_at(module.function_def_at(function_idx).function);
+        let function = format!(
+            "{}::{}",
+            module_name(module_id),
+            module.identifier_at(handle.name)
+        );
+        match source_map.get_code_location(function_idx, offset) {
+            Ok(loc) => Some(format!("{} at {}", function, self.position(loc))),
+            Err(_) => Some(function),
+        }
+    }
+
+    /// Formats a source location as `file:line:column`.
+    fn position(&self, loc: Loc) -> String {
+        let start = loc.span().start().to_usize();
+        let source = match self.files.get(loc.file()) {
+            Some(source) if start <= source.len() => &source[..start],
+            _ => return loc.file().to_string(),
+        };
+        let line = source.matches('\n').count() + 1;
+        let column = start - source.rfind('\n').map_or(0, |n| n + 1) + 1;
+        format!("{}:{}:{}", loc.file(), line, column)
+    }
+}
+
+/// Formats a module name the way Move sources spell it, e.g. `0x2::M`.
+fn module_name(module_id: &ModuleId) -> String {
+    let address = Address::new((*module_id.address()).into());
+    format!("{}::{}", address, module_id.name())
+}
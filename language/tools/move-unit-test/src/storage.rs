@@ -0,0 +1,45 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use move_core_types::{
+    account_address::AccountAddress,
+    language_storage::{ModuleId, TypeTag},
+};
+use move_vm_runtime::data_cache::RemoteCache;
+use std::collections::BTreeMap;
+use vm::{errors::*, CompiledModule};
+
+/// An in-memory [`RemoteCache`] holding the modules under test and their dependencies, and no
+/// resources, such that every test starts from an empty global storage.
+#[derive(Debug, Default)]
+pub struct InMemoryStorage {
+    modules: BTreeMap<ModuleId, Vec<u8>>,
+}
+
+impl InMemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_module(&mut self, module: &CompiledModule) {
+        let mut blob = vec![];
+        module
+            .serialize(&mut blob)
+            .expect("Compiled modules must serialize");
+        self.modules.insert(module.self_id(), blob);
+    }
+}
+
+impl RemoteCache for InMemoryStorage {
+    fn get_module(&self, module_id: &ModuleId) -> VMResult<Option<Vec<u8>>> {
+        Ok(self.modules.get(module_id).cloned())
+    }
+
+    fn get_resource(
+        &self,
+        _address: &AccountAddress,
+        _tag: &TypeTag,
+    ) -> PartialVMResult<Option<Vec<u8>>> {
+        Ok(None)
+    }
+}
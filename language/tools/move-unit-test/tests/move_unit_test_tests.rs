@@ -0,0 +1,117 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use libra_temppath::TempPath;
+use move_unit_test::{TestPackage, TestResult};
+use std::{fs, path::Path, process::Command};
+
+const BAD_SIGNATURE: &str = "address 0x2 {
+module M {
+    #[test]
+    fun test_u64(x: u64) {}
+}
+}
+";
+
+fn manifest_path(path: &str) -> String {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join(path)
+        .to_string_lossy()
+        .to_string()
+}
+
+fn run_counter_tests() -> Vec<TestResult> {
+    let package = TestPackage::compile(
+        &[manifest_path("tests/sources/Counter.move")],
+        &[manifest_path("../../stdlib/modules/Signer.move")],
+        None,
+    )
+    .unwrap();
+    package.run(4_000_000, None).unwrap()
+}
+
+fn failure<'a>(results: &'a [TestResult], name: &str) -> &'a str {
+    results
+        .iter()
+        .find(|result| result.name == format!("0x2::Counter::{}", name))
+        .unwrap()
+        .failure
+        .as_deref()
+        .unwrap()
+}
+
+#[test]
+fn test_results() {
+    let results = run_counter_tests();
+    assert_eq!(results.len(), 7);
+    let passed: Vec<_> = results
+        .iter()
+        .filter(|result| result.passed())
+        .map(|result| result.name.as_str())
+        .collect();
+    assert_eq!(
+        passed,
+        vec![
+            "0x2::Counter::test_increment",
+            "0x2::Counter::test_increment_unpublished",
+            "0x2::Counter::test_value_unchanged",
+            "0x2::Counter::test_fresh_storage",
+        ]
+    );
+    assert!(results.iter().all(|result| result.gas_used > 0));
+}
+
+#[test]
+fn test_failure_messages() {
+    let results = run_counter_tests();
+    let unexpected_abort = failure(&results, "test_unexpected_abort");
+    assert!(
+        unexpected_abort.starts_with(
+            "The test aborted with code 42 in 0x2::Counter::test_unexpected_abort at "
+        ),
+        "{}",
+        unexpected_abort
+    );
+    assert!(
+        unexpected_abort.ends_with("Counter.move:52:9"),
+        "{}",
+        unexpected_abort
+    );
+    assert!(failure(&results, "test_wrong_abort_code")
+        .starts_with("Expected the test to abort with code 1, but it aborted with code 2"));
+    assert_eq!(
+        failure(&results, "test_no_failure"),
+        "Expected the test to fail, but it returned"
+    );
+}
+
+#[test]
+fn test_invalid_test_signature() {
+    let temp = TempPath::new();
+    temp.create_as_dir().unwrap();
+    let file = temp.path().join("M.move");
+    fs::write(&file, BAD_SIGNATURE).unwrap();
+    let error = TestPackage::compile(&[file.to_string_lossy().to_string()], &[], None)
+        .err()
+        .unwrap()
+        .to_string();
+    assert!(
+        error.contains("Test functions can only take '&signer' parameters"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_command_line() {
+    let move_unit_test = |filter: &str| {
+        Command::new(env!("CARGO_BIN_EXE_move-unit-test"))
+            .arg(manifest_path("tests/sources/Counter.move"))
+            .args(&["-d", &manifest_path("../../stdlib/modules/Signer.move")])
+            .args(&["--filter", filter])
+            .status()
+            .unwrap()
+    };
+    assert!(move_unit_test("test_increment").success());
+    assert!(!move_unit_test("Counter").success());
+}
@@ -0,0 +1,65 @@
+address 0x2 {
+module Counter {
+    use 0x1::Signer;
+
+    resource struct Counter { value: u64 }
+
+    public fun publish(account: &signer) {
+        move_to(account, Counter { value: 0 })
+    }
+
+    public fun increment(addr: address) acquires Counter {
+        let counter = borrow_global_mut<Counter>(addr);
+        counter.value = counter.value + 1
+    }
+
+    public fun value(addr: address): u64 acquires Counter {
+        borrow_global<Counter>(addr).value
+    }
+
+    #[test_only]
+    const EWRONG_VALUE: u64 = 7;
+
+    #[test(account = 0x2)]
+    fun test_increment(account: &signer) acquires Counter {
+        publish(account);
+        let addr = Signer::address_of(account);
+        increment(addr);
+        assert(value(addr) == 1, EWRONG_VALUE);
+    }
+
+    #[test]
+    #[expected_failure]
+    fun test_increment_unpublished() acquires Counter {
+        increment(0x3)
+    }
+
+    #[test(account = 0x2)]
+    #[expected_failure(abort_code = 7)]
+    fun test_value_unchanged(account: &signer) acquires Counter {
+        publish(account);
+        assert(value(Signer::address_of(account)) == 1, EWRONG_VALUE);
+    }
+
+    #[test(account = 0x2)]
+    fun test_fresh_storage(account: &signer) {
+        // Each test starts from an empty storage, so publishing again succeeds.
+        publish(account);
+    }
+
+    #[test]
+    fun test_unexpected_abort() {
+        abort 42
+    }
+
+    #[test]
+    #[expected_failure(abort_code = 1)]
+    fun test_wrong_abort_code() {
+        abort 2
+    }
+
+    #[test]
+    #[expected_failure]
+    fun test_no_failure() {}
+}
+}
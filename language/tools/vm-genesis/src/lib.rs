@@ -30,7 +30,7 @@ use libra_types::{
 use libra_vm::{data_cache::StateViewCache, txn_effects_to_writeset_and_events};
 use move_core_types::{
     account_address::AccountAddress,
-    gas_schedule::{CostTable, GasAlgebra, GasUnits},
+    gas_schedule::{CostTable, GasAlgebra, GasConstants, GasUnits},
     identifier::Identifier,
     language_storage::{ModuleId, StructTag, TypeTag},
 };
@@ -59,6 +59,15 @@ pub static GENESIS_KEYPAIR: Lazy<(Ed25519PrivateKey, Ed25519PublicKey)> = Lazy::
 
 pub static ZERO_COST_SCHEDULE: Lazy<CostTable> = Lazy::new(zero_cost_schedule);
 
+/// The gas schedule published at genesis, for metering execution outside of a chain.
+pub static GENESIS_COST_SCHEDULE: Lazy<CostTable> = Lazy::new(|| CostTable {
+    instruction_table: lcs::from_bytes(&INITIAL_GAS_SCHEDULE.0)
+        .expect("Unable to deserialize genesis gas schedule for instructions"),
+    native_table: lcs::from_bytes(&INITIAL_GAS_SCHEDULE.1)
+        .expect("Unable to deserialize genesis gas schedule for natives"),
+    gas_constants: GasConstants::default(),
+});
+
 pub type OperatorAssignment = (Ed25519PublicKey, Script); // Assigns an operator to each owner
 pub type OperatorRegistration = (Ed25519PublicKey, Script); // Registers a validator config
 
@@ -157,16 +166,7 @@ pub fn encode_genesis_change_set(
 
 /// Convert the transaction arguments into move values.
 fn convert_txn_args(args: &[TransactionArgument]) -> Vec<Value> {
-    args.iter()
-        .map(|arg| match arg {
-            TransactionArgument::U8(i) => Value::u8(*i),
-            TransactionArgument::U64(i) => Value::u64(*i),
-            TransactionArgument::U128(i) => Value::u128(*i),
-            TransactionArgument::Address(a) => Value::address(*a),
-            TransactionArgument::Bool(b) => Value::bool(*b),
-            TransactionArgument::U8Vector(v) => Value::vector_u8(v.clone()),
-        })
-        .collect()
+    args.iter().map(Value::from_transaction_argument).collect()
 }
 
 fn exec_function(
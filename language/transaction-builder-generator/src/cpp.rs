@@ -188,7 +188,10 @@ fn quote_type(type_tag: &TypeTag) -> String {
         Address => "AccountAddress".into(),
         Vector(type_tag) => match type_tag.as_ref() {
             U8 => "std::vector<uint8_t>".into(),
-            _ => type_not_allowed(type_tag),
+            Bool | U64 | U128 | Address | Vector(_) => {
+                format!("std::vector<{}>", quote_type(type_tag))
+            }
+            Struct(_) | Signer => type_not_allowed(type_tag),
         },
 
         Struct(_) | Signer => type_not_allowed(type_tag),
@@ -206,7 +209,28 @@ fn make_transaction_argument(type_tag: &TypeTag, name: &str) -> String {
         Address => format!("{{TransactionArgument::Address {{std::move({})}}}}", name),
         Vector(type_tag) => match type_tag.as_ref() {
             U8 => format!("{{TransactionArgument::U8Vector {{std::move({})}}}}", name),
-            _ => type_not_allowed(type_tag),
+            Bool => format!(
+                "{{TransactionArgument::BoolVector {{std::move({})}}}}",
+                name
+            ),
+            U64 => format!("{{TransactionArgument::U64Vector {{std::move({})}}}}", name),
+            U128 => format!(
+                "{{TransactionArgument::U128Vector {{std::move({})}}}}",
+                name
+            ),
+            Address => format!(
+                "{{TransactionArgument::AddressVector {{std::move({})}}}}",
+                name
+            ),
+            // The elements are converted by a lambda. The variables of nested lambdas shadow
+            // each other.
+            Vector(_) => format!(
+                "{{TransactionArgument::Vector {{[&] {{ std::vector<TransactionArgument> v; \
+                 for (auto& x : {}) {{ v.push_back({}); }} return v; }}()}}}}",
+                name,
+                make_transaction_argument(type_tag, "x")
+            ),
+            Struct(_) | Signer => type_not_allowed(type_tag),
         },
 
         Struct(_) | Signer => type_not_allowed(type_tag),
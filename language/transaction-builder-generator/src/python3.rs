@@ -55,7 +55,7 @@ fn output_preamble(
         out,
         r#"import typing
 {}import serde_types as st
-{}import Script, TypeTag, AccountAddress, TransactionArgument__Bool, TransactionArgument__U8, TransactionArgument__U64, TransactionArgument__U128, TransactionArgument__Address, TransactionArgument__U8Vector
+{}import Script, TypeTag, AccountAddress, TransactionArgument__Bool, TransactionArgument__U8, TransactionArgument__U64, TransactionArgument__U128, TransactionArgument__Address, TransactionArgument__U8Vector, TransactionArgument__U64Vector, TransactionArgument__U128Vector, TransactionArgument__BoolVector, TransactionArgument__AddressVector, TransactionArgument__Vector
 "#,
         quote_from_package(serde_package_name),
         quote_from_package_and_module(libra_package_name, "libra_types"),
@@ -153,7 +153,10 @@ fn quote_type(type_tag: &TypeTag) -> String {
         Address => "AccountAddress".into(),
         Vector(type_tag) => match type_tag.as_ref() {
             U8 => "bytes".into(),
-            _ => type_not_allowed(type_tag),
+            Bool | U64 | U128 | Address | Vector(_) => {
+                format!("typing.Sequence[{}]", quote_type(type_tag))
+            }
+            Struct(_) | Signer => type_not_allowed(type_tag),
         },
 
         Struct(_) | Signer => type_not_allowed(type_tag),
@@ -170,7 +173,17 @@ fn make_transaction_argument(type_tag: &TypeTag, name: &str) -> String {
         Address => format!("TransactionArgument__Address({})", name),
         Vector(type_tag) => match type_tag.as_ref() {
             U8 => format!("TransactionArgument__U8Vector({})", name),
-            _ => type_not_allowed(type_tag),
+            Bool => format!("TransactionArgument__BoolVector({})", name),
+            U64 => format!("TransactionArgument__U64Vector({})", name),
+            U128 => format!("TransactionArgument__U128Vector({})", name),
+            Address => format!("TransactionArgument__AddressVector({})", name),
+            // The variables of nested comprehensions shadow each other.
+            Vector(_) => format!(
+                "TransactionArgument__Vector([{} for x in {}])",
+                make_transaction_argument(type_tag, "x"),
+                name
+            ),
+            Struct(_) | Signer => type_not_allowed(type_tag),
         },
 
         Struct(_) | Signer => type_not_allowed(type_tag),
@@ -140,7 +140,8 @@ fn quote_type(type_tag: &TypeTag) -> String {
         Address => "AccountAddress".into(),
         Vector(type_tag) => match type_tag.as_ref() {
             U8 => "Vec<u8>".into(),
-            _ => type_not_allowed(type_tag),
+            Bool | U64 | U128 | Address | Vector(_) => format!("Vec<{}>", quote_type(type_tag)),
+            Struct(_) | Signer => type_not_allowed(type_tag),
         },
 
         Struct(_) | Signer => type_not_allowed(type_tag),
@@ -163,7 +164,17 @@ fn make_transaction_argument(type_tag: &TypeTag, name: &str, local_types: bool)
                     format!("TransactionArgument::U8Vector(ByteBuf::from({}))", name)
                 }
             }
-            _ => type_not_allowed(type_tag),
+            Bool => format!("TransactionArgument::BoolVector({})", name),
+            U64 => format!("TransactionArgument::U64Vector({})", name),
+            U128 => format!("TransactionArgument::U128Vector({})", name),
+            Address => format!("TransactionArgument::AddressVector({})", name),
+            // The closure parameters of nested vectors shadow each other.
+            Vector(_) => format!(
+                "TransactionArgument::Vector({}.into_iter().map(|x| {}).collect())",
+                name,
+                make_transaction_argument(type_tag, "x", local_types)
+            ),
+            Struct(_) | Signer => type_not_allowed(type_tag),
         },
 
         Struct(_) | Signer => type_not_allowed(type_tag),
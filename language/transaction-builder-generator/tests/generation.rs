@@ -1,11 +1,19 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use libra_types::transaction::ScriptABI;
+use libra_types::{
+    account_address::AccountAddress,
+    transaction::{ArgumentABI, Script, ScriptABI, TransactionArgument},
+};
+use move_core_types::language_storage::TypeTag;
 use serde_generate as serdegen;
 use serde_generate::SourceInstaller as _;
 use serde_reflection::Registry;
-use std::{io::Write, process::Command};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
 use tempfile::tempdir;
 use transaction_builder_generator as buildgen;
 use transaction_builder_generator::SourceInstaller as _;
@@ -21,32 +29,77 @@ fn get_stdlib_script_abis() -> Vec<ScriptABI> {
     buildgen::read_abis(path).expect("reading ABI files should not fail")
 }
 
-// Cannot run this test in the CI of Libra.
-#[test]
-#[ignore]
-fn test_that_python_code_parses_and_passes_pyre_check() {
-    let registry = get_libra_registry();
-    let abis = get_stdlib_script_abis();
-    let dir = tempdir().unwrap();
+/// A script with the vector arguments that no stdlib script takes yet.
+fn get_vector_script_abis() -> Vec<ScriptABI> {
+    use TypeTag::*;
+    vec![ScriptABI::new(
+        "vector_arguments".to_string(),
+        "Takes a `vector<address>` and a `vector<vector<u8>>`.".to_string(),
+        vec![1, 2, 3],
+        vec![],
+        vec![
+            ArgumentABI::new("addresses".to_string(), Vector(Box::new(Address))),
+            ArgumentABI::new(
+                "payloads".to_string(),
+                Vector(Box::new(Vector(Box::new(U8)))),
+            ),
+        ],
+    )]
+}
 
-    let src_dir_path = dir.path().join("src");
+/// The LCS encoding of the script that the programs below build with the vector arguments
+/// builder: addresses `0x11..11` and `0x22..22`, and payloads `[1, 2]` and `[]`.
+fn get_vector_script_bytes() -> Vec<u8> {
+    let script = Script::new(
+        vec![1, 2, 3],
+        vec![],
+        vec![
+            TransactionArgument::AddressVector(vec![
+                AccountAddress::new([0x11; AccountAddress::LENGTH]),
+                AccountAddress::new([0x22; AccountAddress::LENGTH]),
+            ]),
+            TransactionArgument::Vector(vec![
+                TransactionArgument::U8Vector(vec![1, 2]),
+                TransactionArgument::U8Vector(vec![]),
+            ]),
+        ],
+    );
+    lcs::to_bytes(&script).unwrap()
+}
+
+/// Installs the Python modules `serde_types`, `lcs`, `libra_types` and `libra_builders` in
+/// `src_dir_path`, returning the `PYTHONPATH` to use them.
+fn install_python_builders(src_dir_path: &Path, abis: &[ScriptABI]) -> String {
+    let registry = get_libra_registry();
     let installer =
-        serdegen::python3::Installer::new(src_dir_path.clone(), /* package */ None);
+        serdegen::python3::Installer::new(src_dir_path.to_path_buf(), /* package */ None);
     installer.install_module("libra_types", &registry).unwrap();
     installer.install_serde_runtime().unwrap();
+    installer.install_lcs_runtime().unwrap();
 
     let builder_dir_path = src_dir_path.join("libra_builders");
     std::fs::create_dir_all(builder_dir_path.clone()).unwrap();
     let source_path = builder_dir_path.join("__init__.py");
 
     let mut source = std::fs::File::create(&source_path).unwrap();
-    buildgen::python3::output(&mut source, &abis).unwrap();
+    buildgen::python3::output(&mut source, abis).unwrap();
 
-    let python_path = format!(
+    format!(
         "{}:{}",
         std::env::var("PYTHONPATH").unwrap_or_default(),
         src_dir_path.to_string_lossy(),
-    );
+    )
+}
+
+// Cannot run this test in the CI of Libra.
+#[test]
+#[ignore]
+fn test_that_python_code_parses_and_passes_pyre_check() {
+    let abis = get_stdlib_script_abis();
+    let dir = tempdir().unwrap();
+
+    let src_dir_path = dir.path().join("src");
+    let python_path = install_python_builders(&src_dir_path, &abis);
     let status = Command::new("python3")
         .arg("-c")
         .arg("import serde_types; import libra_types; import libra_builders")
@@ -89,15 +142,45 @@ fn test_that_python_code_parses_and_passes_pyre_check() {
 }
 
 #[test]
-fn test_that_rust_code_compiles() {
-    let registry = get_libra_registry();
-    let abis = get_stdlib_script_abis();
+#[ignore]
+fn test_that_python_code_with_vector_arguments_runs() {
     let dir = tempdir().unwrap();
 
-    let installer = serdegen::rust::Installer::new(dir.path().to_path_buf());
+    let src_dir_path = dir.path().join("src");
+    let python_path = install_python_builders(&src_dir_path, &get_vector_script_abis());
+    let output = Command::new("python3")
+        .arg("-c")
+        .arg(
+            r#"
+import sys
+import lcs
+import serde_types as st
+from libra_types import AccountAddress, Script
+from libra_builders import encode_vector_arguments_script
+
+def address(byte):
+    return AccountAddress(value=tuple(st.uint8(byte) for _ in range(16)))
+
+script = encode_vector_arguments_script([address(0x11), address(0x22)], [b"\x01\x02", b""])
+sys.stdout.buffer.write(lcs.serialize(script, Script))
+"#,
+        )
+        .env("PYTHONPATH", python_path)
+        .stderr(Stdio::inherit())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, get_vector_script_bytes());
+}
+
+/// Installs the crates `libra-types` and `libra-builders` in `dir`, with the given `main.rs`
+/// for `libra-builders` if any, and returns the directory of `libra-builders`.
+fn install_rust_builders(dir: &Path, abis: &[ScriptABI], main: Option<&str>) -> PathBuf {
+    let registry = get_libra_registry();
+    let installer = serdegen::rust::Installer::new(dir.to_path_buf());
     installer.install_module("libra-types", &registry).unwrap();
 
-    let builder_dir_path = dir.path().join("libra-builders");
+    let builder_dir_path = dir.join("libra-builders");
     std::fs::create_dir_all(builder_dir_path.clone()).unwrap();
 
     let mut cargo = std::fs::File::create(&builder_dir_path.join("Cargo.toml")).unwrap();
@@ -117,12 +200,37 @@ serde_bytes = "0.11"
     std::fs::create_dir(builder_dir_path.join("src")).unwrap();
     let source_path = builder_dir_path.join("src/lib.rs");
     let mut source = std::fs::File::create(&source_path).unwrap();
-    buildgen::rust::output(&mut source, &abis, /* local types */ false).unwrap();
+    buildgen::rust::output(&mut source, abis, /* local types */ false).unwrap();
+
+    if let Some(main) = main {
+        // The serde-generated types are serialized with the LCS implementation of Libra.
+        let lcs_path = std::env::current_dir()
+            .unwrap()
+            .join("../../common/lcs")
+            .canonicalize()
+            .unwrap();
+        writeln!(
+            cargo,
+            r#"lcs = {{ path = "{}", package = "libra-canonical-serialization" }}"#,
+            lcs_path.display(),
+        )
+        .unwrap();
+        std::fs::write(builder_dir_path.join("src/main.rs"), main).unwrap();
+    }
+    builder_dir_path
+}
+
+#[test]
+fn test_that_rust_code_compiles() {
+    let abis = get_stdlib_script_abis();
+    let dir = tempdir().unwrap();
+
+    let builder_dir_path = install_rust_builders(dir.path(), &abis, None);
 
     // Use a stable `target` dir to avoid downloading and recompiling crates everytime.
     let target_dir = std::env::current_dir().unwrap().join("../../target");
     let status = Command::new("cargo")
-        .current_dir(dir.path().join("libra-builders"))
+        .current_dir(builder_dir_path)
         .arg("build")
         .arg("--target-dir")
         .arg(target_dir)
@@ -132,22 +240,71 @@ serde_bytes = "0.11"
 }
 
 #[test]
-#[ignore]
-fn test_that_cpp_code_compiles() {
-    let registry = get_libra_registry();
-    let abis = get_stdlib_script_abis();
+fn test_that_rust_code_with_vector_arguments_runs() {
     let dir = tempdir().unwrap();
 
-    let lcs_installer = serdegen::cpp::Installer::new(dir.path().to_path_buf());
+    let builder_dir_path = install_rust_builders(
+        dir.path(),
+        &get_vector_script_abis(),
+        Some(
+            r#"
+use libra_builders::encode_vector_arguments_script;
+use libra_types::AccountAddress;
+use std::io::Write;
+
+fn main() {
+    // The fields of serde-generated newtypes are private.
+    let address = |byte| lcs::from_bytes::<AccountAddress>(&[byte; 16]).unwrap();
+    let script = encode_vector_arguments_script(
+        vec![address(0x11), address(0x22)],
+        vec![vec![1, 2], vec![]],
+    );
+    let bytes = lcs::to_bytes(&script).unwrap();
+    std::io::stdout().write_all(&bytes).unwrap();
+}
+"#,
+        ),
+    );
+
+    // Use a stable `target` dir to avoid downloading and recompiling crates everytime.
+    let target_dir = std::env::current_dir().unwrap().join("../../target");
+    let output = Command::new("cargo")
+        .current_dir(builder_dir_path)
+        .arg("run")
+        .arg("--quiet")
+        .arg("--target-dir")
+        .arg(target_dir)
+        .stderr(Stdio::inherit())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, get_vector_script_bytes());
+}
+
+/// Installs the C++ files `serde.hpp`, `lcs.hpp`, `libra_types.hpp`, `libra_builder.hpp` and
+/// `libra_builder.cpp` in `dir`.
+fn install_cpp_builders(dir: &Path, abis: &[ScriptABI]) {
+    let registry = get_libra_registry();
+    let lcs_installer = serdegen::cpp::Installer::new(dir.to_path_buf());
     lcs_installer
         .install_module("libra_types", &registry)
         .unwrap();
     lcs_installer.install_serde_runtime().unwrap();
+    lcs_installer.install_lcs_runtime().unwrap();
 
-    let abi_installer = buildgen::cpp::Installer::new(dir.path().to_path_buf());
+    let abi_installer = buildgen::cpp::Installer::new(dir.to_path_buf());
     abi_installer
-        .install_transaction_builders("libra_builder", &abis)
+        .install_transaction_builders("libra_builder", abis)
         .unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_that_cpp_code_compiles() {
+    let abis = get_stdlib_script_abis();
+    let dir = tempdir().unwrap();
+
+    install_cpp_builders(dir.path(), &abis);
 
     let status = Command::new("clang++")
         .arg("--std=c++17")
@@ -158,3 +315,62 @@ fn test_that_cpp_code_compiles() {
         .unwrap();
     assert!(status.success());
 }
+
+#[test]
+#[ignore]
+fn test_that_cpp_code_with_vector_arguments_runs() {
+    let dir = tempdir().unwrap();
+
+    install_cpp_builders(dir.path(), &get_vector_script_abis());
+
+    let main_path = dir.path().join("main.cpp");
+    std::fs::write(
+        &main_path,
+        r#"
+#include <iostream>
+// Used but not included by `lcs.hpp`.
+#include <limits>
+#include "lcs.hpp"
+#include "libra_builder.hpp"
+
+using namespace libra_types;
+
+AccountAddress address(uint8_t byte) {
+    std::array<uint8_t, 16> value;
+    value.fill(byte);
+    return AccountAddress {value};
+}
+
+int main() {
+    auto script = libra_builder::encode_vector_arguments_script(
+        {address(0x11), address(0x22)},
+        {{1, 2}, {}});
+    auto serializer = serde::LcsSerializer();
+    serde::Serializable<Script>::serialize(script, serializer);
+    auto bytes = std::move(serializer).bytes();
+    std::cout.write((const char *)bytes.data(), bytes.size());
+    return 0;
+}
+"#,
+    )
+    .unwrap();
+
+    let exe_path = dir.path().join("main");
+    let status = Command::new("clang++")
+        .arg("--std=c++17")
+        .arg("-g")
+        .arg("-o")
+        .arg(&exe_path)
+        .arg(&main_path)
+        .arg(dir.path().join("libra_builder.cpp"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = Command::new(exe_path)
+        .stderr(Stdio::inherit())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, get_vector_script_bytes());
+}
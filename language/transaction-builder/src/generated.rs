@@ -872,6 +872,22 @@ pub fn encode_update_account_limit_window_info_script(
     )
 }
 
+/// Update the signature scheme of consensus quorum certificates to the LCS-encoded
+/// `scheme`.
+pub fn encode_update_consensus_signature_scheme_script(scheme: Vec<u8>) -> Script {
+    Script::new(
+        vec![
+            161, 28, 235, 11, 1, 0, 0, 0, 5, 1, 0, 2, 3, 2, 5, 5, 7, 6, 7, 13, 34, 8, 47, 16, 0, 0,
+            0, 1, 0, 1, 0, 2, 6, 12, 10, 2, 0, 29, 76, 105, 98, 114, 97, 67, 111, 110, 115, 101,
+            110, 115, 117, 115, 83, 105, 103, 110, 97, 116, 117, 114, 101, 83, 99, 104, 101, 109,
+            101, 3, 115, 101, 116, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 1, 4, 11,
+            0, 11, 1, 17, 0, 2,
+        ],
+        vec![],
+        vec![TransactionArgument::U8Vector(scheme)],
+    )
+}
+
 /// Update the dual attesation limit to `new_micro_lbr_limit`.
 pub fn encode_update_dual_attestation_limit_script(
     sliding_nonce: u64,
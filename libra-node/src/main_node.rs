@@ -3,7 +3,7 @@
 
 use backup_service::start_backup_service;
 use consensus::{consensus_provider::start_consensus, gen_consensus_reconfig_subscription};
-use debug_interface::node_debug_service::NodeDebugService;
+use debug_interface::node_debug_service::{DebugStateProvider, NodeDebugService};
 use executor::{db_bootstrapper::bootstrap_db_if_empty, Executor};
 use executor_types::ChunkExecutor;
 use futures::{channel::mpsc::channel, executor::block_on};
@@ -17,9 +17,11 @@ use libra_mempool::gen_mempool_reconfig_subscription;
 use libra_metrics::metric_server;
 use libra_vm::LibraVM;
 use libradb::LibraDB;
+use network::peer_manager::{ConnectionSnapshot, NetworkSnapshot, NetworkStatus};
 use network_builder::builder::NetworkBuilder;
+use serde_json::json;
 use state_synchronizer::StateSynchronizer;
-use std::{boxed::Box, net::ToSocketAddrs, sync::Arc, thread, time::Instant};
+use std::{boxed::Box, collections::HashMap, net::ToSocketAddrs, sync::Arc, thread, time::Instant};
 use storage_interface::DbReaderWriter;
 use storage_service::start_storage_service_with_db;
 use tokio::runtime::Runtime;
@@ -37,11 +39,67 @@ pub struct LibraHandle {
     _backup: Runtime,
 }
 
+/// Serves the connections of the networks of the node on the debug interface. Peer details are
+/// only served there, the `get_network_status` JSON-RPC method only returns the number of peers.
+struct NetworkStatusProvider(Vec<NetworkStatus>);
+
+impl DebugStateProvider for NetworkStatusProvider {
+    fn snapshot(&self) -> anyhow::Result<serde_json::Value> {
+        let networks: Vec<_> = self
+            .0
+            .iter()
+            .map(|network| network_json(network.snapshot()))
+            .collect();
+        Ok(json!({ "networks": networks }))
+    }
+}
+
+fn network_json(snapshot: NetworkSnapshot) -> serde_json::Value {
+    let connections: Vec<_> = snapshot
+        .connections
+        .into_iter()
+        .map(connection_json)
+        .collect();
+    json!({
+        "network_id": snapshot.network_context.network_id().to_string(),
+        "role": snapshot.network_context.role().as_str(),
+        "peer_id": snapshot.network_context.peer_id().to_string(),
+        "listen_address": snapshot.listen_address.map(|addr| addr.to_string()),
+        "connections": connections,
+    })
+}
+
+fn connection_json(conn: ConnectionSnapshot) -> serde_json::Value {
+    let application_protocols: Vec<_> = conn
+        .application_protocols
+        .iter()
+        .map(|protocol| protocol.to_string())
+        .collect();
+    json!({
+        "peer_id": conn.metadata.peer_id().to_string(),
+        "address": conn.metadata.addr().to_string(),
+        "origin": format!("{:?}", conn.metadata.origin()),
+        "messaging_protocol": format!("{:?}", conn.metadata.messaging_protocol()),
+        "application_protocols": application_protocols,
+        "connected_secs": conn.connected_for.as_secs(),
+        "bytes_sent": conn.bytes_sent,
+        "bytes_received": conn.bytes_received,
+        "messages_sent": conn.messages_sent,
+        "messages_received": conn.messages_received,
+        "pending_messages": conn.pending_messages,
+        "latency_mean_us": conn.latency.map(|stats| stats.mean.as_micros() as u64),
+        "latency_jitter_us": conn.latency.map(|stats| stats.jitter.as_micros() as u64),
+    })
+}
+
 fn setup_chunk_executor(db: DbReaderWriter) -> Box<dyn ChunkExecutor> {
     Box::new(Executor::<LibraVM>::new(db))
 }
 
-fn setup_debug_interface(config: &NodeConfig) -> NodeDebugService {
+fn setup_debug_interface(
+    config: &NodeConfig,
+    providers: HashMap<&'static str, Arc<dyn DebugStateProvider>>,
+) -> NodeDebugService {
     let addr = format!(
         "{}:{}",
         config.debug_interface.address, config.debug_interface.admission_control_node_debug_port,
@@ -54,7 +112,7 @@ fn setup_debug_interface(config: &NodeConfig) -> NodeDebugService {
     libra_trace::set_libra_trace(&config.debug_interface.libra_trace.sampling)
         .expect("Failed to set libra trace sampling rate.");
 
-    NodeDebugService::new(addr)
+    NodeDebugService::new_with_providers(addr, providers)
 }
 
 pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
@@ -96,6 +154,7 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
         instant.elapsed().as_millis()
     );
     let mut network_runtimes = vec![];
+    let mut network_statuses = vec![];
     let mut state_sync_network_handles = vec![];
     let mut mempool_network_handles = vec![];
     let mut consensus_network_handles = None;
@@ -165,6 +224,7 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
         // Start the network and cache the runtime so it does not go out of scope.
         // TODO:  move all 'start' commands to a second phase at the end of setup_environment.  Target is to have one pass to wire the pieces together and a second pass to start processing in an appropriate order.
         let peer_id = network_builder.peer_id();
+        network_statuses.push(network_builder.network_status());
         let _listen_addr = network_builder.build();
         network_runtimes.push(runtime);
         debug!("Network started for peer_id: {}", peer_id);
@@ -190,6 +250,8 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
     let rpc_runtime = bootstrap_rpc(&node_config, libra_db.clone(), mp_client_sender);
 
     let mut consensus_runtime = None;
+    let mut debug_providers: HashMap<&'static str, Arc<dyn DebugStateProvider>> = HashMap::new();
+    debug_providers.insert("network", Arc::new(NetworkStatusProvider(network_statuses)));
     let (consensus_to_mempool_sender, consensus_requests) = channel(INTRA_NODE_CHANNEL_BUFFER_SIZE);
 
     instant = Instant::now();
@@ -219,7 +281,7 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
 
         // Initialize and start consensus.
         instant = Instant::now();
-        consensus_runtime = Some(start_consensus(
+        let (runtime, evidence_provider) = start_consensus(
             node_config,
             consensus_network_sender,
             consensus_network_events,
@@ -227,11 +289,13 @@ pub fn setup_environment(node_config: &mut NodeConfig) -> LibraHandle {
             consensus_to_mempool_sender,
             libra_db,
             consensus_reconfig_events,
-        ));
+        );
+        consensus_runtime = Some(runtime);
+        debug_providers.insert("consensus_evidence", evidence_provider);
         debug!("Consensus started in {} ms", instant.elapsed().as_millis());
     }
 
-    let debug_if = setup_debug_interface(&node_config);
+    let debug_if = setup_debug_interface(&node_config, debug_providers);
 
     let metrics_port = node_config.debug_interface.metrics_server_port;
     let metric_host = node_config.debug_interface.address.clone();
@@ -10,7 +10,9 @@ use libra_types::{transaction::SignedTransaction, PeerId};
 use network::{
     error::NetworkError,
     peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
-    protocols::network::{NetworkEvents, NetworkSender, NewNetworkSender},
+    protocols::network::{
+        NetworkEvents, NetworkSender, NewNetworkSender, PeerBehavior, PeerLatencies, PeerScores,
+    },
     ProtocolId,
 };
 use serde::{Deserialize, Serialize};
@@ -103,4 +105,23 @@ impl MempoolNetworkSender {
         let protocol = ProtocolId::MempoolDirectSend;
         self.inner.send_to(recipient, protocol, message)
     }
+
+    /// Report the outcome of processing a message from `peer` to the network layer.
+    pub fn report_peer(
+        &mut self,
+        peer: PeerId,
+        behavior: PeerBehavior,
+    ) -> Result<(), NetworkError> {
+        self.inner.report_peer(peer, behavior)
+    }
+
+    /// Latency statistics of the peers, measured by the health checker.
+    pub fn peer_latencies(&self) -> &PeerLatencies {
+        self.inner.peer_latencies()
+    }
+
+    /// Scores of the peers, from the behaviors reported by all the applications of the network.
+    pub fn peer_scores(&self) -> &PeerScores {
+        self.inner.peer_scores()
+    }
 }
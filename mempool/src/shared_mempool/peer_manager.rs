@@ -6,6 +6,7 @@ use libra_config::{
     config::{PeerNetworkId, UpstreamConfig},
     network_id::NetworkId,
 };
+use network::protocols::network::{PeerLatencies, PeerScores};
 use rand::seq::SliceRandom;
 use std::{
     collections::{BTreeSet, HashMap, HashSet},
@@ -32,6 +33,10 @@ pub(crate) struct PeerManager {
     // the upstream peer to failover to if all peers in the primary upstream network are dead
     // the number of failover peers is limited to 1 to avoid network competition in the failover networks
     failover_peer: Mutex<Option<PeerNetworkId>>,
+    // latencies of the peers of each network, measured by the health checker
+    peer_latencies: HashMap<NetworkId, PeerLatencies>,
+    // scores of the peers of each network, from the behaviors reported by all the applications
+    peer_scores: HashMap<NetworkId, PeerScores>,
 }
 
 #[derive(Clone)]
@@ -55,11 +60,17 @@ impl BroadcastInfo {
 }
 
 impl PeerManager {
-    pub fn new(upstream_config: UpstreamConfig) -> Self {
+    pub fn new(
+        upstream_config: UpstreamConfig,
+        peer_latencies: HashMap<NetworkId, PeerLatencies>,
+        peer_scores: HashMap<NetworkId, PeerScores>,
+    ) -> Self {
         Self {
             upstream_config,
             peer_info: Mutex::new(PeerInfo::new()),
             failover_peer: Mutex::new(None),
+            peer_latencies,
+            peer_scores,
         }
     }
 
@@ -151,7 +162,7 @@ impl PeerManager {
             // with any live peer and pick a peer from that network
             for failover_network in self.upstream_config.networks[1..].iter() {
                 if let Some(active_peers) = active_peers_by_network.get(failover_network) {
-                    failover_candidate = active_peers.choose(&mut rand::thread_rng());
+                    failover_candidate = self.fastest_peer(failover_network, active_peers);
                     if failover_candidate.is_some() {
                         break;
                     }
@@ -162,9 +173,11 @@ impl PeerManager {
                 if let Some(candidate) = &failover_candidate {
                     if chosen.network_id() == candidate.network_id()
                         && peer_info.get(chosen).expect("missing peer state").is_alive
+                        && (!self.is_suspect(chosen) || self.is_suspect(candidate))
                     {
                         // if current chosen failover peer is alive, then do not overwrite it
-                        // with another live peer of the same network
+                        // with another live peer of the same network, unless it's a suspect
+                        // peer and the other isn't
                         // for mempool broadcasts, broadcasting to the same peer consistently makes
                         // faster progress
                         return;
@@ -172,7 +185,7 @@ impl PeerManager {
                 }
             }
 
-            *current_failover = failover_candidate.cloned().cloned();
+            *current_failover = failover_candidate.cloned();
         } else {
             // there is at least one peer alive in the primary upstream network, so don't pick
             // a failover peer
@@ -180,6 +193,40 @@ impl PeerManager {
         }
     }
 
+    // picks the peer with the lowest latency, or a random one if no latency was measured yet,
+    // among the peers not suspected by the network layer if there are any
+    fn fastest_peer<'a>(
+        &self,
+        network_id: &NetworkId,
+        peers: &[&'a PeerNetworkId],
+    ) -> Option<&'a PeerNetworkId> {
+        let trusted_peers: Vec<_> = peers
+            .iter()
+            .copied()
+            .filter(|peer| !self.is_suspect(peer))
+            .collect();
+        let peers = if trusted_peers.is_empty() {
+            peers
+        } else {
+            &trusted_peers[..]
+        };
+        let fastest = self.peer_latencies.get(network_id).and_then(|latencies| {
+            let fastest = latencies.fastest(peers.iter().map(|peer| peer.peer_id()))?;
+            latencies.get(&fastest)?;
+            peers.iter().find(|peer| peer.peer_id() == fastest)
+        });
+        fastest
+            .or_else(|| peers.choose(&mut rand::thread_rng()))
+            .copied()
+    }
+
+    // whether the applications of the network reported the peer to misbehave more than to behave
+    fn is_suspect(&self, peer: &PeerNetworkId) -> bool {
+        self.peer_scores
+            .get(&peer.network_id())
+            .map_or(false, |scores| scores.is_suspect(&peer.peer_id()))
+    }
+
     pub fn update_peer_broadcast(
         &self,
         peer: PeerNetworkId,
@@ -286,10 +333,55 @@ impl PeerManager {
         }
 
         // checks whether this peer a chosen upstream failover peer
-        self.failover_peer
-            .lock()
-            .expect("failed to get failover peer")
-            .deref()
-            == &Some(peer.clone())
+        let is_failover_peer = |peer_manager: &Self| {
+            peer_manager
+                .failover_peer
+                .lock()
+                .expect("failed to get failover peer")
+                .deref()
+                == &Some(peer.clone())
+        };
+        if is_failover_peer(self) && self.is_suspect(peer) {
+            // the peer got reported to misbehave since it was picked, look for a better one
+            self.update_failover();
+        }
+        is_failover_peer(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libra_types::PeerId;
+    use network::protocols::network::PeerBehavior;
+    use std::{iter, time::Duration};
+
+    #[test]
+    fn test_failover_avoids_suspect_peers() {
+        let upstream_config = UpstreamConfig {
+            networks: vec![NetworkId::vfn_network(), NetworkId::Public],
+        };
+        let (fast, slow) = (
+            PeerNetworkId(NetworkId::Public, PeerId::random()),
+            PeerNetworkId(NetworkId::Public, PeerId::random()),
+        );
+        let latencies = PeerLatencies::default();
+        latencies.record(fast.peer_id(), Duration::from_millis(20));
+        latencies.record(slow.peer_id(), Duration::from_millis(200));
+        let scores = PeerScores::default();
+        let peer_manager = PeerManager::new(
+            upstream_config,
+            iter::once((NetworkId::Public, latencies)).collect(),
+            iter::once((NetworkId::Public, scores.clone())).collect(),
+        );
+        peer_manager.add_peer(fast.clone());
+        peer_manager.add_peer(slow.clone());
+        assert!(peer_manager.is_picked_peer(&fast));
+        assert!(!peer_manager.is_picked_peer(&slow));
+
+        // the failover peer is replaced once reported to misbehave
+        scores.record(fast.peer_id(), PeerBehavior::Invalid);
+        assert!(!peer_manager.is_picked_peer(&fast));
+        assert!(peer_manager.is_picked_peer(&slow));
     }
 }
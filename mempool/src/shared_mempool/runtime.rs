@@ -49,15 +49,25 @@ pub(crate) fn start_shared_mempool<V>(
 ) where
     V: TransactionValidation + 'static,
 {
-    let upstream_config = config.upstream.clone();
-    let peer_manager = Arc::new(PeerManager::new(upstream_config));
-
     let mut all_network_events = vec![];
     let mut network_senders = HashMap::new();
     for (network_id, network_sender, network_events) in mempool_network_handles.into_iter() {
         all_network_events.push((network_id.clone(), network_events));
         network_senders.insert(network_id, network_sender);
     }
+    let peer_latencies = network_senders
+        .iter()
+        .map(|(network_id, sender)| (network_id.clone(), sender.peer_latencies().clone()))
+        .collect();
+    let peer_scores = network_senders
+        .iter()
+        .map(|(network_id, sender)| (network_id.clone(), sender.peer_scores().clone()))
+        .collect();
+    let peer_manager = Arc::new(PeerManager::new(
+        config.upstream.clone(),
+        peer_latencies,
+        peer_scores,
+    ));
 
     let smp = SharedMempool {
         mempool: mempool.clone(),
@@ -13,6 +13,7 @@ use crate::{
     CommitNotification, CommitResponse, CommittedTransaction, ConsensusRequest, ConsensusResponse,
     SubmissionStatus,
 };
+use ::network::protocols::network::PeerBehavior;
 use anyhow::{format_err, Result};
 use futures::{channel::oneshot, stream::FuturesUnordered};
 use libra_config::config::PeerNetworkId;
@@ -22,7 +23,7 @@ use libra_types::{
     on_chain_config::OnChainConfigPayload,
     transaction::SignedTransaction,
     vm_status::{
-        StatusCode::{RESOURCE_DOES_NOT_EXIST, SEQUENCE_NUMBER_TOO_OLD},
+        StatusCode::{INVALID_SIGNATURE, RESOURCE_DOES_NOT_EXIST, SEQUENCE_NUMBER_TOO_OLD},
         VMStatus,
     },
     PeerId,
@@ -259,12 +260,25 @@ pub(crate) async fn process_transaction_broadcast<V>(
             .data("from_peer", &peer));
     }
 
+    // honest peers validate transactions before relaying them
+    let behavior = if results
+        .iter()
+        .any(|(_, vm_status)| vm_status == &Some(VMStatus::Error(INVALID_SIGNATURE)))
+    {
+        PeerBehavior::Invalid
+    } else {
+        PeerBehavior::Good
+    };
+
     // send back ACK
     let ack_response = gen_ack_response(request_id, results);
     let mut network_sender = smp
         .network_senders
         .get_mut(&peer.network_id())
         .expect("[shared mempool] missing network sender");
+    if let Err(e) = network_sender.report_peer(peer.peer_id(), behavior) {
+        error!("[shared mempool] failed to report peer {:?}: {}", peer, e);
+    }
     if let Err(e) = send_mempool_sync_msg(ack_response, peer.peer_id(), &mut network_sender) {
         error!(
             "[shared mempool] failed to send ACK back to peer {:?}: {}",
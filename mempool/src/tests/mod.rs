@@ -6,6 +6,8 @@ mod common;
 #[cfg(test)]
 mod core_mempool_test;
 #[cfg(test)]
+mod replay_test;
+#[cfg(test)]
 mod shared_mempool_test;
 
 /// Mocks used for testing
@@ -0,0 +1,180 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replay of captured network traffic into a single shared mempool, over memsocket.
+
+use crate::{
+    core_mempool::CoreMempool,
+    network::{
+        network_endpoint_config, MempoolNetworkEvents, MempoolNetworkSender, MempoolSyncMsg,
+    },
+    shared_mempool::start_shared_mempool,
+    tests::common::TestTransaction,
+};
+use channel::{libra_channel, message_queues::QueueStyle};
+use futures::channel::mpsc;
+use libra_config::{
+    config::{NetworkConfig, NodeConfig, RoleType},
+    network_id::NetworkId,
+};
+use libra_crypto::{test_utils::TEST_SEED, x25519, Uniform};
+use libra_types::{chain_id::ChainId, transaction::SignedTransaction, PeerId};
+use network::{
+    capture::{replay::Replayer, CapturedMessage, Direction},
+    constants::MAX_FRAME_SIZE,
+    peer_manager::builder::AuthenticationMode,
+    protocols::wire::messaging::v1::{DirectSendMsg, NetworkMessage},
+    ProtocolId,
+};
+use network_builder::builder::NetworkBuilder;
+use rand::{rngs::StdRng, SeedableRng};
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, RwLock},
+    thread,
+    time::Duration,
+};
+use storage_interface::mock::MockDbReader;
+use tokio::runtime::Builder;
+use vm_validator::mocks::mock_vm_validator::MockVMValidator;
+
+fn captured_broadcast(
+    peer_id: PeerId,
+    direction: Direction,
+    request_id: &str,
+    transactions: Vec<SignedTransaction>,
+) -> CapturedMessage {
+    let msg = MempoolSyncMsg::BroadcastTransactionsRequest {
+        request_id: request_id.to_string(),
+        transactions,
+    };
+    CapturedMessage {
+        timestamp_usecs: 0,
+        peer_id,
+        direction,
+        protocol: Some(ProtocolId::MempoolDirectSend),
+        message: NetworkMessage::DirectSendMsg(DirectSendMsg {
+            protocol_id: ProtocolId::MempoolDirectSend,
+            priority: 0,
+            raw_msg: lcs::to_bytes(&msg).unwrap(),
+        }),
+    }
+}
+
+#[test]
+fn test_replay_broadcasts() {
+    let mut runtime = Builder::new()
+        .thread_name("replay-shared-mem-")
+        .threaded_scheduler()
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut config = NodeConfig::random();
+    config.validator_network = Some(NetworkConfig::network_with_id(NetworkId::Validator));
+
+    let mut rng = StdRng::from_seed(TEST_SEED);
+    let node_key = x25519::PrivateKey::generate(&mut rng);
+    let replay_key = x25519::PrivateKey::generate(&mut rng);
+    let node_peer_id = PeerId::random();
+    let replayed_peer_id = PeerId::random();
+
+    // Set up the network of the node, trusting the key of the replayer for the replayed peer.
+    let seed_pubkeys: HashMap<_, _> = vec![(
+        replayed_peer_id,
+        vec![replay_key.public_key()]
+            .into_iter()
+            .collect::<HashSet<_>>(),
+    )]
+    .into_iter()
+    .collect();
+    let mut network_builder = NetworkBuilder::new(
+        runtime.handle().clone(),
+        ChainId::default(),
+        NetworkId::Validator,
+        RoleType::Validator,
+        node_peer_id,
+        "/memory/0".parse().unwrap(),
+        AuthenticationMode::Mutual(node_key),
+        MAX_FRAME_SIZE,
+    );
+    network_builder
+        .seed_pubkeys(seed_pubkeys)
+        .add_connectivity_manager();
+    let (network_sender, network_events) = network_builder
+        .add_protocol_handler::<MempoolNetworkSender, MempoolNetworkEvents>(
+            network_endpoint_config(config.mempool.max_broadcasts_per_peer),
+        );
+    let node_addr = network_builder.build();
+
+    // Start the shared mempool of the node.
+    let mempool = Arc::new(Mutex::new(CoreMempool::new(&config)));
+    let (_ac_client, client_events) = mpsc::channel(1_024);
+    let (_consensus_sender, consensus_events) = mpsc::channel(1_024);
+    let (_state_sync_sender, state_sync_events) = mpsc::channel(1_024);
+    let (_reconfig_events, reconfig_events_receiver) =
+        libra_channel::new(QueueStyle::LIFO, NonZeroUsize::new(1).unwrap(), None);
+    start_shared_mempool(
+        runtime.handle(),
+        &config,
+        Arc::clone(&mempool),
+        vec![(NetworkId::Validator, network_sender, network_events)],
+        client_events,
+        consensus_events,
+        state_sync_events,
+        reconfig_events_receiver,
+        Arc::new(MockDbReader),
+        Arc::new(RwLock::new(MockVMValidator)),
+        vec![],
+    );
+
+    // Only the broadcasts received from the replayed peer are sent to the node.
+    let txns: Vec<_> = (0..3)
+        .map(|seq| TestTransaction::new(1, seq, 1).make_signed_transaction())
+        .collect();
+    let capture = vec![
+        captured_broadcast(
+            replayed_peer_id,
+            Direction::Inbound,
+            "0_1",
+            txns[..2].to_vec(),
+        ),
+        captured_broadcast(
+            replayed_peer_id,
+            Direction::Outbound,
+            "0_1",
+            vec![TestTransaction::new(2, 0, 1).make_signed_transaction()],
+        ),
+        captured_broadcast(
+            replayed_peer_id,
+            Direction::Inbound,
+            "2_2",
+            txns[2..].to_vec(),
+        ),
+    ];
+    let replayer = Replayer::new(&capture, replayed_peer_id);
+    let replayed = runtime.block_on(async {
+        let connection = replayer
+            .dial_memory(
+                ChainId::default(),
+                NetworkId::Validator,
+                replay_key,
+                node_peer_id,
+                node_addr,
+            )
+            .await
+            .unwrap();
+        replayer.replay(connection).await.unwrap()
+    });
+    assert_eq!(replayed, 2);
+
+    let mut block = vec![];
+    for _ in 0..100 {
+        block = mempool.lock().unwrap().get_block(100, HashSet::new());
+        if block.len() >= txns.len() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    assert_eq!(block, txns);
+}
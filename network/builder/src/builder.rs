@@ -11,7 +11,10 @@
 //! long as the latter is in its trusted peers set.
 use channel::{self, message_queues::QueueStyle};
 use libra_config::{
-    config::{DiscoveryMethod, NetworkConfig, RoleType, HANDSHAKE_VERSION},
+    config::{
+        CaptureConfig, DiscoveryMethod, Identity, NetworkConfig, PeerExchangeConfig,
+        PeerReputationConfig, RateLimitConfig, RoleType, HANDSHAKE_VERSION,
+    },
     network_id::{NetworkContext, NetworkId},
 };
 use libra_crypto::x25519;
@@ -27,23 +30,26 @@ use network::{
     constants,
     peer_manager::{
         builder::{AuthenticationMode, PeerManagerBuilder},
-        conn_notifs_channel, ConnectionRequestSender,
+        conn_notifs_channel, ConnectionRequestSender, NetworkStatus,
     },
     protocols::{
         gossip_discovery::{self, builder::GossipDiscoveryBuilder},
         health_checker::{self, builder::HealthCheckerBuilder},
         network::{NewNetworkEvents, NewNetworkSender},
+        peer_exchange::{self, builder::PeerExchangeBuilder},
     },
-    ProtocolId,
+    IdentityKeys, ProtocolId,
 };
 use network_simple_onchain_discovery::{
     builder::ConfigurationChangeListenerBuilder, gen_simple_discovery_reconfig_subscription,
+    KeyRotation,
 };
 use std::{
     clone::Clone,
     collections::{HashMap, HashSet},
     iter,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 use subscription_service::ReconfigSubscription;
 use tokio::runtime::{Builder, Handle, Runtime};
@@ -64,6 +70,7 @@ pub struct NetworkBuilder {
     channel_size: usize,
     connectivity_check_interval_ms: u64,
     max_connection_delay_ms: u64,
+    key_rotation_grace_ms: u64,
     /// For now full node connections are limited by
     max_fullnode_connections: usize,
 
@@ -71,6 +78,7 @@ pub struct NetworkBuilder {
     connectivity_manager_builder: Option<ConnectivityManagerBuilder>,
     discovery_builder: Option<GossipDiscoveryBuilder>,
     health_checker_builder: Option<HealthCheckerBuilder>,
+    peer_exchange_builder: Option<PeerExchangeBuilder>,
     peer_manager_builder: PeerManagerBuilder,
 
     reconfig_subscriptions: Vec<ReconfigSubscription>,
@@ -118,11 +126,13 @@ impl NetworkBuilder {
             channel_size: constants::NETWORK_CHANNEL_SIZE,
             connectivity_check_interval_ms: constants::CONNECTIVITY_CHECK_INTERNAL_MS,
             max_connection_delay_ms: constants::MAX_CONNECTION_DELAY_MS,
+            key_rotation_grace_ms: constants::KEY_ROTATION_GRACE_MS,
             max_fullnode_connections: constants::MAX_FULLNODE_CONNECTIONS,
             configuration_change_listener_builder: None,
             connectivity_manager_builder: None,
             discovery_builder: None,
             health_checker_builder: None,
+            peer_exchange_builder: None,
             peer_manager_builder,
             reconfig_subscriptions: vec![],
         }
@@ -164,6 +174,10 @@ impl NetworkBuilder {
             .seed_addrs(config.seed_addrs.clone())
             .seed_pubkeys(config.seed_pubkeys.clone())
             .connectivity_check_interval_ms(config.connectivity_check_interval_ms)
+            .rate_limit(config.rate_limit.clone())
+            .peer_reputation(config.peer_reputation.clone())
+            .capture(config.capture.clone())
+            .key_rotation_grace_ms(config.key_rotation_grace_ms)
             .add_connection_monitoring(
                 // TODO: Move these values into NetworkConfig
                 constants::PING_INTERVAL_MS,
@@ -203,11 +217,14 @@ impl NetworkBuilder {
                 );
                 // HACK: gossip relies on on-chain discovery for the eligible peers update.
                 if role == RoleType::Validator {
-                    network_builder.add_configuration_change_listener(role);
+                    network_builder.add_configuration_change_listener(role, &config.identity);
                 }
             }
             DiscoveryMethod::Onchain => {
-                network_builder.add_configuration_change_listener(role);
+                network_builder.add_configuration_change_listener(role, &config.identity);
+            }
+            DiscoveryMethod::PeerExchange(peer_exchange_config) => {
+                network_builder.add_peer_exchange(peer_exchange_config.clone(), pubkey);
             }
             DiscoveryMethod::None => {}
         }
@@ -236,6 +253,40 @@ impl NetworkBuilder {
         self
     }
 
+    /// Set the rate limits applied to every connection.
+    pub fn rate_limit(&mut self, rate_limit: RateLimitConfig) -> &mut Self {
+        self.peer_manager_builder.rate_limit(rate_limit);
+        self
+    }
+
+    /// Set when misbehaving peers get banned.
+    pub fn peer_reputation(&mut self, peer_reputation: PeerReputationConfig) -> &mut Self {
+        self.peer_manager_builder.peer_reputation(peer_reputation);
+        self
+    }
+
+    /// Set whether and where the messages exchanged with peers are recorded.
+    pub fn capture(&mut self, capture: CaptureConfig) -> &mut Self {
+        self.peer_manager_builder.capture(capture);
+        self
+    }
+
+    /// Set how long the previous key of a peer stays trusted after it rotates its key.
+    pub fn key_rotation_grace_ms(&mut self, key_rotation_grace_ms: u64) -> &mut Self {
+        self.key_rotation_grace_ms = key_rotation_grace_ms;
+        self
+    }
+
+    /// The keys authenticating this node, to rotate them while the network runs.
+    pub fn identity_keys(&self) -> Arc<IdentityKeys> {
+        self.peer_manager_builder.identity_keys()
+    }
+
+    /// A handle to snapshot the connections of the network.
+    pub fn network_status(&self) -> NetworkStatus {
+        self.peer_manager_builder.network_status()
+    }
+
     /// Set addresses of seed peers to bootstrap discovery
     pub fn seed_addrs(&mut self, seed_addrs: HashMap<PeerId, Vec<NetworkAddress>>) -> &mut Self {
         self.seed_addrs = seed_addrs;
@@ -320,6 +371,7 @@ impl NetworkBuilder {
             ConnectionRequestSender::new(self.peer_manager_builder.connection_reqs_tx()),
             pm_conn_mgr_notifs_rx,
             connection_limit,
+            self.key_rotation_grace_ms,
         ));
         self.build_connectivity_manager()
             .start_connectivity_manager()
@@ -339,7 +391,13 @@ impl NetworkBuilder {
         self
     }
 
-    fn add_configuration_change_listener(&mut self, role: RoleType) -> &mut Self {
+    /// Add the on-chain discovery of peers. If the identity key of this node is kept in secure
+    /// storage, the listener also switches to the new key once it is rotated on-chain.
+    fn add_configuration_change_listener(
+        &mut self,
+        role: RoleType,
+        identity: &Identity,
+    ) -> &mut Self {
         let conn_mgr_reqs_tx = self
             .conn_mgr_reqs_tx()
             .expect("ConnectivityManager must be installed for validator");
@@ -355,12 +413,23 @@ impl NetworkBuilder {
         ))
         .collect();
 
+        let key_rotation = match identity {
+            Identity::FromStorage(identity) => Some(KeyRotation::new(
+                self.peer_id(),
+                self.identity_keys(),
+                identity.clone(),
+                Duration::from_millis(self.key_rotation_grace_ms),
+            )),
+            Identity::FromConfig(_) | Identity::None => None,
+        };
+
         self.configuration_change_listener_builder =
             Some(ConfigurationChangeListenerBuilder::create(
                 role,
                 shared_val_netaddr_key_map,
                 conn_mgr_reqs_tx,
                 simple_discovery_reconfig_rx,
+                key_rotation,
             ));
         self.build_configuration_change_listener()
             .start_configuration_change_listener()
@@ -448,6 +517,54 @@ impl NetworkBuilder {
         self
     }
 
+    /// Add the [`PeerExchange`] protocol to the network.
+    ///
+    /// [`PeerExchange`] lets full nodes learn the addresses of other full nodes
+    /// from their peers, and keeps them in an address book persisted across restarts.
+    pub fn add_peer_exchange(
+        &mut self,
+        config: PeerExchangeConfig,
+        pubkey: x25519::PublicKey,
+    ) -> &mut Self {
+        let conn_mgr_reqs_tx = self
+            .conn_mgr_reqs_tx()
+            .expect("ConnectivityManager not enabled");
+        let (peer_exchange_network_tx, peer_exchange_network_rx) =
+            self.add_protocol_handler(peer_exchange::network_endpoint_config());
+        let self_addrs = config
+            .advertised_address
+            .iter()
+            .map(|addr| addr.clone().append_prod_protos(pubkey, HANDSHAKE_VERSION))
+            .collect();
+
+        self.peer_exchange_builder = Some(PeerExchangeBuilder::create(
+            self.network_context(),
+            self_addrs,
+            config,
+            peer_exchange_network_tx,
+            peer_exchange_network_rx,
+            conn_mgr_reqs_tx,
+        ));
+        self.build_peer_exchange().start_peer_exchange();
+        self
+    }
+
+    fn build_peer_exchange(&mut self) -> &mut Self {
+        if let Some(peer_exchange_builder) = self.peer_exchange_builder.as_mut() {
+            peer_exchange_builder.build(&self.executor);
+            debug!("{} Built peer exchange", self.network_context);
+        }
+        self
+    }
+
+    fn start_peer_exchange(&mut self) -> &mut Self {
+        if let Some(peer_exchange_builder) = self.peer_exchange_builder.as_mut() {
+            peer_exchange_builder.start(&self.executor);
+            debug!("{} Started peer exchange", self.network_context);
+        }
+        self
+    }
+
     /// Add a HealthChecker to the network.
     pub fn add_connection_monitoring(
         &mut self,
@@ -1,7 +1,7 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::transport::Transport;
+use crate::transport::{MessageStreams, Transport};
 use futures::{future, stream::Stream};
 use libra_network_address::{parse_memory, NetworkAddress, Protocol};
 use libra_types::PeerId;
@@ -12,6 +12,8 @@ use std::{
     task::{Context, Poll},
 };
 
+impl MessageStreams for MemorySocket {}
+
 /// Transport to build in-memory connections
 #[derive(Clone, Debug, Default)]
 pub struct MemoryTransport;
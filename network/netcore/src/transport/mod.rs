@@ -11,15 +11,21 @@
 //! [`Transport`]: crate::transport::Transport
 //! [`TransportExt`]: crate::transport::TransportExt
 
-use futures::{future::Future, stream::Stream};
+use bytes::{Bytes, BytesMut};
+use futures::{
+    future::Future,
+    sink::Sink,
+    stream::{BoxStream, Stream},
+};
 use libra_network_address::NetworkAddress;
 use libra_types::PeerId;
 use serde::Serialize;
-use std::time::Duration;
+use std::{io, pin::Pin, time::Duration};
 
 pub mod and_then;
 pub mod boxed;
 pub mod memory;
+pub mod quic;
 pub mod tcp;
 pub mod timeout;
 
@@ -32,6 +38,41 @@ pub enum ConnectionOrigin {
     Outbound,
 }
 
+/// Inbound messages of a connection, each received on its own stream.
+pub type MessageReader = BoxStream<'static, io::Result<BytesMut>>;
+
+/// Outbound messages of a connection, each sent on its own stream.
+pub type MessageWriter = Pin<Box<dyn Sink<Bytes, Error = io::Error> + Send>>;
+
+/// Sockets of transports which can carry each message of a connection on its own stream, so that
+/// a message stalled by packet loss doesn't hold up the others.
+///
+/// Message streams are only secured by the transport itself, e.g. the TLS layer of QUIC. They can
+/// be used once the remote has confirmed the [`channel_binding`](MessageStreams::channel_binding)
+/// of the socket, e.g. by mixing it into an authenticated handshake, showing that both ends are
+/// part of the same transport session. Sockets without message streams keep the default
+/// implementations.
+pub trait MessageStreams {
+    /// Bytes identifying the secure session of the transport, the same at both ends of the
+    /// connection, or `None` if the socket has no message streams.
+    fn channel_binding(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Records that the remote confirmed the channel binding of the socket.
+    fn confirm_channel_binding(&mut self) {}
+
+    /// Takes the message streams of the connection, reading inbound messages of at most
+    /// `max_message_size` bytes. Returns `None` if the socket has no message streams, or if the
+    /// channel binding wasn't confirmed.
+    fn take_message_streams(
+        &mut self,
+        _max_message_size: usize,
+    ) -> Option<(MessageReader, MessageWriter)> {
+        None
+    }
+}
+
 /// A Transport is responsible for establishing connections with remote Peers.
 ///
 /// Connections are established either by [listening](Transport::listen_on)
@@ -0,0 +1,1047 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! QUIC Transport
+//!
+//! The connections of a transport, and of its clones, share one UDP endpoint per IP version.
+//! Dials go out from the listening endpoint when there is one, so that a node is reached and
+//! reaches others through the same port.
+//!
+//! A connection starts with a bidirectional QUIC stream, exposed as a socket-like
+//! [`QuicSocket`]. It carries the Noise IK upgrade and the LibraNet handshake, exactly as with
+//! the [`TcpTransport`]. Once the Noise handshake has confirmed the channel binding of the
+//! connection, each message is sent on its own unidirectional QUIC stream, see
+//! [`MessageStreams`]. QUIC handles loss recovery and flow control per stream over UDP, so a lost
+//! packet only stalls the message it belongs to, not the whole connection as with TCP.
+//!
+//! The TLS layer of QUIC doesn't authenticate peers: listeners present a throwaway self-signed
+//! certificate and dialers don't verify it. The channel binding of a connection is its TLS
+//! exporter ([RFC 5705], [RFC 8446 section 7.5]), derived from the secrets of the TLS session.
+//! The Noise handshake covers it, so a man in the middle relaying the Noise session between two
+//! TLS sessions makes the handshake fail, whatever certificate it presents. Message streams are
+//! thus only readable and writable by the peers authenticated by the Noise handshake.
+//!
+//! Rustls doesn't export keying material from QUIC sessions, so the exporter is derived from the
+//! exporter master secret rustls reports to the key log of the session, see [`ExporterSecrets`].
+//!
+//! [RFC 5705]: https://tools.ietf.org/html/rfc5705
+//! [RFC 8446 section 7.5]: https://tools.ietf.org/html/rfc8446#section-7.5
+//!
+//! [`TcpTransport`]: crate::transport::tcp::TcpTransport
+use crate::{
+    compat::IoCompat,
+    transport::{
+        tcp::resolve_with_filter, MessageReader, MessageStreams, MessageWriter, Transport,
+    },
+};
+use bytes::{Bytes, BytesMut};
+use futures::{
+    future::{Future, FutureExt},
+    io::{AsyncRead, AsyncWrite},
+    ready,
+    sink::Sink,
+    stream::{FusedStream, FuturesUnordered, Stream, StreamExt},
+};
+use libra_crypto::hkdf::Hkdf;
+use libra_network_address::{parse_dns_quic, parse_ip_quic, NetworkAddress, Protocol};
+use libra_types::PeerId;
+use quinn::{generic, ConnectError};
+use quinn_proto::{
+    crypto::{self, rustls::TlsSession},
+    transport_parameters::TransportParameters,
+    ConnectionId, TransportError,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fmt, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+
+/// ALPN protocol negotiated on libranet QUIC connections.
+const ALPN_PROTOCOL: &[u8] = b"libranet";
+
+/// Server name presented in the self-signed listener certificates. It is never verified.
+const SERVER_NAME: &str = "libranet";
+
+/// Label of the TLS exporter used as channel binding, as defined by RFC 9266.
+const EXPORTER_LABEL: &[u8] = b"EXPORTER-Channel-Binding";
+
+/// Size of the channel binding of a connection.
+const EXPORTER_SIZE: usize = 32;
+
+/// Key log label of the TLS 1.3 exporter master secret.
+const EXPORTER_SECRET_LABEL: &str = "EXPORTER_SECRET";
+
+/// Size of the random of the TLS ClientHello.
+const CLIENT_RANDOM_SIZE: usize = 32;
+
+/// Offset of the random in the ClientHello handshake message, after the message type and length,
+/// and the legacy protocol version.
+const CLIENT_RANDOM_OFFSET: usize = 6;
+
+/// Byte sent by the dialer to open the connection stream. QUIC streams are only announced to
+/// the remote once data is sent on them.
+const STREAM_PREAMBLE: u8 = 0;
+
+/// Max number of outbound message streams being written concurrently on a connection.
+const MAX_OUTBOUND_MESSAGE_STREAMS: usize = 64;
+
+/// Max number of inbound message streams being read concurrently on a connection. Further streams
+/// are held back by QUIC flow control until reads complete.
+const MAX_INBOUND_MESSAGE_STREAMS: usize = 64;
+
+type Endpoint = generic::Endpoint<ExporterTlsSession>;
+type Connecting = generic::Connecting<ExporterTlsSession>;
+type Connection = generic::Connection<ExporterTlsSession>;
+type NewConnection = generic::NewConnection<ExporterTlsSession>;
+type SendStream = generic::SendStream<ExporterTlsSession>;
+type RecvStream = generic::RecvStream<ExporterTlsSession>;
+type IncomingUniStreams = generic::IncomingUniStreams<ExporterTlsSession>;
+
+/// Transport to build QUIC connections
+#[derive(Debug, Clone, Default)]
+pub struct QuicTransport {
+    /// Interval of the keep alive packets sent on idle connections, or `None` to keep default.
+    keep_alive_interval: Option<Duration>,
+    /// Endpoints shared by the transport and its clones.
+    endpoints: Arc<Mutex<Endpoints>>,
+}
+
+/// The UDP endpoints of a transport, by IP version.
+#[derive(Default)]
+struct Endpoints {
+    v4: Option<Endpoint>,
+    v6: Option<Endpoint>,
+}
+
+impl fmt::Debug for Endpoints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Endpoints")
+            .field("v4", &self.v4.is_some())
+            .field("v6", &self.v6.is_some())
+            .finish()
+    }
+}
+
+impl Endpoints {
+    fn get_mut(&mut self, ip: IpAddr) -> &mut Option<Endpoint> {
+        match ip {
+            IpAddr::V4(_) => &mut self.v4,
+            IpAddr::V6(_) => &mut self.v6,
+        }
+    }
+}
+
+impl QuicTransport {
+    pub fn new(keep_alive_interval: Option<Duration>) -> Self {
+        Self {
+            keep_alive_interval,
+            endpoints: Arc::default(),
+        }
+    }
+
+    fn transport_config(&self) -> quinn::TransportConfig {
+        let mut config = quinn::TransportConfig::default();
+        if let Some(interval) = self.keep_alive_interval {
+            config.keep_alive_interval(Some(interval));
+        }
+        config
+    }
+
+    /// Returns the config of a listener presenting `cert`.
+    fn server_config(
+        &self,
+        cert: &rcgen::Certificate,
+    ) -> io::Result<generic::ServerConfig<ExporterTlsSession>> {
+        let cert_der = cert.serialize_der().map_err(other_error)?;
+        let mut server_config = generic::ServerConfig::<ExporterTlsSession>::default();
+        server_config.transport = Arc::new(self.transport_config());
+        let crypto = Arc::make_mut(&mut server_config.crypto.tls);
+        crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+        crypto
+            .set_single_cert(
+                vec![rustls::Certificate(cert_der)],
+                rustls::PrivateKey(cert.serialize_private_key_der()),
+            )
+            .map_err(other_error)?;
+        Ok(server_config)
+    }
+
+    fn client_config(&self) -> generic::ClientConfig<ExporterTlsSession> {
+        let mut client_config = generic::ClientConfig::<ExporterTlsSession>::default();
+        client_config.transport = Arc::new(self.transport_config());
+        let crypto = Arc::make_mut(&mut client_config.crypto.tls);
+        crypto.alpn_protocols = vec![ALPN_PROTOCOL.to_vec()];
+        crypto
+            .dangerous()
+            .set_certificate_verifier(Arc::new(AcceptAnyServerCert));
+        client_config
+    }
+
+    /// Listens on `addr` with a listener presenting `cert`.
+    fn listen_with_cert(
+        &self,
+        addr: NetworkAddress,
+        cert: &rcgen::Certificate,
+    ) -> io::Result<(<Self as Transport>::Listener, NetworkAddress)> {
+        let ((ipaddr, port), addr_suffix) =
+            parse_ip_quic(addr.as_slice()).ok_or_else(|| invalid_addr_error(&addr))?;
+        if !addr_suffix.is_empty() {
+            return Err(invalid_addr_error(&addr));
+        }
+
+        let mut builder = Endpoint::builder();
+        builder.listen(self.server_config(cert)?);
+        let (endpoint, incoming) = builder
+            .bind(&SocketAddr::new(ipaddr, port))
+            .map_err(other_error)?;
+        let listen_addr = quic_addr(endpoint.local_addr()?);
+        // Dial from the listening endpoint from now on.
+        *self
+            .endpoints
+            .lock()
+            .expect("Endpoints lock is poisoned")
+            .get_mut(ipaddr) = Some(endpoint.clone());
+
+        // the endpoint handle is moved into the stream to keep the endpoint open as long as we
+        // are listening.
+        let listener = incoming.map(move |connecting| {
+            let _endpoint = &endpoint;
+            let dialer_addr = quic_addr(connecting.remote_address());
+            Ok((accept(connecting).boxed(), dialer_addr))
+        });
+        Ok((listener.boxed(), listen_addr))
+    }
+
+    /// Returns the endpoint to dial `ip` from, binding a new one if the transport has none.
+    fn dialing_endpoint(&self, ip: IpAddr) -> io::Result<Endpoint> {
+        let mut endpoints = self.endpoints.lock().expect("Endpoints lock is poisoned");
+        let endpoint = endpoints.get_mut(ip);
+        if let Some(existing) = endpoint.as_ref() {
+            return Ok(existing.clone());
+        }
+        let bind_addr = match ip {
+            IpAddr::V4(_) => SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0),
+            IpAddr::V6(_) => SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0),
+        };
+        let (new_endpoint, _incoming) =
+            Endpoint::builder().bind(&bind_addr).map_err(other_error)?;
+        *endpoint = Some(new_endpoint.clone());
+        Ok(new_endpoint)
+    }
+}
+
+impl Transport for QuicTransport {
+    type Output = QuicSocket;
+    type Error = ::std::io::Error;
+    type Listener = Pin<Box<dyn Stream<Item = io::Result<(Self::Inbound, NetworkAddress)>> + Send>>;
+    type Inbound = Pin<Box<dyn Future<Output = io::Result<QuicSocket>> + Send>>;
+    type Outbound = Pin<Box<dyn Future<Output = io::Result<QuicSocket>> + Send>>;
+
+    fn listen_on(
+        &self,
+        addr: NetworkAddress,
+    ) -> Result<(Self::Listener, NetworkAddress), Self::Error> {
+        let cert = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()])
+            .map_err(other_error)?;
+        self.listen_with_cert(addr, &cert)
+    }
+
+    fn dial(&self, _peer_id: PeerId, addr: NetworkAddress) -> Result<Self::Outbound, Self::Error> {
+        let protos = addr.as_slice();
+
+        // ensure addr is well formed to save some work before potentially
+        // spawning a dial task that will fail anyway.
+        parse_ip_quic(protos)
+            .map(|_| ())
+            .or_else(|| parse_dns_quic(protos).map(|_| ()))
+            .ok_or_else(|| invalid_addr_error(&addr))?;
+
+        Ok(resolve_and_connect(addr, self.clone()).boxed())
+    }
+}
+
+/// Accepts an inbound connection and its stream.
+async fn accept(connecting: Connecting) -> io::Result<QuicSocket> {
+    let NewConnection {
+        connection,
+        uni_streams,
+        mut bi_streams,
+        ..
+    } = connecting.await.map_err(io::Error::from)?;
+    let (send, mut recv) = bi_streams
+        .next()
+        .await
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "QUIC connection closed before opening a stream",
+            )
+        })?
+        .map_err(io::Error::from)?;
+
+    let mut preamble = [0u8; 1];
+    recv.read_exact(&mut preamble).await.map_err(other_error)?;
+    if preamble[0] != STREAM_PREAMBLE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Unexpected QUIC stream preamble: {}", preamble[0]),
+        ));
+    }
+    QuicSocket::new(connection, send, recv, uni_streams)
+}
+
+/// Note: we need to take ownership of this `NetworkAddress` (instead of just
+/// borrowing the `&[Protocol]` slice) so this future can be `Send + 'static`.
+async fn resolve_and_connect(
+    addr: NetworkAddress,
+    transport: QuicTransport,
+) -> io::Result<QuicSocket> {
+    let protos = addr.as_slice();
+
+    if let Some(((ipaddr, port), _addr_suffix)) = parse_ip_quic(protos) {
+        // this is an /ip4 or /ip6 address, so we can just connect without any
+        // extra resolving or filtering.
+        connect(SocketAddr::new(ipaddr, port), &transport).await
+    } else if let Some(((ip_filter, dns_name, port), _addr_suffix)) = parse_dns_quic(protos) {
+        // resolve dns name and filter
+        let socketaddr_iter = resolve_with_filter(ip_filter, dns_name.as_ref(), port).await?;
+        let mut last_err = None;
+
+        // try to connect until the first succeeds
+        for socketaddr in socketaddr_iter {
+            match connect(socketaddr, &transport).await {
+                Ok(socket) => return Ok(socket),
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "could not resolve dns name to any address: name: {}, ip filter: {:?}",
+                    dns_name.as_ref(),
+                    ip_filter,
+                ),
+            )
+        }))
+    } else {
+        Err(invalid_addr_error(&addr))
+    }
+}
+
+/// Connects from the shared endpoint and opens the connection stream.
+async fn connect(socketaddr: SocketAddr, transport: &QuicTransport) -> io::Result<QuicSocket> {
+    let endpoint = transport.dialing_endpoint(socketaddr.ip())?;
+    let NewConnection {
+        connection,
+        uni_streams,
+        ..
+    } = endpoint
+        .connect_with(transport.client_config(), &socketaddr, SERVER_NAME)
+        .map_err(other_error)?
+        .await
+        .map_err(io::Error::from)?;
+    let (mut send, recv) = connection.open_bi().await.map_err(io::Error::from)?;
+    send.write_all(&[STREAM_PREAMBLE])
+        .await
+        .map_err(io::Error::from)?;
+    QuicSocket::new(connection, send, recv, uni_streams)
+}
+
+fn quic_addr(socketaddr: SocketAddr) -> NetworkAddress {
+    NetworkAddress::from(Protocol::from(socketaddr.ip())).push(Protocol::Quic(socketaddr.port()))
+}
+
+fn invalid_addr_error(addr: &NetworkAddress) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Invalid NetworkAddress: '{}'", addr),
+    )
+}
+
+fn other_error(err: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
+
+/// Accepts any server certificate. Listener certificates are throwaway: the identity of the
+/// listener is checked by the Noise IK upgrade, which also covers the TLS exporter of the
+/// connection.
+struct AcceptAnyServerCert;
+
+impl rustls::ServerCertVerifier for AcceptAnyServerCert {
+    fn verify_server_cert(
+        &self,
+        _roots: &rustls::RootCertStore,
+        _presented_certs: &[rustls::Certificate],
+        _dns_name: webpki::DNSNameRef,
+        _ocsp_response: &[u8],
+    ) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
+        Ok(rustls::ServerCertVerified::assertion())
+    }
+}
+
+/// Exporter master secrets of the TLS sessions of a config, by client random, until the sessions
+/// are dropped. Rustls reports them to the key log of the config when the handshake completes.
+#[derive(Default)]
+struct ExporterSecrets(Mutex<HashMap<[u8; CLIENT_RANDOM_SIZE], Vec<u8>>>);
+
+impl ExporterSecrets {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<[u8; CLIENT_RANDOM_SIZE], Vec<u8>>> {
+        self.0.lock().expect("Exporter secrets lock is poisoned")
+    }
+}
+
+impl rustls::KeyLog for ExporterSecrets {
+    fn will_log(&self, label: &str) -> bool {
+        label == EXPORTER_SECRET_LABEL
+    }
+
+    fn log(&self, label: &str, client_random: &[u8], secret: &[u8]) {
+        if label != EXPORTER_SECRET_LABEL || client_random.len() != CLIENT_RANDOM_SIZE {
+            return;
+        }
+        let mut random = [0; CLIENT_RANDOM_SIZE];
+        random.copy_from_slice(client_random);
+        self.lock().insert(random, secret.to_vec());
+    }
+}
+
+/// TLS 1.3 cipher suites hashing with SHA-256, which `tls13_exporter` is defined for.
+fn sha256_cipher_suites() -> Vec<&'static rustls::SupportedCipherSuite> {
+    rustls::ALL_CIPHERSUITES
+        .iter()
+        .copied()
+        .filter(|suite| {
+            suite.suite == rustls::CipherSuite::TLS13_AES_128_GCM_SHA256
+                || suite.suite == rustls::CipherSuite::TLS13_CHACHA20_POLY1305_SHA256
+        })
+        .collect()
+}
+
+/// HKDF-Expand-Label of RFC 8446 section 7.1, with SHA-256.
+fn hkdf_expand_label(
+    secret: &[u8],
+    label: &[u8],
+    context: &[u8],
+    length: usize,
+) -> Option<Vec<u8>> {
+    const LABEL_PREFIX: &[u8] = b"tls13 ";
+    let mut info = Vec::with_capacity(4 + LABEL_PREFIX.len() + label.len() + context.len());
+    info.extend_from_slice(&(length as u16).to_be_bytes());
+    info.push((LABEL_PREFIX.len() + label.len()) as u8);
+    info.extend_from_slice(LABEL_PREFIX);
+    info.extend_from_slice(label);
+    info.push(context.len() as u8);
+    info.extend_from_slice(context);
+    Hkdf::<Sha256>::expand(secret, Some(&info), length).ok()
+}
+
+/// TLS-Exporter of RFC 8446 section 7.5 with an empty context, for the SHA-256 cipher suites.
+fn tls13_exporter(exporter_master_secret: &[u8], label: &[u8], length: usize) -> Option<Vec<u8>> {
+    let empty_hash = Sha256::digest(&[]);
+    let secret = hkdf_expand_label(exporter_master_secret, label, &empty_hash, empty_hash.len())?;
+    hkdf_expand_label(&secret, b"exporter", &empty_hash, length)
+}
+
+/// The rustls session of quinn, whose authentication data is the TLS exporter of the connection.
+/// Quinn doesn't expose the TLS session of a connection otherwise.
+struct ExporterTlsSession {
+    session: TlsSession,
+    secrets: Arc<ExporterSecrets>,
+    /// Start of the ClientHello, sent by dialers and received by listeners, until it holds the
+    /// client random identifying the session in the key log.
+    client_hello: Vec<u8>,
+}
+
+impl ExporterTlsSession {
+    fn new(session: TlsSession, secrets: Arc<ExporterSecrets>) -> Self {
+        Self {
+            session,
+            secrets,
+            client_hello: Vec::with_capacity(CLIENT_RANDOM_OFFSET + CLIENT_RANDOM_SIZE),
+        }
+    }
+
+    fn record_client_hello(&mut self, data: &[u8]) {
+        let missing = self.client_hello.capacity() - self.client_hello.len();
+        self.client_hello
+            .extend_from_slice(&data[..std::cmp::min(missing, data.len())]);
+    }
+
+    fn client_random(&self) -> Option<[u8; CLIENT_RANDOM_SIZE]> {
+        if self.client_hello.len() < CLIENT_RANDOM_OFFSET + CLIENT_RANDOM_SIZE {
+            return None;
+        }
+        let mut random = [0; CLIENT_RANDOM_SIZE];
+        random.copy_from_slice(&self.client_hello[CLIENT_RANDOM_OFFSET..]);
+        Some(random)
+    }
+}
+
+impl Drop for ExporterTlsSession {
+    fn drop(&mut self) {
+        if let Some(random) = self.client_random() {
+            self.secrets.lock().remove(&random);
+        }
+    }
+}
+
+/// Rustls config of the dialers, starting `ExporterTlsSession`s.
+#[derive(Clone)]
+struct ExporterClientConfig {
+    tls: Arc<rustls::ClientConfig>,
+    secrets: Arc<ExporterSecrets>,
+}
+
+/// Rustls config of the listeners, starting `ExporterTlsSession`s.
+#[derive(Clone)]
+struct ExporterServerConfig {
+    tls: Arc<rustls::ServerConfig>,
+    secrets: Arc<ExporterSecrets>,
+}
+
+impl crypto::Session for ExporterTlsSession {
+    /// The TLS exporter of the connection, or `None` while the handshake is in progress.
+    type AuthenticationData = Option<Vec<u8>>;
+    type ClientConfig = ExporterClientConfig;
+    type HmacKey = <TlsSession as crypto::Session>::HmacKey;
+    type Keys = <TlsSession as crypto::Session>::Keys;
+    type ServerConfig = ExporterServerConfig;
+
+    fn authentication_data(&self) -> Self::AuthenticationData {
+        let random = self.client_random()?;
+        let secrets = self.secrets.lock();
+        tls13_exporter(secrets.get(&random)?, EXPORTER_LABEL, EXPORTER_SIZE)
+    }
+
+    fn early_crypto(&self) -> Option<Self::Keys> {
+        self.session.early_crypto()
+    }
+
+    fn early_data_accepted(&self) -> Option<bool> {
+        self.session.early_data_accepted()
+    }
+
+    fn is_handshaking(&self) -> bool {
+        crypto::Session::is_handshaking(&self.session)
+    }
+
+    fn read_handshake(&mut self, buf: &[u8]) -> Result<(), TransportError> {
+        if let TlsSession::Server(_) = self.session {
+            self.record_client_hello(buf);
+        }
+        self.session.read_handshake(buf)
+    }
+
+    fn transport_parameters(&self) -> Result<Option<TransportParameters>, TransportError> {
+        self.session.transport_parameters()
+    }
+
+    fn write_handshake(&mut self, buf: &mut Vec<u8>) -> Option<Self::Keys> {
+        let start = buf.len();
+        let keys = self.session.write_handshake(buf);
+        if let TlsSession::Client(_) = self.session {
+            self.record_client_hello(&buf[start..]);
+        }
+        keys
+    }
+
+    fn update_keys(&self, keys: &Self::Keys) -> Self::Keys {
+        self.session.update_keys(keys)
+    }
+
+    fn retry_tag(orig_dst_cid: &ConnectionId, packet: &[u8]) -> [u8; 16] {
+        TlsSession::retry_tag(orig_dst_cid, packet)
+    }
+
+    fn is_valid_retry(orig_dst_cid: &ConnectionId, header: &[u8], payload: &[u8]) -> bool {
+        TlsSession::is_valid_retry(orig_dst_cid, header, payload)
+    }
+}
+
+impl crypto::ClientConfig<ExporterTlsSession> for ExporterClientConfig {
+    fn new() -> Self {
+        let mut tls = <Arc<rustls::ClientConfig> as crypto::ClientConfig<TlsSession>>::new();
+        let secrets = Arc::new(ExporterSecrets::default());
+        let config = Arc::make_mut(&mut tls);
+        config.ciphersuites = sha256_cipher_suites();
+        config.key_log = secrets.clone();
+        Self { tls, secrets }
+    }
+
+    fn start_session(
+        &self,
+        server_name: &str,
+        params: &TransportParameters,
+    ) -> Result<ExporterTlsSession, ConnectError> {
+        crypto::ClientConfig::start_session(&self.tls, server_name, params)
+            .map(|session| ExporterTlsSession::new(session, self.secrets.clone()))
+    }
+}
+
+impl crypto::ServerConfig<ExporterTlsSession> for ExporterServerConfig {
+    fn new() -> Self {
+        let mut tls = <Arc<rustls::ServerConfig> as crypto::ServerConfig<TlsSession>>::new();
+        let secrets = Arc::new(ExporterSecrets::default());
+        let config = Arc::make_mut(&mut tls);
+        config.ciphersuites = sha256_cipher_suites();
+        config.key_log = secrets.clone();
+        Self { tls, secrets }
+    }
+
+    fn start_session(&self, params: &TransportParameters) -> ExporterTlsSession {
+        ExporterTlsSession::new(
+            crypto::ServerConfig::start_session(&self.tls, params),
+            self.secrets.clone(),
+        )
+    }
+}
+
+/// The stream of a QUIC connection
+///
+/// The connection handle is kept along with the stream, since dropping the last handle of a QUIC
+/// connection closes it. Closing the socket finishes the send half of the stream.
+pub struct QuicSocket {
+    connection: Connection,
+    send: IoCompat<SendStream>,
+    recv: IoCompat<RecvStream>,
+    /// Streams opened by the remote to send messages, until taken.
+    uni_streams: Option<IncomingUniStreams>,
+    /// TLS exporter of the connection, its channel binding.
+    exporter: Vec<u8>,
+    /// Whether the remote confirmed the channel binding.
+    binding_confirmed: bool,
+}
+
+impl QuicSocket {
+    fn new(
+        connection: Connection,
+        send: SendStream,
+        recv: RecvStream,
+        uni_streams: IncomingUniStreams,
+    ) -> io::Result<Self> {
+        let exporter = connection
+            .authentication_data()
+            .ok_or_else(|| other_error("No TLS exporter for the QUIC connection"))?;
+        Ok(Self {
+            connection,
+            send: IoCompat::new(send),
+            recv: IoCompat::new(recv),
+            uni_streams: Some(uni_streams),
+            exporter,
+            binding_confirmed: false,
+        })
+    }
+}
+
+impl fmt::Debug for QuicSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QuicSocket")
+            .field("remote_address", &self.connection.remote_address())
+            .field("binding_confirmed", &self.binding_confirmed)
+            .finish()
+    }
+}
+
+impl MessageStreams for QuicSocket {
+    fn channel_binding(&self) -> Option<Vec<u8>> {
+        Some(self.exporter.clone())
+    }
+
+    fn confirm_channel_binding(&mut self) {
+        self.binding_confirmed = true;
+    }
+
+    fn take_message_streams(
+        &mut self,
+        max_message_size: usize,
+    ) -> Option<(MessageReader, MessageWriter)> {
+        if !self.binding_confirmed {
+            return None;
+        }
+        let reader = MessageReceiver {
+            uni_streams: self.uni_streams.take()?.fuse(),
+            reads: FuturesUnordered::new(),
+            max_message_size,
+        };
+        let writer = MessageSender {
+            connection: self.connection.clone(),
+            writes: FuturesUnordered::new(),
+        };
+        Some((reader.boxed(), Box::pin(writer)))
+    }
+}
+
+impl AsyncRead for QuicSocket {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.recv).poll_read(context, buf)
+    }
+}
+
+impl AsyncWrite for QuicSocket {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        context: &mut Context,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.send).poll_write(context, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_flush(context)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.send).poll_close(context)
+    }
+}
+
+/// Reads inbound messages, each from a stream opened by the remote, in the order they complete.
+struct MessageReceiver {
+    uni_streams: futures::stream::Fuse<IncomingUniStreams>,
+    reads: FuturesUnordered<Pin<Box<dyn Future<Output = io::Result<BytesMut>> + Send>>>,
+    max_message_size: usize,
+}
+
+async fn read_message(recv: RecvStream, max_message_size: usize) -> io::Result<BytesMut> {
+    let data = recv
+        .read_to_end(max_message_size)
+        .await
+        .map_err(other_error)?;
+    Ok(BytesMut::from(&data[..]))
+}
+
+impl Stream for MessageReceiver {
+    type Item = io::Result<BytesMut>;
+
+    fn poll_next(self: Pin<&mut Self>, context: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while this.reads.len() < MAX_INBOUND_MESSAGE_STREAMS {
+            match this.uni_streams.poll_next_unpin(context) {
+                Poll::Ready(Some(Ok(recv))) => this
+                    .reads
+                    .push(read_message(recv, this.max_message_size).boxed()),
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err.into()))),
+                Poll::Ready(None) | Poll::Pending => break,
+            }
+        }
+        match this.reads.poll_next_unpin(context) {
+            Poll::Ready(Some(message)) => Poll::Ready(Some(message)),
+            // Done once the remote can't open streams anymore and all reads completed.
+            Poll::Ready(None) if this.uni_streams.is_terminated() => Poll::Ready(None),
+            _ => Poll::Pending,
+        }
+    }
+}
+
+/// Writes outbound messages, each on a new stream. A message is sent once handed to its stream,
+/// without waiting for the previous messages to be acknowledged.
+struct MessageSender {
+    connection: Connection,
+    writes: FuturesUnordered<JoinHandle<io::Result<()>>>,
+}
+
+async fn write_message(connection: Connection, message: Bytes) -> io::Result<()> {
+    let mut send = connection.open_uni().await.map_err(io::Error::from)?;
+    send.write_all(&message).await.map_err(io::Error::from)?;
+    send.finish().await.map_err(io::Error::from)
+}
+
+impl MessageSender {
+    /// Polls the completed writes, waiting for some while more than `max_pending` are left.
+    fn poll_writes(&mut self, context: &mut Context, max_pending: usize) -> Poll<io::Result<()>> {
+        while !self.writes.is_empty() {
+            match self.writes.poll_next_unpin(context) {
+                Poll::Ready(Some(result)) => result.map_err(other_error)??,
+                Poll::Ready(None) => break,
+                Poll::Pending if self.writes.len() > max_pending => return Poll::Pending,
+                Poll::Pending => break,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Sink<Bytes> for MessageSender {
+    type Error = io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        self.get_mut()
+            .poll_writes(context, MAX_OUTBOUND_MESSAGE_STREAMS - 1)
+    }
+
+    fn start_send(self: Pin<&mut Self>, message: Bytes) -> io::Result<()> {
+        let this = self.get_mut();
+        let write = tokio::spawn(write_message(this.connection.clone(), message));
+        this.writes.push(write);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        // Messages are on their way once handed to their streams.
+        self.get_mut()
+            .poll_writes(context, MAX_OUTBOUND_MESSAGE_STREAMS)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, context: &mut Context) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_writes(context, 0))?;
+        this.connection.close(quinn::VarInt::from_u32(0), b"");
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transport::{ConnectionOrigin, Transport, TransportExt};
+    use futures::{
+        future::{join, join3},
+        io::{AsyncReadExt, AsyncWriteExt},
+        sink::SinkExt,
+    };
+
+    #[tokio::test]
+    async fn simple_listen_and_dial() -> Result<(), ::std::io::Error> {
+        let t = QuicTransport::default().and_then(|mut out, _addr, origin| async move {
+            match origin {
+                ConnectionOrigin::Inbound => {
+                    out.write_all(b"Earth").await?;
+                    let mut buf = [0; 3];
+                    out.read_exact(&mut buf).await?;
+                    assert_eq!(&buf, b"Air");
+                }
+                ConnectionOrigin::Outbound => {
+                    let mut buf = [0; 5];
+                    out.read_exact(&mut buf).await?;
+                    assert_eq!(&buf, b"Earth");
+                    out.write_all(b"Air").await?;
+                    // Dropping the socket closes the connection, wait for the data to be
+                    // acknowledged first.
+                    out.close().await?;
+                }
+            }
+            Ok(())
+        });
+
+        let (listener, addr) = t.listen_on("/ip4/127.0.0.1/quic/0".parse().unwrap())?;
+        assert!(parse_ip_quic(addr.as_slice()).is_some(), "addr: {}", addr);
+        let peer_id = PeerId::random();
+        let dial = t.dial(peer_id, addr)?;
+        let listener = listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming.map(Result::unwrap)
+        });
+
+        let (outgoing, _incoming) = join(dial, listener).await;
+        assert!(outgoing.is_ok());
+        Ok(())
+    }
+
+    /// Returns the sockets at both ends of a new connection, dialer first.
+    async fn connect_pair(
+        listener: &QuicTransport,
+        dialer: &QuicTransport,
+    ) -> (QuicSocket, QuicSocket) {
+        let (listener, addr) = listener
+            .listen_on("/ip4/127.0.0.1/quic/0".parse().unwrap())
+            .unwrap();
+        let dial = dialer.dial(PeerId::random(), addr).unwrap();
+        let accept = listener.into_future().then(|(maybe_result, _stream)| {
+            let (incoming, _addr) = maybe_result.unwrap().unwrap();
+            incoming.map(Result::unwrap)
+        });
+        let (outbound, inbound) = join(dial, accept).await;
+        (outbound.unwrap(), inbound)
+    }
+
+    #[tokio::test]
+    async fn message_streams() {
+        let (listener, dialer) = (QuicTransport::default(), QuicTransport::default());
+        let (mut outbound, mut inbound) = connect_pair(&listener, &dialer).await;
+
+        // Both ends derive the same TLS exporter.
+        assert!(outbound.channel_binding().is_some());
+        assert_eq!(outbound.channel_binding(), inbound.channel_binding());
+
+        // Message streams are only available once the channel binding is confirmed.
+        assert!(outbound.take_message_streams(1 << 20).is_none());
+        outbound.confirm_channel_binding();
+        inbound.confirm_channel_binding();
+        let (_, mut writer) = outbound.take_message_streams(1 << 20).unwrap();
+        let (mut reader, _) = inbound.take_message_streams(1 << 20).unwrap();
+        assert!(outbound.take_message_streams(1 << 20).is_none());
+
+        let messages: Vec<Vec<u8>> = vec![vec![1; 1 << 19], vec![2; 10], vec![3; 100]];
+        for message in &messages {
+            writer.send(Bytes::from(message.clone())).await.unwrap();
+        }
+        let mut received = vec![];
+        for _ in 0..messages.len() {
+            received.push(reader.next().await.unwrap().unwrap().to_vec());
+        }
+        // Messages may complete in any order.
+        received.sort();
+        assert_eq!(received, messages);
+
+        // Closing the writer closes the connection.
+        writer.close().await.unwrap();
+        assert!(reader.next().await.map_or(true, |result| result.is_err()));
+    }
+
+    #[tokio::test]
+    async fn relay_reusing_listener_certificate() {
+        // Even presenting the certificate of the listener, a relay terminates TLS sessions of its
+        // own on both sides, so the dialer and the listener end up with different channel
+        // bindings, and the Noise handshake covering them fails.
+        let cert = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()]).unwrap();
+        let (listener, relay, dialer) = (
+            QuicTransport::default(),
+            QuicTransport::default(),
+            QuicTransport::default(),
+        );
+        let (listener_incoming, listener_addr) = listener
+            .listen_with_cert("/ip4/127.0.0.1/quic/0".parse().unwrap(), &cert)
+            .unwrap();
+        let (relay_incoming, relay_addr) = relay
+            .listen_with_cert("/ip4/127.0.0.1/quic/0".parse().unwrap(), &cert)
+            .unwrap();
+
+        let dial = dialer.dial(PeerId::random(), relay_addr).unwrap();
+        let relay_connections = async move {
+            let (maybe_inbound, _relay_incoming) = relay_incoming.into_future().await;
+            let (accept, _addr) = maybe_inbound.unwrap().unwrap();
+            let inbound = accept.await.unwrap();
+            let outbound = relay
+                .dial(PeerId::random(), listener_addr)
+                .unwrap()
+                .await
+                .unwrap();
+            (inbound, outbound)
+        };
+        let accept = listener_incoming
+            .into_future()
+            .then(|(maybe_result, _stream)| {
+                let (incoming, _addr) = maybe_result.unwrap().unwrap();
+                incoming.map(Result::unwrap)
+            });
+        let (dialer_socket, (relay_inbound, relay_outbound), listener_socket) =
+            join3(dial, relay_connections, accept).await;
+        let dialer_socket = dialer_socket.unwrap();
+
+        assert_eq!(
+            dialer_socket.channel_binding(),
+            relay_inbound.channel_binding()
+        );
+        assert_eq!(
+            relay_outbound.channel_binding(),
+            listener_socket.channel_binding()
+        );
+        assert_ne!(
+            dialer_socket.channel_binding(),
+            listener_socket.channel_binding()
+        );
+    }
+
+    #[tokio::test]
+    async fn dials_from_listening_endpoint() {
+        let dialer = QuicTransport::default();
+        let (_dialer_listener, dialer_addr) = dialer
+            .listen_on("/ip4/127.0.0.1/quic/0".parse().unwrap())
+            .unwrap();
+        let listener = QuicTransport::default();
+        let (incoming, addr) = listener
+            .listen_on("/ip4/127.0.0.1/quic/0".parse().unwrap())
+            .unwrap();
+
+        // Dials go out from the listening port.
+        let dial = dialer.dial(PeerId::random(), addr).unwrap();
+        let (_outbound, (maybe_inbound, _incoming)) = join(dial, incoming.into_future()).await;
+        let (_inbound, remote_addr) = maybe_inbound.unwrap().unwrap();
+        assert_eq!(remote_addr, dialer_addr);
+    }
+
+    #[test]
+    fn tls13_exporter_matches_rustls() {
+        use rustls::Session;
+
+        // Rustls exports keying material from TLS sessions which don't carry QUIC, check the
+        // exporter derived from the key log against it.
+        let cert = rcgen::generate_simple_self_signed(vec![SERVER_NAME.to_string()]).unwrap();
+        let secrets = Arc::new(ExporterSecrets::default());
+        let mut server_config = rustls::ServerConfig::new(rustls::NoClientAuth::new());
+        server_config.versions = vec![rustls::ProtocolVersion::TLSv1_3];
+        server_config.ciphersuites = sha256_cipher_suites();
+        server_config.key_log = secrets.clone();
+        server_config
+            .set_single_cert(
+                vec![rustls::Certificate(cert.serialize_der().unwrap())],
+                rustls::PrivateKey(cert.serialize_private_key_der()),
+            )
+            .unwrap();
+        let mut client_config = rustls::ClientConfig::new();
+        client_config.versions = vec![rustls::ProtocolVersion::TLSv1_3];
+        client_config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(AcceptAnyServerCert));
+        let mut client = rustls::ClientSession::new(
+            &Arc::new(client_config),
+            webpki::DNSNameRef::try_from_ascii_str(SERVER_NAME).unwrap(),
+        );
+        let mut server = rustls::ServerSession::new(&Arc::new(server_config));
+
+        fn transfer(from: &mut dyn Session, to: &mut dyn Session) {
+            let mut records = vec![];
+            while from.wants_write() {
+                from.write_tls(&mut records).unwrap();
+            }
+            let mut records = &records[..];
+            while !records.is_empty() {
+                to.read_tls(&mut records).unwrap();
+                to.process_new_packets().unwrap();
+            }
+        }
+        while client.is_handshaking() || server.is_handshaking() {
+            transfer(&mut client, &mut server);
+            transfer(&mut server, &mut client);
+        }
+
+        let mut expected = [0; EXPORTER_SIZE];
+        client
+            .export_keying_material(&mut expected, EXPORTER_LABEL, None)
+            .unwrap();
+        let secrets = secrets.lock();
+        assert_eq!(secrets.len(), 1);
+        let exporter_master_secret = secrets.values().next().unwrap();
+        assert_eq!(
+            tls13_exporter(exporter_master_secret, EXPORTER_LABEL, EXPORTER_SIZE),
+            Some(expected.to_vec())
+        );
+    }
+
+    #[test]
+    fn unsupported_multiaddrs() {
+        let t = QuicTransport::default();
+
+        let result = t.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap());
+        assert!(result.is_err());
+
+        let peer_id = PeerId::random();
+        let result = t.dial(peer_id, "/ip4/127.0.0.1/tcp/22".parse().unwrap());
+        assert!(result.is_err());
+        let result = t.dial(peer_id, "/memory/22".parse().unwrap());
+        assert!(result.is_err());
+    }
+}
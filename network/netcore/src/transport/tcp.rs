@@ -2,7 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 //! TCP Transport
-use crate::{compat::IoCompat, transport::Transport};
+use crate::{
+    compat::IoCompat,
+    transport::{MessageStreams, Transport},
+};
 use futures::{
     future::{self, Future},
     io::{AsyncRead, AsyncWrite},
@@ -116,7 +119,7 @@ impl Transport for TcpTransport {
 }
 
 /// Try to lookup the dns name, then filter addrs according to the `IpFilter`.
-fn resolve_with_filter<'a>(
+pub(crate) fn resolve_with_filter<'a>(
     ip_filter: IpFilter,
     dns_name: &'a str,
     port: u16,
@@ -239,6 +242,8 @@ impl TcpSocket {
     }
 }
 
+impl MessageStreams for TcpSocket {}
+
 impl AsyncRead for TcpSocket {
     fn poll_read(
         mut self: Pin<&mut Self>,
@@ -143,6 +143,7 @@ pub enum Protocol {
     // probably need to move network wire into its own crate to avoid circular
     // dependency b/w network and types.
     Handshake(u8),
+    Quic(u16),
 }
 
 /// A minimally parsed DNS name. We don't really do any checking other than
@@ -545,6 +546,12 @@ pub fn arb_libranet_addr() -> impl Strategy<Value = NetworkAddress> {
             .prop_map(|(name, port)| vec![Protocol::Dns4(name), Protocol::Tcp(port)]),
         any::<(DnsName, u16)>()
             .prop_map(|(name, port)| vec![Protocol::Dns6(name), Protocol::Tcp(port)]),
+        any::<(Ipv4Addr, u16)>()
+            .prop_map(|(addr, port)| vec![Protocol::Ip4(addr), Protocol::Quic(port)]),
+        any::<(Ipv6Addr, u16)>()
+            .prop_map(|(addr, port)| vec![Protocol::Ip6(addr), Protocol::Quic(port)]),
+        any::<(DnsName, u16)>()
+            .prop_map(|(name, port)| vec![Protocol::Dns(name), Protocol::Quic(port)]),
     ];
     let arb_libranet_protos = any::<(x25519::PublicKey, u8)>()
         .prop_map(|(pubkey, hs)| vec![Protocol::NoiseIK(pubkey), Protocol::Handshake(hs)]);
@@ -580,6 +587,7 @@ impl fmt::Display for Protocol {
                     .expect("ValidCryptoMaterialStringExt::to_encoded_string is infallible")
             ),
             Handshake(version) => write!(f, "/ln-handshake/{}", version),
+            Quic(port) => write!(f, "/quic/{}", port),
         }
     }
 }
@@ -610,6 +618,7 @@ impl Protocol {
                 args.next().ok_or(ParseError::UnexpectedEnd)?,
             )?),
             "ln-handshake" => Protocol::Handshake(parse_one(args)?),
+            "quic" => Protocol::Quic(parse_one(args)?),
             unknown => return Err(ParseError::UnknownProtocolType(unknown.to_string())),
         };
         Ok(protocol)
@@ -784,6 +793,42 @@ pub fn parse_dns_tcp(protos: &[Protocol]) -> Option<((IpFilter, &DnsName, u16),
     }
 }
 
+/// parse the `&[Protocol]` into the `"/ip4/<addr>/quic/<port>"` or
+/// `"/ip6/<addr>/quic/<port>"` prefix and unparsed `&[Protocol]` suffix.
+pub fn parse_ip_quic(protos: &[Protocol]) -> Option<((IpAddr, u16), &[Protocol])> {
+    use Protocol::*;
+
+    if protos.len() < 2 {
+        return None;
+    }
+
+    let (prefix, suffix) = protos.split_at(2);
+    match prefix {
+        [Ip4(ip), Quic(port)] => Some(((IpAddr::V4(*ip), *port), suffix)),
+        [Ip6(ip), Quic(port)] => Some(((IpAddr::V6(*ip), *port), suffix)),
+        _ => None,
+    }
+}
+
+/// parse the `&[Protocol]` into the `"/dns/<domain>/quic/<port>"`,
+/// `"/dns4/<domain>/quic/<port>"`, or `"/dns6/<domain>/quic/<port>"` prefix and
+/// unparsed `&[Protocol]` suffix.
+pub fn parse_dns_quic(protos: &[Protocol]) -> Option<((IpFilter, &DnsName, u16), &[Protocol])> {
+    use Protocol::*;
+
+    if protos.len() < 2 {
+        return None;
+    }
+
+    let (prefix, suffix) = protos.split_at(2);
+    match prefix {
+        [Dns(name), Quic(port)] => Some(((IpFilter::Any, name, *port), suffix)),
+        [Dns4(name), Quic(port)] => Some(((IpFilter::OnlyIp4, name, *port), suffix)),
+        [Dns6(name), Quic(port)] => Some(((IpFilter::OnlyIp6, name, *port), suffix)),
+        _ => None,
+    }
+}
+
 /// parse the `&[Protocol]` into the `"/ln-noise-ik/<pubkey>"` prefix and
 /// unparsed `&[Protocol]` suffix.
 pub fn parse_noise_ik(protos: &[Protocol]) -> Option<(&x25519::PublicKey, &[Protocol])> {
@@ -810,11 +855,15 @@ fn parse_libranet_protos(protos: &[Protocol]) -> Option<&[Protocol]> {
     // ---
     // parse_ip_tcp
     // <or> parse_dns_tcp
+    // <or> parse_ip_quic
+    // <or> parse_dns_quic
     // <or> cfg!(test) parse_memory
 
     let transport_suffix = parse_ip_tcp(protos)
         .map(|x| x.1)
         .or_else(|| parse_dns_tcp(protos).map(|x| x.1))
+        .or_else(|| parse_ip_quic(protos).map(|x| x.1))
+        .or_else(|| parse_dns_quic(protos).map(|x| x.1))
         .or_else(|| {
             if cfg!(test) {
                 parse_memory(protos).map(|x| x.1)
@@ -898,6 +947,10 @@ mod test {
                 "/dns/example.com/tcp/80",
                 vec![Dns(DnsName("example.com".to_owned())), Tcp(80)],
             ),
+            (
+                "/ip4/12.34.56.78/quic/1234",
+                vec![Ip4(Ipv4Addr::new(12, 34, 56, 78)), Quic(1234)],
+            ),
             (
                 &noise_addr_str,
                 vec![
@@ -1027,6 +1080,29 @@ mod test {
         assert_eq!(None, parse_dns_tcp(addr.as_slice()));
     }
 
+    #[test]
+    fn test_parse_quic() {
+        let addr = NetworkAddress::from_str("/ip6/::1/quic/123/ln-handshake/0").unwrap();
+        let expected_suffix: &[Protocol] = &[Protocol::Handshake(0)];
+        assert_eq!(
+            parse_ip_quic(addr.as_slice()).unwrap(),
+            ((IpAddr::V6(Ipv6Addr::LOCALHOST), 123), expected_suffix)
+        );
+        assert_eq!(None, parse_ip_tcp(addr.as_slice()));
+
+        let dns_name = DnsName::from_str("example.com").unwrap();
+        let addr = NetworkAddress::from_str("/dns4/example.com/quic/123").unwrap();
+        let expected_suffix: &[Protocol] = &[];
+        assert_eq!(
+            parse_dns_quic(addr.as_slice()).unwrap(),
+            ((IpFilter::OnlyIp4, &dns_name, 123), expected_suffix)
+        );
+        assert_eq!(None, parse_dns_tcp(addr.as_slice()));
+
+        let addr = NetworkAddress::from_str("/ip4/1.2.3.4/tcp/123").unwrap();
+        assert_eq!(None, parse_ip_quic(addr.as_slice()));
+    }
+
     #[test]
     fn test_parse_noise_ik() {
         let pubkey_str = "080e287879c918794170e258bfaddd75acac5b3e350419044655e4983a487120";
@@ -4,7 +4,7 @@
 use anyhow::{format_err, Context, Result};
 use channel::libra_channel::{self, Receiver};
 use futures::{sink::SinkExt, StreamExt};
-use libra_config::config::RoleType;
+use libra_config::config::{IdentityFromStorage, RoleType};
 use libra_crypto::x25519;
 use libra_logger::prelude::*;
 use libra_metrics::{register_histogram, DurationHistogram};
@@ -12,14 +12,21 @@ use libra_network_address::{
     encrypted::{EncNetworkAddress, Key, KeyVersion, RawEncNetworkAddress},
     NetworkAddress, RawNetworkAddress,
 };
-use libra_types::on_chain_config::{OnChainConfigPayload, ValidatorSet, ON_CHAIN_CONFIG_REGISTRY};
+use libra_types::{
+    on_chain_config::{OnChainConfigPayload, ValidatorSet, ON_CHAIN_CONFIG_REGISTRY},
+    PeerId,
+};
 use move_core_types::account_address::AccountAddress;
-use network::connectivity_manager::{ConnectivityRequest, DiscoverySource};
+use network::{
+    connectivity_manager::{ConnectivityRequest, DiscoverySource},
+    IdentityKeys,
+};
 use once_cell::sync::Lazy;
 use std::{
     collections::{HashMap, HashSet},
     convert::TryFrom,
-    time::Instant,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 use subscription_service::ReconfigSubscription;
 
@@ -54,6 +61,73 @@ pub struct ConfigurationChangeListener {
     shared_val_netaddr_key_map: HashMap<KeyVersion, Key>,
     conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
     reconfig_events: libra_channel::Receiver<(), OnChainConfigPayload>,
+    key_rotation: Option<KeyRotation>,
+}
+
+/// Rotates the identity key of this node once a new key is published on-chain, e.g. by the
+/// key-manager. The previous key stays usable for `grace`, while peers learn about the new one.
+pub struct KeyRotation {
+    peer_id: PeerId,
+    identity_keys: Arc<IdentityKeys>,
+    /// Where the latest private key is read from.
+    identity: IdentityFromStorage,
+    grace: Duration,
+}
+
+impl KeyRotation {
+    pub fn new(
+        peer_id: PeerId,
+        identity_keys: Arc<IdentityKeys>,
+        identity: IdentityFromStorage,
+        grace: Duration,
+    ) -> Self {
+        Self {
+            peer_id,
+            identity_keys,
+            identity,
+            grace,
+        }
+    }
+
+    /// Switches to the key published on-chain for this node, if it changed and its private key
+    /// is available. Returns whether the key was rotated.
+    fn update(&self, role: RoleType, node_set: &ValidatorSet) -> bool {
+        let onchain_pubkey = match node_set
+            .payload()
+            .iter()
+            .find(|info| *info.account_address() == self.peer_id)
+        {
+            Some(info) => match role {
+                RoleType::Validator => info.config().validator_network_identity_public_key,
+                RoleType::FullNode => info.config().full_node_network_identity_public_key,
+            },
+            None => return false,
+        };
+        if onchain_pubkey == self.identity_keys.public_key() {
+            return false;
+        }
+
+        // The key is published on-chain once it is in storage, so a mismatch means the on-chain
+        // key isn't ours (yet); we'll check again on the next reconfiguration.
+        match self.identity.private_key() {
+            Ok(key) if key.public_key() == onchain_pubkey => {
+                self.identity_keys.rotate(key, self.grace);
+                true
+            }
+            Ok(_) => {
+                warn!(
+                    "On-chain identity key {} of {} doesn't match the key in storage",
+                    onchain_pubkey,
+                    self.peer_id.short_str()
+                );
+                false
+            }
+            Err(err) => {
+                warn!("Failed to read the rotated identity key: {:?}", err);
+                false
+            }
+        }
+    }
 }
 
 pub fn gen_simple_discovery_reconfig_subscription(
@@ -179,12 +253,14 @@ impl ConfigurationChangeListener {
         shared_val_netaddr_key_map: HashMap<KeyVersion, Key>,
         conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
         reconfig_events: libra_channel::Receiver<(), OnChainConfigPayload>,
+        key_rotation: Option<KeyRotation>,
     ) -> Self {
         Self {
             role,
             shared_val_netaddr_key_map,
             conn_mgr_reqs_tx,
             reconfig_events,
+            key_rotation,
         }
     }
 
@@ -195,6 +271,16 @@ impl ConfigurationChangeListener {
             .get()
             .expect("failed to get ValidatorSet from payload");
 
+        if let Some(key_rotation) = &self.key_rotation {
+            if key_rotation.update(self.role, &node_set) {
+                info!(
+                    "Rotated {} Network identity key to {}",
+                    self.role.to_string(),
+                    key_rotation.identity_keys.public_key()
+                );
+            }
+        }
+
         let updates = extract_updates(self.role, &self.shared_val_netaddr_key_map, node_set);
 
         info!(
@@ -0,0 +1,249 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Capture of the messages exchanged with peers, to reproduce bugs offline.
+//!
+//! When capture is enabled in the [`CaptureConfig`], the Peer actors hand every inbound and
+//! outbound [`NetworkMessage`] to the [`MessageRecorder`] of the network. A dedicated thread
+//! appends them to the capture files: each record is the LCS encoding of a [`CapturedMessage`],
+//! prefixed with its length as a big-endian u32. Capture files are named `<index>.capture`,
+//! and a new file is started once the current one exceeds the configured size.
+//!
+//! Recording never holds up the network: messages are dropped, and counted as such, when the
+//! recorder can't keep up.
+//!
+//! Captures are read back with [`read_capture`], and fed to a node with [`replay::Replayer`].
+
+use crate::{counters, protocols::wire::messaging::v1::NetworkMessage, ProtocolId};
+use anyhow::{ensure, Result};
+use libra_config::config::CaptureConfig;
+use libra_logger::prelude::*;
+use libra_types::PeerId;
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryInto,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub mod replay;
+#[cfg(test)]
+mod test;
+
+/// Extension of capture files.
+pub const CAPTURE_FILE_EXTENSION: &str = "capture";
+
+/// Max number of messages waiting to be written.
+const RECORDER_QUEUE_SIZE: usize = 4096;
+
+/// Size of the length prefix of records.
+const LENGTH_PREFIX_SIZE: usize = 4;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+impl Direction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Inbound => counters::INBOUND_LABEL,
+            Direction::Outbound => counters::OUTBOUND_LABEL,
+        }
+    }
+}
+
+/// A message sent to or received from a peer.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct CapturedMessage {
+    /// Time the message was handed to or by the Peer actor, in microseconds since the unix epoch.
+    pub timestamp_usecs: u64,
+    pub peer_id: PeerId,
+    pub direction: Direction,
+    /// Application protocol of the message. Unknown for RPC responses, pings and pongs.
+    pub protocol: Option<ProtocolId>,
+    pub message: NetworkMessage,
+}
+
+/// Handle to the thread writing the capture files of a network.
+#[derive(Clone)]
+pub struct MessageRecorder {
+    sender: mpsc::SyncSender<CapturedMessage>,
+}
+
+impl MessageRecorder {
+    /// Opens the capture files in the configured directory and starts the thread writing them.
+    pub fn start(config: &CaptureConfig) -> Result<Self> {
+        let mut writer =
+            CaptureWriter::open(config.dir(), config.max_file_size_bytes, config.max_files)?;
+        let (sender, receiver) = mpsc::sync_channel::<CapturedMessage>(RECORDER_QUEUE_SIZE);
+        thread::Builder::new()
+            .name("network-capture".to_string())
+            .spawn(move || {
+                // Exits once all the handles are dropped.
+                while let Ok(message) = receiver.recv() {
+                    let result = std::iter::once(message)
+                        .chain(receiver.try_iter())
+                        .try_for_each(|message| writer.write(&message))
+                        .and_then(|()| writer.flush());
+                    if let Err(err) = result {
+                        error!("Failed to write network capture: {:?}", err);
+                        break;
+                    }
+                }
+            })?;
+        Ok(Self { sender })
+    }
+
+    /// Queues a message to be written. Drops it if the writer is lagging behind.
+    pub fn record(
+        &self,
+        peer_id: PeerId,
+        direction: Direction,
+        protocol: Option<ProtocolId>,
+        message: &NetworkMessage,
+    ) {
+        let captured = CapturedMessage {
+            timestamp_usecs: now_usecs(),
+            peer_id,
+            direction,
+            protocol,
+            message: message.clone(),
+        };
+        let state = match self.sender.try_send(captured) {
+            Ok(()) => counters::RECORDED_LABEL,
+            Err(_) => counters::DROPPED_LABEL,
+        };
+        counters::LIBRA_NETWORK_CAPTURED_MESSAGES
+            .with_label_values(&[direction.as_str(), state])
+            .inc();
+    }
+}
+
+/// Appends records to rotating capture files.
+pub struct CaptureWriter {
+    dir: PathBuf,
+    max_file_size: u64,
+    max_files: usize,
+    /// Indices of the files in the directory, oldest first. The last one is being written.
+    indices: Vec<u64>,
+    file: BufWriter<File>,
+    file_size: u64,
+}
+
+impl CaptureWriter {
+    /// Starts a new capture file in `dir`, after the files of previous captures.
+    pub fn open(dir: PathBuf, max_file_size: u64, max_files: usize) -> Result<Self> {
+        ensure!(max_files > 0, "At least one capture file must be kept");
+        fs::create_dir_all(&dir)?;
+        let mut indices: Vec<_> = capture_files(&dir)?
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect();
+        let index = indices.last().map_or(0, |index| index + 1);
+        indices.push(index);
+        let mut writer = Self {
+            file: BufWriter::new(File::create(file_path(&dir, index))?),
+            dir,
+            max_file_size,
+            max_files,
+            indices,
+            file_size: 0,
+        };
+        writer.remove_old_files()?;
+        Ok(writer)
+    }
+
+    pub fn write(&mut self, message: &CapturedMessage) -> Result<()> {
+        let bytes = lcs::to_bytes(message)?;
+        let record_size = (LENGTH_PREFIX_SIZE + bytes.len()) as u64;
+        if self.file_size > 0 && self.file_size + record_size > self.max_file_size {
+            self.rotate()?;
+        }
+        self.file.write_all(&(bytes.len() as u32).to_be_bytes())?;
+        self.file.write_all(&bytes)?;
+        self.file_size += record_size;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        self.flush()?;
+        let index = self.indices.last().map_or(0, |index| index + 1);
+        self.file = BufWriter::new(File::create(file_path(&self.dir, index))?);
+        self.file_size = 0;
+        self.indices.push(index);
+        self.remove_old_files()
+    }
+
+    fn remove_old_files(&mut self) -> Result<()> {
+        while self.indices.len() > self.max_files {
+            let index = self.indices.remove(0);
+            fs::remove_file(file_path(&self.dir, index))?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the capture files in `dir` along with their indices, oldest first.
+pub fn capture_files(dir: &Path) -> Result<Vec<(u64, PathBuf)>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some(CAPTURE_FILE_EXTENSION) {
+            continue;
+        }
+        if let Some(index) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse().ok())
+        {
+            files.push((index, path));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Reads all the messages captured in `dir`, in the order they were recorded.
+///
+/// A record cut short, e.g. because the node crashed while writing it, ends its file.
+pub fn read_capture(dir: &Path) -> Result<Vec<CapturedMessage>> {
+    let mut messages = vec![];
+    for (_, path) in capture_files(dir)? {
+        let bytes = fs::read(&path)?;
+        let mut remaining = &bytes[..];
+        while remaining.len() >= LENGTH_PREFIX_SIZE {
+            let (prefix, rest) = remaining.split_at(LENGTH_PREFIX_SIZE);
+            let size = u32::from_be_bytes(prefix.try_into().expect("Prefix has 4 bytes")) as usize;
+            if rest.len() < size {
+                warn!("Truncated record at the end of {}", path.display());
+                break;
+            }
+            let (record, rest) = rest.split_at(size);
+            messages.push(lcs::from_bytes(record)?);
+            remaining = rest;
+        }
+    }
+    Ok(messages)
+}
+
+fn file_path(dir: &Path, index: u64) -> PathBuf {
+    dir.join(format!("{}.{}", index, CAPTURE_FILE_EXTENSION))
+}
+
+fn now_usecs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System time is before the unix epoch")
+        .as_micros() as u64
+}
@@ -0,0 +1,152 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Replay of a capture into a node.
+//!
+//! A [`Replayer`] connects to the node under test as one of the peers of a capture, and sends the
+//! messages the capturing node received from that peer, in their original order. The node handles
+//! them as if they came from the original peer, so e.g. a validator under test only needs to
+//! trust the identity key used by the replayer for that peer id. Messages sent by the node during
+//! the replay are read and discarded.
+//!
+//! Replays typically run over memsocket, against a single node built for the purpose of a test,
+//! see [`Replayer::dial_memory`].
+
+use crate::{
+    capture::{CapturedMessage, Direction},
+    constants::MAX_FRAME_SIZE,
+    noise::{stream::NoiseStream, IdentityKeys},
+    protocols::wire::{
+        handshake::v1::{MessagingProtocolVersion, SupportedProtocols},
+        messaging::v2::Fragmenter,
+    },
+    transport::{Connection, LibraNetTransport, TSocket},
+    ProtocolId,
+};
+use anyhow::{bail, Result};
+use futures::{
+    future::{self, Either},
+    sink::SinkExt,
+    stream::StreamExt,
+};
+use libra_config::{config::HANDSHAKE_VERSION, network_id::NetworkId};
+use libra_crypto::x25519;
+use libra_network_address::NetworkAddress;
+use libra_types::{chain_id::ChainId, PeerId};
+use memsocket::MemorySocket;
+use netcore::{compat::IoCompat, transport::memory::MemoryTransport};
+use std::{sync::Arc, time::Duration};
+use tokio::time::delay_for;
+use tokio_util::codec::{FramedRead, FramedWrite, LengthDelimitedCodec};
+
+/// Sends the messages captured from one peer to a node.
+pub struct Replayer {
+    peer_id: PeerId,
+    messages: Vec<CapturedMessage>,
+    original_timing: bool,
+}
+
+impl Replayer {
+    /// Creates a replay of the messages received from `peer_id` in `capture`.
+    pub fn new(capture: &[CapturedMessage], peer_id: PeerId) -> Self {
+        let messages = capture
+            .iter()
+            .filter(|captured| {
+                captured.peer_id == peer_id && captured.direction == Direction::Inbound
+            })
+            .cloned()
+            .collect();
+        Self {
+            peer_id,
+            messages,
+            original_timing: false,
+        }
+    }
+
+    /// Waits between messages as long as they were apart when captured, instead of sending them
+    /// back to back.
+    pub fn original_timing(&mut self, original_timing: bool) -> &mut Self {
+        self.original_timing = original_timing;
+        self
+    }
+
+    pub fn peer_id(&self) -> PeerId {
+        self.peer_id
+    }
+
+    /// The messages to replay.
+    pub fn messages(&self) -> &[CapturedMessage] {
+        &self.messages
+    }
+
+    /// Connects over memsocket to the node `remote_peer_id` listening on `addr`, as the replayed
+    /// peer authenticated by `identity_key`.
+    pub async fn dial_memory(
+        &self,
+        chain_id: ChainId,
+        network_id: NetworkId,
+        identity_key: x25519::PrivateKey,
+        remote_peer_id: PeerId,
+        addr: NetworkAddress,
+    ) -> Result<Connection<NoiseStream<MemorySocket>>> {
+        let transport = LibraNetTransport::new(
+            MemoryTransport,
+            self.peer_id,
+            Arc::new(IdentityKeys::new(identity_key)),
+            None,
+            HANDSHAKE_VERSION,
+            chain_id,
+            network_id,
+            SupportedProtocols::from(ProtocolId::all().iter()),
+        );
+        Ok(transport.dial(remote_peer_id, addr)?.await?)
+    }
+
+    /// Sends all the messages over `connection`, then closes it. Returns the number of messages
+    /// sent.
+    pub async fn replay<T: TSocket>(&self, connection: Connection<T>) -> Result<usize> {
+        let messaging_protocol = connection.metadata.messaging_protocol();
+        let (reader, writer) = tokio::io::split(IoCompat::new(connection.socket));
+        let mut codec_builder = LengthDelimitedCodec::builder();
+        codec_builder
+            .max_frame_length(MAX_FRAME_SIZE)
+            .length_field_length(4)
+            .big_endian();
+        let mut reader = FramedRead::new(reader, codec_builder.new_codec());
+        let mut writer = FramedWrite::new(writer, codec_builder.new_codec());
+
+        let drain = async move { while let Some(Ok(_)) = reader.next().await {} };
+        let send = async move {
+            let mut fragmenter = Fragmenter::default();
+            let mut previous_timestamp = None;
+            for captured in &self.messages {
+                if let (true, Some(previous)) = (self.original_timing, previous_timestamp) {
+                    delay_for(Duration::from_micros(
+                        captured.timestamp_usecs.saturating_sub(previous),
+                    ))
+                    .await;
+                }
+                previous_timestamp = Some(captured.timestamp_usecs);
+                let frames = match messaging_protocol {
+                    MessagingProtocolVersion::V1 => vec![lcs::to_bytes(&captured.message)?],
+                    MessagingProtocolVersion::V2 | MessagingProtocolVersion::V3 => {
+                        fragmenter.frames(captured.message.clone())?
+                    }
+                };
+                for frame in frames {
+                    writer.send(frame.into()).await?;
+                }
+            }
+            writer.flush().await?;
+            writer.close().await?;
+            Ok(self.messages.len()) as Result<usize>
+        };
+        match future::select(Box::pin(send), Box::pin(drain)).await {
+            Either::Left((result, _)) => result,
+            Either::Right(((), _)) => bail!(
+                "Connection closed by the node while replaying the messages of {}",
+                self.peer_id.short_str()
+            ),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    capture::{
+        capture_files, read_capture, replay::Replayer, CaptureWriter, CapturedMessage, Direction,
+        MessageRecorder,
+    },
+    constants::MAX_FRAME_SIZE,
+    noise::IdentityKeys,
+    protocols::wire::{
+        handshake::v1::{MessagingProtocolVersion, SupportedProtocols},
+        messaging::{
+            v1::{DirectSendMsg, NetworkMessage, Nonce},
+            v2::{MultiplexMessage, Reassembler, MAX_FRAGMENT_SIZE},
+        },
+    },
+    transport::LibraNetTransport,
+    ProtocolId,
+};
+use futures::{future::join, stream::StreamExt};
+use libra_config::{
+    config::{CaptureConfig, HANDSHAKE_VERSION},
+    network_id::NetworkId,
+};
+use libra_crypto::{test_utils::TEST_SEED, x25519, Uniform};
+use libra_temppath::TempPath;
+use libra_types::{chain_id::ChainId, PeerId};
+use netcore::{compat::IoCompat, transport::memory::MemoryTransport};
+use rand::{rngs::StdRng, SeedableRng};
+use std::{fs, sync::Arc, thread, time::Duration};
+use tokio::runtime::Runtime;
+use tokio_util::codec::{FramedRead, LengthDelimitedCodec};
+
+fn message(peer_id: PeerId, direction: Direction, timestamp_usecs: u64) -> CapturedMessage {
+    CapturedMessage {
+        timestamp_usecs,
+        peer_id,
+        direction,
+        protocol: Some(ProtocolId::ConsensusDirectSend),
+        message: direct_send(vec![timestamp_usecs as u8; 8]),
+    }
+}
+
+fn direct_send(raw_msg: Vec<u8>) -> NetworkMessage {
+    NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: ProtocolId::ConsensusDirectSend,
+        priority: 0,
+        raw_msg,
+    })
+}
+
+#[test]
+fn test_rotation() {
+    let dir = TempPath::new();
+    let peer_id = PeerId::random();
+    let messages: Vec<_> = (0..10)
+        .map(|i| message(peer_id, Direction::Inbound, i))
+        .collect();
+    let record_size = (4 + lcs::to_bytes(&messages[0]).unwrap().len()) as u64;
+
+    // 2 records per file, 3 files kept
+    let mut writer = CaptureWriter::open(dir.path().to_path_buf(), 2 * record_size, 3).unwrap();
+    for message in &messages {
+        writer.write(message).unwrap();
+    }
+    writer.flush().unwrap();
+    let indices: Vec<_> = capture_files(dir.path())
+        .unwrap()
+        .into_iter()
+        .map(|(index, _)| index)
+        .collect();
+    assert_eq!(indices, vec![2, 3, 4]);
+    assert_eq!(read_capture(dir.path()).unwrap(), &messages[4..]);
+
+    // a new capture starts after the existing files
+    drop(writer);
+    let mut writer = CaptureWriter::open(dir.path().to_path_buf(), 2 * record_size, 3).unwrap();
+    writer.write(&messages[0]).unwrap();
+    writer.flush().unwrap();
+    let files = capture_files(dir.path()).unwrap();
+    assert_eq!(files.first().unwrap().0, 3);
+    assert_eq!(files.last().unwrap().0, 5);
+    assert_eq!(read_capture(dir.path()).unwrap().last(), Some(&messages[0]));
+}
+
+#[test]
+fn test_truncated_record() {
+    let dir = TempPath::new();
+    let peer_id = PeerId::random();
+    let messages: Vec<_> = (0..2)
+        .map(|i| message(peer_id, Direction::Outbound, i))
+        .collect();
+    let mut writer = CaptureWriter::open(dir.path().to_path_buf(), u64::max_value(), 1).unwrap();
+    for message in &messages {
+        writer.write(message).unwrap();
+    }
+    writer.flush().unwrap();
+
+    let (_, path) = capture_files(dir.path()).unwrap().pop().unwrap();
+    let bytes = fs::read(&path).unwrap();
+    fs::write(&path, &bytes[..bytes.len() - 3]).unwrap();
+    assert_eq!(read_capture(dir.path()).unwrap(), &messages[..1]);
+}
+
+#[test]
+fn test_recorder() {
+    let dir = TempPath::new();
+    let mut config = CaptureConfig::default();
+    config.enabled = true;
+    config.dir = dir.path().to_path_buf();
+    let recorder = MessageRecorder::start(&config).unwrap();
+    let peer_id = PeerId::random();
+    let ping = NetworkMessage::Ping(Nonce(7));
+    let send = direct_send(vec![1, 2, 3]);
+    recorder.record(peer_id, Direction::Inbound, None, &ping);
+    recorder.record(
+        peer_id,
+        Direction::Outbound,
+        Some(ProtocolId::ConsensusDirectSend),
+        &send,
+    );
+
+    // the messages are written in the background
+    let mut captured = vec![];
+    for _ in 0..100 {
+        captured = read_capture(dir.path()).unwrap();
+        if captured.len() == 2 {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(captured.len(), 2);
+    assert_eq!(captured[0].peer_id, peer_id);
+    assert_eq!(captured[0].direction, Direction::Inbound);
+    assert_eq!(captured[0].protocol, None);
+    assert_eq!(captured[0].message, ping);
+    assert_eq!(captured[1].direction, Direction::Outbound);
+    assert_eq!(captured[1].protocol, Some(ProtocolId::ConsensusDirectSend));
+    assert_eq!(captured[1].message, send);
+    assert!(captured[0].timestamp_usecs <= captured[1].timestamp_usecs);
+}
+
+#[test]
+fn test_replay() {
+    let mut rt = Runtime::new().unwrap();
+    let mut rng = StdRng::from_seed(TEST_SEED);
+    let node_key = x25519::PrivateKey::generate(&mut rng);
+    let node_peer_id = PeerId::from_identity_public_key(node_key.public_key());
+    let replay_key = x25519::PrivateKey::generate(&mut rng);
+    let replayed_peer_id = PeerId::from_identity_public_key(replay_key.public_key());
+    let other_peer_id = PeerId::random();
+
+    // only the messages received from the replayed peer are sent, including large ones
+    let large = direct_send(vec![7; 3 * MAX_FRAGMENT_SIZE]);
+    let mut capture = vec![
+        message(replayed_peer_id, Direction::Inbound, 1),
+        message(replayed_peer_id, Direction::Outbound, 2),
+        message(other_peer_id, Direction::Inbound, 3),
+        message(replayed_peer_id, Direction::Inbound, 4),
+    ];
+    capture[3].message = large.clone();
+    let expected = vec![capture[0].message.clone(), large];
+    let replayer = Replayer::new(&capture, replayed_peer_id);
+    assert_eq!(replayer.messages().len(), 2);
+
+    let transport = LibraNetTransport::new(
+        MemoryTransport,
+        node_peer_id,
+        Arc::new(IdentityKeys::new(node_key)),
+        None,
+        HANDSHAKE_VERSION,
+        ChainId::default(),
+        NetworkId::Validator,
+        SupportedProtocols::from(ProtocolId::all().iter()),
+    );
+    let (mut inbounds, node_addr) =
+        rt.enter(|| transport.listen_on("/memory/0".parse().unwrap()).unwrap());
+
+    let node = async move {
+        let (inbound, _) = inbounds.next().await.unwrap().unwrap();
+        let connection = inbound.await.unwrap();
+        assert_eq!(connection.metadata.peer_id(), replayed_peer_id);
+        assert_eq!(
+            connection.metadata.messaging_protocol(),
+            MessagingProtocolVersion::V3
+        );
+        let mut codec_builder = LengthDelimitedCodec::builder();
+        codec_builder
+            .max_frame_length(MAX_FRAME_SIZE)
+            .length_field_length(4)
+            .big_endian();
+        let mut frames =
+            FramedRead::new(IoCompat::new(connection.socket), codec_builder.new_codec());
+        let mut reassembler = Reassembler::new(MAX_FRAME_SIZE);
+        let mut received = vec![];
+        while let Some(Ok(frame)) = frames.next().await {
+            match lcs::from_bytes(&frame).unwrap() {
+                MultiplexMessage::Message(message) => received.push(message),
+                MultiplexMessage::Fragment(fragment) => {
+                    if let Some((message, _)) = reassembler.push(fragment).unwrap() {
+                        received.push(message);
+                    }
+                }
+            }
+        }
+        received
+    };
+    let replay = async move {
+        let connection = replayer
+            .dial_memory(
+                ChainId::default(),
+                NetworkId::Validator,
+                replay_key,
+                node_peer_id,
+                node_addr,
+            )
+            .await
+            .unwrap();
+        replayer.replay(connection).await.unwrap()
+    };
+    let (received, sent) = rt.block_on(join(node, replay));
+    assert_eq!(sent, 2);
+    assert_eq!(received, expected);
+}
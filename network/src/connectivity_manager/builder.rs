@@ -38,6 +38,7 @@ struct ConnectivityManagerBuilderConfig {
     connection_notifs_rx: conn_notifs_channel::Receiver,
     requests_rx: channel::Receiver<ConnectivityRequest>,
     connection_limit: Option<usize>,
+    key_rotation_grace_ms: u64,
 }
 
 #[derive(Debug, PartialEq, PartialOrd)]
@@ -67,6 +68,7 @@ impl ConnectivityManagerBuilder {
         connection_reqs_tx: ConnectionRequestSender,
         connection_notifs_rx: conn_notifs_channel::Receiver,
         connection_limit: Option<usize>,
+        key_rotation_grace_ms: u64,
     ) -> Self {
         let (conn_mgr_reqs_tx, conn_mgr_reqs_rx) = channel::new(
             channel_size,
@@ -85,6 +87,7 @@ impl ConnectivityManagerBuilder {
                 connection_notifs_rx,
                 requests_rx: conn_mgr_reqs_rx,
                 connection_limit,
+                key_rotation_grace_ms,
             }),
             connectivity_manager: None,
             conn_mgr_reqs_tx,
@@ -118,6 +121,7 @@ impl ConnectivityManagerBuilder {
                     ExponentialBackoff::from_millis(config.backoff_base).factor(1000),
                     config.max_connection_delay_ms,
                     config.connection_limit,
+                    Duration::from_millis(config.key_rotation_grace_ms),
                 )
             })
         });
@@ -9,12 +9,13 @@
 //! Consensus actor informs the ConnectivityManager of eligible nodes.
 //!
 //! Different discovery sources notify the ConnectivityManager of updates to
-//! peers' addresses. Currently, there are 3 discovery sources (ordered by
+//! peers' addresses. Currently, there are 4 discovery sources (ordered by
 //! decreasing dial priority, i.e., first is highest priority):
 //!
 //! 1. Onchain discovery protocol
 //! 2. Gossip discovery protocol
-//! 3. Seed peers from config
+//! 3. Peer exchange protocol, among full nodes
+//! 4. Seed peers from config
 //!
 //! In other words, if a we have some addresses discovered via onchain discovery
 //! and some seed addresses from our local config, we will try the onchain
@@ -26,6 +27,11 @@
 //! absolutely important that we maintain connectivity with all peers and heal
 //! any partitions asap, as we aren't currently gossiping consensus messages or
 //! using a relay protocol.
+//!
+//! When the number of outbound connections is limited, as on full nodes, the
+//! peers to dial are picked so that our connections span as many network
+//! groups (IP subnets or DNS names) as possible. This makes it harder for a
+//! single operator to surround us with its own nodes.
 
 use crate::{
     logging::*,
@@ -33,17 +39,17 @@ use crate::{
 };
 use futures::{
     channel::oneshot,
-    future::{BoxFuture, FutureExt},
+    future::{join_all, BoxFuture, FutureExt},
     stream::{FusedStream, FuturesUnordered, Stream, StreamExt},
 };
 use libra_config::network_id::NetworkContext;
 use libra_crypto::x25519;
 use libra_logger::prelude::*;
-use libra_network_address::NetworkAddress;
+use libra_network_address::{IpFilter, NetworkAddress, Protocol};
 use libra_types::PeerId;
 use num_variants::NumVariants;
 use rand::{
-    prelude::{SeedableRng, SmallRng},
+    prelude::{Rng, SeedableRng, SmallRng},
     seq::SliceRandom,
 };
 use serde::Serialize;
@@ -51,15 +57,19 @@ use std::{
     cmp::min,
     collections::{HashMap, HashSet},
     fmt, mem,
+    net::IpAddr,
     sync::{Arc, RwLock},
     time::{Duration, Instant},
 };
-use tokio::time;
+use tokio::{net::lookup_host, time};
 
 pub mod builder;
 #[cfg(test)]
 mod test;
 
+/// How long to wait for the resolution of a DNS name when grouping the addresses to dial.
+const DNS_RESOLUTION_TIMEOUT: Duration = Duration::from_secs(1);
+
 /// The ConnectivityManager actor.
 pub struct ConnectivityManager<TTicker, TBackoff> {
     network_context: Arc<NetworkContext>,
@@ -71,6 +81,12 @@ pub struct ConnectivityManager<TTicker, TBackoff> {
     peer_addrs: PeerAddresses,
     /// Public key sets of peers received from discovery sources.
     peer_pubkeys: PeerPublicKeys,
+    /// Keys recently removed from the key set of a peer, and when they stop being trusted.
+    /// They remain eligible for a grace period, so that a peer rotating its key can still
+    /// connect with the previous one until the new key propagates to every node.
+    retiring_pubkeys: HashMap<PeerId, HashMap<x25519::PublicKey, Instant>>,
+    /// How long keys remain eligible after being removed.
+    key_rotation_grace: Duration,
     /// Ticker to trigger connectivity checks to provide the guarantees stated above.
     ticker: TTicker,
     /// Channel to send connection requests to PeerManager.
@@ -105,6 +121,7 @@ pub struct ConnectivityManager<TTicker, TBackoff> {
 pub enum DiscoverySource {
     OnChain,
     Gossip,
+    PeerExchange,
     Config,
 }
 
@@ -178,6 +195,7 @@ where
         backoff_strategy: TBackoff,
         max_delay_ms: u64,
         connection_limit: Option<usize>,
+        key_rotation_grace: Duration,
     ) -> Self {
         assert!(
             eligible.read().unwrap().is_empty(),
@@ -193,6 +211,8 @@ where
             connected: HashMap::new(),
             peer_addrs: PeerAddresses::new(),
             peer_pubkeys: PeerPublicKeys::new(),
+            retiring_pubkeys: HashMap::new(),
+            key_rotation_grace,
             ticker,
             connection_reqs_tx,
             connection_notifs_rx,
@@ -359,9 +379,40 @@ where
         // address.
         let init_dial_state = DialState::new(self.backoff_strategy.clone());
 
-        for (p, addrs) in to_connect.choose_multiple(&mut self.rng, to_connect_size) {
+        // Prefer peers in network groups we have no connection or pending dial to, when there
+        // are more candidates than dials allowed.
+        let to_connect = if to_connect_size < to_connect.len() {
+            let used_addrs: Vec<_> = self
+                .connected
+                .values()
+                .chain(
+                    self.dial_queue
+                        .keys()
+                        .filter_map(|peer_id| self.peer_addrs.0.get(peer_id))
+                        .filter_map(|addrs| addrs.get(0)),
+                )
+                .collect();
+            let used_groups = join_all(used_addrs.into_iter().map(network_group))
+                .await
+                .into_iter()
+                .flatten()
+                .collect();
+            let groups = join_all(to_connect.iter().map(|(_, addrs)| async move {
+                match addrs.get(0) {
+                    Some(addr) => network_group(addr).await,
+                    None => None,
+                }
+            }))
+            .await;
+            let candidates = to_connect.into_iter().zip(groups.into_iter()).collect();
+            choose_diverse(&mut self.rng, candidates, to_connect_size, used_groups)
+        } else {
+            to_connect
+        };
+
+        for (p, addrs) in to_connect {
             let mut connction_reqs_tx = self.connection_reqs_tx.clone();
-            let peer_id = **p;
+            let peer_id = *p;
             let dial_state = self
                 .dial_states
                 .entry(peer_id)
@@ -432,6 +483,8 @@ where
         &'a mut self,
         pending_dials: &'a mut FuturesUnordered<BoxFuture<'static, PeerId>>,
     ) {
+        // Stop trusting keys which were rotated out long enough ago.
+        self.expire_retiring_pubkeys();
         // Cancel dials to peers that are no longer eligible.
         self.cancel_stale_dials().await;
         // Disconnect from connected peers that are no longer eligible.
@@ -564,28 +617,76 @@ where
 
         // 4. set shared eligible peers to union
         if have_any_changed {
-            // For each peer, union all of the pubkeys from each discovery source
-            // to generate the new eligible peers set.
-            let new_eligible = self.peer_pubkeys.union_all();
+            let new_eligible = self.update_eligible();
 
             let peer_pubkeys = &self.peer_pubkeys;
             info!(
                 "{} current pubkeys: update src: {:?}, all peer pubkeys: {}, new eligible set: {:?}",
                 self.network_context, src, peer_pubkeys, new_eligible,
             );
-
-            // Swap in the new eligible peers set. Drop the old set after releasing
-            // the write lock.
-            let _old_eligible = {
-                let mut eligible = self.eligible.write().unwrap();
-                mem::replace(&mut *eligible, new_eligible)
-            };
         }
 
         // TODO(philiphayes): we can probably do `cancel_stale_dials` and
         // possibly `cancel_stale_connections` in here?
     }
 
+    /// Recompute the shared eligible peers set from the keys of every discovery
+    /// source, and from the keys of still eligible peers within their grace period.
+    /// Returns the new set.
+    fn update_eligible(&mut self) -> HashMap<PeerId, HashSet<x25519::PublicKey>> {
+        // For each peer, union all of the pubkeys from each discovery source
+        // to generate the new eligible peers set.
+        let mut new_eligible = self.peer_pubkeys.union_all();
+
+        // Keys removed from a peer which is still eligible start their grace period.
+        let now = Instant::now();
+        let expiration = now + self.key_rotation_grace;
+        for (peer_id, old_pubkeys) in self.eligible.read().unwrap().iter() {
+            if let Some(new_pubkeys) = new_eligible.get(peer_id) {
+                let retiring = self.retiring_pubkeys.entry(*peer_id).or_default();
+                for pubkey in old_pubkeys.difference(new_pubkeys) {
+                    retiring.entry(*pubkey).or_insert(expiration);
+                }
+            }
+        }
+        self.retiring_pubkeys
+            .retain(|peer_id, retiring| match new_eligible.get_mut(peer_id) {
+                Some(pubkeys) => {
+                    retiring.retain(|pubkey, expiration| {
+                        !pubkeys.contains(pubkey) && now < *expiration
+                    });
+                    pubkeys.extend(retiring.keys());
+                    !retiring.is_empty()
+                }
+                None => false,
+            });
+
+        // Swap in the new eligible peers set. Drop the old set after releasing
+        // the write lock.
+        let _old_eligible = {
+            let mut eligible = self.eligible.write().unwrap();
+            mem::replace(&mut *eligible, new_eligible.clone())
+        };
+        new_eligible
+    }
+
+    /// Remove the keys whose grace period is over from the eligible peers set.
+    fn expire_retiring_pubkeys(&mut self) {
+        let now = Instant::now();
+        let any_expired = self
+            .retiring_pubkeys
+            .values()
+            .flat_map(HashMap::values)
+            .any(|expiration| *expiration <= now);
+        if any_expired {
+            let new_eligible = self.update_eligible();
+            info!(
+                "{} rotated pubkeys expired, new eligible set: {:?}",
+                self.network_context, new_eligible,
+            );
+        }
+    }
+
     fn handle_control_notification(&mut self, notif: peer_manager::ConnectionNotification) {
         match notif {
             peer_manager::ConnectionNotification::NewPeer(peer_id, addr, _origin, _context) => {
@@ -660,6 +761,62 @@ fn log_dial_result(
     }
 }
 
+/// The network group of an address, i.e., the /16 subnet of IPv4 addresses or the /32 subnet of
+/// IPv6 addresses, after resolving DNS names. Addresses in a group are likely run by the same
+/// operator.
+async fn network_group(addr: &NetworkAddress) -> Option<String> {
+    let (name, ip_filter) = match addr.as_slice().first()? {
+        Protocol::Ip4(ip) => return Some(ip_group(IpAddr::V4(*ip))),
+        Protocol::Ip6(ip) => return Some(ip_group(IpAddr::V6(*ip))),
+        Protocol::Dns(name) => (name, IpFilter::Any),
+        Protocol::Dns4(name) => (name, IpFilter::OnlyIp4),
+        Protocol::Dns6(name) => (name, IpFilter::OnlyIp6),
+        _ => return None,
+    };
+    let ip = time::timeout(DNS_RESOLUTION_TIMEOUT, lookup_host((name.as_ref(), 0)))
+        .await
+        .ok()?
+        .ok()?
+        .map(|socket_addr| socket_addr.ip())
+        .find(|ip| ip_filter.matches(*ip))?;
+    Some(ip_group(ip))
+}
+
+fn ip_group(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => {
+            let octets = ip.octets();
+            format!("{}.{}", octets[0], octets[1])
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            format!("{:x}:{:x}", segments[0], segments[1])
+        }
+    }
+}
+
+/// Randomly picks `amount` of the candidates, first picking candidates in distinct network groups
+/// that are not in `used_groups`, then completing with the others.
+fn choose_diverse<T, R: Rng>(
+    rng: &mut R,
+    mut candidates: Vec<(T, Option<String>)>,
+    amount: usize,
+    mut used_groups: HashSet<String>,
+) -> Vec<T> {
+    candidates.shuffle(rng);
+    let (diverse, others): (Vec<_>, Vec<_>) =
+        candidates.into_iter().partition(|(_, group)| match group {
+            Some(group) => used_groups.insert(group.clone()),
+            None => true,
+        });
+    diverse
+        .into_iter()
+        .chain(others)
+        .take(amount)
+        .map(|(candidate, _)| candidate)
+        .collect()
+}
+
 /////////////////////
 // DiscoverySource //
 /////////////////////
@@ -76,6 +76,7 @@ fn setup_conn_mgr_with_context(
             FixedInterval::from_millis(100),
             300, /* ms */
             Some(MAX_TEST_CONNECTIONS),
+            Duration::from_secs(0),
         )
     };
     rt.spawn(conn_mgr.start());
@@ -1255,10 +1256,14 @@ fn public_connection_limit() {
     rt.block_on(f_peer_mgr);
 }
 
-#[test]
-fn basic_update_eligible_peers() {
-    // setup a basic connectivity manager without starting its event loop
-
+/// Setup a basic connectivity manager without starting its event loop. Returns it along with
+/// the eligible peers set it updates.
+fn setup_conn_mgr_without_start(
+    key_rotation_grace: Duration,
+) -> (
+    ConnectivityManager<channel::Receiver<()>, FixedInterval>,
+    Arc<RwLock<HashMap<PeerId, HashSet<x25519::PublicKey>>>>,
+) {
     let network_context = Arc::new(NetworkContext::new(
         NetworkId::Validator,
         RoleType::Validator,
@@ -1272,9 +1277,8 @@ fn basic_update_eligible_peers() {
     let trusted_peers = Arc::new(RwLock::new(HashMap::new()));
     let seed_addrs = HashMap::new();
     let seed_pubkeys = HashMap::new();
-    let mut rng = StdRng::from_seed(TEST_SEED);
 
-    let mut conn_mgr = ConnectivityManager::new(
+    let conn_mgr = ConnectivityManager::new(
         network_context,
         trusted_peers.clone(),
         seed_addrs,
@@ -1286,7 +1290,16 @@ fn basic_update_eligible_peers() {
         FixedInterval::from_millis(100),
         300,  /* ms */
         None, /* connection limit */
+        key_rotation_grace,
     );
+    (conn_mgr, trusted_peers)
+}
+
+#[test]
+fn basic_update_eligible_peers() {
+    // setup a basic connectivity manager without starting its event loop
+    let (mut conn_mgr, trusted_peers) = setup_conn_mgr_without_start(Duration::from_secs(0));
+    let mut rng = StdRng::from_seed(TEST_SEED);
 
     // sample some example data
 
@@ -1345,3 +1358,113 @@ fn basic_update_eligible_peers() {
     conn_mgr.handle_update_eligible_peers(DiscoverySource::Gossip, pubkeys_map_empty.clone());
     assert_eq!(&*trusted_peers.read().unwrap(), &pubkeys_map_empty);
 }
+
+#[test]
+fn rotated_pubkeys_grace_period() {
+    let grace = Duration::from_millis(200);
+    let (mut conn_mgr, trusted_peers) = setup_conn_mgr_without_start(grace);
+    let mut rng = StdRng::from_seed(TEST_SEED);
+
+    let peer_id_a = PeerId::random();
+    let peer_id_b = PeerId::random();
+    let pubkey_1 = x25519::PrivateKey::generate(&mut rng).public_key();
+    let pubkey_2 = x25519::PrivateKey::generate(&mut rng).public_key();
+    let pubkey_3 = x25519::PrivateKey::generate(&mut rng).public_key();
+    let pubkeys_map = |pubkeys_a: &[x25519::PublicKey], pubkeys_b: &[x25519::PublicKey]| {
+        vec![
+            (peer_id_a, pubkeys_a.iter().copied().collect::<HashSet<_>>()),
+            (peer_id_b, pubkeys_b.iter().copied().collect()),
+        ]
+        .into_iter()
+        .filter(|(_, pubkeys)| !pubkeys.is_empty())
+        .collect::<HashMap<_, _>>()
+    };
+
+    conn_mgr.handle_update_eligible_peers(
+        DiscoverySource::OnChain,
+        pubkeys_map(&[pubkey_1], &[pubkey_3]),
+    );
+    assert_eq!(
+        &*trusted_peers.read().unwrap(),
+        &pubkeys_map(&[pubkey_1], &[pubkey_3])
+    );
+
+    // peer a rotates its key: the previous one is still trusted during the grace period
+    conn_mgr.handle_update_eligible_peers(
+        DiscoverySource::OnChain,
+        pubkeys_map(&[pubkey_2], &[pubkey_3]),
+    );
+    assert_eq!(
+        &*trusted_peers.read().unwrap(),
+        &pubkeys_map(&[pubkey_1, pubkey_2], &[pubkey_3])
+    );
+
+    // peer b leaves: its key isn't kept
+    conn_mgr.handle_update_eligible_peers(DiscoverySource::OnChain, pubkeys_map(&[pubkey_2], &[]));
+    assert_eq!(
+        &*trusted_peers.read().unwrap(),
+        &pubkeys_map(&[pubkey_1, pubkey_2], &[])
+    );
+
+    // nothing expires before the end of the grace period
+    conn_mgr.expire_retiring_pubkeys();
+    assert_eq!(
+        &*trusted_peers.read().unwrap(),
+        &pubkeys_map(&[pubkey_1, pubkey_2], &[])
+    );
+
+    // then the previous key of peer a is no longer trusted
+    std::thread::sleep(grace);
+    conn_mgr.expire_retiring_pubkeys();
+    assert_eq!(
+        &*trusted_peers.read().unwrap(),
+        &pubkeys_map(&[pubkey_2], &[])
+    );
+    assert!(conn_mgr.retiring_pubkeys.is_empty());
+}
+
+#[test]
+fn choose_diverse_network_groups() {
+    let peers: Vec<_> = (0..4).map(|_| PeerId::random()).collect();
+    let groups = vec![
+        Some("10.0".to_string()),
+        Some("10.0".to_string()),
+        Some("10.1".to_string()),
+        None,
+    ];
+    let candidates: Vec<_> = peers.iter().cloned().zip(groups.into_iter()).collect();
+    // We're already connected to the 10.1/16 subnet.
+    let used_groups: HashSet<_> = ["10.1".to_string()].iter().cloned().collect();
+    let mut rng = StdRng::from_seed(TEST_SEED);
+
+    for _ in 0..10 {
+        let chosen: HashSet<_> =
+            choose_diverse(&mut rng, candidates.clone(), 2, used_groups.clone())
+                .into_iter()
+                .collect();
+        // One of the two 10.0/16 peers, and the peer without a known group.
+        assert_eq!(chosen.len(), 2);
+        assert!(chosen.contains(&peers[3]));
+        assert!(chosen.contains(&peers[0]) ^ chosen.contains(&peers[1]));
+    }
+
+    // Peers in used groups complete the selection.
+    let chosen = choose_diverse(&mut rng, candidates, 4, used_groups);
+    assert_eq!(chosen.len(), 4);
+}
+
+#[test]
+fn network_groups_of_resolved_addresses() {
+    let mut rt = Runtime::new().unwrap();
+    let mut group =
+        |addr: &str| rt.block_on(network_group(&NetworkAddress::from_str(addr).unwrap()));
+    assert_eq!(group("/ip4/10.0.1.1/tcp/6180"), Some("10.0".to_string()));
+    assert_eq!(
+        group("/ip6/2001:db8::1/tcp/6180"),
+        Some("2001:db8".to_string())
+    );
+    // DNS names are grouped by the subnet they resolve to, so that names can't be used to
+    // spread the nodes of an operator over many groups.
+    assert_eq!(group("/dns4/localhost/tcp/6180"), Some("127.0".to_string()));
+    assert_eq!(group("/memory/6180"), None);
+}
@@ -14,6 +14,7 @@ pub const DISCOVERY_INTERVAL_MS: u64 = 1000;
 pub const PING_INTERVAL_MS: u64 = 1000;
 pub const PING_TIMEOUT_MS: u64 = 10_000;
 pub const DISOVERY_MSG_TIMEOUT_MS: u64 = 10_000;
+pub const PEER_EXCHANGE_RPC_TIMEOUT_MS: u64 = 10_000;
 pub const CONNECTIVITY_CHECK_INTERNAL_MS: u64 = 5000;
 pub const INBOUND_RPC_TIMEOUT_MS: u64 = 10_000;
 pub const MAX_CONCURRENT_OUTBOUND_RPCS: u32 = 100;
@@ -22,5 +23,6 @@ pub const PING_FAILURES_TOLERATED: u64 = 10;
 pub const MAX_CONCURRENT_NETWORK_REQS: usize = 100;
 pub const MAX_CONCURRENT_NETWORK_NOTIFS: usize = 100;
 pub const MAX_CONNECTION_DELAY_MS: u64 = 60_000; /* 1 minute */
+pub const KEY_ROTATION_GRACE_MS: u64 = 10 * 60 * 1000; /* 10 minutes */
 pub const MAX_FULLNODE_CONNECTIONS: usize = 3;
 pub const MAX_FRAME_SIZE: usize = 8 * 1024 * 1024; /* 8 MiB */
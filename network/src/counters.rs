@@ -30,6 +30,33 @@ pub static LIBRA_NETWORK_PEERS: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static LIBRA_NETWORK_PEER_REPORTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_network_peer_reports",
+        "Libra network interactions with peers reported by upstream applications",
+        &["role_type", "behavior"]
+    )
+    .unwrap()
+});
+
+pub static LIBRA_NETWORK_PEER_BANS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_network_peer_bans",
+        "Libra network peers banned for misbehaving",
+        &["role_type"]
+    )
+    .unwrap()
+});
+
+pub static LIBRA_NETWORK_PEER_LATENCY: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "libra_network_peer_latency_us",
+        "Libra network round-trip time of the health checker pings, in microseconds",
+        &["role_type", "peer_id", "stat"]
+    )
+    .unwrap()
+});
+
 pub static LIBRA_NETWORK_DISCOVERY_NOTES: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec!(
         // metric name
@@ -42,6 +69,18 @@ pub static LIBRA_NETWORK_DISCOVERY_NOTES: Lazy<IntGaugeVec> = Lazy::new(|| {
     .unwrap()
 });
 
+pub static LIBRA_NETWORK_PEER_EXCHANGE_ADDRESSES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        // metric name
+        "libra_network_peer_exchange_addresses",
+        // metric description
+        "Libra network peer exchange address book entries",
+        // metric labels (dimensions)
+        &["role_type", "state"]
+    )
+    .unwrap()
+});
+
 pub static LIBRA_NETWORK_RPC_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
         "libra_network_rpc_messages",
@@ -87,6 +126,64 @@ pub static LIBRA_NETWORK_DIRECT_SEND_BYTES: Lazy<HistogramVec> = Lazy::new(|| {
     .unwrap()
 });
 
+// some direction labels
+pub const INBOUND_LABEL: &str = "inbound";
+pub const OUTBOUND_LABEL: &str = "outbound";
+
+// some rate-limiting action labels
+pub const DELAYED_LABEL: &str = "delayed";
+pub const DROPPED_LABEL: &str = "dropped";
+
+// capture action labels
+pub const RECORDED_LABEL: &str = "recorded";
+
+/// Messages handed to the capture recorder, see `capture::MessageRecorder`. Messages are dropped
+/// when the recorder can't keep up with the traffic.
+pub static LIBRA_NETWORK_CAPTURED_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_network_captured_messages",
+        "Libra network messages recorded for offline replay",
+        &["direction", "state"]
+    )
+    .unwrap()
+});
+
+// noise static key labels
+pub const CURRENT_KEY_LABEL: &str = "current";
+pub const PREVIOUS_KEY_LABEL: &str = "previous";
+
+/// Noise handshakes of established connections, by which of our static keys was used. During a
+/// key rotation, this shows whether peers still expect our previous key.
+pub static LIBRA_NETWORK_NOISE_HANDSHAKES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_network_noise_handshakes",
+        "Libra network noise handshakes counter",
+        &["direction", "local_key"]
+    )
+    .unwrap()
+});
+
+/// Bytes read from or written to peers, by protocol. Traffic which isn't attributed to an
+/// application protocol, e.g. rpc responses and pings, uses the message type as protocol label.
+pub static LIBRA_NETWORK_BYTES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_network_bytes",
+        "Libra network bytes counter",
+        &["direction", "protocol_id"]
+    )
+    .unwrap()
+});
+
+/// Messages delayed or dropped because of rate limits.
+pub static LIBRA_NETWORK_RATE_LIMITED_MESSAGES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "libra_network_rate_limited_messages",
+        "Libra network rate limited messages counter",
+        &["direction", "protocol_id", "action"]
+    )
+    .unwrap()
+});
+
 /// Counters(queued,dequeued,dropped) related to inbound network notifications for RPCs and
 /// DirectSends.
 pub static PENDING_NETWORK_NOTIFICATIONS: Lazy<IntCounterVec> = Lazy::new(|| {
@@ -127,6 +224,16 @@ pub static PENDING_DISCOVERY_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(||
     .unwrap()
 });
 
+/// Counter of pending network events to PeerExchange.
+pub static PENDING_PEER_EXCHANGE_NETWORK_EVENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "pending_peer_exchange_network_events",
+        "Counters(queued,dequeued,dropped) related to pending network notifications to PeerExchange",
+        &["state"]
+    )
+    .unwrap()
+});
+
 /// Counter of pending requests in Peer Manager
 pub static PENDING_PEER_MANAGER_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec!(
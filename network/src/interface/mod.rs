@@ -12,9 +12,10 @@
 //! [`NetworkProvider`] actor. Inbound RPC requests are forwarded to the appropriate
 //! handler, determined using the protocol negotiated on the RPC substream.
 use crate::{
+    capture::MessageRecorder,
     constants, counters,
     peer::{Peer, PeerHandle, PeerNotification},
-    peer_manager::TransportNotification,
+    peer_manager::{status::ConnectionTraffic, TransportNotification},
     protocols::{
         direct_send::{DirectSend, DirectSendNotification, DirectSendRequest, Message},
         rpc::{InboundRpcRequest, OutboundRpcRequest, Rpc, RpcNotification},
@@ -28,9 +29,11 @@ use futures::{
     stream::StreamExt,
     FutureExt, SinkExt,
 };
+use libra_config::config::RateLimitConfig;
 use libra_logger::prelude::*;
 use libra_types::PeerId;
-use std::{fmt::Debug, marker::PhantomData, num::NonZeroUsize, time::Duration};
+use netcore::transport::MessageStreams;
+use std::{fmt::Debug, marker::PhantomData, num::NonZeroUsize, sync::Arc, time::Duration};
 use tokio::runtime::Handle;
 
 /// Requests [`NetworkProvider`] receives from the network interface.
@@ -60,8 +63,9 @@ pub struct NetworkProvider<TSocket> {
 
 impl<TSocket> NetworkProvider<TSocket>
 where
-    TSocket: AsyncRead + AsyncWrite + Send + 'static,
+    TSocket: AsyncRead + AsyncWrite + MessageStreams + Send + 'static,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn start(
         executor: Handle,
         connection: Connection<TSocket>,
@@ -70,11 +74,15 @@ where
         max_concurrent_notifs: usize,
         channel_size: usize,
         max_frame_size: usize,
+        rate_limit: RateLimitConfig,
+        recorder: Option<MessageRecorder>,
+        traffic: Arc<ConnectionTraffic>,
     ) -> (
         libra_channel::Sender<ProtocolId, NetworkRequest>,
         libra_channel::Receiver<ProtocolId, NetworkNotification>,
     ) {
         let peer_id = connection.metadata.peer_id();
+        let messaging_protocol = connection.metadata.messaging_protocol();
 
         // Setup and start Peer actor.
         let (peer_reqs_tx, peer_reqs_rx) = channel::new(
@@ -112,6 +120,9 @@ where
             peer_rpc_notifs_tx,
             peer_ds_notifs_tx,
             max_frame_size,
+            rate_limit,
+            recorder,
+            traffic,
         );
         executor.spawn(peer.start());
 
@@ -134,6 +145,7 @@ where
             Duration::from_millis(constants::INBOUND_RPC_TIMEOUT_MS),
             constants::MAX_CONCURRENT_OUTBOUND_RPCS,
             constants::MAX_CONCURRENT_INBOUND_RPCS,
+            messaging_protocol,
         );
         executor.spawn(rpc.start());
 
@@ -8,6 +8,7 @@
 
 pub use interface::NetworkProvider;
 
+pub mod capture;
 pub mod common;
 pub mod connectivity_manager;
 pub mod constants;
@@ -30,5 +31,6 @@ pub mod noise;
 pub mod testutils;
 
 pub type DisconnectReason = peer::DisconnectReason;
+pub type IdentityKeys = noise::IdentityKeys;
 pub type ConnectivityRequest = connectivity_manager::ConnectivityRequest;
 pub type ProtocolId = protocols::wire::handshake::v1::ProtocolId;
@@ -18,9 +18,9 @@ use netcore::transport::ConnectionOrigin;
 use std::{
     collections::{HashMap, HashSet},
     convert::TryFrom as _,
-    io,
+    io, mem,
     sync::{Arc, RwLock},
-    time,
+    time::{self, Duration, Instant},
 };
 
 /// In a mutually authenticated network, a client message is accompanied with a timestamp.
@@ -123,6 +123,79 @@ impl HandshakeAuthMode {
     }
 }
 
+/// The static keys a node authenticates with.
+///
+/// When the key of a node rotates, e.g. through `key-manager`, its peers only learn about the new
+/// key once it propagates through discovery. Until then, the previous key is kept for a grace
+/// period:
+///
+/// - inbound handshakes are accepted for both keys, depending on which one the dialer expects,
+/// - outbound handshakes keep using the previous key, as peers might not trust the new one yet.
+///
+/// Note that in server-only authenticated networks, where peer ids are derived from the key,
+/// the peer id of a node changes along with its key.
+pub struct IdentityKeys(RwLock<Keys>);
+
+struct Keys {
+    current: Arc<noise::NoiseConfig>,
+    /// The previous key, and when it stops being used.
+    previous: Option<(Arc<noise::NoiseConfig>, Instant)>,
+}
+
+impl IdentityKeys {
+    pub fn new(key: x25519::PrivateKey) -> Self {
+        IdentityKeys(RwLock::new(Keys {
+            current: Arc::new(noise::NoiseConfig::new(key)),
+            previous: None,
+        }))
+    }
+
+    /// The current public key.
+    pub fn public_key(&self) -> x25519::PublicKey {
+        self.0.read().unwrap().current.public_key()
+    }
+
+    /// The previous public key, if it's still in its grace period.
+    pub fn previous_public_key(&self) -> Option<x25519::PublicKey> {
+        self.0
+            .read()
+            .unwrap()
+            .previous()
+            .map(|config| config.public_key())
+    }
+
+    /// Switch to `key`, keeping the current key around for `grace`.
+    pub fn rotate(&self, key: x25519::PrivateKey, grace: Duration) {
+        let mut keys = self.0.write().unwrap();
+        let current = mem::replace(&mut keys.current, Arc::new(noise::NoiseConfig::new(key)));
+        keys.previous = Some((current, Instant::now() + grace));
+    }
+
+    /// The key to dial with.
+    fn dialing_config(&self) -> Arc<noise::NoiseConfig> {
+        let keys = self.0.read().unwrap();
+        keys.previous().unwrap_or(&keys.current).clone()
+    }
+
+    /// The key an inbound dialer expects us to have, if we have it.
+    fn listening_config(&self, expected_public_key: &[u8]) -> Option<Arc<noise::NoiseConfig>> {
+        let keys = self.0.read().unwrap();
+        std::iter::once(&keys.current)
+            .chain(keys.previous())
+            .find(|config| config.public_key().as_slice() == expected_public_key)
+            .cloned()
+    }
+}
+
+impl Keys {
+    fn previous(&self) -> Option<&Arc<noise::NoiseConfig>> {
+        match &self.previous {
+            Some((config, expiration)) if Instant::now() < *expiration => Some(config),
+            _ => None,
+        }
+    }
+}
+
 // Noise Upgrader
 // --------------
 // Noise by default is not aware of the above or lower protocol layers,
@@ -137,8 +210,8 @@ impl HandshakeAuthMode {
 pub struct NoiseUpgrader {
     /// The validator's own peer id.
     self_peer_id: PeerId,
-    /// Config for executing Noise handshakes. Includes our static private key.
-    noise_config: noise::NoiseConfig,
+    /// Our static private keys, used to execute Noise handshakes.
+    identity_keys: Arc<IdentityKeys>,
     /// Handshake authentication can be either mutual or server-only authentication.
     auth_mode: HandshakeAuthMode,
 }
@@ -146,13 +219,27 @@ pub struct NoiseUpgrader {
 impl NoiseUpgrader {
     /// Create a new NoiseConfig with the provided keypair and authentication mode.
     pub fn new(peer_id: PeerId, key: x25519::PrivateKey, auth_mode: HandshakeAuthMode) -> Self {
+        Self::with_identity_keys(peer_id, Arc::new(IdentityKeys::new(key)), auth_mode)
+    }
+
+    /// Create a new NoiseConfig sharing keys which may be rotated later on.
+    pub fn with_identity_keys(
+        peer_id: PeerId,
+        identity_keys: Arc<IdentityKeys>,
+        auth_mode: HandshakeAuthMode,
+    ) -> Self {
         Self {
             self_peer_id: peer_id,
-            noise_config: noise::NoiseConfig::new(key),
+            identity_keys,
             auth_mode,
         }
     }
 
+    /// The keys used for handshakes, which can be rotated while connections are upgraded.
+    pub fn identity_keys(&self) -> Arc<IdentityKeys> {
+        self.identity_keys.clone()
+    }
+
     /// Perform a protocol upgrade on an underlying connection. In addition perform the noise IK
     /// handshake to establish a noise stream and exchange static public keys. Upon success,
     /// returns the static public key of the remote as well as a NoiseStream.
@@ -212,10 +299,32 @@ impl NoiseUpgrader {
     /// Noise handshake payload. Currently this counter is always a millisecond-
     /// granularity unix epoch timestamp.
     pub async fn upgrade_outbound<TSocket, F>(
+        &self,
+        socket: TSocket,
+        remote_public_key: x25519::PublicKey,
+        time_provider: F,
+    ) -> io::Result<NoiseStream<TSocket>>
+    where
+        TSocket: AsyncRead + AsyncWrite + Unpin,
+        F: Fn() -> [u8; AntiReplayTimestamps::TIMESTAMP_SIZE],
+    {
+        self.upgrade_outbound_with_channel_binding(socket, remote_public_key, time_provider, None)
+            .await
+    }
+
+    /// Perform an outbound protocol upgrade on this connection, over a base socket with the
+    /// given channel binding.
+    ///
+    /// The channel binding is mixed into the prologue without being sent, so the handshake only
+    /// succeeds if the server sees the same channel binding, i.e. if both ends are part of the
+    /// same session of the base transport rather than of two sessions relayed by a man in the
+    /// middle.
+    pub async fn upgrade_outbound_with_channel_binding<TSocket, F>(
         &self,
         mut socket: TSocket,
         remote_public_key: x25519::PublicKey,
         time_provider: F,
+        channel_binding: Option<&[u8]>,
     ) -> io::Result<NoiseStream<TSocket>>
     where
         TSocket: AsyncRead + AsyncWrite + Unpin,
@@ -230,17 +339,18 @@ impl NoiseUpgrader {
             .copy_from_slice(remote_public_key.as_slice());
 
         let (prologue_msg, mut client_noise_msg) = client_message.split_at_mut(Self::PROLOGUE_SIZE);
+        let prologue = [&prologue_msg[..], channel_binding.unwrap_or_default()].concat();
 
         // craft 8-byte payload as current timestamp (in milliseconds)
         let payload = time_provider();
 
         // craft first handshake message  (-> e, es, s, ss)
+        let noise_config = self.identity_keys.dialing_config();
         let mut rng = rand::rngs::OsRng;
-        let initiator_state = self
-            .noise_config
+        let initiator_state = noise_config
             .initiate_connection(
                 &mut rng,
-                &prologue_msg,
+                &prologue,
                 remote_public_key,
                 Some(&payload),
                 &mut client_noise_msg,
@@ -257,8 +367,7 @@ impl NoiseUpgrader {
 
         // parse the server's response
         // TODO: security logging here? (mimoo)
-        let (_, session) = self
-            .noise_config
+        let (_, session) = noise_config
             .finalize_connection(initiator_state, &server_response)
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
 
@@ -275,8 +384,23 @@ impl NoiseUpgrader {
     /// In addition, we will expect the client to include an anti replay attack
     /// counter in the Noise handshake payload in mutual auth scenarios.
     pub async fn upgrade_inbound<TSocket>(
+        &self,
+        socket: TSocket,
+    ) -> io::Result<(NoiseStream<TSocket>, PeerId)>
+    where
+        TSocket: AsyncRead + AsyncWrite + Unpin,
+    {
+        self.upgrade_inbound_with_channel_binding(socket, None)
+            .await
+    }
+
+    /// Perform an inbound protocol upgrade on this connection, over a base socket with the
+    /// given channel binding. The handshake fails if the client sees a different channel
+    /// binding, see `upgrade_outbound_with_channel_binding`.
+    pub async fn upgrade_inbound_with_channel_binding<TSocket>(
         &self,
         mut socket: TSocket,
+        channel_binding: Option<&[u8]>,
     ) -> io::Result<(NoiseStream<TSocket>, PeerId)>
     where
         TSocket: AsyncRead + AsyncWrite + Unpin,
@@ -317,22 +441,25 @@ impl NoiseUpgrader {
             ));
         }
 
-        // verify that this is indeed our public key
-        if self_expected_public_key != self.noise_config.public_key().as_slice() {
-            // TODO: security logging (mimoo)
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!(
-                    "noise: client expecting us to have incorrect public key: {}",
-                    hex::encode(self_expected_public_key)
-                ),
-            ));
-        }
+        // verify that this is indeed our public key, or our previous one during a key rotation
+        let noise_config = self
+            .identity_keys
+            .listening_config(self_expected_public_key)
+            .ok_or_else(|| {
+                // TODO: security logging (mimoo)
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "noise: client expecting us to have incorrect public key: {}",
+                        hex::encode(self_expected_public_key)
+                    ),
+                )
+            })?;
 
         // parse it
-        let (prologue, client_init_message) = client_message.split_at(Self::PROLOGUE_SIZE);
-        let (remote_public_key, handshake_state, payload) = self
-            .noise_config
+        let (prologue_msg, client_init_message) = client_message.split_at(Self::PROLOGUE_SIZE);
+        let prologue = [prologue_msg, channel_binding.unwrap_or_default()].concat();
+        let (remote_public_key, handshake_state, payload) = noise_config
             .parse_client_init_message(&prologue, &client_init_message)
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
 
@@ -425,8 +552,7 @@ impl NoiseUpgrader {
         // construct the response
         let mut rng = rand::rngs::OsRng;
         let mut server_response = [0u8; Self::SERVER_MESSAGE_SIZE];
-        let session = self
-            .noise_config
+        let session = noise_config
             .respond_to_client(&mut rng, handshake_state, None, &mut server_response)
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
 
@@ -454,6 +580,7 @@ mod test {
     use std::{
         io,
         sync::{Arc, RwLock},
+        time::Duration,
     };
 
     /// helper to setup two testing peers
@@ -617,6 +744,109 @@ mod test {
         test_handshake_self_fails(true /* is_mutual_auth */);
     }
 
+    #[test]
+    fn test_handshake_channel_binding() {
+        let ((client, _), (server, server_public_key)) =
+            build_peers(true /* is_mutual_auth */);
+        let mut timestamp = 0;
+        let mut handshake = |client_binding: &[u8], server_binding: &[u8]| {
+            timestamp += 1;
+            let (dialer_socket, listener_socket) = MemorySocket::new_pair();
+            block_on(join(
+                client.upgrade_outbound_with_channel_binding(
+                    dialer_socket,
+                    server_public_key,
+                    bad_timestamp(timestamp),
+                    Some(client_binding),
+                ),
+                server.upgrade_inbound_with_channel_binding(listener_socket, Some(server_binding)),
+            ))
+        };
+
+        let (client_res, server_res) = handshake(b"binding", b"binding");
+        client_res.unwrap();
+        server_res.unwrap();
+
+        // The ends of two relayed connections see different channel bindings.
+        let (client_res, server_res) = handshake(b"binding", b"relayed");
+        client_res.unwrap_err();
+        server_res.unwrap_err();
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let ((client, client_public_key), (server, server_public_key)) =
+            build_peers(true /* is_mutual_auth */);
+        let mut rng = ::rand::rngs::StdRng::from_seed([1u8; 32]);
+        let mut timestamp = 0;
+        let mut handshake = |server_public_key| {
+            timestamp += 1;
+            let (dialer_socket, listener_socket) = MemorySocket::new_pair();
+            let (client_session, server_session) = block_on(join(
+                client.upgrade_outbound(dialer_socket, server_public_key, bad_timestamp(timestamp)),
+                server.upgrade_inbound(listener_socket),
+            ));
+            client_session.and_then(|client_stream| {
+                let (server_stream, _) = server_session?;
+                Ok((client_stream, server_stream))
+            })
+        };
+
+        // 1. the server rotates its key: dialers can expect either key during the grace period
+        let new_server_key = x25519::PrivateKey::generate(&mut rng);
+        let new_server_public_key = new_server_key.public_key();
+        server
+            .identity_keys()
+            .rotate(new_server_key, Duration::from_secs(3600));
+        let (_, server_stream) = handshake(server_public_key).unwrap();
+        assert_eq!(server_stream.get_local_static(), server_public_key);
+        let (_, server_stream) = handshake(new_server_public_key).unwrap();
+        assert_eq!(server_stream.get_local_static(), new_server_public_key);
+
+        // 2. the client rotates its key: it keeps dialing with the key trusted by the server
+        client.identity_keys().rotate(
+            x25519::PrivateKey::generate(&mut rng),
+            Duration::from_secs(3600),
+        );
+        assert_eq!(
+            client.identity_keys().previous_public_key(),
+            Some(client_public_key)
+        );
+        let (client_stream, server_stream) = handshake(new_server_public_key).unwrap();
+        assert_eq!(client_stream.get_local_static(), client_public_key);
+        assert_eq!(server_stream.get_remote_static(), client_public_key);
+
+        // 3. once the grace period is over, only the new keys are used
+        let newer_server_key = x25519::PrivateKey::generate(&mut rng);
+        let newer_server_public_key = newer_server_key.public_key();
+        server
+            .identity_keys()
+            .rotate(newer_server_key, Duration::from_secs(0));
+        handshake(new_server_public_key).unwrap_err();
+        client.identity_keys().rotate(
+            x25519::PrivateKey::generate(&mut rng),
+            Duration::from_secs(0),
+        );
+        assert_eq!(client.identity_keys().previous_public_key(), None);
+
+        // the new key of the client is only accepted once the server trusts it
+        handshake(newer_server_public_key).unwrap_err();
+        server
+            .auth_mode
+            .trusted_peers()
+            .unwrap()
+            .write()
+            .unwrap()
+            .get_mut(&client.self_peer_id)
+            .unwrap()
+            .insert(client.identity_keys().public_key());
+        let (client_stream, _) = handshake(newer_server_public_key).unwrap();
+        assert_eq!(
+            client_stream.get_local_static(),
+            client.identity_keys().public_key()
+        );
+    }
+
     #[test]
     fn test_handshake_fragmented_reads() {
         // create an in-memory socket for testing
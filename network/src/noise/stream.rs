@@ -21,6 +21,7 @@ use std::{
 
 use libra_crypto::{noise, x25519};
 use libra_logger::prelude::*;
+use netcore::transport::{MessageReader, MessageStreams, MessageWriter};
 
 //
 // NoiseStream
@@ -61,6 +62,17 @@ impl<TSocket> NoiseStream<TSocket> {
     pub fn get_remote_static(&self) -> x25519::PublicKey {
         self.session.get_remote_static()
     }
+
+    /// Pull out our own static public key used during the handshake
+    pub fn get_local_static(&self) -> x25519::PublicKey {
+        self.session.get_local_static()
+    }
+
+    /// Rekey the session in place every `interval` noise messages, in each direction.
+    /// The remote must enable it with the same interval before any of us reaches the first rekey.
+    pub fn enable_rekey(&mut self, interval: u64) {
+        self.session.set_rekey_interval(interval);
+    }
 }
 
 //
@@ -387,6 +399,28 @@ where
 // ---------------------
 //
 
+/// The message streams of the underlying socket bypass the noise session, they are only used
+/// once the remote confirmed the channel binding of the socket over the noise stream.
+impl<TSocket> MessageStreams for NoiseStream<TSocket>
+where
+    TSocket: MessageStreams,
+{
+    fn channel_binding(&self) -> Option<Vec<u8>> {
+        self.socket.channel_binding()
+    }
+
+    fn confirm_channel_binding(&mut self) {
+        self.socket.confirm_channel_binding()
+    }
+
+    fn take_message_streams(
+        &mut self,
+        max_message_size: usize,
+    ) -> Option<(MessageReader, MessageWriter)> {
+        self.socket.take_message_streams(max_message_size)
+    }
+}
+
 impl<TSocket> AsyncRead for NoiseStream<TSocket>
 where
     TSocket: AsyncRead + Unpin,
@@ -656,6 +690,31 @@ mod test {
         assert_eq!(&buf, b"The Doors of Stone");
     }
 
+    #[test]
+    fn rekeyed_writes() {
+        // perform handshake with two testing peers, then rekey every other message
+        let ((client, client_public), (server, server_public)) = build_peers();
+        let (mut client, mut server) = perform_handshake(client, server_public, server);
+        assert_eq!(client.get_local_static(), client_public);
+        assert_eq!(server.get_local_static(), server_public);
+        client.enable_rekey(2);
+        server.enable_rekey(2);
+
+        for i in 0..5u8 {
+            block_on(client.write_all(&[i; 32])).unwrap();
+            block_on(client.flush()).unwrap();
+            block_on(server.write_all(&[i + 1; 16])).unwrap();
+            block_on(server.flush()).unwrap();
+
+            let mut buf = [0; 32];
+            block_on(server.read_exact(&mut buf)).unwrap();
+            assert_eq!(buf, [i; 32]);
+            let mut buf = [0; 16];
+            block_on(client.read_exact(&mut buf)).unwrap();
+            assert_eq!(buf, [i + 1; 16]);
+        }
+    }
+
     #[test]
     fn u16_max_writes() {
         // perform handshake with two testing peers
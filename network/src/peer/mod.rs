@@ -4,9 +4,20 @@
 //! The Peer actor owns the underlying connection and is responsible for listening for
 //! and opening substreams as well as negotiating particular protocols on those substreams.
 use crate::{
+    capture::{Direction, MessageRecorder},
     counters,
-    peer_manager::PeerManagerError,
-    protocols::wire::messaging::v1::NetworkMessage,
+    peer::{
+        rate_limit::{Admission, ProtocolRateLimiter},
+        write_queue::WriteQueue,
+    },
+    peer_manager::{status::ConnectionTraffic, PeerManagerError},
+    protocols::wire::{
+        handshake::v1::MessagingProtocolVersion,
+        messaging::{
+            v1::NetworkMessage,
+            v2::{MultiplexMessage, Reassembler},
+        },
+    },
     transport,
     transport::{Connection, ConnectionMetadata},
     ProtocolId,
@@ -17,23 +28,43 @@ use futures::{
     channel::oneshot,
     io::{AsyncRead, AsyncWrite},
     stream::StreamExt,
-    FutureExt, SinkExt, TryFutureExt,
+    FutureExt, SinkExt,
 };
+use libra_config::config::RateLimitConfig;
 use libra_logger::prelude::*;
 use libra_types::PeerId;
-use netcore::compat::IoCompat;
+use netcore::{
+    compat::IoCompat,
+    transport::{MessageReader, MessageStreams, MessageWriter},
+};
 use serde::Serialize;
-use std::{fmt::Debug, io, time::Duration};
+use std::{
+    fmt::Debug,
+    io,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use stream_ratelimiter::*;
-use tokio::runtime::Handle;
+use tokio::{runtime::Handle, time::delay_for};
 use tokio_util::codec::{FramedRead, FramedWrite, LengthDelimitedCodec};
 
-// Rate-limit configuration for inbound messages. Allows 100 messages for every 10ms window.
+// Refill window of the inbound message rate limit.
 pub const MESSAGE_RATE_LIMIT_WINDOW: Duration = Duration::from_millis(10);
-pub const MESSAGE_RATE_LIMIT_COUNT: usize = 100;
 
+pub mod rate_limit;
 #[cfg(test)]
 mod test;
+mod write_queue;
+
+pub(crate) use write_queue::{priority_class, NUM_PRIORITY_CLASSES};
+
+/// A message to write on the wire, along with the protocol it is accounted to and the channel to
+/// notify once it is written.
+type WriteRequest = (
+    NetworkMessage,
+    Option<ProtocolId>,
+    oneshot::Sender<Result<(), PeerManagerError>>,
+);
 
 #[derive(Debug)]
 pub enum PeerRequest {
@@ -82,12 +113,26 @@ pub struct Peer<TSocket> {
     /// The maximum size of an inbound or outbound request frame
     /// Currently, requests are only a single frame
     max_frame_size: usize,
+    /// Bandwidth and message rate limits of the connection.
+    rate_limit: RateLimitConfig,
+    /// Per-protocol inbound limits.
+    inbound_limiter: ProtocolRateLimiter,
+    /// Inbound messages being received in fragments, with messaging protocol v2.
+    reassembler: Reassembler,
+    /// Recorder of the messages exchanged with the peer, if capture is enabled.
+    recorder: Option<MessageRecorder>,
+    /// Traffic of the connection, reported in network status snapshots.
+    traffic: Arc<ConnectionTraffic>,
+    /// Deadline until which inbound messages are not read, while a protocol exceeds its inbound
+    /// rate limit.
+    inbound_paused_until: Option<Instant>,
 }
 
 impl<TSocket> Peer<TSocket>
 where
-    TSocket: AsyncRead + AsyncWrite + Send + 'static,
+    TSocket: AsyncRead + AsyncWrite + MessageStreams + Send + 'static,
 {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         executor: Handle,
         connection: Connection<TSocket>,
@@ -96,6 +141,9 @@ where
         rpc_notifs_tx: channel::Sender<PeerNotification>,
         direct_send_notifs_tx: channel::Sender<PeerNotification>,
         max_frame_size: usize,
+        rate_limit: RateLimitConfig,
+        recorder: Option<MessageRecorder>,
+        traffic: Arc<ConnectionTraffic>,
     ) -> Self {
         let Connection {
             metadata: connection_metadata,
@@ -111,6 +159,12 @@ where
             direct_send_notifs_tx,
             state: State::Connected,
             max_frame_size,
+            inbound_limiter: ProtocolRateLimiter::new(&rate_limit),
+            reassembler: Reassembler::new(max_frame_size),
+            rate_limit,
+            recorder,
+            traffic,
+            inbound_paused_until: None,
         }
     }
 
@@ -125,33 +179,73 @@ where
             self_peer_id.short_str()
         );
 
-        // Split the connection into a ReadHalf and a WriteHalf.
-        let (reader, writer) = tokio::io::split(IoCompat::new(self.connection.take().unwrap()));
+        let mut socket = self.connection.take().unwrap();
         let mut codec_builder = LengthDelimitedCodec::builder();
         codec_builder
             .max_frame_length(self.max_frame_size)
             .length_field_length(4)
             .big_endian();
-        // Convert ReadHalf to Stream of length-delimited messages.
-
-        let reader = FramedRead::new(reader, codec_builder.new_codec()).fuse();
-        // Create a rate-limited stream of inbound messages.
+        // If the base transport has message streams, e.g. QUIC, each message is carried on its
+        // own stream. The socket is kept open until the actor stops.
+        let message_streams = socket.take_message_streams(self.max_frame_size);
+        let has_message_streams = message_streams.is_some();
+        let (reader, writer): (MessageReader, MessageWriter) = match message_streams {
+            Some(streams) => streams,
+            None => {
+                // Split the connection into a ReadHalf and a WriteHalf.
+                let (reader, writer) = tokio::io::split(IoCompat::new(socket));
+                // Convert ReadHalf to Stream of length-delimited messages, and WriteHalf to Sink
+                // of length-delimited messages.
+                (
+                    FramedRead::new(reader, codec_builder.new_codec()).boxed(),
+                    Box::pin(FramedWrite::new(writer, codec_builder.new_codec())),
+                )
+            }
+        };
+        let reader = reader.fuse();
+        // Create a stream of inbound messages rate-limited in bytes and in number of messages.
+        let messages_per_window = self.rate_limit.inbound_messages_per_sec
+            * MESSAGE_RATE_LIMIT_WINDOW.as_millis() as u64
+            / 1000;
         let mut reader = reader
-            .ratelimit(MESSAGE_RATE_LIMIT_WINDOW, MESSAGE_RATE_LIMIT_COUNT)
+            .ratelimit_by(
+                rate_limit::token_bucket(self.rate_limit.inbound_bytes_per_sec),
+                |frame| frame.as_ref().map_or(0, |frame| frame.len() as u64),
+            )
+            .ratelimit(
+                MESSAGE_RATE_LIMIT_WINDOW,
+                std::cmp::max(messages_per_window, 1) as usize,
+            )
             .fuse();
 
-        // Convert WriteHalf to Sink of length-delimited messages.
-        let writer = FramedWrite::new(writer, codec_builder.new_codec());
         // Start writer "process" as a separate task. We receive two handles to communicate with
         // the task:
         // `write_reqs_tx`: Instruction to send a NetworkMessage on the wire.
         // `close_tx`: Instruction to close the underlying connection.
-        let (write_reqs_tx, close_tx) =
-            Self::start_writer_task(&self.executor, self_peer_id, writer);
+        let (write_reqs_tx, close_tx) = Self::start_writer_task(
+            &self.executor,
+            self_peer_id,
+            writer,
+            &self.rate_limit,
+            self.connection_metadata.messaging_protocol(),
+            has_message_streams,
+            self.max_frame_size,
+            self.traffic.clone(),
+        );
         // Start main Peer event loop.
         loop {
             match self.state {
                 State::Connected => {
+                    // Only the reader waits out inbound rate limits, requests are still handled.
+                    let paused_until = self.inbound_paused_until;
+                    let next_message = async {
+                        if let Some(deadline) = paused_until {
+                            delay_for(deadline.saturating_duration_since(Instant::now())).await;
+                        }
+                        reader.next().await
+                    }
+                    .fuse();
+                    futures::pin_mut!(next_message);
                     futures::select! {
                         maybe_req = self.requests_rx.next() => {
                             if let Some(request) = maybe_req {
@@ -162,7 +256,8 @@ where
                                 break;
                             }
                         },
-                        maybe_message = reader.next() => {
+                        maybe_message = next_message => {
+                            self.inbound_paused_until = None;
                             match maybe_message {
                                 Some(Ok(message)) =>  {
                                     if let Err(err) = self.handle_inbound_message(message, write_reqs_tx.clone()).await {
@@ -222,51 +317,87 @@ where
     // 2. The second channel is used to instruct the task to close the connection and terminate.
     // If outbound messages are queued when the task receives a close instruction, it discards
     // them and immediately closes the connection.
-    fn start_writer_task<T: tokio::io::AsyncWrite + Send + Unpin + 'static>(
+    // Writes are delayed as needed to keep within the outbound rate limits of the peer and of the
+    // protocol of each message.
+    // Pending messages are written by priority class, see `WriteQueue`. With messaging protocol
+    // v2, large messages are split into several frames so that they can be preempted by more
+    // urgent messages, unless each message has its own stream.
+    #[allow(clippy::too_many_arguments)]
+    fn start_writer_task(
         executor: &Handle,
         self_peer_id: PeerId,
-        mut writer: FramedWrite<T, LengthDelimitedCodec>,
-    ) -> (
-        channel::Sender<(
-            NetworkMessage,
-            oneshot::Sender<Result<(), PeerManagerError>>,
-        )>,
-        oneshot::Sender<()>,
-    ) {
-        let (write_reqs_tx, mut write_reqs_rx): (
-            channel::Sender<(
-                NetworkMessage,
-                oneshot::Sender<Result<(), PeerManagerError>>,
-            )>,
-            _,
-        ) = channel::new(1024, &counters::PENDING_WIRE_MESSAGES);
+        mut writer: MessageWriter,
+        rate_limit: &RateLimitConfig,
+        messaging_protocol: MessagingProtocolVersion,
+        message_streams: bool,
+        max_message_size: usize,
+        traffic: Arc<ConnectionTraffic>,
+    ) -> (channel::Sender<WriteRequest>, oneshot::Sender<()>) {
+        let (write_reqs_tx, mut write_reqs_rx): (channel::Sender<WriteRequest>, _) =
+            channel::new(1024, &counters::PENDING_WIRE_MESSAGES);
         let (close_tx, close_rx) = oneshot::channel();
+        let mut peer_limiter = rate_limit::token_bucket(rate_limit.outbound_bytes_per_sec);
+        let mut protocol_limiter = ProtocolRateLimiter::new(rate_limit);
+        let mut queue = WriteQueue::new(messaging_protocol, !message_streams, max_message_size);
         let writer_task = async move {
             let mut close_rx = close_rx.into_stream();
             loop {
-                futures::select! {
-                    (message, ack_ch) = write_reqs_rx.select_next_some() => {
-                        if let Err(e) = writer
-                            .send(
-                                lcs::to_bytes(&message)
-                                    .expect("Outbound message failed to serialize")
-                                    .into(),
-                            )
-                            .map_ok(|_| ack_ch.send(Ok(())))
-                            .await
-                        {
-                            warn!(
-                                "Error in sending message to peer: {:?}. Error: {:?}",
-                                self_peer_id.short_str(),
-                                e
-                            );
-                            break;
-                        }
-                    },
-                    _ = close_rx.select_next_some() => {
-                        break;
+                // Wait for a message to write if there's none left.
+                if queue.is_empty() {
+                    futures::select! {
+                        (message, protocol, ack_ch) = write_reqs_rx.select_next_some() => {
+                            queue.push(message, protocol, ack_ch);
+                        },
+                        _ = close_rx.select_next_some() => break,
+                    }
+                }
+                // Schedule all the pending requests before picking the next frame to write.
+                while let Some(Some((message, protocol, ack_ch))) =
+                    write_reqs_rx.next().now_or_never()
+                {
+                    queue.push(message, protocol, ack_ch);
+                }
+                if let Some(Some(_)) = close_rx.next().now_or_never() {
+                    break;
+                }
+                let frame = match queue.pop() {
+                    Some(frame) => frame,
+                    None => continue,
+                };
+                traffic.set_pending_messages(queue.len());
+                let delay = std::cmp::max(
+                    peer_limiter.reserve(Instant::now(), frame.data.len() as u64),
+                    frame.protocol.map_or_else(
+                        || Duration::from_secs(0),
+                        |protocol| protocol_limiter.reserve_outbound(protocol, frame.data.len()),
+                    ),
+                );
+                if delay > Duration::from_secs(0) {
+                    rate_limit::count_rate_limited(
+                        counters::OUTBOUND_LABEL,
+                        frame.label,
+                        counters::DELAYED_LABEL,
+                    );
+                    // Keep listening for close while waiting out the rate limit.
+                    futures::select! {
+                        _ = delay_for(delay).fuse() => (),
+                        _ = close_rx.select_next_some() => break,
                     }
                 }
+                let size = frame.data.len();
+                rate_limit::count_bytes(counters::OUTBOUND_LABEL, frame.label, size);
+                if let Err(e) = writer.send(frame.data.into()).await {
+                    warn!(
+                        "Error in sending message to peer: {:?}. Error: {:?}",
+                        self_peer_id.short_str(),
+                        e
+                    );
+                    break;
+                }
+                traffic.count_sent(size, frame.ack_ch.is_some());
+                if let Some(ack_ch) = frame.ack_ch {
+                    let _ = ack_ch.send(Ok(()));
+                }
             }
             info!("Closing connection to peer: {:?}", self_peer_id.short_str());
             let flush_and_close = async move {
@@ -300,17 +431,64 @@ where
     async fn handle_inbound_message(
         &mut self,
         message: BytesMut,
-        mut write_reqs_tx: channel::Sender<(
-            NetworkMessage,
-            oneshot::Sender<Result<(), PeerManagerError>>,
-        )>,
+        mut write_reqs_tx: channel::Sender<WriteRequest>,
     ) -> Result<(), PeerManagerError> {
         trace!("Received message from Peer {}", self.peer_id().short_str(),);
         // Read inbound message from stream.
-        let message = message.freeze();
-        let message: NetworkMessage = lcs::from_bytes(&message)?;
+        let frame = message.freeze();
+        let (message, size): (NetworkMessage, usize) =
+            match self.connection_metadata.messaging_protocol() {
+                MessagingProtocolVersion::V1 => (lcs::from_bytes(&frame)?, frame.len()),
+                MessagingProtocolVersion::V2 | MessagingProtocolVersion::V3 => {
+                    match lcs::from_bytes(&frame)? {
+                        MultiplexMessage::Message(message) => (message, frame.len()),
+                        MultiplexMessage::Fragment(fragment) => {
+                            match self.reassembler.push(fragment)? {
+                                Some(message_and_size) => message_and_size,
+                                // Wait for the remaining fragments.
+                                None => return Ok(()),
+                            }
+                        }
+                    }
+                }
+            };
+        let protocol = rate_limit::message_protocol(&message);
+        self.record(Direction::Inbound, protocol, &message);
+        let label = rate_limit::protocol_label(&message, protocol);
+        rate_limit::count_bytes(counters::INBOUND_LABEL, label, size);
+        self.traffic.count_received(size);
+        if let Some(protocol) = protocol {
+            match self.inbound_limiter.admit_inbound(protocol, size) {
+                Admission::Accept => (),
+                Admission::Delay(delay) => {
+                    rate_limit::count_rate_limited(
+                        counters::INBOUND_LABEL,
+                        label,
+                        counters::DELAYED_LABEL,
+                    );
+                    // Stop reading from the peer until the protocol is within its limits.
+                    self.inbound_paused_until = Some(Instant::now() + delay);
+                }
+                Admission::Drop => {
+                    rate_limit::count_rate_limited(
+                        counters::INBOUND_LABEL,
+                        label,
+                        counters::DROPPED_LABEL,
+                    );
+                    trace!(
+                        "Dropped {} message from Peer {} exceeding rate limits",
+                        label,
+                        self.peer_id().short_str()
+                    );
+                    return Ok(());
+                }
+            }
+        }
         match message {
-            NetworkMessage::RpcRequest(_) | NetworkMessage::RpcResponse(_) => {
+            NetworkMessage::RpcRequest(_)
+            | NetworkMessage::RpcRequestWithDeadline(_)
+            | NetworkMessage::RpcCancel(_)
+            | NetworkMessage::RpcResponse(_) => {
                 let notif = PeerNotification::NewMessage(message);
                 self.rpc_notifs_tx.send(notif).await.map_err(|err| {
                     warn!("Failed to send notification to RPC actor. Error: {:?}", err);
@@ -334,9 +512,10 @@ where
             }
             NetworkMessage::Ping(nonce) => {
                 let pong = NetworkMessage::Pong(nonce);
+                self.record(Direction::Outbound, None, &pong);
                 let (ack_tx, _) = oneshot::channel();
                 // Resond to a ping right away.
-                write_reqs_tx.send((pong, ack_tx)).await?;
+                write_reqs_tx.send((pong, None, ack_tx)).await?;
                 Ok(())
             }
             _ => unreachable!("Unhandled"),
@@ -346,10 +525,7 @@ where
     async fn handle_request<'a>(
         &'a mut self,
         request: PeerRequest,
-        mut write_reqs_tx: channel::Sender<(
-            NetworkMessage,
-            oneshot::Sender<Result<(), PeerManagerError>>,
-        )>,
+        mut write_reqs_tx: channel::Sender<WriteRequest>,
     ) {
         trace!(
             "Peer {} PeerRequest::{:?}",
@@ -358,7 +534,8 @@ where
         );
         match request {
             PeerRequest::SendMessage(message, protocol, channel) => {
-                if let Err(e) = write_reqs_tx.send((message, channel)).await {
+                self.record(Direction::Outbound, Some(protocol), &message);
+                if let Err(e) = write_reqs_tx.send((message, Some(protocol), channel)).await {
                     error!(
                         "Failed to send message for protocol {:?} to peer: {:?}. Error: {:?}",
                         protocol,
@@ -373,6 +550,12 @@ where
         }
     }
 
+    fn record(&self, direction: Direction, protocol: Option<ProtocolId>, message: &NetworkMessage) {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(self.peer_id(), direction, protocol, message);
+        }
+    }
+
     async fn close_connection(&mut self, reason: DisconnectReason) {
         // Set the state of the actor to `State::ShuttingDown` to true ensures that the peer actor
         // will terminate and close the connection.
@@ -0,0 +1,155 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Per-protocol token buckets of a connection, built from the `RateLimitConfig`.
+//!
+//! The per-peer inbound limits are applied on the stream of frames read from the socket, see
+//! `Peer::start`. The per-protocol inbound limits are only known once a frame is decoded and
+//! follow the configured `InboundFairnessPolicy`. Outbound traffic is shaped by delaying writes.
+
+use crate::{counters, protocols::wire::messaging::v1::NetworkMessage, ProtocolId};
+use libra_config::config::{InboundFairnessPolicy, RateLimitConfig};
+use libra_logger::prelude::*;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use stream_ratelimiter::TokenBucket;
+
+/// Builds a bucket allowing `rate` tokens per second with bursts of up to one second of traffic.
+pub fn token_bucket(rate: Option<u64>) -> TokenBucket {
+    match rate {
+        Some(rate) if rate > 0 => TokenBucket::new(rate, rate),
+        _ => TokenBucket::unlimited(),
+    }
+}
+
+/// Protocol of a message for rate-limiting and accounting purposes. Rpc responses and pings
+/// don't carry their protocol.
+pub fn message_protocol(message: &NetworkMessage) -> Option<ProtocolId> {
+    match message {
+        NetworkMessage::RpcRequest(request) => Some(request.protocol_id),
+        NetworkMessage::RpcRequestWithDeadline(request) => Some(request.request.protocol_id),
+        NetworkMessage::DirectSendMsg(message) => Some(message.protocol_id),
+        _ => None,
+    }
+}
+
+/// Label of the traffic of a message in the network counters.
+pub fn protocol_label(message: &NetworkMessage, protocol: Option<ProtocolId>) -> &'static str {
+    match (protocol, message) {
+        (Some(protocol), _) => protocol.as_str(),
+        (None, NetworkMessage::RpcResponse(_)) => "RpcResponse",
+        (None, NetworkMessage::RpcCancel(_)) => "RpcCancel",
+        (None, NetworkMessage::Ping(_)) | (None, NetworkMessage::Pong(_)) => "Ping",
+        (None, _) => "Other",
+    }
+}
+
+/// Outcome of rate-limiting an inbound message.
+#[derive(Debug, PartialEq)]
+pub enum Admission {
+    Accept,
+    /// Accept the message and stop reading from the peer for the given delay.
+    Delay(Duration),
+    Drop,
+}
+
+struct ProtocolBuckets {
+    inbound_bytes: TokenBucket,
+    inbound_messages: TokenBucket,
+    outbound_bytes: TokenBucket,
+}
+
+/// The per-protocol limits of a connection.
+pub struct ProtocolRateLimiter {
+    policy: InboundFairnessPolicy,
+    protocols: HashMap<ProtocolId, ProtocolBuckets>,
+}
+
+impl ProtocolRateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        for name in config.protocols.keys() {
+            if !ProtocolId::all().iter().any(|p| p.as_str() == name) {
+                warn!("Ignoring rate limits of unknown protocol {}", name);
+            }
+        }
+        let protocols = ProtocolId::all()
+            .iter()
+            .filter_map(|protocol| {
+                config.protocols.get(protocol.as_str()).map(|limits| {
+                    (
+                        *protocol,
+                        ProtocolBuckets {
+                            inbound_bytes: token_bucket(limits.inbound_bytes_per_sec),
+                            inbound_messages: token_bucket(limits.inbound_messages_per_sec),
+                            outbound_bytes: token_bucket(limits.outbound_bytes_per_sec),
+                        },
+                    )
+                })
+            })
+            .collect();
+        Self {
+            policy: config.inbound_fairness,
+            protocols,
+        }
+    }
+
+    /// Charges an inbound message of `size` bytes to its protocol.
+    pub fn admit_inbound(&mut self, protocol: ProtocolId, size: usize) -> Admission {
+        let buckets = match self.protocols.get_mut(&protocol) {
+            Some(buckets) => buckets,
+            None => return Admission::Accept,
+        };
+        let now = Instant::now();
+        match self.policy {
+            InboundFairnessPolicy::DropExcess => {
+                // Check both budgets before taking either, so a message dropped for its size does
+                // not also cost a message token.
+                if buckets.inbound_messages.can_acquire(now, 1)
+                    && buckets.inbound_bytes.can_acquire(now, size as u64)
+                {
+                    buckets.inbound_messages.try_acquire(now, 1);
+                    buckets.inbound_bytes.try_acquire(now, size as u64);
+                    Admission::Accept
+                } else {
+                    Admission::Drop
+                }
+            }
+            InboundFairnessPolicy::Backpressure => {
+                let delay = std::cmp::max(
+                    buckets.inbound_messages.reserve(now, 1),
+                    buckets.inbound_bytes.reserve(now, size as u64),
+                );
+                if delay > Duration::from_secs(0) {
+                    Admission::Delay(delay)
+                } else {
+                    Admission::Accept
+                }
+            }
+        }
+    }
+
+    /// Charges an outbound message of `size` bytes to its protocol and returns how long to wait
+    /// before writing it.
+    pub fn reserve_outbound(&mut self, protocol: ProtocolId, size: usize) -> Duration {
+        self.protocols
+            .get_mut(&protocol)
+            .map(|buckets| buckets.outbound_bytes.reserve(Instant::now(), size as u64))
+            .unwrap_or_else(|| Duration::from_secs(0))
+    }
+}
+
+/// Counts `size` bytes of traffic in the given direction.
+pub fn count_bytes(direction: &str, label: &str, size: usize) {
+    counters::LIBRA_NETWORK_BYTES
+        .with_label_values(&[direction, label])
+        .inc_by(size as i64);
+}
+
+/// Counts a message delayed or dropped by a rate limit.
+pub fn count_rate_limited(direction: &str, label: &str, action: &str) {
+    counters::LIBRA_NETWORK_RATE_LIMITED_MESSAGES
+        .with_label_values(&[direction, label, action])
+        .inc();
+}
@@ -3,20 +3,25 @@
 
 use crate::{
     constants,
-    peer::{DisconnectReason, Peer, PeerHandle, PeerNotification},
+    peer::{rate_limit::ProtocolRateLimiter, DisconnectReason, Peer, PeerHandle, PeerNotification},
+    peer_manager::status::ConnectionTraffic,
     protocols::wire::{
         handshake::v1::MessagingProtocolVersion,
-        messaging::v1::{DirectSendMsg, NetworkMessage},
+        messaging::{
+            v1::{DirectSendMsg, NetworkMessage},
+            v2::{Fragmenter, MultiplexMessage, Reassembler},
+        },
     },
     transport::{Connection, ConnectionId, ConnectionMetadata},
     ProtocolId,
 };
 use futures::{future::join, io::AsyncWriteExt, stream::StreamExt, SinkExt};
+use libra_config::config::{InboundFairnessPolicy, ProtocolRateLimit, RateLimitConfig};
 use libra_network_address::NetworkAddress;
 use libra_types::PeerId;
 use memsocket::MemorySocket;
 use netcore::{compat::IoCompat, transport::ConnectionOrigin};
-use std::{mem::ManuallyDrop, str::FromStr, time::Duration};
+use std::{mem::ManuallyDrop, str::FromStr, sync::Arc, time::Duration};
 use tokio::{
     runtime::{Handle, Runtime},
     time::timeout,
@@ -35,6 +40,21 @@ fn build_test_peer(
     channel::Receiver<PeerNotification>,
     channel::Receiver<PeerNotification>,
     channel::Receiver<PeerNotification>,
+) {
+    build_test_peer_with_protocol(executor, origin, MessagingProtocolVersion::V1)
+}
+
+fn build_test_peer_with_protocol(
+    executor: Handle,
+    origin: ConnectionOrigin,
+    messaging_protocol: MessagingProtocolVersion,
+) -> (
+    Peer<MemorySocket>,
+    PeerHandle,
+    MemorySocket,
+    channel::Receiver<PeerNotification>,
+    channel::Receiver<PeerNotification>,
+    channel::Receiver<PeerNotification>,
 ) {
     let (a, b) = MemorySocket::new_pair();
     let peer_id = PeerId::random();
@@ -48,7 +68,7 @@ fn build_test_peer(
             ConnectionId::default(),
             NetworkAddress::from_str("/ip4/127.0.0.1/tcp/8081").unwrap(),
             origin,
-            MessagingProtocolVersion::V1,
+            messaging_protocol,
             [].iter().into(),
         ),
         socket: a,
@@ -62,6 +82,9 @@ fn build_test_peer(
         peer_rpc_notifs_tx,
         peer_direct_send_notifs_tx,
         constants::MAX_FRAME_SIZE,
+        RateLimitConfig::default(),
+        None,
+        Arc::new(ConnectionTraffic::default()),
     );
     let peer_handle = PeerHandle::new(peer_id, peer_req_tx);
 
@@ -240,6 +263,214 @@ fn peer_recv_message() {
     rt.block_on(join(server, client));
 }
 
+// With messaging protocol v2, large messages are sent in fragments which can be interleaved with
+// other messages.
+#[test]
+fn peer_fragmented_messages() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+    let mut rt = Runtime::new().unwrap();
+    let (
+        peer,
+        mut peer_handle,
+        connection,
+        _peer_notifs_rx,
+        _peer_rpc_notifs_rx,
+        mut peer_direct_send_notifs_rx,
+    ) = build_test_peer_with_protocol(
+        rt.handle().clone(),
+        ConnectionOrigin::Inbound,
+        MessagingProtocolVersion::V2,
+    );
+
+    let message = |protocol_id, size| {
+        NetworkMessage::DirectSendMsg(DirectSendMsg {
+            protocol_id,
+            priority: 0,
+            raw_msg: vec![7; size],
+        })
+    };
+    let large_msg = message(ProtocolId::StateSynchronizerDirectSend, 200 * 1024);
+    let small_msg = message(ProtocolId::ConsensusDirectSend, 10);
+    let (recv_large_msg, recv_small_msg) = (large_msg.clone(), small_msg.clone());
+    let send_large_msg = large_msg.clone();
+
+    let server = async move {
+        let mut connection = Framed::new(IoCompat::new(connection), LengthDelimitedCodec::new());
+        // Send the small message in the middle of the fragments of the large one.
+        let mut frames = Fragmenter::default().frames(large_msg).unwrap();
+        assert!(frames.len() > 1);
+        let small_frame = lcs::to_bytes(&MultiplexMessage::Message(small_msg)).unwrap();
+        frames.insert(1, small_frame);
+        for frame in frames {
+            connection.send(frame.into()).await.unwrap();
+        }
+
+        // The peer sends large messages in fragments.
+        let mut reassembler = Reassembler::new(constants::MAX_FRAME_SIZE);
+        loop {
+            let frame = connection.next().await.unwrap().unwrap().freeze();
+            match lcs::from_bytes(&frame).unwrap() {
+                MultiplexMessage::Fragment(fragment) => {
+                    if let Some((message, _)) = reassembler.push(fragment).unwrap() {
+                        assert_eq!(message, recv_large_msg);
+                        break;
+                    }
+                }
+                message => panic!("Unexpected frame: {:?}", message),
+            }
+        }
+        connection.close().await.unwrap();
+    };
+
+    let client = async move {
+        for expected in vec![recv_small_msg, send_large_msg.clone()] {
+            let received = peer_direct_send_notifs_rx.next().await.unwrap();
+            assert!(
+                matches!(received, PeerNotification::NewMessage(received_msg) if received_msg == expected)
+            );
+        }
+        peer_handle
+            .send_message(send_large_msg, ProtocolId::StateSynchronizerDirectSend)
+            .await
+            .unwrap();
+        ManuallyDrop::new(peer_handle);
+    };
+    rt.spawn(peer.start());
+    rt.block_on(join(server, client));
+}
+
+// Messages exceeding the inbound limit of their protocol are dropped, without affecting the
+// other protocols.
+#[test]
+fn peer_drop_excess_messages() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+    let mut rt = Runtime::new().unwrap();
+    let (
+        mut peer,
+        _peer_handle,
+        connection,
+        _peer_notifs_rx,
+        _peer_rpc_notifs_rx,
+        mut peer_direct_send_notifs_rx,
+    ) = build_test_peer(rt.handle().clone(), ConnectionOrigin::Inbound);
+    let mut rate_limit = RateLimitConfig::default();
+    rate_limit.inbound_fairness = InboundFairnessPolicy::DropExcess;
+    rate_limit.protocols.insert(
+        PROTOCOL.as_str().to_string(),
+        ProtocolRateLimit {
+            inbound_messages_per_sec: Some(5),
+            ..ProtocolRateLimit::default()
+        },
+    );
+    peer.inbound_limiter = ProtocolRateLimiter::new(&rate_limit);
+
+    let message = |protocol_id| {
+        NetworkMessage::DirectSendMsg(DirectSendMsg {
+            protocol_id,
+            priority: 0,
+            raw_msg: Vec::from("hello world"),
+        })
+    };
+    let send_msg = message(PROTOCOL);
+    let last_msg = message(ProtocolId::ConsensusDirectSend);
+    let recv_last_msg = last_msg.clone();
+
+    let server = async move {
+        let mut connection = Framed::new(IoCompat::new(connection), LengthDelimitedCodec::new());
+        for _ in 0..10 {
+            connection
+                .send(lcs::to_bytes(&send_msg).unwrap().into())
+                .await
+                .unwrap();
+        }
+        connection
+            .send(lcs::to_bytes(&last_msg).unwrap().into())
+            .await
+            .unwrap();
+        connection.close().await.unwrap();
+    };
+
+    let client = async move {
+        let mut count = 0;
+        loop {
+            match peer_direct_send_notifs_rx.next().await.unwrap() {
+                PeerNotification::NewMessage(message) if message == recv_last_msg => break,
+                PeerNotification::NewMessage(_) => count += 1,
+                event => panic!("Unexpected event: {:?}", event),
+            }
+        }
+        // The burst allows 5 messages, a few more may fit in as the bucket refills.
+        assert!(count >= 5 && count < 10, "count: {}", count);
+    };
+    rt.spawn(peer.start());
+    rt.block_on(join(server, client));
+}
+
+// While inbound messages are delayed by a protocol limit, the peer keeps handling requests.
+#[test]
+fn peer_backpressure_does_not_block_requests() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+    let mut rt = Runtime::new().unwrap();
+    let (
+        mut peer,
+        mut peer_handle,
+        connection,
+        _peer_notifs_rx,
+        _peer_rpc_notifs_rx,
+        mut peer_direct_send_notifs_rx,
+    ) = build_test_peer(rt.handle().clone(), ConnectionOrigin::Inbound);
+    let mut rate_limit = RateLimitConfig::default();
+    rate_limit.inbound_fairness = InboundFairnessPolicy::Backpressure;
+    // A single message takes several seconds of the inbound budget.
+    rate_limit.protocols.insert(
+        PROTOCOL.as_str().to_string(),
+        ProtocolRateLimit {
+            inbound_bytes_per_sec: Some(1),
+            ..ProtocolRateLimit::default()
+        },
+    );
+    peer.inbound_limiter = ProtocolRateLimiter::new(&rate_limit);
+
+    let send_msg = NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: PROTOCOL,
+        priority: 0,
+        raw_msg: Vec::from("hello world"),
+    });
+    let recv_msg = send_msg.clone();
+    let outbound_msg = send_msg.clone();
+
+    let server = async move {
+        let mut connection = Framed::new(IoCompat::new(connection), LengthDelimitedCodec::new());
+        connection
+            .send(lcs::to_bytes(&send_msg).unwrap().into())
+            .await
+            .unwrap();
+        let msg = connection.next().await.unwrap();
+        let msg: NetworkMessage = lcs::from_bytes(&msg.unwrap().freeze()).unwrap();
+        assert_eq!(msg, recv_msg);
+    };
+
+    let client = async move {
+        // The delayed message is delivered right away, the peer only stops reading.
+        timeout(
+            Duration::from_secs(1),
+            assert_new_message_event(&mut peer_direct_send_notifs_rx),
+        )
+        .await
+        .unwrap();
+        timeout(
+            Duration::from_secs(1),
+            peer_handle.send_message(outbound_msg, PROTOCOL),
+        )
+        .await
+        .expect("The request is handled while reading is paused")
+        .unwrap();
+        ManuallyDrop::new(peer_handle);
+    };
+    rt.spawn(peer.start());
+    rt.block_on(join(server, client));
+}
+
 // Test that if two peers request to open a substream with each other simultaneously that
 // we won't deadlock.
 #[test]
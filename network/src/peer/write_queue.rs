@@ -0,0 +1,327 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Outbound messages waiting to be written on a connection.
+//!
+//! Messages are serialized into frames as per the messaging protocol of the connection, and
+//! scheduled by priority class: frames of a more urgent class are always written first, so that
+//! a message split into many frames only delays messages of its own class or of less urgent
+//! classes. Within a class, messages are written in order.
+
+use crate::{
+    peer::rate_limit,
+    peer_manager::PeerManagerError,
+    protocols::wire::{
+        handshake::v1::MessagingProtocolVersion,
+        messaging::{
+            v1::NetworkMessage,
+            v2::{Fragmenter, MultiplexMessage},
+        },
+    },
+    ProtocolId,
+};
+use anyhow::format_err;
+use futures::channel::oneshot;
+use libra_logger::prelude::*;
+use std::collections::VecDeque;
+
+/// Number of priority classes, class 0 being the most urgent.
+pub const NUM_PRIORITY_CLASSES: usize = 3;
+
+/// Consensus and liveness traffic goes first, state sync last.
+pub fn priority_class(protocol: ProtocolId) -> usize {
+    match protocol {
+        ProtocolId::ConsensusRpc
+        | ProtocolId::ConsensusDirectSend
+        | ProtocolId::HealthCheckerRpc => 0,
+        ProtocolId::MempoolDirectSend
+        | ProtocolId::DiscoveryDirectSend
+        | ProtocolId::PeerExchangeRpc => 1,
+        ProtocolId::StateSynchronizerDirectSend => 2,
+    }
+}
+
+/// Priority class of an outbound message. RPC responses and cancellations are accounted to the
+/// protocol of their request, so a large response is no more urgent than its request. Any other
+/// message without a protocol, except for pings, is least urgent.
+fn message_class(message: &NetworkMessage, protocol: Option<ProtocolId>) -> usize {
+    match (message, protocol) {
+        (NetworkMessage::Ping(_), _) | (NetworkMessage::Pong(_), _) => 0,
+        (_, Some(protocol)) => priority_class(protocol),
+        (_, None) => NUM_PRIORITY_CLASSES - 1,
+    }
+}
+
+/// A serialized message, split into one or more frames.
+struct PendingMessage {
+    /// Protocol the message is accounted to, `None` for pings and pongs.
+    protocol: Option<ProtocolId>,
+    /// Priority class of the message.
+    class: usize,
+    /// Label of the message in the network counters.
+    label: &'static str,
+    frames: VecDeque<Vec<u8>>,
+    /// Notified once the last frame is written.
+    ack_ch: Option<oneshot::Sender<Result<(), PeerManagerError>>>,
+}
+
+impl PendingMessage {
+    fn new(
+        protocol: Option<ProtocolId>,
+        class: usize,
+        label: &'static str,
+        frames: Vec<Vec<u8>>,
+        ack_ch: oneshot::Sender<Result<(), PeerManagerError>>,
+    ) -> Self {
+        Self {
+            protocol,
+            class,
+            label,
+            frames: frames.into(),
+            ack_ch: Some(ack_ch),
+        }
+    }
+}
+
+/// A frame to write on the wire.
+pub struct Frame {
+    pub data: Vec<u8>,
+    pub protocol: Option<ProtocolId>,
+    pub label: &'static str,
+    /// Set on the last frame of a message.
+    pub ack_ch: Option<oneshot::Sender<Result<(), PeerManagerError>>>,
+}
+
+pub struct WriteQueue {
+    messaging_protocol: MessagingProtocolVersion,
+    /// Whether large messages are split into fragments. Messages sent on their own streams are
+    /// never fragmented, since fragments could then be received out of order.
+    fragment: bool,
+    fragmenter: Fragmenter,
+    /// Max size of a serialized message, larger messages are rejected.
+    max_message_size: usize,
+    classes: [VecDeque<PendingMessage>; NUM_PRIORITY_CLASSES],
+}
+
+impl WriteQueue {
+    pub fn new(
+        messaging_protocol: MessagingProtocolVersion,
+        fragment: bool,
+        max_message_size: usize,
+    ) -> Self {
+        Self {
+            messaging_protocol,
+            fragment,
+            fragmenter: Fragmenter::default(),
+            max_message_size,
+            classes: Default::default(),
+        }
+    }
+
+    /// Queues a message accounted to `protocol`. `ack_ch` is notified once the message is
+    /// written, or right away if it is too large.
+    pub fn push(
+        &mut self,
+        message: NetworkMessage,
+        protocol: Option<ProtocolId>,
+        ack_ch: oneshot::Sender<Result<(), PeerManagerError>>,
+    ) {
+        let label = rate_limit::protocol_label(&message, protocol);
+        let class = message_class(&message, protocol);
+        let frames = match self.messaging_protocol {
+            MessagingProtocolVersion::V1 => lcs::to_bytes(&message).map(|frame| vec![frame]),
+            MessagingProtocolVersion::V2 | MessagingProtocolVersion::V3 if self.fragment => {
+                self.fragmenter.frames(message)
+            }
+            MessagingProtocolVersion::V2 | MessagingProtocolVersion::V3 => {
+                lcs::to_bytes(&MultiplexMessage::Message(message)).map(|frame| vec![frame])
+            }
+        }
+        .expect("Outbound message failed to serialize");
+        let size: usize = frames.iter().map(Vec::len).sum();
+        if size > self.max_message_size {
+            warn!(
+                "Dropping {} message of {} bytes exceeding the max size",
+                label, size
+            );
+            let _ = ack_ch.send(Err(PeerManagerError::Error(format_err!(
+                "Message of {} bytes exceeds the max size {}",
+                size,
+                self.max_message_size
+            ))));
+            return;
+        }
+        self.push_message(PendingMessage::new(protocol, class, label, frames, ack_ch));
+    }
+
+    fn push_message(&mut self, message: PendingMessage) {
+        self.classes[message.class].push_back(message);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.iter().all(VecDeque::is_empty)
+    }
+
+    /// Number of messages with frames left to write.
+    pub fn len(&self) -> usize {
+        self.classes.iter().map(VecDeque::len).sum()
+    }
+
+    /// Pops the next frame of the oldest message of the most urgent class.
+    pub fn pop(&mut self) -> Option<Frame> {
+        let class = self.classes.iter_mut().find(|class| !class.is_empty())?;
+        let message = class.front_mut()?;
+        let data = message
+            .frames
+            .pop_front()
+            .expect("Pending messages have frames left");
+        let ack_ch = if message.frames.is_empty() {
+            message.ack_ch.take()
+        } else {
+            None
+        };
+        let frame = Frame {
+            data,
+            protocol: message.protocol,
+            label: message.label,
+            ack_ch,
+        };
+        if frame.ack_ch.is_some() {
+            class.pop_front();
+        }
+        Some(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocols::wire::messaging::{
+        v1::{DirectSendMsg, Nonce, RpcResponse},
+        v2::MAX_FRAGMENT_SIZE,
+    };
+
+    fn message(protocol: ProtocolId, frames: &[u8]) -> PendingMessage {
+        let (ack_tx, _) = oneshot::channel();
+        PendingMessage::new(
+            Some(protocol),
+            priority_class(protocol),
+            protocol.as_str(),
+            frames.iter().map(|frame| vec![*frame]).collect(),
+            ack_tx,
+        )
+    }
+
+    #[test]
+    fn test_priorities() {
+        let mut queue = WriteQueue::new(MessagingProtocolVersion::V2, true, 1024);
+        assert!(queue.is_empty());
+        queue.push_message(message(ProtocolId::StateSynchronizerDirectSend, &[1, 2, 3]));
+        queue.push_message(message(ProtocolId::StateSynchronizerDirectSend, &[4]));
+
+        let frame = queue.pop().unwrap();
+        assert_eq!(frame.data, vec![1]);
+        assert!(frame.ack_ch.is_none());
+        assert_eq!(queue.len(), 2);
+
+        // Consensus messages jump ahead of the remaining state sync frames.
+        queue.push_message(message(ProtocolId::MempoolDirectSend, &[10]));
+        queue.push_message(message(ProtocolId::ConsensusDirectSend, &[20]));
+        assert_eq!(queue.len(), 4);
+        let frames: Vec<_> = std::iter::from_fn(|| queue.pop())
+            .map(|frame| (frame.data[0], frame.ack_ch.is_some()))
+            .collect();
+        assert_eq!(
+            frames,
+            vec![(20, true), (10, true), (2, false), (3, true), (4, true)]
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_max_message_size() {
+        let mut queue = WriteQueue::new(MessagingProtocolVersion::V1, true, 100);
+        let direct_send = |size| {
+            NetworkMessage::DirectSendMsg(DirectSendMsg {
+                protocol_id: ProtocolId::MempoolDirectSend,
+                priority: 0,
+                raw_msg: vec![0; size],
+            })
+        };
+
+        let (ack_tx, mut ack_rx) = oneshot::channel();
+        queue.push(
+            direct_send(200),
+            Some(ProtocolId::MempoolDirectSend),
+            ack_tx,
+        );
+        assert!(queue.is_empty());
+        assert!(matches!(ack_rx.try_recv(), Ok(Some(Err(_)))));
+
+        let (ack_tx, _ack_rx) = oneshot::channel();
+        queue.push(direct_send(10), Some(ProtocolId::MempoolDirectSend), ack_tx);
+        let frame = queue.pop().unwrap();
+        assert_eq!(
+            lcs::from_bytes::<NetworkMessage>(&frame.data).unwrap(),
+            direct_send(10)
+        );
+        assert!(frame.ack_ch.is_some());
+    }
+
+    #[test]
+    fn test_response_priorities() {
+        let mut queue = WriteQueue::new(MessagingProtocolVersion::V2, true, 1024);
+        let response = |request_id| {
+            NetworkMessage::RpcResponse(RpcResponse {
+                request_id,
+                priority: 0,
+                raw_response: vec![],
+            })
+        };
+        let push = |queue: &mut WriteQueue, message, protocol| {
+            let (ack_tx, _) = oneshot::channel();
+            queue.push(message, protocol, ack_tx);
+        };
+        push(&mut queue, response(0), None);
+        push(
+            &mut queue,
+            response(1),
+            Some(ProtocolId::StateSynchronizerDirectSend),
+        );
+        push(&mut queue, response(2), Some(ProtocolId::MempoolDirectSend));
+        push(&mut queue, NetworkMessage::Ping(Nonce(3)), None);
+        push(&mut queue, response(4), Some(ProtocolId::ConsensusRpc));
+
+        // Responses go with the class of their request's protocol, behind pings.
+        let order: Vec<_> = std::iter::from_fn(|| queue.pop())
+            .map(|frame| match lcs::from_bytes(&frame.data).unwrap() {
+                MultiplexMessage::Message(NetworkMessage::RpcResponse(response)) => {
+                    response.request_id
+                }
+                MultiplexMessage::Message(NetworkMessage::Ping(Nonce(nonce))) => nonce,
+                message => panic!("Unexpected message: {:?}", message),
+            })
+            .collect();
+        assert_eq!(order, vec![3, 4, 2, 0, 1]);
+    }
+
+    #[test]
+    fn test_unfragmented() {
+        let message = NetworkMessage::DirectSendMsg(DirectSendMsg {
+            protocol_id: ProtocolId::StateSynchronizerDirectSend,
+            priority: 0,
+            raw_msg: vec![0; 3 * MAX_FRAGMENT_SIZE],
+        });
+        for &fragment in &[true, false] {
+            let mut queue = WriteQueue::new(MessagingProtocolVersion::V3, fragment, 1 << 20);
+            let (ack_tx, _ack_rx) = oneshot::channel();
+            queue.push(
+                message.clone(),
+                Some(ProtocolId::StateSynchronizerDirectSend),
+                ack_tx,
+            );
+            let num_frames = std::iter::from_fn(|| queue.pop()).count();
+            assert_eq!(num_frames, if fragment { 4 } else { 1 });
+        }
+    }
+}
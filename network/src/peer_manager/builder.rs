@@ -2,18 +2,23 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    capture::MessageRecorder,
     counters,
-    noise::stream::NoiseStream,
+    noise::{stream::NoiseStream, IdentityKeys},
     peer_manager::{
-        conn_notifs_channel, ConnectionRequest, ConnectionRequestSender, PeerManager,
-        PeerManagerNotification, PeerManagerRequest, PeerManagerRequestSender,
+        conn_notifs_channel, ConnectionRequest, ConnectionRequestSender, NetworkStatus,
+        PeerManager, PeerManagerNotification, PeerManagerRequest, PeerManagerRequestSender,
+        PeerScores,
     },
-    protocols::wire::handshake::v1::SupportedProtocols,
-    transport::{self, Connection, LibraNetTransport, LIBRA_TCP_TRANSPORT},
+    protocols::{health_checker::PeerLatencies, wire::handshake::v1::SupportedProtocols},
+    transport::{self, libra_quic_transport, Connection, LibraNetTransport, LIBRA_TCP_TRANSPORT},
     ProtocolId,
 };
 use channel::{self, libra_channel, message_queues::QueueStyle};
-use libra_config::{config::HANDSHAKE_VERSION, network_id::NetworkContext};
+use libra_config::{
+    config::{CaptureConfig, PeerReputationConfig, RateLimitConfig, HANDSHAKE_VERSION},
+    network_id::NetworkContext,
+};
 use libra_crypto::x25519;
 use libra_logger::prelude::*;
 use libra_metrics::IntCounterVec;
@@ -21,6 +26,7 @@ use libra_network_address::NetworkAddress;
 use libra_types::{chain_id::ChainId, PeerId};
 use netcore::transport::{
     memory::MemoryTransport,
+    quic::{QuicSocket, QuicTransport},
     tcp::{TcpSocket, TcpTransport},
     Transport,
 };
@@ -65,7 +71,7 @@ struct TransportContext {
     chain_id: ChainId,
     direct_send_protocols: Vec<ProtocolId>,
     rpc_protocols: Vec<ProtocolId>,
-    authentication_mode: AuthenticationMode,
+    mutual_authentication: bool,
     trusted_peers: Arc<RwLock<HashMap<PeerId, HashSet<x25519::PublicKey>>>>,
 }
 
@@ -74,14 +80,14 @@ impl TransportContext {
         chain_id: ChainId,
         direct_send_protocols: Vec<ProtocolId>,
         rpc_protocols: Vec<ProtocolId>,
-        authentication_mode: AuthenticationMode,
+        mutual_authentication: bool,
         trusted_peers: Arc<RwLock<HashMap<PeerId, HashSet<x25519::PublicKey>>>>,
     ) -> Self {
         Self {
             chain_id,
             direct_send_protocols,
             rpc_protocols,
-            authentication_mode,
+            mutual_authentication,
             trusted_peers,
         }
     }
@@ -174,6 +180,7 @@ impl PeerManagerContext {
 type MemoryPeerManager =
     PeerManager<LibraNetTransport<MemoryTransport>, NoiseStream<memsocket::MemorySocket>>;
 type TcpPeerManager = PeerManager<LibraNetTransport<TcpTransport>, NoiseStream<TcpSocket>>;
+type QuicPeerManager = PeerManager<LibraNetTransport<QuicTransport>, NoiseStream<QuicSocket>>;
 
 #[derive(Debug, PartialEq, PartialOrd)]
 enum State {
@@ -190,10 +197,21 @@ pub struct PeerManagerBuilder {
     // An option to ensure at most one copy of the contained private key.
     memory_peer_manager: Option<MemoryPeerManager>,
     tcp_peer_manager: Option<TcpPeerManager>,
+    quic_peer_manager: Option<QuicPeerManager>,
+    identity_keys: Arc<IdentityKeys>,
+    /// Latencies of the peers, shared by the senders of the protocol handlers.
+    peer_latencies: PeerLatencies,
+    /// Scores of the peers, shared by the senders of the protocol handlers.
+    peer_scores: PeerScores,
+    /// Connections of the network, for operators.
+    status: NetworkStatus,
     // ListenAddress will be updated when the PeerManager is built
     listen_address: NetworkAddress,
     state: State,
     max_frame_size: usize,
+    rate_limit: RateLimitConfig,
+    peer_reputation: PeerReputationConfig,
+    capture: CaptureConfig,
 }
 
 impl PeerManagerBuilder {
@@ -221,6 +239,12 @@ impl PeerManagerBuilder {
             NonZeroUsize::new(channel_size).unwrap(),
             None,
         );
+        let peer_latencies = PeerLatencies::default();
+        let status = NetworkStatus::new(network_context.clone(), peer_latencies.clone());
+        let (key, mutual_authentication) = match authentication_mode {
+            AuthenticationMode::ServerOnly(key) => (key, false),
+            AuthenticationMode::Mutual(key) => (key, true),
+        };
 
         Self {
             network_context,
@@ -228,7 +252,7 @@ impl PeerManagerBuilder {
                 chain_id,
                 Vec::new(),
                 Vec::new(),
-                authentication_mode,
+                mutual_authentication,
                 trusted_peers,
             )),
             peer_manager_context: Some(PeerManagerContext::new(
@@ -244,9 +268,17 @@ impl PeerManagerBuilder {
             )),
             memory_peer_manager: None,
             tcp_peer_manager: None,
+            quic_peer_manager: None,
+            identity_keys: Arc::new(IdentityKeys::new(key)),
+            peer_latencies,
+            peer_scores: PeerScores::default(),
+            status,
             listen_address,
             state: State::CREATED,
             max_frame_size,
+            rate_limit: RateLimitConfig::default(),
+            peer_reputation: PeerReputationConfig::default(),
+            capture: CaptureConfig::default(),
         }
     }
 
@@ -269,6 +301,35 @@ impl PeerManagerBuilder {
             .add_connection_event_listener()
     }
 
+    /// The keys authenticating this node in handshakes, to rotate them without restarting the
+    /// network.
+    pub fn identity_keys(&self) -> Arc<IdentityKeys> {
+        self.identity_keys.clone()
+    }
+
+    /// A handle to snapshot the connections of the network.
+    pub fn network_status(&self) -> NetworkStatus {
+        self.status.clone()
+    }
+
+    /// Set the rate limits applied to every connection.
+    pub fn rate_limit(&mut self, rate_limit: RateLimitConfig) -> &mut Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Set when misbehaving peers get banned.
+    pub fn peer_reputation(&mut self, peer_reputation: PeerReputationConfig) -> &mut Self {
+        self.peer_reputation = peer_reputation;
+        self
+    }
+
+    /// Set whether and where the messages exchanged with peers are recorded.
+    pub fn capture(&mut self, capture: CaptureConfig) -> &mut Self {
+        self.capture = capture;
+        self
+    }
+
     /// Create the configured transport and start PeerManager.
     /// Return the actual NetworkAddress over which this peer is listening.
     pub fn build(&mut self, executor: &Handle) -> &mut Self {
@@ -286,55 +347,64 @@ impl PeerManagerBuilder {
         let network_id = self.network_context.network_id().clone();
         let peer_id = self.network_context.peer_id();
 
-        let (key, maybe_trusted_peers, peer_id) = match transport_context.authentication_mode {
+        let (maybe_trusted_peers, peer_id) = if transport_context.mutual_authentication {
+            // validator
+            (Some(transport_context.trusted_peers), peer_id)
+        } else if peer_id == PeerId::ZERO {
             // validator-operated full node
-            AuthenticationMode::ServerOnly(key) if peer_id == PeerId::ZERO => {
-                let public_key = key.public_key();
-                let peer_id = PeerId::from_identity_public_key(public_key);
-                (key, None, peer_id)
-            }
+            (
+                None,
+                PeerId::from_identity_public_key(self.identity_keys.public_key()),
+            )
+        } else {
             // full node
-            AuthenticationMode::ServerOnly(key) => (key, None, peer_id),
-            // validator
-            AuthenticationMode::Mutual(key) => {
-                (key, Some(transport_context.trusted_peers), peer_id)
-            }
+            (None, peer_id)
         };
 
         match self.listen_address.as_slice() {
             [Ip4(_), Tcp(_)] | [Ip6(_), Tcp(_)] => {
-                self.tcp_peer_manager = Some(self.build_with_transport(
-                    LibraNetTransport::new(
-                        LIBRA_TCP_TRANSPORT.clone(),
-                        peer_id,
-                        key,
-                        maybe_trusted_peers,
-                        HANDSHAKE_VERSION,
-                        chain_id,
-                        network_id,
-                        protos,
-                    ),
-                    executor,
-                ))
+                let transport = LibraNetTransport::new(
+                    LIBRA_TCP_TRANSPORT.clone(),
+                    peer_id,
+                    self.identity_keys.clone(),
+                    maybe_trusted_peers,
+                    HANDSHAKE_VERSION,
+                    chain_id,
+                    network_id,
+                    protos,
+                );
+                self.tcp_peer_manager = Some(self.build_with_transport(transport, executor))
+            }
+            [Ip4(_), Quic(_)] | [Ip6(_), Quic(_)] => {
+                let transport = LibraNetTransport::new(
+                    libra_quic_transport(),
+                    peer_id,
+                    self.identity_keys.clone(),
+                    maybe_trusted_peers,
+                    HANDSHAKE_VERSION,
+                    chain_id,
+                    network_id,
+                    protos,
+                );
+                self.quic_peer_manager = Some(self.build_with_transport(transport, executor))
             }
             [Memory(_)] => {
-                self.memory_peer_manager = Some(self.build_with_transport(
-                    LibraNetTransport::new(
-                        MemoryTransport,
-                        peer_id,
-                        key,
-                        maybe_trusted_peers,
-                        HANDSHAKE_VERSION,
-                        chain_id,
-                        network_id,
-                        protos,
-                    ),
-                    executor,
-                ))
+                let transport = LibraNetTransport::new(
+                    MemoryTransport,
+                    peer_id,
+                    self.identity_keys.clone(),
+                    maybe_trusted_peers,
+                    HANDSHAKE_VERSION,
+                    chain_id,
+                    network_id,
+                    protos,
+                );
+                self.memory_peer_manager = Some(self.build_with_transport(transport, executor))
             }
             _ => panic!(
                 "{} Unsupported listen_address: '{}', expected '/memory/<port>', \
-                 '/ip4/<addr>/tcp/<port>', or '/ip6/<addr>/tcp/<port>'.",
+                 '/ip4/<addr>/tcp/<port>', '/ip6/<addr>/tcp/<port>', \
+                 '/ip4/<addr>/quic/<port>', or '/ip6/<addr>/quic/<port>'.",
                 self.network_context, self.listen_address
             ),
         };
@@ -358,6 +428,23 @@ impl PeerManagerBuilder {
             .take()
             .expect("PeerManager can only be built once");
 
+        let recorder = if self.capture.enabled {
+            match MessageRecorder::start(&self.capture) {
+                Ok(recorder) => Some(recorder),
+                Err(err) => {
+                    error!(
+                        "{} Failed to start network capture in {}: {:?}",
+                        self.network_context,
+                        self.capture.dir().display(),
+                        err
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
         let peer_mgr = PeerManager::new(
             executor.clone(),
             transport,
@@ -373,6 +460,11 @@ impl PeerManagerBuilder {
             pm_context.max_concurrent_network_notifs,
             pm_context.channel_size,
             self.max_frame_size,
+            self.rate_limit.clone(),
+            self.peer_reputation.clone(),
+            self.peer_scores.clone(),
+            recorder,
+            self.status.clone(),
         );
 
         // PeerManager constructor appends a public key to the listen_address.
@@ -403,6 +495,9 @@ impl PeerManagerBuilder {
         if let Some(tcp_pm) = self.tcp_peer_manager.take() {
             self.start_peer_manager(tcp_pm, executor);
         }
+        if let Some(quic_pm) = self.quic_peer_manager.take() {
+            self.start_peer_manager(quic_pm, executor);
+        }
     }
 
     /// Add a handler for given protocols using raw bytes.
@@ -448,7 +543,9 @@ impl PeerManagerBuilder {
         (
             PeerManagerRequestSender::new(pm_context.pm_reqs_tx.clone()),
             network_notifs_rx,
-            ConnectionRequestSender::new(pm_context.connection_reqs_tx.clone()),
+            ConnectionRequestSender::new(pm_context.connection_reqs_tx.clone())
+                .with_peer_latencies(self.peer_latencies.clone())
+                .with_peer_scores(self.peer_scores.clone()),
             connection_notifs_rx,
         )
     }
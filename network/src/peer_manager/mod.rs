@@ -10,13 +10,18 @@
 //!  * A main event loop actor which is responsible for handling requests and sending
 //!  notification about new/lost Peers to the rest of the network stack.
 //!  * An actor responsible for dialing and listening for new connections.
+//!
+//! The main event loop also tracks the reputation of peers reported by upstream actors, see
+//! [`reputation`].
 use crate::{
+    capture::MessageRecorder,
     counters,
     interface::{NetworkNotification, NetworkProvider, NetworkRequest},
     logging::*,
     peer::DisconnectReason,
     protocols::{
         direct_send::Message,
+        health_checker::PeerLatencies,
         rpc::{error::RpcError, InboundRpcRequest, OutboundRpcRequest},
     },
     transport,
@@ -32,7 +37,10 @@ use futures::{
     sink::SinkExt,
     stream::{Fuse, FuturesUnordered, StreamExt},
 };
-use libra_config::network_id::NetworkContext;
+use libra_config::{
+    config::{PeerReputationConfig, RateLimitConfig},
+    network_id::{NetworkContext, NetworkId},
+};
 use libra_logger::prelude::*;
 use libra_network_address::NetworkAddress;
 use libra_types::PeerId;
@@ -43,17 +51,23 @@ use std::{
     fmt::Debug,
     marker::PhantomData,
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tokio::runtime::Handle;
 
 pub mod builder;
 pub mod conn_notifs_channel;
 mod error;
+pub mod reputation;
+pub mod status;
 #[cfg(test)]
 mod tests;
 
-pub use self::error::PeerManagerError;
+pub use self::{
+    error::PeerManagerError,
+    reputation::{PeerBehavior, PeerReputations, PeerScores},
+    status::{ConnectionSnapshot, NetworkSnapshot, NetworkStatus},
+};
 
 /// Request received by PeerManager from upstream actors.
 #[derive(Debug, Serialize)]
@@ -84,6 +98,8 @@ pub enum ConnectionRequest {
         PeerId,
         #[serde(skip)] oneshot::Sender<Result<(), PeerManagerError>>,
     ),
+    /// Report the outcome of an interaction with a peer.
+    ReportPeer(PeerId, PeerBehavior),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -111,6 +127,10 @@ pub struct PeerManagerRequestSender {
 #[derive(Clone)]
 pub struct ConnectionRequestSender {
     inner: libra_channel::Sender<PeerId, ConnectionRequest>,
+    /// Latencies of the peers, measured by the HealthChecker.
+    peer_latencies: PeerLatencies,
+    /// Scores of the peers, reported by the network applications.
+    peer_scores: PeerScores,
 }
 
 impl PeerManagerRequestSender {
@@ -194,7 +214,31 @@ impl PeerManagerRequestSender {
 impl ConnectionRequestSender {
     /// Construct a new ConnectionRequestSender with a raw libra_channel::Sender
     pub fn new(inner: libra_channel::Sender<PeerId, ConnectionRequest>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            peer_latencies: PeerLatencies::default(),
+            peer_scores: PeerScores::default(),
+        }
+    }
+
+    /// Shares the latency statistics of the peers with the other senders of the network.
+    pub fn with_peer_latencies(mut self, peer_latencies: PeerLatencies) -> Self {
+        self.peer_latencies = peer_latencies;
+        self
+    }
+
+    pub fn peer_latencies(&self) -> &PeerLatencies {
+        &self.peer_latencies
+    }
+
+    /// Shares the scores of the peers with the other senders of the network.
+    pub fn with_peer_scores(mut self, peer_scores: PeerScores) -> Self {
+        self.peer_scores = peer_scores;
+        self
+    }
+
+    pub fn peer_scores(&self) -> &PeerScores {
+        &self.peer_scores
     }
 
     pub async fn dial_peer(
@@ -214,6 +258,18 @@ impl ConnectionRequestSender {
             .push(peer, ConnectionRequest::DisconnectPeer(peer, oneshot_tx))?;
         oneshot_rx.await?
     }
+
+    /// Report the outcome of an interaction with a peer, without waiting for the report to be
+    /// processed.
+    pub fn report_peer(
+        &mut self,
+        peer: PeerId,
+        behavior: PeerBehavior,
+    ) -> Result<(), PeerManagerError> {
+        self.inner
+            .push(peer, ConnectionRequest::ReportPeer(peer, behavior))?;
+        Ok(())
+    }
 }
 
 /// Responsible for handling and maintaining connections to other Peers
@@ -266,6 +322,14 @@ where
     channel_size: usize,
     /// Max network frame size
     max_frame_size: usize,
+    /// Rate limits applied to every connection.
+    rate_limit: RateLimitConfig,
+    /// Scores and bans of peers.
+    reputations: PeerReputations,
+    /// Recorder of the messages exchanged with peers, if capture is enabled.
+    recorder: Option<MessageRecorder>,
+    /// Active connections and their traffic, for operators.
+    status: NetworkStatus,
 }
 
 impl<TTransport, TSocket> PeerManager<TTransport, TSocket>
@@ -291,6 +355,11 @@ where
         max_concurrent_network_reqs: usize,
         max_concurrent_network_notifs: usize,
         max_frame_size: usize,
+        rate_limit: RateLimitConfig,
+        peer_reputation: PeerReputationConfig,
+        peer_scores: PeerScores,
+        recorder: Option<MessageRecorder>,
+        status: NetworkStatus,
     ) -> Self {
         let (transport_notifs_tx, transport_notifs_rx) = channel::new(
             channel_size,
@@ -310,6 +379,16 @@ where
                 transport_notifs_tx_clone,
             )
         });
+        status.set_listen_address(listen_addr.clone());
+        // A validator must stay reachable by all the others, whatever its peers report.
+        let peer_reputation = if network_context.network_id() == &NetworkId::Validator {
+            PeerReputationConfig {
+                enable_bans: false,
+                ..peer_reputation
+            }
+        } else {
+            peer_reputation
+        };
         Self {
             network_context,
             executor,
@@ -329,6 +408,10 @@ where
             max_concurrent_network_notifs,
             channel_size,
             max_frame_size,
+            rate_limit,
+            reputations: PeerReputations::new(peer_reputation, peer_scores),
+            recorder,
+            status,
         }
     }
 
@@ -415,6 +498,8 @@ where
                         entry.remove();
                     }
                 }
+                self.status
+                    .remove_connection(&peer_id, lost_conn_metadata.connection_id());
                 counters::LIBRA_NETWORK_PEERS
                     .with_label_values(&[self.network_context.role().as_str(), "connected"])
                     .set(self.active_peers.len() as i64);
@@ -436,6 +521,7 @@ where
                 // Notify upstream if there's still no active connection. This might be redundant,
                 // but does not affect correctness.
                 if !self.active_peers.contains_key(&peer_id) {
+                    self.reputations.remove(&peer_id, Instant::now());
                     let notif = ConnectionNotification::LostPeer(
                         peer_id,
                         lost_conn_metadata.addr().clone(),
@@ -469,6 +555,26 @@ where
                             requested_peer_id.short_str()
                         );
                     }
+                } else if self
+                    .reputations
+                    .is_banned(&requested_peer_id, Instant::now())
+                {
+                    debug!(
+                        "{} Peer {} is banned. Not dialing address {}",
+                        self.network_context,
+                        requested_peer_id.short_str(),
+                        addr
+                    );
+                    if response_tx
+                        .send(Err(PeerManagerError::Banned(requested_peer_id)))
+                        .is_err()
+                    {
+                        warn!(
+                            "{} Receiver for DialPeer {} dropped",
+                            self.network_context,
+                            requested_peer_id.short_str()
+                        );
+                    }
                 } else {
                     self.dial_peer(requested_peer_id, addr, response_tx).await;
                 };
@@ -496,6 +602,27 @@ where
                     }
                 }
             }
+            ConnectionRequest::ReportPeer(peer_id, behavior) => {
+                counters::LIBRA_NETWORK_PEER_REPORTS
+                    .with_label_values(&[self.network_context.role().as_str(), behavior.as_str()])
+                    .inc();
+                // Scores are only kept for the connected peers, reports might arrive late.
+                if self.active_peers.contains_key(&peer_id)
+                    && self.reputations.report(peer_id, behavior, Instant::now())
+                {
+                    warn!(
+                        "{} Banning peer {} after {:?} behavior",
+                        self.network_context,
+                        peer_id.short_str(),
+                        behavior
+                    );
+                    counters::LIBRA_NETWORK_PEER_BANS
+                        .with_label_values(&[self.network_context.role().as_str()])
+                        .inc();
+                    // Dropping the NetworkRequest sender closes the connection.
+                    self.active_peers.remove(&peer_id);
+                }
+            }
         }
     }
 
@@ -578,6 +705,16 @@ where
 
         let mut send_new_peer_notification = true;
 
+        if self.reputations.is_banned(&peer_id, Instant::now()) {
+            info!(
+                "{} Closing connection with banned Peer {}",
+                self.network_context,
+                peer_id.short_str()
+            );
+            self.close_connection(connection);
+            return;
+        }
+
         // Check for and handle simultaneous dialing
         if let Entry::Occupied(active_entry) = self.active_peers.entry(peer_id) {
             let (curr_conn_metadata, _) = active_entry.get();
@@ -602,30 +739,14 @@ where
                     self.network_context,
                     peer_id.short_str()
                 );
-                let network_context = self.network_context.clone();
                 // Drop the new connection and keep the one already stored in active_peers
-                let drop_fut = async move {
-                    let mut connection = connection;
-                    if let Err(e) = tokio::time::timeout(
-                        transport::TRANSPORT_TIMEOUT,
-                        connection.socket.close(),
-                    )
-                    .await
-                    {
-                        error!(
-                            "{} Closing connection with Peer {} failed with error: {}",
-                            network_context,
-                            peer_id.short_str(),
-                            e
-                        );
-                    };
-                };
-                self.executor.spawn(drop_fut);
+                self.close_connection(connection);
                 return;
             }
         }
 
         // Initialize a new network stack for this connection.
+        let traffic = self.status.add_connection(conn_meta.clone());
         let (network_reqs_tx, network_notifs_rx) = NetworkProvider::start(
             self.executor.clone(),
             connection,
@@ -634,6 +755,9 @@ where
             self.max_concurrent_network_notifs,
             self.channel_size,
             self.max_frame_size,
+            self.rate_limit.clone(),
+            self.recorder.clone(),
+            traffic,
         );
         // Start background task to handle events (RPCs and DirectSend messages) received from
         // peer.
@@ -653,6 +777,26 @@ where
         }
     }
 
+    /// Closes a connection which was not handed over to a `Peer` actor.
+    fn close_connection(&self, connection: Connection<TSocket>) {
+        let network_context = self.network_context.clone();
+        let peer_id = connection.metadata.peer_id();
+        let drop_fut = async move {
+            let mut connection = connection;
+            if let Err(e) =
+                tokio::time::timeout(transport::TRANSPORT_TIMEOUT, connection.socket.close()).await
+            {
+                error!(
+                    "{} Closing connection with Peer {} failed with error: {}",
+                    network_context,
+                    peer_id.short_str(),
+                    e
+                );
+            };
+        };
+        self.executor.spawn(drop_fut);
+    }
+
     /// Sends a `ConnectionNotification` to all event handlers, warns on failures
     fn send_conn_notification(&mut self, peer_id: PeerId, notification: ConnectionNotification) {
         for handler in self.connection_event_handlers.iter_mut() {
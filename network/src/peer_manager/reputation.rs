@@ -0,0 +1,232 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reputation of the peers of a network.
+//!
+//! Upstream applications report the outcome of their interactions with peers through
+//! `NetworkSender::report_peer`. The PeerManager keeps a score per connected peer, which the
+//! applications read back through [`PeerScores`] to prefer well-behaved peers. If bans are
+//! enabled, the peers whose score drops below the configured threshold are disconnected, and
+//! neither dialed nor accepted until the ban expires. Bans are never enabled on the validator
+//! network, where a validator must stay reachable by all the others. A peer starts over with a
+//! neutral score once disconnected.
+
+use libra_config::config::PeerReputationConfig;
+use libra_types::PeerId;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+/// Score of peers nothing was reported about.
+const INITIAL_SCORE: i64 = 0;
+
+/// Cap of the score earned by good interactions, so that a long-lived peer turning bad is
+/// banned after a bounded number of bad interactions.
+const MAX_SCORE: i64 = 100;
+
+/// Outcome of an interaction with a peer, as judged by an upstream application.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum PeerBehavior {
+    /// The peer served a request or sent a useful message.
+    Good,
+    /// A request to the peer timed out or was answered without useful data.
+    Unresponsive,
+    /// The peer sent a message that doesn't deserialize or doesn't fit the protocol.
+    Malformed,
+    /// The peer sent data that fails verification, e.g. a chunk with an invalid proof.
+    Invalid,
+}
+
+impl PeerBehavior {
+    fn score_delta(self) -> i64 {
+        match self {
+            PeerBehavior::Good => 1,
+            PeerBehavior::Unresponsive => -5,
+            PeerBehavior::Malformed => -25,
+            PeerBehavior::Invalid => -50,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PeerBehavior::Good => "good",
+            PeerBehavior::Unresponsive => "unresponsive",
+            PeerBehavior::Malformed => "malformed",
+            PeerBehavior::Invalid => "invalid",
+        }
+    }
+}
+
+/// Scores of the peers of a network, shared between the PeerManager which updates them and the
+/// network applications.
+#[derive(Clone, Debug, Default)]
+pub struct PeerScores(Arc<RwLock<HashMap<PeerId, i64>>>);
+
+impl PeerScores {
+    /// Score of `peer_id`, neutral if nothing was reported about it since it connected.
+    pub fn get(&self, peer_id: &PeerId) -> i64 {
+        self.0
+            .read()
+            .unwrap()
+            .get(peer_id)
+            .copied()
+            .unwrap_or(INITIAL_SCORE)
+    }
+
+    /// Whether `peer_id` was reported to misbehave more than to behave since it connected.
+    pub fn is_suspect(&self, peer_id: &PeerId) -> bool {
+        self.get(peer_id) < INITIAL_SCORE
+    }
+
+    /// Adds a behavior of `peer_id` to its score, and returns the updated score.
+    pub fn record(&self, peer_id: PeerId, behavior: PeerBehavior) -> i64 {
+        let mut scores = self.0.write().unwrap();
+        let score = scores.entry(peer_id).or_insert(INITIAL_SCORE);
+        *score = std::cmp::min(*score + behavior.score_delta(), MAX_SCORE);
+        *score
+    }
+
+    fn remove(&self, peer_id: &PeerId) {
+        self.0.write().unwrap().remove(peer_id);
+    }
+}
+
+pub struct PeerReputations {
+    config: PeerReputationConfig,
+    scores: PeerScores,
+    /// Banned peers and the expiration of their ban.
+    bans: HashMap<PeerId, Instant>,
+}
+
+impl PeerReputations {
+    pub fn new(config: PeerReputationConfig, scores: PeerScores) -> Self {
+        Self {
+            config,
+            scores,
+            bans: HashMap::new(),
+        }
+    }
+
+    pub fn score(&self, peer_id: &PeerId) -> i64 {
+        self.scores.get(peer_id)
+    }
+
+    /// Updates the score of the peer and returns `true` if the peer just got banned.
+    pub fn report(&mut self, peer_id: PeerId, behavior: PeerBehavior, now: Instant) -> bool {
+        if self.is_banned(&peer_id, now) {
+            return false;
+        }
+        let score = self.scores.record(peer_id, behavior);
+        if !self.config.enable_bans || score >= self.config.ban_threshold {
+            return false;
+        }
+        self.scores.remove(&peer_id);
+        self.bans.insert(
+            peer_id,
+            now + Duration::from_millis(self.config.ban_duration_ms),
+        );
+        true
+    }
+
+    /// Returns whether the peer is currently banned, forgetting expired bans.
+    pub fn is_banned(&mut self, peer_id: &PeerId, now: Instant) -> bool {
+        match self.bans.get(peer_id) {
+            Some(expiration) if *expiration > now => true,
+            Some(_) => {
+                self.bans.remove(peer_id);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Forgets the score of a disconnected peer, along with the expired bans.
+    pub fn remove(&mut self, peer_id: &PeerId, now: Instant) {
+        self.scores.remove(peer_id);
+        self.bans.retain(|_, expiration| *expiration > now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled_bans() -> PeerReputationConfig {
+        PeerReputationConfig {
+            enable_bans: true,
+            ..PeerReputationConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_ban_below_threshold() {
+        let scores = PeerScores::default();
+        let mut reputations = PeerReputations::new(enabled_bans(), scores.clone());
+        let peer_id = PeerId::random();
+        let now = Instant::now();
+
+        // good interactions are capped
+        for _ in 0..1000 {
+            assert!(!reputations.report(peer_id, PeerBehavior::Good, now));
+        }
+        assert_eq!(reputations.score(&peer_id), MAX_SCORE);
+        assert_eq!(scores.get(&peer_id), MAX_SCORE);
+
+        // -100 is not below the threshold yet
+        for _ in 0..4 {
+            assert!(!reputations.report(peer_id, PeerBehavior::Invalid, now));
+        }
+        assert!(!reputations.is_banned(&peer_id, now));
+        assert!(reputations.report(peer_id, PeerBehavior::Unresponsive, now));
+        assert!(reputations.is_banned(&peer_id, now));
+
+        // reports about banned peers are ignored
+        assert!(!reputations.report(peer_id, PeerBehavior::Invalid, now));
+
+        // the peer starts over once the ban expires
+        let later = now + Duration::from_millis(PeerReputationConfig::default().ban_duration_ms);
+        assert!(!reputations.is_banned(&peer_id, later));
+        assert_eq!(reputations.score(&peer_id), INITIAL_SCORE);
+        assert!(!reputations.is_banned(&PeerId::random(), now));
+    }
+
+    #[test]
+    fn test_bans_disabled() {
+        let scores = PeerScores::default();
+        let mut reputations = PeerReputations::new(PeerReputationConfig::default(), scores.clone());
+        let peer_id = PeerId::random();
+        let now = Instant::now();
+        for _ in 0..10 {
+            assert!(!reputations.report(peer_id, PeerBehavior::Invalid, now));
+        }
+        assert!(!reputations.is_banned(&peer_id, now));
+        assert_eq!(reputations.score(&peer_id), -500);
+        assert!(scores.is_suspect(&peer_id));
+    }
+
+    #[test]
+    fn test_remove() {
+        let scores = PeerScores::default();
+        let mut reputations = PeerReputations::new(enabled_bans(), scores.clone());
+        let (peer_id, banned) = (PeerId::random(), PeerId::random());
+        let now = Instant::now();
+        reputations.report(peer_id, PeerBehavior::Malformed, now);
+        for _ in 0..3 {
+            reputations.report(banned, PeerBehavior::Invalid, now);
+        }
+        assert!(scores.is_suspect(&peer_id));
+
+        // disconnected peers start over, but remain banned until their ban expires
+        reputations.remove(&peer_id, now);
+        assert_eq!(scores.get(&peer_id), INITIAL_SCORE);
+        assert!(!scores.is_suspect(&peer_id));
+        assert!(reputations.is_banned(&banned, now));
+
+        let later = now + Duration::from_millis(PeerReputationConfig::default().ban_duration_ms);
+        reputations.remove(&peer_id, later);
+        assert!(reputations.bans.is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Snapshot of the connections of a network, for operators.
+//!
+//! The PeerManager registers every connection it hands over to a `Peer` actor, which counts the
+//! traffic of the connection as it reads and writes messages. [`NetworkStatus::snapshot`] returns
+//! the state of all the connections at once, along with the latencies measured by the
+//! HealthChecker. It is only served on the debug interface, as it reveals the peers of the node.
+
+use crate::{
+    protocols::{
+        health_checker::{LatencyStats, PeerLatencies},
+        wire::handshake::v1::ProtocolId,
+    },
+    transport::{ConnectionId, ConnectionMetadata},
+};
+use libra_config::network_id::NetworkContext;
+use libra_network_address::NetworkAddress;
+use libra_types::PeerId;
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// Traffic of a connection, counted by its `Peer` actor.
+#[derive(Debug, Default)]
+pub struct ConnectionTraffic {
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    messages_sent: AtomicU64,
+    messages_received: AtomicU64,
+    /// Outbound messages waiting to be written on the wire.
+    pending_messages: AtomicU64,
+}
+
+impl ConnectionTraffic {
+    /// Counts a frame written on the wire, `last_frame` being set on the last frame of a message.
+    pub(crate) fn count_sent(&self, size: usize, last_frame: bool) {
+        self.bytes_sent.fetch_add(size as u64, Ordering::Relaxed);
+        if last_frame {
+            self.messages_sent.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts a message read from the wire.
+    pub(crate) fn count_received(&self, size: usize) {
+        self.bytes_received
+            .fetch_add(size as u64, Ordering::Relaxed);
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set_pending_messages(&self, pending_messages: usize) {
+        self.pending_messages
+            .store(pending_messages as u64, Ordering::Relaxed);
+    }
+}
+
+struct Connection {
+    metadata: ConnectionMetadata,
+    established_at: Instant,
+    traffic: Arc<ConnectionTraffic>,
+}
+
+struct Connections {
+    listen_address: Option<NetworkAddress>,
+    connections: HashMap<PeerId, Connection>,
+}
+
+/// Connections of a network, maintained by its PeerManager.
+#[derive(Clone)]
+pub struct NetworkStatus {
+    network_context: Arc<NetworkContext>,
+    peer_latencies: PeerLatencies,
+    inner: Arc<RwLock<Connections>>,
+}
+
+impl NetworkStatus {
+    pub fn new(network_context: Arc<NetworkContext>, peer_latencies: PeerLatencies) -> Self {
+        Self {
+            network_context,
+            peer_latencies,
+            inner: Arc::new(RwLock::new(Connections {
+                listen_address: None,
+                connections: HashMap::new(),
+            })),
+        }
+    }
+
+    pub(crate) fn set_listen_address(&self, listen_address: NetworkAddress) {
+        self.inner.write().unwrap().listen_address = Some(listen_address);
+    }
+
+    /// Registers the active connection with a peer, replacing the previous one if any, and
+    /// returns the counters of its traffic.
+    pub(crate) fn add_connection(&self, metadata: ConnectionMetadata) -> Arc<ConnectionTraffic> {
+        let traffic = Arc::new(ConnectionTraffic::default());
+        self.inner.write().unwrap().connections.insert(
+            metadata.peer_id(),
+            Connection {
+                metadata,
+                established_at: Instant::now(),
+                traffic: traffic.clone(),
+            },
+        );
+        traffic
+    }
+
+    /// Unregisters a closed connection, unless it was already replaced.
+    pub(crate) fn remove_connection(&self, peer_id: &PeerId, connection_id: ConnectionId) {
+        let mut inner = self.inner.write().unwrap();
+        if inner
+            .connections
+            .get(peer_id)
+            .map_or(false, |conn| conn.metadata.connection_id() == connection_id)
+        {
+            inner.connections.remove(peer_id);
+        }
+    }
+
+    pub fn num_connections(&self) -> usize {
+        self.inner.read().unwrap().connections.len()
+    }
+
+    pub fn snapshot(&self) -> NetworkSnapshot {
+        let inner = self.inner.read().unwrap();
+        let now = Instant::now();
+        let mut connections: Vec<_> = inner
+            .connections
+            .values()
+            .map(|conn| ConnectionSnapshot {
+                application_protocols: conn
+                    .metadata
+                    .application_protocols()
+                    .clone()
+                    .try_into()
+                    .unwrap_or_default(),
+                connected_for: now.saturating_duration_since(conn.established_at),
+                bytes_sent: conn.traffic.bytes_sent.load(Ordering::Relaxed),
+                bytes_received: conn.traffic.bytes_received.load(Ordering::Relaxed),
+                messages_sent: conn.traffic.messages_sent.load(Ordering::Relaxed),
+                messages_received: conn.traffic.messages_received.load(Ordering::Relaxed),
+                pending_messages: conn.traffic.pending_messages.load(Ordering::Relaxed),
+                latency: self.peer_latencies.get(&conn.metadata.peer_id()),
+                metadata: conn.metadata.clone(),
+            })
+            .collect();
+        connections.sort_by_key(|conn| conn.metadata.peer_id());
+        NetworkSnapshot {
+            network_context: self.network_context.clone(),
+            listen_address: inner.listen_address.clone(),
+            connections,
+        }
+    }
+}
+
+/// State of the connections of a network at the time of a snapshot.
+#[derive(Clone, Debug)]
+pub struct NetworkSnapshot {
+    pub network_context: Arc<NetworkContext>,
+    /// Address the network listens on, once the PeerManager is built.
+    pub listen_address: Option<NetworkAddress>,
+    /// Connections sorted by peer id.
+    pub connections: Vec<ConnectionSnapshot>,
+}
+
+/// State of a connection at the time of a snapshot.
+#[derive(Clone, Debug)]
+pub struct ConnectionSnapshot {
+    pub metadata: ConnectionMetadata,
+    /// Application protocols negotiated in the handshake.
+    pub application_protocols: Vec<ProtocolId>,
+    pub connected_for: Duration,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    /// Outbound messages waiting to be written on the wire.
+    pub pending_messages: u64,
+    /// Ping round-trip times, once the peer responded to a ping.
+    pub latency: Option<LatencyStats>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocols::wire::handshake::v1::MessagingProtocolVersion;
+    use netcore::transport::ConnectionOrigin;
+
+    fn metadata(peer_id: PeerId, connection_id: u32) -> ConnectionMetadata {
+        ConnectionMetadata::new(
+            peer_id,
+            ConnectionId::from(connection_id),
+            NetworkAddress::mock(),
+            ConnectionOrigin::Outbound,
+            MessagingProtocolVersion::V1,
+            [ProtocolId::ConsensusRpc, ProtocolId::HealthCheckerRpc]
+                .iter()
+                .into(),
+        )
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let peer_latencies = PeerLatencies::default();
+        let status = NetworkStatus::new(NetworkContext::mock(), peer_latencies.clone());
+        let peer_id = PeerId::random();
+        peer_latencies.record(peer_id, Duration::from_millis(10));
+
+        let traffic = status.add_connection(metadata(peer_id, 1));
+        traffic.count_sent(100, false);
+        traffic.count_sent(50, true);
+        traffic.count_received(20);
+        traffic.set_pending_messages(3);
+
+        let snapshot = status.snapshot();
+        assert_eq!(snapshot.connections.len(), 1);
+        let conn = &snapshot.connections[0];
+        assert_eq!(conn.metadata.peer_id(), peer_id);
+        assert_eq!(
+            conn.application_protocols,
+            vec![ProtocolId::ConsensusRpc, ProtocolId::HealthCheckerRpc]
+        );
+        assert_eq!((conn.bytes_sent, conn.messages_sent), (150, 1));
+        assert_eq!((conn.bytes_received, conn.messages_received), (20, 1));
+        assert_eq!(conn.pending_messages, 3);
+        assert_eq!(conn.latency.unwrap().mean, Duration::from_millis(10));
+    }
+
+    #[test]
+    fn test_replaced_connection() {
+        let status = NetworkStatus::new(NetworkContext::mock(), PeerLatencies::default());
+        let peer_id = PeerId::random();
+        status.add_connection(metadata(peer_id, 1));
+        status.add_connection(metadata(peer_id, 2));
+
+        // Closing the replaced connection keeps the new one.
+        status.remove_connection(&peer_id, ConnectionId::from(1));
+        assert_eq!(status.num_connections(), 1);
+        status.remove_connection(&peer_id, ConnectionId::from(2));
+        assert_eq!(status.num_connections(), 0);
+    }
+}
@@ -6,11 +6,15 @@ use crate::{
     peer::DisconnectReason,
     peer_manager::{
         conn_notifs_channel, error::PeerManagerError, ConnectionNotification, ConnectionRequest,
-        PeerManager, PeerManagerNotification, PeerManagerRequest, TransportNotification,
+        NetworkStatus, PeerBehavior, PeerManager, PeerManagerNotification, PeerManagerRequest,
+        PeerScores, TransportNotification,
     },
-    protocols::wire::{
-        handshake::v1::MessagingProtocolVersion,
-        messaging::v1::{NetworkMessage, Nonce},
+    protocols::{
+        health_checker::PeerLatencies,
+        wire::{
+            handshake::v1::MessagingProtocolVersion,
+            messaging::v1::{NetworkMessage, Nonce},
+        },
     },
     transport,
     transport::{Connection, ConnectionId, ConnectionMetadata},
@@ -19,7 +23,7 @@ use crate::{
 use channel::{libra_channel, message_queues::QueueStyle};
 use futures::{channel::oneshot, io::AsyncWriteExt, sink::SinkExt, stream::StreamExt};
 use libra_config::{
-    config::RoleType,
+    config::{PeerReputationConfig, RateLimitConfig, RoleType},
     network_id::{NetworkContext, NetworkId},
 };
 use libra_network_address::NetworkAddress;
@@ -72,9 +76,12 @@ fn ordered_peer_ids(num: usize) -> Vec<PeerId> {
     ids
 }
 
-fn build_test_peer_manager(
+fn build_test_peer_manager_with_reputation(
     executor: Handle,
     peer_id: PeerId,
+    network_id: NetworkId,
+    peer_reputation: PeerReputationConfig,
+    peer_scores: PeerScores,
 ) -> (
     PeerManager<
         BoxedTransport<Connection<MemorySocket>, impl std::error::Error + Sync + Send + 'static>,
@@ -92,15 +99,16 @@ fn build_test_peer_manager(
     let (hello_tx, hello_rx) =
         libra_channel::new(QueueStyle::FIFO, NonZeroUsize::new(1).unwrap(), None);
     let (conn_status_tx, conn_status_rx) = conn_notifs_channel::new();
+    let network_context = Arc::new(NetworkContext::new(
+        network_id,
+        RoleType::Validator,
+        peer_id,
+    ));
 
     let peer_manager = PeerManager::new(
         executor,
         build_test_transport(),
-        Arc::new(NetworkContext::new(
-            NetworkId::Validator,
-            RoleType::Validator,
-            peer_id,
-        )),
+        network_context.clone(),
         "/memory/0".parse().unwrap(),
         peer_manager_request_rx,
         connection_reqs_rx,
@@ -110,6 +118,11 @@ fn build_test_peer_manager(
         constants::MAX_CONCURRENT_NETWORK_REQS,
         constants::MAX_CONCURRENT_NETWORK_NOTIFS,
         constants::MAX_FRAME_SIZE,
+        RateLimitConfig::default(),
+        peer_reputation,
+        peer_scores,
+        None,
+        NetworkStatus::new(network_context, PeerLatencies::default()),
     );
 
     (
@@ -121,6 +134,28 @@ fn build_test_peer_manager(
     )
 }
 
+fn build_test_peer_manager(
+    executor: Handle,
+    peer_id: PeerId,
+) -> (
+    PeerManager<
+        BoxedTransport<Connection<MemorySocket>, impl std::error::Error + Sync + Send + 'static>,
+        MemorySocket,
+    >,
+    libra_channel::Sender<(PeerId, ProtocolId), PeerManagerRequest>,
+    libra_channel::Sender<PeerId, ConnectionRequest>,
+    libra_channel::Receiver<(PeerId, ProtocolId), PeerManagerNotification>,
+    conn_notifs_channel::Receiver,
+) {
+    build_test_peer_manager_with_reputation(
+        executor,
+        peer_id,
+        NetworkId::Validator,
+        PeerReputationConfig::default(),
+        PeerScores::default(),
+    )
+}
+
 async fn ping_pong(connection: &mut MemorySocket) -> Result<(), PeerManagerError> {
     let mut connection = Framed::new(IoCompat::new(connection), LengthDelimitedCodec::new());
     let ping = NetworkMessage::Ping(Nonce(42));
@@ -627,3 +662,151 @@ fn test_dial_disconnect() {
 
     runtime.block_on(test);
 }
+
+#[test]
+fn test_ban_misbehaving_peer() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+    let mut runtime = ::tokio::runtime::Runtime::new().unwrap();
+
+    let ids = ordered_peer_ids(2);
+    let (mut peer_manager, _request_tx, _connection_reqs_tx, _hello_rx, mut conn_status_rx) =
+        build_test_peer_manager_with_reputation(
+            runtime.handle().clone(),
+            ids[1],
+            NetworkId::Public,
+            PeerReputationConfig {
+                enable_bans: true,
+                ..PeerReputationConfig::default()
+            },
+            PeerScores::default(),
+        );
+
+    let test = async move {
+        let (outbound, _inbound) = build_test_connection();
+        peer_manager.add_peer(create_connection(
+            outbound,
+            ids[0],
+            NetworkAddress::mock(),
+            ConnectionOrigin::Outbound,
+            ConnectionId::from(0),
+        ));
+
+        // Expect NewPeer notification from PeerManager.
+        let conn_notif = conn_status_rx.next().await.unwrap();
+        assert!(matches!(
+            conn_notif,
+            ConnectionNotification::NewPeer(_, _, _, _)
+        ));
+
+        // Report invalid data from the peer until its score drops below the ban threshold.
+        for _ in 0..3 {
+            peer_manager
+                .handle_connection_request(ConnectionRequest::ReportPeer(
+                    ids[0],
+                    PeerBehavior::Invalid,
+                ))
+                .await;
+        }
+        assert!(!peer_manager.active_peers.contains_key(&ids[0]));
+
+        // The banned peer gets disconnected.
+        assert_peer_disconnected_event(
+            ids[0],
+            ConnectionOrigin::Outbound,
+            DisconnectReason::Requested,
+            &mut peer_manager,
+        )
+        .await;
+        let conn_notif = conn_status_rx.next().await.unwrap();
+        assert!(matches!(
+            conn_notif,
+            ConnectionNotification::LostPeer(_, _, _, _)
+        ));
+
+        // Dials to the banned peer are refused.
+        let (dial_resp_tx, dial_resp_rx) = oneshot::channel();
+        peer_manager
+            .handle_connection_request(ConnectionRequest::DialPeer(
+                ids[0],
+                NetworkAddress::mock(),
+                dial_resp_tx,
+            ))
+            .await;
+        assert!(matches!(
+            dial_resp_rx.await.unwrap(),
+            Err(PeerManagerError::Banned(_))
+        ));
+    };
+
+    runtime.block_on(test);
+}
+
+#[test]
+fn test_no_bans_on_validator_network() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+    let mut runtime = ::tokio::runtime::Runtime::new().unwrap();
+
+    let ids = ordered_peer_ids(2);
+    let peer_scores = PeerScores::default();
+    let (mut peer_manager, _request_tx, _connection_reqs_tx, _hello_rx, mut conn_status_rx) =
+        build_test_peer_manager_with_reputation(
+            runtime.handle().clone(),
+            ids[1],
+            NetworkId::Validator,
+            PeerReputationConfig {
+                enable_bans: true,
+                ..PeerReputationConfig::default()
+            },
+            peer_scores.clone(),
+        );
+
+    let test = async move {
+        let (outbound, _inbound) = build_test_connection();
+        peer_manager.add_peer(create_connection(
+            outbound,
+            ids[0],
+            NetworkAddress::mock(),
+            ConnectionOrigin::Outbound,
+            ConnectionId::from(0),
+        ));
+        let conn_notif = conn_status_rx.next().await.unwrap();
+        assert!(matches!(
+            conn_notif,
+            ConnectionNotification::NewPeer(_, _, _, _)
+        ));
+
+        // The validator stays connected, but its score is visible to the applications.
+        for _ in 0..3 {
+            peer_manager
+                .handle_connection_request(ConnectionRequest::ReportPeer(
+                    ids[0],
+                    PeerBehavior::Invalid,
+                ))
+                .await;
+        }
+        assert!(peer_manager.active_peers.contains_key(&ids[0]));
+        assert!(peer_scores.is_suspect(&ids[0]));
+
+        // The score is forgotten once the peer is lost.
+        let event = TransportNotification::Disconnected(
+            ConnectionMetadata::new(
+                ids[0],
+                ConnectionId::from(0),
+                NetworkAddress::mock(),
+                ConnectionOrigin::Outbound,
+                MessagingProtocolVersion::V1,
+                [TEST_PROTOCOL].iter().into(),
+            ),
+            DisconnectReason::ConnectionLost,
+        );
+        peer_manager.handle_connection_event(event);
+        let conn_notif = conn_status_rx.next().await.unwrap();
+        assert!(matches!(
+            conn_notif,
+            ConnectionNotification::LostPeer(_, _, _, _)
+        ));
+        assert_eq!(peer_scores.get(&ids[0]), 0);
+    };
+
+    runtime.block_on(test);
+}
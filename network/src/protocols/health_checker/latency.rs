@@ -0,0 +1,131 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Round-trip time statistics of the pings sent by the HealthChecker.
+//!
+//! The statistics are smoothed as for the TCP retransmission timer (RFC 6298): the mean is an
+//! exponentially weighted moving average of the round-trip times, and the jitter one of their
+//! deviation from the mean. They are shared with the network applications through
+//! [`NetworkSender`](crate::protocols::network::NetworkSender), so that e.g. state sync can
+//! prefer the peers which respond faster.
+
+use libra_types::PeerId;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+/// Weight of the latest round-trip time in the mean.
+const MEAN_GAIN: f64 = 0.125;
+/// Weight of the latest deviation in the jitter.
+const JITTER_GAIN: f64 = 0.25;
+
+/// Rolling round-trip time statistics of a peer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LatencyStats {
+    /// Smoothed round-trip time.
+    pub mean: Duration,
+    /// Smoothed deviation of the round-trip time from the mean.
+    pub jitter: Duration,
+    /// Latest round-trip time.
+    pub last: Duration,
+    /// Number of round-trip times measured.
+    pub samples: u64,
+}
+
+impl LatencyStats {
+    fn new(rtt: Duration) -> Self {
+        Self {
+            mean: rtt,
+            jitter: rtt / 2,
+            last: rtt,
+            samples: 1,
+        }
+    }
+
+    fn update(&mut self, rtt: Duration) {
+        let mean = self.mean.as_secs_f64();
+        let sample = rtt.as_secs_f64();
+        self.jitter = Duration::from_secs_f64(
+            (1.0 - JITTER_GAIN) * self.jitter.as_secs_f64() + JITTER_GAIN * (mean - sample).abs(),
+        );
+        self.mean = Duration::from_secs_f64((1.0 - MEAN_GAIN) * mean + MEAN_GAIN * sample);
+        self.last = rtt;
+        self.samples += 1;
+    }
+}
+
+/// Latency statistics of the connected peers of a network, shared between the HealthChecker
+/// which updates them and the network applications.
+#[derive(Clone, Debug, Default)]
+pub struct PeerLatencies(Arc<RwLock<HashMap<PeerId, LatencyStats>>>);
+
+impl PeerLatencies {
+    /// Statistics of `peer_id`, if it's connected and responded to a ping.
+    pub fn get(&self, peer_id: &PeerId) -> Option<LatencyStats> {
+        self.0.read().unwrap().get(peer_id).copied()
+    }
+
+    /// Returns the peer with the lowest mean latency, peers without statistics coming last.
+    pub fn fastest(&self, peers: impl IntoIterator<Item = PeerId>) -> Option<PeerId> {
+        let latencies = self.0.read().unwrap();
+        peers.into_iter().min_by_key(|peer_id| {
+            latencies
+                .get(peer_id)
+                .map_or(Duration::from_secs(u64::MAX), |stats| stats.mean)
+        })
+    }
+
+    /// Adds the round-trip time of a ping to `peer_id`, and returns its updated statistics.
+    pub fn record(&self, peer_id: PeerId, rtt: Duration) -> LatencyStats {
+        let mut latencies = self.0.write().unwrap();
+        let stats = latencies
+            .entry(peer_id)
+            .and_modify(|stats| stats.update(rtt))
+            .or_insert_with(|| LatencyStats::new(rtt));
+        *stats
+    }
+
+    pub fn remove(&self, peer_id: &PeerId) {
+        self.0.write().unwrap().remove(peer_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let latencies = PeerLatencies::default();
+        let peer_id = PeerId::random();
+        assert_eq!(latencies.get(&peer_id), None);
+
+        let stats = latencies.record(peer_id, Duration::from_millis(80));
+        assert_eq!(stats.mean, Duration::from_millis(80));
+        assert_eq!(stats.jitter, Duration::from_millis(40));
+
+        let stats = latencies.record(peer_id, Duration::from_millis(160));
+        // mean = 7/8 * 80 + 1/8 * 160, jitter = 3/4 * 40 + 1/4 * |80 - 160|
+        assert!((stats.mean.as_secs_f64() - 0.090).abs() < 1e-6);
+        assert!((stats.jitter.as_secs_f64() - 0.050).abs() < 1e-6);
+        assert_eq!(stats.last, Duration::from_millis(160));
+        assert_eq!(stats.samples, 2);
+        assert_eq!(latencies.get(&peer_id), Some(stats));
+
+        latencies.remove(&peer_id);
+        assert_eq!(latencies.get(&peer_id), None);
+    }
+
+    #[test]
+    fn test_fastest() {
+        let latencies = PeerLatencies::default();
+        let (slow, fast, unknown) = (PeerId::random(), PeerId::random(), PeerId::random());
+        latencies.record(slow, Duration::from_millis(200));
+        latencies.record(fast, Duration::from_millis(20));
+        assert_eq!(latencies.fastest(vec![unknown, slow, fast]), Some(fast));
+        assert_eq!(latencies.fastest(vec![unknown, slow]), Some(slow));
+        assert_eq!(latencies.fastest(vec![]), None);
+    }
+}
@@ -11,6 +11,9 @@
 //! disconnect from the peer. It relies on ConnectivityManager or the remote peer to re-establish
 //! the connection.
 //!
+//! The round-trip times of successful probes are kept as rolling latency and jitter statistics per
+//! peer (see [`PeerLatencies`]), which network applications query to favor faster peers.
+//!
 //! Future Work
 //! -----------
 //! We can make a few other improvements to the health checker. These are:
@@ -40,12 +43,19 @@ use libra_metrics::IntCounterVec;
 use libra_types::PeerId;
 use rand::{rngs::SmallRng, seq::SliceRandom, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 pub mod builder;
+mod latency;
 #[cfg(test)]
 mod test;
 
+pub use latency::{LatencyStats, PeerLatencies};
+
 /// The interface from Network to HealthChecker layer.
 ///
 /// `HealthCheckerNetworkEvents` is a `Stream` of `PeerManagerNotification` where the
@@ -116,6 +126,10 @@ impl HealthCheckerNetworkSender {
     pub async fn disconnect_peer(&mut self, peer_id: PeerId) -> Result<(), NetworkError> {
         self.inner.disconnect_peer(peer_id).await
     }
+
+    pub fn peer_latencies(&self) -> &PeerLatencies {
+        self.inner.peer_latencies()
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -153,6 +167,8 @@ pub struct HealthChecker<TTicker> {
     ping_failures_tolerated: u64,
     /// Counter incremented in each round of health checks
     round: u64,
+    /// Round-trip time statistics of the connected peers.
+    peer_latencies: PeerLatencies,
 }
 
 impl<TTicker> HealthChecker<TTicker>
@@ -168,6 +184,7 @@ where
         ping_timeout: Duration,
         ping_failures_tolerated: u64,
     ) -> Self {
+        let peer_latencies = network_tx.peer_latencies().clone();
         HealthChecker {
             network_context,
             ticker,
@@ -178,6 +195,7 @@ where
             ping_timeout,
             ping_failures_tolerated,
             round: 0,
+            peer_latencies,
         }
     }
 
@@ -192,6 +210,7 @@ where
                         },
                         Ok(Event::LostPeer(peer_id, _origin)) => {
                             self.connected.remove(&peer_id);
+                            self.remove_latency(&peer_id);
                         },
                         Ok(Event::RpcRequest((peer_id, msg, res_tx))) => {
                             match msg {
@@ -244,8 +263,8 @@ where
                     }
                 }
                 res = tick_handlers.select_next_some() => {
-                    let (peer_id, round, nonce, ping_result) = res;
-                    self.handle_ping_response(peer_id, round, nonce, ping_result).await;
+                    let (peer_id, round, nonce, rtt, ping_result) = res;
+                    self.handle_ping_response(peer_id, round, nonce, rtt, ping_result).await;
                 }
                 complete => {
                     break;
@@ -285,6 +304,7 @@ where
         peer_id: PeerId,
         round: u64,
         req_nonce: u32,
+        rtt: Duration,
         ping_result: Result<Pong, RpcError>,
     ) {
         debug!(
@@ -308,6 +328,9 @@ where
                                 *count = 0;
                             }
                         });
+                    if self.connected.contains_key(&peer_id) {
+                        self.record_latency(peer_id, rtt);
+                    }
                 } else {
                     send_struct_log!(security_log(security_events::INVALID_HEALTHCHECKER_MSG)
                         .data("error", "Pong nonce doesn't match our challenge Ping nonce")
@@ -367,13 +390,14 @@ where
         round: u64,
         nonce: u32,
         ping_timeout: Duration,
-    ) -> (PeerId, u64, u32, Result<Pong, RpcError>) {
+    ) -> (PeerId, u64, u32, Duration, Result<Pong, RpcError>) {
         debug!(
             "{} Sending Ping request to peer: {} with nonce: {}",
             network_context,
             peer_id.short_str(),
             nonce
         );
+        let start = Instant::now();
         let res_pong_msg = network_tx
             .send_rpc(peer_id, HealthCheckerMsg::Ping(Ping(nonce)), ping_timeout)
             .await
@@ -381,7 +405,32 @@ where
                 HealthCheckerMsg::Pong(res) => Ok(res),
                 _ => Err(RpcError::InvalidRpcResponse),
             });
-        (peer_id, round, nonce, res_pong_msg)
+        (peer_id, round, nonce, start.elapsed(), res_pong_msg)
+    }
+
+    fn record_latency(&self, peer_id: PeerId, rtt: Duration) {
+        let stats = self.peer_latencies.record(peer_id, rtt);
+        let role = self.network_context.role().as_str();
+        let peer_id_str = peer_id.short_str();
+        counters::LIBRA_NETWORK_PEER_LATENCY
+            .with_label_values(&[role, &peer_id_str, "mean"])
+            .set(stats.mean.as_micros() as i64);
+        counters::LIBRA_NETWORK_PEER_LATENCY
+            .with_label_values(&[role, &peer_id_str, "jitter"])
+            .set(stats.jitter.as_micros() as i64);
+    }
+
+    fn remove_latency(&self, peer_id: &PeerId) {
+        self.peer_latencies.remove(peer_id);
+        let role = self.network_context.role().as_str();
+        let peer_id_str = peer_id.short_str();
+        for stat in &["mean", "jitter"] {
+            let _ = counters::LIBRA_NETWORK_PEER_LATENCY.remove_label_values(&[
+                role,
+                &peer_id_str,
+                stat,
+            ]);
+        }
     }
 
     fn sample_random_peer(&mut self) -> Option<PeerId> {
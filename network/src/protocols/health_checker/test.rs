@@ -3,6 +3,7 @@
 
 use super::*;
 use crate::{
+    peer::DisconnectReason,
     peer_manager::{
         self, conn_notifs_channel, ConnectionRequest, PeerManagerNotification, PeerManagerRequest,
     },
@@ -31,6 +32,20 @@ fn setup_permissive_health_checker(
     libra_channel::Receiver<PeerId, ConnectionRequest>,
     conn_notifs_channel::Sender,
     channel::Sender<()>,
+) {
+    setup_health_checker_with_latencies(rt, ping_failures_tolerated, PeerLatencies::default())
+}
+
+fn setup_health_checker_with_latencies(
+    rt: &mut Runtime,
+    ping_failures_tolerated: u64,
+    peer_latencies: PeerLatencies,
+) -> (
+    libra_channel::Receiver<(PeerId, ProtocolId), PeerManagerRequest>,
+    libra_channel::Sender<(PeerId, ProtocolId), PeerManagerNotification>,
+    libra_channel::Receiver<PeerId, ConnectionRequest>,
+    conn_notifs_channel::Sender,
+    channel::Sender<()>,
 ) {
     let (ticker_tx, ticker_rx) = channel::new_test(0);
 
@@ -44,7 +59,7 @@ fn setup_permissive_health_checker(
 
     let hc_network_tx = HealthCheckerNetworkSender::new(
         PeerManagerRequestSender::new(peer_mgr_reqs_tx),
-        ConnectionRequestSender::new(connection_reqs_tx),
+        ConnectionRequestSender::new(connection_reqs_tx).with_peer_latencies(peer_latencies),
     );
     let hc_network_rx = HealthCheckerNetworkEvents::new(network_notifs_rx, connection_notifs_rx);
     let network_context =
@@ -173,6 +188,23 @@ async fn expect_disconnect(
     res_tx.send(Ok(())).unwrap();
 }
 
+async fn send_lost_peer_notification(
+    peer_id: PeerId,
+    connection_notifs_tx: &mut conn_notifs_channel::Sender,
+) {
+    let (delivered_tx, delivered_rx) = oneshot::channel();
+    let notif = peer_manager::ConnectionNotification::LostPeer(
+        peer_id,
+        NetworkAddress::from_str("/ip6/::1/tcp/8081").unwrap(),
+        ConnectionOrigin::Inbound,
+        DisconnectReason::ConnectionLost,
+    );
+    connection_notifs_tx
+        .push_with_feedback(peer_id, notif, Some(delivered_tx))
+        .unwrap();
+    delivered_rx.await.unwrap();
+}
+
 async fn send_new_peer_notification(
     peer_id: PeerId,
     connection_notifs_tx: &mut conn_notifs_channel::Sender,
@@ -214,6 +246,35 @@ fn outbound() {
     rt.block_on(events_f);
 }
 
+#[test]
+fn outbound_latency() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+    let mut rt = Runtime::new().unwrap();
+    let peer_latencies = PeerLatencies::default();
+    let (mut network_reqs_rx, _, _, mut connection_notifs_tx, mut ticker_tx) =
+        setup_health_checker_with_latencies(&mut rt, 0, peer_latencies.clone());
+
+    let events_f = async move {
+        let peer_id = PeerId::random();
+        send_new_peer_notification(peer_id, &mut connection_notifs_tx).await;
+        ticker_tx.send(()).await.unwrap();
+        expect_ping_send_ok(&mut network_reqs_rx).await;
+
+        // The round-trip time of the ping is recorded once the pong is processed.
+        while peer_latencies.get(&peer_id).is_none() {
+            tokio::time::delay_for(Duration::from_millis(10)).await;
+        }
+        assert_eq!(peer_latencies.get(&peer_id).unwrap().samples, 1);
+
+        // The statistics are dropped along with the connection.
+        send_lost_peer_notification(peer_id, &mut connection_notifs_tx).await;
+        while peer_latencies.get(&peer_id).is_some() {
+            tokio::time::delay_for(Duration::from_millis(10)).await;
+        }
+    };
+    rt.block_on(events_f);
+}
+
 #[test]
 fn inbound() {
     ::libra_logger::Logger::new().environment_only(true).init();
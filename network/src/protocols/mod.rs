@@ -11,4 +11,5 @@ pub mod rpc;
 pub mod gossip_discovery;
 pub mod health_checker;
 pub mod identity;
+pub mod peer_exchange;
 pub mod wire;
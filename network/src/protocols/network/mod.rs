@@ -3,7 +3,6 @@
 
 //! Convenience Network API for Libra
 
-pub use crate::protocols::rpc::error::RpcError;
 use crate::{
     error::NetworkError,
     peer_manager::{
@@ -12,6 +11,13 @@ use crate::{
     },
     ProtocolId,
 };
+pub use crate::{
+    peer_manager::{PeerBehavior, PeerScores},
+    protocols::{
+        health_checker::{LatencyStats, PeerLatencies},
+        rpc::error::RpcError,
+    },
+};
 use bytes::Bytes;
 use channel::libra_channel;
 use futures::{
@@ -227,6 +233,35 @@ impl<TMessage> NetworkSender<TMessage> {
         self.connection_reqs_tx.disconnect_peer(peer).await?;
         Ok(())
     }
+
+    /// Report the outcome of an interaction with a peer. Peers reported to misbehave too often
+    /// are disconnected and temporarily banned.
+    pub fn report_peer(
+        &mut self,
+        peer: PeerId,
+        behavior: PeerBehavior,
+    ) -> Result<(), NetworkError> {
+        self.connection_reqs_tx.report_peer(peer, behavior)?;
+        Ok(())
+    }
+
+    /// Latency statistics of `peer`, measured by the HealthChecker of the network. `None` if the
+    /// peer isn't connected or didn't respond to a ping yet.
+    pub fn peer_latency(&self, peer: &PeerId) -> Option<LatencyStats> {
+        self.connection_reqs_tx.peer_latencies().get(peer)
+    }
+
+    /// Latency statistics of all the peers of the network, which stay up to date as the
+    /// HealthChecker pings the peers.
+    pub fn peer_latencies(&self) -> &PeerLatencies {
+        self.connection_reqs_tx.peer_latencies()
+    }
+
+    /// Scores of the peers of the network, from the behaviors reported by all the applications
+    /// since the peers connected.
+    pub fn peer_scores(&self) -> &PeerScores {
+        self.connection_reqs_tx.peer_scores()
+    }
 }
 
 impl<TMessage: Message> NetworkSender<TMessage> {
@@ -0,0 +1,266 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! The address book of peer exchange: records of full nodes learned from peers, bounded in size
+//! and persisted across restarts.
+//!
+//! A record is verified if it was received from the peer itself over its Noise session, which
+//! authenticates the record as if it were signed with the identity key of the peer. Records
+//! relayed by other peers stay unverified, even once we dialed one of their addresses, as that
+//! wouldn't authenticate the other addresses. Only verified records are shared with other peers,
+//! and an unverified record only ever replaces a record relayed by the same peer, so that a peer
+//! can't redirect our dials to another node by relaying forged records.
+
+use crate::protocols::peer_exchange::PeerRecord;
+use anyhow::Result;
+use libra_types::PeerId;
+use rand::{seq::IteratorRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fs, path::Path};
+
+/// The peer a record was received from.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Source {
+    /// The peer the record is about, which makes the record verified.
+    Peer,
+    /// Another peer, which might have forged the record.
+    Relayed(PeerId),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct Entry {
+    record: PeerRecord,
+    source: Source,
+}
+
+impl Entry {
+    fn is_verified(&self) -> bool {
+        self.source == Source::Peer
+    }
+}
+
+pub struct AddressBook {
+    /// Max number of entries.
+    capacity: usize,
+    entries: HashMap<PeerId, Entry>,
+}
+
+impl AddressBook {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Loads the address book persisted at `path`, keeping at most `capacity` entries.
+    pub fn load(path: &Path, capacity: usize) -> Result<Self> {
+        let entries: Vec<Entry> = lcs::from_bytes(&fs::read(path)?)?;
+        let mut address_book = Self::new(capacity);
+        for entry in entries {
+            address_book.insert(entry.record, entry.source);
+        }
+        Ok(address_book)
+    }
+
+    /// Persists the address book at `path`, atomically replacing the previous version.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let entries: Vec<_> = self.entries.values().collect();
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, lcs::to_bytes(&entries)?)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn num_verified(&self) -> usize {
+        self.entries
+            .values()
+            .filter(|entry| entry.is_verified())
+            .count()
+    }
+
+    pub fn get(&self, peer_id: &PeerId) -> Option<&PeerRecord> {
+        self.entries.get(peer_id).map(|entry| &entry.record)
+    }
+
+    pub fn is_verified(&self, peer_id: &PeerId) -> bool {
+        self.entries.get(peer_id).map_or(false, Entry::is_verified)
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = &PeerRecord> {
+        self.entries.values().map(|entry| &entry.record)
+    }
+
+    /// Adds the record of a peer or replaces its known record. Returns whether the address book
+    /// changed. A verified record replaces older and unverified records, while an unverified
+    /// record only replaces an older record relayed by the same peer. When the address book is
+    /// full, the oldest unverified record is evicted; verified records are only evicted in favor
+    /// of other verified records.
+    pub fn insert(&mut self, record: PeerRecord, source: Source) -> bool {
+        let new_entry = Entry { record, source };
+        if let Some(entry) = self.entries.get_mut(&new_entry.record.peer_id) {
+            let newer = new_entry.record.timestamp_ms > entry.record.timestamp_ms;
+            let replace = if new_entry.is_verified() {
+                newer || !entry.is_verified()
+            } else {
+                newer && entry.source == new_entry.source
+            };
+            if replace {
+                *entry = new_entry;
+            }
+            return replace;
+        }
+        if self.entries.len() >= self.capacity && !self.evict(new_entry.is_verified()) {
+            return false;
+        }
+        self.entries.insert(new_entry.record.peer_id, new_entry);
+        true
+    }
+
+    fn evict(&mut self, for_verified: bool) -> bool {
+        let oldest = |verified: bool| {
+            self.entries
+                .values()
+                .filter(|entry| entry.is_verified() == verified)
+                .min_by_key(|entry| entry.record.timestamp_ms)
+                .map(|entry| entry.record.peer_id)
+        };
+        let victim = match oldest(false) {
+            Some(peer_id) => Some(peer_id),
+            None if for_verified => oldest(true),
+            None => None,
+        };
+        victim
+            .and_then(|peer_id| self.entries.remove(&peer_id))
+            .is_some()
+    }
+
+    /// Removes the records issued before `min_timestamp_ms`, returning whether any was removed.
+    pub fn expire(&mut self, min_timestamp_ms: u64) -> bool {
+        let len = self.entries.len();
+        self.entries
+            .retain(|_, entry| entry.record.timestamp_ms >= min_timestamp_ms);
+        self.entries.len() != len
+    }
+
+    /// Picks up to `amount` random verified records, other than the record of `exclude`.
+    pub fn sample_verified<R: Rng>(
+        &self,
+        rng: &mut R,
+        amount: usize,
+        exclude: &PeerId,
+    ) -> Vec<PeerRecord> {
+        self.entries
+            .values()
+            .filter(|entry| entry.is_verified() && &entry.record.peer_id != exclude)
+            .map(|entry| entry.record.clone())
+            .choose_multiple(rng, amount)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libra_temppath::TempPath;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn record(peer_id: PeerId, timestamp_ms: u64) -> PeerRecord {
+        PeerRecord {
+            peer_id,
+            addrs: vec![],
+            timestamp_ms,
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut address_book = AddressBook::new(10);
+        let peer_id = PeerId::random();
+        let (relayer, other_relayer) = (PeerId::random(), PeerId::random());
+        assert!(address_book.insert(record(peer_id, 10), Source::Relayed(relayer)));
+        // older or identical records are ignored
+        assert!(!address_book.insert(record(peer_id, 5), Source::Relayed(relayer)));
+        assert!(!address_book.insert(record(peer_id, 10), Source::Relayed(relayer)));
+        // records relayed by another peer don't replace the known record, however recent
+        assert!(!address_book.insert(record(peer_id, 20), Source::Relayed(other_relayer)));
+        assert!(address_book.insert(record(peer_id, 20), Source::Relayed(relayer)));
+        // the record received from the peer itself replaces relayed ones, however recent
+        assert!(address_book.insert(record(peer_id, 15), Source::Peer));
+        assert!(address_book.is_verified(&peer_id));
+        // unverified records don't replace verified ones
+        assert!(!address_book.insert(record(peer_id, 30), Source::Relayed(relayer)));
+        assert!(!address_book.insert(record(peer_id, 15), Source::Peer));
+        assert!(address_book.insert(record(peer_id, 30), Source::Peer));
+        assert_eq!(address_book.get(&peer_id).unwrap().timestamp_ms, 30);
+    }
+
+    #[test]
+    fn test_eviction() {
+        let mut address_book = AddressBook::new(2);
+        let peers: Vec<_> = (0..4).map(|_| PeerId::random()).collect();
+        let relayed = Source::Relayed(PeerId::random());
+        assert!(address_book.insert(record(peers[0], 1), Source::Peer));
+        assert!(address_book.insert(record(peers[1], 2), relayed));
+
+        // the unverified record is evicted first
+        assert!(address_book.insert(record(peers[2], 3), relayed));
+        assert!(address_book.get(&peers[1]).is_none());
+        assert!(address_book.insert(record(peers[3], 0), relayed));
+        assert!(address_book.get(&peers[2]).is_none());
+        assert_eq!(address_book.len(), 2);
+
+        // verified records only make room for verified records
+        assert!(address_book.insert(record(peers[3], 0), Source::Peer));
+        assert!(!address_book.insert(record(peers[1], 4), relayed));
+        assert!(address_book.insert(record(peers[1], 4), Source::Peer));
+        assert!(address_book.get(&peers[3]).is_none());
+        assert_eq!(address_book.num_verified(), 2);
+    }
+
+    #[test]
+    fn test_expire_and_sample() {
+        let mut address_book = AddressBook::new(10);
+        let peers: Vec<_> = (0..3).map(|_| PeerId::random()).collect();
+        address_book.insert(record(peers[0], 10), Source::Peer);
+        address_book.insert(record(peers[1], 20), Source::Peer);
+        address_book.insert(record(peers[2], 30), Source::Relayed(peers[0]));
+
+        let mut rng = StdRng::from_seed([0u8; 32]);
+        let sample = address_book.sample_verified(&mut rng, 10, &peers[1]);
+        assert_eq!(sample, vec![record(peers[0], 10)]);
+
+        assert!(address_book.expire(20));
+        assert!(!address_book.expire(20));
+        assert!(address_book.get(&peers[0]).is_none());
+        assert_eq!(address_book.len(), 2);
+    }
+
+    #[test]
+    fn test_persistence() {
+        let path = TempPath::new();
+        let mut address_book = AddressBook::new(10);
+        let peers: Vec<_> = (0..2).map(|_| PeerId::random()).collect();
+        address_book.insert(record(peers[0], 10), Source::Peer);
+        address_book.insert(record(peers[1], 20), Source::Relayed(peers[0]));
+        address_book.save(path.path()).unwrap();
+
+        let loaded = AddressBook::load(path.path(), 10).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert!(loaded.is_verified(&peers[0]));
+        assert!(!loaded.is_verified(&peers[1]));
+
+        // the capacity applies to loaded address books
+        assert_eq!(AddressBook::load(path.path(), 1).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    connectivity_manager::ConnectivityRequest,
+    constants,
+    protocols::peer_exchange::{
+        PeerExchange, PeerExchangeNetworkEvents, PeerExchangeNetworkSender,
+    },
+};
+use futures::stream::StreamExt;
+use futures_util::stream::Fuse;
+use libra_config::{config::PeerExchangeConfig, network_id::NetworkContext};
+use libra_network_address::NetworkAddress;
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    runtime::Handle,
+    time::{interval, Interval},
+};
+
+/// Configuration for a PeerExchangeBuilder.
+struct PeerExchangeBuilderConfig {
+    network_context: Arc<NetworkContext>,
+    self_addrs: Vec<NetworkAddress>,
+    config: PeerExchangeConfig,
+    network_tx: PeerExchangeNetworkSender,
+    network_rx: PeerExchangeNetworkEvents,
+    conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+}
+
+pub type PeerExchangeService = PeerExchange<Fuse<Interval>>;
+
+pub struct PeerExchangeBuilder {
+    config: Option<PeerExchangeBuilderConfig>,
+    service: Option<PeerExchangeService>,
+    built: bool,
+    started: bool,
+}
+
+impl PeerExchangeBuilder {
+    pub fn create(
+        network_context: Arc<NetworkContext>,
+        self_addrs: Vec<NetworkAddress>,
+        config: PeerExchangeConfig,
+        network_tx: PeerExchangeNetworkSender,
+        network_rx: PeerExchangeNetworkEvents,
+        conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+    ) -> Self {
+        Self {
+            config: Some(PeerExchangeBuilderConfig {
+                network_context,
+                self_addrs,
+                config,
+                network_tx,
+                network_rx,
+                conn_mgr_reqs_tx,
+            }),
+            service: None,
+            built: false,
+            started: false,
+        }
+    }
+
+    pub fn build(&mut self, executor: &Handle) -> &mut Self {
+        // Can only build once;  must build before starting.
+        assert!(!self.built);
+        assert!(!self.started);
+        self.built = true;
+        if let Some(config) = self.config.take() {
+            let service = executor.enter(|| {
+                PeerExchange::new(
+                    config.network_context,
+                    config.self_addrs,
+                    config.config.max_addresses,
+                    Some(config.config.address_book_path()),
+                    interval(Duration::from_millis(config.config.exchange_interval_ms)).fuse(),
+                    config.network_tx,
+                    config.network_rx,
+                    config.conn_mgr_reqs_tx,
+                    Duration::from_millis(constants::PEER_EXCHANGE_RPC_TIMEOUT_MS),
+                )
+            });
+            self.service = Some(service);
+        }
+        self
+    }
+
+    pub fn start(&mut self, executor: &Handle) {
+        // Must be built to start.
+        assert!(self.built);
+        // Can only start once.
+        assert!(!self.started);
+        self.started = true;
+        if let Some(service) = self.service.take() {
+            executor.spawn(service.start());
+        }
+    }
+}
@@ -0,0 +1,591 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Protocol used by full nodes to discover the addresses of other full nodes
+//!
+//! Public full nodes only know the seed peers from their config. With peer exchange, a full node
+//! periodically asks a random connected peer for the addresses of other reachable full nodes, and
+//! hands the addresses learned to the [`ConnectivityManager`], which picks a diverse set of them
+//! to dial.
+//!
+//! ## Records
+//!
+//! The addresses of a full node are shared as a [`PeerRecord`], issued by the node itself with a
+//! timestamp so that newer records replace older ones. Identity keys are x25519 keys, which can't
+//! sign, so a record is authenticated by the Noise session it is received over instead: only the
+//! record a peer sends about itself is verified. A record is accepted only if every address ends
+//! with the Noise key the peer id derives from, so that the Noise handshake authenticates the
+//! peer on dial, and a forged record can at worst make us dial an address where the peer isn't.
+//! The [`AddressBook`] keeps relayed records apart from verified ones: they never replace a
+//! record from another source, nor are they relayed further. Once we dialed a peer, we ask it
+//! for its own record right away.
+//!
+//! Peers sending records that fail verification are reported as misbehaving.
+//!
+//! [`ConnectivityManager`]: ../../connectivity_manager
+
+use crate::{
+    connectivity_manager::{ConnectivityRequest, DiscoverySource},
+    constants::NETWORK_CHANNEL_SIZE,
+    counters,
+    error::NetworkError,
+    peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
+    protocols::{
+        network::{Event, NetworkEvents, NetworkSender, NewNetworkSender, PeerBehavior},
+        rpc::error::RpcError,
+    },
+    ProtocolId,
+};
+use anyhow::{ensure, format_err, Result};
+use bytes::Bytes;
+use channel::message_queues::QueueStyle;
+use futures::{
+    channel::oneshot,
+    sink::SinkExt,
+    stream::{FusedStream, FuturesUnordered, Stream, StreamExt},
+};
+use libra_config::network_id::NetworkContext;
+use libra_logger::prelude::*;
+use libra_metrics::IntCounterVec;
+use libra_network_address::NetworkAddress;
+use libra_types::PeerId;
+use netcore::transport::ConnectionOrigin;
+use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+pub use self::address_book::{AddressBook, Source};
+
+pub mod address_book;
+pub mod builder;
+#[cfg(test)]
+mod test;
+
+/// Max number of records in a message.
+pub const MAX_RECORDS_PER_MSG: usize = 32;
+
+/// Max number of addresses in a record.
+pub const MAX_ADDRS_PER_RECORD: usize = 4;
+
+/// Records are dropped from the address book once they are older than this, unless the peer
+/// issues a newer one.
+pub const RECORD_TTL_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// How far in the future the timestamp of a record may be, to account for clock skew.
+pub const MAX_CLOCK_SKEW_MS: u64 = 10 * 60 * 1000;
+
+/// The interface from Network to PeerExchange layer.
+///
+/// `PeerExchangeNetworkEvents` is a `Stream` of `PeerManagerNotification` where the
+/// raw `Bytes` rpc messages are deserialized into
+/// `PeerExchangeMsg` types. `PeerExchangeNetworkEvents` is a thin wrapper
+/// around an `channel::Receiver<PeerManagerNotification>`.
+pub type PeerExchangeNetworkEvents = NetworkEvents<PeerExchangeMsg>;
+
+/// The interface from PeerExchange to Networking layer.
+///
+/// This is a thin wrapper around a `NetworkSender<PeerExchangeMsg>`, so it is
+/// easy to clone and send off to a separate task.
+#[derive(Clone)]
+pub struct PeerExchangeNetworkSender {
+    inner: NetworkSender<PeerExchangeMsg>,
+}
+
+/// Configuration for the network endpoints to support PeerExchange.
+pub fn network_endpoint_config() -> (
+    Vec<ProtocolId>,
+    Vec<ProtocolId>,
+    QueueStyle,
+    usize,
+    Option<&'static IntCounterVec>,
+) {
+    (
+        vec![ProtocolId::PeerExchangeRpc],
+        vec![],
+        QueueStyle::LIFO,
+        NETWORK_CHANNEL_SIZE,
+        Some(&counters::PENDING_PEER_EXCHANGE_NETWORK_EVENTS),
+    )
+}
+
+impl NewNetworkSender for PeerExchangeNetworkSender {
+    fn new(
+        peer_mgr_reqs_tx: PeerManagerRequestSender,
+        connection_reqs_tx: ConnectionRequestSender,
+    ) -> Self {
+        Self {
+            inner: NetworkSender::new(peer_mgr_reqs_tx, connection_reqs_tx),
+        }
+    }
+}
+
+impl PeerExchangeNetworkSender {
+    /// Send a PeerExchange RPC request to remote peer `recipient`. Returns the
+    /// remote peer's future reply.
+    pub async fn send_rpc(
+        &mut self,
+        recipient: PeerId,
+        req_msg: PeerExchangeMsg,
+        timeout: Duration,
+    ) -> Result<PeerExchangeMsg, RpcError> {
+        self.inner
+            .send_rpc(recipient, ProtocolId::PeerExchangeRpc, req_msg, timeout)
+            .await
+    }
+
+    pub fn report_peer(
+        &mut self,
+        peer: PeerId,
+        behavior: PeerBehavior,
+    ) -> Result<(), NetworkError> {
+        self.inner.report_peer(peer, behavior)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum PeerExchangeMsg {
+    GetPeers(GetPeers),
+    Peers(Peers),
+}
+
+/// Request for the records of other full nodes.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GetPeers {
+    /// The record of the requester, if it accepts inbound connections.
+    pub record: Option<PeerRecord>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Peers {
+    pub records: Vec<PeerRecord>,
+}
+
+/// The addresses a full node can be reached at, as issued by the node.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PeerRecord {
+    pub peer_id: PeerId,
+    pub addrs: Vec<NetworkAddress>,
+    /// Unix time in milliseconds at which the node issued the record.
+    pub timestamp_ms: u64,
+}
+
+impl PeerRecord {
+    /// Checks that the record is well-formed, and that its addresses authenticate its peer id.
+    pub fn verify(&self, now_ms: u64) -> Result<()> {
+        ensure!(
+            !self.addrs.is_empty() && self.addrs.len() <= MAX_ADDRS_PER_RECORD,
+            "Record with {} addresses",
+            self.addrs.len()
+        );
+        ensure!(
+            self.timestamp_ms <= now_ms.saturating_add(MAX_CLOCK_SKEW_MS),
+            "Record issued in the future: {}",
+            self.timestamp_ms
+        );
+        for addr in &self.addrs {
+            ensure!(
+                addr.is_libranet_addr(),
+                "Unexpected address format: {}",
+                addr
+            );
+            let pubkey = addr
+                .find_noise_proto()
+                .ok_or_else(|| format_err!("No Noise key in address: {}", addr))?;
+            ensure!(
+                PeerId::from_identity_public_key(pubkey) == self.peer_id,
+                "Address {} doesn't authenticate peer {}",
+                addr,
+                self.peer_id.short_str()
+            );
+        }
+        Ok(())
+    }
+
+    fn is_expired(&self, now_ms: u64) -> bool {
+        self.timestamp_ms.saturating_add(RECORD_TTL_MS) < now_ms
+    }
+}
+
+/// The actor running the peer exchange protocol.
+pub struct PeerExchange<TTicker> {
+    network_context: Arc<NetworkContext>,
+    /// The addresses this node advertises, empty if it doesn't accept inbound connections.
+    self_addrs: Vec<NetworkAddress>,
+    address_book: AddressBook,
+    /// Where the address book is persisted.
+    address_book_path: Option<PathBuf>,
+    /// Whether the address book changed since it was last persisted.
+    dirty: bool,
+    /// Currently connected peers.
+    connected: HashSet<PeerId>,
+    /// Ticker to trigger an exchange with a random peer.
+    ticker: TTicker,
+    /// Channel to send requests to Network layer.
+    network_tx: PeerExchangeNetworkSender,
+    /// Channel to receive notifications from Network layer.
+    network_rx: PeerExchangeNetworkEvents,
+    /// Channel to send requests to ConnectivityManager.
+    conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+    rpc_timeout: Duration,
+    /// Random-number generator.
+    rng: SmallRng,
+}
+
+impl<TTicker> PeerExchange<TTicker>
+where
+    TTicker: Stream + FusedStream + Unpin,
+{
+    pub fn new(
+        network_context: Arc<NetworkContext>,
+        self_addrs: Vec<NetworkAddress>,
+        max_addresses: usize,
+        address_book_path: Option<PathBuf>,
+        ticker: TTicker,
+        network_tx: PeerExchangeNetworkSender,
+        network_rx: PeerExchangeNetworkEvents,
+        conn_mgr_reqs_tx: channel::Sender<ConnectivityRequest>,
+        rpc_timeout: Duration,
+    ) -> Self {
+        let address_book = match &address_book_path {
+            Some(path) if path.exists() => {
+                AddressBook::load(path, max_addresses).unwrap_or_else(|err| {
+                    warn!(
+                        "{} Discarding address book {:?}: {}",
+                        network_context, path, err
+                    );
+                    AddressBook::new(max_addresses)
+                })
+            }
+            _ => AddressBook::new(max_addresses),
+        };
+        Self {
+            network_context,
+            self_addrs,
+            address_book,
+            address_book_path,
+            dirty: false,
+            connected: HashSet::new(),
+            ticker,
+            network_tx,
+            network_rx,
+            conn_mgr_reqs_tx,
+            rpc_timeout,
+            rng: SmallRng::from_entropy(),
+        }
+    }
+
+    pub async fn start(mut self) {
+        // Dial the peers known from a previous run right away.
+        self.address_book
+            .expire(now_ms().saturating_sub(RECORD_TTL_MS));
+        self.update_conn_mgr().await;
+
+        let mut pending_exchanges = FuturesUnordered::new();
+        loop {
+            futures::select! {
+                event = self.network_rx.select_next_some() => {
+                    if let Some(peer_id) = self.handle_network_event(event).await {
+                        pending_exchanges.push(Self::exchange(
+                            self.network_tx.clone(),
+                            peer_id,
+                            self.self_record(),
+                            self.rpc_timeout,
+                        ));
+                    }
+                }
+                _ = self.ticker.select_next_some() => {
+                    self.handle_tick().await;
+                    if let Some(peer_id) = self.sample_random_peer() {
+                        pending_exchanges.push(Self::exchange(
+                            self.network_tx.clone(),
+                            peer_id,
+                            self.self_record(),
+                            self.rpc_timeout,
+                        ));
+                    }
+                }
+                res = pending_exchanges.select_next_some() => {
+                    let (peer_id, result) = res;
+                    self.handle_peers_response(peer_id, result).await;
+                }
+                complete => {
+                    break;
+                }
+            }
+        }
+        crit!("{} Peer exchange actor terminated", self.network_context);
+    }
+
+    /// Handles an event from the network, returning the peer to exchange with right away if any.
+    async fn handle_network_event(
+        &mut self,
+        event: Result<Event<PeerExchangeMsg>, NetworkError>,
+    ) -> Option<PeerId> {
+        match event {
+            Ok(Event::NewPeer(peer_id, origin)) => {
+                self.connected.insert(peer_id);
+                // We dialed the peer, which accepts inbound connections: ask it for its own
+                // record, in place of the record relayed by others, if any.
+                if origin == ConnectionOrigin::Outbound && !self.address_book.is_verified(&peer_id)
+                {
+                    return Some(peer_id);
+                }
+            }
+            Ok(Event::LostPeer(peer_id, _origin)) => {
+                self.connected.remove(&peer_id);
+            }
+            Ok(Event::RpcRequest((peer_id, PeerExchangeMsg::GetPeers(request), res_tx))) => {
+                self.handle_get_peers(peer_id, request, res_tx).await;
+            }
+            Ok(Event::RpcRequest((peer_id, msg, _res_tx))) => {
+                warn!(
+                    "{} Unexpected rpc request from peer {}: {:?}",
+                    self.network_context,
+                    peer_id.short_str(),
+                    msg
+                );
+                self.report_peer(peer_id, PeerBehavior::Malformed);
+            }
+            Ok(Event::Message((peer_id, msg))) => {
+                warn!(
+                    "{} Unexpected message from peer {}: {:?}",
+                    self.network_context,
+                    peer_id.short_str(),
+                    msg
+                );
+                debug_assert!(false, "Unexpected network event");
+            }
+            Err(err) => {
+                info!("{} Received error: {}", self.network_context, err);
+            }
+        }
+        None
+    }
+
+    async fn handle_tick(&mut self) {
+        if self
+            .address_book
+            .expire(now_ms().saturating_sub(RECORD_TTL_MS))
+        {
+            self.dirty = true;
+            self.update_conn_mgr().await;
+        }
+        if !self.dirty {
+            return;
+        }
+        if let Some(path) = &self.address_book_path {
+            if let Err(err) = self.address_book.save(path) {
+                warn!(
+                    "{} Failed to persist address book {:?}: {}",
+                    self.network_context, path, err
+                );
+                return;
+            }
+        }
+        self.dirty = false;
+    }
+
+    async fn handle_get_peers(
+        &mut self,
+        peer_id: PeerId,
+        request: GetPeers,
+        res_tx: oneshot::Sender<Result<Bytes, RpcError>>,
+    ) {
+        if let Some(record) = request.record {
+            // The record comes from the peer itself, over its Noise session.
+            if record.peer_id != peer_id {
+                warn!(
+                    "{} Peer {} sent the record of another peer",
+                    self.network_context,
+                    peer_id.short_str()
+                );
+                self.report_peer(peer_id, PeerBehavior::Malformed);
+            } else if let Err(err) = record.verify(now_ms()) {
+                self.reject_record(peer_id, &record, err);
+            } else if self.address_book.insert(record, Source::Peer) {
+                self.dirty = true;
+                self.update_conn_mgr().await;
+            }
+        }
+
+        let mut records =
+            self.address_book
+                .sample_verified(&mut self.rng, MAX_RECORDS_PER_MSG - 1, &peer_id);
+        records.extend(self.self_record());
+        let response = match lcs::to_bytes(&PeerExchangeMsg::Peers(Peers { records })) {
+            Ok(response) => response,
+            Err(err) => {
+                warn!(
+                    "{} Unable to serialize peer exchange response: {}",
+                    self.network_context, err
+                );
+                return;
+            }
+        };
+        let _ = res_tx.send(Ok(response.into()));
+    }
+
+    async fn handle_peers_response(
+        &mut self,
+        peer_id: PeerId,
+        result: Result<PeerExchangeMsg, RpcError>,
+    ) {
+        let records = match result {
+            Ok(PeerExchangeMsg::Peers(peers)) if peers.records.len() <= MAX_RECORDS_PER_MSG => {
+                peers.records
+            }
+            Ok(msg) => {
+                warn!(
+                    "{} Unexpected peer exchange response from peer {}: {:?}",
+                    self.network_context,
+                    peer_id.short_str(),
+                    msg
+                );
+                self.report_peer(peer_id, PeerBehavior::Malformed);
+                return;
+            }
+            Err(err) => {
+                // Timeouts may well be caused by this node, so they aren't reported.
+                debug!(
+                    "{} Peer exchange with peer {} failed: {:?}",
+                    self.network_context,
+                    peer_id.short_str(),
+                    err
+                );
+                return;
+            }
+        };
+
+        let now_ms = now_ms();
+        let mut valid = true;
+        let mut changed = false;
+        for record in records {
+            if record.peer_id == self.network_context.peer_id() || record.is_expired(now_ms) {
+                continue;
+            }
+            if let Err(err) = record.verify(now_ms) {
+                self.reject_record(peer_id, &record, err);
+                valid = false;
+                break;
+            }
+            // Only the record of the responder itself is known to be authentic.
+            let source = if record.peer_id == peer_id {
+                Source::Peer
+            } else {
+                Source::Relayed(peer_id)
+            };
+            changed |= self.address_book.insert(record, source);
+        }
+        if valid {
+            self.report_peer(peer_id, PeerBehavior::Good);
+        }
+        if changed {
+            self.dirty = true;
+            self.update_conn_mgr().await;
+        }
+    }
+
+    fn reject_record(&mut self, peer_id: PeerId, record: &PeerRecord, err: anyhow::Error) {
+        warn!(
+            "{} Invalid record of peer {} sent by peer {}: {}",
+            self.network_context,
+            record.peer_id.short_str(),
+            peer_id.short_str(),
+            err
+        );
+        self.report_peer(peer_id, PeerBehavior::Malformed);
+    }
+
+    /// Hands the addresses of the address book to the ConnectivityManager, and makes their
+    /// peers eligible for dialing.
+    async fn update_conn_mgr(&mut self) {
+        self.record_num_addresses();
+        let addrs: HashMap<_, _> = self
+            .address_book
+            .records()
+            .map(|record| (record.peer_id, record.addrs.clone()))
+            .collect();
+        let pubkeys = addrs
+            .iter()
+            .map(|(peer_id, addrs)| {
+                let pubkeys: HashSet<_> = addrs
+                    .iter()
+                    .filter_map(NetworkAddress::find_noise_proto)
+                    .collect();
+                (*peer_id, pubkeys)
+            })
+            .collect();
+        for request in vec![
+            ConnectivityRequest::UpdateAddresses(DiscoverySource::PeerExchange, addrs),
+            ConnectivityRequest::UpdateEligibleNodes(DiscoverySource::PeerExchange, pubkeys),
+        ] {
+            if let Err(err) = self.conn_mgr_reqs_tx.send(request).await {
+                warn!(
+                    "{} Failed to update ConnectivityManager: {}",
+                    self.network_context, err
+                );
+            }
+        }
+    }
+
+    fn report_peer(&mut self, peer_id: PeerId, behavior: PeerBehavior) {
+        if let Err(err) = self.network_tx.report_peer(peer_id, behavior) {
+            warn!(
+                "{} Failed to report peer {}: {:?}",
+                self.network_context,
+                peer_id.short_str(),
+                err
+            );
+        }
+    }
+
+    async fn exchange(
+        mut network_tx: PeerExchangeNetworkSender,
+        peer_id: PeerId,
+        record: Option<PeerRecord>,
+        timeout: Duration,
+    ) -> (PeerId, Result<PeerExchangeMsg, RpcError>) {
+        let request = PeerExchangeMsg::GetPeers(GetPeers { record });
+        let result = network_tx.send_rpc(peer_id, request, timeout).await;
+        (peer_id, result)
+    }
+
+    fn self_record(&self) -> Option<PeerRecord> {
+        if self.self_addrs.is_empty() {
+            return None;
+        }
+        Some(PeerRecord {
+            peer_id: self.network_context.peer_id(),
+            addrs: self.self_addrs.clone(),
+            timestamp_ms: now_ms(),
+        })
+    }
+
+    fn sample_random_peer(&mut self) -> Option<PeerId> {
+        let peers: Vec<_> = self.connected.iter().cloned().collect();
+        peers.choose(&mut self.rng).cloned()
+    }
+
+    fn record_num_addresses(&self) {
+        let role = self.network_context.role().as_str();
+        let num_verified = self.address_book.num_verified();
+        counters::LIBRA_NETWORK_PEER_EXCHANGE_ADDRESSES
+            .with_label_values(&[role, "verified"])
+            .set(num_verified as i64);
+        counters::LIBRA_NETWORK_PEER_EXCHANGE_ADDRESSES
+            .with_label_values(&[role, "unverified"])
+            .set((self.address_book.len() - num_verified) as i64);
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("System clock reset to before unix epoch")
+        .as_millis() as u64
+}
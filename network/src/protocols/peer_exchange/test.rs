@@ -0,0 +1,418 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::{
+    peer_manager::{
+        self, conn_notifs_channel, ConnectionRequest, PeerManagerNotification, PeerManagerRequest,
+    },
+    protocols::{
+        network::{NewNetworkEvents, NewNetworkSender},
+        rpc::InboundRpcRequest,
+    },
+    ProtocolId,
+};
+use channel::{libra_channel, message_queues::QueueStyle};
+use libra_config::{
+    config::{RoleType, HANDSHAKE_VERSION},
+    network_id::NetworkId,
+};
+use libra_crypto::{x25519, Uniform};
+use rand::rngs::OsRng;
+use std::{num::NonZeroUsize, str::FromStr};
+use tokio::runtime::Runtime;
+
+const RPC_TIMEOUT: Duration = Duration::from_millis(500);
+
+struct TestHarness {
+    self_record: PeerRecord,
+    network_reqs_rx: libra_channel::Receiver<(PeerId, ProtocolId), PeerManagerRequest>,
+    network_notifs_tx: libra_channel::Sender<(PeerId, ProtocolId), PeerManagerNotification>,
+    connection_reqs_rx: libra_channel::Receiver<PeerId, ConnectionRequest>,
+    connection_notifs_tx: conn_notifs_channel::Sender,
+    conn_mgr_reqs_rx: channel::Receiver<ConnectivityRequest>,
+    ticker_tx: channel::Sender<()>,
+}
+
+fn gen_record(ip: &str) -> PeerRecord {
+    let pubkey = x25519::PrivateKey::generate(&mut OsRng).public_key();
+    let addr = NetworkAddress::from_str(&format!("/ip4/{}/tcp/6180", ip))
+        .unwrap()
+        .append_prod_protos(pubkey, HANDSHAKE_VERSION);
+    PeerRecord {
+        peer_id: PeerId::from_identity_public_key(pubkey),
+        addrs: vec![addr],
+        timestamp_ms: now_ms(),
+    }
+}
+
+fn setup_peer_exchange(rt: &mut Runtime) -> TestHarness {
+    let (ticker_tx, ticker_rx) = channel::new_test(0);
+    let (peer_mgr_reqs_tx, network_reqs_rx) =
+        libra_channel::new(QueueStyle::FIFO, NonZeroUsize::new(8).unwrap(), None);
+    let (connection_reqs_tx, connection_reqs_rx) =
+        libra_channel::new(QueueStyle::FIFO, NonZeroUsize::new(8).unwrap(), None);
+    let (network_notifs_tx, network_notifs_rx) =
+        libra_channel::new(QueueStyle::FIFO, NonZeroUsize::new(8).unwrap(), None);
+    let (connection_notifs_tx, connection_notifs_rx) = conn_notifs_channel::new();
+    let (conn_mgr_reqs_tx, conn_mgr_reqs_rx) = channel::new_test(8);
+
+    let network_tx = PeerExchangeNetworkSender::new(
+        PeerManagerRequestSender::new(peer_mgr_reqs_tx),
+        ConnectionRequestSender::new(connection_reqs_tx),
+    );
+    let network_rx = PeerExchangeNetworkEvents::new(network_notifs_rx, connection_notifs_rx);
+    let self_record = gen_record("10.0.0.1");
+    let network_context =
+        NetworkContext::new(NetworkId::Public, RoleType::FullNode, self_record.peer_id);
+    let peer_exchange = PeerExchange::new(
+        Arc::new(network_context),
+        self_record.addrs.clone(),
+        100,
+        None,
+        ticker_rx,
+        network_tx,
+        network_rx,
+        conn_mgr_reqs_tx,
+        RPC_TIMEOUT,
+    );
+    rt.spawn(peer_exchange.start());
+    TestHarness {
+        self_record,
+        network_reqs_rx,
+        network_notifs_tx,
+        connection_reqs_rx,
+        connection_notifs_tx,
+        conn_mgr_reqs_rx,
+        ticker_tx,
+    }
+}
+
+impl TestHarness {
+    async fn send_new_peer_notification(&mut self, peer_id: PeerId, origin: ConnectionOrigin) {
+        let (delivered_tx, delivered_rx) = oneshot::channel();
+        let notif = peer_manager::ConnectionNotification::NewPeer(
+            peer_id,
+            NetworkAddress::from_str("/ip4/127.0.0.1/tcp/6180").unwrap(),
+            origin,
+            NetworkContext::mock(),
+        );
+        self.connection_notifs_tx
+            .push_with_feedback(peer_id, notif, Some(delivered_tx))
+            .unwrap();
+        delivered_rx.await.unwrap();
+    }
+
+    async fn send_inbound_request(
+        &mut self,
+        peer_id: PeerId,
+        msg: PeerExchangeMsg,
+    ) -> oneshot::Receiver<Result<Bytes, RpcError>> {
+        let protocol = ProtocolId::PeerExchangeRpc;
+        let (res_tx, res_rx) = oneshot::channel();
+        let inbound_rpc_req = InboundRpcRequest {
+            protocol,
+            data: lcs::to_bytes(&msg).unwrap().into(),
+            res_tx,
+        };
+        let (delivered_tx, delivered_rx) = oneshot::channel();
+        self.network_notifs_tx
+            .push_with_feedback(
+                (peer_id, protocol),
+                PeerManagerNotification::RecvRpc(peer_id, inbound_rpc_req),
+                Some(delivered_tx),
+            )
+            .unwrap();
+        delivered_rx.await.unwrap();
+        res_rx
+    }
+
+    async fn expect_get_peers(
+        &mut self,
+    ) -> (PeerId, GetPeers, oneshot::Sender<Result<Bytes, RpcError>>) {
+        match self.network_reqs_rx.next().await.unwrap() {
+            PeerManagerRequest::SendRpc(peer_id, rpc_req) => {
+                assert_eq!(rpc_req.protocol, ProtocolId::PeerExchangeRpc);
+                match lcs::from_bytes(&rpc_req.data).unwrap() {
+                    PeerExchangeMsg::GetPeers(request) => (peer_id, request, rpc_req.res_tx),
+                    msg => panic!("Unexpected PeerExchangeMsg: {:?}", msg),
+                }
+            }
+            req => panic!("Unexpected PeerManagerRequest: {:?}", req),
+        }
+    }
+
+    /// Returns the addresses of the next update sent to the ConnectivityManager.
+    async fn expect_conn_mgr_update(&mut self) -> HashMap<PeerId, Vec<NetworkAddress>> {
+        let addrs = match self.conn_mgr_reqs_rx.next().await.unwrap() {
+            ConnectivityRequest::UpdateAddresses(DiscoverySource::PeerExchange, addrs) => addrs,
+            req => panic!("Unexpected ConnectivityRequest: {:?}", req),
+        };
+        match self.conn_mgr_reqs_rx.next().await.unwrap() {
+            ConnectivityRequest::UpdateEligibleNodes(DiscoverySource::PeerExchange, pubkeys) => {
+                assert_eq!(
+                    pubkeys.keys().collect::<HashSet<_>>(),
+                    addrs.keys().collect::<HashSet<_>>()
+                );
+            }
+            req => panic!("Unexpected ConnectivityRequest: {:?}", req),
+        }
+        addrs
+    }
+
+    async fn expect_report(&mut self, expected_peer_id: PeerId, expected_behavior: PeerBehavior) {
+        match self.connection_reqs_rx.next().await.unwrap() {
+            ConnectionRequest::ReportPeer(peer_id, behavior) => {
+                assert_eq!(peer_id, expected_peer_id);
+                assert_eq!(behavior, expected_behavior);
+            }
+            req => panic!("Unexpected ConnectionRequest: {:?}", req),
+        }
+    }
+}
+
+#[test]
+fn verify_record() {
+    let now = now_ms();
+    let record = gen_record("1.2.3.4");
+    record.verify(now).unwrap();
+
+    // The addresses must authenticate the peer id.
+    let mut other_peer = record.clone();
+    other_peer.peer_id = PeerId::random();
+    other_peer.verify(now).unwrap_err();
+
+    let mut not_libranet = record.clone();
+    not_libranet.addrs = vec![NetworkAddress::from_str("/ip4/1.2.3.4/tcp/6180").unwrap()];
+    not_libranet.verify(now).unwrap_err();
+
+    let mut no_addrs = record.clone();
+    no_addrs.addrs = vec![];
+    no_addrs.verify(now).unwrap_err();
+
+    let mut future = record.clone();
+    future.timestamp_ms = now + 2 * MAX_CLOCK_SKEW_MS;
+    future.verify(now).unwrap_err();
+
+    assert!(!record.is_expired(now));
+    assert!(record.is_expired(now + RECORD_TTL_MS + 1));
+}
+
+#[test]
+fn inbound_get_peers() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+    let mut rt = Runtime::new().unwrap();
+    let mut harness = setup_peer_exchange(&mut rt);
+
+    let f = async move {
+        assert!(harness.expect_conn_mgr_update().await.is_empty());
+
+        // A peer sends its own record along with its request.
+        let record = gen_record("10.1.0.1");
+        let peer_id = record.peer_id;
+        harness
+            .send_new_peer_notification(peer_id, ConnectionOrigin::Inbound)
+            .await;
+        let res_rx = harness
+            .send_inbound_request(
+                peer_id,
+                PeerExchangeMsg::GetPeers(GetPeers {
+                    record: Some(record.clone()),
+                }),
+            )
+            .await;
+
+        // The record is handed to the ConnectivityManager.
+        let addrs = harness.expect_conn_mgr_update().await;
+        assert_eq!(addrs.get(&peer_id), Some(&record.addrs));
+
+        // The response carries our own record, not the requester's.
+        let response = res_rx.await.unwrap().unwrap();
+        match lcs::from_bytes(&response).unwrap() {
+            PeerExchangeMsg::Peers(peers) => {
+                assert_eq!(peers.records.len(), 1);
+                assert_eq!(peers.records[0].peer_id, harness.self_record.peer_id);
+                assert_eq!(peers.records[0].addrs, harness.self_record.addrs);
+            }
+            msg => panic!("Unexpected PeerExchangeMsg: {:?}", msg),
+        }
+
+        // Another peer learns about the first one.
+        let other_peer_id = gen_record("10.2.0.1").peer_id;
+        let res_rx = harness
+            .send_inbound_request(
+                other_peer_id,
+                PeerExchangeMsg::GetPeers(GetPeers { record: None }),
+            )
+            .await;
+        let response = res_rx.await.unwrap().unwrap();
+        match lcs::from_bytes(&response).unwrap() {
+            PeerExchangeMsg::Peers(peers) => {
+                assert_eq!(peers.records.len(), 2);
+                assert!(peers.records.contains(&record));
+            }
+            msg => panic!("Unexpected PeerExchangeMsg: {:?}", msg),
+        }
+    };
+    rt.block_on(f);
+}
+
+#[test]
+fn outbound_exchange() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+    let mut rt = Runtime::new().unwrap();
+    let mut harness = setup_peer_exchange(&mut rt);
+
+    let f = async move {
+        assert!(harness.expect_conn_mgr_update().await.is_empty());
+
+        let responder = gen_record("10.1.0.1");
+        harness
+            .send_new_peer_notification(responder.peer_id, ConnectionOrigin::Inbound)
+            .await;
+        harness.ticker_tx.send(()).await.unwrap();
+
+        // We send our own record along with the request.
+        let (peer_id, request, res_tx) = harness.expect_get_peers().await;
+        assert_eq!(peer_id, responder.peer_id);
+        assert_eq!(request.record.unwrap().addrs, harness.self_record.addrs);
+
+        let relayed = gen_record("10.2.0.1");
+        let response = PeerExchangeMsg::Peers(Peers {
+            records: vec![
+                responder.clone(),
+                relayed.clone(),
+                harness.self_record.clone(),
+            ],
+        });
+        res_tx
+            .send(Ok(lcs::to_bytes(&response).unwrap().into()))
+            .unwrap();
+
+        harness.expect_report(peer_id, PeerBehavior::Good).await;
+        let addrs = harness.expect_conn_mgr_update().await;
+        assert_eq!(addrs.len(), 2);
+        assert_eq!(addrs.get(&responder.peer_id), Some(&responder.addrs));
+        assert_eq!(addrs.get(&relayed.peer_id), Some(&relayed.addrs));
+    };
+    rt.block_on(f);
+}
+
+#[test]
+fn invalid_record() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+    let mut rt = Runtime::new().unwrap();
+    let mut harness = setup_peer_exchange(&mut rt);
+
+    let f = async move {
+        assert!(harness.expect_conn_mgr_update().await.is_empty());
+
+        // We ask the peers we dial for their records right away.
+        let responder = gen_record("10.1.0.1");
+        harness
+            .send_new_peer_notification(responder.peer_id, ConnectionOrigin::Outbound)
+            .await;
+        let (peer_id, _request, res_tx) = harness.expect_get_peers().await;
+
+        // The responder tries to redirect us to its own address for another peer.
+        let mut forged = gen_record("10.2.0.1");
+        forged.addrs = responder.addrs.clone();
+        let response = PeerExchangeMsg::Peers(Peers {
+            records: vec![forged],
+        });
+        res_tx
+            .send(Ok(lcs::to_bytes(&response).unwrap().into()))
+            .unwrap();
+
+        harness
+            .expect_report(peer_id, PeerBehavior::Malformed)
+            .await;
+    };
+    rt.block_on(f);
+}
+
+#[test]
+fn relayed_records() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+    let mut rt = Runtime::new().unwrap();
+    let mut harness = setup_peer_exchange(&mut rt);
+
+    let f = async move {
+        assert!(harness.expect_conn_mgr_update().await.is_empty());
+
+        // A peer relays the record of another peer.
+        let relayer = gen_record("10.1.0.1");
+        let relayed = gen_record("10.2.0.1");
+        harness
+            .send_new_peer_notification(relayer.peer_id, ConnectionOrigin::Outbound)
+            .await;
+        let (peer_id, _request, res_tx) = harness.expect_get_peers().await;
+        assert_eq!(peer_id, relayer.peer_id);
+        let response = PeerExchangeMsg::Peers(Peers {
+            records: vec![relayed.clone()],
+        });
+        res_tx
+            .send(Ok(lcs::to_bytes(&response).unwrap().into()))
+            .unwrap();
+        harness.expect_report(peer_id, PeerBehavior::Good).await;
+        let addrs = harness.expect_conn_mgr_update().await;
+        assert_eq!(addrs.get(&relayed.peer_id), Some(&relayed.addrs));
+
+        // Another peer relays a more recent record, with different addresses. It might be
+        // forged, so it doesn't replace the record relayed first.
+        let other_relayer = gen_record("10.3.0.1");
+        harness
+            .send_new_peer_notification(other_relayer.peer_id, ConnectionOrigin::Outbound)
+            .await;
+        let (peer_id, _request, res_tx) = harness.expect_get_peers().await;
+        let mut newer = relayed.clone();
+        newer.addrs = vec![NetworkAddress::from_str("/ip4/10.3.0.2/tcp/6180")
+            .unwrap()
+            .append_prod_protos(
+                relayed.addrs[0].find_noise_proto().unwrap(),
+                HANDSHAKE_VERSION,
+            )];
+        newer.timestamp_ms += 1;
+        let response = PeerExchangeMsg::Peers(Peers {
+            records: vec![newer],
+        });
+        res_tx
+            .send(Ok(lcs::to_bytes(&response).unwrap().into()))
+            .unwrap();
+        harness.expect_report(peer_id, PeerBehavior::Good).await;
+
+        // Once dialed, the relayed peer is asked for its own record, which isn't relayed to the
+        // other peers before that.
+        harness
+            .send_new_peer_notification(relayed.peer_id, ConnectionOrigin::Outbound)
+            .await;
+        let (peer_id, _request, res_tx) = harness.expect_get_peers().await;
+        assert_eq!(peer_id, relayed.peer_id);
+        let res_rx = harness
+            .send_inbound_request(
+                other_relayer.peer_id,
+                PeerExchangeMsg::GetPeers(GetPeers { record: None }),
+            )
+            .await;
+        match lcs::from_bytes(&res_rx.await.unwrap().unwrap()).unwrap() {
+            PeerExchangeMsg::Peers(peers) => {
+                assert_eq!(peers.records.len(), 1);
+                assert_eq!(peers.records[0].peer_id, harness.self_record.peer_id);
+            }
+            msg => panic!("Unexpected PeerExchangeMsg: {:?}", msg),
+        }
+
+        let mut own = relayed.clone();
+        own.timestamp_ms += 2;
+        let response = PeerExchangeMsg::Peers(Peers {
+            records: vec![own.clone()],
+        });
+        res_tx
+            .send(Ok(lcs::to_bytes(&response).unwrap().into()))
+            .unwrap();
+        harness.expect_report(peer_id, PeerBehavior::Good).await;
+        let addrs = harness.expect_conn_mgr_update().await;
+        assert_eq!(addrs.get(&relayed.peer_id), Some(&own.addrs));
+    };
+    rt.block_on(f);
+}
@@ -0,0 +1,179 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Inbound rpc requests waiting for a free slot.
+//!
+//! Requests are queued by the priority class of their protocol, and dispatched from the most
+//! urgent class first, in arrival order within a class. When the queue is full, requests of the
+//! least urgent class make room for more urgent ones.
+
+use crate::{
+    peer::{priority_class, NUM_PRIORITY_CLASSES},
+    protocols::wire::messaging::v1::{RequestId, RpcRequest},
+};
+use std::{collections::VecDeque, time::Instant};
+
+/// An inbound request and the time by which it must be served.
+#[derive(Debug)]
+pub struct QueuedRequest {
+    pub request: RpcRequest,
+    pub deadline: Instant,
+}
+
+impl QueuedRequest {
+    fn class(&self) -> usize {
+        priority_class(self.request.protocol_id)
+    }
+}
+
+pub struct InboundRpcQueue {
+    /// Max number of queued requests.
+    capacity: usize,
+    classes: [VecDeque<QueuedRequest>; NUM_PRIORITY_CLASSES],
+}
+
+impl InboundRpcQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            classes: Default::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.classes.iter().map(VecDeque::len).sum()
+    }
+
+    /// Queues a request. If the queue is full, the newest request of the least urgent class is
+    /// dropped if it's less urgent than `queued`, otherwise `queued` is. Returns the dropped
+    /// request.
+    pub fn push(&mut self, queued: QueuedRequest) -> Option<QueuedRequest> {
+        let class = queued.class();
+        let dropped = if self.len() < self.capacity {
+            None
+        } else {
+            match self
+                .classes
+                .iter_mut()
+                .enumerate()
+                .rev()
+                .find(|(_, requests)| !requests.is_empty())
+            {
+                Some((least_urgent, requests)) if least_urgent > class => requests.pop_back(),
+                _ => return Some(queued),
+            }
+        };
+        self.classes[class].push_back(queued);
+        dropped
+    }
+
+    /// Whether a request with the given id is queued.
+    pub fn contains(&self, request_id: RequestId) -> bool {
+        self.classes
+            .iter()
+            .flatten()
+            .any(|queued| queued.request.request_id == request_id)
+    }
+
+    /// Removes the request with the given id, e.g. once the sender canceled it.
+    pub fn remove(&mut self, request_id: RequestId) -> Option<QueuedRequest> {
+        self.classes.iter_mut().find_map(|requests| {
+            let index = requests
+                .iter()
+                .position(|queued| queued.request.request_id == request_id)?;
+            requests.remove(index)
+        })
+    }
+
+    /// Pops the oldest request of the most urgent class, only considering the most urgent class
+    /// if `urgent_only` is set.
+    pub fn pop(&mut self, urgent_only: bool) -> Option<QueuedRequest> {
+        let num_classes = if urgent_only { 1 } else { NUM_PRIORITY_CLASSES };
+        self.classes[..num_classes]
+            .iter_mut()
+            .find_map(VecDeque::pop_front)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProtocolId;
+
+    fn request(request_id: RequestId, protocol_id: ProtocolId) -> QueuedRequest {
+        QueuedRequest {
+            request: RpcRequest {
+                request_id,
+                protocol_id,
+                priority: 0,
+                raw_request: vec![],
+            },
+            deadline: Instant::now(),
+        }
+    }
+
+    fn request_id(queued: Option<QueuedRequest>) -> Option<RequestId> {
+        queued.map(|queued| queued.request.request_id)
+    }
+
+    #[test]
+    fn test_priorities() {
+        let mut queue = InboundRpcQueue::new(8);
+        assert!(queue
+            .push(request(0, ProtocolId::PeerExchangeRpc))
+            .is_none());
+        assert!(queue
+            .push(request(1, ProtocolId::PeerExchangeRpc))
+            .is_none());
+        assert!(queue.push(request(2, ProtocolId::ConsensusRpc)).is_none());
+        assert_eq!(queue.len(), 3);
+
+        // Only consensus requests are popped when the remaining slots are reserved.
+        assert_eq!(request_id(queue.pop(true)), Some(2));
+        assert_eq!(request_id(queue.pop(true)), None);
+        assert_eq!(request_id(queue.pop(false)), Some(0));
+        assert_eq!(request_id(queue.pop(false)), Some(1));
+        assert_eq!(request_id(queue.pop(false)), None);
+    }
+
+    #[test]
+    fn test_full_queue() {
+        let mut queue = InboundRpcQueue::new(2);
+        assert!(queue
+            .push(request(0, ProtocolId::PeerExchangeRpc))
+            .is_none());
+        assert!(queue
+            .push(request(1, ProtocolId::PeerExchangeRpc))
+            .is_none());
+
+        // A consensus request takes the place of the newest peer exchange request.
+        assert_eq!(
+            request_id(queue.push(request(2, ProtocolId::ConsensusRpc))),
+            Some(1)
+        );
+        assert_eq!(
+            request_id(queue.push(request(3, ProtocolId::PeerExchangeRpc))),
+            Some(3)
+        );
+        assert_eq!(
+            request_id(queue.push(request(4, ProtocolId::ConsensusRpc))),
+            Some(0)
+        );
+        // The queue is full of consensus requests.
+        assert_eq!(
+            request_id(queue.push(request(5, ProtocolId::ConsensusRpc))),
+            Some(5)
+        );
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut queue = InboundRpcQueue::new(4);
+        queue.push(request(0, ProtocolId::ConsensusRpc));
+        queue.push(request(1, ProtocolId::PeerExchangeRpc));
+        assert_eq!(request_id(queue.remove(1)), Some(1));
+        assert_eq!(request_id(queue.remove(1)), None);
+        assert_eq!(queue.len(), 1);
+    }
+}
@@ -26,12 +26,18 @@
 //! The tasks for inbound and outbound RPCs are also "wrapped" within timeouts to ensure that they
 //! are not running forever. The outbound RPC timeout is specified by the upstream client, where as
 //! the inbound RPC timeout is a configuration parameter for the RPC actor.
+//! With messaging protocol v3, the outbound RPC timeout is sent along with the request, and the
+//! remote peer gives up on the request once it elapses, if that's earlier than its own inbound
+//! RPC timeout. When the upstream client cancels an outbound RPC, the remote peer is notified so
+//! that it stops processing the request.
 //!
 //! Limits:
 //! -------
 //! We limit the number of pending inbound RPC tasks to ensure that resource usage is bounded for
-//! inbound RPCs. For outbound RPCs, we log a warning when the limit is exceeded, but allow the RPC
-//! to proceed.
+//! inbound RPCs. Inbound requests received when at the limit are queued by the priority class of
+//! their protocol (see `InboundRpcQueue`), and the last slots are reserved to the most urgent
+//! class, so that consensus requests aren't held up by bulk traffic. For outbound RPCs, we log a
+//! warning when the limit is exceeded, but allow the RPC to proceed.
 //!
 //! State
 //! -------------
@@ -49,8 +55,12 @@ use crate::{
         RESPONSE_LABEL, SENT_LABEL,
     },
     peer::{PeerHandle, PeerNotification},
-    protocols::wire::messaging::v1::{
-        NetworkMessage, Priority, RequestId, RpcRequest, RpcResponse,
+    protocols::wire::{
+        handshake::v1::MessagingProtocolVersion,
+        messaging::v1::{
+            NetworkMessage, Priority, RequestId, RpcCancel, RpcRequest, RpcRequestWithDeadline,
+            RpcResponse,
+        },
     },
     ProtocolId,
 };
@@ -58,17 +68,24 @@ use bytes::Bytes;
 use error::RpcError;
 use futures::{
     channel::oneshot,
-    future::{self, BoxFuture, FutureExt, TryFutureExt},
+    future::{self, AbortHandle, BoxFuture, FutureExt, TryFutureExt},
     sink::SinkExt,
     stream::{FuturesUnordered, StreamExt},
     task::Context,
 };
+use inbound_queue::{InboundRpcQueue, QueuedRequest};
 use libra_logger::prelude::*;
 use libra_types::PeerId;
 use serde::Serialize;
-use std::{collections::HashMap, fmt::Debug, time::Duration};
+use std::{
+    cmp::min,
+    collections::{hash_map::Entry, HashMap},
+    fmt::Debug,
+    time::{Duration, Instant},
+};
 
 pub mod error;
+mod inbound_queue;
 
 #[cfg(any(feature = "fuzzing", test))]
 #[path = "fuzzing.rs"]
@@ -133,7 +150,8 @@ pub enum RpcNotification {
 }
 
 type OutboundRpcTasks = FuturesUnordered<BoxFuture<'static, RequestId>>;
-type InboundRpcTasks = FuturesUnordered<BoxFuture<'static, ()>>;
+/// Inbound rpc tasks yield their request id, and the sequence number of their abort handle.
+type InboundRpcTasks = FuturesUnordered<BoxFuture<'static, (RequestId, u64)>>;
 
 // Wraps the task of request id generation. Request ids start at 0 and increment till they hit
 // RequestId::MAX. After that, they wrap around to 0.
@@ -190,6 +208,19 @@ pub struct Rpc {
     /// The maximum number of concurrent inbound rpc requests that we will
     /// service before back-pressure kicks in.
     max_concurrent_inbound_rpcs: u32,
+    /// Number of inbound rpc slots which only requests of the most urgent priority class can use.
+    reserved_urgent_inbound_rpcs: u32,
+    /// Inbound rpc requests waiting for a slot.
+    inbound_rpc_queue: InboundRpcQueue,
+    /// Handles to abort inbound rpc tasks, in case the remote peer cancels the request. Each
+    /// handle has a sequence number, so that a task aborted after its request id was reused
+    /// doesn't remove the handle of the newer request.
+    inbound_rpc_handles: HashMap<RequestId, (u64, AbortHandle)>,
+    /// Sequence number of the next inbound rpc task.
+    next_inbound_rpc_seq: u64,
+    /// Messaging protocol of the connection, which determines whether deadlines and cancellations
+    /// are sent to the remote peer.
+    messaging_protocol: MessagingProtocolVersion,
 }
 
 impl Rpc {
@@ -202,6 +233,7 @@ impl Rpc {
         inbound_rpc_timeout: Duration,
         max_concurrent_outbound_rpcs: u32,
         max_concurrent_inbound_rpcs: u32,
+        messaging_protocol: MessagingProtocolVersion,
     ) -> Self {
         Self {
             request_id_gen: RequestIdGenerator::new(peer_handle.peer_id()),
@@ -213,6 +245,12 @@ impl Rpc {
             pending_outbound_rpcs: HashMap::new(),
             max_concurrent_outbound_rpcs,
             max_concurrent_inbound_rpcs,
+            // A quarter of the slots are reserved to the most urgent requests.
+            reserved_urgent_inbound_rpcs: max_concurrent_inbound_rpcs / 4,
+            inbound_rpc_queue: InboundRpcQueue::new(max_concurrent_inbound_rpcs as usize),
+            inbound_rpc_handles: HashMap::new(),
+            next_inbound_rpc_seq: 0,
+            messaging_protocol,
         }
     }
 
@@ -235,7 +273,15 @@ impl Rpc {
                         break;
                     }
                 },
-                () = inbound_rpc_tasks.select_next_some() => {
+                (request_id, seq) = inbound_rpc_tasks.select_next_some() => {
+                    // The handle was already removed if the request was canceled, and might
+                    // belong to a newer request with the same id since.
+                    if let Entry::Occupied(entry) = self.inbound_rpc_handles.entry(request_id) {
+                        if entry.get().0 == seq {
+                            entry.remove();
+                        }
+                    }
+                    self.dispatch_inbound_requests(&mut inbound_rpc_tasks);
                 },
                 request_id = outbound_rpc_tasks.select_next_some() => {
                     // Remove request_id from pending_outbound_rpcs if not already removed.
@@ -265,7 +311,21 @@ impl Rpc {
                     }
                     // This is a new inbound RPC request.
                     NetworkMessage::RpcRequest(request) => {
-                        self.handle_inbound_request(request, inbound_rpc_tasks);
+                        self.handle_inbound_request(request, None, inbound_rpc_tasks);
+                    }
+                    NetworkMessage::RpcRequestWithDeadline(RpcRequestWithDeadline {
+                        request,
+                        timeout_ms,
+                    }) => {
+                        self.handle_inbound_request(
+                            request,
+                            Some(Duration::from_millis(timeout_ms)),
+                            inbound_rpc_tasks,
+                        );
+                    }
+                    // The remote peer gave up on one of its requests.
+                    NetworkMessage::RpcCancel(RpcCancel { request_id }) => {
+                        self.handle_inbound_cancel(request_id);
                     }
                     _ => {
                         error!("Received non-RPC message from Peer actor: {:?}", message);
@@ -312,55 +372,135 @@ impl Rpc {
         }
     }
 
-    // Handle inbound request by spawning task (with timeout).
+    // Handle inbound request by queuing it until a slot is available, within the deadline set by
+    // the remote peer, if any.
     fn handle_inbound_request(
         &mut self,
         request: RpcRequest,
+        remote_timeout: Option<Duration>,
         inbound_rpc_tasks: &mut InboundRpcTasks,
     ) {
-        let notification_tx = self.rpc_handler_tx.clone();
-        let peer_handle = self.peer_handle.clone();
-        let peer_id_str = peer_handle.peer_id().short_str();
-        if inbound_rpc_tasks.len() as u32 == self.max_concurrent_inbound_rpcs {
-            // Increase counter of declined responses and log warning.
+        // Request ids must be unique among in-flight requests, otherwise the response and
+        // cancellation of one request could be mistaken for the other's.
+        let request_id = request.request_id;
+        if self.inbound_rpc_handles.contains_key(&request_id)
+            || self.inbound_rpc_queue.contains(request_id)
+        {
             counters::LIBRA_NETWORK_RPC_MESSAGES
                 .with_label_values(&[RESPONSE_LABEL, DECLINED_LABEL])
                 .inc();
             warn!(
-                "Pending inbound RPCs are at limit ({}). Not processing new inbound rpc requests",
-                self.max_concurrent_inbound_rpcs
+                "Peer {} reused the id {} of an in-flight request. Declining {} request",
+                self.peer_handle.peer_id().short_str(),
+                request_id,
+                request.protocol_id
             );
             return;
         }
-        let timeout = self.inbound_rpc_timeout;
-        // Handle request with timeout.
-        let f = async move {
-            if let Err(err) = tokio::time::timeout(
-                timeout,
-                handle_inbound_request_inner(notification_tx, request, peer_handle),
-            )
-            .map_err(Into::<RpcError>::into)
-            .map(|r| r.and_then(|x| x))
-            .await
+        let timeout = remote_timeout.map_or(self.inbound_rpc_timeout, |remote_timeout| {
+            min(remote_timeout, self.inbound_rpc_timeout)
+        });
+        let queued = QueuedRequest {
+            request,
+            deadline: Instant::now() + timeout,
+        };
+        if let Some(declined) = self.inbound_rpc_queue.push(queued) {
+            // Increase counter of declined responses and log warning.
+            counters::LIBRA_NETWORK_RPC_MESSAGES
+                .with_label_values(&[RESPONSE_LABEL, DECLINED_LABEL])
+                .inc();
+            warn!(
+                "Pending inbound RPCs are at limit ({}). Declining {} request",
+                self.max_concurrent_inbound_rpcs, declined.request.protocol_id
+            );
+        }
+        self.dispatch_inbound_requests(inbound_rpc_tasks);
+    }
+
+    // Handle inbound cancellation by dropping the request if it's still queued, or aborting the
+    // task processing it.
+    fn handle_inbound_cancel(&mut self, request_id: RequestId) {
+        let canceled = if self.inbound_rpc_queue.remove(request_id).is_some() {
+            true
+        } else if let Some((_, handle)) = self.inbound_rpc_handles.remove(&request_id) {
+            handle.abort();
+            true
+        } else {
+            false
+        };
+        if canceled {
+            counters::LIBRA_NETWORK_RPC_MESSAGES
+                .with_label_values(&[RESPONSE_LABEL, CANCELED_LABEL])
+                .inc();
+            trace!(
+                "Peer {} canceled inbound request with request_id {}",
+                self.peer_handle.peer_id().short_str(),
+                request_id
+            );
+        }
+    }
+
+    // Spawn tasks (with timeout) for the queued inbound requests while below the limit. Requests
+    // whose deadline passed while queued are declined.
+    fn dispatch_inbound_requests(&mut self, inbound_rpc_tasks: &mut InboundRpcTasks) {
+        while (inbound_rpc_tasks.len() as u32) < self.max_concurrent_inbound_rpcs {
+            let urgent_only = inbound_rpc_tasks.len() as u32
+                >= self.max_concurrent_inbound_rpcs - self.reserved_urgent_inbound_rpcs;
+            let QueuedRequest { request, deadline } = match self.inbound_rpc_queue.pop(urgent_only)
             {
-                // Log any errors.
+                Some(queued) => queued,
+                None => return,
+            };
+            let now = Instant::now();
+            if deadline <= now {
                 counters::LIBRA_NETWORK_RPC_MESSAGES
-                    .with_label_values(&[RESPONSE_LABEL, FAILED_LABEL])
+                    .with_label_values(&[RESPONSE_LABEL, DECLINED_LABEL])
                     .inc();
                 warn!(
-                    "Error handling inbound rpc request from {}: {:?}",
-                    peer_id_str, err
+                    "Deadline of {} request passed while queued. Declining it",
+                    request.protocol_id
                 );
+                continue;
             }
-        };
-        inbound_rpc_tasks.push(f.boxed());
+            let request_id = request.request_id;
+            let timeout = deadline - now;
+            let notification_tx = self.rpc_handler_tx.clone();
+            let peer_handle = self.peer_handle.clone();
+            let peer_id_str = peer_handle.peer_id().short_str();
+            // Handle request with timeout.
+            let f = async move {
+                if let Err(err) = tokio::time::timeout(
+                    timeout,
+                    handle_inbound_request_inner(notification_tx, request, peer_handle),
+                )
+                .map_err(Into::<RpcError>::into)
+                .map(|r| r.and_then(|x| x))
+                .await
+                {
+                    // Log any errors.
+                    counters::LIBRA_NETWORK_RPC_MESSAGES
+                        .with_label_values(&[RESPONSE_LABEL, FAILED_LABEL])
+                        .inc();
+                    warn!(
+                        "Error handling inbound rpc request from {}: {:?}",
+                        peer_id_str, err
+                    );
+                }
+            };
+            let (f, handle) = future::abortable(f);
+            let seq = self.next_inbound_rpc_seq;
+            self.next_inbound_rpc_seq += 1;
+            self.inbound_rpc_handles.insert(request_id, (seq, handle));
+            inbound_rpc_tasks.push(f.map(move |_| (request_id, seq)).boxed());
+        }
     }
 
     /// Handle an outbound rpc request.
     ///
     /// Cancellation is done by the client dropping the receiver side of the [`req.res_tx`]
     /// oneshot channel. If the request is canceled, the rpc future is dropped and the request is
-    /// canceled. Currently, we don't send a cancellation message to the remote peer.
+    /// canceled. With messaging protocol v3, a cancellation message is sent to the remote peer.
+    /// The remote peer isn't notified on timeout, as it already knows the deadline.
     ///
     /// [`req.res_tx`]: OutboundRpcRequest::res_tx
     async fn handle_outbound_rpc(
@@ -391,7 +531,9 @@ impl Rpc {
         } = req;
 
         let peer_handle = self.peer_handle.clone();
+        let mut cancel_peer_handle = self.peer_handle.clone();
         let peer_id_str = peer_handle.peer_id().short_str();
+        let messaging_protocol = self.messaging_protocol;
 
         // Generate and assign request id to this RPC.
         let request_id = self.request_id_gen.next();
@@ -407,7 +549,15 @@ impl Rpc {
             let mut f_rpc_res = tokio::time::timeout(
                 timeout,
                 // Future to run the actual outbound rpc protocol.
-                handle_outbound_rpc_inner(peer_handle, request_id, protocol, req_data, response_rx),
+                handle_outbound_rpc_inner(
+                    peer_handle,
+                    request_id,
+                    protocol,
+                    req_data,
+                    timeout,
+                    messaging_protocol,
+                    response_rx,
+                ),
             )
             .map_err(Into::<RpcError>::into)
             .map(|r| r.and_then(|x| x))
@@ -419,7 +569,7 @@ impl Rpc {
             let mut f_rpc_cancel =
                 future::poll_fn(|cx: &mut Context| res_tx.poll_canceled(cx)).fuse();
 
-            futures::select! {
+            let canceled = futures::select! {
                 res = f_rpc_res => {
                     // Log any errors.
                     if let Err(ref err) = res {
@@ -438,6 +588,7 @@ impl Rpc {
                             .inc();
                         info!("Rpc client canceled outbound rpc call to {}", peer_id_str);
                     }
+                    false
                 },
                 // The rpc client canceled the request
                 cancel = f_rpc_cancel => {
@@ -445,7 +596,17 @@ impl Rpc {
                         .with_label_values(&[REQUEST_LABEL, CANCELED_LABEL])
                         .inc();
                     info!("Rpc client canceled outbound rpc call to {}", peer_id_str);
+                    true
                 },
+            };
+            if canceled && messaging_protocol >= MessagingProtocolVersion::V3 {
+                let cancel = NetworkMessage::RpcCancel(RpcCancel { request_id });
+                if let Err(err) = cancel_peer_handle.send_message(cancel, protocol).await {
+                    warn!(
+                        "Failed to notify {} about canceled request_id {}: {:?}",
+                        peer_id_str, request_id, err
+                    );
+                }
             }
             // Return the request_id for state management in the main event-loop.
             request_id
@@ -459,6 +620,8 @@ async fn handle_outbound_rpc_inner(
     request_id: RequestId,
     protocol: ProtocolId,
     req_data: Bytes,
+    timeout: Duration,
+    messaging_protocol: MessagingProtocolVersion,
     response_rx: oneshot::Receiver<RpcResponse>,
 ) -> Result<Bytes, RpcError> {
     let req_len = req_data.len();
@@ -466,13 +629,21 @@ async fn handle_outbound_rpc_inner(
     let peer_id_str = peer_id.to_string();
 
     // Create NetworkMessage to be sent over the wire.
-    let request = NetworkMessage::RpcRequest(RpcRequest {
+    let request = RpcRequest {
         request_id,
         // TODO: Use default priority for now. To be exposed via network API.
         priority: Priority::default(),
         protocol_id: protocol,
         raw_request: Vec::from(req_data.as_ref()),
-    });
+    };
+    let request = if messaging_protocol >= MessagingProtocolVersion::V3 {
+        NetworkMessage::RpcRequestWithDeadline(RpcRequestWithDeadline {
+            request,
+            timeout_ms: timeout.as_millis() as u64,
+        })
+    } else {
+        NetworkMessage::RpcRequest(request)
+    };
 
     // Send outbound request to peer_handle.
     trace!(
@@ -8,7 +8,7 @@
 
 use super::{error::RpcError, *};
 use crate::{
-    counters::{CANCELED_LABEL, FAILED_LABEL, REQUEST_LABEL, RESPONSE_LABEL},
+    counters::{CANCELED_LABEL, DECLINED_LABEL, FAILED_LABEL, REQUEST_LABEL, RESPONSE_LABEL},
     peer::{PeerNotification, PeerRequest},
     peer_manager::PeerManagerError,
 };
@@ -33,6 +33,19 @@ fn start_rpc_actor(
     channel::Receiver<RpcNotification>,
     channel::Receiver<PeerRequest>,
     channel::Sender<PeerNotification>,
+) {
+    start_rpc_actor_with_protocol(executor, MessagingProtocolVersion::V1, 10)
+}
+
+fn start_rpc_actor_with_protocol(
+    executor: Handle,
+    messaging_protocol: MessagingProtocolVersion,
+    max_concurrent_inbound_rpcs: u32,
+) -> (
+    channel::Sender<OutboundRpcRequest>,
+    channel::Receiver<RpcNotification>,
+    channel::Receiver<PeerRequest>,
+    channel::Sender<PeerNotification>,
 ) {
     let (peer_reqs_tx, peer_reqs_rx) = channel::new_test(8);
     let (peer_notifs_tx, peer_notifs_rx) = channel::new_test(8);
@@ -47,7 +60,8 @@ fn start_rpc_actor(
         rpc_notifs_tx,
         Duration::from_secs(1), // 1 second inbound rpc timeout.
         10,                     // max_concurrent_outbound_rpcs
-        10,                     // max_concurrent_inbound_rpcs
+        max_concurrent_inbound_rpcs,
+        messaging_protocol,
     );
     executor.spawn(rpc.start());
     (rpc_requests_tx, rpc_notifs_rx, peer_reqs_rx, peer_notifs_tx)
@@ -778,3 +792,270 @@ fn concurrent_inbound_outbound() {
     let f = join(f_send_rpc, f_mock_peer);
     rt.block_on(f);
 }
+
+fn create_network_request_with_deadline(
+    request_id: RequestId,
+    protocol_id: ProtocolId,
+    raw_request: Bytes,
+    timeout_ms: u64,
+) -> NetworkMessage {
+    NetworkMessage::RpcRequestWithDeadline(RpcRequestWithDeadline {
+        request: RpcRequest {
+            request_id,
+            protocol_id,
+            priority: Priority::default(),
+            raw_request: Vec::from(raw_request.as_ref()),
+        },
+        timeout_ms,
+    })
+}
+
+// Test that outbound rpcs carry their deadline, and that the remote peer is notified when they
+// are canceled.
+#[test]
+#[serial]
+fn outbound_rpc_deadline_and_cancellation() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+
+    let mut rt = Runtime::new().unwrap();
+    let (mut rpc_requests_tx, _rpc_notifs_rx, mut peer_reqs_rx, _peer_notifs_tx) =
+        start_rpc_actor_with_protocol(rt.handle().clone(), MessagingProtocolVersion::V3, 10);
+
+    let protocol_id = RPC_PROTOCOL_A;
+    let req_data = Bytes::from_static(b"hello");
+
+    let (res_tx, res_rx) = oneshot::channel();
+
+    let f_send_rpc = async move {
+        rpc_requests_tx
+            .send(OutboundRpcRequest {
+                protocol: protocol_id,
+                data: req_data.clone(),
+                res_tx,
+                timeout: Duration::from_secs(100),
+            })
+            .await
+            .unwrap();
+
+        let request = create_network_request_with_deadline(
+            0 as RequestId,
+            protocol_id,
+            req_data.clone(),
+            100_000,
+        );
+        expect_successful_send(&mut peer_reqs_rx, protocol_id, request).await;
+
+        // Cancel the rpc request and expect the remote peer to be notified.
+        drop(res_rx);
+        let cancel = NetworkMessage::RpcCancel(RpcCancel { request_id: 0 });
+        expect_successful_send(&mut peer_reqs_rx, protocol_id, cancel).await;
+    };
+    rt.block_on(f_send_rpc);
+}
+
+// Test that inbound rpcs canceled by the remote peer are aborted.
+#[test]
+#[serial]
+fn inbound_rpc_cancellation() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+
+    let mut rt = Runtime::new().unwrap();
+    let (_rpc_requests_tx, mut rpc_notifs_rx, _peer_reqs_rx, mut peer_notifs_tx) =
+        start_rpc_actor_with_protocol(rt.handle().clone(), MessagingProtocolVersion::V3, 10);
+
+    let protocol_id = RPC_PROTOCOL_A;
+    let req_data = Bytes::from_static(b"Hello");
+
+    let f_mock_peer = async move {
+        let request = create_network_request_with_deadline(0, protocol_id, req_data, 10_000);
+        peer_notifs_tx
+            .send(PeerNotification::NewMessage(request))
+            .await
+            .unwrap();
+        let RpcNotification::RecvRpc(request) = rpc_notifs_rx.next().await.unwrap();
+
+        let cancel = NetworkMessage::RpcCancel(RpcCancel { request_id: 0 });
+        peer_notifs_tx
+            .send(PeerNotification::NewMessage(cancel))
+            .await
+            .unwrap();
+        // The task waiting for the response is aborted.
+        let mut res_tx = request.res_tx;
+        future::poll_fn(|cx: &mut Context| res_tx.poll_canceled(cx)).await;
+        assert_eq!(
+            counters::LIBRA_NETWORK_RPC_MESSAGES
+                .with_label_values(&[RESPONSE_LABEL, CANCELED_LABEL])
+                .get() as u64,
+            1
+        );
+    };
+    rt.block_on(f_mock_peer);
+}
+
+// Test that an inbound rpc reusing the id of an in-flight request is declined, and that the id can
+// be reused once the request is canceled.
+#[test]
+#[serial]
+fn inbound_rpc_duplicate_request_id() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+
+    let mut rt = Runtime::new().unwrap();
+    let (_rpc_requests_tx, mut rpc_notifs_rx, _peer_reqs_rx, mut peer_notifs_tx) =
+        start_rpc_actor_with_protocol(rt.handle().clone(), MessagingProtocolVersion::V3, 10);
+
+    let protocol_id = RPC_PROTOCOL_A;
+    let req_data = Bytes::from_static(b"Hello");
+
+    let f_mock_peer = async move {
+        let other_data = Bytes::from_static(b"Hola");
+        for (request_id, data) in vec![(0, &req_data), (0, &other_data), (1, &other_data)] {
+            let request =
+                create_network_request_with_deadline(request_id, protocol_id, data.clone(), 10_000);
+            peer_notifs_tx
+                .send(PeerNotification::NewMessage(request))
+                .await
+                .unwrap();
+        }
+        // The duplicate is declined, and the next request goes through.
+        let mut pending = vec![];
+        for _ in 0..2 {
+            let RpcNotification::RecvRpc(request) = rpc_notifs_rx.next().await.unwrap();
+            pending.push(request);
+        }
+        let first = pending
+            .into_iter()
+            .find(|request| request.data == req_data)
+            .unwrap();
+        assert_eq!(
+            counters::LIBRA_NETWORK_RPC_MESSAGES
+                .with_label_values(&[RESPONSE_LABEL, DECLINED_LABEL])
+                .get() as u64,
+            1
+        );
+
+        // Once canceled, the id can be reused right away, and canceling it again aborts the new
+        // request rather than nothing.
+        let cancel = NetworkMessage::RpcCancel(RpcCancel { request_id: 0 });
+        let request = create_network_request_with_deadline(0, protocol_id, req_data, 10_000);
+        for message in vec![cancel.clone(), request, cancel] {
+            peer_notifs_tx
+                .send(PeerNotification::NewMessage(message))
+                .await
+                .unwrap();
+        }
+        let mut res_tx = first.res_tx;
+        future::poll_fn(|cx: &mut Context| res_tx.poll_canceled(cx)).await;
+        let RpcNotification::RecvRpc(reused) = rpc_notifs_rx.next().await.unwrap();
+        let mut res_tx = reused.res_tx;
+        future::poll_fn(|cx: &mut Context| res_tx.poll_canceled(cx)).await;
+        assert_eq!(
+            counters::LIBRA_NETWORK_RPC_MESSAGES
+                .with_label_values(&[RESPONSE_LABEL, CANCELED_LABEL])
+                .get() as u64,
+            2
+        );
+    };
+    rt.block_on(f_mock_peer);
+}
+
+// Test that inbound rpcs whose deadline passes while queued are declined.
+#[test]
+#[serial]
+fn inbound_rpc_expired_deadline() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+
+    let mut rt = Runtime::new().unwrap();
+    let (_rpc_requests_tx, mut rpc_notifs_rx, _peer_reqs_rx, mut peer_notifs_tx) =
+        start_rpc_actor_with_protocol(rt.handle().clone(), MessagingProtocolVersion::V3, 10);
+
+    let protocol_id = RPC_PROTOCOL_A;
+    let req_data = Bytes::from_static(b"Hello");
+
+    let f_mock_peer = async move {
+        let expired = create_network_request_with_deadline(0, protocol_id, req_data.clone(), 0);
+        let request = create_network_request_with_deadline(1, protocol_id, req_data, 10_000);
+        for message in vec![expired, request] {
+            peer_notifs_tx
+                .send(PeerNotification::NewMessage(message))
+                .await
+                .unwrap();
+        }
+        // Only the second request reaches the upstream.
+        let RpcNotification::RecvRpc(request) = rpc_notifs_rx.next().await.unwrap();
+        assert_eq!(request.data, Bytes::from_static(b"Hello"));
+        assert_eq!(
+            counters::LIBRA_NETWORK_RPC_MESSAGES
+                .with_label_values(&[RESPONSE_LABEL, DECLINED_LABEL])
+                .get() as u64,
+            1
+        );
+    };
+    rt.block_on(f_mock_peer);
+}
+
+// Test that consensus rpcs are served even when bulk requests hold up the other slots.
+#[test]
+#[serial]
+fn inbound_rpc_priorities() {
+    ::libra_logger::Logger::new().environment_only(true).init();
+
+    let mut rt = Runtime::new().unwrap();
+    // One out of the 4 slots is reserved to the most urgent protocols.
+    let (_rpc_requests_tx, mut rpc_notifs_rx, mut peer_reqs_rx, mut peer_notifs_tx) =
+        start_rpc_actor_with_protocol(rt.handle().clone(), MessagingProtocolVersion::V3, 4);
+
+    let bulk_protocol = ProtocolId::PeerExchangeRpc;
+    let urgent_protocol = ProtocolId::ConsensusRpc;
+    let req_data = Bytes::from_static(b"Hello");
+    let resp_data = Bytes::from_static(b"Bonjour");
+
+    let f_mock_peer = async move {
+        // The bulk requests take up all the slots but the reserved one, and the last one waits.
+        for request_id in 0..4 {
+            let request = create_network_request_with_deadline(
+                request_id,
+                bulk_protocol,
+                req_data.clone(),
+                10_000,
+            );
+            peer_notifs_tx
+                .send(PeerNotification::NewMessage(request))
+                .await
+                .unwrap();
+        }
+        let mut pending = vec![];
+        for _ in 0..3 {
+            let RpcNotification::RecvRpc(request) = rpc_notifs_rx.next().await.unwrap();
+            assert_eq!(request.protocol, bulk_protocol);
+            pending.push(request);
+        }
+
+        // The consensus request goes through the reserved slot.
+        let request =
+            create_network_request_with_deadline(4, urgent_protocol, req_data.clone(), 10_000);
+        peer_notifs_tx
+            .send(PeerNotification::NewMessage(request))
+            .await
+            .unwrap();
+        let RpcNotification::RecvRpc(request) = rpc_notifs_rx.next().await.unwrap();
+        assert_eq!(request.protocol, urgent_protocol);
+
+        // Once the consensus request and a bulk request are served, the waiting one is
+        // dispatched.
+        request.res_tx.send(Ok(resp_data.clone())).unwrap();
+        let response = create_network_response(4, resp_data.clone());
+        expect_successful_send(&mut peer_reqs_rx, urgent_protocol, response).await;
+        let served = pending.remove(0);
+        served.res_tx.send(Ok(resp_data)).unwrap();
+        match peer_reqs_rx.next().await.unwrap() {
+            PeerRequest::SendMessage(NetworkMessage::RpcResponse(_), protocol, res_tx) => {
+                assert_eq!(protocol, bulk_protocol);
+                res_tx.send(Ok(())).unwrap();
+            }
+            req => panic!("Unexpected PeerRequest: {:?}, expected RpcResponse", req),
+        }
+        let RpcNotification::RecvRpc(request) = rpc_notifs_rx.next().await.unwrap();
+        assert_eq!(request.protocol, bulk_protocol);
+    };
+    rt.block_on(f_mock_peer);
+}
@@ -29,9 +29,24 @@ pub enum ProtocolId {
     StateSynchronizerDirectSend = 3,
     DiscoveryDirectSend = 4,
     HealthCheckerRpc = 5,
+    PeerExchangeRpc = 6,
 }
 
 impl ProtocolId {
+    /// All the application protocols, in wire order.
+    pub fn all() -> &'static [ProtocolId] {
+        use ProtocolId::*;
+        &[
+            ConsensusRpc,
+            ConsensusDirectSend,
+            MempoolDirectSend,
+            StateSynchronizerDirectSend,
+            DiscoveryDirectSend,
+            HealthCheckerRpc,
+            PeerExchangeRpc,
+        ]
+    }
+
     pub fn as_str(self) -> &'static str {
         use ProtocolId::*;
         match self {
@@ -41,6 +56,7 @@ impl ProtocolId {
             StateSynchronizerDirectSend => "StateSynchronizerDirectSend",
             DiscoveryDirectSend => "DiscoveryDirectSend",
             HealthCheckerRpc => "HealthCheckerRpc",
+            PeerExchangeRpc => "PeerExchangeRpc",
         }
     }
 }
@@ -69,6 +85,9 @@ pub struct HandshakeMsg {
 #[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Debug, Hash, Deserialize, Serialize)]
 pub enum MessagingProtocolVersion {
     V1 = 0,
+    V2 = 1,
+    /// Frames messages as V2, adds RPC deadlines and cancellation, and rekeys the noise session.
+    V3 = 2,
 }
 
 impl TryInto<Vec<ProtocolId>> for SupportedProtocols {
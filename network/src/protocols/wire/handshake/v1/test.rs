@@ -91,3 +91,30 @@ fn common_protocols() {
         h1.find_common_protocols(&h2)
     );
 }
+
+#[test]
+fn highest_common_messaging_protocol() {
+    let network_id = NetworkId::default();
+    let chain_id = ChainId::default();
+    let protocols: SupportedProtocols = [ProtocolId::ConsensusRpc].iter().into();
+
+    let mut v1 = HandshakeMsg::new(chain_id, network_id.clone());
+    v1.add(MessagingProtocolVersion::V1, protocols.clone());
+    let mut v1_v2 = HandshakeMsg::new(chain_id, network_id);
+    v1_v2.add(MessagingProtocolVersion::V1, protocols.clone());
+    v1_v2.add(MessagingProtocolVersion::V2, protocols.clone());
+
+    // Nodes supporting V2 fall back to V1 with older nodes.
+    assert_eq!(
+        Some((MessagingProtocolVersion::V1, protocols.clone())),
+        v1_v2.find_common_protocols(&v1)
+    );
+    assert_eq!(
+        Some((MessagingProtocolVersion::V1, protocols.clone())),
+        v1.find_common_protocols(&v1_v2)
+    );
+    assert_eq!(
+        Some((MessagingProtocolVersion::V2, protocols)),
+        v1_v2.find_common_protocols(&v1_v2)
+    );
+}
@@ -3,3 +3,5 @@
 
 // v1 of the LibraNet messaging protocol.
 pub mod v1;
+// v2 of the LibraNet messaging protocol, adding fragmentation of large messages.
+pub mod v2;
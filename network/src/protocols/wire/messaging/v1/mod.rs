@@ -20,6 +20,10 @@ pub enum NetworkMessage {
     RpcRequest(RpcRequest),
     RpcResponse(RpcResponse),
     DirectSendMsg(DirectSendMsg),
+    /// Only sent with messaging protocol v3 and above, in place of `RpcRequest`.
+    RpcRequestWithDeadline(RpcRequestWithDeadline),
+    /// Only sent with messaging protocol v3 and above.
+    RpcCancel(RpcCancel),
 }
 
 /// Enum representing various error codes that can be embedded in NetworkMessage.
@@ -57,6 +61,21 @@ pub struct RpcRequest {
     pub raw_request: Vec<u8>,
 }
 
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct RpcRequestWithDeadline {
+    pub request: RpcRequest,
+    /// Time left to the caller to get a response when the request was sent, in milliseconds.
+    /// This is relative so that it doesn't depend on the clocks of the peers being in sync.
+    pub timeout_ms: u64,
+}
+
+/// Notifies the receiver of an RpcRequest that the sender is no longer waiting for its response.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct RpcCancel {
+    /// RequestId of the canceled request.
+    pub request_id: RequestId,
+}
+
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub struct RpcResponse {
     /// RequestId for corresponding request. This is copied as is from the RpcRequest.
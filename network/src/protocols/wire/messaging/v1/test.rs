@@ -37,3 +37,28 @@ fn rpc_request() -> lcs::Result<()> {
     );
     Ok(())
 }
+
+// The messages of messaging protocol v3 are appended to `NetworkMessage` so that the encoding of
+// the v1 messages is unchanged.
+#[test]
+fn rpc_deadline_and_cancel() -> lcs::Result<()> {
+    let rpc_request = NetworkMessage::RpcRequestWithDeadline(RpcRequestWithDeadline {
+        request: RpcRequest {
+            request_id: 25,
+            protocol_id: ProtocolId::ConsensusRpc,
+            priority: 0,
+            raw_request: [0, 1].to_vec(),
+        },
+        timeout_ms: 1000,
+    });
+    assert_eq!(
+        lcs::to_bytes(&rpc_request)?,
+        // [6] -> variant index
+        // [25, 0, 0, 0, 0, 0, 2, 0, 1] -> request
+        // [232, 3, 0, 0, 0, 0, 0, 0] -> timeout_ms
+        vec![6, 25, 0, 0, 0, 0, 0, 2, 0, 1, 232, 3, 0, 0, 0, 0, 0, 0]
+    );
+    let rpc_cancel = NetworkMessage::RpcCancel(RpcCancel { request_id: 25 });
+    assert_eq!(lcs::to_bytes(&rpc_cancel)?, vec![7, 25, 0, 0, 0]);
+    Ok(())
+}
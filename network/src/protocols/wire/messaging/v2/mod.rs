@@ -0,0 +1,196 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! This module defines the structs transported during the network messaging protocol v2.
+//! These should serialize as per `specifications/network/messaging-v2.md`.
+//!
+//! Messaging protocol v2 carries the same `NetworkMessage`s as v1, but messages larger than
+//! `MAX_FRAGMENT_SIZE` are split into a stream of fragments. The fragments of a large message
+//! can be interleaved with more urgent messages, so that e.g. a state sync chunk doesn't hold up
+//! consensus votes sent over the same connection.
+//!
+//! Messaging protocol v3 uses the same framing, and adds the RPC deadline and cancellation
+//! variants of `NetworkMessage`, see `specifications/network/messaging-v3.md`.
+
+use crate::protocols::wire::messaging::v1::NetworkMessage;
+use anyhow::{ensure, format_err, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod test;
+
+/// Max size of the payload of a fragment.
+pub const MAX_FRAGMENT_SIZE: usize = 64 * 1024;
+
+/// Max number of streams being received concurrently on a connection.
+pub const MAX_CONCURRENT_STREAMS: usize = 16;
+
+/// Max number of max-sized messages buffered by the reassembly of a connection, across all its
+/// streams. A sender interleaves at most one large message per priority class.
+pub const MAX_BUFFERED_MESSAGES: usize = 3;
+
+/// Create alias StreamId for u32.
+pub type StreamId = u32;
+
+/// Frames that are sent on the wire.
+/// New variants cannot be added without bumping up the MessagingProtocolVersion.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub enum MultiplexMessage {
+    /// A message sent in a single frame.
+    Message(NetworkMessage),
+    /// A fragment of a serialized `NetworkMessage`.
+    Fragment(StreamFragment),
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct StreamFragment {
+    /// Identifier of the stream, unique among the streams in flight from the sender.
+    pub stream_id: StreamId,
+    /// Index of the fragment in the stream. Fragments are sent in order.
+    pub fragment_id: u32,
+    /// Number of fragments of the stream.
+    pub num_fragments: u32,
+    /// Fragment payload.
+    #[serde(with = "serde_bytes")]
+    pub raw_data: Vec<u8>,
+}
+
+/// Splits outbound messages into frames.
+#[derive(Default)]
+pub struct Fragmenter {
+    next_stream_id: StreamId,
+}
+
+impl Fragmenter {
+    /// Returns the serialized frames carrying `message`, in sending order.
+    pub fn frames(&mut self, message: NetworkMessage) -> lcs::Result<Vec<Vec<u8>>> {
+        let serialized = lcs::to_bytes(&message)?;
+        if serialized.len() <= MAX_FRAGMENT_SIZE {
+            return Ok(vec![lcs::to_bytes(&MultiplexMessage::Message(message))?]);
+        }
+        let stream_id = self.next_stream_id;
+        self.next_stream_id = self.next_stream_id.wrapping_add(1);
+        let chunks = serialized.chunks(MAX_FRAGMENT_SIZE);
+        let num_fragments = chunks.len() as u32;
+        chunks
+            .enumerate()
+            .map(|(fragment_id, chunk)| {
+                lcs::to_bytes(&MultiplexMessage::Fragment(StreamFragment {
+                    stream_id,
+                    fragment_id: fragment_id as u32,
+                    num_fragments,
+                    raw_data: chunk.to_vec(),
+                }))
+            })
+            .collect()
+    }
+}
+
+struct PartialMessage {
+    num_fragments: u32,
+    next_fragment_id: u32,
+    data: Vec<u8>,
+}
+
+/// Reassembles the inbound streams of a connection.
+pub struct Reassembler {
+    max_message_size: usize,
+    streams: HashMap<StreamId, PartialMessage>,
+    /// Total size of the partial messages.
+    buffered: usize,
+}
+
+impl Reassembler {
+    pub fn new(max_message_size: usize) -> Self {
+        Self {
+            max_message_size,
+            streams: HashMap::new(),
+            buffered: 0,
+        }
+    }
+
+    /// Adds a fragment to its stream and returns the message along with its serialized size
+    /// once the stream is complete. On error, the stream of the fragment is discarded.
+    pub fn push(&mut self, fragment: StreamFragment) -> Result<Option<(NetworkMessage, usize)>> {
+        let stream_id = fragment.stream_id;
+        let result = self.add_fragment(fragment);
+        if !matches!(result, Ok(None)) {
+            if let Some(stream) = self.streams.remove(&stream_id) {
+                self.buffered -= stream.data.len();
+            }
+        }
+        result
+    }
+
+    /// Total size of the partial messages being reassembled.
+    pub fn buffered(&self) -> usize {
+        self.buffered
+    }
+
+    fn add_fragment(
+        &mut self,
+        fragment: StreamFragment,
+    ) -> Result<Option<(NetworkMessage, usize)>> {
+        if fragment.fragment_id == 0 {
+            ensure!(
+                !self.streams.contains_key(&fragment.stream_id),
+                "Stream {} started twice",
+                fragment.stream_id
+            );
+            ensure!(
+                self.streams.len() < MAX_CONCURRENT_STREAMS,
+                "Too many concurrent streams"
+            );
+            ensure!(
+                (fragment.num_fragments as usize).saturating_mul(MAX_FRAGMENT_SIZE)
+                    < self.max_message_size + MAX_FRAGMENT_SIZE,
+                "Stream {} of {} fragments exceeds the max message size {}",
+                fragment.stream_id,
+                fragment.num_fragments,
+                self.max_message_size
+            );
+            self.streams.insert(
+                fragment.stream_id,
+                PartialMessage {
+                    num_fragments: fragment.num_fragments,
+                    next_fragment_id: 0,
+                    data: vec![],
+                },
+            );
+        }
+        let stream = self
+            .streams
+            .get_mut(&fragment.stream_id)
+            .ok_or_else(|| format_err!("Unknown stream {}", fragment.stream_id))?;
+        ensure!(
+            fragment.fragment_id == stream.next_fragment_id
+                && fragment.num_fragments == stream.num_fragments,
+            "Unexpected fragment {}/{} of stream {}",
+            fragment.fragment_id,
+            fragment.num_fragments,
+            fragment.stream_id
+        );
+        ensure!(
+            stream.data.len() + fragment.raw_data.len() <= self.max_message_size,
+            "Stream {} exceeds the max message size {}",
+            fragment.stream_id,
+            self.max_message_size
+        );
+        ensure!(
+            self.buffered + fragment.raw_data.len()
+                <= MAX_BUFFERED_MESSAGES.saturating_mul(self.max_message_size),
+            "Partial messages exceed {} times the max message size {}",
+            MAX_BUFFERED_MESSAGES,
+            self.max_message_size
+        );
+        self.buffered += fragment.raw_data.len();
+        stream.data.extend_from_slice(&fragment.raw_data);
+        stream.next_fragment_id += 1;
+        if stream.next_fragment_id < stream.num_fragments {
+            return Ok(None);
+        }
+        let size = stream.data.len();
+        Ok(Some((lcs::from_bytes(&stream.data)?, size)))
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use super::*;
+use crate::protocols::wire::{handshake::v1::ProtocolId, messaging::v1::DirectSendMsg};
+
+fn direct_send(size: usize) -> NetworkMessage {
+    NetworkMessage::DirectSendMsg(DirectSendMsg {
+        protocol_id: ProtocolId::StateSynchronizerDirectSend,
+        priority: 0,
+        raw_msg: (0..size).map(|i| i as u8).collect(),
+    })
+}
+
+fn decode(frames: Vec<Vec<u8>>) -> Vec<MultiplexMessage> {
+    frames
+        .iter()
+        .map(|frame| lcs::from_bytes(frame).unwrap())
+        .collect()
+}
+
+#[test]
+fn small_message_single_frame() {
+    let message = direct_send(100);
+    let frames = Fragmenter::default().frames(message.clone()).unwrap();
+    assert_eq!(decode(frames), vec![MultiplexMessage::Message(message)]);
+}
+
+#[test]
+fn large_message_round_trip() {
+    let mut fragmenter = Fragmenter::default();
+    let mut reassembler = Reassembler::new(1024 * 1024);
+    let message = direct_send(3 * MAX_FRAGMENT_SIZE);
+    let size = lcs::to_bytes(&message).unwrap().len();
+
+    // Two interleaved streams.
+    let first = decode(fragmenter.frames(message.clone()).unwrap());
+    let second = decode(fragmenter.frames(message.clone()).unwrap());
+    assert_eq!(first.len(), 4);
+    for (a, b) in first.into_iter().zip(second.into_iter()).take(3) {
+        for frame in vec![a, b] {
+            match frame {
+                MultiplexMessage::Fragment(fragment) => {
+                    assert_eq!(reassembler.push(fragment).unwrap(), None)
+                }
+                _ => panic!("Expected a fragment"),
+            }
+        }
+    }
+    let frames = decode(fragmenter.frames(message.clone()).unwrap());
+    let mut last = None;
+    for frame in frames {
+        if let MultiplexMessage::Fragment(fragment) = frame {
+            last = reassembler.push(fragment).unwrap();
+        }
+    }
+    assert_eq!(last, Some((message, size)));
+}
+
+#[test]
+fn invalid_streams() {
+    let fragment = |fragment_id, num_fragments| StreamFragment {
+        stream_id: 7,
+        fragment_id,
+        num_fragments,
+        raw_data: vec![0; MAX_FRAGMENT_SIZE],
+    };
+    let mut reassembler = Reassembler::new(4 * MAX_FRAGMENT_SIZE);
+
+    // Fragments must be received in order.
+    assert!(reassembler.push(fragment(1, 3)).is_err());
+    assert_eq!(reassembler.push(fragment(0, 3)).unwrap(), None);
+    assert!(reassembler.push(fragment(2, 3)).is_err());
+    // The stream was discarded.
+    assert!(reassembler.push(fragment(1, 3)).is_err());
+
+    // Streams can't exceed the max message size.
+    assert!(reassembler.push(fragment(0, 5)).is_err());
+
+    // The number of concurrent streams is bounded.
+    for stream_id in 0..MAX_CONCURRENT_STREAMS as u32 {
+        let mut fragment = fragment(0, 2);
+        fragment.stream_id = stream_id;
+        assert_eq!(reassembler.push(fragment).unwrap(), None);
+    }
+    let mut fragment = fragment(0, 2);
+    fragment.stream_id = 100;
+    assert!(reassembler.push(fragment).is_err());
+}
+
+#[test]
+fn reassembly_memory_is_bounded() {
+    let fragment = |stream_id, fragment_id| StreamFragment {
+        stream_id,
+        fragment_id,
+        num_fragments: 3,
+        raw_data: vec![0; MAX_FRAGMENT_SIZE],
+    };
+    // Each stream fits in the max message size, but they can't all be buffered at once.
+    let mut reassembler = Reassembler::new(3 * MAX_FRAGMENT_SIZE);
+    let budget = MAX_BUFFERED_MESSAGES * 3;
+    let num_streams = budget as u32 / 2;
+    for stream_id in 0..num_streams {
+        for fragment_id in 0..2 {
+            assert_eq!(
+                reassembler.push(fragment(stream_id, fragment_id)).unwrap(),
+                None
+            );
+        }
+    }
+    assert_eq!(
+        reassembler.buffered(),
+        2 * num_streams as usize * MAX_FRAGMENT_SIZE
+    );
+    assert_eq!(reassembler.push(fragment(num_streams, 0)).unwrap(), None);
+    assert!(reassembler.push(fragment(num_streams, 1)).is_err());
+    // The stream going over budget was discarded.
+    assert_eq!(
+        reassembler.buffered(),
+        2 * num_streams as usize * MAX_FRAGMENT_SIZE
+    );
+
+    // Completed streams release their buffers, whether they decode or not.
+    let _ = reassembler.push(fragment(0, 2));
+    assert_eq!(
+        reassembler.buffered(),
+        2 * (num_streams as usize - 1) * MAX_FRAGMENT_SIZE
+    );
+}
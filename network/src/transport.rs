@@ -2,8 +2,11 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{
+    counters,
     logging::network_events,
-    noise::{stream::NoiseStream, AntiReplayTimestamps, HandshakeAuthMode, NoiseUpgrader},
+    noise::{
+        stream::NoiseStream, AntiReplayTimestamps, HandshakeAuthMode, IdentityKeys, NoiseUpgrader,
+    },
     protocols::{
         identity::exchange_handshake,
         wire::handshake::v1::{HandshakeMsg, MessagingProtocolVersion, SupportedProtocols},
@@ -17,9 +20,11 @@ use futures::{
 use libra_config::{config::HANDSHAKE_VERSION, network_id::NetworkId};
 use libra_crypto::x25519;
 use libra_logger::prelude::*;
-use libra_network_address::{parse_dns_tcp, parse_ip_tcp, parse_memory, NetworkAddress};
+use libra_network_address::{
+    parse_dns_quic, parse_dns_tcp, parse_ip_quic, parse_ip_tcp, parse_memory, NetworkAddress,
+};
 use libra_types::{chain_id::ChainId, PeerId};
-use netcore::transport::{tcp, ConnectionOrigin, Transport};
+use netcore::transport::{quic, tcp, ConnectionOrigin, MessageStreams, Transport};
 use serde::Serialize;
 use std::{
     collections::{HashMap, HashSet},
@@ -38,9 +43,17 @@ use tokio::time::timeout;
 /// A timeout for the connection to open and complete all of the upgrade steps.
 pub const TRANSPORT_TIMEOUT: Duration = Duration::from_secs(30);
 
-/// Currently supported messaging protocol version.
-/// TODO: Add ability to support more than one messaging protocol.
-pub const SUPPORTED_MESSAGING_PROTOCOL: MessagingProtocolVersion = MessagingProtocolVersion::V1;
+/// Currently supported messaging protocol versions. The highest version supported by both ends of
+/// a connection is used.
+pub const SUPPORTED_MESSAGING_PROTOCOLS: &[MessagingProtocolVersion] = &[
+    MessagingProtocolVersion::V1,
+    MessagingProtocolVersion::V2,
+    MessagingProtocolVersion::V3,
+];
+
+/// Number of noise messages after which connections using messaging protocol v3 rekey their
+/// noise session in place, in each direction. Nodes which only support v2 or v1 don't rekey.
+pub const NOISE_REKEY_INTERVAL: u64 = 1 << 16;
 
 /// Global connection-id generator.
 static CONNECTION_ID_GENERATOR: ConnectionIdGenerator = ConnectionIdGenerator::new();
@@ -56,10 +69,23 @@ pub const LIBRA_TCP_TRANSPORT: tcp::TcpTransport = tcp::TcpTransport {
     nodelay: Some(true),
 };
 
+/// quic::Transport with Libra-specific configuration applied. The connections of the
+/// transport and of its clones share their UDP endpoints.
+pub fn libra_quic_transport() -> quic::QuicTransport {
+    // Keep idle connections alive, e.g. through NATs.
+    quic::QuicTransport::new(Some(Duration::from_secs(10)))
+}
+
 /// A trait alias for "socket-like" things.
-pub trait TSocket: AsyncRead + AsyncWrite + Send + Debug + Unpin + 'static {}
+pub trait TSocket:
+    AsyncRead + AsyncWrite + MessageStreams + Send + Debug + Unpin + 'static
+{
+}
 
-impl<T> TSocket for T where T: AsyncRead + AsyncWrite + Send + Debug + Unpin + 'static {}
+impl<T> TSocket for T where
+    T: AsyncRead + AsyncWrite + MessageStreams + Send + Debug + Unpin + 'static
+{
+}
 
 /// Unique local identifier for a connection.
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize)]
@@ -134,6 +160,14 @@ impl ConnectionMetadata {
     pub fn origin(&self) -> ConnectionOrigin {
         self.origin
     }
+
+    pub fn messaging_protocol(&self) -> MessagingProtocolVersion {
+        self.messaging_protocol
+    }
+
+    pub fn application_protocols(&self) -> &SupportedProtocols {
+        &self.application_protocols
+    }
 }
 
 /// The `Connection` struct consists of connection metadata and the actual socket for
@@ -218,20 +252,30 @@ async fn upgrade_inbound<T: TSocket>(
 ) -> io::Result<Connection<NoiseStream<T>>> {
     let origin = ConnectionOrigin::Inbound;
     let socket = fut_socket.await?;
+    let channel_binding = socket.channel_binding();
 
-    // try authenticating via noise handshake
-    let (socket, peer_id) = ctxt.noise.upgrade_inbound(socket).await.map_err(|err| {
-        // security logging
-        send_struct_log!(security_log(security_events::INVALID_NETWORK_PEER)
-            .data_display("error", &err)
-            .field(network_events::NETWORK_ADDRESS, &addr));
-        err
-    })?;
+    // try authenticating via noise handshake, which also confirms the channel binding of the
+    // base socket, if it has one.
+    let (mut socket, peer_id) = ctxt
+        .noise
+        .upgrade_inbound_with_channel_binding(socket, channel_binding.as_deref())
+        .await
+        .map_err(|err| {
+            // security logging
+            send_struct_log!(security_log(security_events::INVALID_NETWORK_PEER)
+                .data_display("error", &err)
+                .field(network_events::NETWORK_ADDRESS, &addr));
+            err
+        })?;
+    if channel_binding.is_some() {
+        socket.confirm_channel_binding();
+    }
     let remote_pubkey = socket.get_remote_static();
     let addr = addr.append_prod_protos(remote_pubkey, HANDSHAKE_VERSION);
 
     // try to negotiate common libranet version and supported application protocols
-    perform_handshake(peer_id, socket, addr, origin, &ctxt.own_handshake).await
+    let connection = perform_handshake(peer_id, socket, addr, origin, &ctxt.own_handshake).await?;
+    Ok(finish_upgrade(&ctxt, connection))
 }
 
 /// Upgrade an inbound connection. This means we run a Noise IK handshake for
@@ -245,25 +289,66 @@ async fn upgrade_outbound<T: TSocket>(
 ) -> io::Result<Connection<NoiseStream<T>>> {
     let origin = ConnectionOrigin::Outbound;
     let socket = fut_socket.await?;
+    let channel_binding = socket.channel_binding();
 
-    // noise handshake
-    let socket = ctxt
+    // noise handshake, which also confirms the channel binding of the base socket, if it has one.
+    let mut socket = ctxt
         .noise
-        .upgrade_outbound(socket, remote_pubkey, AntiReplayTimestamps::now)
+        .upgrade_outbound_with_channel_binding(
+            socket,
+            remote_pubkey,
+            AntiReplayTimestamps::now,
+            channel_binding.as_deref(),
+        )
         .await?;
+    if channel_binding.is_some() {
+        socket.confirm_channel_binding();
+    }
 
     // sanity check: Noise IK should always guarantee this is true
     debug_assert_eq!(remote_pubkey, socket.get_remote_static());
 
     // try to negotiate common libranet version and supported application protocols
-    perform_handshake(remote_peer_id, socket, addr, origin, &ctxt.own_handshake).await
+    let connection =
+        perform_handshake(remote_peer_id, socket, addr, origin, &ctxt.own_handshake).await?;
+    Ok(finish_upgrade(&ctxt, connection))
+}
+
+/// Enable rekeying if both ends support it, and count which of our static keys was used.
+fn finish_upgrade<T>(
+    ctxt: &UpgradeContext,
+    mut connection: Connection<NoiseStream<T>>,
+) -> Connection<NoiseStream<T>> {
+    // Both ends enable rekeying right after the messaging protocol is negotiated, before
+    // exchanging any other message, so they rekey at the same nonces. Released v2 nodes don't
+    // rekey, so only v3 signals support for it.
+    if connection.metadata.messaging_protocol() >= MessagingProtocolVersion::V3 {
+        connection.socket.enable_rekey(NOISE_REKEY_INTERVAL);
+    }
+
+    let direction = match connection.metadata.origin() {
+        ConnectionOrigin::Inbound => counters::INBOUND_LABEL,
+        ConnectionOrigin::Outbound => counters::OUTBOUND_LABEL,
+    };
+    let local_key =
+        if connection.socket.get_local_static() == ctxt.noise.identity_keys().public_key() {
+            counters::CURRENT_KEY_LABEL
+        } else {
+            counters::PREVIOUS_KEY_LABEL
+        };
+    counters::LIBRA_NETWORK_NOISE_HANDSHAKES
+        .with_label_values(&[direction, local_key])
+        .inc();
+    connection
 }
 
 /// The common LibraNet Transport.
 ///
 /// The base transport layer is pluggable, so long as it provides a reliable,
 /// ordered, connection-oriented, byte-stream abstraction (e.g., TCP). We currently
-/// use either `MemoryTransport` or `TcpTransport` as this base layer.
+/// use either `MemoryTransport`, `TcpTransport` or `QuicTransport` as this base layer.
+/// Base sockets with message streams, like QUIC's, have their channel binding confirmed
+/// over the Noise session, after which the `Peer` sends each message on its own stream.
 ///
 /// Inbound and outbound connections are first established with the `base_transport`
 /// and then negotiate a secure, authenticated transport layer (currently Noise
@@ -274,7 +359,6 @@ async fn upgrade_outbound<T: TSocket>(
 pub struct LibraNetTransport<TTransport> {
     base_transport: TTransport,
     ctxt: Arc<UpgradeContext>,
-    identity_pubkey: x25519::PublicKey,
 }
 
 impl<TTransport> LibraNetTransport<TTransport>
@@ -288,7 +372,7 @@ where
     pub fn new(
         base_transport: TTransport,
         self_peer_id: PeerId,
-        identity_key: x25519::PrivateKey,
+        identity_keys: Arc<IdentityKeys>,
         trusted_peers: Option<Arc<RwLock<HashMap<PeerId, HashSet<x25519::PublicKey>>>>>,
         handshake_version: u8,
         chain_id: ChainId,
@@ -296,8 +380,9 @@ where
         application_protocols: SupportedProtocols,
     ) -> Self {
         let mut own_handshake = HandshakeMsg::new(chain_id, network_id);
-        own_handshake.add(SUPPORTED_MESSAGING_PROTOCOL, application_protocols);
-        let identity_pubkey = identity_key.public_key();
+        for messaging_protocol in SUPPORTED_MESSAGING_PROTOCOLS {
+            own_handshake.add(*messaging_protocol, application_protocols.clone());
+        }
 
         let auth_mode = match trusted_peers.as_ref() {
             Some(trusted_peers) => HandshakeAuthMode::mutual(trusted_peers.clone()),
@@ -306,15 +391,20 @@ where
 
         Self {
             ctxt: Arc::new(UpgradeContext {
-                noise: NoiseUpgrader::new(self_peer_id, identity_key, auth_mode),
+                noise: NoiseUpgrader::with_identity_keys(self_peer_id, identity_keys, auth_mode),
                 handshake_version,
                 own_handshake,
             }),
             base_transport,
-            identity_pubkey,
         }
     }
 
+    /// The keys authenticating this node, which can be rotated while the transport is in use.
+    /// Addresses returned by `listen_on` afterwards advertise the new key.
+    pub fn identity_keys(&self) -> Arc<IdentityKeys> {
+        self.ctxt.noise.identity_keys()
+    }
+
     fn parse_dial_addr(
         addr: &NetworkAddress,
     ) -> io::Result<(NetworkAddress, x25519::PublicKey, u8)> {
@@ -329,13 +419,15 @@ where
         let (base_transport_protos, base_transport_suffix) = parse_ip_tcp(protos)
             .map(|x| (&protos[..2], x.1))
             .or_else(|| parse_dns_tcp(protos).map(|x| (&protos[..2], x.1)))
+            .or_else(|| parse_ip_quic(protos).map(|x| (&protos[..2], x.1)))
+            .or_else(|| parse_dns_quic(protos).map(|x| (&protos[..2], x.1)))
             .or_else(|| parse_memory(protos).map(|x| (&protos[..1], x.1)))
             .ok_or_else(|| {
                 io::Error::new(
                     io::ErrorKind::InvalidInput,
                     format!(
                         "Unexpected dialing network address: '{}', expected: \
-                         memory, ip+tcp, or dns+tcp",
+                         memory, ip+tcp, dns+tcp, ip+quic, or dns+quic",
                         addr
                     ),
                 )
@@ -381,6 +473,9 @@ where
     /// `/dns/<ipaddr>/tcp/<port>` or
     /// `/dns4/<ipaddr>/tcp/<port>` or
     /// `/dns6/<ipaddr>/tcp/<port>`
+    ///
+    /// If the base transport is `QuicTransport`, then `/<base_transport>` is any of the
+    /// above with `/quic/<port>` instead of `/tcp/<port>`.
     pub fn dial(
         &self,
         peer_id: PeerId,
@@ -427,6 +522,11 @@ where
     ///
     /// `/ip4/<ipaddr>/tcp/<port>` or
     /// `/ip6/<ipaddr>/tcp/<port>`
+    ///
+    /// If the base transport is `QuicTransport`, then we expect:
+    ///
+    /// `/ip4/<ipaddr>/quic/<port>` or
+    /// `/ip6/<ipaddr>/quic/<port>`
     pub fn listen_on(
         &self,
         addr: NetworkAddress,
@@ -449,8 +549,10 @@ where
         // (e.g., `/memory/<port>` with no trailers), so we don't need to do any
         // parsing here.
         let (listener, listen_addr) = self.base_transport.listen_on(addr)?;
-        let listen_addr =
-            listen_addr.append_prod_protos(self.identity_pubkey, self.ctxt.handshake_version);
+        let listen_addr = listen_addr.append_prod_protos(
+            self.ctxt.noise.identity_keys().public_key(),
+            self.ctxt.handshake_version,
+        );
 
         // need to move a ctxt into stream task
         let ctxt = self.ctxt.clone();
@@ -593,7 +695,7 @@ mod test {
         let listener_transport = LibraNetTransport::new(
             base_transport.clone(),
             listener_peer_id,
-            listener_key,
+            Arc::new(IdentityKeys::new(listener_key)),
             trusted_peers.clone(),
             HANDSHAKE_VERSION,
             chain_id,
@@ -604,7 +706,7 @@ mod test {
         let dialer_transport = LibraNetTransport::new(
             base_transport,
             dialer_peer_id,
-            dialer_key,
+            Arc::new(IdentityKeys::new(dialer_key)),
             trusted_peers.clone(),
             HANDSHAKE_VERSION,
             chain_id,
@@ -650,6 +752,16 @@ mod test {
         );
     }
 
+    /// Check that the network address matches the format
+    /// `"/ip4/<ipaddr>/quic/<port>/ln-noise-ik/<pubkey>/ln-handshake/<version>"`
+    fn expect_ip4_quic_noise_addr(addr: &NetworkAddress) {
+        assert!(
+            matches!(addr.as_slice(), [Ip4(_), Quic(_), NoiseIK(_), Handshake(_)]),
+            "addr: '{}'",
+            addr
+        );
+    }
+
     fn test_transport_success<TTransport>(
         base_transport: TTransport,
         auth: Auth,
@@ -692,7 +804,7 @@ mod test {
             assert_eq!(conn.metadata.origin, ConnectionOrigin::Inbound);
             assert_eq!(
                 conn.metadata.messaging_protocol,
-                MessagingProtocolVersion::V1
+                MessagingProtocolVersion::V3
             );
             assert_eq!(
                 conn.metadata.application_protocols,
@@ -702,6 +814,11 @@ mod test {
             // test the socket works
             let msg = write_read_msg(&mut conn.socket, b"foobar").await;
             assert_eq!(&msg, b"barbaz".as_ref());
+            // the channel binding of sockets with message streams was confirmed
+            assert_eq!(
+                conn.socket.channel_binding().is_some(),
+                conn.socket.take_message_streams(1024).is_some()
+            );
             conn.socket.close().await.unwrap();
         };
 
@@ -721,7 +838,7 @@ mod test {
             assert_eq!(conn.metadata.origin, ConnectionOrigin::Outbound);
             assert_eq!(
                 conn.metadata.messaging_protocol,
-                MessagingProtocolVersion::V1
+                MessagingProtocolVersion::V3
             );
             assert_eq!(conn.metadata.application_protocols, supported_protocols);
 
@@ -860,6 +977,39 @@ mod test {
         );
     }
 
+    //////////////////////////////////////
+    // LibraNetTransport<QuicTransport> //
+    //////////////////////////////////////
+
+    #[test]
+    fn test_quic_transport_mutual_auth() {
+        test_transport_success(
+            libra_quic_transport(),
+            Auth::Mutual,
+            "/ip4/127.0.0.1/quic/0",
+            expect_ip4_quic_noise_addr,
+        );
+    }
+
+    #[test]
+    fn test_quic_transport_server_only_auth() {
+        test_transport_success(
+            libra_quic_transport(),
+            Auth::ServerOnly,
+            "/ip4/127.0.0.1/quic/0",
+            expect_ip4_quic_noise_addr,
+        );
+    }
+
+    #[test]
+    fn test_quic_transport_rejects_unauthed_dialer() {
+        test_transport_rejects_unauthed_dialer(
+            libra_quic_transport(),
+            "/ip4/127.0.0.1/quic/0",
+            expect_ip4_quic_noise_addr,
+        );
+    }
+
     ///////////////////////
     // perform_handshake //
     ///////////////////////
@@ -29,7 +29,7 @@ use libra_types::{
     transaction::{Transaction, TransactionListWithProof, Version},
     waypoint::Waypoint,
 };
-use network::protocols::network::Event;
+use network::protocols::network::{Event, PeerBehavior};
 use std::{
     collections::{BTreeMap, HashMap},
     ops::Bound::Included,
@@ -185,6 +185,14 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         executor_proxy: T,
         initial_state: SynchronizerState,
     ) -> Self {
+        let peer_latencies = network_senders
+            .iter()
+            .map(|(network_id, sender)| (network_id.clone(), sender.peer_latencies().clone()))
+            .collect();
+        let peer_scores = network_senders
+            .iter()
+            .map(|(network_id, sender)| (network_id.clone(), sender.peer_scores().clone()))
+            .collect();
         let retry_timeout_val = match role {
             RoleType::FullNode => config.tick_interval_ms + config.long_poll_timeout_ms,
             RoleType::Validator => 2 * config.tick_interval_ms,
@@ -200,7 +208,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             role,
             waypoint,
             network_senders,
-            peer_manager: PeerManager::new(upstream_config),
+            peer_manager: PeerManager::new(upstream_config, peer_latencies, peer_scores),
             subscriptions: HashMap::new(),
             sync_request: None,
             initialization_listener: None,
@@ -298,8 +306,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                         .with_label_values(&[&*peer.peer_id().to_string()])
                         .inc();
                 } else {
-                    self.peer_manager
-                        .update_score(&peer, PeerScoreUpdateType::Success);
+                    self.update_peer_score(&peer, PeerScoreUpdateType::Success);
                     // TODO update dashboards to ID peers using PeerNetworkID, not just peer ID
                     counters::APPLY_CHUNK_SUCCESS
                         .with_label_values(&[&*peer.peer_id().to_string()])
@@ -693,6 +700,23 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         Ok(target_li)
     }
 
+    /// Updates the score of the peer used for chunk requests, and reports the behavior of the
+    /// peer to the network layer.
+    fn update_peer_score(&mut self, peer: &PeerNetworkId, update_type: PeerScoreUpdateType) {
+        if let Some(behavior) = update_type.peer_behavior() {
+            self.report_peer(peer, behavior);
+        }
+        self.peer_manager.update_score(peer, update_type);
+    }
+
+    fn report_peer(&mut self, peer: &PeerNetworkId, behavior: PeerBehavior) {
+        if let Some(network_sender) = self.network_senders.get_mut(&peer.network_id()) {
+            if let Err(err) = network_sender.report_peer(peer.peer_id(), behavior) {
+                warn!("[state sync] failed to report peer {:?}: {}", peer, err);
+            }
+        }
+    }
+
     /// * Issue a request for the next chunk.
     /// * Validate and execute the transactions.
     /// * Notify the clients in case a sync request has been completed.
@@ -711,15 +735,13 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             txn_list_with_proof
                 .first_transaction_version
                 .ok_or_else(|| {
-                    self.peer_manager
-                        .update_score(&peer, PeerScoreUpdateType::EmptyChunk);
+                    self.update_peer_score(&peer, PeerScoreUpdateType::EmptyChunk);
                     format_err!("[state sync] Empty chunk from {:?}", peer)
                 })?;
 
         if chunk_start_version != known_version + 1 {
             // Old / wrong chunk.
-            self.peer_manager
-                .update_score(&peer, PeerScoreUpdateType::ChunkVersionCannotBeApplied);
+            self.update_peer_score(&peer, PeerScoreUpdateType::ChunkVersionCannotBeApplied);
             bail!(
                 "[state sync] Non sequential chunk from {:?}: known_version: {}, received: {}",
                 peer,
@@ -732,7 +754,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
         let new_version = known_version + chunk_size;
         match response.response_li {
             ResponseLedgerInfo::VerifiableLedgerInfo(li) => {
-                self.process_response_with_verifiable_li(txn_list_with_proof, li)
+                self.process_response_with_verifiable_li(peer, txn_list_with_proof, li)
             }
             ResponseLedgerInfo::ProgressiveLedgerInfo {
                 target_li,
@@ -745,20 +767,20 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
                     highest_li
                 );
                 self.pending_ledger_infos.add_li(highest_li);
-                self.process_response_with_verifiable_li(txn_list_with_proof, target_li)
+                self.process_response_with_verifiable_li(peer, txn_list_with_proof, target_li)
             }
             ResponseLedgerInfo::LedgerInfoForWaypoint {
                 waypoint_li,
                 end_of_epoch_li,
             } => self.process_response_with_waypoint_li(
+                peer,
                 txn_list_with_proof,
                 waypoint_li,
                 end_of_epoch_li,
             ),
         }
         .map_err(|e| {
-            self.peer_manager
-                .update_score(peer, PeerScoreUpdateType::InvalidChunk);
+            self.update_peer_score(peer, PeerScoreUpdateType::InvalidChunk);
             format_err!("[state sync] failed to apply chunk: {}", e)
         })?;
 
@@ -785,6 +807,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
     /// current local trusted validator set.
     fn process_response_with_verifiable_li(
         &mut self,
+        peer: &PeerNetworkId,
         txn_list_with_proof: TransactionListWithProof,
         response_li: LedgerInfoWithSignatures,
     ) -> Result<()> {
@@ -816,7 +839,13 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             // Remain in the current epoch
             self.local_state.epoch()
         };
-        self.local_state.trusted_epoch.verify(&response_li)?;
+        if let Err(e) = self.local_state.trusted_epoch.verify(&response_li) {
+            // A ledger info of another epoch might answer a request sent before an epoch change.
+            if response_li.ledger_info().epoch() == self.local_state.epoch() {
+                self.report_peer(peer, PeerBehavior::Invalid);
+            }
+            return Err(e);
+        }
         self.validate_and_store_chunk(txn_list_with_proof, response_li, None)?;
 
         // need to sync with local storage to see whether response LI was actually committed
@@ -829,6 +858,7 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
     /// Processing chunk responses that carry a LedgerInfo corresponding to the waypoint.
     fn process_response_with_waypoint_li(
         &mut self,
+        peer: &PeerNetworkId,
         txn_list_with_proof: TransactionListWithProof,
         waypoint_li: LedgerInfoWithSignatures,
         end_of_epoch_li: Option<LedgerInfoWithSignatures>,
@@ -855,7 +885,10 @@ impl<T: ExecutorProxyTrait> SyncCoordinator<T> {
             self.send_chunk_request(new_version, new_epoch)?;
         }
 
-        self.waypoint.verify(waypoint_li.ledger_info())?;
+        if let Err(e) = self.waypoint.verify(waypoint_li.ledger_info()) {
+            self.report_peer(peer, PeerBehavior::Invalid);
+            return Err(e);
+        }
         self.validate_and_store_chunk(txn_list_with_proof, waypoint_li, end_of_epoch_li)
     }
 
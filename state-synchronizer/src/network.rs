@@ -10,7 +10,9 @@ use libra_types::PeerId;
 use network::{
     error::NetworkError,
     peer_manager::{ConnectionRequestSender, PeerManagerRequestSender},
-    protocols::network::{NetworkEvents, NetworkSender, NewNetworkSender},
+    protocols::network::{
+        NetworkEvents, NetworkSender, NewNetworkSender, PeerBehavior, PeerLatencies, PeerScores,
+    },
     ProtocolId,
 };
 use serde::{Deserialize, Serialize};
@@ -81,4 +83,23 @@ impl StateSynchronizerSender {
         let protocol = ProtocolId::StateSynchronizerDirectSend;
         self.inner.send_to(recipient, protocol, message)
     }
+
+    /// Report the outcome of processing a message from `peer` to the network layer.
+    pub fn report_peer(
+        &mut self,
+        peer: PeerId,
+        behavior: PeerBehavior,
+    ) -> Result<(), NetworkError> {
+        self.inner.report_peer(peer, behavior)
+    }
+
+    /// Latencies of the peers of the network, to favor the faster upstream peers.
+    pub fn peer_latencies(&self) -> &PeerLatencies {
+        self.inner.peer_latencies()
+    }
+
+    /// Scores of the peers of the network, to avoid the upstream peers reported to misbehave.
+    pub fn peer_scores(&self) -> &PeerScores {
+        self.inner.peer_scores()
+    }
 }
@@ -3,15 +3,19 @@
 
 use crate::counters;
 use itertools::Itertools;
-use libra_config::config::{PeerNetworkId, UpstreamConfig};
+use libra_config::{
+    config::{PeerNetworkId, UpstreamConfig},
+    network_id::NetworkId,
+};
 use libra_logger::prelude::*;
+use network::protocols::network::{PeerBehavior, PeerLatencies, PeerScores};
 use rand::{
     distributions::{Distribution, WeightedIndex},
     thread_rng,
 };
 use std::{
     collections::{BTreeMap, HashMap},
-    time::SystemTime,
+    time::{Duration, SystemTime},
 };
 
 const MAX_SCORE: f64 = 100.0;
@@ -61,6 +65,22 @@ pub enum PeerScoreUpdateType {
     TimeOut,
 }
 
+impl PeerScoreUpdateType {
+    /// The behavior reported to the network layer, which bans peers misbehaving too often.
+    /// Timeouts and chunks arriving late are not reported, as they might be caused by this node.
+    /// Neither are chunks failing to apply, as the executor or the storage of this node might
+    /// have failed: the ledger infos failing verification are reported separately.
+    pub fn peer_behavior(&self) -> Option<PeerBehavior> {
+        match self {
+            PeerScoreUpdateType::Success => Some(PeerBehavior::Good),
+            PeerScoreUpdateType::EmptyChunk => Some(PeerBehavior::Unresponsive),
+            PeerScoreUpdateType::InvalidChunk
+            | PeerScoreUpdateType::ChunkVersionCannotBeApplied
+            | PeerScoreUpdateType::TimeOut => None,
+        }
+    }
+}
+
 pub struct PeerManager {
     // list of peers that are eligible for this node to send sync requests to
     eligible_peers: Vec<PeerNetworkId>,
@@ -68,16 +88,26 @@ pub struct PeerManager {
     requests: BTreeMap<u64, ChunkRequestInfo>,
     upstream_config: UpstreamConfig,
     weighted_index: Option<WeightedIndex<f64>>,
+    // latencies of the peers of each network, measured by the network health checker
+    peer_latencies: HashMap<NetworkId, PeerLatencies>,
+    // scores of the peers of each network, from the behaviors reported by all the applications
+    peer_scores: HashMap<NetworkId, PeerScores>,
 }
 
 impl PeerManager {
-    pub fn new(upstream_config: UpstreamConfig) -> Self {
+    pub fn new(
+        upstream_config: UpstreamConfig,
+        peer_latencies: HashMap<NetworkId, PeerLatencies>,
+        peer_scores: HashMap<NetworkId, PeerScores>,
+    ) -> Self {
         Self {
             eligible_peers: vec![],
             peers: HashMap::new(),
             requests: BTreeMap::new(),
             upstream_config,
             weighted_index: None,
+            peer_latencies,
+            peer_scores,
         }
     }
 
@@ -159,14 +189,39 @@ impl PeerManager {
             .ok();
     }
 
+    // Samples two peers weighted by their score and picks the one not suspected by the network
+    // layer, then the one with the lowest latency, so that well-behaved and faster peers are
+    // favored while the others still get requests
     pub fn pick_peer(&self) -> Option<PeerNetworkId> {
-        if let Some(weighted_index) = &self.weighted_index {
-            let mut rng = thread_rng();
-            if let Some(peer) = self.eligible_peers.get(weighted_index.sample(&mut rng)) {
-                return Some(peer.clone());
+        let weighted_index = self.weighted_index.as_ref()?;
+        let mut rng = thread_rng();
+        let first = self.eligible_peers.get(weighted_index.sample(&mut rng))?;
+        let second = self.eligible_peers.get(weighted_index.sample(&mut rng))?;
+        match (self.is_suspect(first), self.is_suspect(second)) {
+            (true, false) => return Some(second.clone()),
+            (false, true) => return Some(first.clone()),
+            _ => (),
+        }
+        match (self.peer_latency(first), self.peer_latency(second)) {
+            (Some(first_latency), Some(second_latency)) if second_latency < first_latency => {
+                Some(second.clone())
             }
+            _ => Some(first.clone()),
         }
-        None
+    }
+
+    // whether the applications of the network reported the peer to misbehave more than to behave
+    fn is_suspect(&self, peer: &PeerNetworkId) -> bool {
+        self.peer_scores
+            .get(&peer.network_id())
+            .map_or(false, |scores| scores.is_suspect(&peer.peer_id()))
+    }
+
+    fn peer_latency(&self, peer: &PeerNetworkId) -> Option<Duration> {
+        self.peer_latencies
+            .get(&peer.network_id())?
+            .get(&peer.peer_id())
+            .map(|stats| stats.mean)
     }
 
     fn get_active_upstream_peers(&self) -> Vec<(&PeerNetworkId, &PeerInfo)> {
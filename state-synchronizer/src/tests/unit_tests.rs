@@ -2,8 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::peer_manager::{PeerManager, PeerScoreUpdateType};
-use libra_config::config::{PeerNetworkId, UpstreamConfig};
-use std::collections::HashMap;
+use libra_config::{
+    config::{PeerNetworkId, UpstreamConfig},
+    network_id::NetworkId,
+};
+use network::protocols::network::{PeerBehavior, PeerLatencies, PeerScores};
+use std::{collections::HashMap, iter, time::Duration};
 
 #[test]
 fn test_peer_manager() {
@@ -13,7 +17,8 @@ fn test_peer_manager() {
         PeerNetworkId::random_validator(),
         PeerNetworkId::random_validator(),
     ];
-    let mut peer_manager = PeerManager::new(UpstreamConfig::default());
+    let mut peer_manager =
+        PeerManager::new(UpstreamConfig::default(), HashMap::new(), HashMap::new());
     for peer_id in peers.clone() {
         peer_manager.enable_peer(peer_id);
     }
@@ -36,13 +41,73 @@ fn test_peer_manager() {
     assert!(pick_counts.get(&peers[0]).unwrap_or(&0) < pick_counts.get(&peers[3]).unwrap());
 }
 
+#[test]
+fn test_peer_manager_latency() {
+    let peers = vec![
+        PeerNetworkId::random_validator(),
+        PeerNetworkId::random_validator(),
+    ];
+    let latencies = PeerLatencies::default();
+    latencies.record(peers[0].peer_id(), Duration::from_millis(200));
+    latencies.record(peers[1].peer_id(), Duration::from_millis(20));
+    let mut peer_manager = PeerManager::new(
+        UpstreamConfig::default(),
+        iter::once((NetworkId::Validator, latencies)).collect(),
+        HashMap::new(),
+    );
+    for peer_id in peers.clone() {
+        peer_manager.enable_peer(peer_id);
+    }
+
+    let mut pick_counts = HashMap::new();
+    for _ in 0..1000 {
+        let picked_peer_id = peer_manager.pick_peer().unwrap();
+        *pick_counts.entry(picked_peer_id).or_insert(0) += 1;
+    }
+
+    // With equal scores, the faster peer is picked unless both samples are the slower peer.
+    assert!(pick_counts.get(&peers[0]).unwrap_or(&0) < pick_counts.get(&peers[1]).unwrap());
+}
+
+#[test]
+fn test_peer_manager_network_scores() {
+    let peers = vec![
+        PeerNetworkId::random_validator(),
+        PeerNetworkId::random_validator(),
+    ];
+    // the suspect peer is faster, but reported to misbehave by the applications of the network
+    let latencies = PeerLatencies::default();
+    latencies.record(peers[0].peer_id(), Duration::from_millis(20));
+    latencies.record(peers[1].peer_id(), Duration::from_millis(200));
+    let scores = PeerScores::default();
+    scores.record(peers[0].peer_id(), PeerBehavior::Invalid);
+    let mut peer_manager = PeerManager::new(
+        UpstreamConfig::default(),
+        iter::once((NetworkId::Validator, latencies)).collect(),
+        iter::once((NetworkId::Validator, scores)).collect(),
+    );
+    for peer_id in peers.clone() {
+        peer_manager.enable_peer(peer_id);
+    }
+
+    let mut pick_counts = HashMap::new();
+    for _ in 0..1000 {
+        let picked_peer_id = peer_manager.pick_peer().unwrap();
+        *pick_counts.entry(picked_peer_id).or_insert(0) += 1;
+    }
+
+    // The suspect peer is picked only when both samples are the suspect peer.
+    assert!(pick_counts.get(&peers[0]).unwrap_or(&0) < pick_counts.get(&peers[1]).unwrap());
+}
+
 #[test]
 fn test_remove_requests() {
     let peers = vec![
         PeerNetworkId::random_validator(),
         PeerNetworkId::random_validator(),
     ];
-    let mut peer_manager = PeerManager::new(UpstreamConfig::default());
+    let mut peer_manager =
+        PeerManager::new(UpstreamConfig::default(), HashMap::new(), HashMap::new());
     for peer in peers.iter() {
         peer_manager.enable_peer(peer.clone());
     }
@@ -68,7 +133,8 @@ fn test_peer_manager_request_metadata() {
         PeerNetworkId::random_validator(),
         PeerNetworkId::random_validator(),
     ];
-    let mut peer_manager = PeerManager::new(UpstreamConfig::default());
+    let mut peer_manager =
+        PeerManager::new(UpstreamConfig::default(), HashMap::new(), HashMap::new());
     for peer in peers.iter() {
         peer_manager.enable_peer(peer.clone());
     }
@@ -56,7 +56,10 @@ fn to_blocks_to_commit(
             }
 
             let partial_ledger_info = partial_ledger_info_with_sigs.ledger_info();
-            let signatures = partial_ledger_info_with_sigs.signatures().clone();
+            let signatures = partial_ledger_info_with_sigs
+                .signatures()
+                .cloned()
+                .unwrap_or_default();
             assert_eq!(cur_ver, partial_ledger_info.version() + 1);
 
             let block_info = BlockInfo::new(
@@ -41,6 +41,7 @@ pub fn get_registry() -> Result<Registry> {
 
     // 2. Trace the main entry point(s) + every enum separately.
     tracer.trace_type::<messaging::v1::NetworkMessage>(&samples)?;
+    tracer.trace_type::<messaging::v2::MultiplexMessage>(&samples)?;
     tracer.trace_type::<handshake::v1::HandshakeMsg>(&samples)?;
     tracer.trace_type::<address::NetworkAddress>(&samples)?;
     tracer.trace_type::<address::RawNetworkAddress>(&samples)?;
@@ -49,6 +50,7 @@ pub fn get_registry() -> Result<Registry> {
 
     tracer.trace_type::<messaging::v1::ErrorCode>(&samples)?;
     tracer.trace_type::<handshake::v1::ProtocolId>(&samples)?;
+    tracer.trace_type::<handshake::v1::MessagingProtocolVersion>(&samples)?;
     tracer.trace_type::<address::Protocol>(&samples)?;
     tracer.trace_type::<libra_config::network_id::NetworkId>(&samples)?;
 
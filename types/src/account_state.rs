@@ -1,6 +1,8 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(feature = "bls")]
+use crate::on_chain_config::ConsensusSignatureScheme;
 use crate::{
     account_address::AccountAddress,
     account_config::{
@@ -93,6 +95,14 @@ impl AccountState {
         self.get_resource(&ValidatorSet::CONFIG_ID.access_path().path)
     }
 
+    #[cfg(feature = "bls")]
+    pub fn get_consensus_signature_scheme(&self) -> Result<Option<ConsensusSignatureScheme>> {
+        self.0
+            .get(&ConsensusSignatureScheme::CONFIG_ID.access_path().path)
+            .map(|bytes| ConsensusSignatureScheme::deserialize_into_config(bytes))
+            .transpose()
+    }
+
     pub fn get_libra_block_resource(&self) -> Result<Option<LibraBlockResource>> {
         self.get_resource(&LibraBlockResource::resource_path())
     }
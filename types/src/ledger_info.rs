@@ -1,6 +1,8 @@
 // Copyright (c) The Libra Core Contributors
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(feature = "bls")]
+use crate::multi_signature::{MultiSignature, MultiSignatureVerifier};
 use crate::{
     account_address::AccountAddress,
     block_info::{BlockInfo, Round},
@@ -17,7 +19,6 @@ use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeMap,
     fmt::{Display, Formatter},
-    ops::{Deref, DerefMut},
 };
 
 /// This structure serves a dual purpose.
@@ -133,12 +134,18 @@ impl LedgerInfo {
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum LedgerInfoWithSignatures {
     V0(LedgerInfoWithV0),
+    /// Signed by a single BLS12-381 multi-signature, see `ConsensusSignatureScheme`. All the
+    /// nodes of a network must agree on the `bls` feature, which changes the persisted format.
+    #[cfg(feature = "bls")]
+    V1(LedgerInfoWithMultiSignature),
 }
 
 impl Display for LedgerInfoWithSignatures {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         match self {
             LedgerInfoWithSignatures::V0(ledger) => write!(f, "{}", ledger),
+            #[cfg(feature = "bls")]
+            LedgerInfoWithSignatures::V1(ledger) => write!(f, "{}", ledger),
         }
     }
 }
@@ -152,30 +159,69 @@ impl LedgerInfoWithSignatures {
         LedgerInfoWithSignatures::V0(LedgerInfoWithV0::new(ledger_info, signatures))
     }
 
+    #[cfg(feature = "bls")]
+    pub fn new_with_multi_signature(
+        ledger_info: LedgerInfo,
+        multi_signature: MultiSignature,
+    ) -> Self {
+        LedgerInfoWithSignatures::V1(LedgerInfoWithMultiSignature::new(
+            ledger_info,
+            multi_signature,
+        ))
+    }
+
     pub fn genesis(genesis_state_root_hash: HashValue, validator_set: ValidatorSet) -> Self {
         LedgerInfoWithSignatures::V0(LedgerInfoWithV0::genesis(
             genesis_state_root_hash,
             validator_set,
         ))
     }
-}
 
-// Temporary hack to avoid massive changes, it won't work when new variant comes and needs proper
-// dispatch at that time.
-impl Deref for LedgerInfoWithSignatures {
-    type Target = LedgerInfoWithV0;
+    pub fn ledger_info(&self) -> &LedgerInfo {
+        match self {
+            LedgerInfoWithSignatures::V0(ledger) => ledger.ledger_info(),
+            #[cfg(feature = "bls")]
+            LedgerInfoWithSignatures::V1(ledger) => ledger.ledger_info(),
+        }
+    }
+
+    /// Returns the individual signatures, `None` if the ledger info is multi-signed.
+    pub fn signatures(&self) -> Option<&BTreeMap<AccountAddress, Ed25519Signature>> {
+        match self {
+            LedgerInfoWithSignatures::V0(ledger) => Some(ledger.signatures()),
+            #[cfg(feature = "bls")]
+            LedgerInfoWithSignatures::V1(_) => None,
+        }
+    }
 
-    fn deref(&self) -> &LedgerInfoWithV0 {
-        match &self {
-            LedgerInfoWithSignatures::V0(ledger) => ledger,
+    /// Returns the validators that signed the ledger info, the signers of a multi-signature are
+    /// looked up in the given validator set.
+    #[cfg_attr(not(feature = "bls"), allow(unused_variables))]
+    pub fn signers(&self, validator: &ValidatorVerifier) -> Vec<AccountAddress> {
+        match self {
+            LedgerInfoWithSignatures::V0(ledger) => ledger.signatures().keys().copied().collect(),
+            #[cfg(feature = "bls")]
+            LedgerInfoWithSignatures::V1(ledger) => validator
+                .multi_signature_verifier()
+                .map(|verifier| verifier.signers(ledger.multi_signature()))
+                .unwrap_or_default(),
         }
     }
-}
 
-impl DerefMut for LedgerInfoWithSignatures {
-    fn deref_mut(&mut self) -> &mut LedgerInfoWithV0 {
+    /// Verifies the signatures against the given validator set. A multi-signed ledger info is
+    /// only valid if the validator set has multi-signatures enabled.
+    pub fn verify_signatures(
+        &self,
+        validator: &ValidatorVerifier,
+    ) -> ::std::result::Result<(), VerifyError> {
         match self {
-            LedgerInfoWithSignatures::V0(ref mut ledger) => ledger,
+            LedgerInfoWithSignatures::V0(ledger) => ledger.verify_signatures(validator),
+            #[cfg(feature = "bls")]
+            LedgerInfoWithSignatures::V1(ledger) => ledger.verify_signatures(
+                validator
+                    .multi_signature_verifier()
+                    .ok_or(VerifyError::UnexpectedMultiSignature)?,
+            ),
         }
     }
 }
@@ -248,6 +294,48 @@ impl LedgerInfoWithV0 {
     }
 }
 
+/// A `LedgerInfo` certified by a single multi-signature, which size doesn't grow with the number
+/// of validators. This is the compact counterpart of `LedgerInfoWithSignatures`, used when the
+/// on-chain `ConsensusSignatureScheme` enables BLS12-381 multi-signatures.
+#[cfg(feature = "bls")]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LedgerInfoWithMultiSignature {
+    ledger_info: LedgerInfo,
+    multi_signature: MultiSignature,
+}
+
+#[cfg(feature = "bls")]
+impl Display for LedgerInfoWithMultiSignature {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.ledger_info)
+    }
+}
+
+#[cfg(feature = "bls")]
+impl LedgerInfoWithMultiSignature {
+    pub fn new(ledger_info: LedgerInfo, multi_signature: MultiSignature) -> Self {
+        Self {
+            ledger_info,
+            multi_signature,
+        }
+    }
+
+    pub fn ledger_info(&self) -> &LedgerInfo {
+        &self.ledger_info
+    }
+
+    pub fn multi_signature(&self) -> &MultiSignature {
+        &self.multi_signature
+    }
+
+    pub fn verify_signatures(
+        &self,
+        verifier: &MultiSignatureVerifier,
+    ) -> ::std::result::Result<(), VerifyError> {
+        verifier.verify(self.ledger_info(), self.multi_signature())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -19,6 +19,8 @@ pub mod ledger_info;
 pub mod libra_timestamp;
 pub mod mempool_status;
 pub mod move_resource;
+#[cfg(feature = "bls")]
+pub mod multi_signature;
 pub mod on_chain_config;
 pub mod proof;
 #[cfg(any(test, feature = "fuzzing"))]
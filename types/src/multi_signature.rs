@@ -0,0 +1,286 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Compact representation of the signatures of a quorum: a single aggregated BLS12-381
+//! signature along with a bitmap of the signers, indexed by the order of the validators'
+//! account addresses.
+
+use crate::{
+    account_address::AccountAddress,
+    on_chain_config::BlsValidatorKey,
+    validator_verifier::{ValidatorVerifier, VerifyError},
+};
+use anyhow::{ensure, format_err, Result};
+use bitvec::BitVec;
+use libra_crypto::{
+    bls12381::{Bls12381PublicKey, Bls12381Signature},
+    hash::CryptoHash,
+    Signature,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The aggregated signature of a set of validators, the size of which doesn't depend on the
+/// number of signers.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MultiSignature {
+    /// The validators which signed, bit `i` stands for the `i`-th validator by account address.
+    signers: BitVec,
+    signature: Bls12381Signature,
+}
+
+impl MultiSignature {
+    pub fn signers(&self) -> &BitVec {
+        &self.signers
+    }
+
+    pub fn signature(&self) -> &Bls12381Signature {
+        &self.signature
+    }
+}
+
+/// Counterpart of the `ValidatorVerifier` for multi-signatures, holding the BLS12-381 keys of
+/// the validators in account address order.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MultiSignatureVerifier {
+    validators: Vec<(AccountAddress, Bls12381PublicKey, u64)>,
+    quorum_voting_power: u64,
+}
+
+impl MultiSignatureVerifier {
+    /// Builds the verifier of the validator set of `verifier`, every validator must have a key
+    /// with a valid proof of possession in `keys`.
+    pub fn new(verifier: &ValidatorVerifier, keys: &[BlsValidatorKey]) -> Result<Self> {
+        ensure!(
+            verifier.len() <= usize::from(u8::max_value()) + 1,
+            "Multi-signatures support at most 256 validators, got {}",
+            verifier.len()
+        );
+        let keys: BTreeMap<_, _> = keys.iter().map(|key| (key.account_address, key)).collect();
+        let validators = verifier
+            .get_ordered_account_addresses_iter()
+            .map(|author| {
+                let key = keys
+                    .get(&author)
+                    .ok_or_else(|| format_err!("No BLS key for validator {}", author))?;
+                key.proof_of_possession
+                    .verify(&key.public_key)
+                    .map_err(|e| format_err!("Invalid proof of possession of {}: {}", author, e))?;
+                let voting_power = verifier
+                    .get_voting_power(&author)
+                    .expect("Validator addresses come from the verifier");
+                Ok((author, key.public_key.clone(), voting_power))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            validators,
+            quorum_voting_power: verifier.quorum_voting_power(),
+        })
+    }
+
+    /// Aggregates the signatures of the given validators into a multi-signature. The individual
+    /// signatures are expected to be verified already.
+    pub fn aggregate(
+        &self,
+        signatures: &BTreeMap<AccountAddress, Bls12381Signature>,
+    ) -> std::result::Result<MultiSignature, VerifyError> {
+        let mut signers = BitVec::default();
+        for author in signatures.keys() {
+            signers.set(self.index_of(author).ok_or(VerifyError::UnknownAuthor)?);
+        }
+        let signature = Bls12381Signature::aggregate(signatures.values())
+            .map_err(|_| VerifyError::InvalidSignature)?;
+        Ok(MultiSignature { signers, signature })
+    }
+
+    /// Verifies that the multi-signature carries at least quorum voting power and is valid for
+    /// the message.
+    pub fn verify<T: CryptoHash + Serialize>(
+        &self,
+        message: &T,
+        multi_signature: &MultiSignature,
+    ) -> std::result::Result<(), VerifyError> {
+        let signers = &multi_signature.signers;
+        if let Some(last) = signers.last_set_bit() {
+            if usize::from(last) >= self.validators.len() {
+                return Err(VerifyError::UnknownAuthor);
+            }
+        }
+        let (public_keys, voting_power) = self
+            .validators
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| signers.is_set(*index as u8))
+            .fold(
+                (vec![], 0),
+                |(mut public_keys, voting_power), (_, (_, public_key, power))| {
+                    public_keys.push(public_key);
+                    (public_keys, voting_power + power)
+                },
+            );
+        if voting_power < self.quorum_voting_power {
+            return Err(VerifyError::TooLittleVotingPower {
+                voting_power,
+                quorum_voting_power: self.quorum_voting_power,
+            });
+        }
+        let public_key =
+            Bls12381PublicKey::aggregate(public_keys).map_err(|_| VerifyError::InvalidSignature)?;
+        multi_signature
+            .signature
+            .verify(message, &public_key)
+            .map_err(|_| VerifyError::InvalidSignature)
+    }
+
+    /// Verifies the signature of a single validator, which is to be aggregated into a
+    /// multi-signature.
+    pub fn verify_share<T: CryptoHash + Serialize>(
+        &self,
+        author: AccountAddress,
+        message: &T,
+        signature: &Bls12381Signature,
+    ) -> std::result::Result<(), VerifyError> {
+        let (_, public_key, _) = self
+            .validators
+            .iter()
+            .find(|(address, _, _)| *address == author)
+            .ok_or(VerifyError::UnknownAuthor)?;
+        signature
+            .verify(message, public_key)
+            .map_err(|_| VerifyError::InvalidSignature)
+    }
+
+    /// Returns the validators which contributed to the multi-signature, ignoring the bits that
+    /// don't stand for any validator.
+    pub fn signers(&self, multi_signature: &MultiSignature) -> Vec<AccountAddress> {
+        self.validators
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| multi_signature.signers.is_set(*index as u8))
+            .map(|(_, (address, _, _))| *address)
+            .collect()
+    }
+
+    fn index_of(&self, author: &AccountAddress) -> Option<u8> {
+        self.validators
+            .iter()
+            .position(|(address, _, _)| address == author)
+            .map(|index| index as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator_verifier::random_validator_verifier;
+    use libra_crypto::{
+        bls12381::{Bls12381PrivateKey, ProofOfPossession},
+        test_utils::TestLibraCrypto,
+        SigningKey, Uniform,
+    };
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn setup(
+        count: usize,
+    ) -> (
+        Vec<(AccountAddress, Bls12381PrivateKey)>,
+        MultiSignatureVerifier,
+    ) {
+        let (signers, verifier) = random_validator_verifier(count, None, false);
+        let mut rng = StdRng::from_seed([3u8; 32]);
+        let private_keys: Vec<_> = signers
+            .iter()
+            .map(|signer| (signer.author(), Bls12381PrivateKey::generate(&mut rng)))
+            .collect();
+        let keys: Vec<_> = private_keys
+            .iter()
+            .map(|(author, private_key)| BlsValidatorKey {
+                account_address: *author,
+                public_key: private_key.into(),
+                proof_of_possession: ProofOfPossession::create(private_key),
+            })
+            .collect();
+        (
+            private_keys,
+            MultiSignatureVerifier::new(&verifier, &keys).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_multi_signature() {
+        let (private_keys, verifier) = setup(4);
+        let message = TestLibraCrypto("Hello, World".to_string());
+        let sign = |keys: &[(AccountAddress, Bls12381PrivateKey)]| -> BTreeMap<_, _> {
+            keys.iter()
+                .map(|(author, key)| (*author, key.sign(&message)))
+                .collect()
+        };
+
+        let multi_signature = verifier.aggregate(&sign(&private_keys[1..])).unwrap();
+        assert_eq!(multi_signature.signers().count_ones(), 3);
+        let mut expected_signers: Vec<_> = private_keys[1..]
+            .iter()
+            .map(|(author, _)| *author)
+            .collect();
+        expected_signers.sort();
+        assert_eq!(verifier.signers(&multi_signature), expected_signers);
+        assert_eq!(verifier.verify(&message, &multi_signature), Ok(()));
+
+        // The size of the multi-signature doesn't depend on the number of signers.
+        let all_signers = verifier.aggregate(&sign(&private_keys)).unwrap();
+        assert_eq!(
+            lcs::to_bytes(&multi_signature).unwrap().len(),
+            lcs::to_bytes(&all_signers).unwrap().len()
+        );
+
+        let too_few = verifier.aggregate(&sign(&private_keys[2..])).unwrap();
+        assert_eq!(
+            verifier.verify(&message, &too_few),
+            Err(VerifyError::TooLittleVotingPower {
+                voting_power: 2,
+                quorum_voting_power: 3
+            })
+        );
+
+        // Claiming an extra signer invalidates the signature.
+        let mut forged = multi_signature;
+        forged
+            .signers
+            .set(verifier.index_of(&private_keys[0].0).unwrap());
+        assert_eq!(
+            verifier.verify(&message, &forged),
+            Err(VerifyError::InvalidSignature)
+        );
+        forged.signers.set(4);
+        assert_eq!(
+            verifier.verify(&message, &forged),
+            Err(VerifyError::UnknownAuthor)
+        );
+    }
+
+    #[test]
+    fn test_missing_or_invalid_keys() {
+        let (signers, verifier) = random_validator_verifier(2, None, false);
+        let mut rng = StdRng::from_seed([3u8; 32]);
+        let private_key = Bls12381PrivateKey::generate(&mut rng);
+        let other_key = Bls12381PrivateKey::generate(&mut rng);
+        let key = |author: AccountAddress, pop_key: &Bls12381PrivateKey| BlsValidatorKey {
+            account_address: author,
+            public_key: (&private_key).into(),
+            proof_of_possession: ProofOfPossession::create(pop_key),
+        };
+
+        assert!(
+            MultiSignatureVerifier::new(&verifier, &[key(signers[0].author(), &private_key)])
+                .is_err()
+        );
+        assert!(MultiSignatureVerifier::new(
+            &verifier,
+            &[
+                key(signers[0].author(), &private_key),
+                key(signers[1].author(), &other_key)
+            ]
+        )
+        .is_err());
+    }
+}
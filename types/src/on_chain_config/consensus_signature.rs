@@ -0,0 +1,76 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+use crate::{
+    account_address::AccountAddress, multi_signature::MultiSignatureVerifier,
+    on_chain_config::OnChainConfig, validator_verifier::ValidatorVerifier,
+};
+use anyhow::{format_err, Result};
+use libra_crypto::bls12381::{Bls12381PublicKey, ProofOfPossession};
+use serde::{Deserialize, Serialize};
+
+/// The BLS12-381 key of a validator, registered along with the proof of possession of the
+/// matching private key.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BlsValidatorKey {
+    pub account_address: AccountAddress,
+    pub public_key: Bls12381PublicKey,
+    pub proof_of_possession: ProofOfPossession,
+}
+
+/// Defines how the signatures of quorum certificates and epoch change proofs are represented.
+///
+/// This config is not part of the `ON_CHAIN_CONFIG_REGISTRY`: it is read from the state when an
+/// epoch starts and, until it is published on chain, readers fall back to the default, one
+/// Ed25519 signature per signer. Multi-signatures additionally require the `bls` feature on all
+/// the nodes of the network.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ConsensusSignatureScheme {
+    /// One Ed25519 signature per signer.
+    Ed25519,
+    /// A single aggregated BLS12-381 signature with a bitmap of the signers, verified with the
+    /// given validator keys.
+    Bls12381(Vec<BlsValidatorKey>),
+}
+
+impl Default for ConsensusSignatureScheme {
+    fn default() -> Self {
+        ConsensusSignatureScheme::Ed25519
+    }
+}
+
+impl ConsensusSignatureScheme {
+    /// Returns the verifier of multi-signatures of the given validator set, or `None` when
+    /// multi-signatures are not enabled.
+    pub fn multi_signature_verifier(
+        &self,
+        verifier: &ValidatorVerifier,
+    ) -> Result<Option<MultiSignatureVerifier>> {
+        match self {
+            ConsensusSignatureScheme::Ed25519 => Ok(None),
+            ConsensusSignatureScheme::Bls12381(keys) => {
+                MultiSignatureVerifier::new(verifier, keys).map(Some)
+            }
+        }
+    }
+}
+
+/// The Move representation of the config, which holds the LCS bytes of the scheme.
+#[derive(Deserialize, Serialize)]
+struct ConsensusSignatureSchemeInner {
+    scheme: Vec<u8>,
+}
+
+impl OnChainConfig for ConsensusSignatureScheme {
+    const IDENTIFIER: &'static str = "LibraConsensusSignatureScheme";
+
+    fn deserialize_into_config(bytes: &[u8]) -> Result<Self> {
+        let inner = lcs::from_bytes::<ConsensusSignatureSchemeInner>(&bytes).map_err(|e| {
+            format_err!(
+                "Failed first round of deserialization for ConsensusSignatureSchemeInner: {}",
+                e
+            )
+        })?;
+        lcs::from_bytes(&inner.scheme).map_err(Into::into)
+    }
+}
@@ -16,11 +16,15 @@ use move_core_types::{
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{collections::HashMap, sync::Arc};
 
+#[cfg(feature = "bls")]
+mod consensus_signature;
 mod libra_version;
 mod registered_currencies;
 mod validator_set;
 mod vm_config;
 
+#[cfg(feature = "bls")]
+pub use self::consensus_signature::{BlsValidatorKey, ConsensusSignatureScheme};
 pub use self::{
     libra_version::LibraVersion,
     registered_currencies::RegisteredCurrencies,
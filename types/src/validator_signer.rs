@@ -2,6 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::account_address::AccountAddress;
+#[cfg(feature = "bls")]
+use crate::on_chain_config::BlsValidatorKey;
+#[cfg(feature = "bls")]
+use libra_crypto::bls12381::{Bls12381PrivateKey, Bls12381Signature, ProofOfPossession};
 use libra_crypto::{
     ed25519::{Ed25519PrivateKey, Ed25519PublicKey, Ed25519Signature},
     hash::CryptoHash,
@@ -12,6 +16,11 @@ use rand::{rngs::StdRng, SeedableRng};
 use serde::ser::Serialize;
 use std::convert::TryFrom;
 
+/// Key info of the BLS12-381 key derivation, which keeps the derived key independent from any
+/// other use of the consensus key.
+#[cfg(feature = "bls")]
+const BLS_KEY_INFO: &[u8] = b"LIBRA::ValidatorSigner::BLS12381";
+
 /// ValidatorSigner associates an author with public and private keys with helpers for signing and
 /// validating. This struct can be used for all signing operations including block and network
 /// signing, respectively.
@@ -45,6 +54,35 @@ impl ValidatorSigner {
         self.private_key.public_key()
     }
 
+    /// Returns the BLS12-381 key of this signer, derived from its consensus key so that no other
+    /// secret has to be provisioned for multi-signatures.
+    #[cfg(feature = "bls")]
+    pub fn bls_private_key(&self) -> Bls12381PrivateKey {
+        Bls12381PrivateKey::derive(&self.private_key.to_bytes(), BLS_KEY_INFO)
+            .expect("Ed25519 private keys are 32 bytes long")
+    }
+
+    /// Constructs the BLS12-381 signature of `message`, which can be aggregated into a
+    /// multi-signature.
+    #[cfg(feature = "bls")]
+    pub fn sign_multi_signature_share<T: Serialize + CryptoHash>(
+        &self,
+        message: &T,
+    ) -> Bls12381Signature {
+        self.bls_private_key().sign(message)
+    }
+
+    /// Returns the BLS12-381 key of this signer as registered in the `ConsensusSignatureScheme`.
+    #[cfg(feature = "bls")]
+    pub fn bls_validator_key(&self) -> BlsValidatorKey {
+        let private_key = self.bls_private_key();
+        BlsValidatorKey {
+            account_address: self.author,
+            public_key: (&private_key).into(),
+            proof_of_possession: ProofOfPossession::create(&private_key),
+        }
+    }
+
     /// Returns the private key associated with this signer. Only available for testing purposes.
     #[cfg(any(test, feature = "fuzzing"))]
     pub fn private_key(&self) -> &Ed25519PrivateKey {
@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::{account_address::AccountAddress, on_chain_config::ValidatorSet};
+#[cfg(feature = "bls")]
+use crate::{multi_signature::MultiSignatureVerifier, on_chain_config::ConsensusSignatureScheme};
 use anyhow::{ensure, Result};
 use libra_crypto::{
     ed25519::{Ed25519PublicKey, Ed25519Signature},
@@ -42,6 +44,16 @@ pub enum VerifyError {
     #[error("Signature is invalid")]
     /// The signature does not match the hash.
     InvalidSignature,
+    #[error(
+        "Signature of author {} in the aggregated signature is invalid",
+        author
+    )]
+    /// One of the signatures of an aggregated signature does not match the hash.
+    InvalidAggregatedSignature { author: AccountAddress },
+    #[cfg(feature = "bls")]
+    #[error("Multi-signatures are not enabled for this validator set")]
+    /// A multi-signature was provided, but the validator set doesn't have the keys to verify it.
+    UnexpectedMultiSignature,
 }
 
 /// Helper struct to manage validator information for validation
@@ -74,6 +86,11 @@ pub struct ValidatorVerifier {
     quorum_voting_power: u64,
     /// Total voting power of all validators (cached from address_to_validator_info)
     total_voting_power: u64,
+    /// The BLS12-381 keys of the validators when the on-chain `ConsensusSignatureScheme` enables
+    /// multi-signatures.
+    #[cfg(feature = "bls")]
+    #[cfg_attr(any(test, feature = "fuzzing"), proptest(value = "None"))]
+    multi_signature_verifier: Option<MultiSignatureVerifier>,
 }
 
 impl ValidatorVerifier {
@@ -95,6 +112,8 @@ impl ValidatorVerifier {
             address_to_validator_info,
             quorum_voting_power,
             total_voting_power,
+            #[cfg(feature = "bls")]
+            multi_signature_verifier: None,
         }
     }
 
@@ -119,6 +138,8 @@ impl ValidatorVerifier {
             address_to_validator_info,
             quorum_voting_power,
             total_voting_power,
+            #[cfg(feature = "bls")]
+            multi_signature_verifier: None,
         })
     }
 
@@ -169,8 +190,9 @@ impl ValidatorVerifier {
         Ok(())
     }
 
-    /// This function will try batch signature verification and falls back to normal
-    /// iterated verification if batching fails.
+    /// Same checks as `verify_aggregated_struct_signature`, but the signatures are verified in a
+    /// single batch that is as strict as verifying them one by one. If the batch is rejected, the
+    /// signatures are verified one by one to find the author of the invalid one.
     pub fn batch_verify_aggregated_signatures<T: CryptoHash + Serialize>(
         &self,
         message: &T,
@@ -178,18 +200,23 @@ impl ValidatorVerifier {
     ) -> std::result::Result<(), VerifyError> {
         self.check_num_of_signatures(aggregated_signature)?;
         self.check_voting_power(aggregated_signature.keys())?;
-        let keys_and_signatures: Vec<(Ed25519PublicKey, Ed25519Signature)> = aggregated_signature
+        // All the authors are known at this point, as checked by `check_voting_power`.
+        let (authors, keys_and_signatures): (Vec<_>, Vec<_>) = aggregated_signature
             .iter()
-            .flat_map(|(address, signature)| {
-                let sig = signature.clone();
-                self.get_public_key(&address).map(|pub_key| (pub_key, sig))
+            .flat_map(|(author, signature)| {
+                self.get_public_key(author)
+                    .map(|public_key| (*author, (public_key, signature.clone())))
             })
-            .collect();
-        // Fallback is required to identify the source of the problem if batching fails.
-        if Ed25519Signature::batch_verify(message, keys_and_signatures).is_err() {
-            self.verify_aggregated_struct_signature(message, aggregated_signature)?
-        }
-        Ok(())
+            .unzip();
+        Ed25519Signature::batch_verify_find_invalid(message, &keys_and_signatures).map_err(
+            |invalid| match invalid {
+                Some(index) => VerifyError::InvalidAggregatedSignature {
+                    author: authors[index],
+                },
+                // The batch disagrees with the individual checks, reject it all the same
+                None => VerifyError::InvalidSignature,
+            },
+        )
     }
 
     /// Ensure there are not more than the maximum expected signatures (all possible signatures).
@@ -266,6 +293,20 @@ impl ValidatorVerifier {
     pub fn quorum_voting_power(&self) -> u64 {
         self.quorum_voting_power
     }
+
+    /// Applies the given signature scheme, i.e. enables the verification of multi-signatures
+    /// of this validator set if the scheme asks for them.
+    #[cfg(feature = "bls")]
+    pub fn with_signature_scheme(mut self, scheme: &ConsensusSignatureScheme) -> Result<Self> {
+        self.multi_signature_verifier = scheme.multi_signature_verifier(&self)?;
+        Ok(self)
+    }
+
+    /// Returns the verifier of multi-signatures, if they are enabled for this validator set.
+    #[cfg(feature = "bls")]
+    pub fn multi_signature_verifier(&self) -> Option<&MultiSignatureVerifier> {
+        self.multi_signature_verifier.as_ref()
+    }
 }
 
 impl fmt::Display for ValidatorVerifier {
@@ -508,6 +549,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_batch_verify_reports_invalid_author() {
+        let (validator_signers, validator_verifier) = random_validator_verifier(7, None, false);
+        let dummy_struct = TestLibraCrypto("Hello, World".to_string());
+        let other_struct = TestLibraCrypto("Goodbye, World".to_string());
+
+        let mut author_to_signature_map: BTreeMap<_, _> = validator_signers
+            .iter()
+            .map(|signer| (signer.author(), signer.sign(&dummy_struct)))
+            .collect();
+        assert_eq!(
+            validator_verifier
+                .batch_verify_aggregated_signatures(&dummy_struct, &author_to_signature_map),
+            Ok(())
+        );
+
+        // One of the validators signed another message; the batch fails and the fallback
+        // names the culprit.
+        let culprit = &validator_signers[3];
+        author_to_signature_map.insert(culprit.author(), culprit.sign(&other_struct));
+        assert_eq!(
+            validator_verifier
+                .batch_verify_aggregated_signatures(&dummy_struct, &author_to_signature_map),
+            Err(VerifyError::InvalidAggregatedSignature {
+                author: culprit.author()
+            })
+        );
+
+        // A signature of another validator is attributed to the culprit.
+        author_to_signature_map.insert(culprit.author(), validator_signers[4].sign(&dummy_struct));
+        assert_eq!(
+            validator_verifier
+                .batch_verify_aggregated_signatures(&dummy_struct, &author_to_signature_map),
+            Err(VerifyError::InvalidAggregatedSignature {
+                author: culprit.author()
+            })
+        );
+    }
+
     #[test]
     fn test_unequal_vote_quorum_validators() {
         const NUM_SIGNERS: u8 = 4;